@@ -0,0 +1,48 @@
+#version 330 core
+precision mediump float;
+
+// FXAA in its simplest form, after Timothy Lottes: blurs along the edge
+// found from the luma of the four diagonal neighbors, unless that strays out
+// of the range of luma around the pixel.
+uniform sampler2D u_image;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+const float REDUCE_MIN = 1.0 / 128.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float SPAN_MAX = 8.0;
+const vec3 LUMA = vec3(0.299, 0.587, 0.114);
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(u_image, 0));
+    vec4 center = texture(u_image, v_uv);
+    float luma_nw = dot(texture(u_image, v_uv + vec2(-1.0, -1.0) * texel).rgb, LUMA);
+    float luma_ne = dot(texture(u_image, v_uv + vec2(1.0, -1.0) * texel).rgb, LUMA);
+    float luma_sw = dot(texture(u_image, v_uv + vec2(-1.0, 1.0) * texel).rgb, LUMA);
+    float luma_se = dot(texture(u_image, v_uv + vec2(1.0, 1.0) * texel).rgb, LUMA);
+    float luma_m = dot(center.rgb, LUMA);
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // across the gradient of luma, which is along the edge
+    vec2 dir = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * scale, -SPAN_MAX, SPAN_MAX) * texel;
+
+    vec4 near = 0.5 * (
+        texture(u_image, v_uv + dir * (1.0 / 3.0 - 0.5))
+        + texture(u_image, v_uv + dir * (2.0 / 3.0 - 0.5))
+    );
+    vec4 far = near * 0.5 + 0.25 * (
+        texture(u_image, v_uv - dir * 0.5)
+        + texture(u_image, v_uv + dir * 0.5)
+    );
+    float luma_far = dot(far.rgb, LUMA);
+    FragColor = (luma_far < luma_min || luma_far > luma_max) ? near : far;
+}
@@ -0,0 +1,17 @@
+#version 330 core
+precision mediump float;
+
+in vec4 v_color;
+in vec4 v_current;
+in vec4 v_previous;
+
+layout(location = 0) out vec4 FragColor;
+// how far the surface moved since the last frame, in texture coordinates
+layout(location = 1) out vec2 Velocity;
+
+void main() {
+    FragColor = v_color;
+    vec2 current = v_current.xy / v_current.w;
+    vec2 previous = v_previous.xy / v_previous.w;
+    Velocity = (current - previous) * 0.5;
+}
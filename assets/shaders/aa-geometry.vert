@@ -0,0 +1,23 @@
+#version 330 core
+precision mediump float;
+
+in vec2 position;
+// where the vertex was the frame before, in world units
+in vec2 previous;
+in vec4 color;
+
+uniform mat4 u_view_projection;
+uniform mat4 u_previous_view_projection;
+// subpixel offset of this frame, in clip space
+uniform vec2 u_jitter;
+
+out vec4 v_color;
+out vec4 v_current;
+out vec4 v_previous;
+
+void main() {
+    v_current = u_view_projection * vec4(position, 0.0, 1.0);
+    v_previous = u_previous_view_projection * vec4(previous, 0.0, 1.0);
+    v_color = color;
+    gl_Position = v_current + vec4(u_jitter * v_current.w, 0.0, 0.0);
+}
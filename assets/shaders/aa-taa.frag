@@ -0,0 +1,55 @@
+#version 330 core
+precision mediump float;
+
+// Blends the jittered frame into the history of the previous ones, found
+// where each pixel was through the velocity buffer, and clamped to the
+// colors around the pixel in this frame so that what got uncovered or
+// changed doesn't leave trails.
+uniform sampler2D u_current;
+uniform sampler2D u_velocity;
+uniform sampler2D u_history;
+// weight of the history, against the current frame
+uniform float u_feedback;
+// whether to follow the velocity back, or take the history in place
+uniform bool u_reproject;
+// whether the history is worth nothing, like on the first frame
+uniform bool u_reset;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+void main() {
+    vec4 current = texture(u_current, v_uv);
+    if (u_reset) {
+        FragColor = current;
+        return;
+    }
+
+    vec2 texel = 1.0 / vec2(textureSize(u_current, 0));
+    vec4 low = current;
+    vec4 high = current;
+    // the fastest motion around, so that edges of moving shapes, half on the
+    // background, follow the shape
+    vec2 velocity = vec2(0.0);
+    for (int y = -1; y <= 1; y++) {
+        for (int x = -1; x <= 1; x++) {
+            vec2 uv = v_uv + vec2(x, y) * texel;
+            vec4 neighbor = texture(u_current, uv);
+            low = min(low, neighbor);
+            high = max(high, neighbor);
+            vec2 moved = texture(u_velocity, uv).xy;
+            if (dot(moved, moved) > dot(velocity, velocity)) {
+                velocity = moved;
+            }
+        }
+    }
+
+    vec2 previous = u_reproject ? v_uv - velocity : v_uv;
+    if (any(lessThan(previous, vec2(0.0))) || any(greaterThan(previous, vec2(1.0)))) {
+        FragColor = current;
+        return;
+    }
+    vec4 history = clamp(texture(u_history, previous), low, high);
+    FragColor = mix(current, history, u_feedback);
+}
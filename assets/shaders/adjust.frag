@@ -0,0 +1,58 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+uniform sampler2D u_image;
+// the equalized luma of every bin, one row per tile
+uniform sampler2D u_luts;
+uniform bool u_equalize;
+// tiles along each side, and rows of the lookup tables
+uniform int u_tiles;
+uniform int u_rows;
+
+// input levels mapped to 0 and 1
+uniform float u_black;
+uniform float u_white;
+uniform float u_gamma;
+uniform float u_contrast;
+uniform float u_brightness;
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+float equalized(float y, ivec2 tile) {
+    int row = tile.y * u_tiles + tile.x;
+    return texture(u_luts, vec2((y * 255.0 + 0.5) / 256.0, (float(row) + 0.5) / float(u_rows))).r;
+}
+
+void main() {
+    vec4 color = texture(u_image, v_uv);
+    vec3 rgb = color.rgb;
+
+    if (u_equalize) {
+        // blend the mappings of the four closest tile centers, so that no
+        // seams show between tiles
+        vec2 position = v_uv * float(u_tiles) - 0.5;
+        ivec2 first = ivec2(floor(position));
+        vec2 f = position - vec2(first);
+        ivec2 a = clamp(first, ivec2(0), ivec2(u_tiles - 1));
+        ivec2 b = clamp(first + 1, ivec2(0), ivec2(u_tiles - 1));
+
+        float y = luma(rgb);
+        float top = mix(equalized(y, a), equalized(y, ivec2(b.x, a.y)), f.x);
+        float bottom = mix(equalized(y, ivec2(a.x, b.y)), equalized(y, b), f.x);
+        float target = mix(top, bottom, f.y);
+
+        // scaling the color keeps its hue
+        rgb = y > 1e-4 ? clamp(rgb * (target / y), 0.0, 1.0) : vec3(target);
+    }
+
+    rgb = clamp((rgb - u_black) / max(u_white - u_black, 1e-3), 0.0, 1.0);
+    rgb = pow(rgb, vec3(1.0 / u_gamma));
+    rgb = (rgb - 0.5) * u_contrast + 0.5 + u_brightness;
+    FragColor = vec4(clamp(rgb, 0.0, 1.0), color.a);
+}
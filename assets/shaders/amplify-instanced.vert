@@ -0,0 +1,26 @@
+#version 330 core
+precision mediump float;
+
+// one instance per point, drawn as a 4 vertex triangle strip
+layout(location = 0) in vec2 a_center;
+layout(location = 1) in float a_size;
+layout(location = 2) in vec4 a_color;
+
+uniform mat4 u_mvp;
+
+out vec2 v_uv;
+out vec4 v_color;
+
+const vec2[4] corners = vec2[4](
+        vec2(-1.0, -1.0),
+        vec2(1.0, -1.0),
+        vec2(-1.0, 1.0),
+        vec2(1.0, 1.0)
+    );
+
+void main() {
+    vec2 corner = corners[gl_VertexID];
+    gl_Position = u_mvp * vec4(a_center + corner * a_size, 0.0, 1.0);
+    v_uv = corner;
+    v_color = a_color;
+}
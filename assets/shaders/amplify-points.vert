@@ -0,0 +1,16 @@
+#version 330 core
+precision mediump float;
+
+// points are forwarded as is, amplify.geom expands them
+layout(location = 0) in vec2 a_center;
+layout(location = 1) in float a_size;
+layout(location = 2) in vec4 a_color;
+
+out float g_size;
+out vec4 g_color;
+
+void main() {
+    gl_Position = vec4(a_center, 0.0, 1.0);
+    g_size = a_size;
+    g_color = a_color;
+}
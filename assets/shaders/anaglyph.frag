@@ -0,0 +1,19 @@
+#version 330 core
+precision mediump float;
+
+uniform sampler2D u_left;
+uniform sampler2D u_right;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+void main() {
+    vec4 left = texture(u_left, v_uv);
+    vec4 right = texture(u_right, v_uv);
+
+    // half-color anaglyph: the red filter sees the left eye in gray, so that
+    // saturated reds don't show up in one eye only
+    float luma = dot(left.rgb, vec3(0.299, 0.587, 0.114));
+    FragColor = vec4(luma, right.gb, max(left.a, right.a));
+}
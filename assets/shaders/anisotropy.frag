@@ -0,0 +1,12 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+uniform sampler2D u_checker;
+
+void main() {
+    FragColor = texture(u_checker, v_uv);
+}
@@ -0,0 +1,17 @@
+#version 330
+precision mediump float;
+
+uniform mat4 u_mvp;
+// world units per repetition of the texture, and how far it has scrolled
+uniform float u_tile;
+uniform vec2 u_offset;
+
+// on the ground, as x and z
+in vec2 position;
+
+out vec2 v_uv;
+
+void main() {
+    gl_Position = u_mvp * vec4(position.x, 0.0, position.y, 1.0);
+    v_uv = position / u_tile + u_offset;
+}
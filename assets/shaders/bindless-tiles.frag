@@ -0,0 +1,17 @@
+#version 430 core
+#extension GL_ARB_bindless_texture : require
+precision mediump float;
+
+in vec2 v_uv;
+flat in int v_index;
+
+out vec4 FragColor;
+
+// one resident texture handle per quad
+layout(std430, binding = 0) readonly buffer Handles {
+    uvec2 handles[];
+};
+
+void main() {
+    FragColor = texture(sampler2D(handles[v_index]), v_uv);
+}
@@ -0,0 +1,61 @@
+#version 330 core
+precision mediump float;
+
+// Rebuilds the whole picture from the half of the checkerboard drawn this
+// frame: those pixels are taken as they are, and the others from the last
+// picture, clamped to the four pixels drawn around them, which belong to this
+// frame. Without a history, as on the first frame, they are the average of
+// those four instead.
+
+// this frame's half, as wide as half the picture
+uniform sampler2D u_half;
+uniform sampler2D u_history;
+uniform int u_parity;
+// whether to fill the other half from the history, or only from the
+// neighbors
+uniform bool u_use_history;
+uniform bool u_reset;
+// tints the pixels that weren't drawn by how much the clamp changed their
+// history, red, or blue where they were averaged
+uniform bool u_artifacts;
+
+out vec4 FragColor;
+
+// the pixel of the picture at `pixel`, which must be one of this frame's
+vec4 drawn(ivec2 pixel) {
+    ivec2 size = textureSize(u_half, 0);
+    ivec2 texel = ivec2(pixel.x / 2, pixel.y);
+    return texelFetch(u_half, clamp(texel, ivec2(0), size - 1), 0);
+}
+
+void main() {
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    if ((pixel.x & 1) == ((pixel.y + u_parity) & 1)) {
+        FragColor = drawn(pixel);
+        return;
+    }
+
+    // left and right are in the same row with the other parity, above and
+    // below in the same column on rows of the other parity, so all four were
+    // drawn this frame
+    vec4 left = drawn(pixel + ivec2(-1, 0));
+    vec4 right = drawn(pixel + ivec2(1, 0));
+    vec4 below = drawn(pixel + ivec2(0, -1));
+    vec4 above = drawn(pixel + ivec2(0, 1));
+    vec4 low = min(min(left, right), min(below, above));
+    vec4 high = max(max(left, right), max(below, above));
+
+    if (u_reset || !u_use_history) {
+        vec4 average = (left + right + below + above) * 0.25;
+        FragColor = u_artifacts ? mix(average, vec4(0.0, 0.2, 1.0, 1.0), 0.5) : average;
+        return;
+    }
+
+    vec4 history = texelFetch(u_history, pixel, 0);
+    vec4 clamped = clamp(history, low, high);
+    FragColor = clamped;
+    if (u_artifacts) {
+        float rejected = clamp(length(history.rgb - clamped.rgb) * 4.0, 0.0, 1.0);
+        FragColor = mix(clamped, vec4(1.0, 0.0, 0.0, 1.0), rejected);
+    }
+}
@@ -0,0 +1,67 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+uniform sampler2D u_image;
+uniform vec3 u_key;
+// chroma distances from the key under which pixels are fully transparent,
+// and over which they go from transparent to opaque
+uniform float u_tolerance;
+uniform float u_softness;
+// how much of the key's hue is taken out of what's left
+uniform float u_spill;
+// 0 for the keyed image, 1 for its matte, 2 for the image untouched
+uniform int u_view;
+// 0 to output straight alpha, 1 for a checkerboard, 2 for u_background
+uniform int u_fill;
+uniform vec3 u_background;
+
+const vec3 LUMA = vec3(0.2126, 0.7152, 0.0722);
+
+// blue and red minus luma, which only changes with the hue and saturation
+vec2 chroma(vec3 color) {
+    float y = dot(color, LUMA);
+    return vec2(color.b - y, color.r - y);
+}
+
+void main() {
+    vec4 texel = texture(u_image, v_uv);
+    vec3 rgb = texel.rgb;
+    float alpha = texel.a;
+
+    if (u_view != 2) {
+        vec2 key = chroma(u_key);
+        vec2 c = chroma(rgb);
+        alpha *= smoothstep(u_tolerance, u_tolerance + u_softness, distance(c, key));
+
+        // the key's color bounces onto the subject and bleeds into its edges,
+        // so take it out of the chroma while keeping the luma
+        if (length(key) > 1e-4) {
+            vec2 direction = normalize(key);
+            vec2 delta = -direction * max(dot(c, direction), 0.0) * u_spill;
+            rgb.b += delta.x;
+            rgb.r += delta.y;
+            rgb.g -= (LUMA.r * delta.y + LUMA.b * delta.x) / LUMA.g;
+            rgb = clamp(rgb, 0.0, 1.0);
+        }
+    }
+    if (u_view == 1) {
+        rgb = vec3(alpha);
+        alpha = 1.0;
+    }
+
+    if (u_fill == 0) {
+        FragColor = vec4(rgb, alpha);
+        return;
+    }
+
+    vec3 background = u_background;
+    if (u_fill == 1) {
+        ivec2 square = ivec2(gl_FragCoord.xy) / 12;
+        background = (square.x + square.y) % 2 == 0 ? vec3(0.8) : vec3(0.55);
+    }
+    FragColor = vec4(mix(background, rgb, alpha), 1.0);
+}
@@ -0,0 +1,28 @@
+#version 330 core
+
+// One point per particle, which cloth-normals.geom turns into lines along its
+// normal and tangent. Found from the neighbours as in cloth.vert.
+
+// the particle buffer, two texels per particle: position then previous one
+uniform samplerBuffer u_particles;
+uniform int u_columns;
+uniform int u_rows;
+
+out vec3 g_normal;
+out vec3 g_tangent;
+
+vec3 position(ivec2 cell) {
+    cell = clamp(cell, ivec2(0), ivec2(u_columns, u_rows) - 1);
+    return texelFetch(u_particles, 2 * (cell.y * u_columns + cell.x)).xyz;
+}
+
+void main() {
+    ivec2 cell = ivec2(gl_VertexID % u_columns, gl_VertexID / u_columns);
+
+    vec3 along_row = position(cell + ivec2(1, 0)) - position(cell - ivec2(1, 0));
+    vec3 along_column = position(cell + ivec2(0, 1)) - position(cell - ivec2(0, 1));
+    g_normal = normalize(cross(along_row, along_column));
+    g_tangent = normalize(along_row);
+    // still in world space, projected once the lines are made
+    gl_Position = vec4(position(cell), 1.0);
+}
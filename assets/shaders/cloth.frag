@@ -0,0 +1,22 @@
+#version 330 core
+precision mediump float;
+
+uniform sampler2D u_texture;
+// toward the light, in world space
+uniform vec3 u_light;
+
+in vec3 v_normal;
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+void main() {
+    // lit the same from both sides, the back a little darker
+    float light = 0.35 + 0.65 * abs(dot(normalize(v_normal), u_light));
+    if (!gl_FrontFacing) {
+        light *= 0.7;
+    }
+
+    vec4 color = texture(u_texture, v_uv);
+    FragColor = vec4(color.rgb * light, 1.0);
+}
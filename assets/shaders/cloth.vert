@@ -0,0 +1,28 @@
+#version 330 core
+
+layout(location = 0) in vec2 a_uv;
+
+uniform mat4 u_mvp;
+// the particle buffer, two texels per particle: position then previous one
+uniform samplerBuffer u_particles;
+uniform int u_columns;
+uniform int u_rows;
+
+out vec3 v_normal;
+out vec2 v_uv;
+
+vec3 position(ivec2 cell) {
+    cell = clamp(cell, ivec2(0), ivec2(u_columns, u_rows) - 1);
+    return texelFetch(u_particles, 2 * (cell.y * u_columns + cell.x)).xyz;
+}
+
+void main() {
+    ivec2 cell = ivec2(gl_VertexID % u_columns, gl_VertexID / u_columns);
+
+    // smooth across the triangles around the particle, from its neighbours
+    vec3 along_row = position(cell + ivec2(1, 0)) - position(cell - ivec2(1, 0));
+    vec3 along_column = position(cell + ivec2(0, 1)) - position(cell - ivec2(0, 1));
+    v_normal = normalize(cross(along_row, along_column));
+    v_uv = a_uv;
+    gl_Position = u_mvp * vec4(position(cell), 1.0);
+}
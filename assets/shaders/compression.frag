@@ -0,0 +1,35 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+uniform sampler2D u_original;
+uniform sampler2D u_compressed;
+// the original is left of it, the compressed image right of it
+uniform float u_split;
+// whether to show how far apart they are instead
+uniform bool u_heatmap;
+uniform float u_gain;
+
+// black through red and yellow to white
+vec3 heat(float t) {
+    return clamp(vec3(t * 3.0, t * 3.0 - 1.0, t * 3.0 - 2.0), 0.0, 1.0);
+}
+
+void main() {
+    vec4 original = texture(u_original, v_uv);
+    vec4 compressed = texture(u_compressed, v_uv);
+
+    if (u_heatmap) {
+        float difference = length(original.rgb - compressed.rgb) / sqrt(3.0);
+        FragColor = vec4(heat(difference * u_gain), 1.0);
+        return;
+    }
+
+    FragColor = v_uv.x < u_split ? original : compressed;
+    if (abs(v_uv.x - u_split) < fwidth(v_uv.x)) {
+        FragColor = vec4(1.0);
+    }
+}
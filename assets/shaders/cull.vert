@@ -0,0 +1,20 @@
+#version 330 core
+precision mediump float;
+
+// per instance, from the compacted buffer
+layout(location = 0) in vec2 a_center;
+layout(location = 1) in vec2 a_half_size;
+layout(location = 2) in vec4 a_color;
+// per vertex
+layout(location = 3) in vec2 a_corner;
+
+uniform mat4 u_mvp;
+
+out vec2 v_uv;
+out vec4 v_color;
+
+void main() {
+    gl_Position = u_mvp * vec4(a_center + a_corner * a_half_size, 0.0, 1.0);
+    v_uv = a_corner;
+    v_color = a_color;
+}
@@ -1,6 +1,7 @@
 #version 330 core
 
 uniform sampler2D u_texture;
+uniform float u_seed;
 
 in vec2 v_uv;
 
@@ -16,7 +17,7 @@ vec4 hash43n(vec3 p) {
 // Color dithering
 // https://pixelmager.github.io/linelight/banding.html
 vec4 dither(vec4 c) {
-    vec4 r0f = hash43n(vec3(gl_FragCoord.xy, 7.27));
+    vec4 r0f = hash43n(vec3(gl_FragCoord.xy, 7.27 + u_seed));
     vec4 rnd = r0f - 0.5; // symmetric rpdf
     vec4 t = step(vec4(0.5 / 255.0), c) * step(c, vec4(1.0 - 0.5 / 255.0));
     rnd += t * (r0f.yzwx - 0.5); // symmetric tpdf
@@ -0,0 +1,15 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+in vec4 v_color;
+
+out vec4 frag_color;
+
+// one bit per texel in red, scaled up without filtering
+uniform sampler2D u_font;
+
+void main() {
+    float coverage = texelFetch(u_font, ivec2(v_uv), 0).r;
+    frag_color = vec4(v_color.rgb, v_color.a * coverage);
+}
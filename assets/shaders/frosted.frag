@@ -0,0 +1,26 @@
+#version 330 core
+
+// the overlay, premultiplied
+uniform sampler2D u_hud;
+// the scene, blurred
+uniform sampler2D u_backdrop;
+// alpha of the overlay's panels
+uniform float u_panel_alpha;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+void main() {
+    vec4 hud = texture(u_hud, v_uv);
+    vec3 backdrop = texture(u_backdrop, v_uv).rgb;
+
+    // the blur replaces the scene under the panels, fading out with them
+    float coverage = clamp(hud.a / u_panel_alpha, 0.0, 1.0);
+
+    // blended with (ONE, ONE_MINUS_SRC_ALPHA): the scene, then the blur where
+    // covered, then the overlay on top
+    vec3 color = backdrop * coverage * (1.0 - hud.a) + hud.rgb;
+    float alpha = 1.0 - (1.0 - coverage) * (1.0 - hud.a);
+    FragColor = vec4(color, alpha);
+}
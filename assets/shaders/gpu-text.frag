@@ -0,0 +1,84 @@
+#version 330 core
+precision mediump float;
+
+// Draws the label of the FPS counter from the cells in `u_label`, formatting
+// the numbers itself, so that nothing but the numbers changes between frames.
+// Must match the `Counter` struct in gpu_text.rs.
+layout(std140) uniform Counter {
+    vec4 u_values;
+    vec4 u_rect;
+    vec2 u_viewport;
+    float u_scale;
+    float u_padding;
+    vec4 u_text_color;
+    vec4 u_background;
+    // four cells per element: a glyph of the atlas if positive, a digit of
+    // one of `u_values` otherwise
+    ivec4 u_label[12];
+};
+
+// signed distances to the edges of the glyphs, side by side, 0.5 on the edge
+uniform sampler2D u_atlas;
+
+in vec2 v_pixel;
+
+out vec4 frag_color;
+
+// font texels of a glyph and of the cell it is drawn in
+const ivec2 GLYPH = ivec2(5, 7);
+const ivec2 CELL = ivec2(6, 8);
+// font texels of distances around each glyph, and atlas texels per font texel
+const int SDF_PADDING = 1;
+const int SDF_SCALE = 6;
+// font texels at which the distance saturates
+const float SDF_RANGE = 1.0;
+// glyphs 0 to 9 are the digits
+const int SPACE = 11;
+const int CELLS = 48;
+
+// Glyph of `code`, formatting the digit of a value it stands for, with its
+// leading zeros left blank.
+int glyph(int code) {
+    if (code >= 0) {
+        return code;
+    }
+
+    code = -code - 1;
+    int field = code % 4;
+    int decimals = (code / 4) % 4;
+    int place = code / 16;
+
+    int n = int(round(u_values[field] * pow(10.0, float(decimals))));
+    int power = 1;
+    for (int i = 0; i < place; i++) {
+        power *= 10;
+    }
+    if (place > decimals && n < power) {
+        return SPACE;
+    }
+    return (n / power) % 10;
+}
+
+void main() {
+    vec2 font = (v_pixel - u_padding) / u_scale;
+    int cell = int(floor(font.x / float(CELL.x)));
+
+    frag_color = u_background;
+    if (font.x < 0.0 || font.y < 0.0 || font.y >= float(CELL.y) || cell >= CELLS) {
+        return;
+    }
+
+    int code = u_label[cell / 4][cell % 4];
+    int index = glyph(code);
+
+    // where the pixel is in the glyph's distances, in font texels
+    vec2 inside = font - vec2(cell * CELL.x, 0.0) + float(SDF_PADDING);
+    vec2 atlas_cell = vec2(GLYPH + 2 * SDF_PADDING) * float(SDF_SCALE);
+    vec2 uv = vec2(float(index) * atlas_cell.x, 0.0) + inside * float(SDF_SCALE);
+    float distance = textureLod(u_atlas, uv / vec2(textureSize(u_atlas, 0)), 0.0).r;
+
+    // a pixel wide edge, whatever the scale
+    float signed_texels = (distance - 0.5) * 2.0 * SDF_RANGE;
+    float coverage = clamp(signed_texels * u_scale + 0.5, 0.0, 1.0);
+    frag_color = mix(u_background, u_text_color, coverage);
+}
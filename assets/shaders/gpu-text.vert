@@ -0,0 +1,32 @@
+#version 330 core
+precision mediump float;
+
+// The panel of the FPS counter, a strip of four corners without any vertex
+// buffer. Must match the `Counter` struct in gpu_text.rs.
+layout(std140) uniform Counter {
+    // frames per second, then the average, shortest and longest frame times
+    // in milliseconds
+    vec4 u_values;
+    // top left and bottom right corners, in pixels from the top left corner
+    // of the window
+    vec4 u_rect;
+    vec2 u_viewport;
+    // pixels per texel of the font
+    float u_scale;
+    float u_padding;
+    vec4 u_text_color;
+    vec4 u_background;
+    ivec4 u_label[12];
+};
+
+// in pixels from the top left corner of the panel
+out vec2 v_pixel;
+
+void main() {
+    vec2 corner = vec2(gl_VertexID & 1, gl_VertexID >> 1);
+    vec2 position = mix(u_rect.xy, u_rect.zw, corner);
+    v_pixel = position - u_rect.xy;
+
+    vec2 clip = position / u_viewport * 2.0 - 1.0;
+    gl_Position = vec4(clip.x, -clip.y, 0.0, 1.0);
+}
@@ -0,0 +1,47 @@
+#version 330 core
+precision mediump float;
+
+// u in red, v in green
+uniform sampler2D u_state;
+// 0 ink, 1 heat, 2 ocean
+uniform int u_colormap;
+// from the screen back to the world
+uniform mat4 u_inverse;
+// half the size of the grid in world units, centered on the origin
+uniform vec2 u_half_size;
+uniform vec4 u_clear;
+uniform vec4 u_ink;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+vec3 heat(float t) {
+    return clamp(vec3(3.0 * t, 3.0 * t - 1.0, 3.0 * t - 2.0), 0.0, 1.0);
+}
+
+vec3 ocean(float t) {
+    vec3 deep = vec3(0.02, 0.05, 0.2);
+    vec3 shallow = vec3(0.0, 0.55, 0.7);
+    vec3 foam = vec3(0.9, 1.0, 0.95);
+    return t < 0.5 ? mix(deep, shallow, t * 2.0) : mix(shallow, foam, t * 2.0 - 1.0);
+}
+
+void main() {
+    vec2 world = (u_inverse * vec4(v_uv * 2.0 - 1.0, 0.0, 1.0)).xy;
+    vec2 uv = world / (2.0 * u_half_size) + 0.5;
+    if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+        FragColor = u_clear;
+        return;
+    }
+
+    // v rarely goes much above 0.4
+    float t = smoothstep(0.0, 0.4, texture(u_state, uv).g);
+    if (u_colormap == 1) {
+        FragColor = vec4(heat(t), 1.0);
+    } else if (u_colormap == 2) {
+        FragColor = vec4(ocean(t), 1.0);
+    } else {
+        FragColor = mix(u_clear, u_ink, t);
+    }
+}
@@ -0,0 +1,45 @@
+#version 330 core
+precision highp float;
+
+// diffusion rates of u and v, in cells² per step
+#define DU 1.0
+#define DV 0.5
+
+// u in red, v in green
+uniform sampler2D u_state;
+uniform float u_feed;
+uniform float u_kill;
+// where v gets poured in, in cells, and how far around it
+uniform vec2 u_brush;
+// 0 when nothing is poured in
+uniform float u_brush_radius;
+
+out vec4 FragColor;
+
+// the grid wraps around on both axes
+vec2 at(ivec2 p) {
+    ivec2 size = textureSize(u_state, 0);
+    return texelFetch(u_state, (p + size) % size, 0).rg;
+}
+
+void main() {
+    ivec2 p = ivec2(gl_FragCoord.xy);
+    vec2 c = at(p);
+
+    // 3x3 laplacian, the diagonals weighing a quarter of the sides
+    vec2 laplacian = -c
+        + 0.2 * (at(p + ivec2(1, 0)) + at(p - ivec2(1, 0)) + at(p + ivec2(0, 1)) + at(p - ivec2(0, 1)))
+        + 0.05 * (at(p + ivec2(1, 1)) + at(p - ivec2(1, 1)) + at(p + ivec2(1, -1)) + at(p - ivec2(1, -1)));
+
+    float reaction = c.r * c.g * c.g;
+    vec2 next = c + vec2(
+        DU * laplacian.r - reaction + u_feed * (1.0 - c.r),
+        DV * laplacian.g + reaction - (u_feed + u_kill) * c.g
+    );
+
+    if (distance(gl_FragCoord.xy, u_brush) < u_brush_radius) {
+        next = vec2(0.5, 0.25);
+    }
+
+    FragColor = vec4(clamp(next, 0.0, 1.0), 0.0, 1.0);
+}
@@ -0,0 +1,65 @@
+#version 330 core
+precision highp float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+// 1 to write linear values up to u_peak times SDR white, see src/output.rs
+uniform int u_hdr_output;
+uniform float u_peak;
+// in stops, applied to everything but the SDR ramp
+uniform float u_exposure;
+// 0 to 1, brightness of the pulsing highlight
+uniform float u_pulse;
+
+const int BANDS = 6;
+const int STEPS = 16;
+
+// Linear value from SDR white (0 stops) up to the peak, stepped across.
+float stops(float x) {
+    float step = floor(x * float(STEPS)) / float(STEPS - 1);
+    return exp2(step * log2(u_peak));
+}
+
+vec3 band(int index, vec2 uv) {
+    float exposure = exp2(u_exposure);
+    switch (index) {
+    // gray from black to SDR white, in steps, the same in both modes
+    case 0:
+        return vec3(floor(uv.x * float(STEPS)) / float(STEPS - 1));
+    // white from SDR white up to the peak, all clipped in SDR
+    case 1:
+        return vec3(stops(uv.x)) * exposure;
+    // primaries from SDR white up to the peak
+    case 2:
+        return vec3(stops(uv.x), 0.0, 0.0) * exposure;
+    case 3:
+        return vec3(0.0, stops(uv.x), 0.0) * exposure;
+    case 4:
+        return vec3(0.0, 0.0, stops(uv.x)) * exposure;
+    // highlights over SDR white: 2x, 4x, 8x, the peak and one pulsing
+    default: {
+        float cell = floor(uv.x * 5.0);
+        vec2 inner = fract(vec2(uv.x * 5.0, uv.y)) - 0.5;
+        if (max(abs(inner.x) * 2.5, abs(inner.y)) > 0.3) {
+            return vec3(1.0);
+        }
+        float highlight = cell < 4.0 ? min(exp2(cell + 1.0), u_peak) : mix(1.0, u_peak, u_pulse);
+        return vec3(highlight) * exposure;
+    }
+    }
+}
+
+void main() {
+    // the first band on top
+    float y = (1.0 - v_uv.y) * float(BANDS);
+    int index = int(min(floor(y), float(BANDS - 1)));
+    vec3 color = band(index, vec2(v_uv.x, fract(y)));
+
+    if (u_hdr_output != 0) {
+        FragColor = vec4(min(color, vec3(u_peak)), 1.0);
+    } else {
+        FragColor = vec4(pow(clamp(color, 0.0, 1.0), vec3(1.0 / 2.2)), 1.0);
+    }
+}
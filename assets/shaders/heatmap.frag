@@ -0,0 +1,12 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+uniform vec4 u_color;
+
+void main() {
+    FragColor = u_color;
+}
@@ -0,0 +1,40 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+uniform sampler2D u_image;
+// row by row, from the top left
+uniform float u_kernel[25];
+uniform vec2 u_texel;
+// what the weighted sum is multiplied by, then offset by
+uniform float u_scale;
+uniform float u_bias;
+// whether the left half shows the image untouched
+uniform bool u_split;
+uniform vec3 u_clear;
+
+// the image as it is seen on screen, over the clear color
+vec3 seen(vec2 uv) {
+    vec4 texel = texture(u_image, uv);
+    return mix(u_clear, texel.rgb, texel.a);
+}
+
+void main() {
+    if (u_split && v_uv.x < 0.5) {
+        FragColor = vec4(seen(v_uv), 1.0);
+        return;
+    }
+
+    vec3 sum = vec3(0.0);
+    for (int y = 0; y < 5; y++) {
+        for (int x = 0; x < 5; x++) {
+            vec2 offset = vec2(x - 2, y - 2) * u_texel;
+            sum += u_kernel[y * 5 + x] * seen(v_uv + offset);
+        }
+    }
+
+    FragColor = vec4(clamp(sum * u_scale + u_bias, 0.0, 1.0), 1.0);
+}
@@ -0,0 +1,60 @@
+#version 330 core
+precision mediump float;
+
+// must match the constants in clustered_lighting.rs and light-binning.comp
+const int CLUSTER_PIXELS = 32;
+const int MAX_LIGHTS_PER_CLUSTER = 128;
+// clusters with this many lights or more are red on the heatmap
+const float HEATMAP_MAX = 32.0;
+
+in vec2 v_world;
+in vec2 v_uv;
+flat in vec3 v_albedo;
+
+out vec4 frag_color;
+
+// two texels per light: position and radius, then color
+uniform samplerBuffer u_lights;
+uniform usamplerBuffer u_cluster_counts;
+uniform usamplerBuffer u_cluster_lights;
+uniform int u_clusters_x;
+uniform float u_ambient;
+uniform bool u_heatmap;
+
+// blue to red through green and yellow
+vec3 jet(float t) {
+    return clamp(1.5 - abs(4.0 * t - vec3(3.0, 2.0, 1.0)), 0.0, 1.0);
+}
+
+void main() {
+    ivec2 cluster = ivec2(gl_FragCoord.xy) / CLUSTER_PIXELS;
+    int index = cluster.y * u_clusters_x + cluster.x;
+    int count = int(texelFetch(u_cluster_counts, index).r);
+
+    // quads bulge like pillows, flat in the middle
+    vec2 bulge = v_uv * v_uv * v_uv;
+    vec3 normal = normalize(vec3(bulge * 0.8, 1.0));
+
+    vec3 lighting = vec3(u_ambient);
+    for (int i = 0; i < count; i++) {
+        int light = int(texelFetch(u_cluster_lights, index * MAX_LIGHTS_PER_CLUSTER + i).r);
+        vec4 position_radius = texelFetch(u_lights, light * 2);
+        vec3 color = texelFetch(u_lights, light * 2 + 1).rgb;
+
+        vec3 to_light = vec3(position_radius.xy - v_world, position_radius.z);
+        float dist = length(to_light);
+        float falloff = clamp(1.0 - dist * dist / (position_radius.w * position_radius.w), 0.0, 1.0);
+        lighting += color * falloff * falloff * max(dot(normal, to_light / dist), 0.0);
+    }
+
+    vec3 color = v_albedo * lighting;
+
+    if (u_heatmap) {
+        vec3 heat = jet(min(float(count) / HEATMAP_MAX, 1.0));
+        vec2 in_cluster = mod(gl_FragCoord.xy, float(CLUSTER_PIXELS));
+        bool edge = any(lessThan(in_cluster, vec2(1.0)));
+        color = edge ? vec3(1.0) : mix(color, heat, 0.6);
+    }
+
+    frag_color = vec4(color, 1.0);
+}
@@ -0,0 +1,43 @@
+#version 330 core
+precision mediump float;
+
+// a grid of quads centered on the origin, placed from their index alone
+uniform mat4 u_mvp;
+uniform int u_columns;
+uniform float u_spacing;
+uniform float u_quad_size;
+
+out vec2 v_world;
+out vec2 v_uv;
+flat out vec3 v_albedo;
+
+const vec2[4] corners = vec2[4](
+        vec2(-1.0, -1.0),
+        vec2(1.0, -1.0),
+        vec2(-1.0, 1.0),
+        vec2(1.0, 1.0)
+    );
+
+// cheap integer hash, good enough to pick colors
+uint hash(uint x) {
+    x ^= x >> 16;
+    x *= 0x7feb352du;
+    x ^= x >> 15;
+    x *= 0x846ca68bu;
+    x ^= x >> 16;
+    return x;
+}
+
+void main() {
+    int index = gl_InstanceID;
+    vec2 cell = vec2(index % u_columns, index / u_columns);
+    vec2 corner = corners[gl_VertexID];
+
+    vec2 center = (cell + 0.5 - float(u_columns) * 0.5) * u_spacing;
+    v_world = center + corner * u_quad_size * 0.5;
+    gl_Position = u_mvp * vec4(v_world, 0.0, 1.0);
+
+    uint h = hash(uint(index));
+    v_albedo = 0.5 + 0.5 * vec3(h & 0xffu, (h >> 8) & 0xffu, (h >> 16) & 0xffu) / 255.0;
+    v_uv = corner;
+}
@@ -0,0 +1,24 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+// the field, bilinearly filtered like marching squares interpolates it
+uniform sampler2D u_field;
+// colors of the values from -1 to 1
+uniform sampler2D u_colormap;
+uniform float u_iso;
+uniform float u_spacing;
+
+void main() {
+    float value = texture(u_field, v_uv).r;
+
+    // every band takes the color of the value in its middle, and the ones
+    // below the iso-value are darker
+    float band = floor((value - u_iso) / u_spacing);
+    float middle = u_iso + (band + 0.5) * u_spacing;
+    vec3 color = texture(u_colormap, vec2(middle * 0.5 + 0.5, 0.5)).rgb;
+    FragColor = vec4(band < 0.0 ? color * 0.55 : color, 1.0);
+}
@@ -0,0 +1,80 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+// the same texture through two samplers, the right half using the second one
+// when comparing filters
+uniform sampler2D u_left;
+uniform sampler2D u_right;
+uniform bool u_compare;
+// whether each sampler filters across mip levels at all
+uniform bool u_left_mips;
+uniform bool u_right_mips;
+// 0 picks the level from the derivatives, 1 forces u_lod, 2 offsets the
+// picked level by u_lod
+uniform int u_mode;
+uniform float u_lod;
+uniform float u_max_level;
+uniform vec2 u_size;
+// how much of the color of the sampled level shows over the image
+uniform float u_tint;
+
+const vec3 LEVEL_COLORS[8] = vec3[8](
+    vec3(0.90, 0.20, 0.20),
+    vec3(0.95, 0.60, 0.15),
+    vec3(0.95, 0.90, 0.20),
+    vec3(0.30, 0.85, 0.30),
+    vec3(0.20, 0.80, 0.85),
+    vec3(0.25, 0.40, 0.95),
+    vec3(0.65, 0.30, 0.90),
+    vec3(0.95, 0.40, 0.75)
+);
+
+vec3 level_color(float level) {
+    int below = int(floor(level));
+    vec3 a = LEVEL_COLORS[below % 8];
+    vec3 b = LEVEL_COLORS[(below + 1) % 8];
+    return mix(a, b, fract(level));
+}
+
+// the level the hardware picks, from how many texels a pixel covers
+float derived_lod() {
+    vec2 texels = v_uv * u_size;
+    float rho = max(length(dFdx(texels)), length(dFdy(texels)));
+    return log2(max(rho, 1e-6));
+}
+
+void main() {
+    bool right = u_compare && v_uv.x >= 0.5;
+    bool mips = right ? u_right_mips : u_left_mips;
+
+    // both halves are sampled everywhere, as implicit derivatives are only
+    // defined in uniform control flow
+    float lod = derived_lod();
+    vec4 left_color, right_color;
+    if (u_mode == 1) {
+        lod = u_lod;
+        left_color = textureLod(u_left, v_uv, lod);
+        right_color = textureLod(u_right, v_uv, lod);
+    } else if (u_mode == 2) {
+        lod += u_lod;
+        left_color = texture(u_left, v_uv, u_lod);
+        right_color = texture(u_right, v_uv, u_lod);
+    } else {
+        left_color = texture(u_left, v_uv);
+        right_color = texture(u_right, v_uv);
+    }
+    vec4 color = right ? right_color : left_color;
+
+    float level = mips ? clamp(lod, 0.0, u_max_level) : 0.0;
+    color.rgb = mix(color.rgb, level_color(level), u_tint);
+
+    // a line between the two filters
+    if (u_compare && abs(v_uv.x - 0.5) < fwidth(v_uv.x)) {
+        color = vec4(1.0);
+    }
+    FragColor = color;
+}
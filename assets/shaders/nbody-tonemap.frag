@@ -0,0 +1,33 @@
+#version 330 core
+precision highp float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+uniform sampler2D u_hdr;
+uniform sampler2D u_bloom;
+uniform float u_bloom_intensity;
+// in stops
+uniform float u_exposure;
+// 1 to write linear values up to u_peak times SDR white, see src/output.rs
+uniform int u_hdr_output;
+uniform float u_peak;
+
+// Narkowicz's fit of the ACES filmic curve.
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+void main() {
+    vec3 hdr = texelFetch(u_hdr, ivec2(gl_FragCoord.xy), 0).rgb;
+    vec3 bloom = texture(u_bloom, v_uv).rgb * u_bloom_intensity;
+
+    // space stays black whatever the theme
+    vec3 exposed = (hdr + bloom) * exp2(u_exposure);
+    if (u_hdr_output != 0) {
+        FragColor = vec4(min(exposed, vec3(u_peak)), 1.0);
+        return;
+    }
+    FragColor = vec4(aces(exposed), 1.0);
+}
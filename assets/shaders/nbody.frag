@@ -0,0 +1,14 @@
+#version 330 core
+precision mediump float;
+
+in vec3 v_color;
+
+out vec4 FragColor;
+
+void main() {
+    // a soft gaussian glow, added up where bodies overlap
+    vec2 p = gl_PointCoord * 2.0 - 1.0;
+    float glow = exp(-4.0 * dot(p, p));
+
+    FragColor = vec4(v_color * glow, 1.0);
+}
@@ -0,0 +1,18 @@
+#version 330 core
+precision mediump float;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec3 a_color;
+
+uniform mat4 u_mvp;
+uniform float u_point_size;
+// light each body adds at its center
+uniform float u_brightness;
+
+out vec3 v_color;
+
+void main() {
+    gl_Position = u_mvp * vec4(a_position, 0.0, 1.0);
+    gl_PointSize = u_point_size;
+    v_color = a_color * u_brightness;
+}
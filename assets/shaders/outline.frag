@@ -0,0 +1,30 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+in vec4 v_color;
+
+out vec4 frag_color;
+
+// outlines a disc instead of the whole quad
+uniform bool u_circle;
+
+void main() {
+    // distance to the border in pixels, v_uv going from -1 to 1 across the quad
+    float edge;
+    if (u_circle) {
+        float d = length(v_uv);
+        edge = (1.0 - d) / fwidth(d);
+    } else {
+        vec2 e = (1.0 - abs(v_uv)) / fwidth(v_uv);
+        edge = min(e.x, e.y);
+    }
+
+    if (edge < 0.0) {
+        discard;
+    }
+
+    // faint outline around a fill as transparent as the color says
+    float alpha = edge < 1.5 ? 0.6 : v_color.a;
+    frag_color = vec4(v_color.rgb, alpha);
+}
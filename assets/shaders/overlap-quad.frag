@@ -0,0 +1,16 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_corner;
+in float v_speed;
+
+out vec4 FragColor;
+
+void main() {
+    // rounded square, smoothed over a pixel
+    float d = length(max(abs(v_corner) - 0.6, 0.0)) - 0.4;
+    float alpha = clamp(0.5 - d / fwidth(d), 0.0, 1.0);
+
+    vec3 color = mix(vec3(0.2, 0.5, 0.9), vec3(1.0, 0.45, 0.2), clamp(v_speed / 400.0, 0.0, 1.0));
+    FragColor = vec4(color, alpha);
+}
@@ -0,0 +1,19 @@
+#version 330 core
+
+// one of the corners of the quad, from -0.5 to 0.5
+in vec2 corner;
+// of the quad, straight from the buffer the last step wrote
+in vec2 position;
+in vec2 velocity;
+
+uniform mat4 u_mvp;
+uniform float u_size;
+
+out vec2 v_corner;
+out float v_speed;
+
+void main() {
+    gl_Position = u_mvp * vec4(position + corner * u_size, 0.0, 1.0);
+    v_corner = corner * 2.0;
+    v_speed = length(velocity);
+}
@@ -0,0 +1,25 @@
+#version 330 core
+
+// Particle simulation step, captured with transform feedback.
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_velocity;
+
+uniform float u_dt;
+uniform vec2 u_attractor;
+uniform float u_strength;
+
+out vec2 v_position;
+out vec2 v_velocity;
+
+void main() {
+    // softened gravity towards the attractor
+    vec2 to_attractor = u_attractor - a_position;
+    float d2 = dot(to_attractor, to_attractor) + 2500.0;
+    vec2 acceleration = u_strength * 3.0e6 * to_attractor / (d2 * sqrt(d2));
+
+    vec2 velocity = (a_velocity + acceleration * u_dt) * exp(-0.3 * u_dt);
+
+    v_position = a_position + velocity * u_dt;
+    v_velocity = velocity;
+}
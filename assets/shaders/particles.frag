@@ -0,0 +1,14 @@
+#version 330 core
+precision mediump float;
+
+in vec4 v_color;
+
+out vec4 FragColor;
+
+void main() {
+    // round points
+    vec2 p = gl_PointCoord * 2.0 - 1.0;
+    float falloff = 1.0 - smoothstep(0.5, 1.0, dot(p, p));
+
+    FragColor = vec4(v_color.rgb, v_color.a * falloff);
+}
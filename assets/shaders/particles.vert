@@ -0,0 +1,18 @@
+#version 330 core
+precision mediump float;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_velocity;
+
+uniform mat4 u_mvp;
+uniform float u_point_size;
+
+out vec4 v_color;
+
+void main() {
+    gl_Position = u_mvp * vec4(a_position, 0.0, 1.0);
+    gl_PointSize = u_point_size;
+
+    float speed = clamp(length(a_velocity) / 600.0, 0.0, 1.0);
+    v_color = vec4(mix(vec3(0.2, 0.4, 1.0), vec3(1.0, 0.55, 0.2), speed), 0.5);
+}
@@ -0,0 +1,32 @@
+#version 330 core
+precision mediump float;
+
+// trail in red
+uniform sampler2D u_trail;
+// from the screen back to the world
+uniform mat4 u_inverse;
+// half the size of the trail map in world units, centered on the origin
+uniform vec2 u_half_size;
+uniform float u_exposure;
+uniform vec4 u_clear;
+uniform vec4 u_color;
+uniform vec4 u_highlight;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+void main() {
+    vec2 world = (u_inverse * vec4(v_uv * 2.0 - 1.0, 0.0, 1.0)).xy;
+    vec2 uv = world / (2.0 * u_half_size) + 0.5;
+    if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+        FragColor = u_clear;
+        return;
+    }
+
+    // the busiest paths pile up far more trail than the rest
+    float t = 1.0 - exp(-texture(u_trail, uv).r * u_exposure);
+    FragColor = t < 0.5
+        ? mix(u_clear, u_color, t * 2.0)
+        : mix(u_color, u_highlight, t * 2.0 - 1.0);
+}
@@ -0,0 +1,13 @@
+#version 330 core
+precision mediump float;
+
+in vec4 v_color;
+in float v_edge;
+
+out vec4 frag_color;
+
+void main() {
+    // antialias the sides of the line
+    float alpha = clamp((1.0 - abs(v_edge)) / fwidth(v_edge), 0.0, 1.0);
+    frag_color = vec4(v_color.rgb, v_color.a * alpha);
+}
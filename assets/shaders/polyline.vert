@@ -0,0 +1,18 @@
+#version 330 core
+precision mediump float;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec4 a_color;
+// -1 on one side of the line, 1 on the other
+layout(location = 2) in float a_edge;
+
+uniform mat4 u_mvp;
+
+out vec4 v_color;
+out float v_edge;
+
+void main() {
+    gl_Position = u_mvp * vec4(a_position, 0.0, 1.0);
+    v_color = a_color;
+    v_edge = a_edge;
+}
@@ -0,0 +1,40 @@
+#version 330 core
+precision mediump float;
+
+// distance between grid lines on the walls, in world units
+const float CELL = 100.0;
+const float BORDER = 6.0;
+
+// depth of the room, where the walls fade out the most
+uniform float u_depth;
+
+in float v_depth;
+in vec2 v_local;
+in vec2 v_size;
+in vec4 v_color;
+in float v_kind;
+
+out vec4 FragColor;
+
+void main() {
+    // darker the deeper, so that depth reads even while the head is still
+    float fade = mix(1.0, 0.3, clamp(v_depth / u_depth, 0.0, 1.0));
+
+    if (v_kind < 0.5) {
+        vec2 cell = v_local / CELL;
+        vec2 lines = abs(fract(cell - 0.5) - 0.5) / fwidth(cell);
+        float line = 1.0 - min(min(lines.x, lines.y), 1.0);
+        FragColor = vec4(v_color.rgb * fade, v_color.a * max(line, 0.08));
+        return;
+    }
+
+    float radius = min(v_size.x, v_size.y) * 0.2;
+    vec2 q = abs(v_local) - v_size * 0.5 + radius;
+    float d = length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - radius;
+    float aa = fwidth(d);
+
+    float alpha = clamp(0.5 - d / aa, 0.0, 1.0);
+    float border = clamp(0.5 + (d + BORDER) / aa, 0.0, 1.0);
+    vec3 rgb = mix(v_color.rgb, v_color.rgb * 0.45, border) * fade;
+    FragColor = vec4(rgb, v_color.a * alpha);
+}
@@ -0,0 +1,28 @@
+#version 330 core
+precision mediump float;
+
+uniform mat4 u_mvp;
+
+in vec3 position;
+// position in the quad, in world units from its center for cards and along
+// the wall for walls
+in vec2 local;
+in vec2 size;
+in vec4 color;
+// 0 for a wall, 1 for a card
+in float kind;
+
+out float v_depth;
+out vec2 v_local;
+out vec2 v_size;
+out vec4 v_color;
+out float v_kind;
+
+void main() {
+    v_depth = position.z;
+    v_local = local;
+    v_size = size;
+    v_color = color;
+    v_kind = kind;
+    gl_Position = u_mvp * vec4(position, 1.0);
+}
@@ -0,0 +1,44 @@
+#version 330 core
+
+uniform sampler2D u_image;
+uniform vec2 u_resolution;
+// 0 for Sobel, 1 for Scharr
+uniform int u_operator;
+// gradient from which pixels count as edges, and how far they fade in
+uniform float u_threshold;
+uniform float u_softness;
+// distance of the taps in pixels, which thickens the outlines
+uniform float u_width;
+// color of the outlines
+uniform float u_red;
+uniform float u_green;
+uniform float u_blue;
+// how much of the image shows under the outlines, the rest being white
+uniform float u_backdrop;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+vec3 tap(int x, int y) {
+    return texture(u_image, v_uv + vec2(x, y) * u_width / u_resolution).rgb;
+}
+
+void main() {
+    vec4 color = texture(u_image, v_uv);
+
+    // weights of the sides and the middle of the kernel, normalized so that
+    // both operators give the same gradient across a hard edge
+    vec2 w = u_operator == 0 ? vec2(1.0, 2.0) / 4.0 : vec2(3.0, 10.0) / 16.0;
+
+    vec3 gx = w.x * (tap(1, -1) + tap(1, 1) - tap(-1, -1) - tap(-1, 1)) + w.y * (tap(1, 0) - tap(-1, 0));
+    vec3 gy = w.x * (tap(-1, 1) + tap(1, 1) - tap(-1, -1) - tap(1, -1)) + w.y * (tap(0, 1) - tap(0, -1));
+
+    // per channel, so that edges between colors of the same brightness show
+    vec3 magnitude = sqrt(gx * gx + gy * gy);
+    float gradient = max(magnitude.r, max(magnitude.g, magnitude.b));
+    float edge = smoothstep(u_threshold, u_threshold + u_softness + 1e-4, gradient);
+
+    vec3 base = mix(vec3(1.0), color.rgb, u_backdrop);
+    FragColor = vec4(mix(base, vec3(u_red, u_green, u_blue), edge), color.a);
+}
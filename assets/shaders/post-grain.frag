@@ -0,0 +1,27 @@
+#version 330 core
+
+uniform sampler2D u_image;
+// blue noise tile, repeating
+uniform sampler2D u_noise;
+uniform vec2 u_resolution;
+// where the tile starts this frame, in pixels
+uniform vec2 u_offset;
+uniform float u_intensity;
+// size of a grain in pixels
+uniform float u_size;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+void main() {
+    vec4 color = texture(u_image, v_uv);
+
+    vec2 texel = floor((v_uv * u_resolution + u_offset) / u_size);
+    float noise = texture(u_noise, texel / vec2(textureSize(u_noise, 0))).r - 0.5;
+
+    // like film, most visible in the midtones
+    float luma = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+    float response = 4.0 * luma * (1.0 - luma);
+    FragColor = vec4(color.rgb + noise * u_intensity * (0.25 + 0.75 * response), color.a);
+}
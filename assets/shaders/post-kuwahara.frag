@@ -0,0 +1,158 @@
+#version 330 core
+
+// Anisotropic Kuwahara filter with polynomial weighting functions, after
+// Kyprianidis et al. Each pixel becomes the average of whichever sector of an
+// ellipse around it varies the least, the ellipse following the local
+// structure of the image, which flattens areas into brush strokes.
+
+uniform sampler2D u_image;
+uniform vec2 u_resolution;
+// radius of the filter in pixels
+uniform float u_radius;
+// 0 for four sectors, 1 for eight
+uniform int u_sectors;
+// how much the sectors with the least variance win over the others
+uniform float u_sharpness;
+uniform float u_hardness;
+// how elongated the ellipse gets along edges, lower being more
+uniform float u_anisotropy;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+const float SQRT_1_2 = 0.70710678;
+
+vec3 fetch(vec2 offset) {
+    return texture(u_image, v_uv + offset / u_resolution).rgb;
+}
+
+// structure tensor (E, F, G) averaged over the 3x3 pixels around this one
+vec3 structure_tensor() {
+    vec3 taps[25];
+    for (int y = -2; y <= 2; y++) {
+        for (int x = -2; x <= 2; x++) {
+            taps[(y + 2) * 5 + x + 2] = fetch(vec2(x, y));
+        }
+    }
+
+    vec3 tensor = vec3(0.0);
+    for (int y = -1; y <= 1; y++) {
+        for (int x = -1; x <= 1; x++) {
+            int i = (y + 2) * 5 + x + 2;
+            // Sobel at this neighbor
+            vec3 gx = (taps[i - 4] + 2.0 * taps[i + 1] + taps[i + 6] - taps[i - 6] - 2.0 * taps[i - 1] - taps[i + 4]) / 4.0;
+            vec3 gy = (taps[i + 4] + 2.0 * taps[i + 5] + taps[i + 6] - taps[i - 6] - 2.0 * taps[i - 5] - taps[i - 4]) / 4.0;
+            // binomial weights, a small Gaussian
+            float weight = (2.0 - abs(float(x))) * (2.0 - abs(float(y))) / 16.0;
+            tensor += weight * vec3(dot(gx, gx), dot(gx, gy), dot(gy, gy));
+        }
+    }
+    return tensor;
+}
+
+void main() {
+    vec4 color = texture(u_image, v_uv);
+
+    // orientation and anisotropy from the eigenvectors of the tensor
+    vec3 t = structure_tensor();
+    float root = sqrt((t.x - t.z) * (t.x - t.z) + 4.0 * t.y * t.y);
+    float lambda1 = 0.5 * (t.x + t.z + root);
+    float lambda2 = 0.5 * (t.x + t.z - root);
+    vec2 v = vec2(lambda1 - t.x, -t.y);
+    vec2 direction = length(v) > 0.0 ? normalize(v) : vec2(0.0, 1.0);
+    float phi = -atan(direction.y, direction.x);
+    float anisotropy = lambda1 + lambda2 > 0.0 ? (lambda1 - lambda2) / (lambda1 + lambda2) : 0.0;
+
+    // the ellipse, stretched along the edges
+    float a = u_radius * clamp((u_anisotropy + anisotropy) / u_anisotropy, 0.1, 2.0);
+    float b = u_radius * clamp(u_anisotropy / (u_anisotropy + anisotropy), 0.1, 2.0);
+    float cos_phi = cos(phi);
+    float sin_phi = sin(phi);
+    mat2 to_disc = mat2(0.5 / a, 0.0, 0.0, 0.5 / b) * mat2(cos_phi, -sin_phi, sin_phi, cos_phi);
+    int max_x = int(sqrt(a * a * cos_phi * cos_phi + b * b * sin_phi * sin_phi));
+    int max_y = int(sqrt(a * a * sin_phi * sin_phi + b * b * cos_phi * cos_phi));
+
+    // polynomial approximation of the sector weights
+    float zeta = 2.0 / u_radius;
+    float zero_crossing = 0.58;
+    float eta = (zeta + cos(zero_crossing)) / (sin(zero_crossing) * sin(zero_crossing));
+
+    vec4 m[8];
+    vec3 s[8];
+    for (int k = 0; k < 8; k++) {
+        m[k] = vec4(0.0);
+        s[k] = vec3(0.0);
+    }
+
+    for (int j = -max_y; j <= max_y; j++) {
+        for (int i = -max_x; i <= max_x; i++) {
+            vec2 p = to_disc * vec2(i, j);
+            if (dot(p, p) > 0.25) {
+                continue;
+            }
+
+            vec3 c = fetch(vec2(i, j));
+            float w[8];
+            float z;
+            float sum = 0.0;
+
+            float vxx = zeta - eta * p.x * p.x;
+            float vyy = zeta - eta * p.y * p.y;
+            z = max(0.0, p.y + vxx);
+            w[0] = z * z;
+            z = max(0.0, -p.x + vyy);
+            w[2] = z * z;
+            z = max(0.0, -p.y + vxx);
+            w[4] = z * z;
+            z = max(0.0, p.x + vyy);
+            w[6] = z * z;
+
+            if (u_sectors == 1) {
+                // the same, rotated by 45°
+                vec2 q = SQRT_1_2 * vec2(p.x - p.y, p.x + p.y);
+                vxx = zeta - eta * q.x * q.x;
+                vyy = zeta - eta * q.y * q.y;
+                z = max(0.0, q.y + vxx);
+                w[1] = z * z;
+                z = max(0.0, -q.x + vyy);
+                w[3] = z * z;
+                z = max(0.0, -q.y + vxx);
+                w[5] = z * z;
+                z = max(0.0, q.x + vyy);
+                w[7] = z * z;
+            } else {
+                w[1] = w[3] = w[5] = w[7] = 0.0;
+            }
+
+            for (int k = 0; k < 8; k++) {
+                sum += w[k];
+            }
+            if (sum <= 0.0) {
+                continue;
+            }
+
+            float g = exp(-3.125 * dot(p, p)) / sum;
+            for (int k = 0; k < 8; k++) {
+                float wk = w[k] * g;
+                m[k] += vec4(c * wk, wk);
+                s[k] += c * c * wk;
+            }
+        }
+    }
+
+    // sectors weighted by how little they vary
+    vec4 result = vec4(0.0);
+    for (int k = 0; k < 8; k++) {
+        if (m[k].w <= 0.0) {
+            continue;
+        }
+        vec3 mean = m[k].rgb / m[k].w;
+        vec3 variance = abs(s[k] / m[k].w - mean * mean);
+        float sigma2 = variance.r + variance.g + variance.b;
+        float w = 1.0 / (1.0 + pow(u_hardness * 1000.0 * sigma2, 0.5 * u_sharpness));
+        result += vec4(mean * w, w);
+    }
+
+    FragColor = vec4(result.w > 0.0 ? result.rgb / result.w : color.rgb, color.a);
+}
@@ -0,0 +1,28 @@
+#version 330 core
+
+uniform sampler2D u_image;
+uniform vec2 u_resolution;
+// how much of the detail is added back
+uniform float u_amount;
+// distance of the blur's taps in pixels
+uniform float u_radius;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+void main() {
+    vec4 color = texture(u_image, v_uv);
+
+    // unsharp mask: the image plus its difference with a blurred copy
+    vec2 d = u_radius / u_resolution;
+    vec3 blurred = (
+        texture(u_image, v_uv + vec2(-d.x, -d.y)).rgb
+        + texture(u_image, v_uv + vec2(d.x, -d.y)).rgb
+        + texture(u_image, v_uv + vec2(-d.x, d.y)).rgb
+        + texture(u_image, v_uv + vec2(d.x, d.y)).rgb
+    ) * 0.25;
+
+    vec3 sharpened = color.rgb + (color.rgb - blurred) * u_amount;
+    FragColor = vec4(clamp(sharpened, 0.0, 1.0), color.a);
+}
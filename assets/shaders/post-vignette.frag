@@ -0,0 +1,27 @@
+#version 330 core
+
+uniform sampler2D u_image;
+uniform vec2 u_resolution;
+// darkening at the corners, from 0 to 1
+uniform float u_strength;
+// where the darkening starts, as a fraction of the distance to the corners
+uniform float u_radius;
+// how far the darkening fades in
+uniform float u_softness;
+// 0 follows the shape of the window, 1 is a circle
+uniform float u_roundness;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+void main() {
+    vec4 color = texture(u_image, v_uv);
+
+    // from the center, scaled so that the corners are at a distance of 1
+    vec2 aspect = mix(vec2(1.0), u_resolution / length(u_resolution) * sqrt(2.0), u_roundness);
+    float d = length((v_uv - 0.5) * 2.0 * aspect) / sqrt(2.0);
+
+    float falloff = smoothstep(u_radius, u_radius + u_softness, d);
+    FragColor = vec4(color.rgb * (1.0 - u_strength * falloff), color.a);
+}
@@ -0,0 +1,162 @@
+#version 330 core
+precision highp float;
+
+// Spheres and rounded boxes over a floor, lit by one light with soft shadows
+// and ambient occlusion, all found by sphere tracing distance fields: the
+// kind of scene that spends everything on fragments.
+//
+// With checkerboard rendering, the viewport is half as wide as the picture,
+// and every pixel drawn stands for one of two in its row of the picture,
+// alternating between rows and between frames, so that two frames cover it.
+
+// size of the whole picture, in pixels
+uniform vec2 u_resolution;
+uniform bool u_checkerboard;
+// which half of the checkerboard this frame draws, 0 or 1
+uniform int u_parity;
+uniform float u_time;
+uniform vec3 u_eye;
+uniform vec3 u_target;
+uniform int u_steps;
+uniform int u_shadow_steps;
+
+out vec4 FragColor;
+
+const float FAR = 60.0;
+const float EPSILON = 0.001;
+const vec3 LIGHT = normalize(vec3(0.6, 0.8, -0.4));
+const vec3 SKY = vec3(0.55, 0.7, 0.9);
+
+float sd_sphere(vec3 p, float radius) {
+    return length(p) - radius;
+}
+
+float sd_round_box(vec3 p, vec3 half_size, float radius) {
+    vec3 q = abs(p) - half_size + radius;
+    return length(max(q, 0.0)) + min(max(q.x, max(q.y, q.z)), 0.0) - radius;
+}
+
+float smooth_min(float a, float b, float k) {
+    float h = clamp(0.5 + 0.5 * (b - a) / k, 0.0, 1.0);
+    return mix(b, a, h) - k * h * (1.0 - h);
+}
+
+// Distance to the scene in x, and what was hit in y: 0 for the floor, 1 for
+// the blobs and 2 for the boxes.
+vec2 map(vec3 p) {
+    vec2 hit = vec2(p.y, 0.0);
+
+    float blobs = FAR;
+    for (int i = 0; i < 5; i++) {
+        float angle = u_time * (0.3 + 0.1 * float(i)) + float(i) * 1.2566;
+        vec3 center = vec3(cos(angle) * 2.5, 1.0 + 0.5 * sin(u_time + float(i)), sin(angle) * 2.5);
+        blobs = smooth_min(blobs, sd_sphere(p - center, 0.7), 0.6);
+    }
+    if (blobs < hit.x) {
+        hit = vec2(blobs, 1.0);
+    }
+
+    // a ring of boxes, repeated around the origin
+    float sector = 6.2832 / 12.0;
+    float a = atan(p.z, p.x);
+    float nearest = (floor(a / sector) + 0.5) * sector;
+    vec3 q = vec3(
+        cos(nearest) * p.x + sin(nearest) * p.z - 6.0,
+        p.y - 0.6,
+        -sin(nearest) * p.x + cos(nearest) * p.z
+    );
+    float boxes = sd_round_box(q, vec3(0.5, 0.6, 0.5), 0.1);
+    if (boxes < hit.x) {
+        hit = vec2(boxes, 2.0);
+    }
+    return hit;
+}
+
+vec3 normal_at(vec3 p) {
+    vec2 e = vec2(EPSILON, 0.0);
+    return normalize(vec3(
+        map(p + e.xyy).x - map(p - e.xyy).x,
+        map(p + e.yxy).x - map(p - e.yxy).x,
+        map(p + e.yyx).x - map(p - e.yyx).x
+    ));
+}
+
+float soft_shadow(vec3 origin, vec3 dir) {
+    float light = 1.0;
+    float t = 0.02;
+    for (int i = 0; i < u_shadow_steps && t < FAR; i++) {
+        float d = map(origin + dir * t).x;
+        if (d < EPSILON) {
+            return 0.0;
+        }
+        light = min(light, 8.0 * d / t);
+        t += clamp(d, 0.02, 1.0);
+    }
+    return clamp(light, 0.0, 1.0);
+}
+
+float occlusion(vec3 p, vec3 n) {
+    float occluded = 0.0;
+    float weight = 1.0;
+    for (int i = 1; i <= 5; i++) {
+        float h = 0.06 * float(i);
+        occluded += (h - map(p + n * h).x) * weight;
+        weight *= 0.75;
+    }
+    return clamp(1.0 - 3.0 * occluded, 0.0, 1.0);
+}
+
+vec3 albedo(vec3 p, float material) {
+    if (material < 0.5) {
+        // checkered floor, thin lines that alias
+        vec2 cell = floor(p.xz);
+        return mod(cell.x + cell.y, 2.0) < 1.0 ? vec3(0.85) : vec3(0.25);
+    }
+    if (material < 1.5) {
+        return vec3(0.9, 0.35, 0.2);
+    }
+    return vec3(0.25, 0.55, 0.85);
+}
+
+void main() {
+    vec2 pixel = gl_FragCoord.xy;
+    if (u_checkerboard) {
+        int row = int(pixel.y);
+        pixel.x = floor(pixel.x) * 2.0 + float((row + u_parity) & 1) + 0.5;
+    }
+
+    vec2 ndc = (pixel * 2.0 - u_resolution) / u_resolution.y;
+    vec3 forward = normalize(u_target - u_eye);
+    vec3 right = normalize(cross(forward, vec3(0.0, 1.0, 0.0)));
+    vec3 up = cross(right, forward);
+    vec3 dir = normalize(forward * 1.8 + right * ndc.x + up * ndc.y);
+
+    float t = 0.0;
+    vec2 hit = vec2(FAR, -1.0);
+    for (int i = 0; i < u_steps; i++) {
+        vec2 h = map(u_eye + dir * t);
+        if (h.x < EPSILON * t) {
+            hit = vec2(t, h.y);
+            break;
+        }
+        t += h.x;
+        if (t > FAR) {
+            break;
+        }
+    }
+
+    vec3 sky = SKY - dir.y * 0.3;
+    if (hit.y < 0.0) {
+        FragColor = vec4(sky, 1.0);
+        return;
+    }
+
+    vec3 p = u_eye + dir * hit.x;
+    vec3 n = normal_at(p);
+    float diffuse = max(dot(n, LIGHT), 0.0) * soft_shadow(p + n * 0.01, LIGHT);
+    float ambient = occlusion(p, n) * (0.5 + 0.5 * n.y);
+    vec3 color = albedo(p, hit.y) * (diffuse * vec3(1.0, 0.95, 0.85) + ambient * SKY * 0.4);
+    // fog toward the horizon
+    color = mix(color, sky, 1.0 - exp(-0.0015 * hit.x * hit.x));
+    FragColor = vec4(pow(color, vec3(1.0 / 2.2)), 1.0);
+}
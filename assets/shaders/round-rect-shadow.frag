@@ -0,0 +1,41 @@
+#version 330
+precision mediump float;
+
+in vec2 v_pos;
+flat in vec2 v_size;
+flat in vec4 v_border_radii;
+flat in float v_corner_exponent;
+flat in float v_blur;
+flat in float v_opacity;
+
+out vec4 FragColor;
+
+// same as round-rect.frag
+float p_norm(vec2 v, float p) {
+    float m = max(v.x, v.y);
+    if (m <= 0.0) {
+        return 0.0;
+    }
+    vec2 n = v / m;
+    return m * pow(pow(n.x, p) + pow(n.y, p), 1.0 / p);
+}
+
+// same as round-rect.frag
+float sd_rounded_box(vec2 pos, vec2 size, vec4 radii, float exponent) {
+    vec2 side = pos.x < 0.0 ? radii.xw : radii.yz;
+    float radius = min(pos.y < 0.0 ? side.x : side.y, min(size.x, size.y) * 0.5);
+
+    vec2 q = abs(pos) - size * 0.5 + radius;
+    return min(max(q.x, q.y), 0.0) + p_norm(max(q, 0.0), exponent) - radius;
+}
+
+void main() {
+    float dist = sd_rounded_box(v_pos, v_size, v_border_radii, v_corner_exponent);
+
+    // a box blurred by a gaussian falls off about like a smoothstep of its
+    // distance field, centered on the edge
+    float blur = max(v_blur, fwidth(dist));
+    float shadow = 1.0 - smoothstep(-blur, blur, dist);
+
+    FragColor = vec4(0.0, 0.0, 0.0, shadow * v_opacity);
+}
@@ -0,0 +1,134 @@
+#version 330
+precision mediump float;
+
+uniform mat4 u_mvp;
+// what the ambient animations need, the same in round-rect.vert and round-rect-shadow.vert
+uniform float u_time;
+// 0 for none, 1 for waves, 2 for ripples, 3 for flow
+uniform int u_ambient;
+// half the size of the field, which ripples start from anywhere in
+uniform vec2 u_field;
+
+// the same attributes as round-rect.vert, for the same vertices
+in vec2 position;
+in vec2 size;
+in vec4 border_radii;
+in float intensity;
+in float corner_exponent;
+in float rotation;
+in float elevation;
+
+// position relative to the center of the quad, in its own space
+out vec2 v_pos;
+flat out vec2 v_size;
+flat out vec4 v_border_radii;
+flat out float v_corner_exponent;
+flat out float v_blur;
+flat out float v_opacity;
+
+const vec2[4] uvs = vec2[4](
+        vec2(-0.5, -0.5),
+        vec2(-0.5, 0.5),
+        vec2(0.5, 0.5),
+        vec2(0.5, -0.5)
+    );
+
+// how a shadow spreads and drops as its quad rises, in world units
+const float BLUR_PER_ELEVATION = 0.75;
+const float OFFSET_PER_ELEVATION = 0.5;
+
+const float TAU = 6.28318530718;
+
+// ripples in flight at once, each starting again from another point once it dies out
+const int RIPPLES = 6;
+const float RIPPLE_PERIOD = 6.0;
+const float RIPPLE_SPEED = 320.0;
+const float RIPPLE_WIDTH = 64.0;
+
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+vec2 hash2(vec2 p) {
+    return vec2(hash(p), hash(p + 17.0));
+}
+
+// Perlin's gradient noise, roughly between -1 and 1.
+float noise(vec2 p) {
+    vec2 i = floor(p);
+    vec2 f = fract(p);
+    vec2 u = f * f * (3.0 - 2.0 * f);
+
+    float a = dot(hash2(i) * 2.0 - 1.0, f);
+    float b = dot(hash2(i + vec2(1.0, 0.0)) * 2.0 - 1.0, f - vec2(1.0, 0.0));
+    float c = dot(hash2(i + vec2(0.0, 1.0)) * 2.0 - 1.0, f - vec2(0.0, 1.0));
+    float d = dot(hash2(i + 1.0) * 2.0 - 1.0, f - 1.0);
+    return 2.0 * mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+// How the ambient animation moves the quad centered on center at u_time, as
+// (scale, added rotation, added intensity, hue rotation).
+vec4 ambient(vec2 center) {
+    if (u_ambient == 1) {
+        // two sine waves crossing the grid
+        float a = sin(dot(center, vec2(0.8, 0.6)) * TAU / 480.0 - u_time * 2.0);
+        float b = sin(dot(center, vec2(-0.3, 0.95)) * TAU / 760.0 - u_time * 1.3);
+        float wave = (a + b) * 0.5;
+        return vec4(1.0 + 0.25 * wave, 0.5 * wave, 0.3 * wave, 0.0);
+    }
+
+    if (u_ambient == 2) {
+        float bump = 0.0;
+        for (int i = 0; i < RIPPLES; i++) {
+            // staggered so that the ripples don't all start at once
+            float t = u_time / RIPPLE_PERIOD + float(i) / float(RIPPLES);
+            float age = fract(t);
+            vec2 origin = (hash2(vec2(floor(t), float(i))) * 2.0 - 1.0) * u_field;
+
+            float radius = age * RIPPLE_PERIOD * RIPPLE_SPEED;
+            float d = (distance(center, origin) - radius) / RIPPLE_WIDTH;
+            bump += (1.0 - age) * exp(-d * d);
+        }
+        return vec4(1.0 + 0.5 * bump, bump, 0.6 * bump, 0.0);
+    }
+
+    if (u_ambient == 3) {
+        // rotations and hues drifting along two noise fields
+        vec2 p = center / 640.0;
+        float angle = noise(p + vec2(0.15, 0.1) * u_time);
+        float hue = noise(p * 1.7 - 0.1 * u_time + 5.0);
+        return vec4(1.0, angle * TAU * 0.25, 0.0, hue * TAU * 0.5);
+    }
+
+    return vec4(1.0, 0.0, 0.0, 0.0);
+}
+
+mat2 rotate(float angle) {
+    return mat2(cos(angle), sin(angle), -sin(angle), cos(angle));
+}
+
+void main() {
+    vec2 uv = uvs[gl_VertexID % 4];
+    vec2 center = position - rotate(rotation) * (uv * size);
+
+    vec4 a = ambient(center);
+    vec2 scaled = size * a.x;
+    mat2 r = rotate(rotation + a.y);
+
+    // spinning quads are lifted off the field, intensity being 0.5 at rest
+    float lifted = elevation * (intensity + a.z) * 2.0;
+    float blur = lifted * BLUR_PER_ELEVATION;
+    // the light comes from the top of the screen, whatever the rotation of the quad
+    vec2 offset = vec2(0.0, lifted * OFFSET_PER_ELEVATION);
+
+    // grown by the blur so that it fits the whole penumbra
+    v_pos = uv * (scaled + 2.0 * blur);
+    gl_Position = u_mvp * vec4(center + offset + r * v_pos, 0.0, 1.0);
+
+    v_size = scaled;
+    v_border_radii = border_radii * a.x;
+    v_corner_exponent = corner_exponent;
+    v_blur = blur;
+    // higher shadows are wider, so lighter
+    v_opacity = 0.45 / (1.0 + lifted * 0.1);
+}
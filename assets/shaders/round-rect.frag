@@ -5,36 +5,97 @@ in vec2 v_uv;
 in vec2 v_size;
 in vec4 v_fill_color;
 in vec4 v_stroke_color;
-in float v_border_radius;
+flat in vec4 v_border_radii;
 in float v_border_width;
 in float v_intensity;
+in vec2 v_atlas_uv;
+in float v_texture_mix;
+in vec4 v_gradient_color;
+flat in vec2 v_gradient_vector;
+flat in float v_gradient_kind;
+flat in float v_corner_exponent;
+in float v_border_fade;
+
+uniform sampler2D u_texture;
+// pixels per world unit, the scale of the camera
+uniform float u_pixels_per_unit;
 
 out vec4 FragColor;
 
+// Length of v in the p-norm, which is the euclidean one for p = 2.
+// Divided by the largest component first so that powers stay in range.
+float p_norm(vec2 v, float p) {
+    float m = max(v.x, v.y);
+    if (m <= 0.0) {
+        return 0.0;
+    }
+    vec2 n = v / m;
+    return m * pow(pow(n.x, p) + pow(n.y, p), 1.0 / p);
+}
+
 // Modified based on https://iquilezles.org/articles/distfunctions2d/
 // That website is very handy
-float sd_rounded_box(vec2 pos, vec2 size, float radius) {
+//
+// radii are top-left, top-right, bottom-right, bottom-left, with y going down.
+// Corners are superellipses of the given exponent, which makes the distance
+// approximate away from 2, but close enough to antialias.
+float sd_rounded_box(vec2 pos, vec2 size, vec4 radii, float exponent) {
+    // the (top, bottom) radii of the side of pos
+    vec2 side = pos.x < 0.0 ? radii.xw : radii.yz;
+    float radius = min(pos.y < 0.0 ? side.x : side.y, min(size.x, size.y) * 0.5);
+
     vec2 q = abs(pos) - size * 0.5 + radius;
-    return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - radius;
+    return min(max(q.x, q.y), 0.0) + p_norm(max(q, 0.0), exponent) - radius;
+}
+
+// where uv is between the fill color (0) and the gradient color (1)
+float gradient(vec2 uv) {
+    if (v_gradient_kind < 0.5) {
+        return 0.0;
+    }
+
+    if (v_gradient_kind < 1.5) {
+        // from one side of the quad to the other along the direction
+        vec2 direction = v_gradient_vector;
+        float extent = 0.5 * (abs(direction.x) + abs(direction.y));
+        return clamp(0.5 + 0.5 * dot(uv, direction) / extent, 0.0, 1.0);
+    }
+
+    // from the center to the farthest corner
+    vec2 center = v_gradient_vector;
+    float extent = length(vec2(0.5) + abs(center));
+    return clamp(distance(uv, center) / extent, 0.0, 1.0);
 }
 
 void main() {
     vec2 pos = v_uv * v_size;
 
-    float dist = sd_rounded_box(pos, v_size, v_border_radius);
-    float delta = fwidth(dist);
+    float dist = sd_rounded_box(pos, v_size, v_border_radii, v_corner_exponent);
+    // a pixel's worth of distance to antialias over: the length of its
+    // gradient rather than fwidth's sum, which smears diagonal edges, and
+    // never less than a pixel at the camera's scale, as derivatives of
+    // distances magnified a lot get too coarse and show bands
+    float delta = max(length(vec2(dFdx(dist), dFdy(dist))), 1.0 / u_pixels_per_unit);
+
+    // sampled before discarding, so that mipmap selection stays defined
+    vec4 texel = texture(u_texture, v_atlas_uv);
 
     if (dist > 0.0) {
         discard;
     }
 
+    vec4 fill_color = mix(v_fill_color, v_gradient_color, gradient(v_uv));
+    fill_color = mix(fill_color, texel, v_texture_mix);
+    // a darker shade of the texture for the border, so that it reads as the image too
+    vec4 stroke_color = mix(v_stroke_color, vec4(texel.rgb * 0.5, v_stroke_color.a), v_texture_mix);
+
     vec4 frag_color = mix(
             mix(
-                v_fill_color,
-                v_stroke_color,
-                smoothstep(-v_border_width - delta, -v_border_width, dist)
+                fill_color,
+                stroke_color,
+                smoothstep(-v_border_width - delta, -v_border_width, dist) * v_border_fade
             ),
-            vec4(v_stroke_color.rgb, 0.0),
+            vec4(stroke_color.rgb, 0.0),
             smoothstep(-delta, 0.0, dist)
         );
 
@@ -2,22 +2,55 @@
 precision mediump float;
 
 uniform mat4 u_mvp;
+// what the ambient animations need, the same in round-rect.vert and round-rect-shadow.vert
+uniform float u_time;
+// 0 for none, 1 for waves, 2 for ripples, 3 for flow
+uniform int u_ambient;
+// half the size of the field, which ripples start from anywhere in
+uniform vec2 u_field;
+// world offset per unit of height, for the eye drawn in stereo
+uniform vec2 u_parallax;
+// pixels per world unit, the scale of the camera
+uniform float u_pixels_per_unit;
 
 in vec2 position;
 in vec2 size;
 in vec4 fill_color;
 in vec4 stroke_color;
-in float border_radius;
+// radii of the corners: top-left, top-right, bottom-right, bottom-left
+in vec4 border_radii;
 in float border_width;
 in float intensity;
+// region of u_texture covering the quad, as (min, max)
+in vec4 atlas_uv;
+// how much the texture replaces the fill color
+in float texture_mix;
+// color the fill blends into
+in vec4 gradient_color;
+// direction of a linear gradient, or center of a radial one
+in vec2 gradient_vector;
+// 0 for flat, 1 for linear, 2 for radial
+in float gradient_kind;
+// exponent of the superellipse rounding the corners, 2 for circular ones
+in float corner_exponent;
+in float rotation;
+in float elevation;
 
 out vec2 v_uv;
 out vec2 v_size;
 out vec4 v_fill_color;
 out vec4 v_stroke_color;
-out float v_border_radius;
+flat out vec4 v_border_radii;
 out float v_border_width;
 out float v_intensity;
+out vec2 v_atlas_uv;
+out float v_texture_mix;
+out vec4 v_gradient_color;
+flat out vec2 v_gradient_vector;
+flat out float v_gradient_kind;
+flat out float v_corner_exponent;
+// how much of the stroke color the border gets, below 1 for subpixel borders
+out float v_border_fade;
 
 const vec2[4] uvs = vec2[4](
         vec2(-0.5, -0.5),
@@ -26,13 +59,139 @@ const vec2[4] uvs = vec2[4](
         vec2(0.5, -0.5)
     );
 
+const float TAU = 6.28318530718;
+
+// Borders thinner than this many pixels are drawn this thick with their color
+// faded into the fill, which keeps what they cover on average without them
+// shimmering as they hit some pixels and miss others.
+const float MIN_BORDER_PIXELS = 1.0;
+// Radii smaller than this many pixels round nothing that can be seen.
+const float MIN_RADIUS_PIXELS = 0.5;
+
+// ripples in flight at once, each starting again from another point once it dies out
+const int RIPPLES = 6;
+const float RIPPLE_PERIOD = 6.0;
+const float RIPPLE_SPEED = 320.0;
+const float RIPPLE_WIDTH = 64.0;
+
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+vec2 hash2(vec2 p) {
+    return vec2(hash(p), hash(p + 17.0));
+}
+
+// Perlin's gradient noise, roughly between -1 and 1.
+float noise(vec2 p) {
+    vec2 i = floor(p);
+    vec2 f = fract(p);
+    vec2 u = f * f * (3.0 - 2.0 * f);
+
+    float a = dot(hash2(i) * 2.0 - 1.0, f);
+    float b = dot(hash2(i + vec2(1.0, 0.0)) * 2.0 - 1.0, f - vec2(1.0, 0.0));
+    float c = dot(hash2(i + vec2(0.0, 1.0)) * 2.0 - 1.0, f - vec2(0.0, 1.0));
+    float d = dot(hash2(i + 1.0) * 2.0 - 1.0, f - 1.0);
+    return 2.0 * mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+// How the ambient animation moves the quad centered on center at u_time, as
+// (scale, added rotation, added intensity, hue rotation).
+vec4 ambient(vec2 center) {
+    if (u_ambient == 1) {
+        // two sine waves crossing the grid
+        float a = sin(dot(center, vec2(0.8, 0.6)) * TAU / 480.0 - u_time * 2.0);
+        float b = sin(dot(center, vec2(-0.3, 0.95)) * TAU / 760.0 - u_time * 1.3);
+        float wave = (a + b) * 0.5;
+        return vec4(1.0 + 0.25 * wave, 0.5 * wave, 0.3 * wave, 0.0);
+    }
+
+    if (u_ambient == 2) {
+        float bump = 0.0;
+        for (int i = 0; i < RIPPLES; i++) {
+            // staggered so that the ripples don't all start at once
+            float t = u_time / RIPPLE_PERIOD + float(i) / float(RIPPLES);
+            float age = fract(t);
+            vec2 origin = (hash2(vec2(floor(t), float(i))) * 2.0 - 1.0) * u_field;
+
+            float radius = age * RIPPLE_PERIOD * RIPPLE_SPEED;
+            float d = (distance(center, origin) - radius) / RIPPLE_WIDTH;
+            bump += (1.0 - age) * exp(-d * d);
+        }
+        return vec4(1.0 + 0.5 * bump, bump, 0.6 * bump, 0.0);
+    }
+
+    if (u_ambient == 3) {
+        // rotations and hues drifting along two noise fields
+        vec2 p = center / 640.0;
+        float angle = noise(p + vec2(0.15, 0.1) * u_time);
+        float hue = noise(p * 1.7 - 0.1 * u_time + 5.0);
+        return vec4(1.0, angle * TAU * 0.25, 0.0, hue * TAU * 0.5);
+    }
+
+    return vec4(1.0, 0.0, 0.0, 0.0);
+}
+
+mat2 rotate(float angle) {
+    return mat2(cos(angle), sin(angle), -sin(angle), cos(angle));
+}
+
+// Rotates the hue of color around the gray axis.
+vec3 rotate_hue(vec3 color, float angle) {
+    const vec3 k = vec3(0.57735);
+    float c = cos(angle);
+    return color * c + cross(k, color) * sin(angle) + k * dot(k, color) * (1.0 - c);
+}
+
+// Adjusts the outline to how big it ends up on screen.
+void outline_lod() {
+    float border_pixels = v_border_width * u_pixels_per_unit;
+    v_border_fade = clamp(border_pixels / MIN_BORDER_PIXELS, 0.0, 1.0);
+    if (v_border_fade > 0.0) {
+        v_border_width = max(v_border_width, MIN_BORDER_PIXELS / u_pixels_per_unit);
+    }
+    v_border_radii *= step(MIN_RADIUS_PIXELS, v_border_radii * u_pixels_per_unit);
+}
+
 void main() {
-    gl_Position = u_mvp * vec4(position, 0.0, 1.0);
     v_uv = uvs[gl_VertexID % 4];
+    v_atlas_uv = mix(atlas_uv.xy, atlas_uv.zw, v_uv + 0.5);
     v_size = size;
     v_fill_color = fill_color;
     v_stroke_color = stroke_color;
-    v_border_radius = border_radius;
+    v_border_radii = border_radii;
     v_border_width = border_width;
     v_intensity = intensity;
+    v_texture_mix = texture_mix;
+    v_gradient_color = gradient_color;
+    v_gradient_vector = gradient_vector;
+    v_gradient_kind = gradient_kind;
+    v_corner_exponent = corner_exponent;
+
+    // lifted like its shadow, intensity being 0.5 at rest
+    vec2 parallax = u_parallax * elevation * intensity * 2.0;
+
+    if (u_ambient == 0) {
+        outline_lod();
+        gl_Position = u_mvp * vec4(position + parallax, 0.0, 1.0);
+        return;
+    }
+
+    // the whole quad moves around its center
+    vec2 center = position - rotate(rotation) * (v_uv * size);
+    vec4 a = ambient(center);
+    float scale = a.x;
+
+    v_size = size * scale;
+    v_border_radii = border_radii * scale;
+    v_border_width = border_width * scale;
+    v_intensity = intensity + a.z;
+    v_fill_color.rgb = rotate_hue(fill_color.rgb, a.w);
+    v_stroke_color.rgb = rotate_hue(stroke_color.rgb, a.w);
+    v_gradient_color.rgb = rotate_hue(gradient_color.rgb, a.w);
+    outline_lod();
+
+    vec2 corner = rotate(rotation + a.y) * (v_uv * v_size);
+    parallax = u_parallax * elevation * v_intensity * 2.0;
+    gl_Position = u_mvp * vec4(center + corner + parallax, 0.0, 1.0);
 }
@@ -0,0 +1,68 @@
+#version 330 core
+precision mediump float;
+
+// color of the blobs, and how much of them there is around in alpha
+uniform sampler2D u_density;
+// density the surface is at
+uniform float u_threshold;
+// window pixels per texel of the density
+uniform float u_pixels_per_texel;
+// from the screen back to the world
+uniform mat4 u_inverse;
+// the box the blobs are in, in world units
+uniform vec2 u_bounds_min;
+uniform vec2 u_bounds_max;
+// center and radius of the finger poking the blobs, a radius of 0 if none
+uniform vec3 u_finger;
+uniform vec4 u_clear;
+uniform vec4 u_wall;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+// toward the light, up and to the left
+const vec3 LIGHT = normalize(vec3(-0.5, 0.6, 0.8));
+// how much the slope of the density per texel tilts the surface
+const float BULGE = 6.0;
+// pixels
+const float OUTLINE = 2.0;
+
+void main() {
+    vec2 world = (u_inverse * vec4(v_uv * 2.0 - 1.0, 0.0, 1.0)).xy;
+    bool in_box = all(greaterThanEqual(world, u_bounds_min))
+        && all(lessThanEqual(world, u_bounds_max));
+    vec4 color = in_box ? u_clear : mix(u_clear, u_wall, 0.5);
+
+    if (u_finger.z > 0.0) {
+        // a thin ring around the finger, in world units
+        float ring = abs(distance(world, u_finger.xy) - u_finger.z);
+        color = mix(color, u_wall, 1.0 - smoothstep(1.0, 2.0, ring));
+    }
+
+    vec4 density = texture(u_density, v_uv);
+    vec2 texel = 1.0 / vec2(textureSize(u_density, 0));
+    vec2 gradient = 0.5 * vec2(
+        texture(u_density, v_uv + vec2(texel.x, 0.0)).a - texture(u_density, v_uv - vec2(texel.x, 0.0)).a,
+        texture(u_density, v_uv + vec2(0.0, texel.y)).a - texture(u_density, v_uv - vec2(0.0, texel.y)).a
+    );
+
+    // signed distance to the surface in pixels, positive inside, from how
+    // fast the density crosses the threshold
+    float slope = max(length(gradient), 1e-4);
+    float distance = (density.a - u_threshold) / slope * u_pixels_per_texel;
+    float coverage = clamp(distance + 0.5, 0.0, 1.0);
+    if (coverage == 0.0) {
+        FragColor = color;
+        return;
+    }
+
+    // the density as a height field, domed where it rises
+    vec3 normal = normalize(vec3(-gradient * BULGE, 1.0));
+    float diffuse = max(dot(normal, LIGHT), 0.0);
+    float specular = pow(max(reflect(-LIGHT, normal).z, 0.0), 40.0);
+    vec3 blob = density.rgb * (0.55 + 0.45 * diffuse) + 0.5 * specular;
+    blob *= mix(0.6, 1.0, smoothstep(OUTLINE - 0.5, OUTLINE + 0.5, distance));
+
+    FragColor = mix(color, vec4(blob, 1.0), coverage);
+}
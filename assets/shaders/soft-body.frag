@@ -0,0 +1,10 @@
+#version 330 core
+
+in vec3 v_color;
+
+out vec4 FragColor;
+
+void main() {
+    // full coverage, which the blur spreads into a density
+    FragColor = vec4(v_color, 1.0);
+}
@@ -0,0 +1,14 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+in vec4 v_color;
+
+out vec4 frag_color;
+
+void main() {
+    // antialiased disc, v_uv going from -1 to 1 across the quad
+    float d = length(v_uv);
+    float alpha = clamp((1.0 - d) / fwidth(d), 0.0, 1.0);
+    frag_color = vec4(v_color.rgb, v_color.a * alpha);
+}
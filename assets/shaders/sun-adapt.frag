@@ -0,0 +1,14 @@
+#version 330 core
+precision highp float;
+
+out vec4 FragColor;
+
+// the lit scene, with its log luminance in alpha
+uniform sampler2D u_hdr;
+// level of the 1x1 mipmap of u_hdr
+uniform float u_lod;
+
+void main() {
+    // blended with what the eye was adapted to until now
+    FragColor = vec4(textureLod(u_hdr, vec2(0.5), u_lod).a, 0.0, 0.0, 1.0);
+}
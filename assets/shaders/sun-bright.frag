@@ -0,0 +1,38 @@
+#version 330 core
+precision highp float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+uniform sampler2D u_hdr;
+// same as sun-tonemap.frag
+uniform sampler2D u_adapted;
+uniform int u_adapt;
+
+// how many times brighter than what the eye is adapted to something has to
+// be to bloom and flare
+const float THRESHOLD = 6.0;
+// width of the soft knee around the threshold, as a fraction of it
+const float KNEE = 0.5;
+
+// same as sun-tonemap.frag
+const float NOON = 4.0;
+const float MIN_ADAPTED = 0.05;
+const float MAX_ADAPTED = 50.0;
+
+void main() {
+    vec3 color = texture(u_hdr, v_uv).rgb;
+
+    float adapted = u_adapt != 0 ? exp(texelFetch(u_adapted, ivec2(0), 0).r) : NOON;
+    float threshold = clamp(adapted, MIN_ADAPTED, MAX_ADAPTED) * THRESHOLD;
+
+    // only what is past the threshold, easing in over the knee
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    float knee = threshold * KNEE;
+    float soft = clamp(luminance - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-4);
+    float bright = max(soft, luminance - threshold) / max(luminance, 1e-4);
+
+    FragColor = vec4(color * bright, 1.0);
+}
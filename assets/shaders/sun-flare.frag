@@ -0,0 +1,63 @@
+#version 330 core
+precision highp float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+// bright spots of the scene, already blurred down the downsample chain
+uniform sampler2D u_bright;
+uniform float u_ghosts;
+uniform float u_halo;
+// width over height of the window
+uniform float u_aspect;
+
+const int GHOSTS = 5;
+// distance between ghosts along the line through the center, in uv
+const float GHOST_SPACING = 0.37;
+// radius of the halo around the center, in uv of the height
+const float HALO_RADIUS = 0.45;
+// how far apart the color channels of ghosts and halo are, in texels
+const float DISPERSION = 2.0;
+
+const float TAU = 6.28318530718;
+
+// The scene at uv with its channels spread along direction, like a lens
+// refracting each wavelength a little differently.
+vec3 dispersed(vec2 uv, vec2 direction) {
+    vec2 texel = direction * DISPERSION / vec2(textureSize(u_bright, 0));
+    return vec3(
+        texture(u_bright, uv - texel).r,
+        texture(u_bright, uv).g,
+        texture(u_bright, uv + texel).b
+    );
+}
+
+// 1 at the center of the screen, 0 in its corners.
+float centered(vec2 uv) {
+    return 1.0 - length(vec2(0.5) - uv) / 0.70710678;
+}
+
+void main() {
+    // ghosts are reflections of the scene through the center of the lens
+    vec2 uv = 1.0 - v_uv;
+    vec2 to_center = (vec2(0.5) - uv) * GHOST_SPACING;
+    vec2 direction = normalize(to_center + 1e-6);
+
+    vec3 ghosts = vec3(0.0);
+    for (int i = 0; i < GHOSTS; i++) {
+        vec2 offset = fract(uv + to_center * float(i));
+        ghosts += dispersed(offset, direction) * pow(max(centered(offset), 0.0), 10.0);
+    }
+    // tinted by how far from the center, like the coatings of a lens do
+    float t = 1.0 - centered(uv);
+    ghosts *= 0.6 + 0.4 * cos(TAU * (t + vec3(0.0, 0.33, 0.67)));
+
+    // a ring around the center, round whatever the shape of the window
+    vec2 aspect = vec2(u_aspect, 1.0);
+    vec2 to_halo = normalize(to_center * aspect + 1e-6) / aspect * HALO_RADIUS;
+    vec2 halo_uv = uv + to_halo;
+    vec3 halo = dispersed(halo_uv, direction) * pow(max(centered(fract(halo_uv)), 0.0), 5.0);
+
+    FragColor = vec4(ghosts * u_ghosts + halo * u_halo, 1.0);
+}
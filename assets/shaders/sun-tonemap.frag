@@ -0,0 +1,75 @@
+#version 330 core
+precision highp float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+uniform sampler2D u_hdr;
+// average log luminance the eye is adapted to, in a 1x1 texture
+uniform sampler2D u_adapted;
+// 1 to adapt the exposure to u_adapted, 0 for a fixed exposure
+uniform int u_adapt;
+// exposure compensation, in stops
+uniform float u_exposure;
+
+// bloom, the lens flare and the dirt on the lens, added before exposure
+uniform sampler2D u_bloom;
+uniform sampler2D u_flare;
+uniform sampler2D u_dirt;
+uniform float u_bloom_intensity;
+uniform float u_starburst;
+uniform float u_dirt_intensity;
+// angle of the starburst, in radians
+uniform float u_rotation;
+// width over height of the window
+uniform float u_aspect;
+// 1 to write linear values up to u_peak times SDR white, see src/output.rs
+uniform int u_hdr_output;
+uniform float u_peak;
+
+// average luminance mapped to middle gray
+const float KEY = 0.18;
+// average luminance of the city at noon, for the fixed exposure
+const float NOON = 4.0;
+// how dark and how bright the eye can adapt to
+const float MIN_ADAPTED = 0.05;
+const float MAX_ADAPTED = 50.0;
+
+// spikes of the starburst, and how thin they are
+const float SPIKES = 8.0;
+const float SHARPNESS = 60.0;
+
+// Narkowicz's fit of the ACES filmic curve.
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+// Diffraction spikes around the center of the lens, from the blades of its
+// aperture, in irregular lengths.
+float starburst(vec2 uv) {
+    vec2 d = (uv - 0.5) * vec2(u_aspect, 1.0);
+    float angle = atan(d.y, d.x) + u_rotation;
+    float spikes = pow(abs(cos(angle * SPIKES * 0.5)), SHARPNESS);
+    float fine = pow(abs(cos(angle * SPIKES * 1.5 + 0.7)), SHARPNESS * 2.0);
+    return spikes + 0.5 * fine;
+}
+
+void main() {
+    vec3 hdr = texelFetch(u_hdr, ivec2(gl_FragCoord.xy), 0).rgb;
+
+    float dirt = texture(u_dirt, v_uv).r * u_dirt_intensity;
+    vec3 bloom = texture(u_bloom, v_uv).rgb * u_bloom_intensity;
+    vec3 flare = texture(u_flare, v_uv).rgb;
+    hdr += bloom * (1.0 + dirt) + flare * (1.0 + dirt + starburst(v_uv) * u_starburst);
+
+    float adapted = u_adapt != 0 ? exp(texelFetch(u_adapted, ivec2(0), 0).r) : NOON;
+    adapted = clamp(adapted, MIN_ADAPTED, MAX_ADAPTED);
+    vec3 exposed = hdr * KEY / adapted * exp2(u_exposure);
+
+    if (u_hdr_output != 0) {
+        FragColor = vec4(min(exposed, vec3(u_peak)), 1.0);
+        return;
+    }
+    FragColor = vec4(pow(aces(exposed), vec3(1.0 / 2.2)), 1.0);
+}
@@ -0,0 +1,142 @@
+#version 330 core
+precision highp float;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+// from clip space to the world
+uniform mat4 u_inverse;
+// albedo in rgb and height in a, over a square of side 2 * u_field centered on the origin
+uniform sampler2D u_ground;
+uniform float u_field;
+// toward the sun, with x east, y south and z up
+uniform vec3 u_sun;
+// height of the sky panorama at the top of the window, in pixels
+uniform float u_sky_height;
+uniform vec2 u_viewport;
+
+const float PI = 3.14159265359;
+
+// irradiance of the sun above the atmosphere, in arbitrary HDR units
+const vec3 SUN = vec3(20.0);
+// optical depth of the whole atmosphere straight up, for air (bluer
+// wavelengths scattering far more) and for aerosols
+const vec3 BETA_RAYLEIGH = vec3(0.058, 0.135, 0.331);
+const float BETA_MIE = 0.02;
+// forward scattering of aerosols, which makes the glow around the sun
+const float MIE_G = 0.76;
+// single scattering alone leaves the sky much darker than it is
+const float MULTIPLE_SCATTERING = 4.0;
+// moonlight and city glow, so that nights aren't black
+const vec3 NIGHT = vec3(0.002, 0.003, 0.006);
+// angular radius of the sun in the sky panorama, in radians
+const float SUN_RADIUS = 0.02;
+
+// tallest building, past which shadow rays stop looking for occluders
+const float MAX_HEIGHT = 320.0;
+const float MAX_REACH = 2048.0;
+const int SHADOW_STEPS = 96;
+// how much shadows soften with distance from their occluder
+const float PENUMBRA = 0.03;
+
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+// How many atmospheres thick the air is toward something at this sine of
+// elevation, after Kasten and Young.
+float air_mass(float sin_elevation) {
+    float zenith = 90.0 - degrees(asin(clamp(sin_elevation, -1.0, 1.0)));
+    return 1.0 / (max(sin_elevation, 0.0) + 0.50572 * pow(max(96.07995 - zenith, 0.001), -1.6364));
+}
+
+vec3 transmittance(float sin_elevation) {
+    return exp(-(BETA_RAYLEIGH + BETA_MIE) * air_mass(sin_elevation));
+}
+
+// Sunlight reaching the ground, fading through twilight.
+vec3 sunlight() {
+    return SUN * transmittance(u_sun.z) * smoothstep(-0.1, 0.02, u_sun.z);
+}
+
+// Light scattered toward the viewer by the air in direction view, in a single
+// scattering, uniformly dense atmosphere.
+vec3 sky(vec3 view) {
+    float mu = dot(view, u_sun);
+    float rayleigh = 3.0 / (16.0 * PI) * (1.0 + mu * mu);
+    float g2 = MIE_G * MIE_G;
+    float mie = (1.0 - g2) / (4.0 * PI * pow(1.0 + g2 - 2.0 * MIE_G * mu, 1.5));
+
+    vec3 beta = BETA_RAYLEIGH + BETA_MIE;
+    vec3 scattered = (BETA_RAYLEIGH * rayleigh + BETA_MIE * mie) / beta;
+    vec3 depth = 1.0 - exp(-beta * air_mass(max(view.z, 0.0)));
+    return MULTIPLE_SCATTERING * sunlight() * scattered * depth + NIGHT;
+}
+
+vec4 ground(vec2 world) {
+    return texture(u_ground, world / (2.0 * u_field) + 0.5);
+}
+
+// How much of the sun a point on the ground sees, marching toward the sun
+// over the height field.
+float visibility(vec3 p) {
+    float horizontal = length(u_sun.xy);
+    if (u_sun.z <= 0.0) {
+        return 0.0;
+    }
+    if (horizontal < 1e-4) {
+        return 1.0;
+    }
+
+    vec2 toward = u_sun.xy / horizontal;
+    float slope = u_sun.z / horizontal;
+    float reach = min((MAX_HEIGHT - p.z) / slope, MAX_REACH);
+    // staggered per pixel, trading banding for noise
+    float jitter = hash(gl_FragCoord.xy);
+
+    float visible = 1.0;
+    for (int i = 0; i < SHADOW_STEPS; i++) {
+        float t = reach * (float(i) + jitter) / float(SHADOW_STEPS);
+        float above = p.z + t * slope - ground(p.xy + toward * t).a;
+        visible = min(visible, clamp(0.5 + above / (2.0 + t * PENUMBRA), 0.0, 1.0));
+    }
+    return visible;
+}
+
+vec3 panorama() {
+    // looking south in the middle, east on the left, from the horizon at the
+    // bottom to the zenith at the top
+    float from_top = u_viewport.y - gl_FragCoord.y;
+    float azimuth = (gl_FragCoord.x / u_viewport.x - 0.5) * 2.0 * PI;
+    float elevation = (1.0 - from_top / u_sky_height) * 0.5 * PI;
+    vec3 view = vec3(
+            -sin(azimuth) * cos(elevation),
+            cos(azimuth) * cos(elevation),
+            sin(elevation)
+        );
+
+    float disk = smoothstep(cos(SUN_RADIUS), cos(SUN_RADIUS * 0.8), dot(view, u_sun));
+    // as bright as it takes for the disk to give all of the sunlight
+    vec3 radiance = sunlight() / (PI * SUN_RADIUS * SUN_RADIUS);
+    return sky(view) + disk * radiance;
+}
+
+vec3 lit_ground() {
+    vec2 world = (u_inverse * vec4(v_uv * 2.0 - 1.0, 0.0, 1.0)).xy;
+    vec4 g = ground(world);
+
+    vec3 direct = sunlight() * max(u_sun.z, 0.0) * visibility(vec3(world, g.a + 0.5));
+    // the whole sky lighting the ground, about as bright as it is straight up
+    vec3 ambient = PI * sky(vec3(0.0, 0.0, 1.0));
+    return g.rgb * (direct + ambient);
+}
+
+void main() {
+    bool in_sky = u_viewport.y - gl_FragCoord.y < u_sky_height;
+    vec3 color = in_sky ? panorama() : lit_ground();
+
+    // log luminance in alpha, averaged by the mipmaps for exposure adaptation
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    FragColor = vec4(color, log(max(luminance, 1e-4)));
+}
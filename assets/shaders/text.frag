@@ -0,0 +1,21 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+in vec4 v_color;
+flat in float v_is_color;
+
+out vec4 frag_color;
+
+// outlines are white with their coverage in alpha, color glyphs are as drawn
+uniform sampler2D u_atlas;
+
+void main() {
+    vec4 texel = texture(u_atlas, v_uv);
+    if (v_is_color > 0.5) {
+        // emoji keep their colors, only fading with the text
+        frag_color = vec4(texel.rgb, texel.a * v_color.a);
+    } else {
+        frag_color = vec4(v_color.rgb, v_color.a * texel.a);
+    }
+}
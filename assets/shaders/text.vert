@@ -0,0 +1,24 @@
+#version 330 core
+precision mediump float;
+
+// in pixels, from the top left corner of the window
+layout(location = 0) in vec2 a_position;
+// in texels of the glyph atlas
+layout(location = 1) in vec2 a_uv;
+layout(location = 2) in vec4 a_color;
+layout(location = 3) in float a_is_color;
+
+uniform vec2 u_viewport;
+uniform vec2 u_atlas_size;
+
+out vec2 v_uv;
+out vec4 v_color;
+flat out float v_is_color;
+
+void main() {
+    vec2 clip = a_position / u_viewport * 2.0 - 1.0;
+    gl_Position = vec4(clip.x, -clip.y, 0.0, 1.0);
+    v_uv = a_uv / u_atlas_size;
+    v_color = a_color;
+    v_is_color = a_is_color;
+}
@@ -0,0 +1,43 @@
+#version 330 core
+precision mediump float;
+
+// a grid of quads, placed from their index alone so that drawing one quad
+// per call needs nothing but a uniform
+uniform mat4 u_mvp;
+uniform int u_base_instance;
+uniform int u_columns;
+uniform float u_spacing;
+uniform float u_quad_size;
+// tiles per side of the atlas, 0 when every quad has its own texture
+uniform int u_atlas_columns;
+// keeps linear filtering from bleeding into neighboring atlas tiles
+uniform float u_inset;
+
+out vec2 v_uv;
+flat out int v_index;
+
+const vec2[4] corners = vec2[4](
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(0.0, 1.0),
+        vec2(1.0, 1.0)
+    );
+
+void main() {
+    int index = u_base_instance + gl_InstanceID;
+    vec2 cell = vec2(index % u_columns, index / u_columns);
+    vec2 corner = corners[gl_VertexID];
+
+    // the grid is centered on the origin
+    vec2 center = (cell + 0.5 - float(u_columns) * 0.5) * u_spacing;
+    gl_Position = u_mvp * vec4(center + (corner - 0.5) * u_quad_size, 0.0, 1.0);
+
+    vec2 uv = mix(vec2(u_inset), vec2(1.0 - u_inset), corner);
+    if (u_atlas_columns > 0) {
+        vec2 tile = vec2(index % u_atlas_columns, index / u_atlas_columns);
+        uv = (tile + uv) / float(u_atlas_columns);
+    }
+
+    v_uv = uv;
+    v_index = index;
+}
@@ -0,0 +1,51 @@
+#version 330 core
+precision mediump float;
+
+// Reconstructs the scene at full resolution from frames drawn at a lower one,
+// each moved by a different subpixel jitter: every frame adds its samples
+// where they landed to the history, which is clamped to what the frame shows
+// around them so that what moved doesn't leave trails.
+uniform sampler2D u_current;
+uniform sampler2D u_history;
+// how far this frame was moved, in its own texels, y up
+uniform vec2 u_jitter;
+// whether the history is worth nothing, like after the camera moved
+uniform bool u_reset;
+// weight of a sample right on the pixel, against the history
+uniform float u_feedback;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+void main() {
+    vec2 size = vec2(textureSize(u_current, 0));
+    // where this pixel is among the texels of the frame, as if it hadn't moved
+    vec2 pos = v_uv * size;
+    if (u_reset) {
+        FragColor = texture(u_current, (pos + u_jitter) / size);
+        return;
+    }
+
+    // the texel whose center landed closest to this pixel, weighted by how
+    // close in pixels of the output
+    ivec2 last = ivec2(size) - 1;
+    ivec2 nearest = clamp(ivec2(floor(pos + u_jitter)), ivec2(0), last);
+    vec2 ratio = vec2(textureSize(u_history, 0)) / size;
+    vec2 distance = (vec2(nearest) + 0.5 - u_jitter - pos) * ratio;
+    float weight = u_feedback * exp(-2.0 * dot(distance, distance));
+    vec4 color = texelFetch(u_current, nearest, 0);
+
+    vec4 low = color;
+    vec4 high = color;
+    for (int y = -1; y <= 1; y++) {
+        for (int x = -1; x <= 1; x++) {
+            vec4 neighbor = texelFetch(u_current, clamp(nearest + ivec2(x, y), ivec2(0), last), 0);
+            low = min(low, neighbor);
+            high = max(high, neighbor);
+        }
+    }
+    vec4 history = clamp(texture(u_history, v_uv), low, high);
+
+    FragColor = mix(history, color, weight);
+}
@@ -0,0 +1,64 @@
+#version 330 core
+precision mediump float;
+
+// The scene drawn at another resolution, resampled to the one it's shown at.
+uniform sampler2D u_image;
+// 0: bilinear, 1: bicubic, 2: average of the texels under each pixel
+uniform int u_filter;
+// size of the image once resampled
+uniform vec2 u_resolution;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+// Catmull-Rom in 9 bilinear taps instead of 16 fetches, by folding the two
+// middle weights of each axis into one tap between them.
+vec4 bicubic(in vec2 uv) {
+    vec2 size = vec2(textureSize(u_image, 0));
+    vec2 pos = uv * size;
+    vec2 center = floor(pos - 0.5) + 0.5;
+    vec2 f = pos - center;
+
+    vec2 w0 = f * (-0.5 + f * (1.0 - 0.5 * f));
+    vec2 w1 = 1.0 + f * f * (-2.5 + 1.5 * f);
+    vec2 w2 = f * (0.5 + f * (2.0 - 1.5 * f));
+    vec2 w3 = f * f * (-0.5 + 0.5 * f);
+    vec2 w12 = w1 + w2;
+
+    vec2 uv0 = (center - 1.0) / size;
+    vec2 uv12 = (center + w2 / w12) / size;
+    vec2 uv3 = (center + 2.0) / size;
+
+    vec4 sum = vec4(0.0);
+    sum += texture(u_image, vec2(uv0.x, uv0.y)) * w0.x * w0.y;
+    sum += texture(u_image, vec2(uv12.x, uv0.y)) * w12.x * w0.y;
+    sum += texture(u_image, vec2(uv3.x, uv0.y)) * w3.x * w0.y;
+    sum += texture(u_image, vec2(uv0.x, uv12.y)) * w0.x * w12.y;
+    sum += texture(u_image, vec2(uv12.x, uv12.y)) * w12.x * w12.y;
+    sum += texture(u_image, vec2(uv3.x, uv12.y)) * w3.x * w12.y;
+    sum += texture(u_image, vec2(uv0.x, uv3.y)) * w0.x * w3.y;
+    sum += texture(u_image, vec2(uv12.x, uv3.y)) * w12.x * w3.y;
+    sum += texture(u_image, vec2(uv3.x, uv3.y)) * w3.x * w3.y;
+    // the negative lobes overshoot around sharp edges
+    return clamp(sum, 0.0, 1.0);
+}
+
+// Four bilinear taps spread over the pixel's footprint, each averaging 2×2
+// texels, which covers up to 4×4 texels: enough for twice the resolution.
+vec4 area(in vec2 uv) {
+    vec2 quarter = 0.25 / u_resolution;
+    vec4 sum = texture(u_image, uv + vec2(-quarter.x, -quarter.y));
+    sum += texture(u_image, uv + vec2(quarter.x, -quarter.y));
+    sum += texture(u_image, uv + vec2(-quarter.x, quarter.y));
+    sum += texture(u_image, uv + vec2(quarter.x, quarter.y));
+    return sum * 0.25;
+}
+
+void main() {
+    switch (u_filter) {
+        case 1: FragColor = bicubic(v_uv); break;
+        case 2: FragColor = area(v_uv); break;
+        default: FragColor = texture(u_image, v_uv); break;
+    }
+}
@@ -0,0 +1,30 @@
+#version 330 core
+precision mediump float;
+
+in vec2 v_uv;
+in vec2 v_local;
+flat in float v_level;
+
+out vec4 FragColor;
+
+uniform sampler2D u_cache;
+uniform bool u_debug;
+
+vec3 level_color(float level) {
+    return 0.5 + 0.5 * cos(6.28318 * (level / 7.0 + vec3(0.0, 0.33, 0.67)));
+}
+
+void main() {
+    vec4 color = texture(u_cache, v_uv);
+
+    if (u_debug) {
+        // tint by mip level and outline every tile
+        color.rgb = mix(color.rgb, level_color(v_level), 0.35);
+        vec2 edge = min(v_local, 1.0 - v_local) / fwidth(v_local);
+        if (min(edge.x, edge.y) < 1.0) {
+            color.rgb = vec3(1.0);
+        }
+    }
+
+    FragColor = color;
+}
@@ -0,0 +1,28 @@
+#version 330 core
+precision mediump float;
+
+// one instance per tile, drawn as a 4 vertex triangle strip
+layout(location = 0) in vec4 a_rect;
+layout(location = 1) in vec4 a_uv_rect;
+layout(location = 2) in float a_level;
+
+uniform mat4 u_mvp;
+
+out vec2 v_uv;
+out vec2 v_local;
+flat out float v_level;
+
+const vec2[4] corners = vec2[4](
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(0.0, 1.0),
+        vec2(1.0, 1.0)
+    );
+
+void main() {
+    vec2 corner = corners[gl_VertexID];
+    gl_Position = u_mvp * vec4(a_rect.xy + corner * a_rect.zw, 0.0, 1.0);
+    v_uv = a_uv_rect.xy + corner * a_uv_rect.zw;
+    v_local = corner;
+    v_level = a_level;
+}
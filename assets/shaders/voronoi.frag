@@ -0,0 +1,77 @@
+#version 330 core
+
+#define MAX_SEEDS 256
+
+// seeds in world units, and their colors
+layout(std140) uniform Seeds {
+    vec4 u_positions[MAX_SEEDS];
+    vec4 u_colors[MAX_SEEDS];
+};
+uniform int u_count;
+// 0 euclidean, 1 manhattan, 2 chebyshev
+uniform int u_metric;
+// 0 cells, 1 distance, 2 worley, 3 borders
+uniform int u_coloring;
+// from the screen back to the world
+uniform mat4 u_inverse;
+// world units per pixel, for antialiasing
+uniform float u_pixel;
+// world distance that counts as far from a seed
+uniform float u_scale;
+uniform vec4 u_clear;
+uniform vec4 u_border;
+
+in vec2 v_uv;
+
+out vec4 FragColor;
+
+float distance_to(vec2 d) {
+    d = abs(d);
+    if (u_metric == 1) {
+        return d.x + d.y;
+    } else if (u_metric == 2) {
+        return max(d.x, d.y);
+    }
+    return length(d);
+}
+
+void main() {
+    if (u_count == 0) {
+        FragColor = u_clear;
+        return;
+    }
+
+    vec2 world = (u_inverse * vec4(v_uv * 2.0 - 1.0, 0.0, 1.0)).xy;
+
+    // closest and second closest seeds
+    float f1 = 1e30;
+    float f2 = 1e30;
+    int nearest = 0;
+    for (int i = 0; i < u_count; i++) {
+        float d = distance_to(world - u_positions[i].xy);
+        if (d < f1) {
+            f2 = f1;
+            f1 = d;
+            nearest = i;
+        } else if (d < f2) {
+            f2 = d;
+        }
+    }
+
+    vec3 cell = u_colors[nearest].rgb;
+    // F2 - F1 is about twice the distance to the border with the next cell
+    float border = clamp(((f2 - f1) * 0.5 - u_pixel * 0.5) / u_pixel, 0.0, 1.0);
+
+    vec3 color;
+    if (u_coloring == 0) {
+        color = mix(u_border.rgb, cell, mix(1.0, border, u_border.a));
+    } else if (u_coloring == 1) {
+        color = cell * (1.0 - 0.8 * clamp(f1 / u_scale, 0.0, 1.0));
+    } else if (u_coloring == 2) {
+        color = vec3(clamp(f1 / u_scale, 0.0, 1.0));
+    } else {
+        color = mix(u_border.rgb, u_clear.rgb, border);
+    }
+
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,45 @@
+#version 330 core
+precision mediump float;
+
+// Puts the picture together from the targets of each rate: every pixel takes
+// the fragment shaded for its block in the target of its tile's rate, as is,
+// the way hardware variable rate shading spreads one over the whole block.
+
+// the targets of rates 1, 2 and 4
+uniform sampler2D u_full;
+uniform sampler2D u_half;
+uniform sampler2D u_quarter;
+// one texel per tile, its rate in red
+uniform sampler2D u_rates;
+uniform int u_tile;
+// tints the tiles by their rate, green to red, and outlines them
+uniform bool u_overlay;
+
+out vec4 FragColor;
+
+void main() {
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    int rate = int(texelFetch(u_rates, pixel / u_tile, 0).r * 255.0 + 0.5);
+
+    vec4 color;
+    vec3 tint;
+    if (rate == 1) {
+        color = texelFetch(u_full, pixel, 0);
+        tint = vec3(0.1, 0.9, 0.2);
+    } else if (rate == 2) {
+        color = texelFetch(u_half, pixel / 2, 0);
+        tint = vec3(1.0, 0.8, 0.1);
+    } else {
+        color = texelFetch(u_quarter, pixel / 4, 0);
+        tint = vec3(1.0, 0.15, 0.1);
+    }
+
+    if (u_overlay) {
+        color.rgb = mix(color.rgb, tint, 0.3);
+        ivec2 inside = pixel % u_tile;
+        if (inside.x == 0 || inside.y == 0) {
+            color.rgb *= 0.5;
+        }
+    }
+    FragColor = color;
+}
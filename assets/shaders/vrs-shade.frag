@@ -0,0 +1,79 @@
+#version 330 core
+precision highp float;
+
+// Clouds of domain-warped fractal noise with thin contour lines, costly
+// enough per fragment for shading fewer of them to show, and with enough
+// detail for shading them too coarsely to show as well.
+//
+// Drawn into a target a `u_rate`th of the size of the picture on each side,
+// one fragment per block of `u_rate` by `u_rate` pixels of the picture, over
+// the tiles of the rate map with that rate only. Tiles are a multiple of
+// twice the coarsest rate wide, so a quad of fragments never straddles two
+// tiles and the derivatives stay defined around the discard.
+
+// one texel per tile, its rate in red
+uniform sampler2D u_rates;
+uniform int u_tile;
+// whether to only shade the tiles of `u_rate`, or the whole target
+uniform bool u_variable;
+uniform int u_rate;
+uniform float u_time;
+uniform int u_octaves;
+
+out vec4 FragColor;
+
+float hash(vec2 p) {
+    p = fract(p * vec2(123.34, 456.21));
+    p += dot(p, p + 45.32);
+    return fract(p.x * p.y);
+}
+
+float noise(vec2 p) {
+    vec2 i = floor(p);
+    vec2 f = fract(p);
+    vec2 u = f * f * (3.0 - 2.0 * f);
+    float bottom = mix(hash(i), hash(i + vec2(1.0, 0.0)), u.x);
+    float top = mix(hash(i + vec2(0.0, 1.0)), hash(i + vec2(1.0, 1.0)), u.x);
+    return mix(bottom, top, u.y);
+}
+
+float fbm(vec2 p) {
+    float sum = 0.0;
+    float amplitude = 0.5;
+    for (int i = 0; i < u_octaves; i++) {
+        sum += amplitude * noise(p);
+        p = p * 2.03 + vec2(17.0, 9.0);
+        amplitude *= 0.5;
+    }
+    return sum;
+}
+
+void main() {
+    // the middle of the block of pixels of the picture this fragment covers
+    vec2 pixel = gl_FragCoord.xy * float(u_rate);
+    if (u_variable) {
+        int rate = int(texelFetch(u_rates, ivec2(pixel) / u_tile, 0).r * 255.0 + 0.5);
+        if (rate != u_rate) {
+            discard;
+        }
+    }
+
+    vec2 p = pixel / 240.0;
+    vec2 q = vec2(fbm(p + 0.05 * u_time), fbm(p + vec2(5.2, 1.3) - 0.04 * u_time));
+    vec2 r = vec2(
+        fbm(p + 4.0 * q + vec2(1.7, 9.2) + 0.1 * u_time),
+        fbm(p + 4.0 * q + vec2(8.3, 2.8))
+    );
+    float f = fbm(p + 4.0 * r);
+
+    vec3 color = mix(vec3(0.1, 0.3, 0.45), vec3(0.95, 0.75, 0.45), clamp(f * f * 2.0, 0.0, 1.0));
+    color = mix(color, vec3(0.1, 0.05, 0.2), clamp(length(q), 0.0, 1.0) * 0.5);
+
+    // contour lines about a fragment wide, so a pixel wide at full rate and
+    // blockier the coarser it gets
+    float contour = f * 24.0;
+    float lines = abs(fract(contour) - 0.5) / fwidth(contour);
+    color *= mix(0.35, 1.0, clamp(lines, 0.0, 1.0));
+
+    FragColor = vec4(color, 1.0);
+}
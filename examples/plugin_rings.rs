@@ -0,0 +1,125 @@
+//! A scene built outside of the playground, as a plugin.
+//!
+//! ```text
+//! cargo build --example plugin_rings
+//! cargo run -- --plugin target/debug/examples/libplugin_rings.so
+//! ```
+//!
+//! Then `PageDown` past the last built-in scene, or `scene rings` in the
+//! console. Space pauses the rings.
+
+use std::error::Error;
+
+use gl::types::{GLint, GLuint};
+use glam::{Mat4, Vec2};
+use opengl_playground::common_gl::create_shader_program;
+use opengl_playground::plugin::{CreateInfo, Scene};
+
+const SRC_VERT: &[u8] = b"#version 330 core
+out vec2 v_ndc;
+
+void main() {
+    // one triangle covering the screen
+    vec2 position = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2)) * 2.0 - 1.0;
+    gl_Position = vec4(position, 0.0, 1.0);
+    v_ndc = position;
+}
+";
+
+const SRC_FRAG: &[u8] = b"#version 330 core
+precision mediump float;
+
+uniform mat4 u_inverse_mvp;
+uniform vec2 u_mouse;
+uniform float u_time;
+
+in vec2 v_ndc;
+out vec4 frag_color;
+
+void main() {
+    vec2 world = (u_inverse_mvp * vec4(v_ndc, 0.0, 1.0)).xy;
+    float d = distance(world, u_mouse);
+    float ring = 0.5 + 0.5 * cos(d * 0.05 - u_time * 4.0);
+    vec3 color = mix(vec3(0.08, 0.09, 0.12), vec3(0.95, 0.55, 0.25), ring * ring);
+    frag_color = vec4(color, 1.0);
+}
+";
+
+struct Rings {
+    program: GLuint,
+    vao: GLuint,
+    u_inverse_mvp: GLint,
+    u_mouse: GLint,
+    u_time: GLint,
+    time: f32,
+    paused: bool,
+}
+
+impl Scene for Rings {
+    fn new(info: &CreateInfo) -> Result<Self, Box<dyn Error>> {
+        unsafe {
+            let program = create_shader_program(SRC_VERT, SRC_FRAG)?;
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+
+            Ok(Self {
+                program,
+                vao,
+                u_inverse_mvp: gl::GetUniformLocation(program, c"u_inverse_mvp".as_ptr()),
+                u_mouse: gl::GetUniformLocation(program, c"u_mouse".as_ptr()),
+                u_time: gl::GetUniformLocation(program, c"u_time".as_ptr()),
+                // the seed shifts the phase of the rings
+                time: (info.seed % 1000) as f32,
+                paused: false,
+            })
+        }
+    }
+
+    fn resize(&mut self, width: i32, height: i32) {
+        unsafe { gl::Viewport(0, 0, width, height) };
+    }
+
+    fn update(&mut self, dt: f32) {
+        if !self.paused {
+            self.time += dt;
+        }
+    }
+
+    fn draw(&mut self, matrix: &Mat4, mouse: Vec2) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Disable(gl::BLEND);
+
+            gl::UseProgram(self.program);
+            let inverse = matrix.inverse().to_cols_array();
+            gl::UniformMatrix4fv(self.u_inverse_mvp, 1, gl::FALSE, inverse.as_ptr());
+            gl::Uniform2f(self.u_mouse, mouse.x, mouse.y);
+            gl::Uniform1f(self.u_time, self.time);
+
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+        }
+    }
+
+    fn on_key(&mut self, key: &str) {
+        if key == " " {
+            self.paused = !self.paused;
+        }
+    }
+}
+
+impl Drop for Rings {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.program);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+opengl_playground::export_scene!(
+    Rings,
+    c"rings",
+    c"Rings rippling away from the mouse, drawn by a plugin."
+);
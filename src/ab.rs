@@ -0,0 +1,535 @@
+//! A/B comparisons of two variants of a scene, measured live.
+//!
+//! A comparison sets a parameter of a scene to one value or the other, like
+//! the solver of the cloth, on the CPU or in compute shaders. With
+//! `--ab <comparison>`, the harness either alternates the two every few
+//! frames, or draws one in each half of the window with `--ab-split`, and
+//! collects paired CPU and GPU timings of both:
+//!
+//! - Alternating, the variants run in blocks of `--ab-frames` frames, in the
+//!   order A B B A, so that neither always comes first after the other. The
+//!   first frames of each block are left out, as the switch itself is often
+//!   slow. Both the update and the draw of the scene are timed, so that this
+//!   suits simulations too.
+//! - Split, each frame draws A in the left half and B in the right one, the
+//!   half drawn last swapping every frame. Only drawing is compared, so it
+//!   suits variants that draw differently, like the binding modes of the
+//!   bindless scene.
+//!
+//! The means of the blocks (or frames, split) of A and B are paired, and a
+//! paired t-test on their differences tells whether the difference is more
+//! than noise. The report is printed every few pairs, and the `fps` command
+//! includes the latest one.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::console;
+use crate::params::Value;
+use crate::scenes::Scenes;
+
+/// Comparisons available by the name of their scene: the scene, its
+/// parameter, then the values of A and B.
+pub const REGISTERED: &[(&str, &str, &str, &str)] = &[
+    ("cloth", "solver", "cpu", "compute"),
+    ("culling", "culling", "cpu", "gpu"),
+    ("clustered-lighting", "binning", "cpu", "gpu"),
+    ("ecs-quads", "storage", "ecs", "vecs"),
+    ("spatial", "index", "grid", "quadtree"),
+    ("amplification", "technique", "instanced", "geometry"),
+    ("bindless", "mode", "bind", "bindless"),
+    ("kawase", "downsampling", "passes", "compute"),
+];
+
+/// Frames of each block left out after switching variants.
+pub const WARMUP_FRAMES: u32 = 5;
+
+/// Pairs between two printed reports: blocks while alternating, frames when
+/// split.
+const REPORT_EVERY: usize = 10;
+const SPLIT_REPORT_EVERY: usize = 300;
+
+/// Significance level of the reports.
+const ALPHA: f64 = 0.05;
+
+/// Two values of a parameter of a scene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub scene: String,
+    pub param: String,
+    pub a: Value,
+    pub b: Value,
+}
+
+impl Comparison {
+    pub fn value(&self, variant: Variant) -> &Value {
+        match variant {
+            Variant::A => &self.a,
+            Variant::B => &self.b,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = String;
+
+    /// Either the scene of a registered comparison, as in `cloth`, or
+    /// `scene.param=a,b`, as in `kawase.layers=1,5`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((target, values)) = s.split_once('=') else {
+            let &(scene, param, a, b) = (REGISTERED.iter())
+                .find(|(scene, ..)| *scene == s)
+                .ok_or_else(|| format!("no registered comparison for '{s}'"))?;
+            return Ok(Self {
+                scene: scene.to_string(),
+                param: param.to_string(),
+                a: console::parse_value(a),
+                b: console::parse_value(b),
+            });
+        };
+
+        let (scene, param) = (target.split_once('.'))
+            .ok_or_else(|| format!("expected SCENE.PARAM=A,B, got '{s}'"))?;
+        let (a, b) = (values.split_once(','))
+            .ok_or_else(|| format!("expected two values separated by a comma, got '{values}'"))?;
+        Ok(Self {
+            scene: scene.to_string(),
+            param: param.to_string(),
+            a: console::parse_value(a),
+            b: console::parse_value(b),
+        })
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}: {} vs {}", self.scene, self.param, self.a, self.b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    A,
+    B,
+}
+
+impl Variant {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// GPU timer zone of the variant.
+    pub fn zone(self) -> &'static str {
+        match self {
+            Self::A => "ab a",
+            Self::B => "ab b",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Blocks of this many frames of each variant, one after the other.
+    Alternate { frames: u32 },
+    /// Both every frame, one per half of the window.
+    Split,
+}
+
+/// Measurements of one variant, in the order they were made, summed per
+/// block.
+#[derive(Debug, Clone, Default)]
+struct Stream {
+    count: u64,
+    /// Sum and number of the measurements kept in each block.
+    blocks: Vec<(f64, u32)>,
+}
+
+impl Stream {
+    fn push(&mut self, value: f64, block_len: u32, warmup: u32) {
+        let block = (self.count / block_len as u64) as usize;
+        let frame = (self.count % block_len as u64) as u32;
+        self.count += 1;
+        if frame < warmup {
+            return;
+        }
+
+        if self.blocks.len() <= block {
+            self.blocks.resize(block + 1, (0.0, 0));
+        }
+        self.blocks[block].0 += value;
+        self.blocks[block].1 += 1;
+    }
+
+    /// Mean of the `block`th block, once it's complete.
+    fn mean(&self, block: usize, kept: u32) -> Option<f64> {
+        match self.blocks.get(block) {
+            Some(&(sum, n)) if n == kept => Some(sum / n as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Means of the blocks A and B both completed, paired.
+fn pairs(streams: &[Stream; 2], kept: u32) -> (Vec<f64>, Vec<f64>) {
+    (0..)
+        .map_while(|block| Some((streams[0].mean(block, kept)?, streams[1].mean(block, kept)?)))
+        .unzip()
+}
+
+pub struct AbHarness {
+    pub comparison: Comparison,
+    pub mode: Mode,
+    /// Variant of the block being run, alternating.
+    variant: Variant,
+    frame: u32,
+    blocks: usize,
+    /// Variant the parameter is set to, `None` until set or after the scene
+    /// changed.
+    applied: Option<Variant>,
+    cpu: [Stream; 2],
+    gpu: [Stream; 2],
+    reported: usize,
+    last_report: Option<Report>,
+    /// Whether B is drawn first this frame, when split.
+    b_first: bool,
+}
+
+impl AbHarness {
+    pub fn new(comparison: Comparison, mode: Mode) -> Self {
+        let mode = match mode {
+            Mode::Alternate { frames } => Mode::Alternate {
+                frames: frames.max(WARMUP_FRAMES + 2),
+            },
+            Mode::Split => Mode::Split,
+        };
+
+        Self {
+            comparison,
+            mode,
+            variant: Variant::A,
+            frame: 0,
+            blocks: 0,
+            applied: None,
+            cpu: Default::default(),
+            gpu: Default::default(),
+            reported: 0,
+            last_report: None,
+            b_first: false,
+        }
+    }
+
+    pub fn scene(&self) -> &str {
+        &self.comparison.scene
+    }
+
+    /// Variant of the block being run while alternating.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    fn block_len(&self) -> (u32, u32) {
+        match self.mode {
+            Mode::Alternate { frames } => (frames, WARMUP_FRAMES),
+            Mode::Split => (1, 0),
+        }
+    }
+
+    /// Order to draw the variants in this frame when split, which swaps every
+    /// frame so that neither always follows the other.
+    pub fn draw_order(&mut self) -> [Variant; 2] {
+        self.b_first = !self.b_first;
+        match self.b_first {
+            true => [Variant::B, Variant::A],
+            false => [Variant::A, Variant::B],
+        }
+    }
+
+    /// Sets the parameter of `scenes` to `variant`, unless it is already.
+    /// Returns `false` without doing anything if the scene isn't the compared
+    /// one.
+    pub fn apply(&mut self, scenes: &mut Scenes, variant: Variant) -> Result<bool, String> {
+        if scenes.name() != self.comparison.scene {
+            self.applied = None;
+            return Ok(false);
+        }
+        if self.applied == Some(variant) {
+            return Ok(true);
+        }
+
+        let name = scenes.name();
+        let params = (scenes.params_mut()).ok_or(format!("{name} has no parameters"))?;
+        let value = self.comparison.value(variant).clone();
+        (params.set(&self.comparison.param, value)).map_err(|e| e.to_string())?;
+        self.applied = Some(variant);
+        Ok(true)
+    }
+
+    /// Sets the parameter for the frame about to run while alternating.
+    /// Returns the variant it runs, `None` if the scene isn't the compared
+    /// one.
+    pub fn begin_frame(&mut self, scenes: &mut Scenes) -> Result<Option<Variant>, String> {
+        let variant = self.variant;
+        Ok(self.apply(scenes, variant)?.then_some(variant))
+    }
+
+    /// Records what the CPU spent on `variant`, in milliseconds.
+    pub fn record_cpu(&mut self, variant: Variant, ms: f64) {
+        let (block_len, warmup) = self.block_len();
+        self.cpu[variant.index()].push(ms, block_len, warmup);
+    }
+
+    /// Records the GPU times of the variants' zones among `resolved`.
+    pub fn record_gpu(&mut self, resolved: &[(&'static str, f32)]) {
+        let (block_len, warmup) = self.block_len();
+        for &(name, ms) in resolved {
+            for variant in [Variant::A, Variant::B] {
+                if name == variant.zone() {
+                    self.gpu[variant.index()].push(ms as f64, block_len, warmup);
+                }
+            }
+        }
+    }
+
+    /// Ends a frame run while alternating, moving on to the next block once
+    /// this one is complete.
+    pub fn end_frame(&mut self) {
+        let Mode::Alternate { frames } = self.mode else {
+            return;
+        };
+
+        self.frame += 1;
+        if self.frame == frames {
+            self.frame = 0;
+            self.blocks += 1;
+            // A B B A, A B B A, ...
+            self.variant = match self.blocks % 4 {
+                0 | 3 => Variant::A,
+                _ => Variant::B,
+            };
+        }
+    }
+
+    pub fn report(&self) -> Report {
+        let (block_len, warmup) = self.block_len();
+        let kept = block_len - warmup;
+        let (cpu_a, cpu_b) = pairs(&self.cpu, kept);
+        let (gpu_a, gpu_b) = pairs(&self.gpu, kept);
+
+        Report {
+            comparison: self.comparison.clone(),
+            block_len,
+            cpu: paired_t_test(&cpu_a, &cpu_b),
+            gpu: paired_t_test(&gpu_a, &gpu_b),
+        }
+    }
+
+    /// A report, if enough pairs came in since the last one.
+    pub fn take_report(&mut self) -> Option<Report> {
+        let report = self.report();
+        let pairs = report.cpu.as_ref().map_or(0, |test| test.pairs);
+        let every = match self.mode {
+            Mode::Alternate { .. } => REPORT_EVERY,
+            Mode::Split => SPLIT_REPORT_EVERY,
+        };
+        if pairs < self.reported + every {
+            return None;
+        }
+        self.reported = pairs;
+        self.last_report = Some(report.clone());
+        Some(report)
+    }
+
+    /// The report last taken.
+    pub fn last_report(&self) -> Option<&Report> {
+        self.last_report.as_ref()
+    }
+}
+
+/// Paired timings of both variants.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub comparison: Comparison,
+    /// Frames per block, 1 when split.
+    pub block_len: u32,
+    pub cpu: Option<TTest>,
+    pub gpu: Option<TTest>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.comparison)?;
+        for (name, test) in [("CPU", &self.cpu), ("GPU", &self.gpu)] {
+            match test {
+                Some(test) => write!(f, ", {name} {test}")?,
+                None => write!(f, ", {name} not measured yet")?,
+            }
+        }
+        match self.block_len {
+            1 => write!(f, " (frames drawn side by side)"),
+            n => write!(f, " (blocks of {n} frames)"),
+        }
+    }
+}
+
+/// Paired t-test of the differences between B and A.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TTest {
+    pub pairs: usize,
+    pub mean_a: f64,
+    pub mean_b: f64,
+    /// Mean of B - A.
+    pub difference: f64,
+    /// Half the width of the 95% confidence interval of the difference.
+    pub margin: f64,
+    /// Probability of a difference at least this large if there was none,
+    /// two-sided.
+    pub p: f64,
+}
+
+impl TTest {
+    pub fn is_significant(&self) -> bool {
+        self.p < ALPHA
+    }
+}
+
+impl fmt::Display for TTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |ms: f64| 100.0 * ms / self.mean_a;
+        write!(
+            f,
+            "{:.3} vs {:.3} ms ({:+.1}% ± {:.1}%, p = {:.3}, {}) over {} pairs",
+            self.mean_a,
+            self.mean_b,
+            percent(self.difference),
+            percent(self.margin),
+            self.p,
+            match self.is_significant() {
+                true => "significant",
+                false => "not significant",
+            },
+            self.pairs,
+        )
+    }
+}
+
+/// Compares the paired samples `a` and `b`, which needs at least two pairs.
+pub fn paired_t_test(a: &[f64], b: &[f64]) -> Option<TTest> {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return None;
+    }
+
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let differences: Vec<f64> = (a.iter().zip(b)).map(|(a, b)| b - a).collect();
+    let difference = mean(&differences);
+    let variance = (differences.iter())
+        .map(|d| (d - difference).powi(2))
+        .sum::<f64>()
+        / (n - 1) as f64;
+    let error = (variance / n as f64).sqrt();
+    let df = (n - 1) as f64;
+
+    // identical differences every time leave no noise to compare with
+    let p = if error > 0.0 {
+        student_t_p(difference / error, df)
+    } else if difference == 0.0 {
+        1.0
+    } else {
+        0.0
+    };
+
+    Some(TTest {
+        pairs: n,
+        mean_a: mean(&a[..n]),
+        mean_b: mean(&b[..n]),
+        difference,
+        margin: student_t_critical(ALPHA, df) * error,
+        p,
+    })
+}
+
+/// Two-sided p-value of `t` under Student's t distribution with `df` degrees
+/// of freedom.
+pub fn student_t_p(t: f64, df: f64) -> f64 {
+    incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+/// The `t` whose two-sided p-value is `alpha`.
+pub fn student_t_critical(alpha: f64, df: f64) -> f64 {
+    // the p-value falls as t grows, so bisect
+    let (mut low, mut high) = (0.0, 1000.0);
+    for _ in 0..100 {
+        let mid = 0.5 * (low + high);
+        match student_t_p(mid, df) > alpha {
+            true => low = mid,
+            false => high = mid,
+        }
+    }
+    0.5 * (low + high)
+}
+
+/// Regularized incomplete beta function I_x(a, b).
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // the continued fraction converges quickly on this side only
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// Continued fraction of the incomplete beta function, by the modified Lentz
+/// method.
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let clamp = |v: f64| if v.abs() < TINY { TINY } else { v };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..300 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / clamp(1.0 + even * d);
+        c = clamp(1.0 + even / c);
+        h *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / clamp(1.0 + odd * d);
+        c = clamp(1.0 + odd / c);
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+/// Logarithm of the gamma function, by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let mut series = 1.000000000190015;
+    for (i, coefficient) in COEFFICIENTS.iter().enumerate() {
+        series += coefficient / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
@@ -0,0 +1,410 @@
+//! Adjustments made to the input image before the blur scenes blur it, so
+//! that badly exposed photos can be corrected: levels, gamma, contrast and
+//! brightness, after an optional histogram equalization.
+//!
+//! Equalization maps the luma of every pixel through the cumulative
+//! histogram of the image, either of the whole image or, like CLAHE, of the
+//! tiles around the pixel with their histograms clipped so that flat regions
+//! don't get their noise blown up. Histograms are counted by a compute pass,
+//! one workgroup per tile, and another one turns them into lookup tables.
+//!
+//! The adjusted image is only drawn again when the settings change.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
+use glam::{vec2, UVec2, Vec2};
+
+use crate::common_gl::{
+    buffer_data, create_compute_program, create_framebuffer, create_shader_program, delete_buffers,
+    delete_framebuffer, delete_textures, gl_version, pop_debug_group, push_debug_group,
+    supports_compute, Framebuffer, SavedState,
+};
+use crate::error::GlError;
+use crate::gpu_memory::{self, Category, Object};
+use crate::params::Params;
+
+const SRC_VERT_SCREEN: &[u8] = include_bytes!("../assets/shaders/screen.vert");
+const SRC_FRAG_ADJUST: &[u8] = include_bytes!("../assets/shaders/adjust.frag");
+const SRC_COMP_HISTOGRAM: &[u8] = include_bytes!("../assets/shaders/adjust-histogram.comp");
+const SRC_COMP_EQUALIZE: &[u8] = include_bytes!("../assets/shaders/adjust-equalize.comp");
+
+/// Bins of the histograms, one per level of luma.
+const BINS: usize = 256;
+const MAX_TILES: usize = 16;
+
+/// Variants of the `equalize` parameter, in order.
+pub const EQUALIZATIONS: &[&str] = &["off", "global", "local"];
+
+/// Appends the parameters of the adjustments to `params`.
+pub fn with_params(params: Params) -> Params {
+    params
+        .with_f32("brightness", 0.0, -1.0, 1.0, 0.05)
+        .with_f32("contrast", 1.0, 0.0, 3.0, 0.1)
+        .with_f32("gamma", 1.0, 0.2, 5.0, 0.1)
+        .with_f32("black", 0.0, 0.0, 1.0, 0.02)
+        .with_f32("white", 1.0, 0.0, 1.0, 0.02)
+        .with_enum("equalize", 0, EQUALIZATIONS)
+        .with_i32("tiles", 8, 2, MAX_TILES as i32)
+        .with_f32("clip", 3.0, 1.0, 16.0, 0.5)
+}
+
+/// The parameter a character key steps, and by how much, for the scenes
+/// adjusting their input.
+pub fn key_step(ch: &str) -> Option<(&'static str, i32)> {
+    let step = match ch {
+        "b" => ("brightness", 1),
+        "B" => ("brightness", -1),
+        "c" => ("contrast", 1),
+        "C" => ("contrast", -1),
+        "g" => ("gamma", 1),
+        "G" => ("gamma", -1),
+        "k" => ("black", 1),
+        "K" => ("black", -1),
+        "w" => ("white", 1),
+        "W" => ("white", -1),
+        "e" => ("equalize", 1),
+        "E" => ("equalize", -1),
+        "t" => ("tiles", 1),
+        "T" => ("tiles", -1),
+        "x" => ("clip", 1),
+        "X" => ("clip", -1),
+        _ => return None,
+    };
+    Some(step)
+}
+
+/// The adjustments, as read from the parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Settings {
+    brightness: f32,
+    contrast: f32,
+    gamma: f32,
+    black: f32,
+    white: f32,
+    /// Index into [`EQUALIZATIONS`].
+    equalize: usize,
+    tiles: i32,
+    clip: f32,
+}
+
+impl Settings {
+    fn from_params(params: &Params) -> Self {
+        Self {
+            brightness: params.f32("brightness"),
+            contrast: params.f32("contrast"),
+            gamma: params.f32("gamma"),
+            black: params.f32("black"),
+            white: params.f32("white"),
+            equalize: params.variant("equalize"),
+            tiles: params.i32("tiles"),
+            clip: params.f32("clip"),
+        }
+    }
+
+    /// Whether the image comes out as it went in.
+    fn is_identity(&self) -> bool {
+        self.brightness == 0.0
+            && self.contrast == 1.0
+            && self.gamma == 1.0
+            && self.black == 0.0
+            && self.white == 1.0
+            && self.equalize == 0
+    }
+}
+
+/// Vertex of the quad covering the framebuffer.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// The compute passes of the equalization, which needs OpenGL 4.3.
+struct Equalizer {
+    histogram: GLuint,
+    u_tiles: GLint,
+    equalize: GLuint,
+    u_clip: GLint,
+    /// `MAX_TILES²` histograms of `BINS` counts.
+    histograms: GLuint,
+    /// R32F, `BINS` wide, with a row per tile.
+    luts: GLuint,
+}
+
+/// Draws the adjusted copy of an image, see the [module docs](self).
+pub struct Adjuster {
+    framebuffer: Framebuffer,
+
+    shader: GLuint,
+    u_equalize: GLint,
+    u_tiles: GLint,
+    u_black: GLint,
+    u_white: GLint,
+    u_gamma: GLint,
+    u_contrast: GLint,
+    u_brightness: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+
+    equalizer: Option<Equalizer>,
+    /// What the framebuffer was last drawn with, and from which texture.
+    drawn: Option<(GLuint, Settings)>,
+}
+
+impl Adjuster {
+    /// Adjuster of images of `size`.
+    pub fn new(size: UVec2) -> Result<Self, GlError> {
+        unsafe {
+            let framebuffer = create_framebuffer("adjusted input", size)?;
+
+            let shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_ADJUST)?;
+            let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(shader, name.as_ptr());
+            gl::UseProgram(shader);
+            gl::Uniform1i(uniform(c"u_image"), 0);
+            gl::Uniform1i(uniform(c"u_luts"), 1);
+            gl::Uniform1i(uniform(c"u_rows"), (MAX_TILES * MAX_TILES) as GLint);
+
+            // texel for texel, without the flip of the screen quads
+            let vertex = |x: f32, y: f32| Vertex {
+                position: vec2(x, y) * 2.0 - 1.0,
+                uv: vec2(x, y),
+            };
+            let vertices = [
+                vertex(0.0, 1.0),
+                vertex(0.0, 0.0),
+                vertex(1.0, 0.0),
+                vertex(0.0, 1.0),
+                vertex(1.0, 0.0),
+                vertex(1.0, 1.0),
+            ];
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let equalizer = if supports_compute() {
+                Some(Equalizer::new()?)
+            } else {
+                let (major, minor) = gl_version();
+                println!("adjust: no compute shaders on OpenGL {major}.{minor}, no equalization");
+                None
+            };
+
+            Ok(Self {
+                framebuffer,
+
+                u_equalize: uniform(c"u_equalize"),
+                u_tiles: uniform(c"u_tiles"),
+                u_black: uniform(c"u_black"),
+                u_white: uniform(c"u_white"),
+                u_gamma: uniform(c"u_gamma"),
+                u_contrast: uniform(c"u_contrast"),
+                u_brightness: uniform(c"u_brightness"),
+                shader,
+                vao,
+                vbo,
+
+                equalizer,
+                drawn: None,
+            })
+        }
+    }
+
+    /// Draws the adjusted image again on the next call to [`Self::apply`],
+    /// for when the content of the source texture changed.
+    pub fn invalidate(&mut self) {
+        self.drawn = None;
+    }
+
+    /// The texture to use in place of `source`: `source` itself when the
+    /// parameters don't change anything, or its adjusted copy.
+    pub fn apply(&mut self, source: GLuint, params: &Params) -> GLuint {
+        unsafe {
+            let settings = Settings::from_params(params);
+            if settings.is_identity() {
+                return source;
+            }
+            if self.drawn == Some((source, settings)) {
+                return self.framebuffer.texture;
+            }
+
+            push_debug_group(c"Adjust input");
+            let saved = SavedState::save();
+            let (mut framebuffer, mut viewport) = (0, [0; 4]);
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+
+            // a single tile clipped at its whole count is the global histogram
+            let (tiles, clip) = match EQUALIZATIONS[settings.equalize] {
+                "global" => (1, BINS as f32),
+                _ => (settings.tiles, settings.clip),
+            };
+            let equalize = match &self.equalizer {
+                Some(equalizer) if settings.equalize != 0 => {
+                    equalizer.run(source, tiles, clip);
+                    true
+                }
+                _ => false,
+            };
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer.fbo);
+            let size = self.framebuffer.size;
+            gl::Viewport(0, 0, size.x as GLsizei, size.y as GLsizei);
+            gl::Disable(gl::BLEND);
+
+            gl::UseProgram(self.shader);
+            gl::Uniform1i(self.u_equalize, equalize as GLint);
+            gl::Uniform1i(self.u_tiles, tiles);
+            gl::Uniform1f(self.u_black, settings.black);
+            gl::Uniform1f(self.u_white, settings.white);
+            gl::Uniform1f(self.u_gamma, settings.gamma);
+            gl::Uniform1f(self.u_contrast, settings.contrast);
+            gl::Uniform1f(self.u_brightness, settings.brightness);
+            gl::ActiveTexture(gl::TEXTURE1);
+            let luts = self
+                .equalizer
+                .as_ref()
+                .map_or(0, |equalizer| equalizer.luts);
+            gl::BindTexture(gl::TEXTURE_2D, luts);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, source);
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::BindVertexArray(0);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as GLuint);
+            let [x, y, width, height] = viewport;
+            gl::Viewport(x, y, width, height);
+            saved.restore();
+            pop_debug_group();
+
+            self.drawn = Some((source, settings));
+            self.framebuffer.texture
+        }
+    }
+}
+
+impl Drop for Adjuster {
+    fn drop(&mut self) {
+        unsafe {
+            delete_framebuffer(&self.framebuffer);
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
+
+impl Equalizer {
+    unsafe fn new() -> Result<Self, GlError> {
+        let histogram = create_compute_program(SRC_COMP_HISTOGRAM)?;
+        gl::UseProgram(histogram);
+        gl::Uniform1i(gl::GetUniformLocation(histogram, c"u_image".as_ptr()), 0);
+        let equalize = create_compute_program(SRC_COMP_EQUALIZE)?;
+
+        let mut histograms = 0;
+        gl::GenBuffers(1, &mut histograms);
+        gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, histograms);
+        let bytes = MAX_TILES * MAX_TILES * BINS * mem::size_of::<u32>();
+        gl::BufferData(
+            gl::SHADER_STORAGE_BUFFER,
+            bytes as GLsizeiptr,
+            std::ptr::null(),
+            gl::DYNAMIC_COPY,
+        );
+        gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, 0);
+        gpu_memory::record(Object::Buffer(histograms), Category::Buffers, bytes);
+
+        let mut luts = 0;
+        gl::GenTextures(1, &mut luts);
+        gl::BindTexture(gl::TEXTURE_2D, luts);
+        let rows = MAX_TILES * MAX_TILES;
+        gl::TexStorage2D(
+            gl::TEXTURE_2D,
+            1,
+            gl::R32F,
+            BINS as GLsizei,
+            rows as GLsizei,
+        );
+        // linear between bins, and rows are only sampled at their centers
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_S,
+            gl::CLAMP_TO_EDGE as GLint,
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_T,
+            gl::CLAMP_TO_EDGE as GLint,
+        );
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+        gpu_memory::record(
+            Object::Texture(luts),
+            Category::Textures,
+            BINS * rows * mem::size_of::<f32>(),
+        );
+
+        Ok(Self {
+            u_tiles: gl::GetUniformLocation(histogram, c"u_tiles".as_ptr()),
+            histogram,
+            u_clip: gl::GetUniformLocation(equalize, c"u_clip".as_ptr()),
+            equalize,
+            histograms,
+            luts,
+        })
+    }
+
+    /// Fills the lookup tables of `tiles`×`tiles` tiles of `source`, with
+    /// their histograms clipped to `clip` times their mean count.
+    unsafe fn run(&self, source: GLuint, tiles: i32, clip: f32) {
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, self.histograms);
+
+        gl::UseProgram(self.histogram);
+        gl::Uniform1i(self.u_tiles, tiles);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, source);
+        gl::DispatchCompute(tiles as u32, tiles as u32, 1);
+        gl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+
+        gl::UseProgram(self.equalize);
+        gl::Uniform1f(self.u_clip, clip);
+        gl::BindImageTexture(0, self.luts, 0, gl::FALSE, 0, gl::WRITE_ONLY, gl::R32F);
+        gl::DispatchCompute((tiles * tiles) as u32, 1, 1);
+        gl::MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT);
+
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, 0);
+    }
+}
+
+impl Drop for Equalizer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.histogram);
+            gl::DeleteProgram(self.equalize);
+            delete_buffers(&[self.histograms]);
+            delete_textures(&[self.luts]);
+        }
+    }
+}
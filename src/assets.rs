@@ -0,0 +1,30 @@
+//! Finding the `assets` directory at runtime, for what is read from disk
+//! rather than embedded: watched and validated shaders, and the scripts remote
+//! clients may run.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding where the assets are.
+pub const ENV_VAR: &str = "PLAYGROUND_ASSETS";
+
+/// The assets directory: [`ENV_VAR`] if set, else `assets` in the working
+/// directory, or next to the executable or one of its parents, as for a build
+/// run from anywhere in a checkout. Falls back to `assets` in the working
+/// directory even if it doesn't exist.
+pub fn dir() -> PathBuf {
+    if let Some(dir) = env::var_os(ENV_VAR) {
+        return PathBuf::from(dir);
+    }
+
+    let local = Path::new("assets");
+    if local.is_dir() {
+        return local.to_path_buf();
+    }
+
+    let exe = env::current_exe().ok();
+    (exe.iter().flat_map(|exe| exe.ancestors().skip(1)))
+        .map(|dir| dir.join("assets"))
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| local.to_path_buf())
+}
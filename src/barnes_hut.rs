@@ -0,0 +1,192 @@
+//! Gravity between many bodies with the Barnes-Hut approximation: bodies
+//! are sorted into a quadtree whose nodes know their total mass and center of
+//! mass, and a node far enough away pulls as a single body instead of going
+//! through everything in it. That makes a step O(n log n) instead of O(n²).
+
+use glam::Vec2;
+
+/// Bodies a leaf holds before it gets split.
+const LEAF_SIZE: usize = 4;
+/// Deepest a node gets, so that bodies on top of each other don't split
+/// forever.
+const MAX_DEPTH: u32 = 24;
+
+/// Gravitational acceleration of every body toward all the others, with a
+/// gravitational constant of 1.
+///
+/// A node of side `s` at distance `d` counts as a single body when
+/// `s / d < theta`, so 0 is exact and larger values are faster and rougher.
+/// `softening` is added to every distance so that close encounters don't
+/// fling bodies away.
+pub fn accelerations(
+    positions: &[Vec2],
+    masses: &[f32],
+    theta: f32,
+    softening: f32,
+    accelerations: &mut [Vec2],
+) {
+    let tree = Tree::new(positions, masses);
+    let mut stack = Vec::new();
+    for (i, acceleration) in accelerations.iter_mut().enumerate() {
+        *acceleration = tree.acceleration(positions, masses, i, theta, softening, &mut stack);
+    }
+}
+
+struct Node {
+    center_of_mass: Vec2,
+    mass: f32,
+    /// Side of the square the node covers.
+    size: f32,
+    /// Indices of the nodes of the non-empty quadrants.
+    children: [u32; 4],
+    n_children: u8,
+    /// Range of [`Tree::order`] of the bodies in a leaf.
+    bodies: (u32, u32),
+}
+
+struct Tree {
+    nodes: Vec<Node>,
+    /// Indices of the bodies, those of each leaf next to each other.
+    order: Vec<u32>,
+}
+
+impl Tree {
+    fn new(positions: &[Vec2], masses: &[f32]) -> Self {
+        let mut tree = Self {
+            nodes: Vec::with_capacity(positions.len()),
+            order: (0..positions.len() as u32).collect(),
+        };
+        if positions.is_empty() {
+            return tree;
+        }
+
+        let (min, max) = (positions.iter()).fold((Vec2::MAX, Vec2::MIN), |(min, max), &p| {
+            (min.min(p), max.max(p))
+        });
+        let size = (max - min).max_element().max(f32::EPSILON);
+        tree.build(positions, masses, (0, positions.len()), min, size, 0);
+        tree
+    }
+
+    /// Builds the node of the bodies in `range` of `order`, within the
+    /// square of side `size` from `min`, returning its index.
+    fn build(
+        &mut self,
+        positions: &[Vec2],
+        masses: &[f32],
+        range: (usize, usize),
+        min: Vec2,
+        size: f32,
+        depth: u32,
+    ) -> u32 {
+        let bodies = &self.order[range.0..range.1];
+        let mass = bodies.iter().map(|&i| masses[i as usize]).sum::<f32>();
+        let weighted = (bodies.iter())
+            .map(|&i| positions[i as usize] * masses[i as usize])
+            .sum::<Vec2>();
+        let center_of_mass = match mass > 0.0 {
+            true => weighted / mass,
+            false => min + size * 0.5,
+        };
+
+        let index = self.nodes.len() as u32;
+        self.nodes.push(Node {
+            center_of_mass,
+            mass,
+            size,
+            children: [0; 4],
+            n_children: 0,
+            bodies: (range.0 as u32, range.1 as u32),
+        });
+        if range.1 - range.0 <= LEAF_SIZE || depth >= MAX_DEPTH {
+            return index;
+        }
+
+        // left and right halves, then each of them into bottom and top
+        let mid = min + size * 0.5;
+        let bodies = &mut self.order[range.0..range.1];
+        let x = partition(bodies, |i| positions[i as usize].x < mid.x);
+        let y_left = partition(&mut bodies[..x], |i| positions[i as usize].y < mid.y);
+        let y_right = partition(&mut bodies[x..], |i| positions[i as usize].y < mid.y);
+
+        let start = range.0;
+        let quadrants = [
+            ((start, start + y_left), Vec2::new(0.0, 0.0)),
+            ((start + y_left, start + x), Vec2::new(0.0, 1.0)),
+            ((start + x, start + x + y_right), Vec2::new(1.0, 0.0)),
+            ((start + x + y_right, range.1), Vec2::new(1.0, 1.0)),
+        ];
+        let half = size * 0.5;
+        for (range, offset) in quadrants {
+            if range.0 == range.1 {
+                continue;
+            }
+            let child = self.build(
+                positions,
+                masses,
+                range,
+                min + offset * half,
+                half,
+                depth + 1,
+            );
+            let node = &mut self.nodes[index as usize];
+            node.children[node.n_children as usize] = child;
+            node.n_children += 1;
+        }
+        index
+    }
+
+    /// Acceleration of body `i`, going through the tree with `stack`.
+    fn acceleration(
+        &self,
+        positions: &[Vec2],
+        masses: &[f32],
+        i: usize,
+        theta: f32,
+        softening: f32,
+        stack: &mut Vec<u32>,
+    ) -> Vec2 {
+        let p = positions[i];
+        let pull = |towards: Vec2, mass: f32| {
+            let d = towards - p;
+            let distance_squared = d.length_squared() + softening * softening;
+            d * (mass / (distance_squared * distance_squared.sqrt()))
+        };
+
+        let mut acceleration = Vec2::ZERO;
+        stack.clear();
+        stack.push(0);
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+
+            if node.n_children == 0 {
+                for &j in &self.order[node.bodies.0 as usize..node.bodies.1 as usize] {
+                    if j as usize != i {
+                        acceleration += pull(positions[j as usize], masses[j as usize]);
+                    }
+                }
+                continue;
+            }
+
+            let distance_squared = node.center_of_mass.distance_squared(p);
+            if node.size * node.size < theta * theta * distance_squared {
+                acceleration += pull(node.center_of_mass, node.mass);
+            } else {
+                stack.extend(&node.children[..node.n_children as usize]);
+            }
+        }
+        acceleration
+    }
+}
+
+/// Moves the elements matching `pred` to the front, returning how many.
+fn partition(slice: &mut [u32], pred: impl Fn(u32) -> bool) -> usize {
+    let mut split = 0;
+    for i in 0..slice.len() {
+        if pred(slice[i]) {
+            slice.swap(split, i);
+            split += 1;
+        }
+    }
+    split
+}
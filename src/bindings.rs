@@ -0,0 +1,498 @@
+//! Key bindings of the app: chords of modifiers and a key bound to actions,
+//! and the repeat of keys held down.
+//!
+//! An action runs once when its chord is pressed. Keys that aren't bound go
+//! to the scene instead, and those keep going to it while they are held:
+//! rather than relying on the repeat of the OS, which has the same pace
+//! however long a key is held, they repeat after a delay and faster and
+//! faster, until they are released.
+
+use std::fmt;
+use std::str::FromStr;
+
+use winit::keyboard::{Key, KeyCode, ModifiersState, NamedKey, PhysicalKey, SmolStr};
+
+/// How much shorter each repeat gets than the one before, until the fastest.
+const ACCELERATION: f32 = 0.85;
+
+/// Something the app does, whatever the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Reseed,
+    Snapshot,
+    Screenshot,
+    GpuMemory,
+    Export,
+    Home,
+    Theme,
+    Console,
+    Split,
+    Stereo,
+    PostNext,
+    PostToggle,
+    PostEarlier,
+    PostLater,
+    Help,
+    Frosted,
+    Locale,
+    Transparency,
+    SaveReplay,
+    Record,
+    NextMonitor,
+    Params,
+    Fps,
+    PolygonMode,
+    Normals,
+}
+
+impl Action {
+    pub const ALL: &'static [Self] = &[
+        Self::Quit,
+        Self::Reseed,
+        Self::Snapshot,
+        Self::Screenshot,
+        Self::GpuMemory,
+        Self::Export,
+        Self::Home,
+        Self::Theme,
+        Self::Console,
+        Self::Split,
+        Self::Stereo,
+        Self::PostNext,
+        Self::PostToggle,
+        Self::PostEarlier,
+        Self::PostLater,
+        Self::Help,
+        Self::Frosted,
+        Self::Locale,
+        Self::Transparency,
+        Self::SaveReplay,
+        Self::Record,
+        Self::NextMonitor,
+        Self::Params,
+        Self::Fps,
+        Self::PolygonMode,
+        Self::Normals,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::Reseed => "reseed",
+            Self::Snapshot => "snapshot",
+            Self::Screenshot => "screenshot",
+            Self::GpuMemory => "gpu-memory",
+            Self::Export => "export",
+            Self::Home => "home",
+            Self::Theme => "theme",
+            Self::Console => "console",
+            Self::Split => "split",
+            Self::Stereo => "stereo",
+            Self::PostNext => "post-next",
+            Self::PostToggle => "post-toggle",
+            Self::PostEarlier => "post-earlier",
+            Self::PostLater => "post-later",
+            Self::Help => "help",
+            Self::Frosted => "frosted",
+            Self::Locale => "locale",
+            Self::Transparency => "transparency",
+            Self::SaveReplay => "save-replay",
+            Self::Record => "record",
+            Self::NextMonitor => "next-monitor",
+            Self::Params => "params",
+            Self::Fps => "fps",
+            Self::PolygonMode => "polygon-mode",
+            Self::Normals => "normals",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (Self::ALL.iter())
+            .find(|action| action.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown action '{s}'"))
+    }
+}
+
+/// A key, either by what it types or by where it is on the keyboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    Logical(Key<SmolStr>),
+    /// For punctuation, which moves around between layouts.
+    Physical(KeyCode),
+}
+
+const NAMED_KEYS: &[(&str, NamedKey)] = &[
+    ("Escape", NamedKey::Escape),
+    ("Tab", NamedKey::Tab),
+    ("Enter", NamedKey::Enter),
+    ("Space", NamedKey::Space),
+    ("Backspace", NamedKey::Backspace),
+    ("Delete", NamedKey::Delete),
+    ("Insert", NamedKey::Insert),
+    ("Home", NamedKey::Home),
+    ("End", NamedKey::End),
+    ("PageUp", NamedKey::PageUp),
+    ("PageDown", NamedKey::PageDown),
+    ("ArrowUp", NamedKey::ArrowUp),
+    ("ArrowDown", NamedKey::ArrowDown),
+    ("ArrowLeft", NamedKey::ArrowLeft),
+    ("ArrowRight", NamedKey::ArrowRight),
+    ("F1", NamedKey::F1),
+    ("F2", NamedKey::F2),
+    ("F3", NamedKey::F3),
+    ("F4", NamedKey::F4),
+    ("F5", NamedKey::F5),
+    ("F6", NamedKey::F6),
+    ("F7", NamedKey::F7),
+    ("F8", NamedKey::F8),
+    ("F9", NamedKey::F9),
+    ("F10", NamedKey::F10),
+    ("F11", NamedKey::F11),
+    ("F12", NamedKey::F12),
+];
+
+const PHYSICAL_KEYS: &[(&str, KeyCode)] = &[
+    ("Backquote", KeyCode::Backquote),
+    ("Backslash", KeyCode::Backslash),
+    ("Slash", KeyCode::Slash),
+    ("Semicolon", KeyCode::Semicolon),
+    ("Quote", KeyCode::Quote),
+    ("BracketLeft", KeyCode::BracketLeft),
+    ("BracketRight", KeyCode::BracketRight),
+];
+
+/// Named key called `name`, among those chords can use.
+pub fn named_key(name: &str) -> Option<NamedKey> {
+    (NAMED_KEYS.iter())
+        .find(|(key_name, _)| *key_name == name)
+        .map(|&(_, key)| key)
+}
+
+/// Name of `key`, if chords can use it.
+pub fn named_key_name(key: NamedKey) -> Option<&'static str> {
+    (NAMED_KEYS.iter())
+        .find(|(_, named)| *named == key)
+        .map(|&(name, _)| name)
+}
+
+/// Key at the place called `name`, among those chords can use.
+pub fn key_code(name: &str) -> Option<KeyCode> {
+    (PHYSICAL_KEYS.iter())
+        .find(|(key_name, _)| *key_name == name)
+        .map(|&(_, code)| code)
+}
+
+/// Name of the place of `code`, if chords can use it.
+pub fn key_code_name(code: KeyCode) -> Option<&'static str> {
+    (PHYSICAL_KEYS.iter())
+        .find(|(_, key)| *key == code)
+        .map(|&(name, _)| name)
+}
+
+impl FromStr for Trigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(named) = named_key(s) {
+            return Ok(Self::Logical(Key::Named(named)));
+        }
+        if let Some(code) = key_code(s) {
+            return Ok(Self::Physical(code));
+        }
+        match s.chars().count() {
+            1 => Ok(Self::Logical(Key::Character(s.to_lowercase().into()))),
+            _ => Err(format!("unknown key '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Logical(Key::Named(named)) => {
+                write!(f, "{}", named_key_name(*named).unwrap_or("?"))
+            }
+            Self::Logical(Key::Character(ch)) => write!(f, "{}", ch.to_uppercase()),
+            Self::Logical(_) => write!(f, "?"),
+            Self::Physical(code) => write!(f, "{}", key_code_name(*code).unwrap_or("?")),
+        }
+    }
+}
+
+/// Modifiers held with a key, as in `Ctrl+Shift+S`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chord {
+    pub modifiers: ModifiersState,
+    pub trigger: Trigger,
+}
+
+impl Chord {
+    /// Whether pressing the key that types `logical_key` at `physical_key`
+    /// with `modifiers` held plays the chord.
+    ///
+    /// Its modifiers must all be held, and no other besides Shift, which
+    /// chords without it ignore since it's often needed to type the key.
+    pub fn matches(
+        &self,
+        logical_key: &Key<SmolStr>,
+        physical_key: PhysicalKey,
+        modifiers: ModifiersState,
+    ) -> bool {
+        let extra = modifiers - self.modifiers - ModifiersState::SHIFT;
+        if !modifiers.contains(self.modifiers) || !extra.is_empty() {
+            return false;
+        }
+
+        match (&self.trigger, logical_key) {
+            (Trigger::Physical(code), _) => physical_key == PhysicalKey::Code(*code),
+            (Trigger::Logical(Key::Character(ch)), Key::Character(pressed)) => {
+                pressed.to_lowercase() == ch.as_str()
+            }
+            (Trigger::Logical(key), pressed) => key == pressed,
+        }
+    }
+}
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the key is last, and may be a `+` itself
+        let (modifiers, key) = match s.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None if s == "+" => ("", s),
+            None => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+
+        let mut chord_modifiers = ModifiersState::empty();
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            chord_modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => ModifiersState::CONTROL,
+                "shift" => ModifiersState::SHIFT,
+                "alt" => ModifiersState::ALT,
+                "super" | "meta" | "cmd" => ModifiersState::SUPER,
+                _ => return Err(format!("unknown modifier '{modifier}'")),
+            };
+        }
+
+        Ok(Self {
+            modifiers: chord_modifiers,
+            trigger: key.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (ModifiersState::CONTROL, "Ctrl"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SUPER, "Super"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{}", self.trigger)
+    }
+}
+
+/// Which chords run which actions.
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    bindings: Vec<(Chord, Action)>,
+}
+
+/// The keys the app always had, and chords for what's commonly done with
+/// them.
+const DEFAULT_BINDINGS: &[(&str, Action)] = &[
+    ("Escape", Action::Quit),
+    ("F5", Action::Reseed),
+    ("Ctrl+R", Action::Reseed),
+    ("F6", Action::Snapshot),
+    ("Ctrl+S", Action::Snapshot),
+    ("F9", Action::GpuMemory),
+    ("F10", Action::Export),
+    ("Ctrl+E", Action::Export),
+    ("Home", Action::Home),
+    ("F11", Action::Theme),
+    ("F12", Action::Screenshot),
+    ("Backquote", Action::Console),
+    ("Backslash", Action::Split),
+    ("Slash", Action::Stereo),
+    ("Semicolon", Action::PostNext),
+    ("Quote", Action::PostToggle),
+    ("BracketLeft", Action::PostEarlier),
+    ("BracketRight", Action::PostLater),
+    ("Tab", Action::Help),
+    ("Insert", Action::Frosted),
+    ("End", Action::Locale),
+    ("Ctrl+T", Action::Transparency),
+    ("Ctrl+Shift+R", Action::SaveReplay),
+    ("Ctrl+Shift+V", Action::Record),
+    ("Ctrl+M", Action::NextMonitor),
+    ("Ctrl+P", Action::Params),
+    ("Ctrl+F", Action::Fps),
+    ("Ctrl+W", Action::PolygonMode),
+    ("Ctrl+N", Action::Normals),
+];
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let bindings = (DEFAULT_BINDINGS.iter())
+            .map(|&(chord, action)| (chord.parse().unwrap(), action))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Bindings {
+    /// Binds `chord` to `action`, in place of what it was bound to.
+    pub fn bind(&mut self, chord: Chord, action: Action) {
+        self.bindings.retain(|(bound, _)| *bound != chord);
+        self.bindings.push((chord, action));
+    }
+
+    /// Action of the chord played by a key press, the one with the most
+    /// modifiers if several match, as `Ctrl+Shift+S` over `Ctrl+S`.
+    pub fn action(
+        &self,
+        logical_key: &Key<SmolStr>,
+        physical_key: PhysicalKey,
+        modifiers: ModifiersState,
+    ) -> Option<Action> {
+        (self.bindings.iter())
+            .filter(|(chord, _)| chord.matches(logical_key, physical_key, modifiers))
+            .max_by_key(|(chord, _)| chord.modifiers.bits().count_ones())
+            .map(|&(_, action)| action)
+    }
+}
+
+/// Parses `chord=action`, as given to `--bind`.
+pub fn parse_binding(s: &str) -> Result<(Chord, Action), String> {
+    let (chord, action) = (s.rsplit_once('=')).ok_or("expected CHORD=ACTION")?;
+    Ok((chord.parse()?, action.parse()?))
+}
+
+/// Pace at which held keys repeat, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RepeatConfig {
+    /// Before the first repeat.
+    pub delay: f32,
+    /// Between the first repeats.
+    pub interval: f32,
+    /// Between repeats once they've sped up all the way, the same as
+    /// `interval` for a steady pace.
+    pub fastest: f32,
+}
+
+impl Default for RepeatConfig {
+    fn default() -> Self {
+        Self {
+            delay: 0.3,
+            interval: 0.1,
+            fastest: 0.02,
+        }
+    }
+}
+
+impl FromStr for RepeatConfig {
+    type Err = String;
+
+    /// Parses `DELAY,INTERVAL,FASTEST` in milliseconds.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let millis = (s.split(','))
+            .map(|ms| ms.trim().parse::<f32>().map(|ms| ms / 1000.0))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        let [delay, interval, fastest] = millis[..] else {
+            return Err("expected DELAY,INTERVAL,FASTEST".to_owned());
+        };
+        if interval <= 0.0 || fastest <= 0.0 {
+            return Err("intervals must be positive".to_owned());
+        }
+
+        Ok(Self {
+            delay: delay.max(0.0),
+            interval,
+            fastest: fastest.min(interval),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Held {
+    key: Key<SmolStr>,
+    /// Time left until the next repeat.
+    next: f32,
+    /// Time between this repeat and the next.
+    interval: f32,
+}
+
+/// Keys held down, repeating on their own pace.
+#[derive(Debug, Clone)]
+pub struct KeyRepeat {
+    config: RepeatConfig,
+    held: Vec<Held>,
+}
+
+impl KeyRepeat {
+    pub fn new(config: RepeatConfig) -> Self {
+        Self {
+            config,
+            held: Vec::new(),
+        }
+    }
+
+    /// Starts repeating `key`, which was just pressed.
+    pub fn press(&mut self, key: Key<SmolStr>) {
+        self.release(&key);
+        self.held.push(Held {
+            key,
+            next: self.config.delay,
+            interval: self.config.interval,
+        });
+    }
+
+    /// Stops repeating `key`. Characters are compared without their case,
+    /// since Shift may be released first.
+    pub fn release(&mut self, key: &Key<SmolStr>) {
+        let same = |held: &Key<SmolStr>| match (held, key) {
+            (Key::Character(a), Key::Character(b)) => a.to_lowercase() == b.to_lowercase(),
+            (a, b) => a == b,
+        };
+        self.held.retain(|held| !same(&held.key));
+    }
+
+    /// Stops repeating everything, as when the window loses focus and won't
+    /// see the keys getting released.
+    pub fn release_all(&mut self) {
+        self.held.clear();
+    }
+
+    /// Advances time by `dt`, returning the keys repeating in the meantime,
+    /// once each at most: after a stall, keys carry on repeating from now
+    /// rather than catching up all at once.
+    pub fn update(&mut self, dt: f32) -> Vec<Key<SmolStr>> {
+        let mut repeats = Vec::new();
+        for held in &mut self.held {
+            held.next -= dt;
+            if held.next <= 0.0 {
+                repeats.push(held.key.clone());
+                held.next += held.interval;
+                if held.next <= 0.0 {
+                    held.next = held.interval;
+                }
+                held.interval = (held.interval * ACCELERATION).max(self.config.fastest);
+            }
+        }
+        repeats
+    }
+}
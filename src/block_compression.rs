@@ -0,0 +1,422 @@
+//! BC1 and BC7 encoders and decoders, to compare what GPU texture compression
+//! does to an image with the image itself.
+//!
+//! Both formats store 4×4 blocks as two endpoints and an index per pixel into
+//! the colors interpolated between them. Endpoints are fitted along the
+//! principal axis of the block's colors, then refined by least squares for
+//! the indices they give. BC7 is only encoded with mode 6, its single subset
+//! RGBA mode, so the decoder only reads that mode.
+
+use std::thread;
+
+use glam::{Vec3, Vec4};
+use image::RgbaImage;
+
+/// Bytes per 4×4 block.
+pub const BC1_BLOCK_BYTES: usize = 8;
+pub const BC7_BLOCK_BYTES: usize = 16;
+
+/// Weights of the 16 colors of a mode 6 block, out of 64.
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// The largest part of `image` made of whole blocks, which is what gets
+/// compressed.
+pub fn block_aligned(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = (image.width() / 4 * 4, image.height() / 4 * 4);
+    image::imageops::crop_imm(image, 0, 0, width, height).to_image()
+}
+
+/// Compresses an image whose sides are multiples of 4 to BC1, dropping alpha.
+pub fn encode_bc1(image: &RgbaImage) -> Vec<u8> {
+    encode(image, BC1_BLOCK_BYTES, |pixels, block| {
+        block.copy_from_slice(&encode_bc1_block(pixels))
+    })
+}
+
+/// Compresses an image whose sides are multiples of 4 to BC7.
+pub fn encode_bc7(image: &RgbaImage) -> Vec<u8> {
+    encode(image, BC7_BLOCK_BYTES, |pixels, block| {
+        block.copy_from_slice(&encode_bc7_block(pixels))
+    })
+}
+
+pub fn decode_bc1(data: &[u8], width: u32, height: u32) -> RgbaImage {
+    decode(data, width, height, BC1_BLOCK_BYTES, decode_bc1_block)
+}
+
+pub fn decode_bc7(data: &[u8], width: u32, height: u32) -> RgbaImage {
+    decode(data, width, height, BC7_BLOCK_BYTES, decode_bc7_block)
+}
+
+/// Peak signal-to-noise ratio between two images of the same size over RGB,
+/// in decibels. Identical images are infinitely far from noise.
+pub fn psnr(a: &RgbaImage, b: &RgbaImage) -> f32 {
+    let squared_error = (a.pixels().zip(b.pixels()))
+        .flat_map(|(a, b)| (0..3).map(move |c| (a[c] as f64 - b[c] as f64).powi(2)))
+        .sum::<f64>();
+    let mse = squared_error / (a.width() as f64 * a.height() as f64 * 3.0);
+    (10.0 * (255.0 * 255.0 / mse).log10()) as f32
+}
+
+/// Encodes rows of blocks on every core, as BC7 takes a while.
+fn encode(
+    image: &RgbaImage,
+    block_bytes: usize,
+    encode_block: impl Fn(&[Vec4; 16], &mut [u8]) + Sync,
+) -> Vec<u8> {
+    assert!(
+        image.width().is_multiple_of(4) && image.height().is_multiple_of(4),
+        "{}x{} isn't made of whole blocks",
+        image.width(),
+        image.height()
+    );
+    let blocks_x = image.width() as usize / 4;
+    let row_bytes = blocks_x * block_bytes;
+    let mut data = vec![0; row_bytes * image.height() as usize / 4];
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let rows_per_thread = (image.height() as usize / 4).div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        for (chunk, rows) in data.chunks_mut(row_bytes * rows_per_thread).enumerate() {
+            let encode_block = &encode_block;
+            scope.spawn(move || {
+                for (row, row_data) in rows.chunks_mut(row_bytes).enumerate() {
+                    let block_y = (chunk * rows_per_thread + row) as u32;
+                    for (block_x, block) in row_data.chunks_mut(block_bytes).enumerate() {
+                        let pixels = read_block(image, block_x as u32, block_y);
+                        encode_block(&pixels, block);
+                    }
+                }
+            });
+        }
+    });
+    data
+}
+
+fn decode(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    block_bytes: usize,
+    decode_block: impl Fn(&[u8]) -> [[u8; 4]; 16],
+) -> RgbaImage {
+    let mut image = RgbaImage::new(width, height);
+    let blocks_x = width / 4;
+    for (i, block) in data.chunks_exact(block_bytes).enumerate() {
+        let (block_x, block_y) = (i as u32 % blocks_x, i as u32 / blocks_x);
+        for (j, pixel) in decode_block(block).into_iter().enumerate() {
+            let (x, y) = (block_x * 4 + j as u32 % 4, block_y * 4 + j as u32 / 4);
+            image.put_pixel(x, y, image::Rgba(pixel));
+        }
+    }
+    image
+}
+
+/// Pixels of a block in row order, from 0 to 255.
+fn read_block(image: &RgbaImage, block_x: u32, block_y: u32) -> [Vec4; 16] {
+    std::array::from_fn(|i| {
+        let pixel = image.get_pixel(block_x * 4 + i as u32 % 4, block_y * 4 + i as u32 / 4);
+        Vec4::from_array(pixel.0.map(|c| c as f32))
+    })
+}
+
+/// Mean and principal axis of points, the axis found by power iteration on
+/// their covariance.
+fn principal_axis(points: &[Vec4; 16]) -> (Vec4, Vec4) {
+    let mean = points.iter().sum::<Vec4>() / 16.0;
+    let mut covariance = [Vec4::ZERO; 4];
+    for point in points {
+        let d = *point - mean;
+        for (row, &component) in covariance.iter_mut().zip(d.as_ref()) {
+            *row += d * component;
+        }
+    }
+
+    // starting from the widest extent avoids landing on a null vector
+    let (min, max) = (points.iter()).fold((points[0], points[0]), |(min, max), &p| {
+        (min.min(p), max.max(p))
+    });
+    let mut axis = max - min;
+    for _ in 0..8 {
+        let next = Vec4::new(
+            covariance[0].dot(axis),
+            covariance[1].dot(axis),
+            covariance[2].dot(axis),
+            covariance[3].dot(axis),
+        );
+        if next.length_squared() < 1e-6 {
+            break;
+        }
+        axis = next.normalize();
+    }
+    (mean, axis.normalize_or_zero())
+}
+
+/// Endpoints spanning the projections of the points on their principal axis.
+fn fit_endpoints(points: &[Vec4; 16]) -> (Vec4, Vec4) {
+    let (mean, axis) = principal_axis(points);
+    let (t_min, t_max) = (points.iter())
+        .map(|&p| (p - mean).dot(axis))
+        .fold((f32::MAX, f32::MIN), |(min, max), t| {
+            (min.min(t), max.max(t))
+        });
+    (mean + axis * t_min, mean + axis * t_max)
+}
+
+/// Endpoints minimizing the squared error for fixed interpolation weights
+/// from 0 to 1, or `None` if every pixel uses the same weight.
+fn refine_endpoints(points: &[Vec4; 16], weights: &[f32; 16]) -> Option<(Vec4, Vec4)> {
+    let (mut aa, mut ab, mut bb) = (0.0, 0.0, 0.0);
+    let (mut ax, mut bx) = (Vec4::ZERO, Vec4::ZERO);
+    for (&point, &w) in points.iter().zip(weights) {
+        let (a, b) = (1.0 - w, w);
+        aa += a * a;
+        ab += a * b;
+        bb += b * b;
+        ax += point * a;
+        bx += point * b;
+    }
+    let determinant = aa * bb - ab * ab;
+    if determinant.abs() < 1e-6 {
+        return None;
+    }
+    let e0 = (ax * bb - bx * ab) / determinant;
+    let e1 = (bx * aa - ax * ab) / determinant;
+    Some((
+        e0.clamp(Vec4::ZERO, Vec4::splat(255.0)),
+        e1.clamp(Vec4::ZERO, Vec4::splat(255.0)),
+    ))
+}
+
+/// Index of the closest color of a palette to every point, and the total
+/// squared error.
+fn pick_indices(points: &[Vec4; 16], palette: &[Vec4]) -> ([u8; 16], f32) {
+    let mut error = 0.0;
+    let indices = points.map(|point| {
+        let (index, distance) = (palette.iter().enumerate())
+            .map(|(i, color)| (i, color.distance_squared(point)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        error += distance;
+        index as u8
+    });
+    (indices, error)
+}
+
+// --- BC1 ---
+
+fn to_565(color: Vec3) -> u16 {
+    let c = (color / 255.0).clamp(Vec3::ZERO, Vec3::ONE);
+    let r = (c.x * 31.0).round() as u16;
+    let g = (c.y * 63.0).round() as u16;
+    let b = (c.z * 31.0).round() as u16;
+    (r << 11) | (g << 5) | b
+}
+
+fn from_565(color: u16) -> Vec3 {
+    let (r, g, b) = ((color >> 11) & 31, (color >> 5) & 63, color & 31);
+    let expand5 = |c: u16| ((c << 3) | (c >> 2)) as f32;
+    let expand6 = |c: u16| ((c << 2) | (c >> 4)) as f32;
+    Vec3::new(expand5(r), expand6(g), expand5(b))
+}
+
+/// The 4 colors of a block whose first endpoint is the greater one.
+fn bc1_palette(color0: u16, color1: u16) -> [Vec4; 4] {
+    let (c0, c1) = (from_565(color0), from_565(color1));
+    [c0, c1, (c0 * 2.0 + c1) / 3.0, (c0 + c1 * 2.0) / 3.0].map(|c| c.extend(255.0))
+}
+
+/// The block with endpoints quantized to 5:6:5, their indices and its error.
+fn bc1_candidate(points: &[Vec4; 16], e0: Vec4, e1: Vec4) -> ([u8; 8], f32) {
+    let (mut color0, mut color1) = (to_565(e0.truncate()), to_565(e1.truncate()));
+    if color0 < color1 {
+        (color0, color1) = (color1, color0);
+    }
+    let (indices, error) = match color0 == color1 {
+        // a single color, which 4-color blocks can't have
+        true => (
+            [0; 16],
+            pick_indices(points, &bc1_palette(color0, color1)[..1]).1,
+        ),
+        false => pick_indices(points, &bc1_palette(color0, color1)),
+    };
+
+    let bits = (indices.iter().enumerate())
+        .fold(0u32, |bits, (i, &index)| bits | (index as u32) << (i * 2));
+    let mut block = [0; 8];
+    block[0..2].copy_from_slice(&color0.to_le_bytes());
+    block[2..4].copy_from_slice(&color1.to_le_bytes());
+    block[4..8].copy_from_slice(&bits.to_le_bytes());
+    (block, error)
+}
+
+fn encode_bc1_block(pixels: &[Vec4; 16]) -> [u8; 8] {
+    // alpha doesn't take part in the fit
+    let points = pixels.map(|p| p.truncate().extend(255.0));
+    let (e0, e1) = fit_endpoints(&points);
+    let (block, error) = bc1_candidate(&points, e0, e1);
+
+    let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+    const WEIGHTS: [f32; 4] = [0.0, 1.0, 1.0 / 3.0, 2.0 / 3.0];
+    let weights = std::array::from_fn(|i| WEIGHTS[(indices >> (i * 2)) as usize & 3]);
+    let color0 = from_565(u16::from_le_bytes([block[0], block[1]])).extend(255.0);
+    let color1 = from_565(u16::from_le_bytes([block[2], block[3]])).extend(255.0);
+    match refine_endpoints(&points, &weights) {
+        Some((r0, r1)) if (r0, r1) != (color0, color1) => {
+            let (refined, refined_error) = bc1_candidate(&points, r0, r1);
+            if refined_error < error {
+                return refined;
+            }
+            block
+        }
+        _ => block,
+    }
+}
+
+fn decode_bc1_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+
+    let mut palette = bc1_palette(color0, color1);
+    if color0 <= color1 {
+        // 3 colors and transparent black
+        let (c0, c1) = (palette[0], palette[1]);
+        palette[2] = (c0 + c1) / 2.0;
+        palette[3] = Vec4::ZERO;
+    }
+    std::array::from_fn(|i| {
+        let color = palette[(indices >> (i * 2)) as usize & 3];
+        color.round().to_array().map(|c| c as u8)
+    })
+}
+
+// --- BC7 mode 6 ---
+
+/// Endpoints of a mode 6 block: 7 bits per channel and a shared low bit.
+#[derive(Debug, Clone, Copy)]
+struct Endpoint {
+    color: [u8; 4],
+    p_bit: u8,
+}
+
+impl Endpoint {
+    /// The closest endpoint to a color, trying both low bits.
+    fn quantize(color: Vec4) -> Self {
+        [0, 1]
+            .map(|p_bit| {
+                let color = color
+                    .to_array()
+                    .map(|c| (((c - p_bit as f32) / 2.0).round()).clamp(0.0, 127.0) as u8);
+                Self { color, p_bit }
+            })
+            .into_iter()
+            .min_by(|a, b| {
+                let error = |e: &Self| e.expand().distance_squared(color);
+                error(a).total_cmp(&error(b))
+            })
+            .unwrap()
+    }
+
+    fn expand(&self) -> Vec4 {
+        Vec4::from_array(self.color.map(|c| ((c << 1) | self.p_bit) as f32))
+    }
+}
+
+fn bc7_palette(e0: Endpoint, e1: Endpoint) -> [Vec4; 16] {
+    let (a, b) = (e0.expand(), e1.expand());
+    BC7_WEIGHTS.map(|w| ((a * (64 - w) as f32 + b * w as f32 + 32.0) / 64.0).floor())
+}
+
+fn bc7_candidate(points: &[Vec4; 16], e0: Vec4, e1: Vec4) -> (Endpoint, Endpoint, [u8; 16], f32) {
+    let (e0, e1) = (Endpoint::quantize(e0), Endpoint::quantize(e1));
+    let (indices, error) = pick_indices(points, &bc7_palette(e0, e1));
+    (e0, e1, indices, error)
+}
+
+fn encode_bc7_block(pixels: &[Vec4; 16]) -> [u8; 16] {
+    let (e0, e1) = fit_endpoints(pixels);
+    let mut best = bc7_candidate(pixels, e0, e1);
+    for _ in 0..2 {
+        let weights = best
+            .2
+            .map(|index| BC7_WEIGHTS[index as usize] as f32 / 64.0);
+        let Some((r0, r1)) = refine_endpoints(pixels, &weights) else {
+            break;
+        };
+        let candidate = bc7_candidate(pixels, r0, r1);
+        if candidate.3 >= best.3 {
+            break;
+        }
+        best = candidate;
+    }
+
+    let (mut e0, mut e1, mut indices, _) = best;
+    // the first index loses its top bit, so it has to be in the lower half
+    if indices[0] >= 8 {
+        (e0, e1) = (e1, e0);
+        indices = indices.map(|index| 15 - index);
+    }
+
+    let mut writer = BitWriter::default();
+    writer.write(1 << 6, 7);
+    for channel in 0..4 {
+        writer.write(e0.color[channel] as u128, 7);
+        writer.write(e1.color[channel] as u128, 7);
+    }
+    writer.write(e0.p_bit as u128, 1);
+    writer.write(e1.p_bit as u128, 1);
+    for (i, &index) in indices.iter().enumerate() {
+        writer.write(index as u128, if i == 0 { 3 } else { 4 });
+    }
+    writer.bits.to_le_bytes()
+}
+
+fn decode_bc7_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut reader = BitReader(u128::from_le_bytes(block.try_into().unwrap()), 0);
+    assert_eq!(reader.read(7), 1 << 6, "only mode 6 blocks are decoded");
+
+    let mut colors = [[0; 4]; 2];
+    for channel in 0..4 {
+        for color in &mut colors {
+            color[channel] = reader.read(7) as u8;
+        }
+    }
+    let e0 = Endpoint {
+        color: colors[0],
+        p_bit: reader.read(1) as u8,
+    };
+    let e1 = Endpoint {
+        color: colors[1],
+        p_bit: reader.read(1) as u8,
+    };
+    let palette = bc7_palette(e0, e1);
+    std::array::from_fn(|i| {
+        let index = reader.read(if i == 0 { 3 } else { 4 });
+        palette[index as usize].to_array().map(|c| c as u8)
+    })
+}
+
+/// Bits packed from the least significant one up, as BC7 blocks are laid out.
+#[derive(Default)]
+struct BitWriter {
+    bits: u128,
+    len: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u128, bits: u32) {
+        self.bits |= value << self.len;
+        self.len += bits;
+    }
+}
+
+struct BitReader(u128, u32);
+
+impl BitReader {
+    fn read(&mut self, bits: u32) -> u128 {
+        let value = (self.0 >> self.1) & ((1 << bits) - 1);
+        self.1 += bits;
+        value
+    }
+}
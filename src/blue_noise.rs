@@ -0,0 +1,129 @@
+//! Blue noise generated with Ulichney's void-and-cluster method: a tileable
+//! threshold map whose values are spread evenly, with no low frequencies, so
+//! that it reads as fine even grain rather than as blotches.
+
+use rand::{rngs::StdRng, Rng as _, SeedableRng as _};
+
+/// Spread of the Gaussian measuring how clustered pixels are.
+const SIGMA: f32 = 1.5;
+/// Fraction of pixels set in the initial pattern.
+const INITIAL_DENSITY: f32 = 0.1;
+
+/// A `size`×`size` tile of blue noise in row order, as values from 0 to 255
+/// used equally often.
+pub fn generate(size: usize, seed: u64) -> Vec<u8> {
+    let n = size * size;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut pattern = Pattern::new(size);
+
+    // random points, then moved from the tightest cluster into the largest
+    // void until that doesn't change anything
+    let initial = ((n as f32 * INITIAL_DENSITY) as usize).max(1);
+    while pattern.ones < initial {
+        let i = rng.gen_range(0..n);
+        if !pattern.set[i] {
+            pattern.toggle(i);
+        }
+    }
+    loop {
+        let cluster = pattern.tightest_cluster();
+        pattern.toggle(cluster);
+        let void = pattern.largest_void();
+        pattern.toggle(void);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; n];
+    let prototype = pattern.clone();
+
+    // the points of the prototype rank below it, the tightest clusters last
+    while pattern.ones > 0 {
+        let cluster = pattern.tightest_cluster();
+        pattern.toggle(cluster);
+        ranks[cluster] = pattern.ones;
+    }
+
+    // and the other pixels above it, filling the largest voids first
+    let mut pattern = prototype;
+    while pattern.ones < n {
+        let void = pattern.largest_void();
+        ranks[void] = pattern.ones;
+        pattern.toggle(void);
+    }
+
+    (ranks.into_iter())
+        .map(|rank| (rank * 256 / n) as u8)
+        .collect()
+}
+
+/// Binary pattern on a torus, with how close each pixel is to those set.
+#[derive(Clone)]
+struct Pattern {
+    size: usize,
+    set: Vec<bool>,
+    ones: usize,
+    /// Sum of the Gaussian of the distance to every set pixel.
+    energy: Vec<f32>,
+    /// Gaussian of the wrapped offset from one pixel to another.
+    kernel: Vec<f32>,
+}
+
+impl Pattern {
+    fn new(size: usize) -> Self {
+        let kernel = (0..size * size)
+            .map(|i| {
+                let wrapped = |d: usize| d.min(size - d) as f32;
+                let (dx, dy) = (wrapped(i % size), wrapped(i / size));
+                (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+            })
+            .collect();
+
+        Self {
+            size,
+            set: vec![false; size * size],
+            ones: 0,
+            energy: vec![0.0; size * size],
+            kernel,
+        }
+    }
+
+    fn toggle(&mut self, i: usize) {
+        self.set[i] = !self.set[i];
+        let sign = match self.set[i] {
+            true => {
+                self.ones += 1;
+                1.0
+            }
+            false => {
+                self.ones -= 1;
+                -1.0
+            }
+        };
+
+        let size = self.size;
+        let (x, y) = (i % size, i / size);
+        for (j, energy) in self.energy.iter_mut().enumerate() {
+            let dx = (j % size + size - x) % size;
+            let dy = (j / size + size - y) % size;
+            *energy += sign * self.kernel[dy * size + dx];
+        }
+    }
+
+    /// The set pixel with the most set pixels around it.
+    fn tightest_cluster(&self) -> usize {
+        (0..self.set.len())
+            .filter(|&i| self.set[i])
+            .max_by(|&a, &b| self.energy[a].total_cmp(&self.energy[b]))
+            .unwrap()
+    }
+
+    /// The unset pixel with the fewest set pixels around it.
+    fn largest_void(&self) -> usize {
+        (0..self.set.len())
+            .filter(|&i| !self.set[i])
+            .min_by(|&a, &b| self.energy[a].total_cmp(&self.energy[b]))
+            .unwrap()
+    }
+}
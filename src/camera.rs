@@ -1,6 +1,7 @@
-use glam::{Mat4, Vec2, Vec4, Vec4Swizzles};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Camera {
     pub position: Vec2,
     pub rotation: f32,
@@ -17,6 +18,21 @@ impl Default for Camera {
     }
 }
 
+/// How a camera projects the world onto the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Straight onto the plane of the scene, as with [`Camera::matrix`].
+    Orthographic,
+    /// In perspective, through the window as if it were a portal. `eye` is
+    /// where the viewer's head is relative to the center of the window in
+    /// pixels, `eye.z` being its distance in front of the screen.
+    ///
+    /// The plane at depth 0 lines up with the orthographic projection,
+    /// positive depths go behind the screen, up to `far` in world units, and
+    /// negative ones come out of it.
+    OffAxis { eye: Vec3, far: f32 },
+}
+
 impl Camera {
     /// Gets the real size of the viewport
     pub fn real_size(&self, viewport: Vec2) -> Vec2 {
@@ -31,33 +47,83 @@ impl Camera {
         self.real_size(viewport) / 2.0
     }
 
+    /// The point of the world at the center of the window.
+    pub fn center(&self) -> Vec2 {
+        -self.position
+    }
+
+    /// The same view of a world moved so that [`Self::center`] is its origin.
+    ///
+    /// Far from the origin, floats get too coarse to tell apart points a pixel
+    /// away once zoomed in. Positions taken relative to the center on the CPU
+    /// and drawn with this camera only lose as much precision as how far they
+    /// are from what is on screen.
+    pub fn centered(&self) -> Camera {
+        Camera {
+            position: Vec2::ZERO,
+            ..self.clone()
+        }
+    }
+
+    /// From the world to window pixels, y going down. Depth 0 in the world
+    /// ends up halfway through the depth range of [`Self::matrix`].
+    pub fn view(&self, viewport: Vec2) -> Mat4 {
+        Mat4::from_translation((viewport / 2.0).extend(0.0))
+            * Mat4::from_scale(self.scale.extend(1.0))
+            * Mat4::from_rotation_z(self.rotation)
+            * Mat4::from_translation(self.position.extend(-(u16::MAX as f32 / 2.0)))
+    }
+
     /// Convert screen pointer position to camera-corresponding position
     pub fn pointer_to_pos(&self, pointer: Vec2, viewport: Vec2) -> Vec2 {
-        let origin = self.center_offset(viewport);
-        let pos = self.position.extend(-(u16::MAX as f32 / 2.0));
+        let inverse = self.view(viewport).inverse();
+        inverse.transform_point3(pointer.extend(0.0)).truncate()
+    }
 
-        (
-			Mat4::from_translation(-pos)
-			* Mat4::from_translation(-origin.extend(0.0))
-			* Mat4::from_rotation_z(-self.rotation)
-			* Mat4::from_scale(1.0 / self.scale.extend(1.0))
-            * Vec4::new(pointer.x, pointer.y, 0.0, 1.0)
-		)
-        .xy()
+    /// Where a position in the world shows up on the screen, the inverse of
+    /// [`Self::pointer_to_pos`]
+    pub fn pos_to_pointer(&self, pos: Vec2, viewport: Vec2) -> Vec2 {
+        self.view(viewport)
+            .transform_point3(pos.extend(0.0))
+            .truncate()
     }
 
     /// Gets the resulting matrix from the camera and viewport
     pub fn matrix(&self, viewport: Vec2) -> Mat4 {
-        let real_size = self.real_size(viewport);
+        Mat4::orthographic_lh(0.0, viewport.x, viewport.y, 0.0, 0.0, u16::MAX as f32)
+            * self.view(viewport)
+    }
+
+    /// Gets the resulting matrix from the camera and viewport through
+    /// `projection`, for points with their depth in `z`
+    pub fn projection_matrix(&self, viewport: Vec2, projection: Projection) -> Mat4 {
+        let Projection::OffAxis { eye, far } = projection else {
+            return self.matrix(viewport);
+        };
 
-        // Faster to reuse real_size, so do that instead of calling get_center_offset
-        let origin = real_size / 2.0;
-        let pos = self.position.extend(-(u16::MAX as f32 / 2.0));
+        // the window and the eye in world units, from the center of the window
+        let half = self.center_offset(viewport);
+        let eye = eye / self.scale.extend(self.scale.x);
+        let near = eye.z * 0.1;
+        let far = eye.z + far;
 
-        // Return camera ortho matrix
-        Mat4::orthographic_lh(0.0, real_size.x, real_size.y, 0.0, 0.0, u16::MAX as f32)
-            * Mat4::from_translation(origin.extend(0.0))
+        // from the world to the eye, with x right, y down and z into the screen
+        let view = Mat4::from_translation(-eye.truncate().extend(-eye.z))
             * Mat4::from_rotation_z(self.rotation)
-            * Mat4::from_translation(pos)
+            * Mat4::from_translation(self.position.extend(0.0));
+
+        // a frustum from the eye through the edges of the window
+        let projection = Mat4::from_cols(
+            Vec4::new(eye.z / half.x, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, -eye.z / half.y, 0.0, 0.0),
+            Vec4::new(
+                eye.x / half.x,
+                -eye.y / half.y,
+                (far + near) / (far - near),
+                1.0,
+            ),
+            Vec4::new(0.0, 0.0, -2.0 * far * near / (far - near), 0.0),
+        );
+        projection * view
     }
 }
@@ -0,0 +1,386 @@
+//! Command-line arguments.
+
+use std::path::PathBuf;
+use std::process;
+
+use glam::{IVec2, UVec2};
+
+use crate::ab::Comparison;
+use crate::bindings::{self, Action, Chord, RepeatConfig};
+use crate::input_image::DEFAULT_MAX_DIMENSION;
+use crate::locale::Locale;
+use crate::output::OutputMode;
+use crate::placement;
+use crate::present::PresentMode;
+use crate::recorder;
+use crate::render_scale::{self, Upscale};
+use crate::scene_controller::ZoomLimits;
+use crate::surface::Compositing;
+
+const USAGE: &str = "\
+Usage: opengl-playground [OPTIONS]
+
+Options:
+  --scene <NAME>          Scene to start on, by the name shown in the help overlay (round-quads, kawase, ...)
+                          [default: kawase]
+  --image <FILE>          Image the scenes draw instead of the embedded Gura one, also loaded by dropping it
+  --size <WxH>            Size of the window in physical pixels, like 1280x720 [default: left to the platform]
+  --no-vsync              Present frames as soon as they're drawn instead of waiting for the monitor's refresh
+  --max-image-size <PX>   Downscale input images whose largest side exceeds PX [default: 4096]
+  --export-tiles <N>      Number of tiles per side for high-resolution exports [default: 4]
+  --sequence <FILE>       Play a scripted TOML timeline (see src/sequencer.rs)
+  --script <FILE>         Run a Rhai script on top of the scenes (see src/script.rs), with the scripting feature
+  --csv <FILE>            Table of numbers shown by the data mode of round-quads, also loaded by dropping it
+  --plugin <LIBRARY>      Load the scene of a shared library built against src/plugin.rs, can be repeated, with
+                          the plugins feature
+  --remote <PORT>         Accept JSON commands over TCP on PORT of this machine (see src/remote.rs)
+  --remote-lan            Accept remote commands from the whole network too, without any authentication
+  --midi-map <MAP>        MIDI CC to parameter mapping, with the midi feature [default: 1=radius,2=layers,3=kernel,4=dither]
+  --webcam <DEVICE>       Stream a webcam (index or /dev/video path) as the input image, with the webcam feature
+  --capture-screen <SRC>  Stream a monitor (0), window (0x3a00007) or region (1280x720+0+0) of the X11 screen as
+                          the input image, with the screen-capture feature
+  --hot-reload            Relink programs when their files in assets/shaders change, with the hot-reload feature
+  --seed <N>              Seed for everything random, so runs are reproducible [default: random]
+  --load-snapshot <FILE>  Restore the scene, parameters, camera, seed and input saved with F6 (not with --scene or --image)
+  --surface <MODE>        Whether the window is opaque or lets the desktop through its alpha (opaque, transparent)
+                          [default: opaque]
+  --output <MODE>         Experimental HDR output of the HDR scenes on a float surface where available (sdr, hdr)
+                          [default: sdr]
+  --present <MODE>        Draw scenes over the window (free), at a resolution scaled to fit it (fixed:320x180) or
+                          scaled up by whole pixels (integer:320x180) [default: free]
+  --render-scale <SCALE>  Draw scenes at this percentage of the resolution they're shown at, from 50 for heavy
+                          scenes to 200 for supersampling [default: 100]
+  --upscale <FILTER>      How scenes drawn below 100% are scaled back up (bilinear, bicubic, temporal)
+                          [default: bilinear]
+  --monitor <INDEX>       Open the window on this monitor, in the order the platform lists them, Ctrl+M moves it
+                          to the next one
+  --position <X,Y>        Open the window at this position in pixels, from the corner of --monitor if given or of
+                          the desktop [default: centered on --monitor, left to the platform without it]
+  --locale <CODE>         Language of the on-screen text (en, ja) [default: from LANG]
+  --watchdog <MS>         Report frames taking longer than MS, 0 turns it off [default: 500]
+  --watchdog-diagnose     Wait for the GPU around every pass, so that long frames report which passes took the time
+  --zoom-limits <MIN,MAX> Farthest and closest zoom, as multiples of the default one [default: 0.01,256]
+  --mouse-smoothing <MS>  Time the position scenes react to takes to follow the mouse, 0 follows it exactly [default: 0]
+  --key-repeat <PACE>     Delay, first and fastest interval in ms of held keys, or 'off' for the OS repeat [default: 300,100,20]
+  --bind <CHORD=ACTION>   Bind a chord like Ctrl+S to an action like snapshot, can be repeated
+  --record-input <FILE>   Record the frames and input events of the session to FILE (see src/session.rs)
+  --replay-input <FILE>   Replay a recorded session with its frame lengths, then report how long frames took (not with --scene or --image)
+  --replay-buffer <SECS>  Keep the last SECS of frames downscaled in memory, saved as PNGs with Ctrl+Shift+R [default: off]
+  --record-format <FMT>   What Ctrl+Shift+V records every frame to, numbered PNGs or an MP4 through ffmpeg (png, ffmpeg)
+                          [default: png]
+  --ab <COMPARISON>       Compare two variants of a scene: a registered one by scene (cloth, culling, ...) or
+                          SCENE.PARAM=A,B, reporting paired CPU/GPU timings (see src/ab.rs)
+  --ab-frames <N>         Frames per block when alternating the variants [default: 30]
+  --ab-split              Draw A in the left half and B in the right one every frame instead of alternating
+  --check-gl-state        Report scenes leaving GL state other than the one they declare (see src/render_state.rs)
+  --validate-shaders      Compile every shader permutation on a hidden context, report errors and exit
+  --thumbnails <DIR>      Render a labeled thumbnail of every scene to DIR as <scene>.png and exit
+  -h, --help              Print this help
+";
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    /// Scene shown first, kawase like `Scenes::new` if `None`.
+    pub scene: Option<String>,
+    /// Input image in place of the embedded one.
+    pub image: Option<PathBuf>,
+    /// Size the window opens at.
+    pub size: Option<UVec2>,
+    /// Wait for vertical blanks between frames.
+    pub vsync: bool,
+    /// Largest side allowed for input images before they get downscaled.
+    /// It is additionally clamped to `GL_MAX_TEXTURE_SIZE` at runtime.
+    pub max_image_size: u32,
+    /// High-resolution exports are `export_tiles`×`export_tiles` windows large.
+    pub export_tiles: u32,
+    /// Timeline to play as an unattended demo.
+    pub sequence: Option<PathBuf>,
+    /// Script run on top of the scenes.
+    #[cfg(feature = "scripting")]
+    pub script: Option<PathBuf>,
+    /// Table shown by the data mode of the round quads.
+    pub csv: Option<PathBuf>,
+    /// Shared libraries whose scenes join the built-in ones.
+    #[cfg(feature = "plugins")]
+    pub plugins: Vec<PathBuf>,
+    /// Port of the remote control server, disabled if `None`.
+    pub remote: Option<u16>,
+    /// Whether the remote control listens on every interface rather than
+    /// only the loopback one.
+    pub remote_lan: bool,
+    /// Seed for scene construction, picked at random if `None`.
+    pub seed: Option<u64>,
+    /// Snapshot to restore on startup.
+    pub load_snapshot: Option<PathBuf>,
+    /// Whether the window lets what's behind it through.
+    pub surface: Compositing,
+    /// Range the window outputs, falling back to SDR without a float config.
+    pub output: OutputMode,
+    /// How scenes fill the window.
+    pub present: PresentMode,
+    /// Ratio of the resolution scenes draw at to the one they're shown at.
+    pub render_scale: f32,
+    /// How scenes drawn below their resolution are scaled back up.
+    pub upscale: Upscale,
+    /// Monitor the window opens on.
+    pub monitor: Option<usize>,
+    /// Where the window opens, relative to [`Self::monitor`] if any.
+    pub position: Option<IVec2>,
+    /// Language of the on-screen text, taken from the environment if `None`.
+    pub locale: Option<Locale>,
+    /// Frames taking longer are reported, unless it's zero.
+    pub watchdog_ms: u64,
+    /// Bracket passes with `glFinish` to time each of them.
+    pub watchdog_diagnose: bool,
+    /// How far the cameras zoom out and in.
+    pub zoom_limits: ZoomLimits,
+    /// Time constant of the smoothed mouse position, in milliseconds.
+    pub mouse_smoothing_ms: f64,
+    /// Pace of held keys, `None` to leave it to the OS.
+    pub key_repeat: Option<RepeatConfig>,
+    /// Bindings on top of the default ones.
+    pub bindings: Vec<(Chord, Action)>,
+    /// Where to record the input session.
+    pub record_input: Option<PathBuf>,
+    /// Recorded input session to replay.
+    pub replay_input: Option<PathBuf>,
+    /// Seconds of frames kept to be saved on demand, disabled if `None`.
+    pub replay_seconds: Option<f32>,
+    /// Where recordings of every frame go.
+    pub record_format: recorder::Format,
+    /// Variants of a scene to measure against each other.
+    pub ab: Option<Comparison>,
+    /// Frames of each variant in a row when alternating them.
+    pub ab_frames: u32,
+    /// Draw both variants every frame, side by side.
+    pub ab_split: bool,
+    /// Check after each draw that the scene left the state it declared.
+    pub check_gl_state: bool,
+    /// Only validate the shaders, without opening a window.
+    pub validate_shaders: bool,
+    /// Only render thumbnails of the scenes there, without opening a window.
+    pub thumbnails: Option<PathBuf>,
+    /// Which MIDI controller drives which parameter, as `cc=param,...`.
+    #[cfg(feature = "midi")]
+    pub midi_mapping: crate::midi::Mapping,
+    /// Webcam streamed as the input image, as an index or a device path.
+    #[cfg(feature = "webcam")]
+    pub webcam: Option<String>,
+    /// Part of the screen streamed as the input image.
+    #[cfg(feature = "screen-capture")]
+    pub capture_screen: Option<crate::screen_capture::Source>,
+    /// Watch the shader files and relink the programs made from them.
+    #[cfg(feature = "hot-reload")]
+    pub hot_reload: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            scene: None,
+            image: None,
+            size: None,
+            vsync: true,
+            max_image_size: DEFAULT_MAX_DIMENSION,
+            export_tiles: 4,
+            sequence: None,
+            #[cfg(feature = "scripting")]
+            script: None,
+            csv: None,
+            #[cfg(feature = "plugins")]
+            plugins: Vec::new(),
+            remote: None,
+            remote_lan: false,
+            seed: None,
+            load_snapshot: None,
+            surface: Compositing::default(),
+            output: OutputMode::default(),
+            present: PresentMode::default(),
+            render_scale: 1.0,
+            upscale: Upscale::default(),
+            monitor: None,
+            position: None,
+            locale: None,
+            watchdog_ms: crate::watchdog::DEFAULT_THRESHOLD_MS,
+            watchdog_diagnose: false,
+            zoom_limits: ZoomLimits::default(),
+            mouse_smoothing_ms: crate::input::DEFAULT_SMOOTHING_MS,
+            key_repeat: Some(RepeatConfig::default()),
+            bindings: Vec::new(),
+            record_input: None,
+            replay_input: None,
+            replay_seconds: None,
+            record_format: recorder::Format::default(),
+            ab: None,
+            ab_frames: 30,
+            ab_split: false,
+            check_gl_state: false,
+            validate_shaders: false,
+            thumbnails: None,
+            #[cfg(feature = "midi")]
+            midi_mapping: crate::midi::parse_mapping(crate::midi::DEFAULT_MAPPING).unwrap(),
+            #[cfg(feature = "webcam")]
+            webcam: None,
+            #[cfg(feature = "screen-capture")]
+            capture_screen: None,
+            #[cfg(feature = "hot-reload")]
+            hot_reload: false,
+        }
+    }
+}
+
+impl Args {
+    pub fn parse() -> Self {
+        let mut args = Self::default();
+        let mut argv = std::env::args().skip(1);
+
+        while let Some(arg) = argv.next() {
+            match arg.as_str() {
+                "--scene" => args.scene = Some(parse_value(&arg, argv.next())),
+                "--image" => args.image = Some(parse_value(&arg, argv.next())),
+                "--size" => {
+                    let size: String = parse_value(&arg, argv.next());
+                    args.size = Some(
+                        placement::parse_size(&size)
+                            .unwrap_or_else(|e| fail(&format!("invalid window size: {e}"))),
+                    );
+                }
+                "--no-vsync" => args.vsync = false,
+                "--max-image-size" => args.max_image_size = parse_value(&arg, argv.next()),
+                "--export-tiles" => {
+                    args.export_tiles = parse_value::<u32>(&arg, argv.next()).max(1)
+                }
+                "--sequence" => args.sequence = Some(parse_value(&arg, argv.next())),
+                #[cfg(feature = "scripting")]
+                "--script" => args.script = Some(parse_value(&arg, argv.next())),
+                "--csv" => args.csv = Some(parse_value(&arg, argv.next())),
+                #[cfg(feature = "plugins")]
+                "--plugin" => args.plugins.push(parse_value(&arg, argv.next())),
+                "--seed" => args.seed = Some(parse_value(&arg, argv.next())),
+                "--load-snapshot" => args.load_snapshot = Some(parse_value(&arg, argv.next())),
+                "--surface" => args.surface = parse_value(&arg, argv.next()),
+                "--output" => args.output = parse_value(&arg, argv.next()),
+                "--present" => {
+                    let mode: String = parse_value(&arg, argv.next());
+                    args.present = mode
+                        .parse()
+                        .unwrap_or_else(|e| fail(&format!("invalid presentation '{mode}': {e}")));
+                }
+                "--render-scale" => {
+                    let scale: String = parse_value(&arg, argv.next());
+                    args.render_scale = render_scale::parse_scale(&scale)
+                        .unwrap_or_else(|e| fail(&format!("invalid render scale: {e}")));
+                }
+                "--upscale" => {
+                    let upscale: String = parse_value(&arg, argv.next());
+                    args.upscale = upscale
+                        .parse()
+                        .unwrap_or_else(|e| fail(&format!("invalid upscale filter: {e}")));
+                }
+                "--monitor" => args.monitor = Some(parse_value(&arg, argv.next())),
+                "--position" => {
+                    let position: String = parse_value(&arg, argv.next());
+                    args.position = Some(
+                        placement::parse_position(&position)
+                            .unwrap_or_else(|e| fail(&format!("invalid position: {e}"))),
+                    );
+                }
+                "--locale" => args.locale = Some(parse_value(&arg, argv.next())),
+                "--watchdog" => args.watchdog_ms = parse_value(&arg, argv.next()),
+                "--watchdog-diagnose" => args.watchdog_diagnose = true,
+                "--zoom-limits" => {
+                    let limits: String = parse_value(&arg, argv.next());
+                    args.zoom_limits = limits
+                        .parse()
+                        .unwrap_or_else(|e| fail(&format!("invalid zoom limits '{limits}': {e}")));
+                }
+                "--mouse-smoothing" => args.mouse_smoothing_ms = parse_value(&arg, argv.next()),
+                "--key-repeat" => {
+                    let pace: String = parse_value(&arg, argv.next());
+                    args.key_repeat = match pace.as_str() {
+                        "off" => None,
+                        pace => Some(pace.parse().unwrap_or_else(|e| {
+                            fail(&format!("invalid key repeat '{pace}': {e}"))
+                        })),
+                    };
+                }
+                "--bind" => {
+                    let binding: String = parse_value(&arg, argv.next());
+                    let binding = bindings::parse_binding(&binding)
+                        .unwrap_or_else(|e| fail(&format!("invalid binding '{binding}': {e}")));
+                    args.bindings.push(binding);
+                }
+                "--record-input" => args.record_input = Some(parse_value(&arg, argv.next())),
+                "--replay-input" => args.replay_input = Some(parse_value(&arg, argv.next())),
+                "--replay-buffer" => {
+                    let seconds: f32 = parse_value(&arg, argv.next());
+                    args.replay_seconds = (seconds > 0.0).then_some(seconds);
+                }
+                "--record-format" => {
+                    let format: String = parse_value(&arg, argv.next());
+                    args.record_format = format.parse().unwrap_or_else(|e| {
+                        fail(&format!("invalid record format '{format}': {e}"))
+                    });
+                }
+                "--ab" => {
+                    let comparison: String = parse_value(&arg, argv.next());
+                    args.ab = Some(comparison.parse().unwrap_or_else(|e| {
+                        fail(&format!("invalid comparison '{comparison}': {e}"))
+                    }));
+                }
+                "--ab-frames" => args.ab_frames = parse_value(&arg, argv.next()),
+                "--ab-split" => args.ab_split = true,
+                "--check-gl-state" => args.check_gl_state = true,
+                "--validate-shaders" => args.validate_shaders = true,
+                "--thumbnails" => args.thumbnails = Some(parse_value(&arg, argv.next())),
+                "--remote" => args.remote = Some(parse_value(&arg, argv.next())),
+                "--remote-lan" => args.remote_lan = true,
+                #[cfg(feature = "midi")]
+                "--midi-map" => {
+                    let mapping: String = parse_value(&arg, argv.next());
+                    args.midi_mapping = crate::midi::parse_mapping(&mapping)
+                        .unwrap_or_else(|e| fail(&format!("invalid MIDI mapping: {e}")));
+                }
+                #[cfg(feature = "webcam")]
+                "--webcam" => args.webcam = Some(parse_value(&arg, argv.next())),
+                #[cfg(feature = "screen-capture")]
+                "--capture-screen" => args.capture_screen = Some(parse_value(&arg, argv.next())),
+                #[cfg(feature = "hot-reload")]
+                "--hot-reload" => args.hot_reload = true,
+                "-h" | "--help" => {
+                    print!("{USAGE}");
+                    process::exit(0);
+                }
+                _ => fail(&format!("unknown argument '{arg}'")),
+            }
+        }
+
+        // both start from the scene and image they were made with
+        let restoring = (args.load_snapshot.is_some().then_some("--load-snapshot"))
+            .or(args.replay_input.is_some().then_some("--replay-input"));
+        let overriding = (args.scene.is_some().then_some("--scene"))
+            .or(args.image.is_some().then_some("--image"));
+        if let (Some(restoring), Some(overriding)) = (restoring, overriding) {
+            fail(&format!(
+                "'{overriding}' can't be used with '{restoring}', which restores its own"
+            ));
+        }
+
+        args
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: Option<String>) -> T {
+    let Some(value) = value else {
+        fail(&format!("missing value for '{name}'"));
+    };
+
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("invalid value '{value}' for '{name}'")))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("Error: {message}\n\n{USAGE}");
+    process::exit(2);
+}
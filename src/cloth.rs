@@ -0,0 +1,153 @@
+//! Cloth as a grid of particles held together by distance constraints, after
+//! Jakobsen's "Advanced Character Physics": particles move with Verlet
+//! integration, then every constraint is pulled back toward its rest length a
+//! few times over, one after the other.
+//!
+//! Neighbours along the grid keep it from stretching, diagonal ones from
+//! shearing, and those two apart from folding too sharply.
+
+use glam::{Vec2, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    /// Between neighbours along a row or a column.
+    Structural,
+    /// Between diagonal neighbours.
+    Shear,
+    /// Between particles two apart along a row or a column.
+    Bend,
+}
+
+/// Two particles kept `rest` apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constraint {
+    pub a: u32,
+    pub b: u32,
+    pub rest: f32,
+    pub kind: ConstraintKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cloth {
+    pub columns: usize,
+    pub rows: usize,
+    /// Row by row, from the first one.
+    pub positions: Vec<Vec3>,
+    /// Positions at the previous step, which make up the velocities.
+    pub previous: Vec<Vec3>,
+    /// 0 for pinned particles, which nothing moves.
+    pub inverse_masses: Vec<f32>,
+    pub constraints: Vec<Constraint>,
+}
+
+impl Cloth {
+    /// A flat cloth at rest, from `origin` along +x for the columns and +y
+    /// for the rows, with nothing pinned.
+    pub fn grid(columns: usize, rows: usize, origin: Vec3, spacing: f32) -> Self {
+        let positions = (0..rows)
+            .flat_map(|y| (0..columns).map(move |x| (x, y)))
+            .map(|(x, y)| origin + Vec3::new(x as f32, y as f32, 0.0) * spacing)
+            .collect::<Vec<_>>();
+
+        let mut constraints = Vec::new();
+        let index = |x: usize, y: usize| (y * columns + x) as u32;
+        for y in 0..rows {
+            for x in 0..columns {
+                let mut link = |dx: usize, dy: usize, kind: ConstraintKind| {
+                    if x + dx < columns && y + dy < rows {
+                        constraints.push(Constraint {
+                            a: index(x, y),
+                            b: index(x + dx, y + dy),
+                            rest: spacing * ((dx * dx + dy * dy) as f32).sqrt(),
+                            kind,
+                        });
+                    }
+                };
+                link(1, 0, ConstraintKind::Structural);
+                link(0, 1, ConstraintKind::Structural);
+                link(1, 1, ConstraintKind::Shear);
+                link(2, 0, ConstraintKind::Bend);
+                link(0, 2, ConstraintKind::Bend);
+            }
+            // the other diagonal, which goes back a column
+            for x in 1..columns {
+                if y + 1 < rows {
+                    constraints.push(Constraint {
+                        a: index(x, y),
+                        b: index(x - 1, y + 1),
+                        rest: spacing * 2f32.sqrt(),
+                        kind: ConstraintKind::Shear,
+                    });
+                }
+            }
+        }
+
+        Self {
+            columns,
+            rows,
+            previous: positions.clone(),
+            inverse_masses: vec![1.0; positions.len()],
+            positions,
+            constraints,
+        }
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.columns + x
+    }
+
+    pub fn pin(&mut self, i: usize) {
+        self.inverse_masses[i] = 0.0;
+    }
+
+    /// Moves every free particle along its velocity and by `acceleration`
+    /// over `dt`, losing `damping` of its velocity.
+    pub fn integrate(&mut self, dt: f32, damping: f32, acceleration: impl Fn(Vec3) -> Vec3) {
+        let particles = (self.positions.iter_mut())
+            .zip(&mut self.previous)
+            .zip(&self.inverse_masses);
+        for ((position, previous), &inverse_mass) in particles {
+            if inverse_mass == 0.0 {
+                continue;
+            }
+            let velocity = (*position - *previous) * (1.0 - damping);
+            *previous = *position;
+            *position += velocity + acceleration(*position) * dt * dt;
+        }
+    }
+
+    /// Pulls every constraint back toward its rest length, `iterations`
+    /// times over. Each kind only goes `stiffness` of the way at a time.
+    pub fn relax(&mut self, iterations: usize, stiffness: impl Fn(ConstraintKind) -> f32) {
+        for _ in 0..iterations {
+            for constraint in &self.constraints {
+                let (a, b) = (constraint.a as usize, constraint.b as usize);
+                let (wa, wb) = (self.inverse_masses[a], self.inverse_masses[b]);
+                if wa + wb == 0.0 {
+                    continue;
+                }
+
+                let delta = self.positions[b] - self.positions[a];
+                let length = delta.length();
+                if length < f32::EPSILON {
+                    continue;
+                }
+                let correction = delta
+                    * ((length - constraint.rest) / (length * (wa + wb)))
+                    * stiffness(constraint.kind);
+                self.positions[a] += correction * wa;
+                self.positions[b] -= correction * wb;
+            }
+        }
+    }
+
+    /// Index of the particle closest to `target` on the xy plane, if any is
+    /// within `radius`.
+    pub fn nearest(&self, target: Vec2, radius: f32) -> Option<usize> {
+        (self.positions.iter().enumerate())
+            .map(|(i, p)| (i, p.truncate().distance_squared(target)))
+            .filter(|&(_, distance_squared)| distance_squared <= radius * radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+}
@@ -1,18 +1,32 @@
 // come on it's just OpenGL
 #![allow(clippy::missing_safety_doc)]
 
-use std::ffi::CStr;
+use std::ffi::{c_void, CStr};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, Range};
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use gl::types::{GLchar, GLenum, GLint, GLsizei, GLuint};
-use glam::UVec2;
+use gl::types::{GLboolean, GLchar, GLenum, GLint, GLsizei, GLsizeiptr, GLuint};
+use glam::{uvec2, Mat4, UVec2, Vec2, Vec3};
+
+use crate::error::GlError;
+use crate::gl_ext;
+use crate::gpu_memory::{self, Category, Object};
+use crate::input_image::InputImage;
+use crate::profiling;
+use crate::render_state;
+use crate::watchdog;
 
 // --- debugging ---
 
 // Set in main when checking for the GL_KHR_debug extension.
 pub static DEBUG_ENABLED: AtomicBool = AtomicBool::new(false);
 
-pub unsafe fn push_debug_group(message: &CStr) {
+/// Names the following commands in debuggers like RenderDoc, and the pass
+/// being run for the [watchdog](crate::watchdog).
+pub unsafe fn push_debug_group(message: &'static CStr) {
+    watchdog::enter_pass(message);
     if DEBUG_ENABLED.load(Ordering::Relaxed) {
         gl::PushDebugGroup(
             gl::DEBUG_SOURCE_APPLICATION,
@@ -27,28 +41,35 @@ pub unsafe fn pop_debug_group() {
     if DEBUG_ENABLED.load(Ordering::Relaxed) {
         gl::PopDebugGroup();
     }
+    watchdog::leave_pass();
 }
 
 // --- shader compilation ---
 
-pub unsafe fn create_shader_program(vert_source: &[u8], frag_source: &[u8]) -> GLuint {
-    let vert_shader = gl::CreateShader(gl::VERTEX_SHADER);
-    {
-        let length = vert_source.len() as i32;
-        let source = vert_source.as_ptr() as *const i8;
-        gl::ShaderSource(vert_shader, 1, &source, &length);
-        gl::CompileShader(vert_shader);
-    }
-    verify_shader(vert_shader, "vert");
+pub unsafe fn create_shader_program(
+    vert_source: &[u8],
+    frag_source: &[u8],
+) -> Result<GLuint, GlError> {
+    // without glPolygonMode, both stages draw the debug polygon modes
+    let wrapped = match render_state::is_gles() {
+        true => render_state::barycentric_source(vert_source, gl::VERTEX_SHADER).zip(
+            render_state::barycentric_source(frag_source, gl::FRAGMENT_SHADER),
+        ),
+        false => None,
+    };
+    let (vert_compiled, frag_compiled) = match &wrapped {
+        Some((vert, frag)) => (vert.as_slice(), frag.as_slice()),
+        None => (vert_source, frag_source),
+    };
 
-    let frag_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
-    {
-        let length = frag_source.len() as i32;
-        let source = frag_source.as_ptr() as *const i8;
-        gl::ShaderSource(frag_shader, 1, &source, &length);
-        gl::CompileShader(frag_shader);
-    }
-    verify_shader(frag_shader, "frag");
+    let vert_shader = compile_shader(gl::VERTEX_SHADER, vert_compiled, "vert")?;
+    let frag_shader = match compile_shader(gl::FRAGMENT_SHADER, frag_compiled, "frag") {
+        Ok(shader) => shader,
+        Err(e) => {
+            gl::DeleteShader(vert_shader);
+            return Err(e);
+        }
+    };
 
     let program = gl::CreateProgram();
     {
@@ -56,51 +77,567 @@ pub unsafe fn create_shader_program(vert_source: &[u8], frag_source: &[u8]) -> G
         gl::AttachShader(program, frag_shader);
 
         gl::LinkProgram(program);
-        gl::UseProgram(program);
 
         gl::DeleteShader(vert_shader);
         gl::DeleteShader(frag_shader);
     }
-    verify_program(program);
+    verify_program(program)?;
+    gl::UseProgram(program);
+
+    if wrapped.is_some() {
+        let block = gl::GetUniformBlockIndex(program, c"DebugPolygonMode".as_ptr());
+        gl::UniformBlockBinding(program, block, render_state::DEBUG_BINDING);
+    }
+
+    #[cfg(feature = "hot-reload")]
+    hot_reload::track(
+        program,
+        &[
+            (gl::VERTEX_SHADER, "vert", vert_source),
+            (gl::FRAGMENT_SHADER, "frag", frag_source),
+        ],
+    );
+
+    Ok(program)
+}
+
+/// Creates a vertex-only program whose `varyings` get captured interleaved
+/// into a single transform feedback buffer.
+pub unsafe fn create_transform_feedback_program(
+    vert_source: &[u8],
+    varyings: &[&CStr],
+) -> Result<GLuint, GlError> {
+    let vert_shader = compile_shader(gl::VERTEX_SHADER, vert_source, "vert")?;
+
+    let program = gl::CreateProgram();
+    {
+        let varyings = varyings.iter().map(|v| v.as_ptr()).collect::<Vec<_>>();
+        gl::TransformFeedbackVaryings(
+            program,
+            varyings.len() as GLsizei,
+            varyings.as_ptr(),
+            gl::INTERLEAVED_ATTRIBS,
+        );
+
+        gl::AttachShader(program, vert_shader);
+        gl::LinkProgram(program);
+        gl::DeleteShader(vert_shader);
+    }
+    verify_program(program)?;
+
+    // relinking would lose the varyings, but the name may have been that of
+    // a tracked program deleted since
+    #[cfg(feature = "hot-reload")]
+    hot_reload::forget(program);
+
+    Ok(program)
+}
+
+/// Creates a program with a geometry shader between the vertex and fragment
+/// stages.
+pub unsafe fn create_geometry_shader_program(
+    vert_source: &[u8],
+    geom_source: &[u8],
+    frag_source: &[u8],
+) -> Result<GLuint, GlError> {
+    let stages = [
+        (gl::VERTEX_SHADER, vert_source, "vert"),
+        (gl::GEOMETRY_SHADER, geom_source, "geom"),
+        (gl::FRAGMENT_SHADER, frag_source, "frag"),
+    ];
+
+    let program = gl::CreateProgram();
+    for (stage, source, ty) in stages {
+        let shader = match compile_shader(stage, source, ty) {
+            Ok(shader) => shader,
+            Err(e) => {
+                gl::DeleteProgram(program);
+                return Err(e);
+            }
+        };
+        gl::AttachShader(program, shader);
+        gl::DeleteShader(shader);
+    }
+
+    gl::LinkProgram(program);
+    verify_program(program)?;
 
-    program
+    #[cfg(feature = "hot-reload")]
+    hot_reload::track(
+        program,
+        &stages.map(|(stage, source, ty)| (stage, ty, source)),
+    );
+
+    Ok(program)
 }
 
-pub unsafe fn verify_shader(shader: GLuint, ty: &str) {
+/// Creates a program made of a single compute shader. Check
+/// [`supports_compute`] first.
+pub unsafe fn create_compute_program(comp_source: &[u8]) -> Result<GLuint, GlError> {
+    let comp_shader = compile_shader(gl::COMPUTE_SHADER, comp_source, "comp")?;
+
+    let program = gl::CreateProgram();
+    {
+        gl::AttachShader(program, comp_shader);
+        gl::LinkProgram(program);
+        gl::DeleteShader(comp_shader);
+    }
+    verify_program(program)?;
+
+    #[cfg(feature = "hot-reload")]
+    hot_reload::track(program, &[(gl::COMPUTE_SHADER, "comp", comp_source)]);
+
+    Ok(program)
+}
+
+/// Compiles a shader of type `ty`, called `stage` in errors.
+unsafe fn compile_shader(
+    ty: GLenum,
+    source: &[u8],
+    stage: &'static str,
+) -> Result<GLuint, GlError> {
+    let shader = gl::CreateShader(ty);
+    {
+        let length = source.len() as i32;
+        let source = source.as_ptr() as *const i8;
+        gl::ShaderSource(shader, 1, &source, &length);
+        gl::CompileShader(shader);
+    }
+    verify_shader(shader, stage).inspect_err(|_| gl::DeleteShader(shader))?;
+    Ok(shader)
+}
+
+pub unsafe fn verify_shader(shader: GLuint, stage: &'static str) -> Result<(), GlError> {
     let mut status = 0;
     gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
 
-    if status != 1 {
-        let mut length = 0;
-        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut length);
+    match status {
+        1 => Ok(()),
+        _ => Err(GlError::Compile {
+            stage,
+            log: shader_info_log(shader),
+        }),
+    }
+}
 
-        if length > 0 {
-            let mut log = String::with_capacity(length as usize);
-            log.extend(std::iter::repeat('\0').take(length as usize));
-            gl::GetShaderInfoLog(shader, length, &mut length, log.as_mut_ptr().cast());
-            log.truncate(length as usize);
+pub unsafe fn shader_info_log(shader: GLuint) -> String {
+    let mut length = 0;
+    gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut length);
 
-            eprintln!("SHADER COMPILE ERROR ({ty}): {log}");
-        }
+    let mut log = String::with_capacity(length as usize);
+    if length > 0 {
+        log.extend(std::iter::repeat_n('\0', length as usize));
+        gl::GetShaderInfoLog(shader, length, &mut length, log.as_mut_ptr().cast());
+        log.truncate(length as usize);
     }
+
+    log
 }
 
-pub unsafe fn verify_program(shader: GLuint) {
+/// Checks that a program linked, deleting it if it didn't.
+pub unsafe fn verify_program(program: GLuint) -> Result<(), GlError> {
     let mut status = 0;
-    gl::GetProgramiv(shader, gl::LINK_STATUS, &mut status);
+    gl::GetProgramiv(program, gl::LINK_STATUS, &mut status);
+    if status == 1 {
+        return Ok(());
+    }
+
+    let mut length = 0;
+    gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut length);
+
+    let mut log = String::with_capacity(length as usize);
+    if length > 0 {
+        log.extend(std::iter::repeat_n('\0', length as usize));
+        gl::GetProgramInfoLog(program, length, &mut length, log.as_mut_ptr().cast());
+        log.truncate(length as usize);
+    }
+    gl::DeleteProgram(program);
+
+    Err(GlError::Link { log })
+}
+
+// --- hot reloading ---
+
+#[cfg(feature = "hot-reload")]
+pub use hot_reload::{reload_shaders, watch_shaders, Reload};
+
+/// Programs compiled again from their files when those change, with the
+/// `hot-reload` feature.
+///
+/// Shaders are embedded with `include_bytes!`, so the programs made from them
+/// don't know which files they came from: once [`watch_shaders`] is called,
+/// the sources of every program created are looked up among the contents of
+/// the watched files. A program is then relinked in place, under the same
+/// name, so that scenes holding it draw with the new code right away, and
+/// the values of its uniforms are carried over since relinking resets them.
+/// Programs that don't compile or link keep running the old code.
+///
+/// Scenes look up uniform and attribute locations once, and adding or
+/// removing some can move the others; switching away and back to the scene
+/// looks them up again.
+#[cfg(feature = "hot-reload")]
+mod hot_reload {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::ffi::{CStr, CString};
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{self, Receiver};
+    use std::{fs, io};
+
+    use gl::types::{GLenum, GLint, GLsizei, GLuint};
+    use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+    use super::{compile_shader, verify_program};
+    use crate::error::GlError;
+
+    /// A stage of a tracked program.
+    struct Stage {
+        ty: GLenum,
+        /// What the stage is called in errors, like `frag`.
+        name: &'static str,
+        source: Vec<u8>,
+        /// The watched file the source came from, if it came from one.
+        path: Option<PathBuf>,
+    }
+
+    struct Watch {
+        _watcher: RecommendedWatcher,
+        events: Receiver<notify::Result<notify::Event>>,
+        /// Watched files by their contents when watching started.
+        files: HashMap<Vec<u8>, PathBuf>,
+        programs: HashMap<GLuint, Vec<Stage>>,
+    }
+
+    thread_local! {
+        static WATCH: RefCell<Option<Watch>> = const { RefCell::new(None) };
+    }
+
+    /// A watched file that changed, and how many programs were relinked with
+    /// it or why they couldn't be.
+    #[derive(Debug)]
+    pub struct Reload {
+        pub path: PathBuf,
+        pub result: Result<usize, GlError>,
+    }
+
+    /// Watches the shaders in `dir`, and tracks the programs created from
+    /// then on to reload them with [`reload_shaders`].
+    pub fn watch_shaders(dir: &Path) -> io::Result<()> {
+        let mut files = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.insert(fs::read(&path)?, path);
+            }
+        }
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(io::Error::other)?;
+        (watcher.watch(dir, RecursiveMode::NonRecursive)).map_err(io::Error::other)?;
+
+        WATCH.set(Some(Watch {
+            _watcher: watcher,
+            events,
+            files,
+            programs: HashMap::new(),
+        }));
+        Ok(())
+    }
+
+    /// Remembers the stages of `program`, if some come from watched files.
+    /// Whatever was tracked under the same name before belonged to a program
+    /// deleted since, and is forgotten either way.
+    pub(super) fn track(program: GLuint, stages: &[(GLenum, &'static str, &[u8])]) {
+        forget(program);
+        WATCH.with_borrow_mut(|watch| {
+            let Some(watch) = watch else {
+                return;
+            };
+            let stages = (stages.iter())
+                .map(|&(ty, name, source)| Stage {
+                    ty,
+                    name,
+                    source: source.to_vec(),
+                    path: watch.files.get(source).cloned(),
+                })
+                .collect::<Vec<_>>();
+            if stages.iter().any(|stage| stage.path.is_some()) {
+                watch.programs.insert(program, stages);
+            }
+        });
+    }
+
+    /// Stops tracking `program`, which is deleted or must not be relinked.
+    pub(super) fn forget(program: GLuint) {
+        WATCH.with_borrow_mut(|watch| {
+            if let Some(watch) = watch {
+                watch.programs.remove(&program);
+            }
+        });
+    }
+
+    /// Relinks the programs using the watched files that changed since the
+    /// last call. Call it once per frame, with the context current.
+    pub unsafe fn reload_shaders() -> Vec<Reload> {
+        WATCH.with_borrow_mut(|watch| {
+            let Some(watch) = watch else {
+                return Vec::new();
+            };
+
+            let mut changed = Vec::<PathBuf>::new();
+            for event in watch.events.try_iter().flatten() {
+                if let EventKind::Create(_) | EventKind::Modify(_) = event.kind {
+                    for path in event.paths {
+                        if !changed.contains(&path) {
+                            changed.push(path);
+                        }
+                    }
+                }
+            }
+
+            // programs deleted since they were created
+            watch
+                .programs
+                .retain(|&program, _| gl::IsProgram(program) == gl::TRUE);
+
+            let mut reloads = Vec::new();
+            for path in changed {
+                // editors save in several steps, the last of which has it all
+                let Ok(source) = fs::read(&path) else {
+                    continue;
+                };
+                let mut relinked = 0;
+                let mut result = Ok(());
+                for (&program, stages) in &mut watch.programs {
+                    let uses = |stage: &Stage| {
+                        let file = stage.path.as_ref().and_then(|p| p.file_name());
+                        file.is_some() && file == path.file_name()
+                    };
+                    if !stages
+                        .iter()
+                        .any(|stage| uses(stage) && stage.source != source)
+                    {
+                        continue;
+                    }
+                    for stage in stages.iter_mut().filter(|stage| uses(stage)) {
+                        stage.source = source.clone();
+                    }
+                    match relink(program, stages) {
+                        Ok(()) => relinked += 1,
+                        Err(e) => result = Err(e),
+                    }
+                }
+                if relinked > 0 || result.is_err() {
+                    let result = result.map(|()| relinked);
+                    reloads.push(Reload { path, result });
+                }
+            }
+            reloads
+        })
+    }
+
+    /// Compiles `stages` and links them into `program`, which keeps its old
+    /// code if either fails.
+    unsafe fn relink(program: GLuint, stages: &[Stage]) -> Result<(), GlError> {
+        let mut shaders = Vec::new();
+        for stage in stages {
+            match compile_shader(stage.ty, &stage.source, stage.name) {
+                Ok(shader) => shaders.push(shader),
+                Err(e) => {
+                    shaders.iter().for_each(|&shader| gl::DeleteShader(shader));
+                    return Err(e);
+                }
+            }
+        }
+
+        // a failed link would leave the program broken, so it's tried on
+        // another one first
+        let trial = gl::CreateProgram();
+        shaders
+            .iter()
+            .for_each(|&shader| gl::AttachShader(trial, shader));
+        gl::LinkProgram(trial);
+        if let Err(e) = verify_program(trial) {
+            shaders.iter().for_each(|&shader| gl::DeleteShader(shader));
+            return Err(e);
+        }
+        gl::DeleteProgram(trial);
+
+        let uniforms = save_uniforms(program);
+        let mut attached = vec![0; 8];
+        let mut count = 0;
+        gl::GetAttachedShaders(program, 8, &mut count, attached.as_mut_ptr());
+        for &shader in &attached[..count as usize] {
+            gl::DetachShader(program, shader);
+        }
+        for &shader in &shaders {
+            gl::AttachShader(program, shader);
+            gl::DeleteShader(shader);
+        }
+        gl::LinkProgram(program);
+        restore_uniforms(program, &uniforms);
+        Ok(())
+    }
+
+    /// What is read and written of uniforms of this type, and how many
+    /// values they hold.
+    #[derive(Clone, Copy, PartialEq)]
+    enum Kind {
+        Float(usize),
+        Int(usize),
+        Uint(usize),
+        Matrix(usize),
+    }
+
+    fn kind(ty: GLenum) -> Option<Kind> {
+        Some(match ty {
+            gl::FLOAT => Kind::Float(1),
+            gl::FLOAT_VEC2 => Kind::Float(2),
+            gl::FLOAT_VEC3 => Kind::Float(3),
+            gl::FLOAT_VEC4 => Kind::Float(4),
+            gl::FLOAT_MAT2 => Kind::Matrix(2),
+            gl::FLOAT_MAT3 => Kind::Matrix(3),
+            gl::FLOAT_MAT4 => Kind::Matrix(4),
+            gl::INT | gl::BOOL => Kind::Int(1),
+            gl::INT_VEC2 | gl::BOOL_VEC2 => Kind::Int(2),
+            gl::INT_VEC3 | gl::BOOL_VEC3 => Kind::Int(3),
+            gl::INT_VEC4 | gl::BOOL_VEC4 => Kind::Int(4),
+            gl::UNSIGNED_INT => Kind::Uint(1),
+            gl::UNSIGNED_INT_VEC2 => Kind::Uint(2),
+            gl::UNSIGNED_INT_VEC3 => Kind::Uint(3),
+            gl::UNSIGNED_INT_VEC4 => Kind::Uint(4),
+            // texture units
+            gl::SAMPLER_2D
+            | gl::SAMPLER_3D
+            | gl::SAMPLER_CUBE
+            | gl::SAMPLER_2D_ARRAY
+            | gl::SAMPLER_2D_SHADOW
+            | gl::SAMPLER_2D_MULTISAMPLE
+            | gl::SAMPLER_BUFFER
+            | gl::INT_SAMPLER_2D
+            | gl::UNSIGNED_INT_SAMPLER_2D
+            | gl::IMAGE_2D
+            | gl::IMAGE_2D_ARRAY
+            | gl::INT_IMAGE_2D
+            | gl::UNSIGNED_INT_IMAGE_2D => Kind::Int(1),
+            _ => return None,
+        })
+    }
+
+    /// Value of a uniform, as raw bits of its type.
+    struct SavedUniform {
+        name: CString,
+        kind: Kind,
+        bits: [u32; 16],
+    }
+
+    unsafe fn save_uniforms(program: GLuint) -> Vec<SavedUniform> {
+        let mut count = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
 
-    if status != 1 {
-        let mut length = 0;
-        gl::GetProgramiv(shader, gl::INFO_LOG_LENGTH, &mut length);
+        let mut saved = Vec::new();
+        for index in 0..count as GLuint {
+            let mut name = [0u8; 256];
+            let (mut length, mut size, mut ty) = (0, 0, 0);
+            gl::GetActiveUniform(
+                program,
+                index,
+                name.len() as GLsizei,
+                &mut length,
+                &mut size,
+                &mut ty,
+                name.as_mut_ptr().cast(),
+            );
+            let Some(kind) = kind(ty) else {
+                continue;
+            };
+            let name = String::from_utf8_lossy(&name[..length as usize]).into_owned();
+            let base = name.strip_suffix("[0]").unwrap_or(&name);
 
-        if length > 0 {
-            let mut log = String::with_capacity(length as usize);
-            log.extend(std::iter::repeat('\0').take(length as usize));
-            gl::GetProgramInfoLog(shader, length, &mut length, log.as_mut_ptr().cast());
-            log.truncate(length as usize);
+            for element in 0..size {
+                let name = match size {
+                    1 => CString::new(name.clone()).unwrap(),
+                    _ => CString::new(format!("{base}[{element}]")).unwrap(),
+                };
+                let location = gl::GetUniformLocation(program, name.as_ptr());
+                // in uniform blocks
+                if location < 0 {
+                    continue;
+                }
+                let mut bits = [0u32; 16];
+                match kind {
+                    Kind::Float(_) | Kind::Matrix(_) => {
+                        gl::GetUniformfv(program, location, bits.as_mut_ptr().cast())
+                    }
+                    Kind::Int(_) => gl::GetUniformiv(program, location, bits.as_mut_ptr().cast()),
+                    Kind::Uint(_) => gl::GetUniformuiv(program, location, bits.as_mut_ptr()),
+                }
+                saved.push(SavedUniform { name, kind, bits });
+            }
+        }
+        saved
+    }
+
+    unsafe fn restore_uniforms(program: GLuint, uniforms: &[SavedUniform]) {
+        let mut current = 0;
+        gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut current);
+        gl::UseProgram(program);
 
-            eprintln!("PROGRAM LINK ERROR: {log}");
+        for uniform in uniforms {
+            let location = gl::GetUniformLocation(program, uniform.name.as_ptr());
+            if location < 0 || Some(uniform.kind) != uniform_kind(program, &uniform.name) {
+                continue;
+            }
+            let floats = uniform.bits.as_ptr().cast::<f32>();
+            let ints = uniform.bits.as_ptr().cast::<GLint>();
+            let uints = uniform.bits.as_ptr();
+            match uniform.kind {
+                Kind::Float(1) => gl::Uniform1fv(location, 1, floats),
+                Kind::Float(2) => gl::Uniform2fv(location, 1, floats),
+                Kind::Float(3) => gl::Uniform3fv(location, 1, floats),
+                Kind::Float(_) => gl::Uniform4fv(location, 1, floats),
+                Kind::Matrix(2) => gl::UniformMatrix2fv(location, 1, gl::FALSE, floats),
+                Kind::Matrix(3) => gl::UniformMatrix3fv(location, 1, gl::FALSE, floats),
+                Kind::Matrix(_) => gl::UniformMatrix4fv(location, 1, gl::FALSE, floats),
+                Kind::Int(1) => gl::Uniform1iv(location, 1, ints),
+                Kind::Int(2) => gl::Uniform2iv(location, 1, ints),
+                Kind::Int(3) => gl::Uniform3iv(location, 1, ints),
+                Kind::Int(_) => gl::Uniform4iv(location, 1, ints),
+                Kind::Uint(1) => gl::Uniform1uiv(location, 1, uints),
+                Kind::Uint(2) => gl::Uniform2uiv(location, 1, uints),
+                Kind::Uint(3) => gl::Uniform3uiv(location, 1, uints),
+                Kind::Uint(_) => gl::Uniform4uiv(location, 1, uints),
+            }
         }
+
+        gl::UseProgram(current as GLuint);
+    }
+
+    /// Kind of the uniform called `name` in the relinked program, which may
+    /// have changed its type.
+    unsafe fn uniform_kind(program: GLuint, name: &CStr) -> Option<Kind> {
+        let base = name.to_str().ok()?.split('[').next()?;
+        let mut count = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+        for index in 0..count as GLuint {
+            let mut active = [0u8; 256];
+            let (mut length, mut size, mut ty) = (0, 0, 0);
+            gl::GetActiveUniform(
+                program,
+                index,
+                active.len() as GLsizei,
+                &mut length,
+                &mut size,
+                &mut ty,
+                active.as_mut_ptr().cast(),
+            );
+            let active = &active[..length as usize];
+            let active = active.strip_suffix(b"[0]").unwrap_or(active);
+            if active == base.as_bytes() {
+                return kind(ty);
+            }
+        }
+        None
     }
 }
 
@@ -114,14 +651,75 @@ pub struct Framebuffer {
     pub size: UVec2,
 }
 
-pub unsafe fn create_framebuffer(name: &str, size: UVec2) -> Framebuffer {
-    let mut fbo: GLuint = 0;
-    gl::GenFramebuffers(1, &mut fbo);
-    gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+pub unsafe fn create_framebuffer(name: &str, size: UVec2) -> Result<Framebuffer, GlError> {
+    let mut texture: GLuint = 0;
+    gl::GenTextures(1, &mut texture);
+    upload_texture(texture, size.x, size.y, std::ptr::null(), gl::CLAMP_TO_EDGE)
+        .inspect_err(|_| delete_textures(&[texture]))?;
+
+    attach_framebuffer(name, texture, size, texture_bytes(size.x, size.y))
+}
+
+/// Framebuffer drawing to a floating point texture of `format`, either
+/// `gl::RGBA16F` or `gl::R32F`, for values outside of 0 to 1 like HDR colors.
+pub unsafe fn create_float_framebuffer(
+    name: &str,
+    size: UVec2,
+    format: GLenum,
+) -> Result<Framebuffer, GlError> {
+    let (pixel_format, bytes_per_pixel) = match format {
+        gl::R32F => (gl::RED, 4),
+        _ => (gl::RGBA, 8),
+    };
+    check_texture_size(size.x, size.y)?;
 
     let mut texture: GLuint = 0;
     gl::GenTextures(1, &mut texture);
-    upload_texture(texture, size.x, size.y, std::ptr::null(), gl::CLAMP_TO_EDGE);
+    gl::BindTexture(gl::TEXTURE_2D, texture);
+    clear_errors();
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        format as GLint,
+        size.x as GLsizei,
+        size.y as GLsizei,
+        0,
+        pixel_format,
+        gl::FLOAT,
+        std::ptr::null(),
+    );
+    if out_of_memory() {
+        gl::DeleteTextures(1, &texture);
+        return Err(GlError::OutOfMemory { size });
+    }
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_WRAP_S,
+        gl::CLAMP_TO_EDGE as GLint,
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_WRAP_T,
+        gl::CLAMP_TO_EDGE as GLint,
+    );
+
+    let bytes = size.x as usize * size.y as usize * bytes_per_pixel;
+    attach_framebuffer(name, texture, size, bytes)
+}
+
+/// Creates a framebuffer drawing to `texture`, which takes `bytes`. Both are
+/// deleted if it isn't complete.
+unsafe fn attach_framebuffer(
+    name: &str,
+    texture: GLuint,
+    size: UVec2,
+    bytes: usize,
+) -> Result<Framebuffer, GlError> {
+    let mut fbo: GLuint = 0;
+    gl::GenFramebuffers(1, &mut fbo);
+    gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
     gl::FramebufferTexture2D(
         gl::FRAMEBUFFER,
         gl::COLOR_ATTACHMENT0,
@@ -130,11 +728,82 @@ pub unsafe fn create_framebuffer(name: &str, size: UVec2) -> Framebuffer {
         0,
     );
 
-    if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
-        eprintln!("{name} framebuffer ({}x{}) not complete", size.x, size.y);
+    let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+    if status != gl::FRAMEBUFFER_COMPLETE {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::DeleteFramebuffers(1, &fbo);
+        delete_textures(&[texture]);
+        return Err(GlError::IncompleteFramebuffer {
+            name: name.to_string(),
+            size,
+            status,
+        });
     }
 
-    Framebuffer { fbo, texture, size }
+    // the texture is owned by the framebuffer, so account for it there
+    gpu_memory::record(Object::Texture(texture), Category::Framebuffers, bytes);
+
+    Ok(Framebuffer { fbo, texture, size })
+}
+
+pub unsafe fn delete_framebuffer(framebuffer: &Framebuffer) {
+    gl::DeleteFramebuffers(1, &framebuffer.fbo);
+    delete_textures(&[framebuffer.texture]);
+}
+
+// --- capabilities ---
+
+/// Version of the current context as `(major, minor)`.
+pub unsafe fn gl_version() -> (i32, i32) {
+    let (mut major, mut minor) = (0, 0);
+    gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+    gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    (major, minor)
+}
+
+/// Whether the context is OpenGL ES rather than desktop OpenGL.
+pub unsafe fn is_gles() -> bool {
+    let version = gl::GetString(gl::VERSION);
+    !version.is_null()
+        && CStr::from_ptr(version.cast())
+            .to_bytes()
+            .starts_with(b"OpenGL ES")
+}
+
+pub unsafe fn has_extension(name: &str) -> bool {
+    let mut num_extensions = 0;
+    gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut num_extensions);
+
+    (0..num_extensions as GLuint).any(|i| {
+        let extension = gl::GetStringi(gl::EXTENSIONS, i);
+        !extension.is_null() && CStr::from_ptr(extension.cast()).to_bytes() == name.as_bytes()
+    })
+}
+
+/// Compute shaders, along with the SSBOs, atomic counters and indirect draws
+/// that go with them, are core since 4.3.
+pub unsafe fn supports_compute() -> bool {
+    gl_version() >= (4, 3)
+}
+
+/// Highest anisotropy textures can be filtered with, 1 if anisotropic
+/// filtering isn't supported at all.
+pub unsafe fn max_anisotropy() -> f32 {
+    let supported = gl_version() >= (4, 6)
+        || has_extension("GL_ARB_texture_filter_anisotropic")
+        || has_extension("GL_EXT_texture_filter_anisotropic");
+    if !supported {
+        return 1.0;
+    }
+    let mut max = 1.0;
+    gl::GetFloatv(gl_ext::MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
+    max
+}
+
+pub unsafe fn max_texture_size() -> u32 {
+    let mut size: GLint = 0;
+    gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut size);
+    size as u32
 }
 
 pub unsafe fn upload_texture(
@@ -143,8 +812,12 @@ pub unsafe fn upload_texture(
     height: u32,
     data: *const u8,
     clamp: GLenum,
-) {
+) -> Result<(), GlError> {
+    profiling::cpu_zone!("upload texture");
+    check_texture_size(width, height)?;
+
     gl::BindTexture(gl::TEXTURE_2D, texture);
+    clear_errors();
     gl::TexImage2D(
         gl::TEXTURE_2D,
         0,
@@ -156,8 +829,792 @@ pub unsafe fn upload_texture(
         gl::UNSIGNED_BYTE,
         data as *const _,
     );
+    if out_of_memory() {
+        return Err(GlError::OutOfMemory {
+            size: uvec2(width, height),
+        });
+    }
     gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
     gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
     gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, clamp as GLint);
     gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, clamp as GLint);
+
+    gpu_memory::record(
+        Object::Texture(texture),
+        Category::Textures,
+        texture_bytes(width, height),
+    );
+    Ok(())
+}
+
+/// Textures need at least a pixel, and at most `GL_MAX_TEXTURE_SIZE` on a
+/// side.
+unsafe fn check_texture_size(width: u32, height: u32) -> Result<(), GlError> {
+    let max = max_texture_size();
+    if width == 0 || height == 0 || width > max || height > max {
+        return Err(GlError::TextureSize {
+            size: uvec2(width, height),
+            max,
+        });
+    }
+    Ok(())
+}
+
+/// Forgets the errors raised so far, so that [`out_of_memory`] only sees
+/// those of the allocation that follows.
+unsafe fn clear_errors() {
+    // a lost context may keep reporting errors
+    for _ in 0..MAX_PENDING_ERRORS {
+        if gl::GetError() == gl::NO_ERROR {
+            break;
+        }
+    }
+}
+
+/// Whether the allocation since [`clear_errors`] ran out of memory, taking
+/// every error it raised so none is left for later checks.
+unsafe fn out_of_memory() -> bool {
+    let mut out_of_memory = false;
+    for _ in 0..MAX_PENDING_ERRORS {
+        match gl::GetError() {
+            gl::NO_ERROR => break,
+            gl::OUT_OF_MEMORY => out_of_memory = true,
+            _ => {}
+        }
+    }
+    out_of_memory
+}
+
+const MAX_PENDING_ERRORS: usize = 32;
+
+fn texture_bytes(width: u32, height: u32) -> usize {
+    // every texture we upload is RGBA8
+    width as usize * height as usize * 4
+}
+
+pub unsafe fn delete_textures(textures: &[GLuint]) {
+    for &texture in textures {
+        gpu_memory::forget(Object::Texture(texture));
+    }
+    gl::DeleteTextures(textures.len() as GLsizei, textures.as_ptr());
+}
+
+/// Reads back RGBA pixels from the currently bound read framebuffer,
+/// flipped so that the first row is the top one.
+pub unsafe fn read_pixels(width: u32, height: u32) -> Vec<u8> {
+    let row_len = width as usize * 4;
+    let mut pixels = vec![0u8; row_len * height as usize];
+
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+    gl::ReadPixels(
+        0,
+        0,
+        width as GLsizei,
+        height as GLsizei,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixels.as_mut_ptr() as *mut _,
+    );
+
+    // GL's origin is bottom-left, images' is top-left
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for row in pixels.chunks_exact(row_len).rev() {
+        flipped.extend_from_slice(row);
+    }
+    flipped
+}
+
+// --- buffers ---
+
+/// Binds `buffer` to `target` and (re)allocates its storage with `data`.
+pub unsafe fn buffer_data<T>(target: GLenum, buffer: GLuint, data: &[T], usage: GLenum) {
+    let size = std::mem::size_of_val(data);
+
+    gl::BindBuffer(target, buffer);
+    gl::BufferData(target, size as GLsizeiptr, data.as_ptr() as *const _, usage);
+
+    gpu_memory::record(Object::Buffer(buffer), Category::Buffers, size);
+}
+
+pub unsafe fn delete_buffers(buffers: &[GLuint]) {
+    for &buffer in buffers {
+        gpu_memory::forget(Object::Buffer(buffer));
+    }
+    gl::DeleteBuffers(buffers.len() as GLsizei, buffers.as_ptr());
+}
+
+/// Blending and bindings, saved by overlays drawn on top of a scene to put
+/// them back afterwards, since scenes set them once and rely on them.
+pub struct SavedState {
+    blend: GLboolean,
+    blend_funcs: [GLint; 4],
+    blend_equations: [GLint; 2],
+    program: GLint,
+    vao: GLint,
+    active_texture: GLint,
+    texture: GLint,
+}
+
+impl SavedState {
+    pub unsafe fn save() -> Self {
+        let get = |name: GLenum| {
+            let mut value = 0;
+            gl::GetIntegerv(name, &mut value);
+            value
+        };
+
+        let active_texture = get(gl::ACTIVE_TEXTURE);
+        gl::ActiveTexture(gl::TEXTURE0);
+
+        Self {
+            blend: gl::IsEnabled(gl::BLEND),
+            blend_funcs: [
+                gl::BLEND_SRC_RGB,
+                gl::BLEND_DST_RGB,
+                gl::BLEND_SRC_ALPHA,
+                gl::BLEND_DST_ALPHA,
+            ]
+            .map(get),
+            blend_equations: [gl::BLEND_EQUATION_RGB, gl::BLEND_EQUATION_ALPHA].map(get),
+            program: get(gl::CURRENT_PROGRAM),
+            vao: get(gl::VERTEX_ARRAY_BINDING),
+            active_texture,
+            texture: get(gl::TEXTURE_BINDING_2D),
+        }
+    }
+
+    pub unsafe fn restore(&self) {
+        match self.blend {
+            gl::TRUE => gl::Enable(gl::BLEND),
+            _ => gl::Disable(gl::BLEND),
+        }
+        let [src_rgb, dst_rgb, src_alpha, dst_alpha] = self.blend_funcs.map(|f| f as GLenum);
+        gl::BlendFuncSeparate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+        let [rgb, alpha] = self.blend_equations.map(|e| e as GLenum);
+        gl::BlendEquationSeparate(rgb, alpha);
+
+        gl::UseProgram(self.program as GLuint);
+        gl::BindVertexArray(self.vao as GLuint);
+        gl::BindTexture(gl::TEXTURE_2D, self.texture as GLuint);
+        gl::ActiveTexture(self.active_texture as GLenum);
+    }
+}
+
+// --- safe wrappers ---
+//
+// Objects that delete themselves and calls that can't be given dangling
+// pointers or sizes that don't match, so that scenes can be written without
+// `unsafe`. Like the scenes themselves, they take for granted that the
+// context is current. Names of objects are still passed around as `GLuint`
+// where they come from elsewhere: a wrong one is a GL error, not undefined
+// behavior.
+
+/// Linked shader program, deleted when dropped.
+pub struct Program(GLuint);
+
+impl Program {
+    pub fn new(vert_source: &[u8], frag_source: &[u8]) -> Result<Self, GlError> {
+        unsafe { create_shader_program(vert_source, frag_source) }.map(Self)
+    }
+
+    pub fn compute(comp_source: &[u8]) -> Result<Self, GlError> {
+        unsafe { create_compute_program(comp_source) }.map(Self)
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.0
+    }
+
+    pub fn uniform(&self, name: &CStr) -> Uniform {
+        Uniform(unsafe { gl::GetUniformLocation(self.0, name.as_ptr()) })
+    }
+
+    /// Makes draw calls use this program.
+    pub fn bind(&self) {
+        unsafe { gl::UseProgram(self.0) };
+    }
+
+    /// Binds the program and sets one of its uniforms.
+    pub fn set(&self, uniform: Uniform, value: impl UniformValue) {
+        self.bind();
+        value.set(uniform);
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        #[cfg(feature = "hot-reload")]
+        hot_reload::forget(self.0);
+        unsafe { gl::DeleteProgram(self.0) };
+    }
+}
+
+/// Location of a uniform in a program. Uniforms the program doesn't have
+/// are -1, and setting them does nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uniform(GLint);
+
+/// Values uniforms can be set to, in the program currently bound.
+pub trait UniformValue {
+    fn set(&self, uniform: Uniform);
+}
+
+impl UniformValue for f32 {
+    fn set(&self, uniform: Uniform) {
+        unsafe { gl::Uniform1f(uniform.0, *self) };
+    }
+}
+
+impl UniformValue for i32 {
+    fn set(&self, uniform: Uniform) {
+        unsafe { gl::Uniform1i(uniform.0, *self) };
+    }
+}
+
+impl UniformValue for bool {
+    fn set(&self, uniform: Uniform) {
+        unsafe { gl::Uniform1i(uniform.0, *self as GLint) };
+    }
+}
+
+impl UniformValue for Vec2 {
+    fn set(&self, uniform: Uniform) {
+        unsafe { gl::Uniform2f(uniform.0, self.x, self.y) };
+    }
+}
+
+impl UniformValue for Vec3 {
+    fn set(&self, uniform: Uniform) {
+        unsafe { gl::Uniform3f(uniform.0, self.x, self.y, self.z) };
+    }
+}
+
+impl UniformValue for [f32; 4] {
+    fn set(&self, uniform: Uniform) {
+        unsafe { gl::Uniform4fv(uniform.0, 1, self.as_ptr()) };
+    }
+}
+
+impl UniformValue for Mat4 {
+    fn set(&self, uniform: Uniform) {
+        unsafe { gl::UniformMatrix4fv(uniform.0, 1, gl::FALSE, self.as_ref().as_ptr()) };
+    }
+}
+
+/// Buffer of `T`s, deleted when dropped.
+pub struct Buffer<T> {
+    id: GLuint,
+    target: GLenum,
+    len: usize,
+    _items: PhantomData<T>,
+}
+
+impl<T: Copy> Buffer<T> {
+    /// Buffer bound to `target`, like `gl::ARRAY_BUFFER`, holding `data`.
+    pub fn new(target: GLenum, data: &[T], usage: GLenum) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+            buffer_data(target, id, data, usage);
+        }
+        Self {
+            id,
+            target,
+            len: data.len(),
+            _items: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bind(&self) {
+        unsafe { gl::BindBuffer(self.target, self.id) };
+    }
+
+    /// Overwrites the first items of the buffer with `data`.
+    ///
+    /// # Panics
+    ///
+    /// If there are more items in `data` than in the buffer.
+    pub fn update(&self, data: &[T]) {
+        self.update_at(0, data);
+    }
+
+    /// Overwrites the items of the buffer from index `first` with `data`.
+    ///
+    /// # Panics
+    ///
+    /// If `data` goes past the end of the buffer.
+    pub fn update_at(&self, first: usize, data: &[T]) {
+        assert!(
+            first + data.len() <= self.len,
+            "{} items from {first} don't fit in {}",
+            data.len(),
+            self.len
+        );
+        let offset = (first * mem::size_of::<T>()) as GLsizeiptr;
+        let size = mem::size_of_val(data) as GLsizeiptr;
+        self.bind();
+        unsafe { gl::BufferSubData(self.target, offset, size, data.as_ptr() as *const _) };
+    }
+}
+
+impl<T> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        unsafe { delete_buffers(&[self.id]) };
+    }
+}
+
+/// Element array buffer of indices, deleted when dropped. It remembers its
+/// largest index so vertex arrays can check it before drawing.
+pub struct IndexBuffer {
+    buffer: Buffer<u32>,
+    max: Option<u32>,
+}
+
+impl IndexBuffer {
+    pub fn new(indices: &[u32], usage: GLenum) -> Self {
+        Self {
+            buffer: Buffer::new(gl::ELEMENT_ARRAY_BUFFER, indices, usage),
+            max: indices.iter().copied().max(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn bind(&self) {
+        self.buffer.bind();
+    }
+}
+
+/// Vertex attribute: `components` floats, `offset` bytes into a vertex.
+#[derive(Debug, Clone, Copy)]
+pub struct Attrib {
+    pub name: &'static CStr,
+    pub components: usize,
+    pub offset: usize,
+}
+
+/// Vertex array, deleted when dropped. It only draws as many vertices as its
+/// buffer has.
+pub struct VertexArray {
+    id: GLuint,
+    vertices: usize,
+}
+
+#[allow(clippy::new_without_default)]
+impl VertexArray {
+    pub fn new() -> Self {
+        let mut id = 0;
+        unsafe { gl::GenVertexArrays(1, &mut id) };
+        Self { id, vertices: 0 }
+    }
+
+    /// Feeds the attributes of `program` listed in `layout` from the vertices
+    /// of `buffer`. Attributes the program doesn't have are skipped, so
+    /// programs sharing a vertex shader can share a layout.
+    ///
+    /// # Panics
+    ///
+    /// If an attribute doesn't fit in a vertex.
+    pub fn set_layout<V: Copy>(
+        &mut self,
+        program: &Program,
+        buffer: &Buffer<V>,
+        layout: &[Attrib],
+    ) {
+        self.set_attribs(program, buffer, layout, 0);
+        self.vertices = buffer.len();
+    }
+
+    /// Like [`Self::set_layout`], but the attributes advance once per
+    /// instance instead of once per vertex.
+    pub fn set_instance_layout<V: Copy>(
+        &mut self,
+        program: &Program,
+        buffer: &Buffer<V>,
+        layout: &[Attrib],
+    ) {
+        self.set_attribs(program, buffer, layout, 1);
+    }
+
+    fn set_attribs<V: Copy>(
+        &self,
+        program: &Program,
+        buffer: &Buffer<V>,
+        layout: &[Attrib],
+        divisor: GLuint,
+    ) {
+        let stride = mem::size_of::<V>();
+        unsafe {
+            gl::BindVertexArray(self.id);
+            buffer.bind();
+            for attrib in layout {
+                let end = attrib.offset + attrib.components * mem::size_of::<f32>();
+                assert!(end <= stride, "{:?} is outside of its vertex", attrib.name);
+
+                let location = gl::GetAttribLocation(program.id(), attrib.name.as_ptr());
+                if location < 0 {
+                    continue;
+                }
+                gl::VertexAttribPointer(
+                    location as GLuint,
+                    attrib.components as GLint,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride as GLsizei,
+                    attrib.offset as *const _,
+                );
+                gl::EnableVertexAttribArray(location as GLuint);
+                gl::VertexAttribDivisor(location as GLuint, divisor);
+            }
+            gl::BindVertexArray(0);
+        }
+    }
+
+    /// Makes draw calls use this vertex array, for those there's no method
+    /// for.
+    pub fn bind(&self) {
+        unsafe { gl::BindVertexArray(self.id) };
+    }
+
+    /// Draws all its vertices as `mode`, like `gl::TRIANGLES`.
+    pub fn draw_arrays(&self, mode: GLenum) {
+        unsafe {
+            gl::BindVertexArray(self.id);
+            gl::DrawArrays(mode, 0, self.vertices as GLsizei);
+            gl::BindVertexArray(0);
+        }
+    }
+
+    /// Draws the vertices in `range` as `mode`.
+    ///
+    /// # Panics
+    ///
+    /// If `range` goes past the last vertex.
+    pub fn draw_arrays_range(&self, mode: GLenum, range: Range<usize>) {
+        self.check_range(&range);
+        unsafe {
+            gl::BindVertexArray(self.id);
+            gl::DrawArrays(mode, range.start as GLint, range.len() as GLsizei);
+            gl::BindVertexArray(0);
+        }
+    }
+
+    /// Draws the vertices in each of `ranges` as `mode`, in a single call.
+    ///
+    /// # Panics
+    ///
+    /// If a range goes past the last vertex.
+    pub fn multi_draw_arrays(&self, mode: GLenum, ranges: &[Range<usize>]) {
+        let (firsts, counts): (Vec<GLint>, Vec<GLsizei>) = (ranges.iter())
+            .map(|range| {
+                self.check_range(range);
+                (range.start as GLint, range.len() as GLsizei)
+            })
+            .unzip();
+        unsafe {
+            gl::BindVertexArray(self.id);
+            let draws = ranges.len() as GLsizei;
+            gl::MultiDrawArrays(mode, firsts.as_ptr(), counts.as_ptr(), draws);
+            gl::BindVertexArray(0);
+        }
+    }
+
+    /// Draws all its vertices `instances` times as `mode`.
+    pub fn draw_arrays_instanced(&self, mode: GLenum, instances: usize) {
+        unsafe {
+            gl::BindVertexArray(self.id);
+            let count = self.vertices as GLsizei;
+            gl::DrawArraysInstanced(mode, 0, count, instances as GLsizei);
+            gl::BindVertexArray(0);
+        }
+    }
+
+    /// Draws the vertices listed in `indices` as `mode`.
+    ///
+    /// # Panics
+    ///
+    /// If an index is past the last vertex.
+    pub fn draw_elements(&self, mode: GLenum, indices: &IndexBuffer) {
+        self.check_indices(indices);
+        unsafe {
+            gl::BindVertexArray(self.id);
+            indices.bind();
+            let count = indices.len() as GLsizei;
+            gl::DrawElements(mode, count, gl::UNSIGNED_INT, std::ptr::null());
+            gl::BindVertexArray(0);
+        }
+    }
+
+    /// Draws the vertices listed in each of `ranges` of `indices` as `mode`,
+    /// in a single call.
+    ///
+    /// # Panics
+    ///
+    /// If a range goes past the last index, or an index past the last vertex.
+    pub fn multi_draw_elements(
+        &self,
+        mode: GLenum,
+        indices: &IndexBuffer,
+        ranges: &[Range<usize>],
+    ) {
+        self.check_indices(indices);
+        let (counts, offsets): (Vec<GLsizei>, Vec<*const c_void>) = (ranges.iter())
+            .map(|range| {
+                assert!(
+                    range.start <= range.end && range.end <= indices.len(),
+                    "indices {range:?} are past the {} indices",
+                    indices.len()
+                );
+                let offset = range.start * mem::size_of::<u32>();
+                (range.len() as GLsizei, offset as *const c_void)
+            })
+            .unzip();
+        unsafe {
+            gl::BindVertexArray(self.id);
+            indices.bind();
+            let draws = ranges.len() as GLsizei;
+            let (counts, offsets) = (counts.as_ptr(), offsets.as_ptr());
+            gl::MultiDrawElements(mode, counts, gl::UNSIGNED_INT, offsets, draws);
+            gl::BindVertexArray(0);
+        }
+    }
+
+    fn check_range(&self, range: &Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.vertices,
+            "vertices {range:?} are past the {} vertices",
+            self.vertices
+        );
+    }
+
+    fn check_indices(&self, indices: &IndexBuffer) {
+        if let Some(max) = indices.max {
+            assert!(
+                (max as usize) < self.vertices,
+                "index {max} is past the {} vertices",
+                self.vertices
+            );
+        }
+    }
+}
+
+impl Drop for VertexArray {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteVertexArrays(1, &self.id) };
+    }
+}
+
+/// RGBA8 texture, deleted when dropped.
+pub struct Texture {
+    id: GLuint,
+    size: UVec2,
+}
+
+impl Texture {
+    /// Texture of `size` holding `pixels`, 4 bytes each, with `clamp` as both
+    /// wrap modes.
+    pub fn new(size: UVec2, pixels: &[u8], clamp: GLenum) -> Result<Self, GlError> {
+        let mut id = 0;
+        unsafe { gl::GenTextures(1, &mut id) };
+        let mut texture = Self { id, size };
+        texture.upload(size, pixels, clamp)?;
+        Ok(texture)
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Replaces the whole texture, which can change its size.
+    ///
+    /// # Panics
+    ///
+    /// If there aren't exactly 4 bytes per pixel.
+    pub fn upload(&mut self, size: UVec2, pixels: &[u8], clamp: GLenum) -> Result<(), GlError> {
+        assert_eq!(
+            pixels.len(),
+            texture_bytes(size.x, size.y),
+            "not a {size} image"
+        );
+        unsafe { upload_texture(self.id, size.x, size.y, pixels.as_ptr(), clamp)? };
+        self.size = size;
+        Ok(())
+    }
+
+    /// Replaces the texture with the image of `input`, as frames of a live
+    /// input come in. Returns `false` without uploading if the image has
+    /// another size, in which case the scene has to be recreated.
+    pub fn stream(&mut self, input: &InputImage, clamp: GLenum) -> Result<bool, GlError> {
+        let size = input.size();
+        if size != self.size {
+            return Ok(false);
+        }
+        self.upload(size, input.image.as_raw(), clamp)?;
+        Ok(true)
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe { delete_textures(&[self.id]) };
+    }
+}
+
+/// Framebuffer drawing to a texture of its own, both deleted when dropped.
+pub struct RenderTarget(Framebuffer);
+
+impl RenderTarget {
+    /// RGBA8 target, like [`create_framebuffer`].
+    pub fn new(name: &str, size: UVec2) -> Result<Self, GlError> {
+        unsafe { create_framebuffer(name, size) }.map(Self)
+    }
+
+    /// Floating point target, like [`create_float_framebuffer`].
+    pub fn new_float(name: &str, size: UVec2, format: GLenum) -> Result<Self, GlError> {
+        unsafe { create_float_framebuffer(name, size, format) }.map(Self)
+    }
+
+    /// Draws to the whole target.
+    pub fn bind(&self) {
+        bind_framebuffer(Some(&self.0));
+        set_viewport(self.0.size);
+    }
+}
+
+impl Deref for RenderTarget {
+    type Target = Framebuffer;
+
+    fn deref(&self) -> &Framebuffer {
+        &self.0
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe { delete_framebuffer(&self.0) };
+    }
+}
+
+/// Pops its debug group when dropped.
+pub struct DebugGroup(());
+
+impl DebugGroup {
+    pub fn push(message: &'static CStr) -> Self {
+        unsafe { push_debug_group(message) };
+        Self(())
+    }
+}
+
+impl Drop for DebugGroup {
+    fn drop(&mut self) {
+        unsafe { pop_debug_group() };
+    }
+}
+
+/// Draws to `framebuffer`, or to the window with `None`.
+pub fn bind_framebuffer(framebuffer: Option<&Framebuffer>) {
+    let fbo = framebuffer.map_or(0, |framebuffer| framebuffer.fbo);
+    unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, fbo) };
+}
+
+/// Draws to `size` pixels from the bottom left corner.
+pub fn set_viewport(size: UVec2) {
+    unsafe { gl::Viewport(0, 0, size.x as GLsizei, size.y as GLsizei) };
+}
+
+/// Draws to `size` pixels from `min`, counted from the bottom left corner.
+pub fn set_viewport_rect(min: UVec2, size: UVec2) {
+    let (min, size) = (min.as_ivec2(), size.as_ivec2());
+    unsafe { gl::Viewport(min.x, min.y, size.x, size.y) };
+}
+
+/// Only draws within `min` to `min + size`, or everywhere with `None`.
+pub fn set_scissor(rect: Option<(UVec2, UVec2)>) {
+    unsafe {
+        match rect {
+            Some((min, size)) => {
+                gl::Enable(gl::SCISSOR_TEST);
+                let (min, size) = (min.as_ivec2(), size.as_ivec2());
+                gl::Scissor(min.x, min.y, size.x, size.y);
+            }
+            None => gl::Disable(gl::SCISSOR_TEST),
+        }
+    }
+}
+
+/// Blends what's drawn over what's there by its alpha, or replaces it.
+pub fn set_alpha_blending(enabled: bool) {
+    unsafe {
+        if enabled {
+            gl::Enable(gl::BLEND);
+            gl::BlendEquation(gl::FUNC_ADD);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        } else {
+            gl::Disable(gl::BLEND);
+        }
+    }
+}
+
+/// Weighs what's drawn by `src` and what's there by `dst` when blending, like
+/// `gl::SRC_ALPHA` and `gl::ONE_MINUS_SRC_ALPHA`.
+pub fn set_blend_func(src: GLenum, dst: GLenum) {
+    unsafe { gl::BlendFunc(src, dst) };
+}
+
+/// Like [`set_blend_func`], with other factors for alpha than for color.
+pub fn set_blend_func_separate(src: GLenum, dst: GLenum, src_alpha: GLenum, dst_alpha: GLenum) {
+    unsafe { gl::BlendFuncSeparate(src, dst, src_alpha, dst_alpha) };
+}
+
+/// Clears the bound framebuffer with `color`.
+pub fn clear(color: [f32; 4]) {
+    let [r, g, b, a] = color;
+    unsafe {
+        gl::ClearColor(r, g, b, a);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+    }
+}
+
+/// Binds `texture` to texture unit `unit`, which stays the active one.
+pub fn bind_texture(unit: u32, texture: GLuint) {
+    unsafe {
+        gl::ActiveTexture(gl::TEXTURE0 + unit);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+    }
+}
+
+/// Generates the mipmaps of `texture` from what it holds now, and filters it
+/// trilinearly. It stays bound to the active texture unit.
+pub fn generate_mipmaps(texture: GLuint) {
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::GenerateMipmap(gl::TEXTURE_2D);
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MIN_FILTER,
+            gl::LINEAR_MIPMAP_LINEAR as GLint,
+        );
+    }
 }
@@ -0,0 +1,382 @@
+//! Developer console, opened with the backquote key.
+//!
+//! A line of text is parsed into the same [`Command`]s as the remote control,
+//! so both go through one code path in the app:
+//!
+//! ```text
+//! > scene kawase
+//! > set kawase.radius 3
+//! > key ArrowRight
+//! > screenshot shot.png
+//! > script assets/scripts/pulse.rhai
+//! ```
+//!
+//! Text comes from key events and from the IME, whose composition is shown
+//! inline until it is committed.
+
+use glam::{vec2, Vec2};
+use winit::event::{ElementState, Ime};
+use winit::keyboard::{Key, KeyCode, NamedKey, PhysicalKey};
+
+use crate::input::KeyInput;
+use crate::params::Value;
+use crate::remote::Command;
+use crate::text::TextRenderer;
+use crate::theme;
+use crate::viewport::Viewport;
+
+/// Output lines kept around, older ones are dropped.
+const MAX_LOG: usize = 200;
+/// Output lines shown above the input line.
+const VISIBLE_LOG: usize = 8;
+
+/// Sizes in logical pixels.
+const TEXT_SIZE: f32 = 14.0;
+const PADDING: f32 = 8.0;
+
+pub const HELP: &str = "\
+scene <name> - switch to a scene
+set [<scene>.]<param> <value> - set a parameter of the current scene
+get - list the parameters of the current scene
+key <key> - press a key in the scene
+screenshot [<path>] - save the scene to a PNG
+fps - print frame timings
+script [<path>] - run a Rhai script on top of the scenes, or stop it
+clear - clear the output
+help - show this";
+
+/// What a line of the console asks for.
+#[derive(Debug, Clone)]
+pub enum Action {
+    Run(Command),
+    Help,
+    Clear,
+}
+
+/// Parses a line of the console.
+pub fn parse(line: &str) -> Result<Action, String> {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else {
+        return Err("empty command".to_string());
+    };
+    let args = words.collect::<Vec<_>>();
+
+    let action = match (name, args.as_slice()) {
+        ("scene", [scene]) => Action::Run(Command::SwitchScene {
+            scene: scene.to_string(),
+        }),
+        ("set", [name, value]) => Action::Run(Command::SetParam {
+            name: name.to_string(),
+            value: parse_value(value),
+        }),
+        ("get", []) => Action::Run(Command::GetParams),
+        ("key", [key]) => Action::Run(Command::PressKey {
+            key: key.to_string(),
+        }),
+        ("screenshot", [] | [_]) => Action::Run(Command::Screenshot {
+            path: args.first().map(|path| path.to_string()),
+        }),
+        ("fps", []) => Action::Run(Command::Fps),
+        ("script", [] | [_]) => Action::Run(Command::RunScript {
+            path: args.first().map(|path| path.to_string()),
+        }),
+        ("clear", []) => Action::Clear,
+        ("help", []) => Action::Help,
+        (
+            "scene" | "set" | "get" | "key" | "screenshot" | "fps" | "script" | "clear" | "help",
+            _,
+        ) => return Err(format!("wrong arguments for '{name}', see 'help'")),
+        _ => return Err(format!("unknown command '{name}', see 'help'")),
+    };
+
+    Ok(action)
+}
+
+/// Booleans and numbers as such, anything else as the name of an enum variant.
+pub fn parse_value(value: &str) -> Value {
+    match value {
+        "true" | "on" => Value::Bool(true),
+        "false" | "off" => Value::Bool(false),
+        _ => value
+            .parse()
+            .map_or_else(|_| Value::Text(value.to_string()), Value::Number),
+    }
+}
+
+/// A reply of the app to a command, as a line of output.
+pub fn describe(reply: &serde_json::Value) -> String {
+    fn field(value: &serde_json::Value) -> String {
+        match value {
+            serde_json::Value::String(string) => string.clone(),
+            serde_json::Value::Object(fields) => (fields.iter())
+                .map(|(name, v)| format!("{name}={}", field(v)))
+                .collect::<Vec<_>>()
+                .join(" "),
+            other => other.to_string(),
+        }
+    }
+
+    match reply {
+        serde_json::Value::Object(fields) if fields.is_empty() => "ok".to_string(),
+        serde_json::Value::Object(fields) => (fields.iter())
+            .map(|(name, v)| format!("{name}: {}", field(v)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => field(other),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LogLine {
+    text: String,
+    is_error: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    /// Byte offset of the caret in `input`.
+    cursor: usize,
+    /// Text being composed by the IME, with its caret as a byte range.
+    preedit: Option<(String, Option<(usize, usize)>)>,
+
+    history: Vec<String>,
+    /// Entry of `history` being browsed, `None` when editing a new line.
+    history_index: Option<usize>,
+    log: Vec<LogLine>,
+    /// Commands submitted since the app last ran them.
+    pending: Vec<Command>,
+
+    /// Where the caret was last drawn, in physical pixels, for the IME popup.
+    caret_area: (Vec2, Vec2),
+    caret_moved: bool,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.preedit = None;
+        // the IME forgets where the caret was while it was disabled
+        self.caret_moved = true;
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn insert(&mut self, text: &str) {
+        self.input.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.input[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+            self.input.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.input.len() {
+            self.input.remove(self.cursor);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(c) = self.input[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(c) = self.input[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    /// Replaces the input with the previous entry of the history.
+    pub fn history_previous(&mut self) {
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.show_history(Some(index));
+    }
+
+    /// Replaces the input with the next entry of the history, or an empty line
+    /// past the last one.
+    pub fn history_next(&mut self) {
+        let index = self.history_index.map(|i| i + 1);
+        self.show_history(index.filter(|&i| i < self.history.len()));
+    }
+
+    fn show_history(&mut self, index: Option<usize>) {
+        self.history_index = index;
+        self.input = index.map_or_else(String::new, |i| self.history[i].clone());
+        self.cursor = self.input.len();
+    }
+
+    /// Runs the input line: commands for the app are queued for
+    /// [`Self::take_pending`], the rest is handled here.
+    pub fn submit(&mut self) {
+        let line = std::mem::take(&mut self.input);
+        self.cursor = 0;
+        self.history_index = None;
+
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        if self.history.last().map(String::as_str) != Some(line) {
+            self.history.push(line.to_string());
+        }
+        self.print(format!("> {line}"));
+
+        match parse(line) {
+            Ok(Action::Run(command)) => self.pending.push(command),
+            Ok(Action::Help) => {
+                for line in HELP.lines() {
+                    self.print(line);
+                }
+            }
+            Ok(Action::Clear) => self.log.clear(),
+            Err(e) => self.error(e),
+        }
+    }
+
+    /// Commands submitted since the last call.
+    pub fn take_pending(&mut self) -> Vec<Command> {
+        std::mem::take(&mut self.pending)
+    }
+
+    pub fn print(&mut self, text: impl Into<String>) {
+        self.push_log(text.into(), false);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push_log(text.into(), true);
+    }
+
+    fn push_log(&mut self, text: String, is_error: bool) {
+        if self.log.len() == MAX_LOG {
+            self.log.remove(0);
+        }
+        self.log.push(LogLine { text, is_error });
+    }
+
+    /// Edits the input with a key press. Returns whether the console used it.
+    pub fn on_key(&mut self, event: &KeyInput) -> bool {
+        if event.state != ElementState::Pressed {
+            return true;
+        }
+        // the IME is still composing, the keys are meant for it
+        if self.preedit.is_some() {
+            return true;
+        }
+
+        // the key below Escape, whatever it types on this layout
+        if event.physical_key == PhysicalKey::Code(KeyCode::Backquote) {
+            self.toggle();
+            return true;
+        }
+
+        match &event.logical_key {
+            Key::Named(NamedKey::Enter) => self.submit(),
+            Key::Named(NamedKey::Backspace) => self.backspace(),
+            Key::Named(NamedKey::Delete) => self.delete(),
+            Key::Named(NamedKey::ArrowLeft) => self.move_left(),
+            Key::Named(NamedKey::ArrowRight) => self.move_right(),
+            Key::Named(NamedKey::ArrowUp) => self.history_previous(),
+            Key::Named(NamedKey::ArrowDown) => self.history_next(),
+            Key::Named(NamedKey::Home) => self.cursor = 0,
+            Key::Named(NamedKey::End) => self.cursor = self.input.len(),
+            Key::Named(NamedKey::Escape) => self.toggle(),
+            _ => match &event.text {
+                Some(text) if !text.chars().any(char::is_control) => self.insert(text),
+                _ => return false,
+            },
+        }
+
+        true
+    }
+
+    pub fn on_ime(&mut self, ime: Ime) {
+        match ime {
+            Ime::Preedit(text, _) if text.is_empty() => self.preedit = None,
+            Ime::Preedit(text, cursor) => self.preedit = Some((text, cursor)),
+            Ime::Commit(text) => {
+                self.preedit = None;
+                self.insert(&text);
+            }
+            Ime::Enabled | Ime::Disabled => self.preedit = None,
+        }
+    }
+
+    /// Where the IME should show its candidates, as a position and size in
+    /// physical pixels, if the caret moved since the last call.
+    pub fn take_caret_area(&mut self) -> Option<(Vec2, Vec2)> {
+        std::mem::take(&mut self.caret_moved).then_some(self.caret_area)
+    }
+
+    /// Queues the console at the top of the window, full width, over a panel
+    /// of color `background`.
+    pub fn queue(&mut self, text: &mut TextRenderer, viewport: &Viewport, background: [f32; 4]) {
+        let theme = theme::current();
+        let size = viewport.px(TEXT_SIZE);
+        let padding = viewport.px(PADDING);
+        // a logical pixel, but never thinner than a physical one
+        let line = viewport.px(1.0).max(1.0);
+        let mut pen = Vec2::splat(padding);
+
+        let visible = &self.log[self.log.len().saturating_sub(VISIBLE_LOG)..];
+        for line in visible {
+            let color = match line.is_error {
+                true => theme.accent,
+                false => theme.hud_text,
+            };
+            pen.y += text.queue(&line.text, pen, size, None, color).y;
+        }
+
+        // the input line: prompt, text before the caret, composition, the rest
+        let (before, after) = self.input.split_at(self.cursor);
+        pen.x += text.queue("> ", pen, size, None, theme.grid).x;
+        pen.x += text.queue(before, pen, size, None, theme.hud_text).x;
+
+        let mut caret = pen.x;
+        if let Some((preedit, preedit_cursor)) = &self.preedit {
+            let extent = text.queue(preedit, pen, size, None, theme.accent);
+            let underline = vec2(pen.x, pen.y + extent.y - line);
+            text.queue_rect(underline, underline + vec2(extent.x, line), theme.accent);
+
+            if let Some((start, _)) = preedit_cursor {
+                caret += text.measure(&preedit[..*start], size, None).x;
+            }
+            pen.x += extent.x;
+        }
+        let line_height = text
+            .queue(after, pen, size, None, theme.hud_text)
+            .y
+            .max(size);
+
+        let caret_min = vec2(caret, pen.y);
+        let caret_size = vec2(line, line_height);
+        text.queue_rect(caret_min, caret_min + caret_size, theme.hud_text);
+        if self.caret_area != (caret_min, caret_size) {
+            self.caret_area = (caret_min, caret_size);
+            self.caret_moved = true;
+        }
+
+        let bottom = pen.y + line_height + padding;
+        text.queue_rect(Vec2::ZERO, vec2(viewport.size().x, bottom), background);
+    }
+}
@@ -0,0 +1,97 @@
+//! Tables of numbers loaded from CSV files, shown as a heatmap by the data
+//! mode of the round quads.
+//!
+//! Cells are separated by commas, semicolons or tabs. A first row that isn't
+//! all numbers is taken as a header and skipped, and cells that aren't
+//! numbers are missing values.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataGrid {
+    /// Where the table was loaded from, `None` if it was parsed from memory.
+    pub path: Option<PathBuf>,
+    pub width: u32,
+    pub height: u32,
+    /// Row by row, NaN where a value is missing.
+    pub values: Vec<f32>,
+    /// Range of the values that aren't missing.
+    pub min: f32,
+    pub max: f32,
+}
+
+impl DataGrid {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let mut grid = Self::parse_csv(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        grid.path = Some(path.to_path_buf());
+        Ok(grid)
+    }
+
+    pub fn parse_csv(text: &str) -> Result<Self, String> {
+        let separator = [',', ';', '\t']
+            .into_iter()
+            .max_by_key(|&separator| text.matches(separator).count())
+            .unwrap();
+
+        let mut rows = (text.lines())
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                (line.split(separator))
+                    .map(|cell| cell.trim().parse::<f32>().unwrap_or(f32::NAN))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        if rows
+            .first()
+            .is_some_and(|row| row.iter().any(|v| v.is_nan()))
+        {
+            rows.remove(0);
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if width == 0 {
+            return Err("no values".to_string());
+        }
+
+        let mut values = Vec::with_capacity(width * rows.len());
+        for row in &rows {
+            values.extend_from_slice(row);
+            values.resize(values.len() + width - row.len(), f32::NAN);
+        }
+
+        let present = values.iter().copied().filter(|v| v.is_finite());
+        let (min, max) = present.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+        if min > max {
+            return Err("no values".to_string());
+        }
+
+        Ok(Self {
+            path: None,
+            width: width as u32,
+            height: rows.len() as u32,
+            values,
+            min,
+            max,
+        })
+    }
+
+    /// The value at column `x` of row `y`, unless it is missing.
+    pub fn get(&self, x: u32, y: u32) -> Option<f32> {
+        let value = self.values[(y * self.width + x) as usize];
+        value.is_finite().then_some(value)
+    }
+
+    /// Where `value` is between the smallest and largest values, from 0 to 1.
+    pub fn normalize(&self, value: f32) -> f32 {
+        if self.max > self.min {
+            (value - self.min) / (self.max - self.min)
+        } else {
+            0.5
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Delaunay triangulation of a set of points with the Bowyer-Watson
+//! algorithm: points are inserted one at a time into a triangle containing
+//! them all, replacing the triangles whose circumcircle they fall in.
+//!
+//! This is quadratic at worst, which is fine for the few hundred points it is
+//! used with.
+
+use glam::{DVec2, Vec2};
+
+/// Triangles as indices into the points they were made from, counterclockwise
+/// in a y-up frame.
+pub fn triangulate(points: &[Vec2]) -> Vec<[usize; 3]> {
+    if points.len() < 3 {
+        return Vec::new();
+    }
+
+    // in doubles, as the circumcircle test is sensitive to rounding
+    let mut vertices = points.iter().map(|p| p.as_dvec2()).collect::<Vec<_>>();
+
+    // a triangle far bigger than the bounding box, whose corners are removed
+    // at the end
+    let (min, max) = (vertices.iter()).fold(
+        (DVec2::splat(f64::MAX), DVec2::splat(f64::MIN)),
+        |(min, max), &p| (min.min(p), max.max(p)),
+    );
+    let center = (min + max) * 0.5;
+    let extent = (max - min).max_element().max(1.0) * 20.0;
+    let n = points.len();
+    vertices.extend([
+        center + DVec2::new(-extent, -extent),
+        center + DVec2::new(extent, -extent),
+        center + DVec2::new(0.0, extent),
+    ]);
+
+    let mut triangles = vec![Triangle::new([n, n + 1, n + 2], &vertices)];
+    let mut edges = Vec::new();
+
+    for i in 0..n {
+        let p = vertices[i];
+
+        // the hole left by the triangles the point breaks, and its outline
+        edges.clear();
+        triangles.retain(|triangle| {
+            if !triangle.circumcircle_contains(p) {
+                return true;
+            }
+            let [a, b, c] = triangle.indices;
+            edges.extend([(a, b), (b, c), (c, a)]);
+            false
+        });
+
+        // edges shared by two broken triangles are inside the hole
+        for e in 0..edges.len() {
+            let (a, b) = edges[e];
+            let shared = (edges.iter()).any(|&(c, d)| c == b && d == a);
+            if !shared {
+                triangles.push(Triangle::new([a, b, i], &vertices));
+            }
+        }
+    }
+
+    (triangles.into_iter())
+        .map(|triangle| triangle.indices)
+        .filter(|indices| indices.iter().all(|&i| i < n))
+        .collect()
+}
+
+/// Unique edges of `triangles`, each with its lowest index first.
+pub fn edges(triangles: &[[usize; 3]]) -> Vec<(usize, usize)> {
+    let mut edges = (triangles.iter())
+        .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+        .map(|(a, b)| (a.min(b), a.max(b)))
+        .collect::<Vec<_>>();
+    edges.sort_unstable();
+    edges.dedup();
+    edges
+}
+
+struct Triangle {
+    indices: [usize; 3],
+    center: DVec2,
+    radius_squared: f64,
+}
+
+impl Triangle {
+    /// Triangle between `indices`, made counterclockwise.
+    fn new(mut indices: [usize; 3], vertices: &[DVec2]) -> Self {
+        let [a, b, c] = indices.map(|i| vertices[i]);
+        if (b - a).perp_dot(c - a) < 0.0 {
+            indices.swap(1, 2);
+        }
+
+        // circumcenter, relative to a
+        let (b, c) = (b - a, c - a);
+        let d = 2.0 * b.perp_dot(c);
+        let center = match d.abs() > f64::EPSILON {
+            true => {
+                DVec2::new(
+                    c.y * b.length_squared() - b.y * c.length_squared(),
+                    b.x * c.length_squared() - c.x * b.length_squared(),
+                ) / d
+            }
+            // collinear points: a circle that contains everything
+            false => DVec2::splat(f64::MAX.sqrt()),
+        };
+
+        Self {
+            indices,
+            center: a + center,
+            radius_squared: center.length_squared(),
+        }
+    }
+
+    fn circumcircle_contains(&self, p: DVec2) -> bool {
+        p.distance_squared(self.center) < self.radius_squared
+    }
+}
@@ -0,0 +1,315 @@
+//! A chain of framebuffers, each half the size of the one before, blurred
+//! down and back up with Kawase's dual filter.
+//!
+//! The Kawase blur scene blurs its image through one, and HDR effects like
+//! bloom and lens flares read the levels of another for cheap wide blurs.
+//!
+//! Going down, every level is a draw into its own framebuffer, each waiting
+//! for the last. With compute shaders, the first levels of chains of 8-bit
+//! colors can instead come out of a single dispatch, as in AMD's single pass
+//! downsampler: level 1 is filtered from level 0 like the first pass, and the
+//! ones after it are averages of 2×2 texels of the level above, reduced in
+//! shared memory. Those blur a bit less than the passes they replace at large
+//! distances, which the upsampling hides well, and leave out the last row or
+//! column of levels of an odd size, as the sizes round down.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::mem;
+
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+use glam::{vec2, UVec2, Vec2};
+
+use crate::common_gl::{
+    buffer_data, create_compute_program, create_float_framebuffer, create_framebuffer,
+    create_shader_program, delete_buffers, delete_framebuffer, pop_debug_group, push_debug_group,
+    supports_compute, Framebuffer,
+};
+use crate::error::GlError;
+
+const SRC_VERT_SCREEN: &[u8] = include_bytes!("../assets/shaders/screen.vert");
+const SRC_FRAG_KAWASE: &[u8] = include_bytes!("../assets/shaders/kawase.frag");
+const SRC_COMP_DOWNSAMPLE: &[u8] = include_bytes!("../assets/shaders/downsample.comp");
+
+/// Levels after level 0 a single dispatch goes down, the workgroups of
+/// 16×16 invocations covering 32×32 texels of level 0.
+pub const COMPUTE_LEVELS: usize = 5;
+const COMPUTE_TILE: u32 = 16;
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+pub struct DownsampleChain {
+    name: &'static str,
+    /// `gl::RGBA8`, or a float format for HDR.
+    format: GLenum,
+    /// From the largest to the smallest.
+    levels: Vec<Framebuffer>,
+    n_levels: usize,
+
+    shader: GLuint,
+    u_distance: GLint,
+    u_upsample: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    /// Program of the single dispatch, once enabled.
+    compute: Option<ComputeProgram>,
+}
+
+struct ComputeProgram {
+    program: GLuint,
+    u_distance: GLint,
+    u_levels: GLint,
+}
+
+impl DownsampleChain {
+    /// Chain of `n_levels` framebuffers of `format`, the first being `size`.
+    pub fn new(
+        name: &'static str,
+        size: UVec2,
+        n_levels: usize,
+        format: GLenum,
+    ) -> Result<Self, GlError> {
+        unsafe {
+            let shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_KAWASE)?;
+            let u_distance = gl::GetUniformLocation(shader, c"u_distance".as_ptr());
+            let u_upsample = gl::GetUniformLocation(shader, c"u_upsample".as_ptr());
+
+            let vertex = |x: f32, y: f32| Vertex {
+                position: vec2(x, y) * 2.0 - 1.0,
+                uv: vec2(x, y),
+            };
+            let vertices = [
+                vertex(0.0, 1.0),
+                vertex(0.0, 0.0),
+                vertex(1.0, 0.0),
+                vertex(0.0, 1.0),
+                vertex(1.0, 0.0),
+                vertex(1.0, 1.0),
+            ];
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let mut chain = Self {
+                name,
+                format,
+                levels: Vec::new(),
+                n_levels,
+                shader,
+                u_distance,
+                u_upsample,
+                vao,
+                vbo,
+                compute: None,
+            };
+            chain.resize(size)?;
+            Ok(chain)
+        }
+    }
+
+    /// Level `i` of the chain, 0 being the largest.
+    pub fn level(&self, i: usize) -> &Framebuffer {
+        &self.levels[i]
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// Recreates the levels so that the first one is `size`, if it isn't
+    /// already. Levels stop at 1×1. The chain is left empty if one can't be
+    /// created.
+    pub unsafe fn resize(&mut self, size: UVec2) -> Result<(), GlError> {
+        if self.levels.first().is_some_and(|level| level.size == size) {
+            return Ok(());
+        }
+
+        self.delete_framebuffers();
+        if size.min_element() == 0 {
+            return Ok(());
+        }
+
+        for i in 0..self.n_levels {
+            let size = (size >> i as u32).max(UVec2::ONE);
+            let level = match self.format {
+                gl::RGBA8 => create_framebuffer(self.name, size),
+                format => create_float_framebuffer(self.name, size, format),
+            };
+            match level {
+                Ok(level) => self.levels.push(level),
+                Err(e) => {
+                    self.delete_framebuffers();
+                    return Err(e);
+                }
+            }
+        }
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        Ok(())
+    }
+
+    /// Blurs level 0 down into the `layers` levels after it, sampling
+    /// `distance` pixels away.
+    pub fn downsample(&self, layers: usize, distance: f32) {
+        unsafe {
+            push_debug_group(c"Kawase downsampling");
+            for i in 1..=layers.min(self.len().saturating_sub(1)) {
+                self.pass(distance, false, &self.levels[i - 1], &self.levels[i]);
+            }
+            pop_debug_group();
+        }
+    }
+
+    /// Blurs level `layers` back up into level 0, sampling `distance` pixels
+    /// away.
+    pub fn upsample(&self, layers: usize, distance: f32) {
+        unsafe {
+            push_debug_group(c"Kawase upsampling");
+            for i in (0..layers.min(self.len().saturating_sub(1))).rev() {
+                self.pass(distance, true, &self.levels[i + 1], &self.levels[i]);
+            }
+            pop_debug_group();
+        }
+    }
+
+    /// Compiles the program of [`Self::downsample_compute`], if it isn't
+    /// already. Returns whether it can be used, which takes compute shaders
+    /// and levels of `gl::RGBA8`.
+    pub fn enable_compute(&mut self) -> Result<bool, GlError> {
+        if self.compute.is_some() {
+            return Ok(true);
+        }
+        if self.format != gl::RGBA8 || !unsafe { supports_compute() } {
+            return Ok(false);
+        }
+
+        unsafe {
+            let program = create_compute_program(SRC_COMP_DOWNSAMPLE)?;
+            self.compute = Some(ComputeProgram {
+                program,
+                u_distance: gl::GetUniformLocation(program, c"u_distance".as_ptr()),
+                u_levels: gl::GetUniformLocation(program, c"u_levels".as_ptr()),
+            });
+        }
+        Ok(true)
+    }
+
+    /// Blurs level 0 down into the `layers` levels after it like
+    /// [`Self::downsample`], the first [`COMPUTE_LEVELS`] of them in a single
+    /// dispatch. Falls back to [`Self::downsample`] unless
+    /// [`Self::enable_compute`] succeeded.
+    pub fn downsample_compute(&self, layers: usize, distance: f32) {
+        let Some(compute) = &self.compute else {
+            return self.downsample(layers, distance);
+        };
+        let layers = layers.min(self.len().saturating_sub(1));
+        let levels = layers.min(COMPUTE_LEVELS);
+        if levels == 0 {
+            return;
+        }
+
+        unsafe {
+            push_debug_group(c"Compute downsampling");
+            gl::UseProgram(compute.program);
+            gl::Uniform1f(compute.u_distance, distance);
+            gl::Uniform1i(compute.u_levels, levels as GLint);
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.levels[0].texture);
+            for (unit, level) in self.levels[1..=levels].iter().enumerate() {
+                let texture = level.texture;
+                gl::BindImageTexture(unit as GLuint, texture, 0, 0, 0, gl::WRITE_ONLY, gl::RGBA8);
+            }
+
+            let groups = (self.levels[1].size + COMPUTE_TILE - 1) / COMPUTE_TILE;
+            gl::DispatchCompute(groups.x, groups.y, 1);
+            // the levels are sampled and drawn into by the passes that follow
+            gl::MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT | gl::FRAMEBUFFER_BARRIER_BIT);
+
+            for unit in 0..levels {
+                gl::BindImageTexture(unit as GLuint, 0, 0, 0, 0, gl::WRITE_ONLY, gl::RGBA8);
+            }
+            gl::UseProgram(0);
+            pop_debug_group();
+
+            // levels the dispatch doesn't reach are drawn as usual
+            if layers > levels {
+                push_debug_group(c"Kawase downsampling");
+                for i in levels + 1..=layers {
+                    self.pass(distance, false, &self.levels[i - 1], &self.levels[i]);
+                }
+                pop_debug_group();
+            }
+        }
+    }
+
+    unsafe fn pass(&self, distance: f32, upsample: bool, from: &Framebuffer, to: &Framebuffer) {
+        push_debug_group(c"Kawase pass");
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, to.fbo);
+        gl::Viewport(0, 0, to.size.x as i32, to.size.y as i32);
+
+        gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        gl::UseProgram(self.shader);
+
+        gl::Uniform1f(self.u_distance, distance);
+        gl::Uniform1i(self.u_upsample, upsample as i32);
+
+        gl::BindVertexArray(self.vao);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, from.texture);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        gl::BindVertexArray(0);
+
+        pop_debug_group();
+    }
+
+    unsafe fn delete_framebuffers(&mut self) {
+        for level in self.levels.drain(..) {
+            delete_framebuffer(&level);
+        }
+    }
+}
+
+impl Drop for DownsampleChain {
+    fn drop(&mut self) {
+        unsafe {
+            self.delete_framebuffers();
+            gl::DeleteProgram(self.shader);
+            if let Some(compute) = &self.compute {
+                gl::DeleteProgram(compute.program);
+            }
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Errors of resource creation, which scenes surface on an error screen
+//! rather than carrying on with broken objects and drawing black, and panics
+//! caught while scenes run, which end up on the same screen.
+
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use gl::types::GLenum;
+use glam::UVec2;
+
+/// GL objects that couldn't be created.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GlError {
+    /// A shader stage (`vert`, `frag`, ...) didn't compile.
+    Compile {
+        stage: &'static str,
+        log: String,
+    },
+    Link {
+        log: String,
+    },
+    /// `status` as returned by `glCheckFramebufferStatus`.
+    IncompleteFramebuffer {
+        name: String,
+        size: UVec2,
+        status: GLenum,
+    },
+    /// A texture larger than `GL_MAX_TEXTURE_SIZE` or with no pixels.
+    TextureSize {
+        size: UVec2,
+        max: u32,
+    },
+    OutOfMemory {
+        size: UVec2,
+    },
+}
+
+impl fmt::Display for GlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compile { stage, log } => write!(f, "{stage} shader compile error: {log}"),
+            Self::Link { log } => write!(f, "program link error: {log}"),
+            Self::IncompleteFramebuffer { name, size, status } => write!(
+                f,
+                "{name} framebuffer ({}x{}) not complete (status 0x{status:04X})",
+                size.x, size.y
+            ),
+            Self::TextureSize { size, max } => write!(
+                f,
+                "invalid texture size {}x{}, sides must be between 1 and {max}",
+                size.x, size.y
+            ),
+            Self::OutOfMemory { size } => {
+                write!(f, "out of memory for a {}x{} texture", size.x, size.y)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GlError {}
+
+/// Anything that keeps a scene from being created or from running.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaygroundError {
+    Gl(GlError),
+    Plugin(String),
+    /// The scene panicked. `message` ends with where, and `backtrace` only
+    /// has the frames of this crate when symbols are available.
+    Panic {
+        message: String,
+        backtrace: String,
+    },
+}
+
+impl fmt::Display for PlaygroundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gl(e) => write!(f, "{e}"),
+            Self::Plugin(message) => write!(f, "{message}"),
+            Self::Panic { message, .. } => write!(f, "panicked at {message}"),
+        }
+    }
+}
+
+impl std::error::Error for PlaygroundError {}
+
+impl From<GlError> for PlaygroundError {
+    fn from(e: GlError) -> Self {
+        Self::Gl(e)
+    }
+}
+
+thread_local! {
+    /// Message and backtrace of the last panic on this thread, as seen by the
+    /// hook, which knows where it happened while `catch_unwind` doesn't.
+    static LAST_PANIC: RefCell<Option<(String, String)>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, turning a panic into [`PlaygroundError::Panic`] instead of
+/// unwinding further. The panic is still printed as usual.
+///
+/// Whatever `f` was mutating may be left half-updated, so it should be thrown
+/// away on error rather than used again.
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, PlaygroundError> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let payload = info.payload();
+            let text = (payload.downcast_ref::<&str>().copied())
+                .or(payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("Box<dyn Any>");
+            let message = match info.location() {
+                Some(location) => format!("{location}: {text}"),
+                None => text.to_owned(),
+            };
+            let backtrace = own_frames(&Backtrace::force_capture().to_string());
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some((message, backtrace)));
+            previous(info);
+        }));
+    });
+
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|_| {
+        let (message, backtrace) = LAST_PANIC
+            .with(|last| last.borrow_mut().take())
+            .unwrap_or_else(|| ("somewhere".to_owned(), String::new()));
+        PlaygroundError::Panic { message, backtrace }
+    })
+}
+
+/// Keeps the frames of a printed backtrace that are in this crate, with the
+/// lines saying where they are, from where it panicked up to where it was
+/// caught. Keeps everything if there are no symbols to tell.
+fn own_frames(backtrace: &str) -> String {
+    let mut kept = String::new();
+    let mut keeping = false;
+    for line in backtrace.lines() {
+        let trimmed = line.trim_start();
+        let is_frame = trimmed
+            .split_once(": ")
+            .is_some_and(|(index, _)| index.parse::<usize>().is_ok());
+        if is_frame {
+            if trimmed.ends_with("opengl_playground::error::catch_panic") {
+                break;
+            }
+            // the hook itself is in this module
+            keeping = trimmed.contains("opengl_playground::")
+                && !trimmed.contains("opengl_playground::error::");
+            if keeping {
+                kept.push_str(trimmed);
+                kept.push('\n');
+            }
+        } else if keeping {
+            kept.push_str("    ");
+            kept.push_str(trimmed);
+            kept.push('\n');
+        }
+    }
+
+    if kept.is_empty() {
+        backtrace.to_owned()
+    } else {
+        kept
+    }
+}
@@ -0,0 +1,103 @@
+//! Saving what gets rendered to image files: plain screenshots, and
+//! high-resolution exports rendered in window-sized tiles stitched together.
+
+use std::fs::OpenOptions;
+use std::io::BufWriter;
+use std::path::Path;
+
+use glam::{IVec2, Mat2, UVec2, Vec2};
+use image::{ImageFormat, ImageResult, RgbaImage};
+
+use crate::camera::Camera;
+use crate::common_gl::read_pixels;
+use crate::profiling;
+use crate::scenes::Scenes;
+
+/// Saves the content of the currently bound read framebuffer.
+pub fn save_framebuffer(path: &Path, size: UVec2) -> ImageResult<()> {
+    let pixels = unsafe { read_pixels(size.x, size.y) };
+    let image = RgbaImage::from_raw(size.x, size.y, pixels).unwrap();
+    image.save(path)
+}
+
+/// Like [`save_framebuffer`], but always as a PNG and failing rather than
+/// overwriting an existing file.
+pub fn save_new_png(path: &Path, size: UVec2) -> ImageResult<()> {
+    let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    let pixels = unsafe { read_pixels(size.x, size.y) };
+    let image = RgbaImage::from_raw(size.x, size.y, pixels).unwrap();
+    image.write_to(&mut BufWriter::new(file), ImageFormat::Png)
+}
+
+/// Renders the scene as a `tiles`×`tiles` grid of window-sized tiles, each one
+/// zoomed in by `tiles` so that together they cover exactly what the window shows.
+pub fn render_tiled(
+    scenes: &mut Scenes,
+    camera: &Camera,
+    viewport: IVec2,
+    tiles: u32,
+) -> RgbaImage {
+    profiling::cpu_zone!("tiled export");
+
+    let tile_size = viewport.as_uvec2();
+    let mut poster = RgbaImage::new(tile_size.x * tiles, tile_size.y * tiles);
+
+    let tile_scale = camera.scale * tiles as f32;
+    let unrotate = Mat2::from_angle(-camera.rotation);
+
+    // keep the pointer out of the picture so it doesn't interact with every tile
+    let pointer = Vec2::splat(-1e9);
+
+    for ty in 0..tiles {
+        for tx in 0..tiles {
+            // offset of the tile's center from the poster's center, in poster pixels
+            let tile_center =
+                (IVec2::new(tx as i32, ty as i32).as_vec2() + 0.5) * tile_size.as_vec2();
+            let offset = tile_center - (tile_size * tiles).as_vec2() / 2.0;
+
+            let tile_camera = Camera {
+                position: camera.position - unrotate * (offset / tile_scale),
+                rotation: camera.rotation,
+                scale: tile_scale,
+            };
+
+            let pixels = unsafe {
+                scenes.resize(&tile_camera, viewport.x, viewport.y);
+                scenes.draw(&tile_camera, pointer);
+                gl::Finish();
+
+                read_pixels(tile_size.x, tile_size.y)
+            };
+
+            let tile = RgbaImage::from_raw(tile_size.x, tile_size.y, pixels).unwrap();
+            image::imageops::replace(
+                &mut poster,
+                &tile,
+                (tx * tile_size.x) as i64,
+                (ty * tile_size.y) as i64,
+            );
+        }
+    }
+
+    // back to the regular view
+    scenes.resize(camera, viewport.x, viewport.y);
+
+    poster
+}
+
+pub fn export_tiled(
+    path: &Path,
+    scenes: &mut Scenes,
+    camera: &Camera,
+    viewport: IVec2,
+    tiles: u32,
+) -> ImageResult<()> {
+    let poster = render_tiled(scenes, camera, viewport, tiles);
+    println!(
+        "Saving {}x{} export to {}",
+        poster.width(),
+        poster.height(),
+        path.display()
+    );
+    poster.save(path)
+}
@@ -0,0 +1,420 @@
+//! Frame rate and frame times in a corner of the window, shown with `Ctrl+F`
+//! over whatever scene is running.
+//!
+//! Unlike the rest of the overlay, the counter doesn't go through
+//! cosmic-text: it is drawn with a tiny bitmap font baked into a texture at
+//! startup, a quad per character scaled up to whole pixels, so that it costs
+//! next to nothing and keeps working when the text renderer doesn't.
+//!
+//! Pressing `Ctrl+F` again draws it from the GPU instead (see
+//! [`crate::gpu_text`]), and leaving either path prints what it cost per
+//! frame, to compare them.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem;
+use std::time::Instant;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, UVec2, Vec2};
+
+use crate::common_gl::{
+    buffer_data, create_shader_program, delete_buffers, delete_textures, upload_texture, SavedState,
+};
+use crate::error::GlError;
+use crate::gpu_text::GpuText;
+use crate::theme;
+use crate::viewport::Viewport;
+
+const SRC_VERT_FPS: &[u8] = include_bytes!("../assets/shaders/fps.vert");
+const SRC_FRAG_FPS: &[u8] = include_bytes!("../assets/shaders/fps.frag");
+
+/// Seconds of frames the rates and extremes are taken over.
+const WINDOW: f32 = 1.0;
+
+/// Texels of a character, with a column and a row between each.
+pub(crate) const GLYPH: UVec2 = uvec2(5, 7);
+pub(crate) const CELL: UVec2 = uvec2(GLYPH.x + 1, GLYPH.y + 1);
+
+/// Sizes in logical pixels.
+pub(crate) const MARGIN: f32 = 16.0;
+pub(crate) const PADDING: f32 = 4.0;
+/// Logical pixels per texel of the font.
+pub(crate) const SCALE: f32 = 2.0;
+
+/// Rows of each character, top first, its leftmost texel in the highest of
+/// the five bits. Enough for the counter and nothing else.
+pub(crate) const FONT: &[(char, [u8; 7])] = &[
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    (' ', [0x00; 7]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('a', [0x00, 0x00, 0x0E, 0x01, 0x0F, 0x11, 0x0F]),
+    ('i', [0x04, 0x00, 0x0C, 0x04, 0x04, 0x04, 0x0E]),
+    ('m', [0x00, 0x00, 0x1A, 0x15, 0x15, 0x11, 0x11]),
+    ('n', [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11]),
+    ('s', [0x00, 0x00, 0x0E, 0x10, 0x0E, 0x01, 0x1E]),
+    ('x', [0x00, 0x00, 0x11, 0x0A, 0x04, 0x0A, 0x11]),
+    // the whole glyph, stretched over the panel behind the text
+    ('█', [0x1F; 7]),
+];
+
+/// Index of `c` in the font, if it has it.
+pub fn glyph_index(c: char) -> Option<usize> {
+    FONT.iter().position(|&(glyph, _)| glyph == c)
+}
+
+/// Durations of the frames of the last [`WINDOW`], in seconds.
+#[derive(Debug, Default, Clone)]
+pub struct FrameTimes {
+    frames: VecDeque<f32>,
+    total: f32,
+}
+
+impl FrameTimes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a frame that took `dt` seconds, dropping those older than the
+    /// window but always keeping the last one.
+    pub fn push(&mut self, dt: f32) {
+        self.frames.push_back(dt);
+        self.total += dt;
+        while self.total - self.frames[0] >= WINDOW {
+            self.total -= self.frames.pop_front().unwrap();
+        }
+    }
+
+    /// Frames per second over the window.
+    pub fn fps(&self) -> f32 {
+        match self.total > 0.0 {
+            true => self.frames.len() as f32 / self.total,
+            false => 0.0,
+        }
+    }
+
+    /// Average frame time over the window.
+    pub fn average(&self) -> f32 {
+        self.total / self.frames.len().max(1) as f32
+    }
+
+    pub fn min(&self) -> f32 {
+        self.frames.iter().copied().reduce(f32::min).unwrap_or(0.0)
+    }
+
+    pub fn max(&self) -> f32 {
+        self.frames.iter().copied().reduce(f32::max).unwrap_or(0.0)
+    }
+}
+
+impl fmt::Display for FrameTimes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} FPS  {:.2} ms  min {:.2}  max {:.2}",
+            self.fps(),
+            self.average() * 1000.0,
+            self.min() * 1000.0,
+            self.max() * 1000.0,
+        )
+    }
+}
+
+/// How the counter is drawn, if at all.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FpsMode {
+    #[default]
+    Hidden,
+    /// A quad per character, uploaded every frame.
+    Quads,
+    /// A single quad whose shader formats the numbers, which are all that's
+    /// uploaded.
+    Shader,
+}
+
+impl FpsMode {
+    pub const ALL: [Self; 3] = [Self::Hidden, Self::Quads, Self::Shader];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hidden => "hidden",
+            Self::Quads => "quads",
+            Self::Shader => "shader",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// What drawing the counter took per frame, averaged since it was shown.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DrawCost {
+    frames: u32,
+    /// Seconds of CPU time.
+    cpu: f32,
+    bytes: usize,
+}
+
+impl DrawCost {
+    pub fn add(&mut self, cpu: f32, bytes: usize) {
+        self.frames += 1;
+        self.cpu += cpu;
+        self.bytes += bytes;
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Average CPU time in seconds.
+    pub fn cpu(&self) -> f32 {
+        self.cpu / self.frames.max(1) as f32
+    }
+
+    /// Average bytes uploaded.
+    pub fn bytes(&self) -> usize {
+        self.bytes / self.frames.max(1) as usize
+    }
+}
+
+impl fmt::Display for DrawCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.3} ms of CPU and {} bytes uploaded per frame, over {} frames",
+            self.cpu() * 1000.0,
+            self.bytes(),
+            self.frames,
+        )
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct FpsVertex {
+    position: Vec2,
+    uv: Vec2,
+    color: [f32; 4],
+}
+
+pub struct FpsCounter {
+    times: FrameTimes,
+    mode: FpsMode,
+    /// Of the current mode since it was picked.
+    cost: DrawCost,
+    /// Created the first time it's needed.
+    gpu_text: Option<GpuText>,
+
+    shader: GLuint,
+    u_viewport: GLint,
+    font: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+    /// Kept around between frames so that they don't allocate.
+    vertices: Vec<FpsVertex>,
+}
+
+impl FpsCounter {
+    pub unsafe fn new() -> Result<Self, GlError> {
+        let shader = create_shader_program(SRC_VERT_FPS, SRC_FRAG_FPS)?;
+        let u_viewport = gl::GetUniformLocation(shader, c"u_viewport".as_ptr());
+
+        // every glyph side by side, white where its bits are set
+        let size = uvec2(CELL.x * FONT.len() as u32, CELL.y);
+        let mut texels = vec![0u8; (size.x * size.y * 4) as usize];
+        for (index, (_, rows)) in FONT.iter().enumerate() {
+            for (y, row) in rows.iter().enumerate() {
+                for x in 0..GLYPH.x {
+                    if row & (1 << (GLYPH.x - 1 - x)) != 0 {
+                        let texel = y * size.x as usize + index * CELL.x as usize + x as usize;
+                        texels[texel * 4..][..4].fill(255);
+                    }
+                }
+            }
+        }
+        let mut font = 0;
+        gl::GenTextures(1, &mut font);
+        upload_texture(font, size.x, size.y, texels.as_ptr(), gl::CLAMP_TO_EDGE)?;
+
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        let mut vbo = 0;
+        gl::GenBuffers(1, &mut vbo);
+
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+        let size_f32 = mem::size_of::<f32>() as GLsizei;
+        let size_vertex = mem::size_of::<FpsVertex>() as GLsizei;
+        gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, size_vertex, 0 as _);
+        gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, size_vertex, (2 * size_f32) as _);
+        gl::VertexAttribPointer(2, 4, gl::FLOAT, gl::FALSE, size_vertex, (4 * size_f32) as _);
+        for location in 0..3 {
+            gl::EnableVertexAttribArray(location);
+        }
+
+        gl::BindVertexArray(0);
+
+        Ok(Self {
+            times: FrameTimes::new(),
+            mode: FpsMode::Hidden,
+            cost: DrawCost::default(),
+            gpu_text: None,
+            shader,
+            u_viewport,
+            font,
+            vao,
+            vbo,
+            vertices: Vec::new(),
+        })
+    }
+
+    pub fn mode(&self) -> FpsMode {
+        self.mode
+    }
+
+    /// Goes to the next mode, printing what the one it leaves cost.
+    pub unsafe fn cycle(&mut self) -> FpsMode {
+        if self.mode != FpsMode::Hidden && self.cost.frames() > 0 {
+            println!("FPS counter with {}: {}", self.mode.name(), self.cost);
+        }
+
+        self.mode = self.mode.next();
+        self.cost = DrawCost::default();
+        if self.mode == FpsMode::Shader && self.gpu_text.is_none() {
+            match GpuText::new() {
+                Ok(gpu_text) => self.gpu_text = Some(gpu_text),
+                Err(e) => {
+                    eprintln!("Could not draw the FPS counter from the GPU: {e}");
+                    self.mode = self.mode.next();
+                }
+            }
+        }
+        self.mode
+    }
+
+    /// Counts a frame that took `dt` seconds, even while hidden so that the
+    /// counter is right as soon as it shows.
+    pub fn update(&mut self, dt: f32) {
+        self.times.push(dt);
+    }
+
+    pub fn times(&self) -> &FrameTimes {
+        &self.times
+    }
+
+    /// Draws the counter at the bottom left of the default framebuffer.
+    pub unsafe fn draw(&mut self, viewport: &Viewport) {
+        let start = Instant::now();
+        let bytes = match (self.mode, self.gpu_text.as_mut()) {
+            (FpsMode::Hidden, _) => return,
+            (FpsMode::Shader, Some(gpu_text)) => gpu_text.draw(&self.times, viewport),
+            _ => self.draw_quads(viewport),
+        };
+        self.cost.add(start.elapsed().as_secs_f32(), bytes);
+    }
+
+    /// Returns how many bytes were uploaded for it.
+    unsafe fn draw_quads(&mut self, viewport: &Viewport) -> usize {
+        let theme = theme::current();
+        let label = self.times.to_string();
+        let scale = viewport.px(SCALE).max(1.0);
+        let cell = CELL.as_vec2() * scale;
+        let padding = viewport.px(PADDING);
+
+        let extent = vec2(cell.x * label.chars().count() as f32, cell.y) + 2.0 * padding;
+        let margin = viewport.px(MARGIN);
+        let min = vec2(margin, viewport.size().y - margin - extent.y);
+        let max = min + extent;
+
+        self.vertices.clear();
+        let solid = glyph_index('█').unwrap() as f32 * CELL.x as f32;
+        let solid = (vec2(solid, 0.0), vec2(solid, 0.0) + GLYPH.as_vec2());
+        self.push_quad((min, max), solid, theme.hud_background);
+
+        let mut pen = min + padding;
+        for c in label.chars() {
+            if let Some(index) = glyph_index(c) {
+                let uv = vec2((index as u32 * CELL.x) as f32, 0.0);
+                self.push_quad((pen, pen + cell), (uv, uv + CELL.as_vec2()), theme.hud_text);
+            }
+            pen.x += cell.x;
+        }
+
+        let saved = SavedState::save();
+        let size = viewport.size();
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, size.x as GLsizei, size.y as GLsizei);
+        gl::Enable(gl::BLEND);
+        gl::BlendEquation(gl::FUNC_ADD);
+        gl::BlendFuncSeparate(
+            gl::SRC_ALPHA,
+            gl::ONE_MINUS_SRC_ALPHA,
+            gl::ONE,
+            gl::ONE_MINUS_SRC_ALPHA,
+        );
+
+        gl::UseProgram(self.shader);
+        gl::Uniform2f(self.u_viewport, size.x, size.y);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, self.font);
+
+        buffer_data(gl::ARRAY_BUFFER, self.vbo, &self.vertices, gl::STREAM_DRAW);
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, self.vertices.len() as GLsizei);
+
+        saved.restore();
+        mem::size_of_val(self.vertices.as_slice())
+    }
+
+    fn push_quad(
+        &mut self,
+        (min, max): (Vec2, Vec2),
+        (uv_min, uv_max): (Vec2, Vec2),
+        color: [f32; 4],
+    ) {
+        let corners = [
+            (min, uv_min),
+            (vec2(max.x, min.y), vec2(uv_max.x, uv_min.y)),
+            (vec2(min.x, max.y), vec2(uv_min.x, uv_max.y)),
+            (max, uv_max),
+        ];
+        for i in [0, 1, 2, 2, 1, 3] {
+            let (position, uv) = corners[i];
+            self.vertices.push(FpsVertex {
+                position,
+                uv,
+                color,
+            });
+        }
+    }
+}
+
+impl Drop for FpsCounter {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+            delete_textures(&[self.font]);
+        }
+    }
+}
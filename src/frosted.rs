@@ -0,0 +1,213 @@
+//! Frosted glass for the overlay: the HUD is rendered to its own texture, and
+//! composited over a blurred copy of the scene wherever it covers it.
+//!
+//! The passes only work in this order, once the scene is done drawing:
+//!
+//! 1. the HUD to its texture, premultiplied,
+//! 2. the default framebuffer copied at half resolution,
+//! 3. Kawase downsampling then upsampling through a [`DownsampleChain`],
+//! 4. the blurred copy and the HUD composited back onto the default framebuffer.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{vec2, UVec2, Vec2};
+
+use crate::common_gl::{
+    buffer_data, create_framebuffer, create_shader_program, delete_buffers, delete_framebuffer,
+    pop_debug_group, push_debug_group, Framebuffer, SavedState,
+};
+use crate::downsample::DownsampleChain;
+use crate::error::GlError;
+use crate::text::TextRenderer;
+
+const SRC_VERT_SCREEN: &[u8] = include_bytes!("../assets/shaders/screen.vert");
+const SRC_FRAG_FROSTED: &[u8] = include_bytes!("../assets/shaders/frosted.frag");
+
+/// Levels of the blur chain, from the copy of the scene at half resolution.
+const BLUR_LEVELS: usize = 4;
+const BLUR_RADIUS: f32 = 2.0;
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+pub struct Frosted {
+    /// Framebuffers sized for this viewport.
+    viewport: UVec2,
+    hud_fb: Option<Framebuffer>,
+    chain: DownsampleChain,
+
+    composite_shader: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+
+    u_panel_alpha: GLint,
+}
+
+impl Frosted {
+    pub unsafe fn new() -> Result<Self, GlError> {
+        let composite_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_FROSTED)?;
+        let u_panel_alpha = gl::GetUniformLocation(composite_shader, c"u_panel_alpha".as_ptr());
+        gl::Uniform1i(
+            gl::GetUniformLocation(composite_shader, c"u_hud".as_ptr()),
+            0,
+        );
+        gl::Uniform1i(
+            gl::GetUniformLocation(composite_shader, c"u_backdrop".as_ptr()),
+            1,
+        );
+
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        gl::BindVertexArray(vao);
+        let mut vbo = 0;
+        gl::GenBuffers(1, &mut vbo);
+        buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+        const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+        for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+            let location = gl::GetAttribLocation(composite_shader, name.as_ptr()) as GLuint;
+            gl::VertexAttribPointer(location, 2, gl::FLOAT, gl::FALSE, SIZE_VERTEX, offset as _);
+            gl::EnableVertexAttribArray(location);
+        }
+        gl::BindVertexArray(0);
+
+        Ok(Self {
+            viewport: UVec2::ZERO,
+            hud_fb: None,
+            chain: DownsampleChain::new("frosted blur", UVec2::ZERO, BLUR_LEVELS, gl::RGBA8)?,
+            composite_shader,
+            vao,
+            vbo,
+            u_panel_alpha,
+        })
+    }
+
+    /// Draws what is queued in `text` over the default framebuffer, with the
+    /// scene blurred behind it. `panel_alpha` is the alpha of the panels the
+    /// blur shows through.
+    pub unsafe fn draw(
+        &mut self,
+        text: &mut TextRenderer,
+        viewport: Vec2,
+        panel_alpha: f32,
+    ) -> Result<(), GlError> {
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        self.resize(viewport.as_uvec2())?;
+        let Some(hud_fb) = &self.hud_fb else {
+            return Ok(());
+        };
+
+        push_debug_group(c"Frosted overlay");
+        let saved = SavedState::save();
+
+        push_debug_group(c"Overlay to texture");
+        gl::BindFramebuffer(gl::FRAMEBUFFER, hud_fb.fbo);
+        gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        text.draw(hud_fb.fbo, viewport);
+        pop_debug_group();
+
+        push_debug_group(c"Scene to framebuffer");
+        let first = self.chain.level(0);
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, first.fbo);
+        gl::BlitFramebuffer(
+            0,
+            0,
+            self.viewport.x as GLint,
+            self.viewport.y as GLint,
+            0,
+            0,
+            first.size.x as GLint,
+            first.size.y as GLint,
+            gl::COLOR_BUFFER_BIT,
+            gl::LINEAR,
+        );
+        pop_debug_group();
+
+        gl::Disable(gl::BLEND);
+        self.chain.downsample(BLUR_LEVELS - 1, BLUR_RADIUS);
+        self.chain.upsample(BLUR_LEVELS - 1, BLUR_RADIUS * 0.5);
+
+        push_debug_group(c"Composite");
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, self.viewport.x as GLsizei, self.viewport.y as GLsizei);
+
+        // Premultiplied blending
+        gl::Enable(gl::BLEND);
+        gl::BlendEquation(gl::FUNC_ADD);
+        gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+
+        gl::UseProgram(self.composite_shader);
+        gl::BindVertexArray(self.vao);
+        gl::Uniform1f(self.u_panel_alpha, panel_alpha);
+        gl::ActiveTexture(gl::TEXTURE1);
+        gl::BindTexture(gl::TEXTURE_2D, self.chain.level(0).texture);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, hud_fb.texture);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        pop_debug_group();
+
+        saved.restore();
+        pop_debug_group();
+        Ok(())
+    }
+
+    /// Recreates the framebuffers if the viewport changed size.
+    unsafe fn resize(&mut self, viewport: UVec2) -> Result<(), GlError> {
+        if viewport == self.viewport {
+            return Ok(());
+        }
+
+        self.delete_framebuffers();
+        if viewport.min_element() > 0 {
+            self.hud_fb = Some(create_framebuffer("frosted overlay", viewport)?);
+            self.chain.resize((viewport / 2).max(UVec2::ONE))?;
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        // only once they exist, so that failing tries again next time
+        self.viewport = viewport;
+        Ok(())
+    }
+
+    unsafe fn delete_framebuffers(&mut self) {
+        if let Some(framebuffer) = self.hud_fb.take() {
+            delete_framebuffer(&framebuffer);
+        }
+    }
+}
+
+impl Drop for Frosted {
+    fn drop(&mut self) {
+        unsafe {
+            self.delete_framebuffers();
+            gl::DeleteProgram(self.composite_shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Entry points and enums of extensions the `gl` crate has no bindings for,
+//! as it only generates the core profile.
+//!
+//! [`load_with`] must be called next to `gl::load_with`. Functions that
+//! aren't exposed by the driver stay unloaded, so check for the extension
+//! before calling them.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::c_void;
+use std::mem;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use gl::types::{GLenum, GLuint, GLuint64};
+
+// GL_ARB_bindless_texture
+static GET_TEXTURE_HANDLE_ARB: AtomicPtr<c_void> = AtomicPtr::new(std::ptr::null_mut());
+static MAKE_TEXTURE_HANDLE_RESIDENT_ARB: AtomicPtr<c_void> = AtomicPtr::new(std::ptr::null_mut());
+static MAKE_TEXTURE_HANDLE_NON_RESIDENT_ARB: AtomicPtr<c_void> =
+    AtomicPtr::new(std::ptr::null_mut());
+
+pub fn load_with(mut loader: impl FnMut(&'static str) -> *const c_void) {
+    let functions = [
+        (&GET_TEXTURE_HANDLE_ARB, "glGetTextureHandleARB"),
+        (
+            &MAKE_TEXTURE_HANDLE_RESIDENT_ARB,
+            "glMakeTextureHandleResidentARB",
+        ),
+        (
+            &MAKE_TEXTURE_HANDLE_NON_RESIDENT_ARB,
+            "glMakeTextureHandleNonResidentARB",
+        ),
+    ];
+
+    for (function, symbol) in functions {
+        function.store(loader(symbol).cast_mut(), Ordering::Relaxed);
+    }
+}
+
+/// Loads a function pointer, panicking like `gl` does if it wasn't loaded.
+unsafe fn function<F: Copy>(ptr: &AtomicPtr<c_void>, symbol: &str) -> F {
+    let ptr = ptr.load(Ordering::Relaxed);
+    assert!(!ptr.is_null(), "{symbol} was not loaded");
+    mem::transmute_copy(&ptr)
+}
+
+// --- GL_ARB_bindless_texture ---
+
+pub unsafe fn get_texture_handle(texture: GLuint) -> GLuint64 {
+    type F = extern "system" fn(GLuint) -> GLuint64;
+    function::<F>(&GET_TEXTURE_HANDLE_ARB, "glGetTextureHandleARB")(texture)
+}
+
+pub unsafe fn make_texture_handle_resident(handle: GLuint64) {
+    type F = extern "system" fn(GLuint64);
+    function::<F>(
+        &MAKE_TEXTURE_HANDLE_RESIDENT_ARB,
+        "glMakeTextureHandleResidentARB",
+    )(handle)
+}
+
+pub unsafe fn make_texture_handle_non_resident(handle: GLuint64) {
+    type F = extern "system" fn(GLuint64);
+    function::<F>(
+        &MAKE_TEXTURE_HANDLE_NON_RESIDENT_ARB,
+        "glMakeTextureHandleNonResidentARB",
+    )(handle)
+}
+
+// --- GL_ARB_texture_filter_anisotropic, core since 4.6 ---
+
+pub const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+pub const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
+
+// --- GL_ARB_pipeline_statistics_query, core since 4.6 ---
+
+pub const VERTICES_SUBMITTED: GLenum = 0x82EE;
+pub const FRAGMENT_SHADER_INVOCATIONS: GLenum = 0x82F4;
+
+// --- GL_EXT_texture_compression_s3tc ---
+
+pub const COMPRESSED_RGB_S3TC_DXT1: GLenum = 0x83F0;
@@ -0,0 +1,143 @@
+//! Bookkeeping of the GPU memory we allocate ourselves, plus whatever the
+//! driver is willing to tell us through vendor extensions.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+
+use gl::types::{GLint, GLuint};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Textures,
+    Buffers,
+    Framebuffers,
+}
+
+impl Category {
+    pub const ALL: [Category; 3] = [Self::Textures, Self::Buffers, Self::Framebuffers];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Textures => "textures",
+            Self::Buffers => "buffers",
+            Self::Framebuffers => "framebuffers",
+        }
+    }
+}
+
+/// Textures and buffers live in different GL namespaces, so the same name can
+/// refer to one of each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Object {
+    Texture(GLuint),
+    Buffer(GLuint),
+}
+
+static ALLOCATIONS: Mutex<BTreeMap<Object, (Category, usize)>> = Mutex::new(BTreeMap::new());
+
+/// Records (or replaces) the size of the storage behind a GL object.
+pub fn record(object: Object, category: Category, bytes: usize) {
+    ALLOCATIONS
+        .lock()
+        .unwrap()
+        .insert(object, (category, bytes));
+}
+
+/// Forgets about a GL object, usually right before deleting it.
+pub fn forget(object: Object) {
+    ALLOCATIONS.lock().unwrap().remove(&object);
+}
+
+/// Bytes currently allocated in a category.
+pub fn allocated(category: Category) -> usize {
+    (ALLOCATIONS.lock().unwrap().values())
+        .filter(|(c, _)| *c == category)
+        .map(|(_, bytes)| bytes)
+        .sum()
+}
+
+// --- vendor extensions ---
+
+const VENDOR_NONE: u8 = 0;
+const VENDOR_NVX: u8 = 1;
+const VENDOR_ATI: u8 = 2;
+
+// Set in main when checking for extensions.
+static VENDOR: AtomicU8 = AtomicU8::new(VENDOR_NONE);
+
+// GL_NVX_gpu_memory_info
+const GPU_MEMORY_INFO_DEDICATED_VIDMEM_NVX: u32 = 0x9047;
+const GPU_MEMORY_INFO_TOTAL_AVAILABLE_MEMORY_NVX: u32 = 0x9048;
+const GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX: u32 = 0x9049;
+
+// GL_ATI_meminfo
+const VBO_FREE_MEMORY_ATI: u32 = 0x87FB;
+const TEXTURE_FREE_MEMORY_ATI: u32 = 0x87FC;
+const RENDERBUFFER_FREE_MEMORY_ATI: u32 = 0x87FD;
+
+pub fn detect_vendor_extensions(extensions: &HashSet<String>) {
+    let vendor = if extensions.contains("GL_NVX_gpu_memory_info") {
+        VENDOR_NVX
+    } else if extensions.contains("GL_ATI_meminfo") {
+        VENDOR_ATI
+    } else {
+        VENDOR_NONE
+    };
+
+    VENDOR.store(vendor, Ordering::Relaxed);
+}
+
+/// What the driver reports, in kilobytes.
+#[derive(Debug, Clone, Copy)]
+pub enum DriverMemoryInfo {
+    Nvx {
+        dedicated_kb: i32,
+        total_available_kb: i32,
+        current_available_kb: i32,
+    },
+    Ati {
+        vbo_free_kb: i32,
+        texture_free_kb: i32,
+        renderbuffer_free_kb: i32,
+    },
+}
+
+pub unsafe fn query_driver() -> Option<DriverMemoryInfo> {
+    let get = |pname: u32| {
+        // ATI_meminfo returns 4 values, the first one being the total free memory.
+        let mut values: [GLint; 4] = [0; 4];
+        gl::GetIntegerv(pname, values.as_mut_ptr());
+        values[0]
+    };
+
+    match VENDOR.load(Ordering::Relaxed) {
+        VENDOR_NVX => Some(DriverMemoryInfo::Nvx {
+            dedicated_kb: get(GPU_MEMORY_INFO_DEDICATED_VIDMEM_NVX),
+            total_available_kb: get(GPU_MEMORY_INFO_TOTAL_AVAILABLE_MEMORY_NVX),
+            current_available_kb: get(GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX),
+        }),
+        VENDOR_ATI => Some(DriverMemoryInfo::Ati {
+            vbo_free_kb: get(VBO_FREE_MEMORY_ATI),
+            texture_free_kb: get(TEXTURE_FREE_MEMORY_ATI),
+            renderbuffer_free_kb: get(RENDERBUFFER_FREE_MEMORY_ATI),
+        }),
+        _ => None,
+    }
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = KIB * 1024.0;
+
+    let bytes_f = bytes as f64;
+    if bytes_f >= MIB {
+        format!("{:.1} MiB", bytes_f / MIB)
+    } else if bytes_f >= KIB {
+        format!("{:.1} KiB", bytes_f / KIB)
+    } else {
+        format!("{bytes} B")
+    }
+}
@@ -0,0 +1,299 @@
+//! The FPS counter drawn by a shader, as an alternative to the glyph quads of
+//! [`crate::fps_counter`], to see what the always-on overlay costs when the
+//! CPU does nothing but hand over the numbers.
+//!
+//! The label is encoded once into a uniform block: a cell per character,
+//! either a glyph or a digit of one of the numbers. Every frame, the only
+//! thing written is the four numbers, 16 bytes, and a single quad without
+//! vertex buffer covers the panel while its fragment shader picks the digits
+//! and draws them from a signed distance field of the font, which stays sharp
+//! at any scale.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::mem;
+
+use gl::types::{GLsizei, GLsizeiptr, GLuint};
+use glam::{uvec2, vec2, UVec2, Vec2};
+
+use crate::common_gl::{
+    buffer_data, create_shader_program, delete_buffers, delete_textures, upload_texture, SavedState,
+};
+use crate::error::GlError;
+use crate::fps_counter::{glyph_index, FrameTimes, CELL, FONT, GLYPH, MARGIN, PADDING, SCALE};
+use crate::theme;
+use crate::viewport::Viewport;
+
+const SRC_VERT_GPU_TEXT: &[u8] = include_bytes!("../assets/shaders/gpu-text.vert");
+const SRC_FRAG_GPU_TEXT: &[u8] = include_bytes!("../assets/shaders/gpu-text.frag");
+
+/// The label, each `{}` replaced by the next of [`FIELDS`].
+const TEMPLATE: &str = "{} FPS  {} ms  min {}  max {}";
+/// Digits before and after the point of each number, which are right
+/// aligned so that the label keeps its width.
+const FIELDS: [(u32, u32); 4] = [(4, 0), (3, 2), (3, 2), (3, 2)];
+
+/// Cells of the label in the uniform block, four per `ivec4`. Must match
+/// `CELLS` in gpu-text.frag.
+const CELLS: usize = 48;
+
+/// Font texels of distances around each glyph, atlas texels per font texel,
+/// and font texels at which distances saturate. Must match gpu-text.frag.
+const SDF_PADDING: u32 = 1;
+const SDF_SCALE: u32 = 6;
+const SDF_RANGE: f32 = 1.0;
+
+/// The `Counter` uniform block of gpu-text.vert and gpu-text.frag, laid out
+/// as std140.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Counter {
+    values: [f32; 4],
+    rect: [f32; 4],
+    viewport: [f32; 2],
+    scale: f32,
+    padding: f32,
+    text_color: [f32; 4],
+    background: [f32; 4],
+    label: [[i32; 4]; CELLS / 4],
+}
+
+/// Cells of the label: the glyph of a character, or `-1 - code` for the digit
+/// of a number, with `code` packing which of the four numbers in its two
+/// lowest bits, its decimals in the next two and the power of ten of the
+/// digit above.
+pub fn encode_label() -> Vec<i32> {
+    let mut cells = Vec::new();
+    let mut fields = FIELDS.iter().enumerate();
+
+    for (i, part) in TEMPLATE.split("{}").enumerate() {
+        if i > 0 {
+            let (field, &(integer, decimals)) = fields.next().unwrap();
+            let digit = |place: u32| -1 - (field as u32 + 4 * decimals + 16 * place) as i32;
+            cells.extend((decimals..decimals + integer).rev().map(digit));
+            if decimals > 0 {
+                cells.push(glyph_index('.').unwrap() as i32);
+                cells.extend((0..decimals).rev().map(digit));
+            }
+        }
+        cells.extend(part.chars().map(|c| glyph_index(c).unwrap() as i32));
+    }
+
+    cells
+}
+
+/// What the shader draws for `cells` showing `values`, the same as `glyph` in
+/// gpu-text.frag.
+pub fn decode_label(cells: &[i32], values: [f32; 4]) -> String {
+    let glyph = |code: i32| -> usize {
+        if code >= 0 {
+            return code as usize;
+        }
+
+        let code = (-code - 1) as u32;
+        let (field, decimals, place) = (code % 4, (code / 4) % 4, code / 16);
+        let n = (values[field as usize] * 10f32.powi(decimals as i32)).round() as u32;
+        let power = 10u32.pow(place);
+        if place > decimals && n < power {
+            return glyph_index(' ').unwrap();
+        }
+        (n / power % 10) as usize
+    };
+
+    cells.iter().map(|&code| FONT[glyph(code)].0).collect()
+}
+
+/// Signed distances to the edges of every glyph of the font, side by side,
+/// as the red of RGBA texels: 0.5 on the edge, more inside.
+pub fn sdf_atlas() -> (UVec2, Vec<u8>) {
+    let cell = (GLYPH + 2 * SDF_PADDING) * SDF_SCALE;
+    let size = uvec2(cell.x * FONT.len() as u32, cell.y);
+    let mut texels = vec![0u8; (size.x * size.y * 4) as usize];
+
+    for (index, (_, rows)) in FONT.iter().enumerate() {
+        let is_set = |x: i32, y: i32| {
+            let inside = (0..GLYPH.x as i32).contains(&x) && (0..GLYPH.y as i32).contains(&y);
+            inside && rows[y as usize] & (1 << (GLYPH.x as i32 - 1 - x)) != 0
+        };
+
+        for y in 0..cell.y {
+            for x in 0..cell.x {
+                let point = (vec2(x as f32, y as f32) + 0.5) / SDF_SCALE as f32;
+                let point = point - SDF_PADDING as f32;
+                let inside = is_set(point.x.floor() as i32, point.y.floor() as i32);
+
+                // to the nearest texel on the other side of the edge
+                let mut distance = SDF_RANGE;
+                let pad = SDF_PADDING as i32 + 1;
+                for ty in -pad..GLYPH.y as i32 + pad {
+                    for tx in -pad..GLYPH.x as i32 + pad {
+                        if is_set(tx, ty) != inside {
+                            let min = vec2(tx as f32, ty as f32);
+                            let outside = (min - point).max(point - min - 1.0).max(Vec2::ZERO);
+                            distance = distance.min(outside.length());
+                        }
+                    }
+                }
+
+                let signed = if inside { distance } else { -distance };
+                let value = 0.5 + signed / (2.0 * SDF_RANGE);
+                let texel = (y * size.x + index as u32 * cell.x + x) as usize;
+                texels[texel * 4..][..4].copy_from_slice(&[
+                    (value * 255.0).round() as u8,
+                    0,
+                    0,
+                    255,
+                ]);
+            }
+        }
+    }
+
+    (size, texels)
+}
+
+pub struct GpuText {
+    program: GLuint,
+    atlas: GLuint,
+    ubo: GLuint,
+    /// Empty, as core profiles need one bound to draw.
+    vao: GLuint,
+    /// Cells of the label actually used.
+    cells: usize,
+    /// What the block holds beyond the numbers, rewritten when it changes.
+    counter: Counter,
+}
+
+impl GpuText {
+    pub unsafe fn new() -> Result<Self, GlError> {
+        let program = create_shader_program(SRC_VERT_GPU_TEXT, SRC_FRAG_GPU_TEXT)?;
+        let block = gl::GetUniformBlockIndex(program, c"Counter".as_ptr());
+        gl::UniformBlockBinding(program, block, 0);
+        gl::UseProgram(program);
+        gl::Uniform1i(gl::GetUniformLocation(program, c"u_atlas".as_ptr()), 0);
+
+        let (size, texels) = sdf_atlas();
+        let mut atlas = 0;
+        gl::GenTextures(1, &mut atlas);
+        upload_texture(atlas, size.x, size.y, texels.as_ptr(), gl::CLAMP_TO_EDGE)?;
+
+        let mut label = [[0; 4]; CELLS / 4];
+        let space = glyph_index(' ').unwrap() as i32;
+        let encoded = encode_label();
+        let cells = encoded.len();
+        let codes = encoded.into_iter().chain(std::iter::repeat(space));
+        for (cell, code) in label.as_flattened_mut().iter_mut().zip(codes) {
+            *cell = code;
+        }
+        let counter = Counter {
+            values: [0.0; 4],
+            rect: [0.0; 4],
+            viewport: [0.0; 2],
+            scale: 1.0,
+            padding: 0.0,
+            text_color: [0.0; 4],
+            background: [0.0; 4],
+            label,
+        };
+
+        let mut ubo = 0;
+        gl::GenBuffers(1, &mut ubo);
+        buffer_data(gl::UNIFORM_BUFFER, ubo, &[counter], gl::DYNAMIC_DRAW);
+        gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+
+        Ok(Self {
+            program,
+            atlas,
+            ubo,
+            vao,
+            cells,
+            counter,
+        })
+    }
+
+    /// Draws `times` at the bottom left of the default framebuffer. Returns
+    /// how many bytes were uploaded for it.
+    pub unsafe fn draw(&mut self, times: &FrameTimes, viewport: &Viewport) -> usize {
+        let theme = theme::current();
+        let scale = viewport.px(SCALE).max(1.0);
+        let padding = viewport.px(PADDING);
+        let margin = viewport.px(MARGIN);
+        let extent = vec2(self.cells as f32, 1.0) * CELL.as_vec2() * scale + 2.0 * padding;
+        let min = vec2(margin, viewport.size().y - margin - extent.y);
+        let max = min + extent;
+
+        let counter = Counter {
+            values: [
+                times.fps(),
+                times.average() * 1000.0,
+                times.min() * 1000.0,
+                times.max() * 1000.0,
+            ],
+            rect: [min.x, min.y, max.x, max.y],
+            viewport: viewport.size().to_array(),
+            scale,
+            padding,
+            text_color: theme.hud_text,
+            background: theme.hud_background,
+            ..self.counter
+        };
+
+        // only the numbers change on most frames, and they come first
+        let changed = Counter {
+            values: counter.values,
+            ..self.counter
+        } != counter;
+        let uploaded = match changed {
+            true => mem::size_of::<Counter>(),
+            false => mem::size_of_val(&counter.values),
+        };
+        gl::BindBuffer(gl::UNIFORM_BUFFER, self.ubo);
+        gl::BufferSubData(
+            gl::UNIFORM_BUFFER,
+            0,
+            uploaded as GLsizeiptr,
+            &counter as *const Counter as *const _,
+        );
+        gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+        self.counter = counter;
+
+        let saved = SavedState::save();
+        let size = viewport.size();
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, size.x as GLsizei, size.y as GLsizei);
+        gl::Enable(gl::BLEND);
+        gl::BlendEquation(gl::FUNC_ADD);
+        gl::BlendFuncSeparate(
+            gl::SRC_ALPHA,
+            gl::ONE_MINUS_SRC_ALPHA,
+            gl::ONE,
+            gl::ONE_MINUS_SRC_ALPHA,
+        );
+
+        gl::UseProgram(self.program);
+        gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, self.ubo);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, self.atlas);
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+        gl::BindVertexArray(0);
+        gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, 0);
+
+        saved.restore();
+        uploaded
+    }
+}
+
+impl Drop for GpuText {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.program);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.ubo]);
+            delete_textures(&[self.atlas]);
+        }
+    }
+}
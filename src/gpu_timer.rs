@@ -0,0 +1,171 @@
+//! GPU timings through `GL_TIMESTAMP` queries.
+//!
+//! Query results only become available a few frames after being issued, so
+//! zones stay pending until then. Resolved zones update the last known
+//! duration of their name, and are forwarded to Tracy as GPU zones with the
+//! `tracy` feature.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::{BTreeMap, VecDeque};
+
+use gl::types::{GLint, GLuint, GLuint64};
+
+struct Zone {
+    name: &'static str,
+    start: GLuint,
+    end: Option<GLuint>,
+    #[cfg(feature = "tracy")]
+    span: Option<tracy_client::GpuSpan>,
+}
+
+pub struct GpuTimer {
+    free_queries: Vec<GLuint>,
+    /// Zones in the order they began.
+    pending: VecDeque<Zone>,
+    /// Last measured duration of each zone, in milliseconds.
+    durations: BTreeMap<&'static str, f32>,
+    /// Zones resolved by the last poll with their durations, in the order
+    /// they began.
+    resolved: Vec<(&'static str, f32)>,
+    #[cfg(feature = "tracy")]
+    context: Option<tracy_client::GpuContext>,
+}
+
+impl GpuTimer {
+    pub unsafe fn new() -> Self {
+        #[cfg(feature = "tracy")]
+        let context = {
+            let mut timestamp = 0;
+            gl::GetInteger64v(gl::TIMESTAMP, &mut timestamp);
+
+            // GL timestamps are in nanoseconds
+            tracy_client::Client::running().and_then(|client| {
+                let ty = tracy_client::GpuContextType::OpenGL;
+                (client.new_gpu_context(Some("OpenGL"), ty, timestamp, 1.0))
+                    .inspect_err(|e| eprintln!("Could not create Tracy GPU context: {e}"))
+                    .ok()
+            })
+        };
+
+        Self {
+            free_queries: Vec::new(),
+            pending: VecDeque::new(),
+            durations: BTreeMap::new(),
+            resolved: Vec::new(),
+            #[cfg(feature = "tracy")]
+            context,
+        }
+    }
+
+    /// Starts timing the GPU commands issued from now on. Zones can be nested.
+    pub unsafe fn begin(&mut self, name: &'static str) {
+        let start = self.query();
+
+        #[cfg(feature = "tracy")]
+        let span = (self.context.as_ref())
+            .and_then(|context| context.span_alloc(name, "", file!(), line!()).ok());
+
+        gl::QueryCounter(start, gl::TIMESTAMP);
+        self.pending.push_back(Zone {
+            name,
+            start,
+            end: None,
+            #[cfg(feature = "tracy")]
+            span,
+        });
+    }
+
+    /// Ends the innermost zone that is still open.
+    pub unsafe fn end(&mut self) {
+        let end = self.query();
+
+        let zone = (self.pending.iter_mut().rev())
+            .find(|zone| zone.end.is_none())
+            .expect("GpuTimer::end called without a matching begin");
+
+        gl::QueryCounter(end, gl::TIMESTAMP);
+        zone.end = Some(end);
+
+        #[cfg(feature = "tracy")]
+        if let Some(span) = &mut zone.span {
+            span.end_zone();
+        }
+    }
+
+    /// Collects the results of finished zones. Call once per frame.
+    pub unsafe fn poll(&mut self) {
+        let mut resolved = Vec::new();
+        self.resolved.clear();
+
+        // queries complete in order, so the first unavailable one blocks the rest
+        while let Some(&Zone { end: Some(end), .. }) = self.pending.front() {
+            let mut available: GLint = 0;
+            gl::GetQueryObjectiv(end, gl::QUERY_RESULT_AVAILABLE, &mut available);
+            if available == 0 {
+                break;
+            }
+
+            let zone = self.pending.pop_front().unwrap();
+
+            let (mut start_time, mut end_time): (GLuint64, GLuint64) = (0, 0);
+            gl::GetQueryObjectui64v(zone.start, gl::QUERY_RESULT, &mut start_time);
+            gl::GetQueryObjectui64v(end, gl::QUERY_RESULT, &mut end_time);
+
+            let duration = end_time.saturating_sub(start_time) as f32 * 1e-6;
+            self.durations.insert(zone.name, duration);
+            self.resolved.push((zone.name, duration));
+            self.free_queries.extend([zone.start, end]);
+
+            resolved.push((zone, start_time, end_time));
+        }
+
+        // Tracy wants timestamps in increasing order, which is not the order
+        // nested zones end in
+        #[cfg(feature = "tracy")]
+        {
+            let mut timestamps = (resolved.iter())
+                .flat_map(|(zone, start, end)| [(*start, zone, true), (*end, zone, false)])
+                .collect::<Vec<_>>();
+            timestamps.sort_by_key(|&(time, ..)| time);
+
+            for (time, zone, is_start) in timestamps {
+                match &zone.span {
+                    Some(span) if is_start => span.upload_timestamp_start(time as i64),
+                    Some(span) => span.upload_timestamp_end(time as i64),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    /// Every zone the last [`Self::poll`] resolved, for those that need each
+    /// measurement rather than the last one.
+    pub fn resolved(&self) -> &[(&'static str, f32)] {
+        &self.resolved
+    }
+
+    /// Last measured duration of a zone in milliseconds.
+    pub fn duration(&self, name: &str) -> Option<f32> {
+        self.durations.get(name).copied()
+    }
+
+    unsafe fn query(&mut self) -> GLuint {
+        self.free_queries.pop().unwrap_or_else(|| {
+            let mut query = 0;
+            gl::GenQueries(1, &mut query);
+            query
+        })
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        let pending = (self.pending.iter()).flat_map(|zone| [Some(zone.start), zone.end]);
+        let queries = (self.free_queries.iter().copied())
+            .chain(pending.flatten())
+            .collect::<Vec<_>>();
+
+        unsafe { gl::DeleteQueries(queries.len() as i32, queries.as_ptr()) };
+    }
+}
@@ -0,0 +1,106 @@
+//! Offscreen rendering without a window, through an EGL pbuffer.
+//!
+//! Mesa's software rasterizer is enough to run it, so it works on CI machines
+//! without a GPU or a display server.
+
+use std::error::Error;
+use std::ffi::CString;
+use std::num::NonZeroU32;
+
+use glam::UVec2;
+use glutin::api::egl::context::PossiblyCurrentContext;
+use glutin::api::egl::device::Device;
+use glutin::api::egl::display::Display;
+use glutin::api::egl::surface::Surface;
+use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
+use glutin::prelude::*;
+use glutin::surface::{PbufferSurface, SurfaceAttributesBuilder};
+use winit::dpi::PhysicalSize;
+
+use crate::gl_ext;
+#[cfg(feature = "plugins")]
+use crate::plugin;
+
+/// A current GL context rendering to an offscreen surface of a fixed size.
+pub struct HeadlessContext {
+    size: UVec2,
+    // the surface must go before the context
+    _surface: Surface<PbufferSurface>,
+    _context: PossiblyCurrentContext,
+}
+
+impl HeadlessContext {
+    /// Creates a GL 3.3+ core context on the first EGL device that supports
+    /// one, makes it current and loads the GL functions.
+    pub fn new(size: UVec2) -> Result<Self, Box<dyn Error>> {
+        let width = NonZeroU32::new(size.x).ok_or("width must not be zero")?;
+        let height = NonZeroU32::new(size.y).ok_or("height must not be zero")?;
+
+        let mut last_error: Box<dyn Error> = "no EGL device found".into();
+        for device in Device::query_devices()? {
+            match unsafe { Self::with_device(&device, size, width, height) } {
+                Ok(context) => return Ok(context),
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(last_error)
+    }
+
+    unsafe fn with_device(
+        device: &Device,
+        size: UVec2,
+        width: NonZeroU32,
+        height: NonZeroU32,
+    ) -> Result<Self, Box<dyn Error>> {
+        let display = Display::with_device(device, None)?;
+
+        let template = ConfigTemplateBuilder::new()
+            .with_alpha_size(8)
+            .with_surface_type(ConfigSurfaceTypes::PBUFFER)
+            .build();
+        let config = (display.find_configs(template)?)
+            .next()
+            .ok_or("no pbuffer config")?;
+
+        let context_attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::OpenGl(Some(Version::new(3, 3))))
+            .build(None);
+        let context = display.create_context(&config, &context_attributes)?;
+
+        let surface_attributes =
+            SurfaceAttributesBuilder::<PbufferSurface>::new().build(width, height);
+        let surface = display.create_pbuffer_surface(&config, &surface_attributes)?;
+        let context = context.make_current(&surface)?;
+
+        gl::load_with(|symbol| {
+            let symbol = CString::new(symbol).unwrap();
+            display.get_proc_address(symbol.as_c_str()).cast()
+        });
+        gl_ext::load_with(|symbol| {
+            let symbol = CString::new(symbol).unwrap();
+            display.get_proc_address(symbol.as_c_str()).cast()
+        });
+        #[cfg(feature = "plugins")]
+        {
+            let plugin_display = display.clone();
+            plugin::set_gl_loader(move |symbol| plugin_display.get_proc_address(symbol).cast());
+        }
+
+        Ok(Self {
+            size,
+            _surface: surface,
+            _context: context,
+        })
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// The size as scenes expect it.
+    pub fn physical_size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.size.x, self.size.y)
+    }
+}
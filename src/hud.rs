@@ -0,0 +1,376 @@
+//! Overlay drawn on top of every scene: a help panel listing the global keys,
+//! short-lived toasts about what just happened, a tooltip about what is under
+//! the mouse, what the scene says it's doing, the GPU memory in use and
+//! sliders for the parameters of the scene.
+//!
+//! Every string goes through [`locale::tr`], so the overlay follows the
+//! language picked at runtime. With frosted glass on, the panels blur the
+//! scene behind them, see [`crate::frosted`].
+
+#![allow(clippy::missing_safety_doc)]
+
+use glam::{vec2, Vec2};
+
+use crate::console::Console;
+use crate::error::GlError;
+use crate::frosted::Frosted;
+use crate::gpu_memory::{self, format_bytes, Category, DriverMemoryInfo};
+use crate::locale::{tr, tr_args};
+use crate::params::Params;
+use crate::params_panel::ParamsPanel;
+use crate::scenes::Scenes;
+use crate::text::SharedText;
+use crate::theme;
+use crate::tween::Easing;
+use crate::viewport::Viewport;
+
+/// Seconds a toast stays on screen, fading out at the end.
+const TOAST_DURATION: f32 = 2.5;
+const TOAST_FADE: f32 = 0.4;
+/// Toasts beyond this many push out the oldest one.
+const MAX_TOASTS: usize = 4;
+
+/// Sizes in logical pixels.
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 12.0;
+const TITLE_SIZE: f32 = 22.0;
+const TEXT_SIZE: f32 = 15.0;
+const HELP_WIDTH: f32 = 460.0;
+
+/// Keys handled by the app itself, with the key of their description.
+const GLOBAL_KEYS: &[(&str, &str)] = &[
+    ("PageDown / PageUp", "help.keys.scenes"),
+    ("Home", "help.keys.home"),
+    ("Tab", "help.keys.help"),
+    ("End", "help.keys.locale"),
+    ("`", "help.keys.console"),
+    ("F5", "help.keys.reseed"),
+    ("F6", "help.keys.snapshot"),
+    ("F9", "help.keys.memory"),
+    ("F10", "help.keys.export"),
+    ("F11", "help.keys.theme"),
+    ("F12", "help.keys.screenshot"),
+    ("Insert", "help.keys.frosted"),
+    ("\\", "help.keys.split"),
+    ("/", "help.keys.stereo"),
+    (";", "help.keys.post_select"),
+    ("'", "help.keys.post_toggle"),
+    ("[ / ]", "help.keys.post_move"),
+    ("Esc", "help.keys.quit"),
+];
+
+#[derive(Debug)]
+struct Toast {
+    message: String,
+    remaining: f32,
+}
+
+pub struct Hud {
+    /// Shared with the scenes.
+    text: SharedText,
+    help: bool,
+    toasts: Vec<Toast>,
+    pub console: Console,
+    pub params: ParamsPanel,
+    /// Created the first time frosted glass is turned on.
+    frosted: Option<Frosted>,
+    is_frosted: bool,
+    /// Text next to the mouse, in pixels.
+    tooltip: Option<(String, Vec2)>,
+    /// Text from the scene, at the bottom right.
+    status: Option<String>,
+    /// Whether the GPU memory in use shows above the status.
+    memory: bool,
+}
+
+impl Hud {
+    pub fn new(text: SharedText) -> Self {
+        Self {
+            text,
+            help: false,
+            toasts: Vec::new(),
+            console: Console::new(),
+            params: ParamsPanel::new(),
+            frosted: None,
+            is_frosted: false,
+            tooltip: None,
+            status: None,
+            memory: false,
+        }
+    }
+
+    pub fn toggle_help(&mut self) {
+        self.help = !self.help;
+    }
+
+    /// Shows or hides the GPU memory in use.
+    pub fn toggle_memory(&mut self) {
+        self.memory = !self.memory;
+    }
+
+    /// Turns frosted glass on or off, returning whether it is now on.
+    pub fn toggle_frosted(&mut self) -> bool {
+        self.is_frosted = !self.is_frosted;
+        self.is_frosted
+    }
+
+    /// Color of the panels.
+    fn background(&self) -> [f32; 4] {
+        let theme = theme::current();
+        match self.is_frosted {
+            true => theme.hud_frosted,
+            false => theme.hud_background,
+        }
+    }
+
+    /// Shows `message` for a couple of seconds.
+    pub fn toast(&mut self, message: impl Into<String>) {
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+
+        self.toasts.push(Toast {
+            message: message.into(),
+            remaining: TOAST_DURATION,
+        });
+    }
+
+    /// Shows `text` next to the mouse at `mouse_pos` on the next frame.
+    pub fn set_tooltip(&mut self, text: Option<String>, mouse_pos: Vec2) {
+        self.tooltip = text.map(|text| (text, mouse_pos));
+    }
+
+    /// Shows `text` from the scene at the bottom right on the next frame.
+    pub fn set_status(&mut self, text: Option<String>) {
+        self.status = text;
+    }
+
+    /// Ages the toasts by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        for toast in &mut self.toasts {
+            toast.remaining -= dt;
+        }
+        self.toasts.retain(|toast| toast.remaining > 0.0);
+    }
+
+    /// Draws the overlay over the default framebuffer, laid out in logical
+    /// pixels of `viewport`, with `params` those of the current scene.
+    pub unsafe fn draw(&mut self, scene: &str, params: Option<&Params>, viewport: &Viewport) {
+        let background = self.background();
+
+        // both sit at the top, the console wins
+        if self.console.is_open() {
+            (self.console).queue(&mut self.text.borrow_mut(), viewport, background);
+        } else if self.help {
+            self.queue_help(scene, viewport, background);
+        }
+        // the console spans the whole width, so it covers the panel too
+        if self.console.is_open() {
+            self.params.hide();
+        } else {
+            (self.params).queue(&mut self.text.borrow_mut(), viewport, params, background);
+        }
+        self.queue_toasts(viewport, background);
+        self.queue_corner(viewport, background);
+        self.queue_tooltip(viewport, background);
+
+        let viewport = viewport.size();
+
+        if self.is_frosted {
+            if let Err(e) = self.draw_frosted(viewport, background[3]) {
+                eprintln!("Frosted glass turned off: {e}");
+                self.frosted = None;
+                self.is_frosted = false;
+                self.text.borrow_mut().draw(0, viewport);
+            }
+        } else {
+            self.text.borrow_mut().draw(0, viewport);
+        }
+    }
+
+    unsafe fn draw_frosted(&mut self, viewport: Vec2, panel_alpha: f32) -> Result<(), GlError> {
+        let frosted = match &mut self.frosted {
+            Some(frosted) => frosted,
+            None => self.frosted.insert(Frosted::new()?),
+        };
+        frosted.draw(&mut self.text.borrow_mut(), viewport, panel_alpha)
+    }
+
+    fn queue_help(&mut self, scene: &str, viewport: &Viewport, background: [f32; 4]) {
+        let theme = theme::current();
+        let mut text = self.text.borrow_mut();
+
+        let min = Vec2::splat(viewport.px(MARGIN));
+        let width = viewport.px(HELP_WIDTH);
+        let size = viewport.px(TEXT_SIZE);
+        let padding = viewport.px(PADDING);
+        let mut pen = min + padding;
+
+        let title = tr("help.title");
+        pen.y += text
+            .queue(&title, pen, viewport.px(TITLE_SIZE), None, theme.hud_text)
+            .y;
+
+        let names = Scenes::names();
+        let index = (names.iter()).position(|&name| name == scene).unwrap_or(0);
+        let heading = tr_args(
+            "help.scene",
+            &[
+                ("index", &(index + 1).to_string()),
+                ("count", &names.len().to_string()),
+                ("name", &Scenes::title(scene)),
+            ],
+        );
+        pen.y += text.queue(&heading, pen, size, Some(width), theme.accent).y;
+
+        let description = Scenes::description(scene);
+        pen.y += text
+            .queue(&description, pen, size, Some(width), theme.hud_text)
+            .y;
+        pen.y += size;
+
+        pen.y += text
+            .queue(&tr("help.global"), pen, size, None, theme.accent)
+            .y;
+
+        let key_width = (GLOBAL_KEYS.iter())
+            .map(|(key, _)| text.measure(key, size, None).x)
+            .fold(0.0, f32::max);
+        let column = (key_width + padding).round();
+
+        for (key, description) in GLOBAL_KEYS {
+            text.queue(key, pen, size, None, theme.grid);
+            let description_pos = vec2(pen.x + column, pen.y);
+            let description_width = Some(width - column);
+            let description = tr(description);
+            let color = theme.hud_text;
+            pen.y += (text.queue(
+                &description,
+                description_pos,
+                size,
+                description_width,
+                color,
+            ))
+            .y;
+        }
+
+        let max = vec2(min.x + width + 2.0 * padding, pen.y + padding);
+        text.queue_rect(min, max, background);
+    }
+
+    fn queue_tooltip(&mut self, viewport: &Viewport, background: [f32; 4]) {
+        let Some((tooltip, mouse_pos)) = &self.tooltip else {
+            return;
+        };
+
+        let mut text = self.text.borrow_mut();
+        let size = viewport.px(TEXT_SIZE);
+        let padding = Vec2::splat(viewport.px(PADDING / 2.0));
+        let extent = text.measure(tooltip, size, None);
+
+        // below and right of the cursor, but always on screen
+        let offset = Vec2::splat(viewport.px(MARGIN));
+        let min = (*mouse_pos + offset).min(viewport.size() - extent - 2.0 * padding);
+        let min = min.round();
+        let max = min + extent + 2.0 * padding;
+
+        let color = theme::current().hud_text;
+        text.queue(tooltip, min + padding, size, None, color);
+        text.queue_rect(min, max, background);
+    }
+
+    /// Queues the status of the scene at the bottom right, with the GPU
+    /// memory in use above it.
+    unsafe fn queue_corner(&mut self, viewport: &Viewport, background: [f32; 4]) {
+        let memory = self.memory.then(|| memory_report());
+        let panels = [self.status.as_deref(), memory.as_deref()];
+
+        let theme = theme::current();
+        let mut text = self.text.borrow_mut();
+        let size = viewport.px(TEXT_SIZE);
+        let padding = Vec2::splat(viewport.px(PADDING));
+        let gap = viewport.px(PADDING / 2.0);
+        let mut corner = viewport.size() - Vec2::splat(viewport.px(MARGIN));
+
+        for panel in panels.into_iter().flatten() {
+            let extent = text.measure(panel, size, None);
+            let min = (corner - extent - 2.0 * padding).round();
+            let max = min + extent + 2.0 * padding;
+
+            text.queue(panel, min + padding, size, None, theme.hud_text);
+            text.queue_rect(min, max, background);
+
+            corner.y = min.y - gap;
+        }
+    }
+
+    fn queue_toasts(&mut self, viewport: &Viewport, background: [f32; 4]) {
+        let theme = theme::current();
+        let mut text = self.text.borrow_mut();
+        let size = viewport.px(TEXT_SIZE);
+        let padding = Vec2::splat(viewport.px(PADDING));
+        let gap = viewport.px(PADDING / 2.0);
+        let mut bottom = viewport.size().y - viewport.px(MARGIN);
+        let viewport = viewport.size();
+
+        // newest at the bottom
+        for toast in self.toasts.iter().rev() {
+            let fade = Easing::QuadOut.ease(toast.remaining / TOAST_FADE);
+            let with_alpha = |[r, g, b, a]: [f32; 4]| [r, g, b, a * fade];
+
+            let extent = text.measure(&toast.message, size, None);
+            let min = vec2((viewport.x - extent.x) / 2.0, bottom - extent.y) - padding;
+            let min = min.round();
+            let max = min + extent + 2.0 * padding;
+
+            let color = with_alpha(theme.hud_text);
+            text.queue(&toast.message, min + padding, size, None, color);
+            text.queue_rect(min, max, with_alpha(background));
+
+            bottom = min.y - gap;
+        }
+    }
+}
+
+/// What we allocated on the GPU per category, and what the driver says is
+/// left if it can tell.
+unsafe fn memory_report() -> String {
+    let mut lines = vec![tr("memory.title")];
+    for category in Category::ALL {
+        let name = tr(&format!("memory.{}", category.name()));
+        let size = format_bytes(gpu_memory::allocated(category));
+        lines.push(tr_args(
+            "memory.category",
+            &[("category", &name), ("size", &size)],
+        ));
+    }
+
+    lines.push(match gpu_memory::query_driver() {
+        Some(DriverMemoryInfo::Nvx {
+            dedicated_kb,
+            total_available_kb,
+            current_available_kb,
+        }) => tr_args(
+            "memory.nvx",
+            &[
+                ("available", &current_available_kb.to_string()),
+                ("total", &total_available_kb.to_string()),
+                ("dedicated", &dedicated_kb.to_string()),
+            ],
+        ),
+        Some(DriverMemoryInfo::Ati {
+            vbo_free_kb,
+            texture_free_kb,
+            renderbuffer_free_kb,
+        }) => tr_args(
+            "memory.ati",
+            &[
+                ("textures", &texture_free_kb.to_string()),
+                ("buffers", &vbo_free_kb.to_string()),
+                ("renderbuffers", &renderbuffer_free_kb.to_string()),
+            ],
+        ),
+        None => tr("memory.none"),
+    });
+    lines.join("\n")
+}
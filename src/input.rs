@@ -0,0 +1,125 @@
+//! The mouse pointer, kept at the precision winit reports it with.
+//!
+//! High-rate mice report positions many times per frame. The raw position is
+//! the latest of them, while all of them are kept until the end of the frame
+//! for whatever wants the path in between. The smoothed position follows the
+//! raw one once per frame, with an exponential decay whose time constant is
+//! picked on the command line, so that a scene reacting to the mouse, like
+//! the spinning quads of the round quads scene, glides between positions
+//! instead of jumping with every event.
+//!
+//! Key presses are handled as [`KeyInput`], which unlike winit's events can
+//! be made up, as when replaying a recorded session.
+
+use glam::{DVec2, Vec2};
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::{Key, PhysicalKey, SmolStr};
+
+pub const DEFAULT_SMOOTHING_MS: f64 = 0.0;
+
+#[derive(Debug, Clone)]
+pub struct Pointer {
+    /// Latest position reported, in physical pixels.
+    raw: DVec2,
+    smoothed: DVec2,
+    /// Positions reported since the last frame, oldest first.
+    pending: Vec<DVec2>,
+    /// Positions reported during the last frame, oldest first.
+    samples: Vec<DVec2>,
+    /// Time constant of the smoothing in seconds, 0 to follow the raw
+    /// position as is.
+    smoothing: f64,
+    /// Whether a position was reported yet, before which the smoothed
+    /// position has nothing to catch up from.
+    has_moved: bool,
+}
+
+impl Pointer {
+    pub fn new(smoothing_ms: f64) -> Self {
+        Self {
+            raw: DVec2::ZERO,
+            smoothed: DVec2::ZERO,
+            pending: Vec::new(),
+            samples: Vec::new(),
+            smoothing: smoothing_ms.max(0.0) / 1000.0,
+            has_moved: false,
+        }
+    }
+
+    /// Records a position reported by the window.
+    pub fn moved(&mut self, position: PhysicalPosition<f64>) {
+        self.raw = DVec2::new(position.x, position.y);
+        self.pending.push(self.raw);
+        if !self.has_moved {
+            self.smoothed = self.raw;
+            self.has_moved = true;
+        }
+    }
+
+    /// Ends a frame `dt` seconds long: the smoothed position catches up with
+    /// the raw one, and the positions reported during it become
+    /// [`Self::samples`].
+    pub fn update(&mut self, dt: f32) {
+        self.samples.clear();
+        self.samples.append(&mut self.pending);
+
+        if self.smoothing <= 0.0 {
+            self.smoothed = self.raw;
+            return;
+        }
+
+        let amount = 1.0 - (-dt as f64 / self.smoothing).exp();
+        self.smoothed += (self.raw - self.smoothed) * amount;
+    }
+
+    /// Latest position, for picking and anything that has to be exact.
+    pub fn raw(&self) -> DVec2 {
+        self.raw
+    }
+
+    /// Position following [`Self::raw`] smoothly, for scenes to react to.
+    pub fn smoothed(&self) -> DVec2 {
+        self.smoothed
+    }
+
+    /// Every position reported during the last frame, oldest first, for
+    /// strokes that shouldn't miss what happened between frames.
+    pub fn samples(&self) -> &[DVec2] {
+        &self.samples
+    }
+
+    /// [`Self::raw`] in the single precision of the rest of the playground.
+    pub fn position(&self) -> Vec2 {
+        self.raw.as_vec2()
+    }
+
+    /// [`Self::smoothed`] in single precision.
+    pub fn smoothed_position(&self) -> Vec2 {
+        self.smoothed.as_vec2()
+    }
+}
+
+/// What the app looks at in a key event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyInput {
+    pub logical_key: Key<SmolStr>,
+    pub physical_key: PhysicalKey,
+    pub state: ElementState,
+    /// Whether the OS repeats a held key.
+    pub repeat: bool,
+    /// Text the key types, if any.
+    pub text: Option<SmolStr>,
+}
+
+impl From<&KeyEvent> for KeyInput {
+    fn from(event: &KeyEvent) -> Self {
+        Self {
+            logical_key: event.logical_key.clone(),
+            physical_key: event.physical_key,
+            state: event.state,
+            repeat: event.repeat,
+            text: event.text.clone(),
+        }
+    }
+}
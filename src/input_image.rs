@@ -0,0 +1,95 @@
+//! The image fed to the blur scenes, either the embedded Gura JPEG or
+//! something dropped onto the window.
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use glam::{uvec2, UVec2};
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageResult, RgbaImage};
+
+const GURA_JPG: &[u8] = include_bytes!("../assets/gura.jpg");
+// const BIG_SQUARES_PNG: &[u8] = include_bytes!("../assets/big-squares.png");
+
+/// Default limit on the largest side of an input image. Every blur layer
+/// allocates framebuffers proportional to the input size, so an 8K photo
+/// adds up quickly.
+pub const DEFAULT_MAX_DIMENSION: u32 = 4096;
+
+pub struct InputImage {
+    /// Where the image was loaded from, `None` for the embedded one or one
+    /// made by a scene.
+    pub path: Option<PathBuf>,
+    /// The image as it was loaded, kept around for offline processing.
+    pub original: Rc<RgbaImage>,
+    /// The image that actually gets uploaded to the GPU.
+    pub image: Rc<RgbaImage>,
+}
+
+impl InputImage {
+    /// Size of the image that gets uploaded.
+    pub fn size(&self) -> UVec2 {
+        uvec2(self.image.width(), self.image.height())
+    }
+
+    pub fn gura(max_dimension: u32) -> Self {
+        let gura = image::load_from_memory_with_format(GURA_JPG, ImageFormat::Jpeg);
+        // let gura = image::load_from_memory_with_format(BIG_SQUARES_PNG, ImageFormat::Png);
+        Self::from_image(None, gura.unwrap().into_rgba8(), max_dimension)
+    }
+
+    pub fn open(path: &Path, max_dimension: u32) -> ImageResult<Self> {
+        let image = image::open(path)?.into_rgba8();
+        Ok(Self::from_image(
+            Some(path.to_path_buf()),
+            image,
+            max_dimension,
+        ))
+    }
+
+    /// Wraps an image made at runtime, e.g. by the Gray-Scott scene.
+    pub fn from_pixels(image: RgbaImage, max_dimension: u32) -> Self {
+        Self::from_image(None, image, max_dimension)
+    }
+
+    fn from_image(path: Option<PathBuf>, original: RgbaImage, max_dimension: u32) -> Self {
+        let original = Rc::new(original);
+
+        let (width, height) = original.dimensions();
+        let image = match fit_within(width, height, max_dimension) {
+            Some((new_width, new_height)) => {
+                println!(
+                    "Downscaling input image from {width}x{height} to {new_width}x{new_height}"
+                );
+                let resized = image::imageops::resize(
+                    &*original,
+                    new_width,
+                    new_height,
+                    FilterType::Lanczos3,
+                );
+                Rc::new(resized)
+            }
+            None => original.clone(),
+        };
+
+        Self {
+            path,
+            original,
+            image,
+        }
+    }
+}
+
+/// New dimensions preserving the aspect ratio so that neither side exceeds
+/// `max_dimension`, or `None` if the image already fits.
+fn fit_within(width: u32, height: u32, max_dimension: u32) -> Option<(u32, u32)> {
+    let largest = width.max(height);
+    if largest <= max_dimension {
+        return None;
+    }
+
+    let ratio = max_dimension as f64 / largest as f64;
+    let new_width = ((width as f64 * ratio).round() as u32).max(1);
+    let new_height = ((height as f64 * ratio).round() as u32).max(1);
+    Some((new_width, new_height))
+}
@@ -0,0 +1,75 @@
+//! The playground's scenes and everything around them. The windowed app
+//! lives in `main.rs`, and tests render the scenes headlessly through
+//! [`headless`].
+
+pub mod ab;
+pub mod adjust;
+pub mod assets;
+pub mod barnes_hut;
+pub mod bindings;
+pub mod block_compression;
+pub mod blue_noise;
+pub mod camera;
+pub mod cli;
+pub mod cloth;
+pub mod common_gl;
+pub mod console;
+pub mod data_grid;
+pub mod delaunay;
+pub mod downsample;
+pub mod error;
+pub mod export;
+pub mod fps_counter;
+pub mod frosted;
+pub mod gl_ext;
+pub mod gpu_memory;
+pub mod gpu_text;
+pub mod gpu_timer;
+pub mod headless;
+pub mod hud;
+pub mod input;
+pub mod input_image;
+pub mod locale;
+pub mod marching_squares;
+#[cfg(feature = "midi")]
+pub mod midi;
+pub mod output;
+pub mod palette;
+pub mod params;
+pub mod params_panel;
+pub mod pipeline_stats;
+pub mod placement;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod post;
+pub mod present;
+pub mod profiling;
+pub mod recorder;
+pub mod remote;
+pub mod render_scale;
+pub mod render_state;
+pub mod replay;
+pub mod scene_controller;
+pub mod scenes;
+#[cfg(feature = "screen-capture")]
+pub mod screen_capture;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod sequencer;
+pub mod session;
+pub mod shader_validation;
+pub mod snapshot;
+pub mod soft_body;
+pub mod spatial;
+pub mod spline;
+pub mod split_view;
+pub mod stereo;
+pub mod surface;
+pub mod text;
+pub mod theme;
+pub mod thumbnails;
+pub mod tween;
+pub mod viewport;
+pub mod watchdog;
+#[cfg(feature = "webcam")]
+pub mod webcam;
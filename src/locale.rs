@@ -0,0 +1,149 @@
+//! User-facing strings of the on-screen text, looked up by key in a table per
+//! locale.
+//!
+//! Tables are flat TOML files in `assets/locales`, with `{name}` placeholders
+//! filled in by [`tr_args`]. Keys missing from a table fall back to English,
+//! then to the key itself, so that a half-translated locale stays usable.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+const SRC_EN: &str = include_str!("../assets/locales/en.toml");
+const SRC_JA: &str = include_str!("../assets/locales/ja.toml");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    Japanese,
+}
+
+impl Locale {
+    pub const ALL: &'static [Locale] = &[Self::English, Self::Japanese];
+
+    /// BCP 47 language code, as given to `--locale`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Japanese => "ja",
+        }
+    }
+
+    /// Name of the locale in its own language.
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Japanese => "日本語",
+        }
+    }
+
+    /// The locale of the environment (`LC_ALL`, `LC_MESSAGES` or `LANG`), if
+    /// there is a table for it.
+    pub fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| value.split(['_', '.', '-']).next()?.parse().ok())
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Self::English => SRC_EN,
+            Self::Japanese => SRC_JA,
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        (Self::ALL.iter())
+            .find(|locale| locale.code().eq_ignore_ascii_case(code))
+            .copied()
+            .ok_or_else(|| format!("no strings for locale '{code}'"))
+    }
+}
+
+/// Parses a table, joining the keys of nested tables with dots.
+pub fn parse_table(source: &str) -> Result<HashMap<String, String>, toml::de::Error> {
+    fn flatten(prefix: &str, table: toml::Table, out: &mut HashMap<String, String>) {
+        for (key, value) in table {
+            let key = match prefix {
+                "" => key,
+                _ => format!("{prefix}.{key}"),
+            };
+
+            match value {
+                toml::Value::String(string) => {
+                    out.insert(key, string);
+                }
+                toml::Value::Table(table) => flatten(&key, table, out),
+                other => {
+                    out.insert(key, other.to_string());
+                }
+            }
+        }
+    }
+
+    let mut strings = HashMap::new();
+    flatten("", toml::from_str(source)?, &mut strings);
+    Ok(strings)
+}
+
+/// The strings of `locale`, parsed on first use.
+pub fn table(locale: Locale) -> &'static HashMap<String, String> {
+    static TABLES: OnceLock<Vec<HashMap<String, String>>> = OnceLock::new();
+
+    let tables = TABLES.get_or_init(|| {
+        (Locale::ALL.iter())
+            .map(|locale| {
+                parse_table(locale.source()).unwrap_or_else(|e| {
+                    panic!("invalid strings for locale '{}': {e}", locale.code())
+                })
+            })
+            .collect()
+    });
+
+    let index = Locale::ALL.iter().position(|&l| l == locale).unwrap();
+    &tables[index]
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+pub fn current() -> Locale {
+    Locale::ALL[CURRENT.load(Ordering::Relaxed) as usize]
+}
+
+pub fn set_current(locale: Locale) {
+    let index = Locale::ALL.iter().position(|&l| l == locale).unwrap();
+    CURRENT.store(index as u8, Ordering::Relaxed);
+}
+
+/// Switches to the next locale. Returns the new one.
+pub fn cycle() -> Locale {
+    let index = Locale::ALL.iter().position(|&l| l == current()).unwrap();
+    let next = Locale::ALL[(index + 1) % Locale::ALL.len()];
+    set_current(next);
+    next
+}
+
+/// The string for `key` in the current locale.
+pub fn tr(key: &str) -> String {
+    (table(current()).get(key))
+        .or_else(|| table(Locale::English).get(key))
+        .map_or(key, String::as_str)
+        .to_string()
+}
+
+/// The string for `key` in the current locale, with each `{name}` replaced by
+/// its value.
+pub fn tr_args(key: &str, args: &[(&str, &str)]) -> String {
+    let mut string = tr(key);
+    for (name, value) in args {
+        string = string.replace(&format!("{{{name}}}"), value);
+    }
+    string
+}
@@ -2,12 +2,14 @@ use std::{
     collections::HashSet,
     ffi::{c_void, CStr, CString},
     num::NonZeroU32,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::atomic::Ordering,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use gl::types::{GLchar, GLenum, GLsizei, GLuint};
-use glam::{IVec2, Vec2};
+use glam::{ivec2, uvec2, UVec2, Vec2};
 use glutin::{
     config::{Config, ConfigTemplateBuilder, GlConfig as _},
     context::{
@@ -18,34 +20,213 @@ use glutin::{
     surface::{GlSurface as _, Surface, SwapInterval, WindowSurface},
 };
 use glutin_winit::{DisplayBuilder, GlWindow as _};
-use scene_controller::SceneController;
-use scenes::Scenes;
+#[cfg(feature = "midi")]
+use opengl_playground::midi;
+#[cfg(feature = "plugins")]
+use opengl_playground::plugin::{self, Plugin};
+#[cfg(feature = "scripting")]
+use opengl_playground::scenes::scripted::ScriptedScene;
+#[cfg(feature = "screen-capture")]
+use opengl_playground::screen_capture;
+#[cfg(feature = "webcam")]
+use opengl_playground::webcam;
+use opengl_playground::{
+    ab::{AbHarness, Mode as AbMode, Report as AbReport, Variant},
+    assets,
+    bindings::{Action, Bindings, KeyRepeat},
+    camera::Camera,
+    cli::Args,
+    common_gl, console,
+    data_grid::DataGrid,
+    export,
+    fps_counter::FpsCounter,
+    gl_ext, gpu_memory,
+    gpu_timer::GpuTimer,
+    headless::HeadlessContext,
+    hud::Hud,
+    input::{KeyInput, Pointer},
+    input_image::InputImage,
+    locale::{self, tr, tr_args, Locale},
+    output::{self, OutputMode},
+    placement::{self, Monitor},
+    post::{PassKind, PostStack},
+    present::Presenter,
+    profiling,
+    recorder::Recording,
+    remote::{Command as RemoteCommand, RemoteServer},
+    render_scale::{Region, RenderScale},
+    render_state,
+    replay::{self, ReplayBuffer},
+    scene_controller::{SceneController, FIXED_DT},
+    scenes::{SceneContext, Scenes},
+    sequencer::{self, Cue, Sequencer},
+    session::{InputEvent, Player, Recorder},
+    shader_validation::Validation,
+    snapshot::Snapshot,
+    split_view::{Side, SplitView},
+    stereo::{Eye, Stereo, StereoMode},
+    surface::{self, Compositing},
+    text::{SharedText, TextRenderer},
+    theme, thumbnails,
+    tween::{Easing, Tweener},
+    viewport::Viewport,
+    watchdog::{self, Watchdog},
+};
+use rand::{rngs::StdRng, Rng as _, SeedableRng as _};
+use serde_json::json;
 use winit::{
     application::ApplicationHandler,
-    event::{ElementState, KeyEvent, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-    keyboard::{Key, NamedKey},
+    keyboard::{Key, ModifiersState},
+    monitor::MonitorHandle,
     raw_window_handle::HasWindowHandle as _,
     window::{Theme, Window, WindowAttributes},
 };
 
-pub mod camera;
-pub mod common_gl;
-pub mod scene_controller;
-pub mod scenes;
-
 fn main() {
+    let args = Args::parse();
+    let _profiler = profiling::start();
+
+    if args.validate_shaders {
+        validate_shaders();
+    }
+
+    locale::set_current(
+        args.locale
+            .or_else(Locale::from_env)
+            .unwrap_or(Locale::English),
+    );
+
+    #[cfg(feature = "plugins")]
+    for path in &args.plugins {
+        let plugin = unsafe { Plugin::load(path) };
+        if let Err(e) = plugin.and_then(|plugin| plugin::register(plugin, &Scenes::builtin_names()))
+        {
+            eprintln!("Error: could not load plugin {e}");
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(dir) = &args.thumbnails {
+        render_thumbnails(dir, args.max_image_size);
+    }
+
+    if let Some(comparison) = &args.ab {
+        if !Scenes::names().contains(&comparison.scene.as_str()) {
+            eprintln!("Error: no scene '{}' to compare", comparison.scene);
+            std::process::exit(1);
+        }
+    }
+    if let Some(scene) = &args.scene {
+        if !Scenes::names().contains(&scene.as_str()) {
+            eprintln!(
+                "Error: no scene '{scene}', pick one of {}",
+                Scenes::names().join(", ")
+            );
+            std::process::exit(1);
+        }
+    }
+
+    let sequencer = args.sequence.as_deref().map(|path| {
+        Sequencer::load(path).unwrap_or_else(|e| {
+            eprintln!("Error: could not load sequence {}: {e}", path.display());
+            std::process::exit(1);
+        })
+    });
+
+    let snapshot = args.load_snapshot.as_deref().map(|path| {
+        Snapshot::load(path).unwrap_or_else(|e| {
+            eprintln!("Error: could not load snapshot {}: {e}", path.display());
+            std::process::exit(1);
+        })
+    });
+
+    let player = args.replay_input.as_deref().map(|path| {
+        let player = Player::load(path).unwrap_or_else(|e| {
+            eprintln!(
+                "Error: could not load input session {}: {e}",
+                path.display()
+            );
+            std::process::exit(1);
+        });
+        println!("Replaying {} frames of {}", player.len(), path.display());
+        player
+    });
+    // the session starts from its own view, at its own size
+    let snapshot = match &player {
+        Some(player) => Some(player.header.snapshot.clone()),
+        None => snapshot,
+    };
+    let mut win_attribs = WindowAttributes::default();
+    if let Some(size) = args.size {
+        win_attribs = win_attribs.with_inner_size(PhysicalSize::new(size.x, size.y));
+    }
+    if let Some(player) = &player {
+        let [width, height] = player.header.size;
+        win_attribs = win_attribs.with_inner_size(PhysicalSize::new(width, height));
+    }
+
+    let remote = args.remote.map(|port| {
+        RemoteServer::start(port, args.remote_lan).unwrap_or_else(|e| {
+            eprintln!("Error: could not start remote control on port {port}: {e}");
+            std::process::exit(1);
+        })
+    });
+
+    #[cfg(feature = "midi")]
+    let midi = midi::MidiControls::connect(args.midi_mapping.clone())
+        .inspect_err(|e| eprintln!("Error: could not open MIDI input: {e}"))
+        .ok();
+
+    #[cfg(feature = "webcam")]
+    let webcam = args.webcam.as_deref().and_then(|device| {
+        webcam::Webcam::open(device)
+            .inspect_err(|e| {
+                eprintln!("Error: could not open webcam {device}: {e}");
+                for (path, name) in webcam::devices() {
+                    eprintln!("  available: {} ({name})", path.display());
+                }
+            })
+            .ok()
+    });
+
+    #[cfg(feature = "screen-capture")]
+    let screen_capture = args.capture_screen.and_then(|source| {
+        screen_capture::ScreenCapture::open(source)
+            .inspect_err(|e| eprintln!("Error: could not capture the screen: {e}"))
+            .ok()
+    });
+
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(ControlFlow::Poll);
 
     let mut app = App::new(
-        WindowAttributes::default()
+        win_attribs
             .with_active(true)
-            .with_theme(Some(Theme::Dark))
             .with_title("OpenGL Playground")
             .with_resizable(true),
+        args,
+        sequencer,
+        remote,
+        snapshot,
     );
 
+    app.player = player;
+    #[cfg(feature = "midi")]
+    {
+        app.midi = midi;
+    }
+    #[cfg(feature = "webcam")]
+    {
+        app.webcam = webcam;
+    }
+    #[cfg(feature = "screen-capture")]
+    {
+        app.screen_capture = screen_capture;
+    }
+
     event_loop.run_app(&mut app).unwrap();
 }
 
@@ -56,19 +237,87 @@ struct AppState {
 }
 
 struct App {
+    args: Args,
     win_attribs: WindowAttributes,
     template_builder: ConfigTemplateBuilder,
     display_builder: DisplayBuilder,
     not_current_gl_context: Option<NotCurrentContext>,
     scenes: Option<(Scenes, SceneController)>,
     state: Option<AppState>,
+    gpu_timer: Option<GpuTimer>,
+    /// Shared by the HUD and the scenes, `None` if it couldn't be created.
+    text: Option<SharedText>,
+    hud: Option<Hud>,
+    fps_counter: Option<FpsCounter>,
+    split_view: Option<SplitView>,
+    stereo: Option<Stereo>,
+    /// Post-processing between the scene and the overlay.
+    post: PostStack,
+    /// How the scene fills the window.
+    present: Presenter,
+    render_scale: RenderScale,
+    #[cfg(feature = "scripting")]
+    script: Option<ScriptedScene>,
+    input: Option<InputImage>,
+    /// Table shown by the data mode of the round quads.
+    data: Option<DataGrid>,
+    sequencer: Option<Sequencer>,
+    remote: Option<RemoteServer>,
+    /// Reports frames that take too long.
+    watchdog: Option<Watchdog>,
+    /// The last seconds of frames, saved on demand.
+    replay: Option<ReplayBuffer>,
+    /// Every frame, while recording.
+    recording: Option<Recording>,
+    /// Input session being written.
+    recorder: Option<Recorder>,
+    /// Input session being played back, in place of live input.
+    player: Option<Player>,
+    /// Two variants of a scene measured against each other.
+    ab: Option<AbHarness>,
+    #[cfg(feature = "midi")]
+    midi: Option<midi::MidiControls>,
+    /// Streamed as the input image until an image is dropped.
+    #[cfg(feature = "webcam")]
+    webcam: Option<webcam::Webcam>,
+    /// Streamed as the input image until an image is dropped.
+    #[cfg(feature = "screen-capture")]
+    screen_capture: Option<screen_capture::ScreenCapture>,
+
+    /// Snapshot waiting to be restored once the context exists.
+    snapshot: Option<Snapshot>,
+    /// Seed used to create scenes, printed so that a run can be reproduced.
+    seed: u64,
+    /// Whether to save a screenshot once the current frame is drawn.
+    screenshot: bool,
 
-    viewport: IVec2,
-    mouse_pos: Vec2,
+    /// Size of the window, everything else derives its own from.
+    viewport: Viewport,
+    pointer: Pointer,
+    /// Modifiers held right now, for chords.
+    modifiers: ModifiersState,
+    bindings: Bindings,
+    /// Keys held down, unless the OS repeats them.
+    key_repeat: Option<KeyRepeat>,
+    /// Smoothed frame time in seconds.
+    frame_time: f32,
+    /// Monitor the window was last seen on.
+    monitor: Option<MonitorHandle>,
+    /// Animations of the camera, like going back home.
+    camera_tweens: Tweener<&'static str, Camera>,
+    /// Whether the config has alpha the compositor can use, without which
+    /// the window can't be made transparent.
+    surface_alpha: bool,
 }
 
 impl App {
-    fn new(win_attribs: WindowAttributes) -> Self {
+    fn new(
+        win_attribs: WindowAttributes,
+        args: Args,
+        sequencer: Option<Sequencer>,
+        remote: Option<RemoteServer>,
+        snapshot: Option<Snapshot>,
+    ) -> Self {
         // The template will match only the configurations supporting rendering
         // to windows.
         //
@@ -77,34 +326,671 @@ impl App {
         // that, because we can query only one config at a time on it, but all
         // normal platforms will return multiple configs, so we can find the config
         // with transparency ourselves inside the `reduce`.
+        let transparent = args.surface == Compositing::Transparent;
         let template_builder = ConfigTemplateBuilder::new()
             .with_alpha_size(8)
-            .with_transparency(cfg!(target_os = "macos"));
+            .with_transparency(transparent && cfg!(target_os = "macos"))
+            .with_float_pixels(args.output == OutputMode::Hdr);
+        let win_attribs = win_attribs.with_transparent(transparent);
+        surface::set_compositing(args.surface);
 
         let display_builder =
             DisplayBuilder::new().with_window_attributes(Some(win_attribs.clone()));
 
+        let seed = (snapshot.as_ref().map(|s| s.seed))
+            .or(args.seed)
+            .unwrap_or_else(rand::random);
+        println!("Seed: {seed}");
+
+        let data = (args.csv.as_deref()).and_then(|path| {
+            DataGrid::open(path)
+                .inspect_err(|e| eprintln!("Could not load CSV data: {e}"))
+                .ok()
+        });
+
+        let watchdog = (args.watchdog_ms > 0).then(|| {
+            let threshold = Duration::from_millis(args.watchdog_ms);
+            Watchdog::start(threshold, args.watchdog_diagnose)
+        });
+        render_state::set_validation(args.check_gl_state);
+        #[cfg(feature = "hot-reload")]
+        if args.hot_reload {
+            let dir = assets::dir().join("shaders");
+            match common_gl::watch_shaders(&dir) {
+                Ok(()) => println!("Watching {} for shader changes", dir.display()),
+                Err(e) => eprintln!("Error: could not watch {}: {e}", dir.display()),
+            }
+        }
+        let replay = args.replay_seconds.map(ReplayBuffer::new);
+        let ab = args.ab.clone().map(|comparison| {
+            let mode = match args.ab_split {
+                true => AbMode::Split,
+                false => AbMode::Alternate {
+                    frames: args.ab_frames,
+                },
+            };
+            println!("A/B: comparing {comparison}");
+            AbHarness::new(comparison, mode)
+        });
+        let recorder = args.record_input.as_deref().and_then(|path| {
+            Recorder::create(path)
+                .inspect_err(|e| eprintln!("Could not record input to {}: {e}", path.display()))
+                .ok()
+        });
+        let pointer = Pointer::new(args.mouse_smoothing_ms);
+
+        let mut bindings = Bindings::default();
+        for (chord, action) in &args.bindings {
+            bindings.bind(chord.clone(), *action);
+        }
+        let key_repeat = args.key_repeat.map(KeyRepeat::new);
+        let present = Presenter::new(args.present);
+        let render_scale = RenderScale::new(args.render_scale, args.upscale);
+
         Self {
+            args,
             win_attribs,
             template_builder,
             display_builder,
             not_current_gl_context: None,
             scenes: None,
             state: None,
+            gpu_timer: None,
+            text: None,
+            hud: None,
+            fps_counter: None,
+            split_view: None,
+            stereo: None,
+            post: PostStack::new(),
+            present,
+            render_scale,
+            #[cfg(feature = "scripting")]
+            script: None,
+            input: None,
+            data,
+            sequencer,
+            remote,
+            watchdog,
+            replay,
+            recording: None,
+            recorder,
+            player: None,
+            ab,
+            #[cfg(feature = "midi")]
+            midi: None,
+            #[cfg(feature = "webcam")]
+            webcam: None,
+            #[cfg(feature = "screen-capture")]
+            screen_capture: None,
+
+            snapshot,
+            seed,
+            screenshot: false,
+
+            viewport: Viewport::default(),
+            pointer,
+            modifiers: ModifiersState::empty(),
+            bindings,
+            key_repeat,
+            frame_time: 0.0,
+            monitor: None,
+            camera_tweens: Tweener::new(),
+            surface_alpha: false,
+        }
+    }
+
+    /// Shows `message` over the scene once the overlay exists.
+    fn toast(&mut self, message: String) {
+        if let Some(hud) = self.hud.as_mut() {
+            hud.toast(message);
+        }
+    }
+
+    /// Shows the order of the post-processing stack, with the selected pass
+    /// marked and the disabled ones set apart.
+    fn toast_post(&mut self) {
+        println!("Post: {}", self.post);
+
+        let selected = self.post.selected();
+        let passes = (self.post.passes().iter())
+            .map(|pass| {
+                let name = tr(&format!("post.{}", pass.kind.name()));
+                let name = match pass.enabled {
+                    true => name,
+                    false => tr_args("post.off", &[("pass", &name)]),
+                };
+                match pass.kind == selected {
+                    true => tr_args("post.selected", &[("pass", &name)]),
+                    false => name,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" → ");
+        self.toast(tr_args("toast.post", &[("passes", &passes)]));
+    }
+
+    /// Feeds the events of the next frame of the session being replayed, and
+    /// returns how long that frame was.
+    fn replay_frame(&mut self, event_loop: &ActiveEventLoop) -> Option<f32> {
+        // nothing to replay into until the scenes exist
+        if self.scenes.is_none() || self.player.is_none() {
+            return None;
+        }
+        let mut player = self.player.take().unwrap();
+        let Some(frame) = player.next_frame().cloned() else {
+            println!("Replay finished: {}", player.report());
+            self.toast(tr("toast.session_done"));
+            return None;
+        };
+
+        let window = self.state.as_ref().map(|state| state.window.clone());
+        for event in &frame.events {
+            if let Some(key) = event.to_key() {
+                self.on_key(event_loop, &key);
+            } else if let (Some(size), Some(window)) = (event.resized(), &window) {
+                let _ = window.request_inner_size(size);
+            } else if let (Some(event), Some(window)) = (event.to_window_event(), &window) {
+                self.window_event(event_loop, window.id(), event);
+            }
+        }
+
+        self.player = Some(player);
+        Some(frame.dt)
+    }
 
-            viewport: IVec2::default(),
-            mouse_pos: Vec2::default(),
+    fn on_key(&mut self, event_loop: &ActiveEventLoop, event: &KeyInput) {
+        // an open console takes the keyboard, except for keys it doesn't edit with
+        if let (Some(hud), Some(AppState { window, .. })) = (self.hud.as_mut(), &self.state) {
+            if hud.console.is_open() && hud.console.on_key(event) {
+                window.set_ime_allowed(hud.console.is_open());
+                return;
+            }
+        }
+
+        if event.state == ElementState::Released {
+            if let Some(key_repeat) = &mut self.key_repeat {
+                key_repeat.release(&event.logical_key);
+            }
+            return;
+        }
+        // held keys repeat on their own pace instead
+        if event.repeat && self.key_repeat.is_some() {
+            return;
+        }
+
+        let modifiers = self.modifiers;
+        match (self.bindings).action(&event.logical_key, event.physical_key, modifiers) {
+            Some(action) => self.run_action(event_loop, action),
+            None => self.scene_key(&event.logical_key),
         }
     }
+
+    fn run_action(&mut self, event_loop: &ActiveEventLoop, action: Action) {
+        match action {
+            Action::Quit => event_loop.exit(),
+
+            Action::GpuMemory => {
+                if let Some(hud) = self.hud.as_mut() {
+                    hud.toggle_memory();
+                }
+            }
+
+            Action::Reseed if self.state.is_some() => {
+                // derived from the last one, so that replayed sessions reseed the same
+                self.seed = StdRng::seed_from_u64(self.seed).gen();
+                println!("Seed: {}", self.seed);
+
+                let (scenes, _) = self.scenes.as_mut().unwrap();
+                scenes.recreate(&SceneContext {
+                    size: self.viewport.physical_size(),
+                    input: self.input.as_ref().unwrap(),
+                    data: self.data.as_ref(),
+                    seed: self.seed,
+                    text: self.text.as_ref(),
+                });
+            }
+
+            Action::Snapshot => {
+                if let Some((scenes, scene_ctrl)) = self.scenes.as_ref() {
+                    let input = self.input.as_ref().and_then(|i| i.path.as_deref());
+                    let snapshot = Snapshot::capture(scenes, &scene_ctrl.camera, self.seed, input);
+
+                    let path = timestamped_path("view", "snapshot");
+                    match snapshot.save(&path) {
+                        Ok(()) => {
+                            println!("Saved snapshot to {}", path.display());
+                            let path = path.display().to_string();
+                            self.toast(tr_args("toast.snapshot", &[("path", &path)]));
+                        }
+                        Err(e) => eprintln!("Could not save snapshot to {}: {e}", path.display()),
+                    }
+                }
+            }
+
+            // the frame isn't drawn yet, it's saved once it is
+            Action::Screenshot => self.screenshot = true,
+
+            Action::Home => {
+                if let Some((_, scene_ctrl)) = self.scenes.as_ref() {
+                    let home = Camera {
+                        scale: Vec2::splat(self.viewport.scale_factor),
+                        ..Default::default()
+                    };
+                    let camera = scene_ctrl.camera.clone();
+                    let tweens = &mut self.camera_tweens;
+                    tweens.animate_to("camera", camera, home, 0.6, Easing::CubicInOut);
+                }
+            }
+
+            Action::Theme => {
+                let mode = theme::cycle_mode();
+
+                if let Some(AppState { window, .. }) = self.state.as_ref() {
+                    // the title bar follows along where the platform allows it
+                    window.set_theme(match mode {
+                        theme::Mode::System => None,
+                        theme::Mode::Dark => Some(Theme::Dark),
+                        theme::Mode::Light => Some(Theme::Light),
+                    });
+
+                    // the OS may have changed theme while it was being overridden
+                    if let (theme::Mode::System, Some(window_theme)) = (mode, window.theme()) {
+                        theme::set_system_dark(window_theme == Theme::Dark);
+                    }
+                }
+
+                println!("Theme: {} ({})", theme::current().name, mode.name());
+                let name = tr(&format!("theme.{}", mode.name()));
+                self.toast(tr_args("toast.theme", &[("theme", &name)]));
+            }
+
+            Action::Console => {
+                if let (Some(hud), Some(AppState { window, .. })) = (self.hud.as_mut(), &self.state)
+                {
+                    hud.console.toggle();
+                    window.set_ime_allowed(hud.console.is_open());
+                }
+            }
+
+            Action::Split => {
+                if let Some((_, scene_ctrl)) = self.scenes.as_mut() {
+                    let message = match scene_ctrl.toggle_split() {
+                        true => tr("toast.split_on"),
+                        false => tr("toast.split_off"),
+                    };
+                    self.toast(message);
+                }
+            }
+
+            Action::Stereo => {
+                if let Some(stereo) = self.stereo.as_mut() {
+                    let mode = stereo.cycle();
+                    println!("Stereo: {}", mode.name());
+                    let name = tr(&format!("stereo.{}", mode.name()));
+                    self.toast(tr_args("toast.stereo", &[("mode", &name)]));
+                }
+            }
+
+            Action::PolygonMode => {
+                let mode = render_state::polygon_mode().next();
+                render_state::set_polygon_mode(mode);
+                println!("Polygons: {}", mode.name());
+                let name = tr(&format!("polygon.{}", mode.name()));
+                self.toast(tr_args("toast.polygon", &[("mode", &name)]));
+            }
+
+            Action::Normals => {
+                let enabled = !render_state::show_normals();
+                render_state::set_normals(enabled);
+                self.toast(tr(match enabled {
+                    true => "toast.normals_on",
+                    false => "toast.normals_off",
+                }));
+            }
+
+            Action::PostNext => {
+                self.post.select_next();
+                self.toast_post();
+            }
+
+            Action::PostToggle => {
+                self.post.toggle_selected();
+                self.toast_post();
+            }
+
+            Action::PostEarlier | Action::PostLater => {
+                self.post.move_selected(match action {
+                    Action::PostEarlier => -1,
+                    _ => 1,
+                });
+                self.toast_post();
+            }
+
+            Action::Help => {
+                if let Some(hud) = self.hud.as_mut() {
+                    hud.toggle_help();
+                }
+            }
+
+            Action::Params => {
+                if let Some(hud) = self.hud.as_mut() {
+                    hud.params.toggle();
+                }
+            }
+
+            Action::Fps => {
+                if let Some(fps_counter) = self.fps_counter.as_mut() {
+                    let mode = unsafe { fps_counter.cycle() };
+                    let name = tr(&format!("fps.{}", mode.name()));
+                    self.toast(tr_args("toast.fps", &[("mode", &name)]));
+                }
+            }
+
+            Action::Frosted => {
+                if let Some(hud) = self.hud.as_mut() {
+                    let message = match hud.toggle_frosted() {
+                        true => tr("toast.frosted_on"),
+                        false => tr("toast.frosted_off"),
+                    };
+                    self.toast(message);
+                }
+            }
+
+            Action::Transparency => {
+                let compositing = surface::compositing().toggled();
+                surface::set_compositing(compositing);
+                if let Some(AppState { window, .. }) = self.state.as_ref() {
+                    window.set_transparent(compositing == Compositing::Transparent);
+                }
+
+                println!("Surface: {}", compositing.name());
+                let message = match compositing {
+                    Compositing::Transparent if !self.surface_alpha => {
+                        tr("toast.transparent_unsupported")
+                    }
+                    Compositing::Transparent => tr("toast.transparent_on"),
+                    Compositing::Opaque => tr("toast.transparent_off"),
+                };
+                self.toast(message);
+            }
+
+            Action::Locale => {
+                let locale = locale::cycle();
+                println!("Locale: {}", locale.code());
+                let name = locale.native_name();
+                self.toast(tr_args("toast.locale", &[("locale", name)]));
+            }
+
+            Action::Export => {
+                if let Some((scenes, scene_ctrl)) = self.scenes.as_mut() {
+                    let path = timestamped_path("export", "png");
+                    let tiles = self.args.export_tiles;
+                    let res = export::export_tiled(
+                        &path,
+                        scenes,
+                        &scene_ctrl.camera,
+                        self.viewport.physical().as_ivec2(),
+                        tiles,
+                    );
+
+                    if let Err(e) = res {
+                        eprintln!("Could not export to {}: {e}", path.display());
+                    }
+                }
+            }
+
+            Action::SaveReplay => {
+                let Some(replay) = self.replay.as_mut() else {
+                    println!("No replay buffer: run with --replay-buffer <seconds> to keep one");
+                    self.toast(tr("toast.replay_off"));
+                    return;
+                };
+
+                unsafe { replay.flush() };
+                let frames: Vec<_> = replay.frames().iter().cloned().collect();
+                let dir = timestamped_path("replay", "");
+                let seconds = format!("{:.1}", replay.seconds());
+                println!("Saving {} replay frames to {}", frames.len(), dir.display());
+                let path = dir.display().to_string();
+                self.toast(tr_args(
+                    "toast.replay",
+                    &[("seconds", &seconds), ("path", &path)],
+                ));
+
+                // encoding takes a while, and the window keeps going meanwhile
+                std::thread::spawn(move || match replay::save_sequence(&dir, &frames) {
+                    Ok(()) => println!("Saved replay to {}", dir.display()),
+                    Err(e) => eprintln!("Could not save replay to {}: {e}", dir.display()),
+                });
+            }
+
+            Action::Record => {
+                if let Some(recording) = self.recording.take() {
+                    finish_recording(recording, self.hud.as_mut());
+                    return;
+                }
+
+                let format = self.args.record_format;
+                let path = timestamped_path("recording", format.extension());
+                match Recording::start(format, &path, self.viewport.physical()) {
+                    Ok(recording) => {
+                        println!("Recording to {}", path.display());
+                        let path = path.display().to_string();
+                        self.toast(tr_args("toast.recording", &[("path", &path)]));
+                        self.recording = Some(recording);
+                    }
+                    Err(e) => {
+                        eprintln!("Could not record to {}: {e}", path.display());
+                        let error = e.to_string();
+                        self.toast(tr_args("toast.recording_failed", &[("error", &error)]));
+                    }
+                }
+            }
+
+            Action::NextMonitor => self.next_monitor(),
+
+            Action::Reseed => {}
+        }
+    }
+
+    /// Sends a key no action is bound to to the script and the scene, and
+    /// keeps sending it to the scene while it's held.
+    fn scene_key(&mut self, logical_key: &Key) {
+        if self.state.is_none() {
+            return;
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(script) = &mut self.script {
+            let (scenes, scene_ctrl) = self.scenes.as_mut().unwrap();
+            let camera = &scene_ctrl.camera;
+            let (pointer, size) =
+                (self.present).to_scene(self.pointer.position(), self.viewport.size());
+            let mouse = camera.pointer_to_pos(pointer, size);
+            match script.on_key(logical_key, scenes, camera, mouse) {
+                Ok((used, camera)) => {
+                    if let Some(camera) = camera {
+                        scene_ctrl.set_camera(camera);
+                    }
+                    if used {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    if let Some(hud) = self.hud.as_mut() {
+                        hud.console.error(e);
+                    }
+                }
+            }
+        }
+
+        let (scenes, _) = self.scenes.as_mut().unwrap();
+        let ctx = SceneContext {
+            size: self.viewport.physical_size(),
+            input: self.input.as_ref().unwrap(),
+            data: self.data.as_ref(),
+            seed: self.seed,
+            text: self.text.as_ref(),
+        };
+        let previous = scenes.name();
+        let switched = scenes.switch_scene(&ctx, logical_key.clone());
+        scenes.on_key(logical_key.clone());
+        // switching scenes over and over would only recreate them
+        if let (false, Some(key_repeat)) = (switched, &mut self.key_repeat) {
+            key_repeat.press(logical_key.clone());
+        }
+
+        let name = scenes.name();
+        if name != previous {
+            let name = Scenes::title(name);
+            self.toast(tr_args("toast.scene", &[("name", &name)]));
+        }
+
+        self.take_scene_input();
+    }
+
+    /// Makes the image a scene exported the input, which the blur scenes
+    /// pick up when they get recreated.
+    fn take_scene_input(&mut self) {
+        let Some((scenes, _)) = self.scenes.as_mut() else {
+            return;
+        };
+        if let Some(image) = scenes.take_input() {
+            let (width, height) = image.dimensions();
+            let max_dimension = self.max_image_dimension();
+            self.input = Some(InputImage::from_pixels(image, max_dimension));
+            let size = format!("{width}x{height}");
+            self.toast(tr_args("toast.input", &[("size", &size)]));
+        }
+    }
+
+    /// Largest side allowed for input images. The context needs to be current.
+    fn max_image_dimension(&self) -> u32 {
+        let max_texture_size = unsafe { common_gl::max_texture_size() };
+        self.args.max_image_size.min(max_texture_size)
+    }
+
+    /// Moves the new window where `--monitor` and `--position` ask.
+    fn place_window(&self, window: &Window) {
+        let position = match (self.args.monitor, self.args.position) {
+            (None, None) => return,
+            (None, Some(position)) => position,
+            (Some(index), position) => {
+                let monitors: Vec<_> = window.available_monitors().collect();
+                let Some(monitor) = monitors.get(index) else {
+                    eprintln!("No monitor {index}, there are {}", monitors.len());
+                    return;
+                };
+                let size = window.outer_size();
+                Monitor::of(monitor).place(uvec2(size.width, size.height), position)
+            }
+        };
+        window.set_outer_position(PhysicalPosition::new(position.x, position.y));
+    }
+
+    /// Moves the window to the next monitor, at the same place on it.
+    fn next_monitor(&mut self) {
+        let Some(AppState { window, .. }) = self.state.as_ref() else {
+            return;
+        };
+        let monitors: Vec<_> = window.available_monitors().collect();
+        let current = window.current_monitor();
+        let index = placement::next(
+            (current.as_ref()).and_then(|current| monitors.iter().position(|m| m == current)),
+            monitors.len(),
+        );
+        let Some(monitor) = monitors.get(index) else {
+            return;
+        };
+
+        let size = window.outer_size();
+        let size = uvec2(size.width, size.height);
+        let position = match (window.outer_position(), &current) {
+            (Ok(position), Some(current)) => Monitor::of(monitor).carry_over(
+                &Monitor::of(current),
+                ivec2(position.x, position.y),
+                size,
+            ),
+            _ => Monitor::of(monitor).place(size, None),
+        };
+        window.set_outer_position(PhysicalPosition::new(position.x, position.y));
+
+        let name = placement::name(monitor);
+        let index = index.to_string();
+        self.toast(tr_args(
+            "toast.monitor",
+            &[("index", &index), ("name", &name)],
+        ));
+    }
+
+    /// Notices when the window lands on another monitor, whose refresh rate
+    /// sets the pace of frames under vsync. Its scale factor comes with its
+    /// own event.
+    fn check_monitor(&mut self) {
+        let Some(AppState { window, .. }) = self.state.as_ref() else {
+            return;
+        };
+        let monitor = window.current_monitor();
+        if monitor == self.monitor {
+            return;
+        }
+
+        if let Some(monitor) = &monitor {
+            let refresh_rate = placement::refresh_rate(monitor);
+            let size = monitor.size();
+            println!(
+                "Monitor: {} ({}x{}, {}, scale {})",
+                placement::name(monitor),
+                size.width,
+                size.height,
+                refresh_rate.map_or("unknown refresh rate".to_string(), |hz| format!("{hz} Hz")),
+                monitor.scale_factor(),
+            );
+            // the average would take a while to forget the last monitor
+            if let Some(hz) = refresh_rate.filter(|&hz| hz > 0.0) {
+                self.frame_time = 1.0 / hz;
+            }
+        }
+        self.monitor = monitor;
+    }
+
+    /// Latest frame of the webcam or the screen capture, if one came in.
+    #[cfg(any(feature = "webcam", feature = "screen-capture"))]
+    fn live_frame(&self) -> Option<InputImage> {
+        #[cfg(feature = "webcam")]
+        if let Some(frame) = self.webcam.as_ref().and_then(|webcam| webcam.latest()) {
+            return Some(InputImage::from_pixels(frame, self.max_image_dimension()));
+        }
+        #[cfg(feature = "screen-capture")]
+        if let Some(frame) = (self.screen_capture.as_ref()).and_then(|capture| capture.latest()) {
+            return Some(InputImage::from_pixels(frame, self.max_image_dimension()));
+        }
+        None
+    }
 }
 
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let (mut window, gl_config) = match self.display_builder.clone().build(
+        let transparent = surface::compositing() == Compositing::Transparent;
+        let hdr = self.args.output == OutputMode::Hdr;
+        let built = self.display_builder.clone().build(
             event_loop,
             self.template_builder.clone(),
-            gl_config_picker,
-        ) {
+            |configs| gl_config_picker(configs, transparent, hdr),
+        );
+        // HDR is an experiment, which shouldn't keep the window from opening
+        let built = match built {
+            Err(e) if hdr => {
+                eprintln!("No float config for HDR output ({e}), falling back to SDR");
+                self.display_builder.clone().build(
+                    event_loop,
+                    self.template_builder.clone().with_float_pixels(false),
+                    |configs| gl_config_picker(configs, transparent, false),
+                )
+            }
+            built => built,
+        };
+        let (mut window, gl_config) = match built {
             Ok(ok) => ok,
             Err(e) => {
                 eprintln!("Error: {e}");
@@ -115,6 +1001,15 @@ impl ApplicationHandler for App {
 
         println!("Chosen OpenGL config:");
         debug_gl_config(&gl_config);
+        if hdr && !gl_config.float_pixels() {
+            eprintln!("The chosen config has no float pixels, falling back to SDR output");
+        }
+        output::set_mode(match gl_config.float_pixels() {
+            true => OutputMode::Hdr,
+            false => OutputMode::Sdr,
+        });
+        self.surface_alpha =
+            (gl_config.supports_transparency()).unwrap_or(gl_config.alpha_size() > 0);
 
         let raw_window_handle = window
             .as_ref()
@@ -182,6 +1077,16 @@ impl ApplicationHandler for App {
             let symbol = CString::new(symbol).unwrap();
             gl_display.get_proc_address(symbol.as_c_str()).cast()
         });
+        gl_ext::load_with(|symbol| {
+            let symbol = CString::new(symbol).unwrap();
+            gl_display.get_proc_address(symbol.as_c_str()).cast()
+        });
+        render_state::set_gles(unsafe { common_gl::is_gles() });
+        #[cfg(feature = "plugins")]
+        {
+            let display = gl_display.clone();
+            plugin::set_gl_loader(move |symbol| display.get_proc_address(symbol).cast());
+        }
 
         // Print some OpenGL constants
         unsafe {
@@ -207,26 +1112,117 @@ impl ApplicationHandler for App {
             } else {
                 println!("Debug ext:   unsupported\n");
             }
+
+            gpu_memory::detect_vendor_extensions(&extensions);
+
+            self.gpu_timer.get_or_insert_with(|| GpuTimer::new());
+            if self.text.is_none() {
+                match TextRenderer::shared() {
+                    Ok(text) => self.text = Some(text),
+                    Err(e) => eprintln!("Could not create the text renderer: {e}"),
+                }
+            }
+            if let (None, Some(text)) = (&self.hud, &self.text) {
+                self.hud = Some(Hud::new(text.clone()));
+            }
+            if self.fps_counter.is_none() {
+                match FpsCounter::new() {
+                    Ok(fps_counter) => self.fps_counter = Some(fps_counter),
+                    Err(e) => eprintln!("Could not create the FPS counter: {e}"),
+                }
+            }
+            if self.stereo.is_none() {
+                match Stereo::new() {
+                    Ok(stereo) => self.stereo = Some(stereo),
+                    Err(e) => eprintln!("Could not set up stereo: {e}"),
+                }
+            }
+
+            #[cfg(feature = "scripting")]
+            if let (None, Some(path)) = (&self.script, &self.args.script) {
+                match ScriptedScene::load(path) {
+                    Ok(script) => self.script = Some(script),
+                    Err(e) => eprintln!("Could not load script {e}"),
+                }
+            }
         }
 
         // The context needs to be current for the Renderer to set up shaders and
         // buffers.
+        let max_image_dimension = self.max_image_dimension();
+        let snapshot = self.snapshot.take();
+
+        let image = self.args.image.as_deref();
+        let input = (self.input).get_or_insert_with(|| {
+            // a snapshot restores the view it was taken of, image included
+            let snapshot_input = snapshot.as_ref().and_then(|s| s.input.as_deref());
+            match (snapshot_input.or(image))
+                .map(|path| (path, InputImage::open(path, max_image_dimension)))
+            {
+                Some((_, Ok(input))) => input,
+                Some((path, Err(e))) => {
+                    eprintln!("Could not load input image {}: {e}", path.display());
+                    InputImage::gura(max_image_dimension)
+                }
+                None => InputImage::gura(max_image_dimension),
+            }
+        });
+
+        self.viewport = Viewport::from_physical(window.inner_size(), window.scale_factor());
+        let viewport = self.viewport;
+
+        let seed = self.seed;
+        let data = self.data.as_ref();
+        let text = self.text.as_ref();
+        // the comparison needs its own scene
+        let first_scene =
+            (self.ab.as_ref().map(|ab| ab.scene().to_string())).or_else(|| self.args.scene.clone());
+        let zoom_limits = self.args.zoom_limits;
         self.scenes.get_or_insert_with(|| {
-            let scenes = Scenes::new(window.as_ref());
-            let scene_controller = SceneController::new(window.scale_factor() as f32, 0.5);
+            let ctx = SceneContext {
+                size: viewport.physical_size(),
+                input,
+                data,
+                seed,
+                text,
+            };
+            let mut scene_controller = SceneController::new(viewport, 0.5);
+            scene_controller.zoom_limits = zoom_limits;
+
+            let scenes = match &snapshot {
+                Some(snapshot) => {
+                    let mut scenes = Scenes::from_name(&snapshot.scene, &ctx).unwrap();
+                    snapshot.restore_params(&mut scenes);
+                    scene_controller.set_camera(snapshot.camera.clone());
+                    scenes
+                }
+                None => (first_scene.as_deref())
+                    .and_then(|scene| Scenes::from_name(scene, &ctx))
+                    .unwrap_or_else(|| Scenes::new(&ctx)),
+            };
+
             (scenes, scene_controller)
         });
 
-        let win_size = window.inner_size();
-        self.viewport = IVec2::new(win_size.width as i32, win_size.height as i32);
+        if let Some((_, scene_ctrl)) = self.scenes.as_mut() {
+            scene_ctrl.resize(viewport);
+        }
 
-        // Try setting vsync.
-        if let Err(res) = gl_surface
-            .set_swap_interval(&gl_context, SwapInterval::Wait(NonZeroU32::new(1).unwrap()))
-        {
+        if let Some(window_theme) = window.theme() {
+            theme::set_system_dark(window_theme == Theme::Dark);
+        }
+
+        // Try setting vsync, or turning it off.
+        let interval = match self.args.vsync {
+            true => SwapInterval::Wait(NonZeroU32::new(1).unwrap()),
+            false => SwapInterval::DontWait,
+        };
+        if let Err(res) = gl_surface.set_swap_interval(&gl_context, interval) {
             eprintln!("Error setting vsync: {res:?}");
         }
 
+        self.place_window(&window);
+
         let prev_state = (self.state).replace(AppState {
             gl_context,
             gl_surface,
@@ -234,6 +1230,7 @@ impl ApplicationHandler for App {
         });
 
         assert!(prev_state.is_none());
+        self.check_monitor();
     }
 
     fn window_event(
@@ -242,6 +1239,19 @@ impl ApplicationHandler for App {
         _window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
+        profiling::cpu_zone!("window event");
+
+        if let Some(input) = InputEvent::from_window_event(&event) {
+            // the session being replayed is the only input, but the window
+            // still resizes as it's told
+            if self.player.is_some() && input.resized().is_none() {
+                return;
+            }
+            if let Some(recorder) = &mut self.recorder {
+                recorder.push(input);
+            }
+        }
+
         match event {
             WindowEvent::Resized(size) if size.width != 0 && size.height != 0 => {
                 // Some platforms like EGL require resizing GL surface to update the size
@@ -251,6 +1261,7 @@ impl ApplicationHandler for App {
                 if let Some(AppState {
                     gl_context,
                     gl_surface,
+                    window,
                     ..
                 }) = self.state.as_mut()
                 {
@@ -260,41 +1271,134 @@ impl ApplicationHandler for App {
                         NonZeroU32::new(size.height).unwrap(),
                     );
 
-                    self.viewport = IVec2::new(size.width as i32, size.height as i32);
+                    self.viewport = Viewport::from_physical(size, window.scale_factor());
+                }
+                if let Some((_, scene_ctrl)) = self.scenes.as_mut() {
+                    scene_ctrl.resize(self.viewport);
+                }
+            }
+
+            // the window keeps its logical size, and the `Resized` that usually
+            // follows brings its new physical size
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.viewport = self.viewport.with_scale_factor(scale_factor);
+                if let Some((_, scene_ctrl)) = self.scenes.as_mut() {
+                    scene_ctrl.resize(self.viewport);
+                }
+            }
+
+            WindowEvent::Moved(_) => self.check_monitor(),
+
+            WindowEvent::DroppedFile(ref path) => {
+                let is_csv = (path.extension()).is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+                if is_csv && self.state.is_some() {
+                    match DataGrid::open(path) {
+                        Ok(data) => {
+                            println!(
+                                "Loaded {}x{} values from {}",
+                                data.width,
+                                data.height,
+                                path.display()
+                            );
+                            let (scenes, _) = self.scenes.as_mut().unwrap();
+                            scenes.reload_input(&SceneContext {
+                                size: self.viewport.physical_size(),
+                                input: self.input.as_ref().unwrap(),
+                                data: Some(&data),
+                                seed: self.seed,
+                                text: self.text.as_ref(),
+                            });
+                            self.data = Some(data);
+                        }
+                        Err(e) => eprintln!("Could not load {e}"),
+                    }
+                } else if self.state.is_some() {
+                    match InputImage::open(path, self.max_image_dimension()) {
+                        Ok(input) => {
+                            #[cfg(feature = "webcam")]
+                            if self.webcam.take().is_some() {
+                                println!("Webcam: closed for {}", path.display());
+                            }
+                            #[cfg(feature = "screen-capture")]
+                            if self.screen_capture.take().is_some() {
+                                println!("Screen capture: stopped for {}", path.display());
+                            }
+                            let (scenes, _) = self.scenes.as_mut().unwrap();
+                            scenes.reload_input(&SceneContext {
+                                size: self.viewport.physical_size(),
+                                input: &input,
+                                data: self.data.as_ref(),
+                                seed: self.seed,
+                                text: self.text.as_ref(),
+                            });
+                            self.input = Some(input);
+                        }
+                        Err(e) => eprintln!("Could not load {}: {e}", path.display()),
+                    }
+                }
+            }
+
+            WindowEvent::ThemeChanged(window_theme) if theme::mode() == theme::Mode::System => {
+                theme::set_system_dark(window_theme == Theme::Dark);
+                println!("Theme: {}", theme::current().name);
+                self.toast(tr_args(
+                    "toast.theme",
+                    &[("theme", &tr(&format!("theme.{}", theme::current().name)))],
+                ));
+            }
+
+            WindowEvent::Ime(ref ime) => {
+                if let Some(hud) = self.hud.as_mut() {
+                    hud.console.on_ime(ime.clone());
                 }
             }
 
             WindowEvent::CursorMoved { position, .. } => {
-                self.mouse_pos = Vec2::new(position.x as f32, position.y as f32);
-            }
-
-            WindowEvent::CloseRequested
-            | WindowEvent::KeyboardInput {
-                event:
-                    KeyEvent {
-                        logical_key: Key::Named(NamedKey::Escape),
-                        state: ElementState::Pressed,
-                        ..
-                    },
-                ..
-            } => event_loop.exit(),
-
-            WindowEvent::KeyboardInput {
-                event:
-                    KeyEvent {
-                        ref logical_key,
-                        state: ElementState::Pressed,
-                        ..
-                    },
-                ..
-            } => {
-                if let Some(AppState { window, .. }) = self.state.as_ref() {
-                    let (scenes, _) = self.scenes.as_mut().unwrap();
-                    scenes.switch_scene(window, logical_key.clone());
-                    scenes.on_key(logical_key.clone());
+                self.pointer.moved(position);
+
+                if let (Some(hud), Some((scenes, _))) = (self.hud.as_mut(), self.scenes.as_mut()) {
+                    let mouse_pos = self.pointer.position();
+                    if hud.params.on_mouse_moved(scenes.params_mut(), mouse_pos) {
+                        // dragging a slider, not the camera
+                        return;
+                    }
                 }
             }
 
+            WindowEvent::MouseInput { state, button, .. } => {
+                if let (Some(hud), Some((scenes, _))) = (self.hud.as_mut(), self.scenes.as_mut()) {
+                    let mouse_pos = self.pointer.position();
+                    if (hud.params).on_mouse_input(scenes.params_mut(), state, button, mouse_pos) {
+                        return;
+                    }
+                }
+                if let Some((scenes, scene_ctrl)) = self.scenes.as_mut() {
+                    let (pointer, _) =
+                        (self.present).to_scene(self.pointer.position(), self.viewport.size());
+                    let (camera, mouse_pos) = scene_ctrl.view_at(pointer);
+                    let (camera, mouse_pos) = self.render_scale.view_at(camera, mouse_pos);
+                    if scenes.on_mouse_input(&camera, state, button, mouse_pos) {
+                        // the scene took the click, so the camera doesn't pan
+                        return;
+                    }
+                }
+            }
+
+            WindowEvent::CloseRequested => event_loop.exit(),
+
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+
+            // keys released elsewhere would keep repeating
+            WindowEvent::Focused(false) => {
+                if let Some(key_repeat) = &mut self.key_repeat {
+                    key_repeat.release_all();
+                }
+            }
+
+            WindowEvent::KeyboardInput { ref event, .. } => {
+                self.on_key(event_loop, &KeyInput::from(event))
+            }
+
             _ => {}
         };
 
@@ -303,7 +1407,16 @@ impl ApplicationHandler for App {
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        // the writer would be cut short, leaving a broken video
+        if let Some(recording) = self.recording.take() {
+            finish_recording(recording, None);
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        let replayed_dt = self.replay_frame(event_loop);
+
         if let Some(AppState {
             gl_context,
             gl_surface,
@@ -311,30 +1424,689 @@ impl ApplicationHandler for App {
             ..
         }) = self.state.as_ref()
         {
+            #[cfg(any(feature = "webcam", feature = "screen-capture"))]
+            let frame = self.live_frame();
+
             let (scenes, scene_ctrl) = self.scenes.as_mut().unwrap();
+            let gpu_timer = self.gpu_timer.as_mut().unwrap();
+
+            profiling::cpu_zone!("frame");
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.begin_frame(scenes.name(), scenes.params());
+            }
+            watchdog::enter_pass(c"Update");
+
+            if let Some(recorder) = self.recorder.as_mut().filter(|r| !r.is_started()) {
+                let input = self.input.as_ref().and_then(|i| i.path.as_deref());
+                let snapshot = Snapshot::capture(scenes, &scene_ctrl.camera, self.seed, input);
+                if let Err(e) = recorder.start(self.viewport.physical_size(), snapshot) {
+                    eprintln!("Input recording stopped: {e}");
+                    self.recorder = None;
+                }
+            }
+            match replayed_dt {
+                Some(dt) => scene_ctrl.update_by(dt),
+                None => scene_ctrl.update(),
+            }
+            if let Some(recorder) = &mut self.recorder {
+                if let Err(e) = recorder.end_frame(scene_ctrl.dt()) {
+                    eprintln!("Input recording stopped: {e}");
+                    self.recorder = None;
+                }
+            }
+            self.pointer.update(scene_ctrl.dt());
+            if let Some(key_repeat) = &mut self.key_repeat {
+                for key in key_repeat.update(scene_ctrl.dt()) {
+                    scenes.on_key(key);
+                }
+            }
+            // exact for what the mouse points at, smooth for what follows it
+            let mouse_pos = self.pointer.position();
+            let smoothed_pos = self.pointer.smoothed_position();
+
+            // alternating, the update and the draw of the variant are both timed
+            let alternating = self.ab.as_mut().filter(|ab| ab.mode != AbMode::Split);
+            let ab_variant = match alternating.map(|ab| ab.begin_frame(scenes)) {
+                Some(Ok(variant)) => variant,
+                Some(Err(e)) => {
+                    eprintln!("A/B comparison turned off: {e}");
+                    self.ab = None;
+                    None
+                }
+                None => None,
+            };
+            let ab_start = Instant::now();
+            if let Some(variant) = ab_variant {
+                unsafe { gpu_timer.begin(variant.zone()) };
+            }
+
+            for _ in 0..scene_ctrl.take_fixed_steps() {
+                self.camera_tweens.update(FIXED_DT);
+                scenes.update(FIXED_DT);
+                if let Some(hud) = self.hud.as_mut() {
+                    hud.update(FIXED_DT);
+                }
+            }
+            if let Some(camera) = self.camera_tweens.value(&"camera") {
+                scene_ctrl.set_camera(camera);
+            }
+            self.camera_tweens.remove_finished();
+
+            #[cfg(feature = "scripting")]
+            if let Some(script) = &mut self.script {
+                let camera = &scene_ctrl.camera;
+                let (pointer, size) = self.present.to_scene(mouse_pos, self.viewport.size());
+                let mouse = camera.pointer_to_pos(pointer, size);
+                match script.update(scene_ctrl.dt(), scenes, camera, mouse) {
+                    Ok(Some(camera)) => scene_ctrl.set_camera(camera),
+                    Ok(None) => {}
+                    Err(e) => {
+                        eprintln!("{e}");
+                        if let Some(hud) = self.hud.as_mut() {
+                            hud.console.error(e);
+                        }
+                    }
+                }
+            }
+
+            #[cfg(feature = "hot-reload")]
+            for reload in unsafe { common_gl::reload_shaders() } {
+                let file = reload
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                match reload.result {
+                    Ok(programs) => println!("Reloaded {file} into {programs} programs"),
+                    Err(e) => {
+                        let e = format!("{file}: {e}");
+                        eprintln!("{e}");
+                        if let Some(hud) = self.hud.as_mut() {
+                            hud.console.error(e);
+                        }
+                    }
+                }
+            }
+
+            #[cfg(feature = "midi")]
+            if let Some(midi) = &self.midi {
+                let scene_name = scenes.name();
+                for (param, t) in midi.poll() {
+                    if let Some(params) = scenes.params_mut() {
+                        if params.set_normalized(param, t).is_ok() {
+                            println!("{scene_name} config: {params}");
+                        }
+                    }
+                }
+            }
+
+            #[cfg(any(feature = "webcam", feature = "screen-capture"))]
+            if let Some(input) = frame {
+                scenes.stream_input(&SceneContext {
+                    size: self.viewport.physical_size(),
+                    input: &input,
+                    data: self.data.as_ref(),
+                    seed: self.seed,
+                    text: self.text.as_ref(),
+                });
+                self.input = Some(input);
+            }
+
+            if let Some(sequencer) = &mut self.sequencer {
+                for cue in sequencer.update() {
+                    match cue {
+                        Cue::SwitchScene(name) => {
+                            let ctx = SceneContext {
+                                size: self.viewport.physical_size(),
+                                input: self.input.as_ref().unwrap(),
+                                data: self.data.as_ref(),
+                                seed: self.seed,
+                                text: self.text.as_ref(),
+                            };
+                            *scenes = Scenes::from_name(&name, &ctx).unwrap();
+                        }
+                        Cue::PressKey(key) => scenes.on_key(key),
+                    }
+                }
+
+                if let Some(camera) = sequencer.camera(self.viewport.scale_factor) {
+                    scene_ctrl.set_camera(camera);
+                }
+
+                if sequencer.is_finished() {
+                    self.sequencer = None;
+                }
+            }
+
+            watchdog::leave_pass();
+
+            {
+                profiling::cpu_zone!("draw");
+
+                watchdog::enter_pass(c"Draw");
+                unsafe { gpu_timer.begin("draw") };
+                // presented only when drawn over the whole window, below
+                self.present.skip();
+                self.render_scale.skip();
+                let split_ab = (self.ab.as_mut())
+                    .filter(|ab| ab.mode == AbMode::Split && ab.scene() == scenes.name());
+                if let Some(ab) = split_ab {
+                    let viewport = self.viewport.physical();
+                    let half = SplitView::half_size(viewport);
+                    let split_view = self.split_view.get_or_insert_with(SplitView::new);
+                    let [_, second] = ab.draw_order();
+                    let last = match second {
+                        Variant::A => Side::Left,
+                        Variant::B => Side::Right,
+                    };
+
+                    // A on the left, B on the right, both from the main camera
+                    let mut error = None;
+                    let camera = &scene_ctrl.camera;
+                    let draw_half = |side: Side| {
+                        let (variant, offset) = match side {
+                            Side::Left => (Variant::A, Vec2::ZERO),
+                            Side::Right => {
+                                (Variant::B, Vec2::new((viewport.x - half.x) as f32, 0.0))
+                            }
+                        };
+                        if let Err(e) = ab.apply(scenes, variant) {
+                            error = Some(e);
+                            return;
+                        }
+
+                        let start = Instant::now();
+                        unsafe { gpu_timer.begin(variant.zone()) };
+                        scenes.resize(camera, half.x as i32, half.y as i32);
+                        scenes.draw(camera, smoothed_pos - offset);
+                        unsafe { gpu_timer.end() };
+                        ab.record_cpu(variant, start.elapsed().as_secs_f64() * 1000.0);
+                    };
+                    if let Err(e) = unsafe { split_view.draw(viewport, last, draw_half) } {
+                        error = Some(e.to_string());
+                    }
+                    if let Some(e) = error {
+                        eprintln!("A/B comparison turned off: {e}");
+                        self.ab = None;
+                    }
+                } else if scene_ctrl.split_camera.is_some() {
+                    let viewport = self.viewport.physical();
+                    let half = SplitView::half_size(viewport);
+                    let split_view = self.split_view.get_or_insert_with(SplitView::new);
+                    let last = scene_ctrl.side_at(mouse_pos);
+
+                    let draw_half = |side: Side| {
+                        let (camera, offset) = scene_ctrl.view(side);
+                        scenes.resize(camera, half.x as i32, half.y as i32);
+                        scenes.draw(camera, smoothed_pos - offset);
+                        #[cfg(feature = "scripting")]
+                        if let Some(script) = &mut self.script {
+                            unsafe { script.draw(camera, half.as_vec2()) };
+                        }
+                    };
+                    if let Err(e) = unsafe { split_view.draw(viewport, last, draw_half) } {
+                        eprintln!("Split view turned off: {e}");
+                        scene_ctrl.toggle_split();
+                    }
+                } else if let Some(stereo) =
+                    (self.stereo.as_mut()).filter(|stereo| stereo.mode() != StereoMode::Off)
+                {
+                    let viewport = self.viewport.physical();
+                    // side by side, both eyes see the mouse where it is in its half
+                    let right = (viewport.x - SplitView::half_size(viewport).x) as f32;
+                    let eye_pos = match stereo.mode() {
+                        StereoMode::SideBySide if smoothed_pos.x >= right => {
+                            smoothed_pos - Vec2::new(right, 0.0)
+                        }
+                        _ => smoothed_pos,
+                    };
+
+                    let draw_eye = |eye: Eye, size: UVec2| {
+                        let camera = eye.camera(&scene_ctrl.camera);
+                        scenes.set_parallax(eye.parallax(&camera));
+                        scenes.resize(&camera, size.x as i32, size.y as i32);
+                        scenes.draw(&camera, eye_pos);
+                        #[cfg(feature = "scripting")]
+                        if let Some(script) = &mut self.script {
+                            unsafe { script.draw(&camera, size.as_vec2()) };
+                        }
+                    };
+                    if let Err(e) = unsafe { stereo.draw(viewport, draw_eye) } {
+                        eprintln!("Stereo turned off: {e}");
+                        stereo.turn_off();
+                    }
+                    scenes.set_parallax(Vec2::ZERO);
+                } else {
+                    let window = self.viewport.physical();
+                    let layout = self.present.mode.layout(window);
+                    let camera = &scene_ctrl.camera;
+                    let render_scale = &mut self.render_scale;
+                    #[cfg(feature = "scripting")]
+                    let script = &mut self.script;
+                    let mut scale_error = None;
+                    let draw = |size: UVec2| {
+                        let draw_region = |region: &Region| {
+                            let camera = &region.camera;
+                            scenes.resize(camera, region.size.x as i32, region.size.y as i32);
+                            scenes.draw(camera, region.pointer(layout.to_scene(smoothed_pos)));
+                            #[cfg(feature = "scripting")]
+                            if let Some(script) = script {
+                                unsafe { script.draw(camera, region.size.as_vec2()) };
+                            }
+                        };
+                        // drawn in tiles past what the window holds
+                        let result =
+                            unsafe { render_scale.draw(size, window, camera, draw_region) };
+                        scale_error = result.err();
+                    };
+                    if let Err(e) = unsafe { self.present.draw(window, draw) } {
+                        eprintln!("Presentation turned off: {e}");
+                        self.present.turn_off();
+                    }
+                    if let Some(e) = scale_error {
+                        eprintln!("Render scale turned off: {e}");
+                        self.render_scale.turn_off();
+                    }
+                }
+                scene_ctrl.pixel_ratio = self.present.layout().map_or(Vec2::ONE, |l| l.ratio());
+                if let Err(e) = unsafe { self.post.draw(self.viewport.physical(), gpu_timer) } {
+                    eprintln!("Post-processing turned off: {e}");
+                    self.post.turn_off();
+                }
+                unsafe { gpu_timer.end() };
+                watchdog::leave_pass();
+            }
 
-            scene_ctrl.update();
-            scenes.resize(&scene_ctrl.camera, self.viewport.x, self.viewport.y);
-            scenes.draw(&scene_ctrl.camera, self.mouse_pos);
+            if let (Some(variant), Some(ab)) = (ab_variant, self.ab.as_mut()) {
+                unsafe { gpu_timer.end() };
+                ab.record_cpu(variant, ab_start.elapsed().as_secs_f64() * 1000.0);
+                ab.end_frame();
+            }
+
+            // the scene as post-processed, without the overlay
+            if let Some(replay) = self.replay.as_mut() {
+                let res = unsafe { replay.capture(self.viewport.physical(), scene_ctrl.dt()) };
+                if let Err(e) = res {
+                    eprintln!("Replay buffer turned off: {e}");
+                    unsafe { replay.delete() };
+                    self.replay = None;
+                }
+            }
+
+            if let Some(recording) = self.recording.as_mut() {
+                let resized = recording.size() != self.viewport.physical();
+                unsafe { gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0) };
+                if resized || !unsafe { recording.capture() } {
+                    if resized {
+                        println!("Recording stopped: the window was resized");
+                    }
+                    let recording = self.recording.take().unwrap();
+                    finish_recording(recording, self.hud.as_mut());
+                }
+            }
+
+            self.frame_time += (scene_ctrl.dt() - self.frame_time) * 0.05;
+            if let Some(fps_counter) = self.fps_counter.as_mut() {
+                fps_counter.update(scene_ctrl.dt());
+            }
+
+            // handled after drawing so that screenshots capture the frame about to be presented
+            let ctx = SceneContext {
+                size: self.viewport.physical_size(),
+                input: self.input.as_ref().unwrap(),
+                data: self.data.as_ref(),
+                seed: self.seed,
+                text: self.text.as_ref(),
+            };
+            let stats = FrameStats {
+                viewport: self.viewport.physical(),
+                frame_time: self.frame_time,
+                gpu_time: gpu_timer.duration("draw"),
+                post_times: (self.post.passes().iter())
+                    .filter(|pass| pass.enabled)
+                    .filter_map(|pass| {
+                        Some((pass.kind.name(), gpu_timer.duration(pass.kind.name())?))
+                    })
+                    .collect(),
+                ab: (self.ab.as_ref()).and_then(AbHarness::last_report).cloned(),
+            };
+
+            if let Some(remote) = &self.remote {
+                for request in remote.poll() {
+                    let command = request.command.clone();
+                    let targets = CommandTargets {
+                        scenes: &mut *scenes,
+                        post: &mut self.post,
+                        #[cfg(feature = "scripting")]
+                        script: &mut self.script,
+                    };
+                    match run_command(command, targets, &ctx, &stats) {
+                        Ok(fields) => request.ok(fields),
+                        Err(e) => request.err(e),
+                    }
+                }
+            }
+
+            if let Some(hud) = self.hud.as_mut() {
+                for command in hud.console.take_pending() {
+                    let targets = CommandTargets {
+                        scenes: &mut *scenes,
+                        post: &mut self.post,
+                        #[cfg(feature = "scripting")]
+                        script: &mut self.script,
+                    };
+                    match run_command(command, targets, &ctx, &stats) {
+                        Ok(fields) => hud.console.print(console::describe(&fields)),
+                        Err(e) => hud.console.error(e),
+                    }
+                }
+            }
+
+            if std::mem::take(&mut self.screenshot) {
+                let path = timestamped_path("screenshot", "png");
+                match export::save_framebuffer(&path, self.viewport.physical()) {
+                    Ok(()) => {
+                        println!("Saved screenshot to {}", path.display());
+                        if let Some(hud) = self.hud.as_mut() {
+                            let path = path.display().to_string();
+                            hud.toast(tr_args("toast.screenshot", &[("path", &path)]));
+                        }
+                    }
+                    Err(e) => eprintln!("Could not save screenshot to {}: {e}", path.display()),
+                }
+            }
+
+            // drawn last so that screenshots only show the scene
+            watchdog::enter_pass(c"Overlay");
+            if let Some(hud) = self.hud.as_mut() {
+                hud.set_tooltip(scenes.tooltip(), mouse_pos);
+                hud.set_status(scenes.status());
+                unsafe { hud.draw(scenes.name(), scenes.params(), &self.viewport) };
+
+                if let Some((position, size)) = hud.console.take_caret_area() {
+                    window.set_ime_cursor_area(
+                        PhysicalPosition::new(position.x, position.y),
+                        PhysicalSize::new(size.x, size.y),
+                    );
+                }
+            }
+            if let Some(fps_counter) = self.fps_counter.as_mut() {
+                unsafe { fps_counter.draw(&self.viewport) };
+            }
+
+            watchdog::leave_pass();
 
             window.request_redraw();
-            gl_surface.swap_buffers(gl_context).unwrap();
+            {
+                profiling::cpu_zone!("swap");
+                watchdog::enter_pass(c"Swap");
+                unsafe { surface::finish_frame() };
+                gl_surface.swap_buffers(gl_context).unwrap();
+                watchdog::leave_pass();
+            }
+            if let Some(long_frame) = self.watchdog.as_ref().and_then(Watchdog::end_frame) {
+                eprintln!("Watchdog: {long_frame}");
+            }
+
+            unsafe { gpu_timer.poll() };
+            if let Some(ab) = self.ab.as_mut() {
+                ab.record_gpu(gpu_timer.resolved());
+                if let Some(report) = ab.take_report() {
+                    println!("A/B: {report}");
+                }
+            }
+            profiling::frame_mark();
         }
     }
 }
 
-// Find the config with the maximum number of samples, so our triangle will be
-// smooth.
-pub fn gl_config_picker(configs: Box<dyn Iterator<Item = Config> + '_>) -> Config {
+/// What the frame took, for commands asking about it.
+struct FrameStats {
+    viewport: UVec2,
+    /// Smoothed frame time in seconds.
+    frame_time: f32,
+    gpu_time: Option<f32>,
+    /// GPU time of each enabled post-processing pass, part of `gpu_time`.
+    post_times: Vec<(&'static str, f32)>,
+    /// Latest report of the A/B comparison running, if any.
+    ab: Option<AbReport>,
+}
+
+/// What commands from the remote control and the console act on.
+struct CommandTargets<'a> {
+    scenes: &'a mut Scenes,
+    post: &'a mut PostStack,
+    #[cfg(feature = "scripting")]
+    script: &'a mut Option<ScriptedScene>,
+}
+
+/// Runs a command from the remote control or the console. Returns the fields
+/// of its reply.
+///
+/// Parameter names may be prefixed with the scene they belong to, as in
+/// `kawase.radius`, which must be the current one. Those of post-processing
+/// passes are prefixed with `post` and the pass, as in `post.grain.size`.
+fn run_command(
+    command: RemoteCommand,
+    targets: CommandTargets,
+    ctx: &SceneContext,
+    stats: &FrameStats,
+) -> Result<serde_json::Value, String> {
+    let CommandTargets {
+        scenes,
+        post,
+        #[cfg(feature = "scripting")]
+        script,
+    } = targets;
+    match command {
+        RemoteCommand::SwitchScene { scene } => {
+            *scenes = Scenes::from_name(&scene, ctx).ok_or(format!("unknown scene '{scene}'"))?;
+            Ok(json!({}))
+        }
+        RemoteCommand::PressKey { key } => {
+            let key = sequencer::parse_key(&key).ok_or(format!("unknown key '{key}'"))?;
+            scenes.on_key(key);
+            Ok(json!({}))
+        }
+        RemoteCommand::SetParam { name, value } => {
+            if let Some((pass, name)) = (name.strip_prefix("post.")).and_then(|n| n.split_once('.'))
+            {
+                let kind =
+                    PassKind::from_name(pass).ok_or(format!("unknown post pass '{pass}'"))?;
+                let params = post.params_mut(kind);
+                params.set(name, value).map_err(|e| e.to_string())?;
+                return Ok(json!({ "value": params.get(name) }));
+            }
+
+            let scene = scenes.name();
+            let name = match name.split_once('.') {
+                Some((prefix, name)) if prefix == scene => name,
+                Some((prefix, _)) => {
+                    return Err(format!("the current scene is {scene}, not {prefix}"))
+                }
+                None => &name,
+            };
+
+            let params = (scenes.params_mut()).ok_or(format!("{scene} has no parameters"))?;
+            params.set(name, value).map_err(|e| e.to_string())?;
+            Ok(json!({ "value": params.get(name) }))
+        }
+        RemoteCommand::GetParams => {
+            let params = (scenes.params().into_iter())
+                .flat_map(|params| params.iter())
+                .map(|param| (param.name.to_string(), json!(param.value())))
+                .collect::<serde_json::Map<_, _>>();
+            Ok(json!({ "scene": scenes.name(), "params": params }))
+        }
+        RemoteCommand::Screenshot { path } => {
+            let path = path.map_or_else(|| timestamped_path("screenshot", "png"), PathBuf::from);
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            export::save_new_png(&path, stats.viewport).map_err(|e| e.to_string())?;
+            Ok(json!({ "path": path }))
+        }
+        RemoteCommand::Fps => {
+            let mut fields = json!({
+                "fps": 1.0 / stats.frame_time,
+                "frame_time_ms": stats.frame_time * 1000.0,
+                "gpu_time_ms": stats.gpu_time,
+                "post_time_ms": (stats.post_times.iter())
+                    .map(|&(name, time)| (name.to_string(), json!(time)))
+                    .collect::<serde_json::Map<_, _>>(),
+            });
+            if let Some(report) = &stats.ab {
+                fields["ab"] = json!(report.to_string());
+            }
+            Ok(fields)
+        }
+        #[cfg(feature = "scripting")]
+        RemoteCommand::RunScript { path } => {
+            *script = match path {
+                Some(path) => Some(unsafe { ScriptedScene::load(Path::new(&path)) }?),
+                None => None,
+            };
+            Ok(json!({}))
+        }
+        #[cfg(not(feature = "scripting"))]
+        RemoteCommand::RunScript { .. } => Err("scripts need the scripting feature".to_string()),
+    }
+}
+
+/// Compiles every shader on a hidden context, reports errors and exits.
+fn validate_shaders() -> ! {
+    let _headless = HeadlessContext::new(UVec2::ONE).unwrap_or_else(|e| {
+        eprintln!("Error: could not create a headless context: {e}");
+        std::process::exit(1);
+    });
+
+    let dir = assets::dir().join("shaders");
+    let validation = unsafe { Validation::dir(&dir) }.unwrap_or_else(|e| {
+        eprintln!("Error: could not read {}: {e}", dir.display());
+        std::process::exit(1);
+    });
+
+    for error in &validation.errors {
+        eprintln!("{error}");
+    }
+    println!(
+        "Compiled {} shader permutations, {} errors",
+        validation.compiled,
+        validation.errors.len()
+    );
+    if validation.skipped > 0 {
+        println!(
+            "Skipped {} shaders this context doesn't support",
+            validation.skipped
+        );
+    }
+
+    std::process::exit(if validation.is_ok() { 0 } else { 1 });
+}
+
+/// Renders a thumbnail of every scene, plugins included, to `dir` and exits.
+fn render_thumbnails(dir: &Path, max_image_size: u32) -> ! {
+    let headless = HeadlessContext::new(thumbnails::SIZE).unwrap_or_else(|e| {
+        eprintln!("Error: could not create a headless context: {e}");
+        std::process::exit(1);
+    });
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        eprintln!("Error: could not create {}: {e}", dir.display());
+        std::process::exit(1);
+    }
+
+    let text = unsafe { TextRenderer::shared() }.unwrap_or_else(|e| {
+        eprintln!("Error: could not create the text renderer: {e}");
+        std::process::exit(1);
+    });
+    let input = InputImage::gura(max_image_size);
+    let ctx = SceneContext {
+        size: headless.physical_size(),
+        input: &input,
+        data: None,
+        seed: thumbnails::SEED,
+        text: Some(&text),
+    };
+
+    let mut failed = 0;
+    for name in Scenes::names() {
+        let path = thumbnails::path(dir, name);
+        let mut scenes = Scenes::from_name(name, &ctx).unwrap();
+        let image = unsafe {
+            thumbnails::render(&mut scenes, &Scenes::title(name), &text, headless.size())
+        };
+
+        if let Some(e) = scenes.error() {
+            eprintln!("Error: {name} failed: {e}");
+            failed += 1;
+        }
+        match image.save(&path) {
+            Ok(()) => println!("Saved {}", path.display()),
+            Err(e) => {
+                eprintln!("Error: could not save {}: {e}", path.display());
+                failed += 1;
+            }
+        }
+    }
+
+    std::process::exit(if failed == 0 { 0 } else { 1 });
+}
+
+/// Writes what's left of `recording` and reports how it went.
+fn finish_recording(recording: Recording, hud: Option<&mut Hud>) {
+    let path = recording.path().display().to_string();
+    let message = match unsafe { recording.finish() } {
+        Ok(frames) => {
+            println!("Recorded {frames} frames to {path}");
+            let frames = frames.to_string();
+            tr_args("toast.recorded", &[("frames", &frames), ("path", &path)])
+        }
+        Err(e) => {
+            eprintln!("Recording to {path} failed: {e}");
+            let error = e.to_string();
+            tr_args("toast.recording_failed", &[("error", &error)])
+        }
+    };
+    if let Some(hud) = hud {
+        hud.toast(message);
+    }
+}
+
+/// `<prefix>-<unix time in ms>.<extension>` in the working directory, or
+/// without extension if it's empty, as for directories.
+fn timestamped_path(prefix: &str, extension: &str) -> PathBuf {
+    let millis = (SystemTime::now().duration_since(UNIX_EPOCH))
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+
+    match extension {
+        "" => PathBuf::from(format!("{prefix}-{millis}")),
+        extension => PathBuf::from(format!("{prefix}-{millis}.{extension}")),
+    }
+}
+
+// Find a config supporting transparency if the window should be transparent,
+// or one without it otherwise, as compositors have less to do for it. Float
+// pixels for HDR output come first.
+pub fn gl_config_picker(
+    configs: Box<dyn Iterator<Item = Config> + '_>,
+    transparent: bool,
+    hdr: bool,
+) -> Config {
     configs
         // .map(|config| {
         //     debug_gl_config(&config);
         //     config
         // })
         .reduce(|accum, config| {
-            if config.supports_transparency().unwrap_or(false)
-                && !accum.supports_transparency().unwrap_or(false)
-            {
+            let score = |config: &Config| {
+                let float = config.float_pixels() == hdr;
+                (
+                    float,
+                    config.supports_transparency().unwrap_or(false) == transparent,
+                )
+            };
+            if score(&config) > score(&accum) {
                 config
             } else {
                 accum
@@ -0,0 +1,155 @@
+//! Marching squares: the iso-contours of a scalar field sampled on a grid,
+//! through the points where the field crosses the iso-value along the edges
+//! of its cells, found cell by cell and then chained into polylines.
+//!
+//! Each contour keeps the values above the iso-value on its right, opposite
+//! [`Vec2::perp`] of its direction, and saddle cells are told apart by the
+//! average of their corners.
+
+use glam::{vec2, Vec2};
+
+/// Values of a scalar field at the points of a grid, row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>,
+}
+
+impl Field {
+    pub fn new(width: usize, height: usize, value: impl Fn(usize, usize) -> f32) -> Self {
+        let values = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| value(x, y))
+            .collect();
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        self.values[y * self.width + x]
+    }
+
+    /// Bilinear interpolation of the field at `p`, in grid units.
+    pub fn sample(&self, p: Vec2) -> f32 {
+        let max = vec2(self.width as f32 - 1.0, self.height as f32 - 1.0);
+        let p = p.clamp(Vec2::ZERO, max);
+        let (x, y) = (p.x as usize, p.y as usize);
+        let (x1, y1) = ((x + 1).min(self.width - 1), (y + 1).min(self.height - 1));
+        let f = p - vec2(x as f32, y as f32);
+
+        let top = self.get(x, y) + (self.get(x1, y) - self.get(x, y)) * f.x;
+        let bottom = self.get(x, y1) + (self.get(x1, y1) - self.get(x, y1)) * f.x;
+        top + (bottom - top) * f.y
+    }
+
+    pub fn range(&self) -> (f32, f32) {
+        (self.values.iter()).fold((f32::MAX, f32::MIN), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        })
+    }
+
+    /// Edge between the grid point at `(x, y)` and the next one down if
+    /// `vertical`, or right otherwise.
+    fn edge(&self, x: usize, y: usize, vertical: bool) -> usize {
+        (y * self.width + x) * 2 + vertical as usize
+    }
+
+    /// Where the field crosses `iso` along `edge`, in grid units.
+    fn crossing(&self, edge: usize, iso: f32) -> Vec2 {
+        let (point, vertical) = (edge / 2, edge % 2 == 1);
+        let (x, y) = (point % self.width, point / self.width);
+        let (x1, y1) = if vertical { (x, y + 1) } else { (x + 1, y) };
+
+        let (a, b) = (self.get(x, y), self.get(x1, y1));
+        let t = (iso - a) / (b - a);
+        vec2(x as f32, y as f32).lerp(vec2(x1 as f32, y1 as f32), t)
+    }
+}
+
+/// The contours of `field` at `iso`, in grid units. Closed ones end with
+/// their first point, open ones start and end on the border of the grid.
+pub fn contours(field: &Field, iso: f32) -> Vec<Vec<Vec2>> {
+    let (width, height) = (field.width, field.height);
+    if width < 2 || height < 2 {
+        return Vec::new();
+    }
+
+    // every crossing is left by at most one segment and reached by at most
+    // one, from the two cells sharing its edge
+    let mut next = vec![None; width * height * 2];
+    let mut reached = vec![false; width * height * 2];
+
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+            let above = corners.map(|(x, y)| field.get(x, y) > iso);
+            // clockwise on screen, each from the corner of the same index
+            let edges = [
+                field.edge(x, y, false),
+                field.edge(x + 1, y, true),
+                field.edge(x, y + 1, false),
+                field.edge(x, y, true),
+            ];
+
+            // crossings in the same order, with whether they enter the
+            // values above the iso-value
+            let mut crossings = [(0, false); 4];
+            let mut n = 0;
+            for i in 0..4 {
+                let entering = above[(i + 1) % 4];
+                if above[i] != entering {
+                    crossings[n] = (edges[i], entering);
+                    n += 1;
+                }
+            }
+
+            // in a saddle, the values above the iso-value either join through
+            // the middle, cutting off the corners below, or don't
+            let center = corners.iter().map(|&(x, y)| field.get(x, y)).sum::<f32>() / 4.0;
+            let joined = n == 4 && center > iso;
+            for i in 0..n {
+                let (from, entering) = crossings[i];
+                if !entering {
+                    continue;
+                }
+                let to = match joined {
+                    true => crossings[(i + n - 1) % n].0,
+                    false => crossings[(i + 1) % n].0,
+                };
+                next[from] = Some(to);
+                reached[to] = true;
+            }
+        }
+    }
+
+    // open contours first, from the border where nothing reaches them, so
+    // that only closed ones are left
+    let open_starts = (0..next.len())
+        .filter(|&edge| next[edge].is_some() && !reached[edge])
+        .collect::<Vec<_>>();
+    let mut lines = (open_starts.into_iter())
+        .map(|start| follow(field, iso, &mut next, start))
+        .collect::<Vec<_>>();
+    for start in 0..next.len() {
+        if next[start].is_some() {
+            lines.push(follow(field, iso, &mut next, start));
+        }
+    }
+    lines
+}
+
+/// Walks the segments from the crossing on `start`, taking them out of
+/// `next`.
+fn follow(field: &Field, iso: f32, next: &mut [Option<usize>], start: usize) -> Vec<Vec2> {
+    let mut line = vec![field.crossing(start, iso)];
+    let mut edge = start;
+    while let Some(to) = next[edge].take() {
+        line.push(field.crossing(to, iso));
+        edge = to;
+    }
+    line
+}
@@ -0,0 +1,86 @@
+//! MIDI controller input: CC knobs and faders mapped to scene parameters.
+
+use std::error::Error;
+use std::sync::mpsc::{self, Receiver};
+
+use midir::{Ignore, MidiInput, MidiInputConnection};
+
+/// Knobs 1-4 of most controllers.
+pub const DEFAULT_MAPPING: &str = "1=radius,2=layers,3=kernel,4=dither";
+
+/// Which controller number drives which scene parameter.
+pub type Mapping = Vec<(u8, String)>;
+
+/// Parses a mapping written as `cc=param,cc=param,...`.
+pub fn parse_mapping(mapping: &str) -> Result<Mapping, String> {
+    (mapping.split(','))
+        .map(|entry| {
+            let (cc, param) = (entry.split_once('='))
+                .ok_or_else(|| format!("expected 'cc=param', got '{entry}'"))?;
+            let cc = (cc.trim().parse::<u8>())
+                .map_err(|_| format!("invalid controller number '{cc}'"))?;
+            Ok((cc, param.trim().to_string()))
+        })
+        .collect()
+}
+
+pub struct MidiControls {
+    // dropping a connection closes it
+    _connections: Vec<MidiInputConnection<()>>,
+    events: Receiver<(u8, u8)>,
+    mapping: Mapping,
+}
+
+impl MidiControls {
+    /// Listens to control changes on every available MIDI input port.
+    pub fn connect(mapping: Mapping) -> Result<Self, Box<dyn Error>> {
+        let (sender, events) = mpsc::channel();
+
+        let ports = MidiInput::new("opengl-playground")?.ports();
+        let mut connections = Vec::with_capacity(ports.len());
+
+        for port in &ports {
+            let mut input = MidiInput::new("opengl-playground")?;
+            input.ignore(Ignore::All);
+
+            let name = input.port_name(port)?;
+            let sender = sender.clone();
+            let connection = input
+                .connect(
+                    port,
+                    "opengl-playground-in",
+                    move |_stamp, message, _| {
+                        // control change: 0xBn cc value
+                        if let &[status, cc, value] = message {
+                            if status & 0xF0 == 0xB0 {
+                                let _ = sender.send((cc, value));
+                            }
+                        }
+                    },
+                    (),
+                )
+                .map_err(|e| e.to_string())?;
+
+            println!("MIDI input: {name}");
+            connections.push(connection);
+        }
+
+        if connections.is_empty() {
+            println!("MIDI input: no ports available");
+        }
+
+        Ok(Self {
+            _connections: connections,
+            events,
+            mapping,
+        })
+    }
+
+    /// Parameter changes received since the last call, as normalized values.
+    pub fn poll(&self) -> impl Iterator<Item = (&str, f32)> + '_ {
+        self.events.try_iter().filter_map(|(cc, value)| {
+            let (_, param) = self.mapping.iter().find(|(c, _)| *c == cc)?;
+            Some((param.as_str(), value as f32 / 127.0))
+        })
+    }
+}
@@ -0,0 +1,74 @@
+//! Experimental output of the HDR scenes beyond SDR white.
+//!
+//! The sun and the n-body scenes render in linear floats and tonemap to the
+//! window with a filmic curve. With `--output hdr`, the window asks for a
+//! floating point config, which compositors like those of Windows and macOS
+//! present as extended linear sRGB (scRGB): 1.0 is SDR white and brighter
+//! values light up HDR displays. Those scenes then write exposed linear
+//! values instead of tonemapping them, as does the `hdr-test` scene.
+//!
+//! This is as far as glutin goes: it can't request a Display-P3 or HDR10 (PQ)
+//! color space, so wide gamut only comes from the negative and out of gamut
+//! values scRGB allows. Without a float config the window comes up as usual,
+//! and everything stays in SDR.
+//!
+//! The rest of the playground keeps drawing gamma encoded colors, which a
+//! linear surface shows washed out.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Brightest value written in HDR, in multiples of SDR white: 1000 nits over
+/// the 80 of scRGB.
+pub const PEAK: f32 = 12.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// Tonemapped to 8 bits per channel.
+    #[default]
+    Sdr,
+    /// Linear floats beyond SDR white, where a float config is available.
+    Hdr,
+}
+
+impl OutputMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sdr => "sdr",
+            Self::Hdr => "hdr",
+        }
+    }
+}
+
+impl FromStr for OutputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sdr" => Ok(Self::Sdr),
+            "hdr" => Ok(Self::Hdr),
+            _ => Err(format!("'{s}' is neither sdr nor hdr")),
+        }
+    }
+}
+
+// Only set once the surface turned out to have float pixels.
+static HDR: AtomicBool = AtomicBool::new(false);
+
+/// The mode the window actually outputs in, which falls back to SDR when HDR
+/// was requested but isn't available.
+pub fn mode() -> OutputMode {
+    match HDR.load(Ordering::Relaxed) {
+        true => OutputMode::Hdr,
+        false => OutputMode::Sdr,
+    }
+}
+
+pub fn set_mode(mode: OutputMode) {
+    HDR.store(mode == OutputMode::Hdr, Ordering::Relaxed);
+}
+
+/// Whether HDR scenes should skip tonemapping, as a shader uniform.
+pub fn hdr_uniform() -> i32 {
+    (mode() == OutputMode::Hdr) as i32
+}
@@ -0,0 +1,163 @@
+//! Color schemes for randomly generated shapes.
+//!
+//! Colors are packed as RGBA bytes in a little-endian `u32`, the way quads
+//! store them until they turn into vertices.
+
+use glam::{Quat, Vec3};
+use rand::Rng;
+
+/// Variants of [`Palette`], in order, as named in parameters.
+pub const PALETTES: &[&str] = &["random", "viridis", "pastel", "monochrome", "complementary"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    /// Any bright color, partly transparent.
+    #[default]
+    Random,
+    /// Along the viridis colormap, from deep purple to yellow.
+    Viridis,
+    /// Soft and light, every hue washed with white.
+    Pastel,
+    /// Shades of gray.
+    Monochrome,
+    /// Teal and orange, each fading into the other.
+    Complementary,
+}
+
+/// Colors of one shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Swatch {
+    pub fill: u32,
+    pub stroke: u32,
+    /// What the fill blends into with a gradient.
+    pub gradient: u32,
+}
+
+/// Hues of [`Palette::Complementary`], opposite on the color wheel.
+const COMPLEMENTARY_HUES: [f32; 2] = [0.56, 0.06];
+
+impl Palette {
+    pub fn from_variant(variant: usize) -> Self {
+        match variant {
+            1 => Self::Viridis,
+            2 => Self::Pastel,
+            3 => Self::Monochrome,
+            4 => Self::Complementary,
+            _ => Self::Random,
+        }
+    }
+
+    pub fn swatch(self, rng: &mut impl Rng) -> Swatch {
+        match self {
+            Self::Random => {
+                let fill = u32::from_le_bytes([
+                    rng.gen_range(128..=255),
+                    rng.gen_range(128..=255),
+                    rng.gen_range(128..=255),
+                    rng.gen_range(128..=255),
+                ]);
+                let stroke = u32::from_le_bytes([
+                    rng.gen_range(24..=128),
+                    rng.gen_range(24..=128),
+                    rng.gen_range(24..=128),
+                    rng.gen_range(128..=255),
+                ]);
+
+                // a darker neighboring hue: fill colors are already light,
+                // and hues too far apart clash
+                let hue_shift = rng.gen_range(0.4..=1.0) * if rng.gen() { 1.0 } else { -1.0 };
+                let lightness = rng.gen_range(0.45..=0.75);
+                let gradient = shade(fill, hue_shift, lightness);
+
+                Swatch {
+                    fill,
+                    stroke,
+                    gradient,
+                }
+            }
+
+            Self::Viridis => {
+                let t = rng.gen_range(0.0..=1.0);
+                // toward the other end of the map, for contrast
+                let other = if t < 0.5 { t + 0.35 } else { t - 0.35 };
+                opaque(viridis(t), viridis(t) * 0.4, viridis(other))
+            }
+
+            Self::Pastel => {
+                let h = rng.gen_range(0.0..1.0);
+                let neighbor = h + rng.gen_range(0.08..=0.16) * if rng.gen() { 1.0 } else { -1.0 };
+                let fill = hue(h).lerp(Vec3::ONE, rng.gen_range(0.5..=0.65));
+                let stroke = hue(h) * 0.45 + 0.25;
+                opaque(fill, stroke, hue(neighbor).lerp(Vec3::ONE, 0.45))
+            }
+
+            Self::Monochrome => {
+                let l = rng.gen_range(0.25..=0.95);
+                let stroke = rng.gen_range(0.1..=0.2);
+                opaque(Vec3::splat(l), Vec3::splat(stroke), Vec3::splat(l * 0.55))
+            }
+
+            Self::Complementary => {
+                let side = rng.gen_range(0..2);
+                let jitter = rng.gen_range(-0.03..=0.03);
+                let [h, other] = [side, 1 - side].map(|i| COMPLEMENTARY_HUES[i] + jitter);
+
+                let l = rng.gen_range(0.55..=0.85);
+                let fill = Vec3::splat(l).lerp(hue(h), 0.7);
+                let gradient = Vec3::splat(l * 0.8).lerp(hue(other), 0.7);
+                opaque(fill, fill * 0.4, gradient)
+            }
+        }
+    }
+}
+
+/// The viridis colormap at `t` between 0 and 1, from a polynomial fit by
+/// Matt Zucker.
+pub fn viridis(t: f32) -> Vec3 {
+    const C: [Vec3; 7] = [
+        Vec3::new(0.277_727_33, 0.005_407_344_5, 0.334_099_8),
+        Vec3::new(0.105_093_04, 1.404_613_5, 1.384_590_2),
+        Vec3::new(-0.330_861_83, 0.214_847_56, 0.095_095_16),
+        Vec3::new(-4.634_230_6, -5.799_101, -19.332_441),
+        Vec3::new(6.228_27, 14.179_933, 56.690_55),
+        Vec3::new(4.776_385, -13.745_145, -65.353_035),
+        Vec3::new(-5.435_456, 4.645_852_6, 26.312_435),
+    ];
+
+    let t = t.clamp(0.0, 1.0);
+    C.iter().rev().fold(Vec3::ZERO, |acc, &c| acc * t + c)
+}
+
+/// Fully saturated color of hue `h`, in turns.
+pub fn hue(h: f32) -> Vec3 {
+    let h = h.rem_euclid(1.0) * 6.0;
+    let channel = |offset: f32| ((h - offset).rem_euclid(6.0) - 3.0).abs() - 1.0;
+    Vec3::new(channel(0.0), channel(2.0), channel(4.0)).clamp(Vec3::ZERO, Vec3::ONE)
+}
+
+/// Rotates the hue of `color` by `angle` radians and scales it by `lightness`.
+pub fn shade(color: u32, angle: f32, lightness: f32) -> u32 {
+    let [r, g, b, a] = color.to_le_bytes();
+    let rgb = Vec3::new(r as f32, g as f32, b as f32) / 255.0;
+
+    // rotating around the gray diagonal of the RGB cube shifts the hue
+    let hue = Quat::from_axis_angle(Vec3::ONE.normalize(), angle);
+    pack(hue * rgb * lightness, a)
+}
+
+/// Packs `rgb` between 0 and 1 with `alpha`.
+pub fn pack(rgb: Vec3, alpha: u8) -> u32 {
+    let [r, g, b] = (rgb * 255.0)
+        .clamp(Vec3::ZERO, Vec3::splat(255.0))
+        .to_array()
+        .map(|n| n as u8);
+    u32::from_le_bytes([r, g, b, alpha])
+}
+
+fn opaque(fill: Vec3, stroke: Vec3, gradient: Vec3) -> Swatch {
+    Swatch {
+        fill: pack(fill, 255),
+        stroke: pack(stroke, 255),
+        gradient: pack(gradient, 255),
+    }
+}
@@ -0,0 +1,272 @@
+//! Named, typed and ranged scene parameters.
+//!
+//! Scenes register their parameters once, and every frontend (keys, MIDI,
+//! remote control, ...) reads and writes them through the registry instead
+//! of needing bespoke glue per scene.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    F32 { min: f32, max: f32, step: f32 },
+    I32 { min: i32, max: i32 },
+    Bool,
+    Enum { variants: &'static [&'static str] },
+}
+
+/// A parameter value as exchanged with the outside world.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Bool(bool),
+    Number(f64),
+    /// Variant name of an enum parameter.
+    Text(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Text(t) => write!(f, "{t}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: &'static str,
+    pub kind: Kind,
+    // f32 and i32 both fit losslessly in an f64, bools are 0 or 1, enums are
+    // the index of their variant
+    value: f64,
+}
+
+impl Param {
+    pub fn value(&self) -> Value {
+        match self.kind {
+            Kind::F32 { .. } | Kind::I32 { .. } => Value::Number(self.value),
+            Kind::Bool => Value::Bool(self.value != 0.0),
+            Kind::Enum { variants } => Value::Text(variants[self.value as usize].to_string()),
+        }
+    }
+
+    /// The value mapped to `[0, 1]` over the parameter's range.
+    pub fn normalized(&self) -> f32 {
+        let (min, max) = self.range();
+        if max > min {
+            ((self.value - min) / (max - min)) as f32
+        } else {
+            0.0
+        }
+    }
+
+    fn range(&self) -> (f64, f64) {
+        match self.kind {
+            Kind::F32 { min, max, .. } => (min as f64, max as f64),
+            Kind::I32 { min, max } => (min as f64, max as f64),
+            Kind::Bool => (0.0, 1.0),
+            Kind::Enum { variants } => (0.0, (variants.len() - 1) as f64),
+        }
+    }
+
+    fn set_raw(&mut self, value: f64) {
+        let (min, max) = self.range();
+        let value = value.clamp(min, max);
+
+        self.value = match self.kind {
+            Kind::F32 { .. } => value as f32 as f64,
+            _ => value.round(),
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamError {
+    Unknown(String),
+    WrongType { name: &'static str, value: Value },
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "unknown parameter '{name}'"),
+            Self::WrongType { name, value } => write!(f, "invalid value '{value}' for '{name}'"),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+#[derive(Debug, Clone, Default)]
+pub struct Params {
+    params: Vec<Param>,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // --- registration ---
+
+    pub fn with_f32(self, name: &'static str, value: f32, min: f32, max: f32, step: f32) -> Self {
+        self.with(name, Kind::F32 { min, max, step }, value as f64)
+    }
+
+    pub fn with_i32(self, name: &'static str, value: i32, min: i32, max: i32) -> Self {
+        self.with(name, Kind::I32 { min, max }, value as f64)
+    }
+
+    pub fn with_bool(self, name: &'static str, value: bool) -> Self {
+        self.with(name, Kind::Bool, value as u8 as f64)
+    }
+
+    pub fn with_enum(
+        self,
+        name: &'static str,
+        value: usize,
+        variants: &'static [&'static str],
+    ) -> Self {
+        self.with(name, Kind::Enum { variants }, value as f64)
+    }
+
+    fn with(mut self, name: &'static str, kind: Kind, value: f64) -> Self {
+        debug_assert!(
+            self.find(name).is_none(),
+            "parameter {name} registered twice"
+        );
+
+        let mut param = Param {
+            name,
+            kind,
+            value: 0.0,
+        };
+        param.set_raw(value);
+
+        self.params.push(param);
+        self
+    }
+
+    // --- typed access for the scene itself ---
+
+    pub fn f32(&self, name: &str) -> f32 {
+        self.expect(name).value as f32
+    }
+
+    pub fn i32(&self, name: &str) -> i32 {
+        self.expect(name).value as i32
+    }
+
+    pub fn bool(&self, name: &str) -> bool {
+        self.expect(name).value != 0.0
+    }
+
+    /// Index of the current variant of an enum parameter.
+    pub fn variant(&self, name: &str) -> usize {
+        self.expect(name).value as usize
+    }
+
+    fn expect(&self, name: &str) -> &Param {
+        (self.find(name)).unwrap_or_else(|| panic!("no parameter named {name}"))
+    }
+
+    fn find(&self, name: &str) -> Option<&Param> {
+        self.params.iter().find(|p| p.name == name)
+    }
+
+    fn find_mut(&mut self, name: &str) -> Result<&mut Param, ParamError> {
+        (self.params.iter_mut().find(|p| p.name == name))
+            .ok_or_else(|| ParamError::Unknown(name.to_string()))
+    }
+
+    // --- generic access for frontends ---
+
+    pub fn iter(&self) -> impl Iterator<Item = &Param> {
+        self.params.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.find(name).map(Param::value)
+    }
+
+    /// Sets a parameter, clamping it to its range.
+    pub fn set(&mut self, name: &str, value: Value) -> Result<(), ParamError> {
+        let param = self.find_mut(name)?;
+
+        let raw = match (&param.kind, &value) {
+            (Kind::F32 { .. } | Kind::I32 { .. }, Value::Number(n)) => *n,
+            (Kind::Bool, Value::Bool(b)) => *b as u8 as f64,
+            (Kind::Enum { variants }, Value::Text(t)) => match variants.iter().position(|v| v == t)
+            {
+                Some(i) => i as f64,
+                None => {
+                    return Err(ParamError::WrongType {
+                        name: param.name,
+                        value,
+                    })
+                }
+            },
+            (Kind::Enum { .. }, Value::Number(n)) => *n,
+            _ => {
+                return Err(ParamError::WrongType {
+                    name: param.name,
+                    value,
+                })
+            }
+        };
+
+        param.set_raw(raw);
+        Ok(())
+    }
+
+    /// Sets a parameter from a value in `[0, 1]` mapped over its range, e.g. from a MIDI knob.
+    pub fn set_normalized(&mut self, name: &str, t: f32) -> Result<(), ParamError> {
+        let param = self.find_mut(name)?;
+        let (min, max) = param.range();
+        param.set_raw(min + (max - min) * t.clamp(0.0, 1.0) as f64);
+        Ok(())
+    }
+
+    /// Moves a parameter by `steps` increments: its step for floats, one for
+    /// integers and enums (wrapping around), toggling for booleans.
+    pub fn step(&mut self, name: &str, steps: i32) -> Result<(), ParamError> {
+        let param = self.find_mut(name)?;
+
+        match param.kind {
+            Kind::F32 { step, .. } => param.set_raw(param.value + (step * steps as f32) as f64),
+            Kind::I32 { .. } => param.set_raw(param.value + steps as f64),
+            Kind::Bool => param.set_raw(if param.value == 0.0 { 1.0 } else { 0.0 }),
+            Kind::Enum { variants } => {
+                let len = variants.len() as i32;
+                param.set_raw((param.value as i32 + steps).rem_euclid(len) as f64);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn toggle(&mut self, name: &str) -> Result<(), ParamError> {
+        self.step(name, 1)
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+
+            match param.kind {
+                Kind::F32 { .. } => write!(f, "{}={:.2}", param.name, param.value)?,
+                _ => write!(f, "{}={}", param.name, param.value())?,
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+//! Panel of sliders for the parameters of the current scene, opened with
+//! `Ctrl+P`.
+//!
+//! Numbers are dragged along their range, booleans toggle on a click and
+//! enums go to their next variant, or the previous one on a right click. The
+//! panel is immediate mode: it is laid out again from the parameters every
+//! frame, and clicks are tested against what was laid out last.
+
+use glam::{vec2, Vec2};
+use winit::event::{ElementState, MouseButton};
+
+use crate::locale::tr;
+use crate::params::{Kind, Param, Params, Value};
+use crate::text::TextRenderer;
+use crate::theme;
+use crate::viewport::Viewport;
+
+/// Sizes in logical pixels.
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+const TEXT_SIZE: f32 = 14.0;
+const SLIDER_WIDTH: f32 = 150.0;
+
+/// Decimals shown for steps finer than this many.
+const MAX_DECIMALS: usize = 4;
+
+/// A slider as laid out on the last frame, in physical pixels.
+#[derive(Debug, Clone, Copy)]
+struct Slider {
+    name: &'static str,
+    kind: Kind,
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Slider {
+    fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmplt(self.max).all()
+    }
+
+    /// Where `x` falls along the slider, in `[0, 1]`.
+    fn fraction(&self, x: f32) -> f32 {
+        ((x - self.min.x) / (self.max.x - self.min.x)).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ParamsPanel {
+    open: bool,
+    /// Area of the whole panel on the last frame, if it was shown.
+    area: Option<(Vec2, Vec2)>,
+    sliders: Vec<Slider>,
+    /// Index of the slider being dragged.
+    dragging: Option<usize>,
+}
+
+impl ParamsPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.dragging = None;
+    }
+
+    /// Handles a mouse button at `mouse_pos`, in physical pixels, and returns
+    /// whether the panel took it, in which case the scene shouldn't see it.
+    pub fn on_mouse_input(
+        &mut self,
+        params: Option<&mut Params>,
+        state: ElementState,
+        button: MouseButton,
+        mouse_pos: Vec2,
+    ) -> bool {
+        if state == ElementState::Released {
+            return self.dragging.take().is_some() || self.contains(mouse_pos);
+        }
+        if !self.contains(mouse_pos) {
+            return false;
+        }
+
+        let Some(params) = params else {
+            return true;
+        };
+        let Some(index) = (self.sliders.iter()).position(|slider| slider.contains(mouse_pos))
+        else {
+            return true;
+        };
+
+        let slider = self.sliders[index];
+        let result = match (slider.kind, button) {
+            (Kind::F32 { .. } | Kind::I32 { .. }, MouseButton::Left) => {
+                self.dragging = Some(index);
+                params.set_normalized(slider.name, slider.fraction(mouse_pos.x))
+            }
+            (Kind::Bool | Kind::Enum { .. }, MouseButton::Left) => params.step(slider.name, 1),
+            (Kind::Enum { .. }, MouseButton::Right) => params.step(slider.name, -1),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("{e}");
+        }
+
+        true
+    }
+
+    /// Moves the slider being dragged, if any, and returns whether there was
+    /// one.
+    pub fn on_mouse_moved(&mut self, params: Option<&mut Params>, mouse_pos: Vec2) -> bool {
+        let (Some(index), Some(params)) = (self.dragging, params) else {
+            return false;
+        };
+
+        // the scene could have changed since the slider was laid out
+        let Some(&slider) = self.sliders.get(index) else {
+            return false;
+        };
+        if let Err(e) = params.set_normalized(slider.name, slider.fraction(mouse_pos.x)) {
+            eprintln!("{e}");
+        }
+        true
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        self.area
+            .is_some_and(|(min, max)| point.cmpge(min).all() && point.cmplt(max).all())
+    }
+
+    /// Forgets the layout of the last frame, when the panel isn't shown.
+    pub fn hide(&mut self) {
+        self.area = None;
+        self.sliders.clear();
+        self.dragging = None;
+    }
+
+    /// Queues the panel at the top right of the window, over a panel of color
+    /// `background`, with a slider for each of `params`.
+    pub fn queue(
+        &mut self,
+        text: &mut TextRenderer,
+        viewport: &Viewport,
+        params: Option<&Params>,
+        background: [f32; 4],
+    ) {
+        if !self.open {
+            self.hide();
+            return;
+        }
+
+        let theme = theme::current();
+        let size = viewport.px(TEXT_SIZE);
+        let padding = viewport.px(PADDING);
+        let slider_width = viewport.px(SLIDER_WIDTH);
+        let params = params.map(|params| params.iter().collect::<Vec<_>>());
+        let params = params.unwrap_or_default();
+
+        let title = tr("params.title");
+        let none = tr("params.none");
+        let mut header_width = text.measure(&title, size, None).x;
+        if params.is_empty() {
+            header_width = header_width.max(text.measure(&none, size, None).x);
+        }
+        let label_width = (params.iter())
+            .map(|param| text.measure(param.name, size, None).x)
+            .fold(header_width - slider_width - padding, f32::max)
+            .max(0.0);
+        let width = (label_width + padding + slider_width).round();
+
+        let line = text.measure(&title, size, None).y;
+        let gap = padding / 2.0;
+        let rows = params.len().max(1) as f32;
+        let min = vec2(
+            viewport.size().x - viewport.px(MARGIN) - width - 2.0 * padding,
+            viewport.px(MARGIN),
+        );
+        let max = min + vec2(width, line + rows * (line + gap)) + 2.0 * padding;
+        // rectangles are blended in order, so the panel goes first
+        text.queue_rect(min, max, background);
+        self.area = Some((min, max));
+
+        let mut pen = min + padding;
+        text.queue(&title, pen, size, None, theme.accent);
+        pen.y += line + gap;
+
+        if params.is_empty() {
+            text.queue(&none, pen, size, None, theme.hud_text);
+        }
+
+        self.sliders.clear();
+        for param in params {
+            text.queue(param.name, pen, size, None, theme.hud_text);
+
+            let slider_min = vec2(pen.x + label_width + padding, pen.y);
+            let slider_max = slider_min + vec2(slider_width, line);
+            let filled = slider_min.x + (slider_width * param.normalized()).round();
+            text.queue_rect(slider_min, slider_max, with_alpha(theme.grid, 0.25));
+            text.queue_rect(
+                slider_min,
+                vec2(filled, slider_max.y),
+                with_alpha(theme.accent, 0.5),
+            );
+            let value_pos = slider_min + vec2(gap, 0.0);
+            text.queue(&format_value(param), value_pos, size, None, theme.hud_text);
+
+            self.sliders.push(Slider {
+                name: param.name,
+                kind: param.kind,
+                min: slider_min,
+                max: slider_max,
+            });
+            pen.y += line + gap;
+        }
+    }
+}
+
+fn with_alpha([r, g, b, _]: [f32; 4], alpha: f32) -> [f32; 4] {
+    [r, g, b, alpha]
+}
+
+/// Value of `param` as shown on its slider: numbers with as many decimals as
+/// their step has, enums by the name of their variant.
+pub fn format_value(param: &Param) -> String {
+    match param.kind {
+        Kind::F32 { step, .. } => {
+            let decimals = (0..MAX_DECIMALS)
+                .find(|&decimals| {
+                    let scaled = step * 10f32.powi(decimals as i32);
+                    (scaled - scaled.round()).abs() < 1e-3
+                })
+                .unwrap_or(MAX_DECIMALS);
+            match param.value() {
+                Value::Number(value) => format!("{value:.decimals$}"),
+                value => value.to_string(),
+            }
+        }
+        _ => param.value().to_string(),
+    }
+}
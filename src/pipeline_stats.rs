@@ -0,0 +1,185 @@
+//! How much work passes give the GPU, through `GL_ARB_pipeline_statistics_query`
+//! (core since 4.6) next to the core primitive and occlusion queries: the
+//! vertices submitted, the primitives generated, the fragment shader
+//! invocations and the samples that passed the depth and stencil tests.
+//!
+//! Fragments per pixel of the target tell how much a pass overdraws, and
+//! fragments per sample passed how much of its shading is thrown away. As
+//! with [`crate::gpu_timer`], results only become available a few frames
+//! after being queried, so passes stay pending until then.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use gl::types::{GLenum, GLint, GLuint, GLuint64};
+
+use crate::common_gl::{gl_version, has_extension};
+use crate::gl_ext;
+
+/// What each of the queries of a pass counts, in the order of the fields of
+/// [`Stats`].
+const TARGETS: [GLenum; 4] = [
+    gl_ext::VERTICES_SUBMITTED,
+    gl::PRIMITIVES_GENERATED,
+    gl_ext::FRAGMENT_SHADER_INVOCATIONS,
+    gl::SAMPLES_PASSED,
+];
+
+/// Work of a pass, or the average of several.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub vertices: u64,
+    pub primitives: u64,
+    pub fragments: u64,
+    pub samples: u64,
+}
+
+impl Stats {
+    fn from_counts([vertices, primitives, fragments, samples]: [u64; 4]) -> Self {
+        Self {
+            vertices,
+            primitives,
+            fragments,
+            samples,
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} vertices, {} primitives, {} fragments, {} samples",
+            Count(self.vertices),
+            Count(self.primitives),
+            Count(self.fragments),
+            Count(self.samples),
+        )
+    }
+}
+
+/// A count with a metric suffix and 3 significant digits, as in `2.07M`.
+struct Count(u64);
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = self.0 as f64;
+        let (value, suffix) = match self.0 {
+            0..1_000 => return write!(f, "{}", self.0),
+            1_000..1_000_000 => (n / 1e3, "k"),
+            1_000_000..1_000_000_000 => (n / 1e6, "M"),
+            _ => (n / 1e9, "G"),
+        };
+        let decimals = match value {
+            100.0.. => 0,
+            10.0.. => 1,
+            _ => 2,
+        };
+        write!(f, "{value:.decimals$}{suffix}")
+    }
+}
+
+struct Pass {
+    name: &'static str,
+    queries: [GLuint; 4],
+}
+
+/// Pipeline statistics of named passes, averaged over the passes resolved
+/// between two calls to [`Self::take_averages`].
+pub struct PipelineStats {
+    free_queries: Vec<[GLuint; 4]>,
+    current: Option<Pass>,
+    /// Passes in the order they ended.
+    pending: VecDeque<Pass>,
+    /// Sum of the resolved stats of each pass, and how many there were.
+    totals: BTreeMap<&'static str, ([u64; 4], u64)>,
+}
+
+impl PipelineStats {
+    pub unsafe fn supported() -> bool {
+        gl_version() >= (4, 6) || has_extension("GL_ARB_pipeline_statistics_query")
+    }
+
+    /// `None` without pipeline statistics queries.
+    pub unsafe fn new() -> Option<Self> {
+        Self::supported().then(|| Self {
+            free_queries: Vec::new(),
+            current: None,
+            pending: VecDeque::new(),
+            totals: BTreeMap::new(),
+        })
+    }
+
+    /// Starts counting the work of the GPU commands issued from now on.
+    /// Passes can't be nested.
+    pub unsafe fn begin(&mut self, name: &'static str) {
+        assert!(
+            self.current.is_none(),
+            "pipeline statistics can't be nested"
+        );
+
+        let queries = self.free_queries.pop().unwrap_or_else(|| {
+            let mut queries = [0; 4];
+            gl::GenQueries(4, queries.as_mut_ptr());
+            queries
+        });
+        for (target, query) in TARGETS.into_iter().zip(queries) {
+            gl::BeginQuery(target, query);
+        }
+        self.current = Some(Pass { name, queries });
+    }
+
+    pub unsafe fn end(&mut self) {
+        let pass = (self.current.take()).expect("PipelineStats::end called without begin");
+        for target in TARGETS {
+            gl::EndQuery(target);
+        }
+        self.pending.push_back(pass);
+    }
+
+    /// Collects the results of finished passes. Call once per frame.
+    pub unsafe fn poll(&mut self) {
+        // passes complete in order, so the first unavailable one blocks the rest
+        while let Some(pass) = self.pending.front() {
+            let last = pass.queries[3];
+            let mut available: GLint = 0;
+            gl::GetQueryObjectiv(last, gl::QUERY_RESULT_AVAILABLE, &mut available);
+            if available == 0 {
+                break;
+            }
+
+            let pass = self.pending.pop_front().unwrap();
+            let (total, count) = self.totals.entry(pass.name).or_default();
+            for (sum, query) in total.iter_mut().zip(pass.queries) {
+                let mut result: GLuint64 = 0;
+                gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut result);
+                *sum += result;
+            }
+            *count += 1;
+            self.free_queries.push(pass.queries);
+        }
+    }
+
+    /// Average stats of each pass resolved since the last call, by name.
+    pub fn take_averages(&mut self) -> BTreeMap<&'static str, Stats> {
+        let totals = std::mem::take(&mut self.totals).into_iter();
+        totals
+            .map(|(name, (total, count))| (name, Stats::from_counts(total.map(|sum| sum / count))))
+            .collect()
+    }
+}
+
+impl Drop for PipelineStats {
+    fn drop(&mut self) {
+        let queries = (self.free_queries.iter())
+            .chain(self.current.iter().map(|pass| &pass.queries))
+            .chain(self.pending.iter().map(|pass| &pass.queries))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+
+        unsafe { gl::DeleteQueries(queries.len() as i32, queries.as_ptr()) };
+    }
+}
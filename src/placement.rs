@@ -0,0 +1,87 @@
+//! Where the window opens and which monitor it's on.
+//!
+//! `--monitor` opens it on a monitor, by its index in the list the platform
+//! gives, `--position` at a place relative to that monitor, or to the whole
+//! desktop without one, and `--size` at a given size. Positions and sizes are
+//! in physical pixels like the monitors themselves. Wayland doesn't let
+//! windows place themselves, so there both are only requests the compositor
+//! ignores.
+
+use glam::{IVec2, UVec2};
+use winit::monitor::MonitorHandle;
+
+/// The rectangle of a monitor on the desktop, in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Monitor {
+    pub position: IVec2,
+    pub size: UVec2,
+}
+
+impl Monitor {
+    pub fn of(handle: &MonitorHandle) -> Self {
+        let position = handle.position();
+        let size = handle.size();
+        Self {
+            position: IVec2::new(position.x, position.y),
+            size: UVec2::new(size.width, size.height),
+        }
+    }
+
+    /// Where a window of size `window` goes on this monitor: at `offset`
+    /// from its corner if given, centered otherwise. Windows larger than the
+    /// monitor keep their title bar on it.
+    pub fn place(&self, window: UVec2, offset: Option<IVec2>) -> IVec2 {
+        let offset = offset.unwrap_or_else(|| {
+            let centered = (self.size.as_ivec2() - window.as_ivec2()) / 2;
+            centered.max(IVec2::ZERO)
+        });
+        self.position + offset
+    }
+
+    /// Where a window at `position` on `from` goes on this monitor, at the
+    /// same place relative to its size and fully on it if it fits.
+    pub fn carry_over(&self, from: &Monitor, position: IVec2, window: UVec2) -> IVec2 {
+        let relative = (position - from.position).as_vec2() / from.size.as_vec2();
+        let offset = (relative * self.size.as_vec2()).round().as_ivec2();
+        let max = (self.size.as_ivec2() - window.as_ivec2()).max(IVec2::ZERO);
+        self.position + offset.clamp(IVec2::ZERO, max)
+    }
+}
+
+/// Index of the monitor after the one at `current` among `count`, the first
+/// one if the window isn't on any.
+pub fn next(current: Option<usize>, count: usize) -> usize {
+    match current {
+        Some(current) => (current + 1) % count.max(1),
+        None => 0,
+    }
+}
+
+/// Parses a position like `100,-20`.
+pub fn parse_position(s: &str) -> Result<IVec2, String> {
+    let (x, y) = (s.split_once(',')).ok_or_else(|| format!("expected X,Y, got '{s}'"))?;
+    let parse = |n: &str| {
+        (n.trim().parse::<i32>()).map_err(|_| format!("'{}' is not a whole number", n.trim()))
+    };
+    Ok(IVec2::new(parse(x)?, parse(y)?))
+}
+
+/// Parses a window size like `1280x720`.
+pub fn parse_size(s: &str) -> Result<UVec2, String> {
+    let (width, height) = (s.split_once('x')).ok_or_else(|| format!("expected WxH, got '{s}'"))?;
+    let parse = |n: &str| match n.trim().parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("'{}' is not a positive whole number", n.trim())),
+    };
+    Ok(UVec2::new(parse(width)?, parse(height)?))
+}
+
+/// What the monitor is called, for messages.
+pub fn name(handle: &MonitorHandle) -> String {
+    handle.name().unwrap_or_else(|| "unnamed".to_string())
+}
+
+/// Refresh rate of the monitor in hertz, if the platform tells.
+pub fn refresh_rate(handle: &MonitorHandle) -> Option<f32> {
+    (handle.refresh_rate_millihertz()).map(|millihertz| millihertz as f32 / 1000.0)
+}
@@ -0,0 +1,274 @@
+//! Scenes built in separate crates and loaded at runtime with `--plugin`.
+//!
+//! A plugin is a `cdylib` exporting [`ENTRY_SYMBOL`], a function returning the
+//! [`SceneVTable`] of its scene. Everything crossing the boundary is
+//! `#[repr(C)]`, so plugins only have to agree with the playground on
+//! [`ABI_VERSION`], not on the compiler that built them.
+//!
+//! Plugins written in Rust implement [`Scene`] and export it with
+//! [`export_scene`]:
+//!
+//! ```ignore
+//! struct Rings { ... }
+//!
+//! impl plugin::Scene for Rings {
+//!     fn new(info: &plugin::CreateInfo) -> Self { ... }
+//!     fn draw(&mut self, matrix: &Mat4, mouse: Vec2) { ... }
+//! }
+//!
+//! opengl_playground::export_scene!(Rings, c"rings", c"Rings following the mouse.");
+//! ```
+//!
+//! The plugin has its own copy of the GL function pointers, which it loads from
+//! the [`Host`] each time a scene is created. See `examples/plugin_rings.rs`.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CStr};
+use std::path::Path;
+use std::rc::Rc;
+
+use glam::{Mat4, Vec2};
+use libloading::Library;
+
+use crate::gl_ext;
+
+/// Bumped whenever a type of this module changes layout.
+pub const ABI_VERSION: u32 = 1;
+
+/// Name of the function exported by plugins, of type [`EntryFn`].
+pub const ENTRY_SYMBOL: &CStr = c"playground_plugin_v1";
+
+pub type EntryFn = unsafe extern "C" fn() -> *const SceneVTable;
+
+/// What the playground gives to plugins.
+#[repr(C)]
+pub struct Host {
+    /// Address of a GL function of the current context, or null.
+    pub get_proc_address: unsafe extern "C" fn(name: *const c_char) -> *const c_void,
+}
+
+/// Everything a plugin scene may need when it gets created.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CreateInfo {
+    /// Size of the surface the scene renders to.
+    pub width: u32,
+    pub height: u32,
+    /// Seed for everything random in the scene.
+    pub seed: u64,
+}
+
+/// A plugin scene, as functions over its opaque state.
+///
+/// All functions are called on the thread owning the GL context, with the
+/// context current.
+#[repr(C)]
+pub struct SceneVTable {
+    /// Must be [`ABI_VERSION`].
+    pub abi_version: u32,
+    /// Name used on the command line, in sequences and in the scene cycle.
+    pub name: *const c_char,
+    /// Shown in the help overlay.
+    pub description: *const c_char,
+
+    /// Creates the scene, returning null on failure.
+    pub create: unsafe extern "C" fn(host: *const Host, info: *const CreateInfo) -> *mut c_void,
+    pub destroy: unsafe extern "C" fn(scene: *mut c_void),
+    pub resize: unsafe extern "C" fn(scene: *mut c_void, width: i32, height: i32),
+    pub update: unsafe extern "C" fn(scene: *mut c_void, dt: f32),
+    /// `matrix` points to the 16 floats of the view-projection matrix, column
+    /// by column, and the mouse is in world coordinates.
+    pub draw:
+        unsafe extern "C" fn(scene: *mut c_void, matrix: *const f32, mouse_x: f32, mouse_y: f32),
+    /// `key` is a character, or the name of a named key like `ArrowRight`.
+    pub on_key: unsafe extern "C" fn(scene: *mut c_void, key: *const c_char),
+}
+
+// the pointers are to static strings
+unsafe impl Sync for SceneVTable {}
+
+/// Scene of a plugin written in Rust, see [`export_scene`].
+pub trait Scene {
+    /// Errors are printed, and the host shows that the scene couldn't be
+    /// created.
+    fn new(info: &CreateInfo) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        Self: Sized;
+
+    fn resize(&mut self, _width: i32, _height: i32) {}
+
+    /// Advances animations by a fixed step.
+    fn update(&mut self, _dt: f32) {}
+
+    /// Called with blending, depth testing, face culling and sRGB encoding
+    /// off, which it should leave as it found them.
+    fn draw(&mut self, matrix: &Mat4, mouse: Vec2);
+
+    fn on_key(&mut self, _key: &str) {}
+}
+
+/// Exports a type implementing [`Scene`] as the scene of this plugin, with a
+/// name and a description given as C string literals.
+#[macro_export]
+macro_rules! export_scene {
+    ($scene:ty, $name:expr, $description:expr) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn playground_plugin_v1() -> *const $crate::plugin::SceneVTable {
+            static VTABLE: $crate::plugin::SceneVTable =
+                $crate::plugin::vtable::<$scene>($name, $description);
+            &VTABLE
+        }
+    };
+}
+
+/// The vtable calling into `T`, for [`export_scene`].
+pub const fn vtable<T: Scene>(name: &'static CStr, description: &'static CStr) -> SceneVTable {
+    SceneVTable {
+        abi_version: ABI_VERSION,
+        name: name.as_ptr(),
+        description: description.as_ptr(),
+        create: create::<T>,
+        destroy: destroy::<T>,
+        resize: resize::<T>,
+        update: update::<T>,
+        draw: draw::<T>,
+        on_key: on_key::<T>,
+    }
+}
+
+unsafe extern "C" fn create<T: Scene>(host: *const Host, info: *const CreateInfo) -> *mut c_void {
+    let get_proc_address = (*host).get_proc_address;
+    let loader = |symbol: &'static str| {
+        let symbol = std::ffi::CString::new(symbol).unwrap();
+        get_proc_address(symbol.as_ptr())
+    };
+    gl::load_with(loader);
+    gl_ext::load_with(loader);
+
+    match T::new(&*info) {
+        Ok(scene) => Box::into_raw(Box::new(scene)).cast(),
+        Err(e) => {
+            eprintln!("{e}");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+unsafe extern "C" fn destroy<T: Scene>(scene: *mut c_void) {
+    drop(Box::from_raw(scene.cast::<T>()));
+}
+
+unsafe extern "C" fn resize<T: Scene>(scene: *mut c_void, width: i32, height: i32) {
+    (*scene.cast::<T>()).resize(width, height);
+}
+
+unsafe extern "C" fn update<T: Scene>(scene: *mut c_void, dt: f32) {
+    (*scene.cast::<T>()).update(dt);
+}
+
+unsafe extern "C" fn draw<T: Scene>(
+    scene: *mut c_void,
+    matrix: *const f32,
+    mouse_x: f32,
+    mouse_y: f32,
+) {
+    let matrix = Mat4::from_cols_array(&*matrix.cast::<[f32; 16]>());
+    (*scene.cast::<T>()).draw(&matrix, Vec2::new(mouse_x, mouse_y));
+}
+
+unsafe extern "C" fn on_key<T: Scene>(scene: *mut c_void, key: *const c_char) {
+    (*scene.cast::<T>()).on_key(&CStr::from_ptr(key).to_string_lossy());
+}
+
+/// A scene exported by a plugin, registered with [`register`].
+pub struct Plugin {
+    pub name: &'static str,
+    pub description: String,
+    pub vtable: &'static SceneVTable,
+    /// Kept loaded as long as the plugin, the vtable points into it.
+    _library: Option<Library>,
+}
+
+impl Plugin {
+    /// Loads the shared library at `path`.
+    pub unsafe fn load(path: &Path) -> Result<Self, String> {
+        let error = |e: libloading::Error| format!("{}: {e}", path.display());
+        let library = Library::new(path).map_err(error)?;
+        let entry = *library
+            .get::<EntryFn>(ENTRY_SYMBOL.to_bytes_with_nul())
+            .map_err(error)?;
+
+        let mut plugin = Self::from_entry(entry).map_err(|e| format!("{}: {e}", path.display()))?;
+        plugin._library = Some(library);
+        Ok(plugin)
+    }
+
+    /// The plugin of an entry function linked into the playground itself.
+    pub unsafe fn from_entry(entry: EntryFn) -> Result<Self, String> {
+        let vtable = entry().as_ref().ok_or("the plugin returned no scene")?;
+        if vtable.abi_version != ABI_VERSION {
+            return Err(format!(
+                "the plugin was built for ABI version {}, the playground uses {ABI_VERSION}",
+                vtable.abi_version
+            ));
+        }
+
+        let name = CStr::from_ptr(vtable.name).to_string_lossy().into_owned();
+        Ok(Self {
+            // plugins stay loaded until the playground exits
+            name: Box::leak(name.into_boxed_str()),
+            description: (CStr::from_ptr(vtable.description).to_string_lossy()).into_owned(),
+            vtable,
+            _library: None,
+        })
+    }
+}
+
+type GlLoader = Box<dyn Fn(&CStr) -> *const c_void>;
+
+thread_local! {
+    static PLUGINS: RefCell<Vec<Rc<Plugin>>> = const { RefCell::new(Vec::new()) };
+    static GL_LOADER: RefCell<Option<GlLoader>> = const { RefCell::new(None) };
+}
+
+/// Adds the scene of `plugin` after the built-in ones.
+pub fn register(plugin: Plugin, builtin: &[&str]) -> Result<(), String> {
+    PLUGINS.with_borrow_mut(|plugins| {
+        let taken = builtin.contains(&plugin.name)
+            || (plugins.iter()).any(|other| other.name == plugin.name);
+        if taken {
+            return Err(format!("there is already a scene named '{}'", plugin.name));
+        }
+
+        plugins.push(Rc::new(plugin));
+        Ok(())
+    })
+}
+
+/// Names of the registered scenes, in order.
+pub fn names() -> Vec<&'static str> {
+    PLUGINS.with_borrow(|plugins| plugins.iter().map(|plugin| plugin.name).collect())
+}
+
+pub fn find(name: &str) -> Option<Rc<Plugin>> {
+    PLUGINS.with_borrow(|plugins| (plugins.iter()).find(|plugin| plugin.name == name).cloned())
+}
+
+/// Sets how [`host`] looks up GL functions, to call next to `gl::load_with`.
+pub fn set_gl_loader(loader: impl Fn(&CStr) -> *const c_void + 'static) {
+    GL_LOADER.set(Some(Box::new(loader)));
+}
+
+/// The host given to plugins.
+pub fn host() -> Host {
+    Host { get_proc_address }
+}
+
+unsafe extern "C" fn get_proc_address(name: *const c_char) -> *const c_void {
+    GL_LOADER.with_borrow(|loader| match loader {
+        Some(loader) => loader(CStr::from_ptr(name)),
+        None => std::ptr::null(),
+    })
+}
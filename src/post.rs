@@ -0,0 +1,484 @@
+//! Post-processing applied to whatever the scene drew, before the overlay: a
+//! stack of small passes run in an order that can be changed at runtime.
+//!
+//! Every pass is off until turned on. Once one is, the default framebuffer
+//! is copied to a texture and goes through the enabled passes one after the
+//! other, ping-ponging between two framebuffers, the last one writing back
+//! to the default framebuffer.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::{fmt, mem};
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{vec2, UVec2, Vec2};
+
+use crate::blue_noise;
+use crate::common_gl::{
+    buffer_data, create_framebuffer, create_shader_program, delete_buffers, delete_framebuffer,
+    delete_textures, pop_debug_group, push_debug_group, upload_texture, Framebuffer, SavedState,
+};
+use crate::error::GlError;
+use crate::gpu_timer::GpuTimer;
+use crate::params::{Kind, Params};
+
+const SRC_VERT_SCREEN: &[u8] = include_bytes!("../assets/shaders/screen.vert");
+const SRC_FRAG_VIGNETTE: &[u8] = include_bytes!("../assets/shaders/post-vignette.frag");
+const SRC_FRAG_GRAIN: &[u8] = include_bytes!("../assets/shaders/post-grain.frag");
+const SRC_FRAG_SHARPEN: &[u8] = include_bytes!("../assets/shaders/post-sharpen.frag");
+const SRC_FRAG_EDGES: &[u8] = include_bytes!("../assets/shaders/post-edges.frag");
+const SRC_FRAG_KUWAHARA: &[u8] = include_bytes!("../assets/shaders/post-kuwahara.frag");
+
+/// Side of the blue noise tile the grain is made of.
+const NOISE_SIZE: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassKind {
+    /// Darkens the edges of the image.
+    Vignette,
+    /// Animated film grain from a blue noise tile.
+    Grain,
+    /// Unsharp mask.
+    Sharpen,
+    /// Outlines drawn where the image changes sharply.
+    Edges,
+    /// Anisotropic Kuwahara filter, which looks like an oil painting.
+    Kuwahara,
+}
+
+impl PassKind {
+    pub const ALL: [PassKind; 5] = [
+        Self::Vignette,
+        Self::Grain,
+        Self::Sharpen,
+        Self::Edges,
+        Self::Kuwahara,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Vignette => "vignette",
+            Self::Grain => "grain",
+            Self::Sharpen => "sharpen",
+            Self::Edges => "edges",
+            Self::Kuwahara => "kuwahara",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Parameters of the pass, each set to the uniform of the same name
+    /// prefixed with `u_`: floats as floats, and everything else as integers.
+    fn params(self) -> Params {
+        match self {
+            Self::Vignette => Params::new()
+                .with_f32("strength", 0.5, 0.0, 1.0, 0.05)
+                .with_f32("radius", 0.5, 0.0, 1.0, 0.05)
+                .with_f32("softness", 0.5, 0.0, 1.0, 0.05)
+                .with_f32("roundness", 1.0, 0.0, 1.0, 0.1),
+            Self::Grain => Params::new()
+                .with_f32("intensity", 0.1, 0.0, 0.5, 0.01)
+                .with_f32("size", 1.0, 1.0, 4.0, 0.5),
+            Self::Sharpen => Params::new()
+                .with_f32("amount", 0.6, 0.0, 2.0, 0.1)
+                .with_f32("radius", 1.0, 0.5, 3.0, 0.25),
+            Self::Edges => Params::new()
+                .with_enum("operator", 0, &["sobel", "scharr"])
+                .with_f32("threshold", 0.1, 0.0, 1.0, 0.02)
+                .with_f32("softness", 0.1, 0.0, 0.5, 0.05)
+                .with_f32("width", 1.0, 0.5, 4.0, 0.5)
+                .with_f32("red", 0.0, 0.0, 1.0, 0.1)
+                .with_f32("green", 0.0, 0.0, 1.0, 0.1)
+                .with_f32("blue", 0.0, 0.0, 1.0, 0.1)
+                .with_f32("backdrop", 1.0, 0.0, 1.0, 0.1),
+            Self::Kuwahara => Params::new()
+                .with_f32("radius", 6.0, 2.0, 16.0, 1.0)
+                .with_enum("sectors", 1, &["four", "eight"])
+                .with_f32("sharpness", 8.0, 1.0, 18.0, 1.0)
+                .with_f32("hardness", 8.0, 1.0, 100.0, 1.0)
+                .with_f32("anisotropy", 1.0, 0.1, 4.0, 0.1),
+        }
+    }
+
+    fn fragment_source(self) -> &'static [u8] {
+        match self {
+            Self::Vignette => SRC_FRAG_VIGNETTE,
+            Self::Grain => SRC_FRAG_GRAIN,
+            Self::Sharpen => SRC_FRAG_SHARPEN,
+            Self::Edges => SRC_FRAG_EDGES,
+            Self::Kuwahara => SRC_FRAG_KUWAHARA,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pass {
+    pub kind: PassKind,
+    pub enabled: bool,
+    pub params: Params,
+}
+
+pub struct PostStack {
+    /// In the order they run.
+    passes: Vec<Pass>,
+    /// Index of the pass the ordering keys act on.
+    selected: usize,
+    /// Frames drawn, which animate the grain.
+    frame: u32,
+    /// Created the first time a pass is drawn.
+    renderer: Option<Renderer>,
+}
+
+impl Default for PostStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PostStack {
+    /// Every pass, turned off, with the first one selected.
+    pub fn new() -> Self {
+        let passes = (PassKind::ALL.into_iter())
+            .map(|kind| Pass {
+                kind,
+                enabled: false,
+                params: kind.params(),
+            })
+            .collect();
+
+        Self {
+            passes,
+            selected: 0,
+            frame: 0,
+            renderer: None,
+        }
+    }
+
+    pub fn passes(&self) -> &[Pass] {
+        &self.passes
+    }
+
+    pub fn selected(&self) -> PassKind {
+        self.passes[self.selected].kind
+    }
+
+    /// Whether drawing does anything.
+    pub fn is_active(&self) -> bool {
+        self.passes.iter().any(|pass| pass.enabled)
+    }
+
+    /// Selects the pass after the selected one, wrapping around.
+    pub fn select_next(&mut self) -> PassKind {
+        self.selected = (self.selected + 1) % self.passes.len();
+        self.selected()
+    }
+
+    /// Turns the selected pass on or off. Returns whether it is now on.
+    pub fn toggle_selected(&mut self) -> bool {
+        let pass = &mut self.passes[self.selected];
+        pass.enabled = !pass.enabled;
+        pass.enabled
+    }
+
+    /// Turns every pass off.
+    pub fn turn_off(&mut self) {
+        for pass in &mut self.passes {
+            pass.enabled = false;
+        }
+    }
+
+    /// Moves the selected pass `offset` places later in the stack, or
+    /// earlier if negative, stopping at either end.
+    pub fn move_selected(&mut self, offset: isize) {
+        let last = self.passes.len() - 1;
+        let to = (self.selected as isize + offset).clamp(0, last as isize) as usize;
+        let pass = self.passes.remove(self.selected);
+        self.passes.insert(to, pass);
+        self.selected = to;
+    }
+
+    pub fn params(&self, kind: PassKind) -> &Params {
+        &self.pass(kind).params
+    }
+
+    pub fn params_mut(&mut self, kind: PassKind) -> &mut Params {
+        let i = self.index(kind);
+        &mut self.passes[i].params
+    }
+
+    fn pass(&self, kind: PassKind) -> &Pass {
+        &self.passes[self.index(kind)]
+    }
+
+    fn index(&self, kind: PassKind) -> usize {
+        (self.passes.iter().position(|pass| pass.kind == kind))
+            .expect("every kind of pass is in the stack")
+    }
+
+    /// Runs the enabled passes over the default framebuffer, in order, each
+    /// timed in its own zone of `gpu_timer` named after it.
+    pub unsafe fn draw(
+        &mut self,
+        viewport: UVec2,
+        gpu_timer: &mut GpuTimer,
+    ) -> Result<(), GlError> {
+        self.frame = self.frame.wrapping_add(1);
+        if !self.is_active() || viewport.min_element() == 0 {
+            return Ok(());
+        }
+
+        let renderer = match &mut self.renderer {
+            Some(renderer) => renderer,
+            None => self.renderer.insert(Renderer::new()?),
+        };
+        renderer.draw(&self.passes, viewport, self.frame, gpu_timer)
+    }
+}
+
+/// The passes in order, the disabled ones in parentheses.
+impl fmt::Display for PostStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, pass) in self.passes.iter().enumerate() {
+            if i > 0 {
+                write!(f, " → ")?;
+            }
+
+            match pass.enabled {
+                true => write!(f, "{} [{}]", pass.kind.name(), pass.params)?,
+                false => write!(f, "({})", pass.kind.name())?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// Shader of a pass, with the locations of its uniforms.
+struct PassShader {
+    program: GLuint,
+    u_resolution: GLint,
+    u_offset: GLint,
+    /// Location of the uniform of each parameter, in the order of the
+    /// parameters.
+    u_params: Vec<GLint>,
+}
+
+struct Renderer {
+    /// Framebuffers sized for this viewport.
+    viewport: UVec2,
+    /// Both ends of the ping-pong.
+    framebuffers: Option<[Framebuffer; 2]>,
+
+    shaders: Vec<PassShader>,
+    noise: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+}
+
+impl Renderer {
+    unsafe fn new() -> Result<Self, GlError> {
+        let shaders = (PassKind::ALL.into_iter())
+            .map(|kind| {
+                let program = create_shader_program(SRC_VERT_SCREEN, kind.fragment_source())?;
+                for (name, unit) in [(c"u_image", 0), (c"u_noise", 1)] {
+                    gl::Uniform1i(gl::GetUniformLocation(program, name.as_ptr()), unit);
+                }
+
+                let u_params = (kind.params().iter())
+                    .map(|param| {
+                        let name = format!("u_{}\0", param.name);
+                        gl::GetUniformLocation(program, name.as_ptr().cast())
+                    })
+                    .collect();
+
+                Ok(PassShader {
+                    program,
+                    u_resolution: gl::GetUniformLocation(program, c"u_resolution".as_ptr()),
+                    u_offset: gl::GetUniformLocation(program, c"u_offset".as_ptr()),
+                    u_params,
+                })
+            })
+            .collect::<Result<Vec<_>, GlError>>()?;
+
+        let noise_pixels = (blue_noise::generate(NOISE_SIZE, 0).into_iter())
+            .flat_map(|value| [value, value, value, 255])
+            .collect::<Vec<_>>();
+        let mut noise = 0;
+        gl::GenTextures(1, &mut noise);
+        let size = NOISE_SIZE as u32;
+        upload_texture(noise, size, size, noise_pixels.as_ptr(), gl::REPEAT)?;
+        // grains are single texels
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        gl::BindVertexArray(vao);
+        let mut vbo = 0;
+        gl::GenBuffers(1, &mut vbo);
+        buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+        // every pass shares screen.vert, so their attributes are at the same
+        // locations
+        const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+        let program = shaders[0].program;
+        for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+            let location = gl::GetAttribLocation(program, name.as_ptr()) as GLuint;
+            gl::VertexAttribPointer(location, 2, gl::FLOAT, gl::FALSE, SIZE_VERTEX, offset as _);
+            gl::EnableVertexAttribArray(location);
+        }
+        gl::BindVertexArray(0);
+
+        Ok(Self {
+            viewport: UVec2::ZERO,
+            framebuffers: None,
+            shaders,
+            noise,
+            vao,
+            vbo,
+        })
+    }
+
+    unsafe fn draw(
+        &mut self,
+        passes: &[Pass],
+        viewport: UVec2,
+        frame: u32,
+        gpu_timer: &mut GpuTimer,
+    ) -> Result<(), GlError> {
+        self.resize(viewport)?;
+        let Some(framebuffers) = &self.framebuffers else {
+            return Ok(());
+        };
+
+        push_debug_group(c"Post-processing");
+        let saved = SavedState::save();
+
+        let size = viewport.as_ivec2();
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, framebuffers[0].fbo);
+        gl::BlitFramebuffer(
+            0,
+            0,
+            size.x,
+            size.y,
+            0,
+            0,
+            size.x,
+            size.y,
+            gl::COLOR_BUFFER_BIT,
+            gl::NEAREST,
+        );
+
+        gl::Viewport(0, 0, size.x, size.y);
+        gl::Disable(gl::BLEND);
+        gl::BindVertexArray(self.vao);
+        gl::ActiveTexture(gl::TEXTURE1);
+        gl::BindTexture(gl::TEXTURE_2D, self.noise);
+
+        // the tile jumps around every frame, following the R2 sequence so
+        // that consecutive positions are far apart
+        let offset = (vec2(0.754_877_7, 0.569_840_3) * frame as f32).fract() * NOISE_SIZE as f32;
+
+        let enabled = passes
+            .iter()
+            .filter(|pass| pass.enabled)
+            .collect::<Vec<_>>();
+        for (i, pass) in enabled.iter().enumerate() {
+            let shader = &self.shaders[pass.kind as usize];
+            let from = &framebuffers[i % 2];
+            let to = match i + 1 == enabled.len() {
+                true => 0,
+                false => framebuffers[(i + 1) % 2].fbo,
+            };
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, to);
+            gl::UseProgram(shader.program);
+            gl::Uniform2f(shader.u_resolution, size.x as f32, size.y as f32);
+            gl::Uniform2f(shader.u_offset, offset.x.floor(), offset.y.floor());
+            for (param, &location) in pass.params.iter().zip(&shader.u_params) {
+                match param.kind {
+                    Kind::F32 { .. } => gl::Uniform1f(location, pass.params.f32(param.name)),
+                    Kind::Enum { .. } => {
+                        gl::Uniform1i(location, pass.params.variant(param.name) as GLint)
+                    }
+                    Kind::I32 { .. } => gl::Uniform1i(location, pass.params.i32(param.name)),
+                    Kind::Bool => gl::Uniform1i(location, pass.params.bool(param.name) as GLint),
+                }
+            }
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, from.texture);
+            gpu_timer.begin(pass.kind.name());
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gpu_timer.end();
+        }
+
+        gl::BindVertexArray(0);
+        saved.restore();
+        pop_debug_group();
+        Ok(())
+    }
+
+    /// Recreates the framebuffers if the viewport changed size.
+    unsafe fn resize(&mut self, viewport: UVec2) -> Result<(), GlError> {
+        if viewport == self.viewport {
+            return Ok(());
+        }
+
+        self.delete_framebuffers();
+        if viewport.min_element() > 0 {
+            let ping = create_framebuffer("post ping", viewport)?;
+            let pong = create_framebuffer("post pong", viewport)
+                .inspect_err(|_| delete_framebuffer(&ping))?;
+            self.framebuffers = Some([ping, pong]);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        // only once they exist, so that failing tries again next time
+        self.viewport = viewport;
+        Ok(())
+    }
+
+    unsafe fn delete_framebuffers(&mut self) {
+        for framebuffer in self.framebuffers.take().iter().flatten() {
+            delete_framebuffer(framebuffer);
+        }
+    }
+}
+
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        unsafe {
+            self.delete_framebuffers();
+            for shader in &self.shaders {
+                gl::DeleteProgram(shader.program);
+            }
+            delete_textures(&[self.noise]);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
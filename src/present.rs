@@ -0,0 +1,267 @@
+//! How the scene fills the window: over all of it as it always did, at a
+//! fixed resolution scaled to fit with bars around it, or at a fixed
+//! resolution blown up by a whole number of pixels with nearest filtering,
+//! for scenes whose pixels are the point, like dithering and simulations on
+//! a grid of texels, which fractional scaling smears.
+//!
+//! As with the split view, scenes can't be told to draw anywhere but over the
+//! default framebuffer from its origin, so they draw there at their own size,
+//! which gets copied aside and blitted back scaled up. A resolution larger
+//! than the window can't fit in it to begin with, and is drawn at the largest
+//! size of the same aspect ratio that does.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use gl::types::GLint;
+use glam::{UVec2, Vec2};
+
+use crate::common_gl::{
+    create_framebuffer, delete_framebuffer, pop_debug_group, push_debug_group, Framebuffer,
+};
+use crate::error::GlError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PresentMode {
+    /// Drawn over the whole window at its resolution.
+    #[default]
+    Free,
+    /// Drawn at this resolution, scaled to fit the window and letterboxed.
+    Fixed(UVec2),
+    /// Drawn at this resolution, scaled up by the largest whole number that
+    /// fits the window with nearest filtering, and letterboxed.
+    Integer(UVec2),
+}
+
+impl PresentMode {
+    /// Where the scene goes in a window of size `window`.
+    pub fn layout(self, window: UVec2) -> Layout {
+        let full = Layout {
+            size: window,
+            min: UVec2::ZERO,
+            shown: window,
+        };
+
+        let fit = |resolution: UVec2| {
+            let scale = (window.as_vec2() / resolution.as_vec2()).min_element();
+            let shown = (resolution.as_vec2() * scale).round().as_uvec2();
+            shown.min(window)
+        };
+        let (resolution, shown) = match self {
+            Self::Free => return full,
+            Self::Fixed(resolution) => (resolution, fit(resolution)),
+            // not even once, so scaled down like a fixed resolution
+            Self::Integer(resolution) => match (window / resolution).min_element() {
+                0 => (resolution, fit(resolution)),
+                scale => (resolution, resolution * scale),
+            },
+        };
+        if shown.min_element() == 0 {
+            return full;
+        }
+
+        Layout {
+            // what doesn't fit in the default framebuffer can't be drawn there
+            size: match resolution.cmple(window).all() {
+                true => resolution,
+                false => shown,
+            },
+            min: (window - shown) / 2,
+            shown,
+        }
+    }
+}
+
+impl fmt::Display for PresentMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Free => write!(f, "free"),
+            Self::Fixed(size) => write!(f, "fixed:{}x{}", size.x, size.y),
+            Self::Integer(size) => write!(f, "integer:{}x{}", size.x, size.y),
+        }
+    }
+}
+
+impl FromStr for PresentMode {
+    type Err = String;
+
+    /// Parses `free`, `fixed:<W>x<H>` or `integer:<W>x<H>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "free" {
+            return Ok(Self::Free);
+        }
+
+        let Some((mode, size)) = s.split_once(':') else {
+            return Err("expected free, fixed:<W>x<H> or integer:<W>x<H>".to_string());
+        };
+        let size = (size.split_once('x'))
+            .and_then(|(w, h)| Some(UVec2::new(w.parse().ok()?, h.parse().ok()?)))
+            .filter(|size| size.min_element() > 0)
+            .ok_or_else(|| format!("'{size}' is not a resolution like 320x180"))?;
+
+        match mode {
+            "fixed" => Ok(Self::Fixed(size)),
+            "integer" => Ok(Self::Integer(size)),
+            _ => Err(format!("'{mode}' is neither fixed nor integer")),
+        }
+    }
+}
+
+/// Where the scene is drawn and shown in the window, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    /// Resolution the scene draws at.
+    pub size: UVec2,
+    /// Top left corner of the scene in the window.
+    pub min: UVec2,
+    /// Size of the scene in the window once scaled.
+    pub shown: UVec2,
+}
+
+impl Layout {
+    /// Whether the scene is drawn over the whole window as is.
+    pub fn is_full(&self) -> bool {
+        self.size == self.shown && self.min == UVec2::ZERO
+    }
+
+    /// Pixels of the scene per pixel of the window.
+    pub fn ratio(&self) -> Vec2 {
+        self.size.as_vec2() / self.shown.as_vec2()
+    }
+
+    /// A position in the window, y going down, to where it is in the scene.
+    pub fn to_scene(&self, pos: Vec2) -> Vec2 {
+        (pos - self.min.as_vec2()) * self.ratio()
+    }
+}
+
+#[derive(Default)]
+pub struct Presenter {
+    pub mode: PresentMode,
+    /// Copy of the scene, at the size it was drawn at.
+    copy: Option<Framebuffer>,
+    /// Where the last frame was shown, unless it was over the whole window.
+    layout: Option<Layout>,
+}
+
+impl Presenter {
+    pub fn new(mode: PresentMode) -> Self {
+        Self {
+            mode,
+            copy: None,
+            layout: None,
+        }
+    }
+
+    pub fn turn_off(&mut self) {
+        self.mode = PresentMode::Free;
+        self.layout = None;
+    }
+
+    /// Where the last frame was shown, unless it was over the whole window.
+    pub fn layout(&self) -> Option<Layout> {
+        self.layout
+    }
+
+    /// Records that this frame is drawn some other way, as split in two.
+    pub fn skip(&mut self) {
+        self.layout = None;
+    }
+
+    /// Where the pointer at `pos` in a window of size `window` is in the last
+    /// frame, and the size of that frame.
+    pub fn to_scene(&self, pos: Vec2, window: Vec2) -> (Vec2, Vec2) {
+        match self.layout {
+            Some(layout) => (layout.to_scene(pos), layout.size.as_vec2()),
+            None => (pos, window),
+        }
+    }
+
+    /// Draws the scene with `draw`, which renders it to the default
+    /// framebuffer at the given size, then scales it up in place.
+    pub unsafe fn draw(&mut self, window: UVec2, draw: impl FnOnce(UVec2)) -> Result<(), GlError> {
+        let layout = self.mode.layout(window);
+        self.layout = (!layout.is_full()).then_some(layout);
+        if layout.is_full() {
+            draw(window);
+            return Ok(());
+        }
+
+        let copy = self.resize(layout.size)?.fbo;
+        draw(layout.size);
+
+        push_debug_group(c"Present");
+        let size = layout.size.as_ivec2();
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, copy);
+        gl::BlitFramebuffer(
+            0,
+            0,
+            size.x,
+            size.y,
+            0,
+            0,
+            size.x,
+            size.y,
+            gl::COLOR_BUFFER_BIT,
+            gl::NEAREST,
+        );
+
+        // bars around it
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, window.x as GLint, window.y as GLint);
+        gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+
+        // GL's origin is at the bottom
+        let min = layout.min.as_ivec2();
+        let min = (min.x, window.y as i32 - min.y - layout.shown.y as i32);
+        let max = (min.0 + layout.shown.x as i32, min.1 + layout.shown.y as i32);
+        let filter = match self.mode {
+            PresentMode::Integer(_) => gl::NEAREST,
+            _ => gl::LINEAR,
+        };
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, copy);
+        gl::BlitFramebuffer(
+            0,
+            0,
+            size.x,
+            size.y,
+            min.0,
+            min.1,
+            max.0,
+            max.1,
+            gl::COLOR_BUFFER_BIT,
+            filter,
+        );
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        pop_debug_group();
+        Ok(())
+    }
+
+    /// The copy of the scene, recreated if the scene changed size.
+    unsafe fn resize(&mut self, size: UVec2) -> Result<&Framebuffer, GlError> {
+        if self.copy.as_ref().is_some_and(|copy| copy.size != size) {
+            self.delete_framebuffer();
+        }
+        if self.copy.is_none() {
+            self.copy = Some(create_framebuffer("present", size)?);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        Ok(self.copy.as_ref().unwrap())
+    }
+
+    unsafe fn delete_framebuffer(&mut self) {
+        if let Some(copy) = self.copy.take() {
+            delete_framebuffer(&copy);
+        }
+    }
+}
+
+impl Drop for Presenter {
+    fn drop(&mut self) {
+        unsafe { self.delete_framebuffer() };
+    }
+}
@@ -0,0 +1,63 @@
+//! Profiler integrations: Tracy with the `tracy` feature, and puffin with the
+//! `puffin` feature.
+//!
+//! Without either feature every hook below is a no-op, so they can be
+//! sprinkled around freely.
+
+/// Keeps the profilers running while alive.
+pub struct Profiler {
+    #[cfg(feature = "puffin")]
+    _puffin_server: Option<puffin_http::Server>,
+}
+
+/// Starts the enabled profilers. Must run before any zone is entered.
+pub fn start() -> Profiler {
+    #[cfg(feature = "tracy")]
+    tracy_client::Client::start();
+
+    #[cfg(feature = "puffin")]
+    let puffin_server = {
+        puffin::set_scopes_on(true);
+
+        let addr = format!("127.0.0.1:{}", puffin_http::DEFAULT_PORT);
+        match puffin_http::Server::new(&addr) {
+            Ok(server) => {
+                println!("Puffin server listening on {addr}, connect with puffin_viewer");
+                Some(server)
+            }
+            Err(e) => {
+                eprintln!("Could not start puffin server on {addr}: {e}");
+                None
+            }
+        }
+    };
+
+    Profiler {
+        #[cfg(feature = "puffin")]
+        _puffin_server: puffin_server,
+    }
+}
+
+/// Marks the end of a frame.
+pub fn frame_mark() {
+    #[cfg(feature = "tracy")]
+    tracy_client::frame_mark();
+
+    #[cfg(feature = "puffin")]
+    puffin::GlobalProfiler::lock().new_frame();
+}
+
+/// Profiles the rest of the enclosing block as a CPU zone named `$name`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cpu_zone {
+    ($name:literal) => {
+        #[cfg(feature = "tracy")]
+        let _zone = tracy_client::span!($name);
+
+        #[cfg(feature = "puffin")]
+        puffin::profile_scope!($name);
+    };
+}
+
+pub use __cpu_zone as cpu_zone;
@@ -0,0 +1,254 @@
+//! Recording of every frame of the window, started and stopped with
+//! `Ctrl+Shift+V`.
+//!
+//! Unlike the replay buffer (see [`crate::replay`]), which keeps a few small
+//! frames per second around in case something happens, the recorder keeps
+//! every frame the window shows, at full size, for as long as it runs: to
+//! make videos comparing the scenes, like the blur techniques side by side.
+//! Frames go either to a folder of numbered PNGs or through the pipe of an
+//! `ffmpeg` process encoding them as a video, which needs `ffmpeg` on the
+//! `PATH`.
+//!
+//! Readbacks go through pixel buffer objects read a couple of frames later,
+//! as in the replay buffer, and frames are written on a thread of their own
+//! so that encoding doesn't hold the window back. If the writer falls too
+//! far behind, capturing waits for it rather than dropping frames.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use gl::types::{GLsizei, GLsizeiptr, GLuint};
+use glam::UVec2;
+use image::RgbaImage;
+
+use crate::gpu_memory::{self, Category, Object};
+
+/// Frames between a readback being issued and being read.
+const LATENCY: usize = 2;
+
+/// Frames read back and waiting for the writer before capturing waits.
+const QUEUE: usize = 8;
+
+/// Frame rate of the videos, that of most monitors. Frames are recorded as
+/// they're swapped, so the video plays at the right speed when they are
+/// swapped at that rate.
+pub const VIDEO_FPS: u32 = 60;
+
+/// Where recorded frames go.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A folder of `frame-00000.png`, `frame-00001.png`, etc.
+    #[default]
+    Png,
+    /// An MP4 video encoded by `ffmpeg`.
+    Ffmpeg,
+}
+
+impl Format {
+    /// Extension of what is recorded in this format, empty for a folder.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "",
+            Self::Ffmpeg => "mp4",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(Self::Png),
+            "ffmpeg" => Ok(Self::Ffmpeg),
+            _ => Err(format!("'{s}' is neither png nor ffmpeg")),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Png => write!(f, "png"),
+            Self::Ffmpeg => write!(f, "ffmpeg"),
+        }
+    }
+}
+
+/// A recording in progress.
+pub struct Recording {
+    path: PathBuf,
+    /// Size of every frame, that of the window when the recording started.
+    size: UVec2,
+    free_pbos: Vec<GLuint>,
+    /// Readbacks in the order they were issued.
+    pending: VecDeque<GLuint>,
+    /// Frames read back, bottom row first, to the writer.
+    frames: SyncSender<Vec<u8>>,
+    /// Returns how many frames were written.
+    writer: JoinHandle<io::Result<usize>>,
+}
+
+impl Recording {
+    /// Starts recording frames of `size` to `path` in `format`.
+    pub fn start(format: Format, path: &Path, size: UVec2) -> io::Result<Self> {
+        let (frames, received) = mpsc::sync_channel::<Vec<u8>>(QUEUE);
+        let writer = match format {
+            Format::Png => {
+                fs::create_dir_all(path)?;
+                let dir = path.to_path_buf();
+                thread::spawn(move || {
+                    let mut count = 0;
+                    for pixels in received {
+                        let image = RgbaImage::from_raw(size.x, size.y, flip(&pixels, size.x));
+                        let path = dir.join(format!("frame-{count:05}.png"));
+                        image.unwrap().save(path).map_err(io::Error::other)?;
+                        count += 1;
+                    }
+                    Ok(count)
+                })
+            }
+            Format::Ffmpeg => {
+                let mut ffmpeg = Command::new("ffmpeg")
+                    .args(["-y", "-loglevel", "error", "-f", "rawvideo"])
+                    .args(["-pixel_format", "rgba"])
+                    .args(["-video_size", &format!("{}x{}", size.x, size.y)])
+                    .args(["-framerate", &VIDEO_FPS.to_string(), "-i", "-"])
+                    // frames are bottom row first, and H.264 wants even sides
+                    .args(["-vf", "vflip,pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+                    .args(["-pix_fmt", "yuv420p"])
+                    .arg(path)
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| io::Error::new(e.kind(), format!("could not run ffmpeg: {e}")))?;
+                let mut stdin = ffmpeg.stdin.take().unwrap();
+                thread::spawn(move || {
+                    let mut count = 0;
+                    for pixels in received {
+                        stdin.write_all(&pixels)?;
+                        count += 1;
+                    }
+                    // closing the pipe is what tells ffmpeg the video is over
+                    drop(stdin);
+                    let status = ffmpeg.wait()?;
+                    match status.success() {
+                        true => Ok(count),
+                        false => Err(io::Error::other(format!("ffmpeg failed ({status})"))),
+                    }
+                })
+            }
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            size,
+            free_pbos: Vec::new(),
+            pending: VecDeque::new(),
+            frames,
+            writer,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Captures the currently bound read framebuffer, at least as large as
+    /// the recording. Returns false once the writer stopped, whose error
+    /// [`Self::finish`] returns.
+    pub unsafe fn capture(&mut self) -> bool {
+        while self.pending.len() >= LATENCY {
+            if !self.read_oldest() {
+                return false;
+            }
+        }
+
+        let pbo = self.free_pbos.pop().unwrap_or_else(|| {
+            let mut pbo = 0;
+            gl::GenBuffers(1, &mut pbo);
+            pbo
+        });
+        let bytes = self.size.x as usize * self.size.y as usize * 4;
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, pbo);
+        gl::BufferData(
+            gl::PIXEL_PACK_BUFFER,
+            bytes as GLsizeiptr,
+            std::ptr::null(),
+            gl::STREAM_READ,
+        );
+        gpu_memory::record(Object::Buffer(pbo), Category::Buffers, bytes);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            self.size.x as GLsizei,
+            self.size.y as GLsizei,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            std::ptr::null_mut(),
+        );
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+
+        self.pending.push_back(pbo);
+        true
+    }
+
+    /// Hands the oldest readback to the writer, and returns whether it's
+    /// still there to take it.
+    unsafe fn read_oldest(&mut self) -> bool {
+        let Some(pbo) = self.pending.pop_front() else {
+            return true;
+        };
+
+        let mut pixels = vec![0u8; self.size.x as usize * self.size.y as usize * 4];
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, pbo);
+        gl::GetBufferSubData(
+            gl::PIXEL_PACK_BUFFER,
+            0,
+            pixels.len() as GLsizeiptr,
+            pixels.as_mut_ptr() as *mut _,
+        );
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+        self.free_pbos.push(pbo);
+
+        self.frames.send(pixels).is_ok()
+    }
+
+    /// Writes the frames still being read back, deletes the GL objects of
+    /// the recorder and waits for the writer to be done. Returns how many
+    /// frames were recorded.
+    pub unsafe fn finish(mut self) -> io::Result<usize> {
+        while !self.pending.is_empty() && self.read_oldest() {}
+
+        let pbos = (self.free_pbos.drain(..)).chain(self.pending.drain(..));
+        for pbo in pbos.collect::<Vec<_>>() {
+            gl::DeleteBuffers(1, &pbo);
+            gpu_memory::forget(Object::Buffer(pbo));
+        }
+
+        drop(self.frames);
+        self.writer.join().unwrap()
+    }
+}
+
+/// Rows of RGBA `pixels` `width` wide, in the opposite order.
+fn flip(pixels: &[u8], width: u32) -> Vec<u8> {
+    let row_len = width as usize * 4;
+    (pixels.chunks_exact(row_len).rev())
+        .flatten()
+        .copied()
+        .collect()
+}
@@ -0,0 +1,234 @@
+//! A small TCP server to drive the playground from external tools.
+//!
+//! Each line sent to the server is a JSON command, and each command gets
+//! exactly one JSON line back:
+//!
+//! ```text
+//! > {"cmd": "switch_scene", "scene": "kawase"}
+//! < {"ok":true}
+//! > {"cmd": "press_key", "key": "ArrowRight"}
+//! < {"ok":true}
+//! > {"cmd": "set_param", "name": "radius", "value": 3.5}
+//! < {"ok":true,"value":3.5}
+//! > {"cmd": "get_params"}
+//! < {"ok":true,"scene":"kawase","params":{"radius":3.5,"layers":1,"dither":false}}
+//! > {"cmd": "fps"}
+//! < {"ok":true,"fps":143.8,"frame_time_ms":6.95,"gpu_time_ms":1.2}
+//! > {"cmd": "screenshot", "path": "shot"}
+//! < {"ok":true,"path":"/home/me/playground/screenshots/shot.png"}
+//! > {"cmd": "run_script", "path": "assets/scripts/pulse.rhai"}
+//! < {"ok":true}
+//! ```
+//!
+//! There is no authentication, so the server only listens on the loopback
+//! interface unless `--remote-lan` asks for every interface. Screenshots are
+//! only ever written as new PNG files in [`SCREENSHOT_DIR`], so `path` is just
+//! a file name. Scripts can only be run from `assets/scripts`, and not at all
+//! when listening on every interface.
+
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::assets;
+use crate::params::Value as ParamValue;
+
+/// Where remote screenshots go, relative to the working directory.
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Command {
+    SwitchScene {
+        scene: String,
+    },
+    PressKey {
+        key: String,
+    },
+    SetParam {
+        name: String,
+        value: ParamValue,
+    },
+    GetParams,
+    Screenshot {
+        path: Option<String>,
+    },
+    Fps,
+    /// Runs a script on top of the scenes, or stops the one running without
+    /// a path.
+    RunScript {
+        path: Option<String>,
+    },
+}
+
+/// A command waiting to be executed on the main thread.
+pub struct Request {
+    pub command: Command,
+    reply: Sender<Value>,
+}
+
+impl Request {
+    pub fn ok(self, mut fields: Value) {
+        fields["ok"] = json!(true);
+        let _ = self.reply.send(fields);
+    }
+
+    pub fn err(self, error: impl ToString) {
+        let _ = (self.reply).send(json!({ "ok": false, "error": error.to_string() }));
+    }
+}
+
+pub struct RemoteServer {
+    requests: Receiver<Request>,
+    addr: SocketAddr,
+}
+
+impl RemoteServer {
+    /// Listens on `port` of the loopback interface, or of every interface if
+    /// `lan`, letting anyone who can reach it in.
+    pub fn start(port: u16, lan: bool) -> io::Result<Self> {
+        let host = match lan {
+            true => Ipv4Addr::UNSPECIFIED,
+            false => Ipv4Addr::LOCALHOST,
+        };
+        let listener = TcpListener::bind((host, port))?;
+        let addr = listener.local_addr()?;
+        println!("Remote control listening on {addr}");
+
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                thread::spawn(move || handle_client(stream, sender, lan));
+            }
+        });
+
+        Ok(Self { requests, addr })
+    }
+
+    /// Where the server listens, with the port picked if it was 0.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Commands received since the last call.
+    pub fn poll(&self) -> impl Iterator<Item = Request> + '_ {
+        self.requests.try_iter()
+    }
+}
+
+fn handle_client(stream: TcpStream, requests: Sender<Request>, lan: bool) {
+    let peer = stream.peer_addr().ok();
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let command = (serde_json::from_str::<Command>(&line))
+            .map_err(|e| e.to_string())
+            .and_then(|command| confine(command, lan));
+        let response = match command {
+            Ok(command) => {
+                let (reply, response) = mpsc::channel();
+                if requests.send(Request { command, reply }).is_err() {
+                    break;
+                }
+
+                // the main thread answers within a frame
+                match response.recv() {
+                    Ok(response) => response,
+                    Err(_) => break,
+                }
+            }
+            Err(e) => json!({ "ok": false, "error": e }),
+        };
+
+        if writeln!(writer, "{response}").is_err() {
+            break;
+        }
+    }
+
+    if let Some(peer) = peer {
+        println!("Remote client {peer} disconnected");
+    }
+}
+
+/// Refuses `command` if it names a screenshot with anything but a file name,
+/// or would run a script from outside the scripts directory or while
+/// listening to the whole network (`lan`). Screenshot paths are rewritten to
+/// where they will be saved.
+fn confine(command: Command, lan: bool) -> Result<Command, String> {
+    match &command {
+        Command::Screenshot { path } => {
+            let path = screenshot_path(path.as_deref())?;
+            return Ok(Command::Screenshot {
+                path: Some(path.to_string_lossy().into_owned()),
+            });
+        }
+        Command::RunScript { .. } if lan => {
+            return Err("scripts can't be run with --remote-lan".to_string());
+        }
+        Command::RunScript { path: Some(path) } => {
+            let dir = scripts_dir();
+            if !is_within(Path::new(path), &dir) {
+                return Err(format!("scripts must be in {}", dir.display()));
+            }
+        }
+        _ => {}
+    }
+    Ok(command)
+}
+
+/// Where a remote screenshot named `name` is saved: in [`SCREENSHOT_DIR`],
+/// with a `.png` extension. Without a name, one is made from the time.
+pub fn screenshot_path(name: Option<&str>) -> Result<PathBuf, String> {
+    let name = match name {
+        Some(name) => match Path::new(name).components().collect::<Vec<_>>()[..] {
+            [Component::Normal(name)] => PathBuf::from(name),
+            _ => return Err(format!("{name:?} is not a file name")),
+        },
+        None => {
+            let millis = (SystemTime::now().duration_since(UNIX_EPOCH))
+                .map(|d| d.as_millis())
+                .unwrap_or_default();
+            PathBuf::from(format!("screenshot-{millis}"))
+        }
+    };
+
+    let dir = env::current_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(SCREENSHOT_DIR).join(name.with_extension("png")))
+}
+
+/// Where the scripts remote clients may run are.
+pub fn scripts_dir() -> PathBuf {
+    assets::dir().join("scripts")
+}
+
+/// Whether `path`, which may not exist yet, is inside `dir` once relative
+/// components and symbolic links are resolved.
+pub fn is_within(path: &Path, dir: &Path) -> bool {
+    let Ok(dir) = dir.canonicalize() else {
+        return false;
+    };
+    let resolved = path.canonicalize().or_else(|_| {
+        let name = path.file_name().ok_or(io::ErrorKind::InvalidInput)?;
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        parent.canonicalize().map(|parent| parent.join(name))
+    });
+    resolved.is_ok_and(|path| path.starts_with(dir))
+}
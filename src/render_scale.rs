@@ -0,0 +1,541 @@
+//! Drawing scenes at another resolution than the window's: below it for heavy
+//! scenes, resampled back up bilinearly, with a bicubic filter, or rebuilt
+//! over several frames, and above it for supersampling.
+//!
+//! Scenes can't be told to draw anywhere but over the default framebuffer, so
+//! like `--present` they draw there at the scaled size, with a camera zoomed
+//! by the same ratio so that they show what they would at full resolution.
+//! That gets copied aside and resampled back into place. Supersampled sizes
+//! that don't fit in the default framebuffer are drawn in tiles, the way
+//! tiled exports are.
+//!
+//! The temporal reconstruction moves the camera by a different fraction of a
+//! texel every frame, and every frame adds its samples where they landed to a
+//! history at full resolution. It only converges while the camera stands
+//! still: scenes don't give motion vectors to reproject the history with, so
+//! it starts over whenever the camera moves, and animated scenes ghost a
+//! little where the neighborhood clamp lets them.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::{fmt, mem};
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, Mat2, UVec2, Vec2};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_framebuffer, create_shader_program, delete_buffers, delete_framebuffer,
+    pop_debug_group, push_debug_group, Framebuffer, SavedState,
+};
+use crate::error::GlError;
+
+const SRC_VERT_SCREEN: &[u8] = include_bytes!("../assets/shaders/screen.vert");
+const SRC_FRAG_UPSCALE: &[u8] = include_bytes!("../assets/shaders/upscale.frag");
+const SRC_FRAG_TEMPORAL: &[u8] = include_bytes!("../assets/shaders/upscale-temporal.frag");
+
+/// Render scales `--render-scale` accepts, in percent.
+pub const PERCENT_RANGE: (u32, u32) = (50, 200);
+
+/// Frames before the jitter repeats.
+const JITTER_FRAMES: u32 = 8;
+
+/// Weight of a sample landing right on a pixel, against the history.
+const FEEDBACK: f32 = 0.5;
+
+/// How the scene drawn below the window's resolution is brought back up to
+/// it. Above it, the scene is always averaged down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Upscale {
+    #[default]
+    Bilinear,
+    /// Catmull-Rom, sharper than bilinear.
+    Bicubic,
+    /// Jittered frames accumulated into a history at full resolution.
+    Temporal,
+}
+
+impl Upscale {
+    pub const ALL: [Upscale; 3] = [Self::Bilinear, Self::Bicubic, Self::Temporal];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bilinear => "bilinear",
+            Self::Bicubic => "bicubic",
+            Self::Temporal => "temporal",
+        }
+    }
+}
+
+impl fmt::Display for Upscale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Upscale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (Self::ALL.into_iter())
+            .find(|upscale| upscale.name() == s)
+            .ok_or_else(|| format!("'{s}' is not bilinear, bicubic or temporal"))
+    }
+}
+
+/// Parses a render scale like `50` or `50%` into a ratio.
+pub fn parse_scale(s: &str) -> Result<f32, String> {
+    let percent = (s.strip_suffix('%').unwrap_or(s).parse::<u32>())
+        .map_err(|_| format!("'{s}' is not a percentage"))?;
+    let (min, max) = PERCENT_RANGE;
+    if !(min..=max).contains(&percent) {
+        return Err(format!("{percent}% is not between {min}% and {max}%"));
+    }
+    Ok(percent as f32 / 100.0)
+}
+
+/// Where the camera is moved by in frame `frame`, in texels of the scaled
+/// image, y going down: the Halton sequence in bases 2 and 3 centered on
+/// the texel, which spreads the samples of a few frames evenly over it.
+pub fn jitter(frame: u32) -> Vec2 {
+    let halton = |mut index: u32, base: u32| {
+        let mut fraction = 1.0;
+        let mut value = 0.0;
+        while index > 0 {
+            fraction /= base as f32;
+            value += fraction * (index % base) as f32;
+            index /= base;
+        }
+        value
+    };
+    // starting at 1, as 0 is 0 in every base
+    let index = frame % JITTER_FRAMES + 1;
+    vec2(halton(index, 2), halton(index, 3)) - 0.5
+}
+
+/// Part of the scaled image drawn at once, over the default framebuffer: all
+/// of it unless supersampling past what it holds.
+#[derive(Debug, Clone)]
+pub struct Region {
+    /// Camera showing this part at the scaled resolution.
+    pub camera: Camera,
+    /// Size the scene is drawn at.
+    pub size: UVec2,
+    /// Top left corner of the region in the scaled image.
+    pub min: UVec2,
+    /// Pixels of the scaled image per pixel of the scene at full resolution.
+    pub ratio: Vec2,
+}
+
+impl Region {
+    /// A position in the scene at full resolution, y going down, to where it
+    /// is in this region.
+    pub fn pointer(&self, pos: Vec2) -> Vec2 {
+        pos * self.ratio - self.min.as_vec2()
+    }
+
+    /// Whether a position in the scaled image is in this region.
+    fn contains(&self, pos: Vec2) -> bool {
+        let min = self.min.as_vec2();
+        pos.cmpge(min).all() && pos.cmplt(min + self.size.as_vec2()).all()
+    }
+}
+
+pub struct RenderScale {
+    /// Ratio of the resolution drawn at to the one shown, 1 for the same.
+    pub scale: f32,
+    pub upscale: Upscale,
+    /// Frames drawn with the temporal reconstruction, which pick the jitter.
+    frame: u32,
+    /// Size of the last frame and of the default framebuffer, if it was
+    /// drawn scaled.
+    last: Option<(UVec2, UVec2)>,
+    /// Camera of the last frame, which the history was accumulated with.
+    last_camera: Option<Camera>,
+    /// Created the first time the scale isn't 1.
+    renderer: Option<Renderer>,
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self::new(1.0, Upscale::default())
+    }
+}
+
+impl RenderScale {
+    pub fn new(scale: f32, upscale: Upscale) -> Self {
+        Self {
+            scale,
+            upscale,
+            frame: 0,
+            last: None,
+            last_camera: None,
+            renderer: None,
+        }
+    }
+
+    pub fn turn_off(&mut self) {
+        self.scale = 1.0;
+        self.last = None;
+        self.renderer = None;
+    }
+
+    /// Records that this frame is drawn some other way, as split in two.
+    pub fn skip(&mut self) {
+        self.last = None;
+    }
+
+    /// Size a scene of size `size` is drawn at, at least a pixel.
+    pub fn scaled_size(&self, size: UVec2) -> UVec2 {
+        (size.as_vec2() * self.scale)
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE)
+    }
+
+    /// The parts the scaled image of a scene of size `size`, shown by
+    /// `camera`, is drawn in over a default framebuffer of size `capacity`,
+    /// moved by `jitter` scaled texels.
+    pub fn regions(
+        &self,
+        camera: &Camera,
+        size: UVec2,
+        capacity: UVec2,
+        jitter: Vec2,
+    ) -> Vec<Region> {
+        let scaled = self.scaled_size(size);
+        let ratio = scaled.as_vec2() / size.as_vec2();
+        let scale = camera.scale * ratio;
+        let unrotate = Mat2::from_angle(-camera.rotation);
+
+        let tiles = (scaled + capacity.max(UVec2::ONE) - 1) / capacity.max(UVec2::ONE);
+        let tile = (scaled + tiles - 1) / tiles;
+
+        let mut regions = Vec::new();
+        for ty in 0..tiles.y {
+            for tx in 0..tiles.x {
+                let min = uvec2(tx, ty) * tile;
+                // offset of the tile's center from the image's center, as
+                // in tiled exports
+                let offset = (min.as_vec2() + tile.as_vec2() / 2.0) - scaled.as_vec2() / 2.0;
+                regions.push(Region {
+                    camera: Camera {
+                        position: camera.position - unrotate * ((offset - jitter) / scale),
+                        rotation: camera.rotation,
+                        scale,
+                    },
+                    size: tile,
+                    min,
+                    ratio,
+                });
+            }
+        }
+        regions
+    }
+
+    /// The camera and pointer position a scene drawn by the last frame
+    /// expects for the pointer at `pos` in the scene at full resolution,
+    /// those of the region it's in.
+    pub fn view_at(&self, camera: &Camera, pos: Vec2) -> (Camera, Vec2) {
+        let Some((size, capacity)) = self.last else {
+            return (camera.clone(), pos);
+        };
+        let regions = self.regions(camera, size, capacity, Vec2::ZERO);
+        let region = (regions.iter())
+            .find(|region| region.contains(pos * region.ratio))
+            .unwrap_or(&regions[0]);
+        (region.camera.clone(), region.pointer(pos))
+    }
+
+    /// Draws the scene shown by `camera` at size `size` with `draw`, which
+    /// renders a region of it to the default framebuffer of size `capacity`,
+    /// then resamples it into place.
+    pub unsafe fn draw(
+        &mut self,
+        size: UVec2,
+        capacity: UVec2,
+        camera: &Camera,
+        mut draw: impl FnMut(&Region),
+    ) -> Result<(), GlError> {
+        let scaled = self.scaled_size(size);
+        if scaled == size || size.min_element() == 0 {
+            self.last = None;
+            draw(&Region {
+                camera: camera.clone(),
+                size,
+                min: UVec2::ZERO,
+                ratio: Vec2::ONE,
+            });
+            return Ok(());
+        }
+
+        let temporal = self.upscale == Upscale::Temporal && scaled.cmplt(size).all();
+        let jitter = match temporal {
+            true => jitter(self.frame),
+            false => Vec2::ZERO,
+        };
+        let moved = (self.last_camera.as_ref()).is_none_or(|last| {
+            last.position != camera.position
+                || last.rotation != camera.rotation
+                || last.scale != camera.scale
+        });
+        let regions = self.regions(camera, size, capacity, jitter);
+
+        if self.renderer.is_none() {
+            self.renderer = Some(Renderer::new()?);
+        }
+        let renderer = self.renderer.as_mut().unwrap();
+        let fresh = renderer.resize(scaled, temporal.then_some(size))?;
+        self.last = Some((size, capacity));
+        self.last_camera = Some(camera.clone());
+        if temporal {
+            self.frame = self.frame.wrapping_add(1);
+        }
+
+        let copy = renderer.copy.as_ref().unwrap().fbo;
+        for region in &regions {
+            draw(region);
+
+            push_debug_group(c"Render scale");
+            // the part of the region inside the image, at the top of what the
+            // scene drew, GL's origin being at the bottom
+            let shown = region.size.min(scaled - region.min).as_ivec2();
+            let top = region.size.y as i32;
+            let min = region.min.as_ivec2();
+            let dst_y = scaled.y as i32 - min.y;
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, copy);
+            gl::BlitFramebuffer(
+                0,
+                top - shown.y,
+                shown.x,
+                top,
+                min.x,
+                dst_y - shown.y,
+                min.x + shown.x,
+                dst_y,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            pop_debug_group();
+        }
+
+        push_debug_group(c"Render scale");
+        let filter = match self.upscale {
+            _ if scaled.cmpgt(size).any() => Filter::Area,
+            Upscale::Bilinear => Filter::Bilinear,
+            Upscale::Bicubic => Filter::Bicubic,
+            Upscale::Temporal => Filter::Bilinear,
+        };
+        match temporal {
+            true => renderer.accumulate(size, jitter, moved || fresh),
+            false => renderer.resample(size, filter),
+        }
+        pop_debug_group();
+        Ok(())
+    }
+}
+
+/// Filters of upscale.frag, by the value of `u_filter`.
+#[derive(Debug, Clone, Copy)]
+enum Filter {
+    Bilinear = 0,
+    Bicubic = 1,
+    Area = 2,
+}
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+struct Renderer {
+    /// The scene at the scaled resolution.
+    copy: Option<Framebuffer>,
+    /// Both ends of the ping-pong the temporal reconstruction accumulates in,
+    /// at full resolution, the last one written first.
+    history: Option<[Framebuffer; 2]>,
+
+    upscale: GLuint,
+    u_filter: GLint,
+    u_resolution: GLint,
+    temporal: GLuint,
+    u_jitter: GLint,
+    u_reset: GLint,
+    u_feedback: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+}
+
+impl Renderer {
+    unsafe fn new() -> Result<Self, GlError> {
+        let upscale = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_UPSCALE)?;
+        let temporal = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_TEMPORAL)
+            .inspect_err(|_| gl::DeleteProgram(upscale))?;
+        for (name, unit) in [(c"u_current", 0), (c"u_history", 1)] {
+            gl::Uniform1i(gl::GetUniformLocation(temporal, name.as_ptr()), unit);
+        }
+
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        gl::BindVertexArray(vao);
+        let mut vbo = 0;
+        gl::GenBuffers(1, &mut vbo);
+        buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+        // both share screen.vert, so their attributes are at the same
+        // locations
+        const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+        for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+            let location = gl::GetAttribLocation(upscale, name.as_ptr()) as GLuint;
+            gl::VertexAttribPointer(location, 2, gl::FLOAT, gl::FALSE, SIZE_VERTEX, offset as _);
+            gl::EnableVertexAttribArray(location);
+        }
+        gl::BindVertexArray(0);
+
+        let uniform =
+            |program: GLuint, name: &std::ffi::CStr| gl::GetUniformLocation(program, name.as_ptr());
+        Ok(Self {
+            copy: None,
+            history: None,
+            upscale,
+            u_filter: uniform(upscale, c"u_filter"),
+            u_resolution: uniform(upscale, c"u_resolution"),
+            temporal,
+            u_jitter: uniform(temporal, c"u_jitter"),
+            u_reset: uniform(temporal, c"u_reset"),
+            u_feedback: uniform(temporal, c"u_feedback"),
+            vao,
+            vbo,
+        })
+    }
+
+    /// Recreates the copy if the scaled image changed size, and the history
+    /// if the full one did, returning whether the history is new.
+    unsafe fn resize(&mut self, scaled: UVec2, history: Option<UVec2>) -> Result<bool, GlError> {
+        if self.copy.as_ref().is_some_and(|copy| copy.size != scaled) {
+            self.delete_copy();
+        }
+        if self.copy.is_none() {
+            self.copy = Some(create_framebuffer("render scale", scaled)?);
+        }
+
+        let stale = (self.history.as_ref()).is_some_and(|h| Some(h[0].size) != history);
+        if stale {
+            self.delete_history();
+        }
+        let mut fresh = false;
+        if let (None, Some(size)) = (&self.history, history) {
+            let ping = create_framebuffer("render scale history ping", size)?;
+            let pong = create_framebuffer("render scale history pong", size)
+                .inspect_err(|_| delete_framebuffer(&ping))?;
+            self.history = Some([ping, pong]);
+            fresh = true;
+        }
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        Ok(fresh)
+    }
+
+    /// Draws a quad over `size` pixels of `to` with the program in use.
+    unsafe fn draw_quad(&self, to: GLuint, size: UVec2) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, to);
+        gl::Viewport(0, 0, size.x as GLint, size.y as GLint);
+        gl::Disable(gl::BLEND);
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    }
+
+    /// Resamples the copy over `size` pixels of the default framebuffer.
+    unsafe fn resample(&self, size: UVec2, filter: Filter) {
+        let copy = self.copy.as_ref().unwrap();
+        let saved = SavedState::save();
+        gl::UseProgram(self.upscale);
+        gl::Uniform1i(self.u_filter, filter as GLint);
+        gl::Uniform2f(self.u_resolution, size.x as f32, size.y as f32);
+        gl::BindTexture(gl::TEXTURE_2D, copy.texture);
+        self.draw_quad(0, size);
+        saved.restore();
+    }
+
+    /// Adds the copy, moved by `jitter`, to the history, and shows the result
+    /// over `size` pixels of the default framebuffer.
+    unsafe fn accumulate(&mut self, size: UVec2, jitter: Vec2, reset: bool) {
+        let copy = self.copy.as_ref().unwrap();
+        let [last, next] = self.history.as_ref().unwrap();
+
+        let saved = SavedState::save();
+        gl::UseProgram(self.temporal);
+        // the texture's y goes up
+        gl::Uniform2f(self.u_jitter, jitter.x, -jitter.y);
+        gl::Uniform1i(self.u_reset, reset as GLint);
+        gl::Uniform1f(self.u_feedback, FEEDBACK);
+        gl::ActiveTexture(gl::TEXTURE1);
+        gl::BindTexture(gl::TEXTURE_2D, last.texture);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, copy.texture);
+        self.draw_quad(next.fbo, size);
+        saved.restore();
+
+        let size = size.as_ivec2();
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, next.fbo);
+        gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+        gl::BlitFramebuffer(
+            0,
+            0,
+            size.x,
+            size.y,
+            0,
+            0,
+            size.x,
+            size.y,
+            gl::COLOR_BUFFER_BIT,
+            gl::NEAREST,
+        );
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+        self.history.as_mut().unwrap().swap(0, 1);
+    }
+
+    unsafe fn delete_copy(&mut self) {
+        if let Some(copy) = self.copy.take() {
+            delete_framebuffer(&copy);
+        }
+    }
+
+    unsafe fn delete_history(&mut self) {
+        for framebuffer in self.history.take().iter().flatten() {
+            delete_framebuffer(framebuffer);
+        }
+    }
+}
+
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        unsafe {
+            self.delete_copy();
+            self.delete_history();
+            gl::DeleteProgram(self.upscale);
+            gl::DeleteProgram(self.temporal);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! GL state each scene declares rather than sets once and relies on.
+//!
+//! Scenes used to enable blending in their `new()` and assume it was still
+//! there when drawing, which stopped being true as soon as another scene was
+//! created in between. Each scene now declares a [`RenderState`], which
+//! [`Scenes`](crate::scenes::Scenes) applies before updating and drawing it.
+//! Within a pass a scene may change the state as it needs, as long as it puts
+//! the declared one back.
+//!
+//! With validation on (`--check-gl-state`), the state is read back after each
+//! draw, and a scene leaving something different from what it declared is
+//! reported once, as that would leak into whatever is drawn next.
+//!
+//! On top of what scenes declare, a [`PolygonMode`] picked at runtime draws
+//! every scene as a wireframe or as the points of its vertices, to look at
+//! its geometry, and 3D scenes draw their normals and tangents when asked to.
+//! Both only apply to scenes: [`reset_debug`] puts polygons back to being
+//! filled before the post-processing and the overlay.
+//!
+//! OpenGL ES has no `glPolygonMode`, so there the shaders do it themselves:
+//! [`barycentric_source`] wraps their `main` to discard what isn't near an
+//! edge or a corner of its triangle, as told by a uniform block every
+//! program shares. Triangles get their corners from `gl_VertexID`, which is
+//! exact for arrays of triangles and only an approximation for indexed ones.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+use std::sync::Mutex;
+
+use gl::types::{GLenum, GLint, GLsizeiptr, GLuint};
+
+static VALIDATE: AtomicBool = AtomicBool::new(false);
+static REPORTED: Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+static POLYGON_MODE: AtomicU8 = AtomicU8::new(PolygonMode::Fill as u8);
+static NORMALS: AtomicBool = AtomicBool::new(false);
+/// Whether [`RenderState::apply`] left a polygon mode or point size that
+/// [`reset_debug`] has to undo.
+static APPLIED: AtomicBool = AtomicBool::new(false);
+static GLES: AtomicBool = AtomicBool::new(false);
+/// The buffer behind [`DEBUG_BINDING`] on OpenGL ES, and the mode it holds.
+static DEBUG_UBO: AtomicU32 = AtomicU32::new(0);
+static DEBUG_UBO_MODE: AtomicU8 = AtomicU8::new(PolygonMode::Fill as u8);
+
+/// Uniform buffer binding of the polygon mode on OpenGL ES, high enough to
+/// stay clear of those scenes use.
+pub const DEBUG_BINDING: GLuint = 15;
+
+/// Size of the points drawn in [`PolygonMode::Point`], in pixels.
+const POINT_SIZE: f32 = 3.0;
+
+/// How every scene's polygons are rasterized.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonMode {
+    #[default]
+    Fill,
+    /// Only the edges of the triangles.
+    Line,
+    /// Only their vertices.
+    Point,
+}
+
+impl PolygonMode {
+    pub const ALL: [Self; 3] = [Self::Fill, Self::Line, Self::Point];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fill => "fill",
+            Self::Line => "line",
+            Self::Point => "point",
+        }
+    }
+
+    /// The mode after this one, back to filled after points.
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    fn gl(self) -> GLenum {
+        match self {
+            Self::Fill => gl::FILL,
+            Self::Line => gl::LINE,
+            Self::Point => gl::POINT,
+        }
+    }
+}
+
+/// How fragments are combined with what's already in the framebuffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blend {
+    /// Fragments replace what's there.
+    Off,
+    /// Over by straight alpha.
+    Alpha,
+    /// Over by premultiplied alpha.
+    Premultiplied,
+    /// Added to what's there, weighted by alpha.
+    Additive,
+}
+
+impl Blend {
+    /// Source and destination factors, the same for color and alpha.
+    fn funcs(self) -> Option<(GLenum, GLenum)> {
+        match self {
+            Self::Off => None,
+            Self::Alpha => Some((gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA)),
+            Self::Premultiplied => Some((gl::ONE, gl::ONE_MINUS_SRC_ALPHA)),
+            Self::Additive => Some((gl::SRC_ALPHA, gl::ONE)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderState {
+    pub blend: Blend,
+    pub depth_test: bool,
+    pub cull_face: bool,
+    /// Linear colors written to sRGB framebuffers get encoded.
+    pub srgb: bool,
+}
+
+impl RenderState {
+    /// Everything off, fragments written as they are.
+    pub const OPAQUE: Self = Self {
+        blend: Blend::Off,
+        depth_test: false,
+        cull_face: false,
+        srgb: false,
+    };
+
+    /// What most 2D scenes draw with.
+    pub const ALPHA_BLENDED: Self = Self {
+        blend: Blend::Alpha,
+        ..Self::OPAQUE
+    };
+
+    pub unsafe fn apply(&self) {
+        match self.blend.funcs() {
+            Some((src, dst)) => {
+                gl::Enable(gl::BLEND);
+                gl::BlendEquation(gl::FUNC_ADD);
+                gl::BlendFunc(src, dst);
+            }
+            None => gl::Disable(gl::BLEND),
+        }
+        set_capability(gl::DEPTH_TEST, self.depth_test);
+        set_capability(gl::CULL_FACE, self.cull_face);
+        set_capability(gl::FRAMEBUFFER_SRGB, self.srgb);
+
+        let mode = polygon_mode();
+        if is_gles() {
+            set_debug_ubo(mode);
+        } else if mode != PolygonMode::Fill {
+            gl::PolygonMode(gl::FRONT_AND_BACK, mode.gl());
+            if mode == PolygonMode::Point {
+                gl::PointSize(POINT_SIZE);
+            }
+            APPLIED.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// How the current GL state differs from this one, empty if it doesn't.
+    pub unsafe fn differences(&self) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        let blending = gl::IsEnabled(gl::BLEND) == gl::TRUE;
+        match self.blend.funcs() {
+            None if blending => differences.push("blending on".to_string()),
+            None => {}
+            Some(_) if !blending => differences.push("blending off".to_string()),
+            Some((src, dst)) => {
+                let funcs = [
+                    gl::BLEND_SRC_RGB,
+                    gl::BLEND_DST_RGB,
+                    gl::BLEND_SRC_ALPHA,
+                    gl::BLEND_DST_ALPHA,
+                ]
+                .map(get);
+                let equations = [gl::BLEND_EQUATION_RGB, gl::BLEND_EQUATION_ALPHA].map(get);
+                if funcs != [src, dst, src, dst] {
+                    differences.push(format!("blend factors {funcs:x?}"));
+                }
+                if equations != [gl::FUNC_ADD; 2] {
+                    differences.push(format!("blend equations {equations:x?}"));
+                }
+            }
+        }
+
+        let capabilities = [
+            ("depth test", gl::DEPTH_TEST, self.depth_test),
+            ("face culling", gl::CULL_FACE, self.cull_face),
+            ("sRGB encoding", gl::FRAMEBUFFER_SRGB, self.srgb),
+        ];
+        for (name, capability, expected) in capabilities {
+            let enabled = gl::IsEnabled(capability) == gl::TRUE;
+            if enabled != expected {
+                let state = if enabled { "on" } else { "off" };
+                differences.push(format!("{name} {state}"));
+            }
+        }
+
+        // front and back, which are always set together
+        if !is_gles() {
+            let mut modes: [GLint; 2] = [0; 2];
+            gl::GetIntegerv(gl::POLYGON_MODE, modes.as_mut_ptr());
+            if modes[0] as GLenum != polygon_mode().gl() {
+                differences.push(format!("polygon mode {:x}", modes[0]));
+            }
+        }
+
+        differences
+    }
+}
+
+pub fn set_polygon_mode(mode: PolygonMode) {
+    POLYGON_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn polygon_mode() -> PolygonMode {
+    PolygonMode::ALL[POLYGON_MODE.load(Ordering::Relaxed) as usize]
+}
+
+/// Turns drawing the normals and tangents of 3D scenes on or off.
+pub fn set_normals(enabled: bool) {
+    NORMALS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn show_normals() -> bool {
+    NORMALS.load(Ordering::Relaxed)
+}
+
+/// Fills polygons again after a scene, whatever the polygon mode, so that
+/// what is drawn on top of it isn't affected.
+pub unsafe fn reset_debug() {
+    if is_gles() {
+        set_debug_ubo(PolygonMode::Fill);
+    } else if APPLIED.swap(false, Ordering::Relaxed) {
+        gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+        gl::PointSize(1.0);
+    }
+}
+
+/// Tells whether the context is OpenGL ES, where polygon modes go through
+/// [`barycentric_source`]. Must be set before any shader is compiled.
+pub fn set_gles(gles: bool) {
+    GLES.store(gles, Ordering::Relaxed);
+}
+
+pub fn is_gles() -> bool {
+    GLES.load(Ordering::Relaxed)
+}
+
+/// Writes `mode` to the buffer shaders wrapped by [`barycentric_source`] read
+/// it from, creating it the first time.
+unsafe fn set_debug_ubo(mode: PolygonMode) {
+    let mut ubo = DEBUG_UBO.load(Ordering::Relaxed);
+    if ubo == 0 {
+        gl::GenBuffers(1, &mut ubo);
+        gl::BindBuffer(gl::UNIFORM_BUFFER, ubo);
+        // std140 rounds the block up to a vec4
+        let data = [PolygonMode::Fill as i32, 0, 0, 0];
+        gl::BufferData(
+            gl::UNIFORM_BUFFER,
+            std::mem::size_of_val(&data) as GLsizeiptr,
+            data.as_ptr() as *const _,
+            gl::DYNAMIC_DRAW,
+        );
+        gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+        gl::BindBufferBase(gl::UNIFORM_BUFFER, DEBUG_BINDING, ubo);
+        DEBUG_UBO.store(ubo, Ordering::Relaxed);
+        DEBUG_UBO_MODE.store(PolygonMode::Fill as u8, Ordering::Relaxed);
+    }
+
+    if DEBUG_UBO_MODE.swap(mode as u8, Ordering::Relaxed) != mode as u8 {
+        let value = mode as i32;
+        gl::BindBuffer(gl::UNIFORM_BUFFER, ubo);
+        gl::BufferSubData(
+            gl::UNIFORM_BUFFER,
+            0,
+            std::mem::size_of_val(&value) as GLsizeiptr,
+            &value as *const i32 as *const _,
+        );
+        gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+    }
+    // scenes may have bound something else there in the meantime
+    gl::BindBufferBase(gl::UNIFORM_BUFFER, DEBUG_BINDING, ubo);
+}
+
+/// `source`, a shader of type `ty`, with its `main` wrapped to draw in the
+/// polygon mode held at [`DEBUG_BINDING`]: vertex shaders pass on which
+/// corner of its triangle each vertex is, and fragment shaders discard what
+/// is too far from the edges, or from the corners. `None` for other types of
+/// shaders, or if `main` can't be found.
+pub fn barycentric_source(source: &[u8], ty: GLenum) -> Option<Vec<u8>> {
+    let wrapper = match ty {
+        gl::VERTEX_SHADER => VERTEX_WRAPPER.to_string(),
+        gl::FRAGMENT_SHADER => FRAGMENT_WRAPPER.replace("POINT_SIZE", &format!("{POINT_SIZE:?}")),
+        _ => return None,
+    };
+
+    let source = std::str::from_utf8(source).ok()?;
+    let main = find_main(source)?;
+    let mut wrapped = String::with_capacity(source.len() + wrapper.len());
+    wrapped.push_str(&source[..main]);
+    wrapped.push_str("debug_scene_main");
+    wrapped.push_str(&source[main + "main".len()..]);
+    wrapped.push_str(&wrapper);
+    Some(wrapped.into_bytes())
+}
+
+/// Byte offset of the name of the `void main(` function in `source`.
+fn find_main(source: &str) -> Option<usize> {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    source.match_indices("main").map(|(i, _)| i).find(|&i| {
+        let before = &source[..i];
+        let returns_void =
+            (before.trim_end().strip_suffix("void")).is_some_and(|rest| !rest.ends_with(is_ident));
+        before.ends_with(char::is_whitespace)
+            && returns_void
+            && source[i + "main".len()..].trim_start().starts_with('(')
+    })
+}
+
+const VERTEX_WRAPPER: &str = "
+// added by render_state.rs, for polygon modes without glPolygonMode
+out vec3 debug_barycentric;
+
+void main() {
+    debug_scene_main();
+    debug_barycentric = vec3(equal(ivec3(gl_VertexID % 3), ivec3(0, 1, 2)));
+}
+";
+
+const FRAGMENT_WRAPPER: &str = "
+// added by render_state.rs, for polygon modes without glPolygonMode
+in vec3 debug_barycentric;
+
+layout(std140) uniform DebugPolygonMode {
+    int debug_polygon_mode;
+};
+
+void main() {
+    debug_scene_main();
+
+    // pixels to each edge, and to each corner
+    vec3 pixel = max(fwidth(debug_barycentric), vec3(1e-6));
+    vec3 to_edge = debug_barycentric / pixel;
+    vec3 to_corner = (1.0 - debug_barycentric) / pixel;
+    if (debug_polygon_mode == 1 && min(to_edge.x, min(to_edge.y, to_edge.z)) > 1.0) {
+        discard;
+    }
+    if (debug_polygon_mode == 2
+        && min(to_corner.x, min(to_corner.y, to_corner.z)) > POINT_SIZE / 2.0) {
+        discard;
+    }
+}
+";
+
+/// Turns checking the state after each draw on or off.
+pub fn set_validation(enabled: bool) {
+    VALIDATE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_validating() -> bool {
+    VALIDATE.load(Ordering::Relaxed)
+}
+
+/// Checks that `scene` left the state it declared, reporting the first time
+/// it didn't. Returns whether it did.
+pub unsafe fn check(scene: &'static str, state: &RenderState) -> bool {
+    let differences = state.differences();
+    if differences.is_empty() {
+        return true;
+    }
+
+    let mut reported = REPORTED.lock().unwrap_or_else(|e| e.into_inner());
+    if reported.get_or_insert_with(HashSet::new).insert(scene) {
+        eprintln!(
+            "GL state leaked by {scene}: {}, declared {state:?}",
+            differences.join(", ")
+        );
+    }
+    false
+}
+
+unsafe fn set_capability(capability: GLenum, enabled: bool) {
+    match enabled {
+        true => gl::Enable(capability),
+        false => gl::Disable(capability),
+    }
+}
+
+fn get(name: GLenum) -> GLenum {
+    let mut value: GLint = 0;
+    unsafe { gl::GetIntegerv(name, &mut value) };
+    value as GLenum
+}
@@ -0,0 +1,245 @@
+//! Ring buffer of the last seconds of the window, saved on demand.
+//!
+//! With `--replay-buffer <seconds>`, every frame the scene is drawn to the
+//! window is also blitted into a small framebuffer, at most [`MAX_SIDE`]
+//! pixels wide or tall and [`FPS`] times per second, and read back into
+//! memory. Saving writes what the buffer holds as a numbered PNG sequence, so
+//! that a glitch seen a moment ago can be looked at frame by frame instead of
+//! reproduced.
+//!
+//! Readbacks go through pixel buffer objects and are only read from a couple
+//! of captures later, by which time the GPU has long finished them, so that
+//! capturing doesn't wait for the frame in flight. Frames are kept bottom row
+//! first as GL reads them, and flipped when saved.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
+use glam::UVec2;
+use image::{ImageResult, RgbaImage};
+
+use crate::common_gl::{create_framebuffer, delete_framebuffer, Framebuffer};
+use crate::error::GlError;
+use crate::gpu_memory::{self, Category, Object};
+
+/// Largest side of the captured frames, in pixels.
+pub const MAX_SIDE: u32 = 360;
+
+/// Captures per second, whatever the frame rate.
+pub const FPS: f32 = 20.0;
+
+/// Captures between a readback being issued and being read.
+const LATENCY: usize = 2;
+
+/// A captured frame, bottom row first.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Seconds since the buffer started capturing.
+    pub time: f32,
+    pub size: UVec2,
+    pub pixels: Vec<u8>,
+}
+
+/// A readback issued into `pbo` and not read yet.
+struct Pending {
+    pbo: GLuint,
+    time: f32,
+    size: UVec2,
+}
+
+pub struct ReplayBuffer {
+    seconds: f32,
+    /// Where the window is downscaled to, recreated when its size changes.
+    framebuffer: Option<Framebuffer>,
+    free_pbos: Vec<GLuint>,
+    /// Readbacks in the order they were issued.
+    pending: VecDeque<Pending>,
+    /// Captured frames, oldest first.
+    frames: VecDeque<Frame>,
+    time: f32,
+    since_capture: f32,
+}
+
+impl ReplayBuffer {
+    /// Keeps the last `seconds` of frames.
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            seconds,
+            framebuffer: None,
+            free_pbos: Vec::new(),
+            pending: VecDeque::new(),
+            frames: VecDeque::new(),
+            time: 0.0,
+            // the first frame is captured right away
+            since_capture: 1.0 / FPS,
+        }
+    }
+
+    pub fn seconds(&self) -> f32 {
+        self.seconds
+    }
+
+    /// Frames captured so far, oldest first, without those still being read
+    /// back.
+    pub fn frames(&self) -> &VecDeque<Frame> {
+        &self.frames
+    }
+
+    /// Size frames of a window of `viewport` are captured at: the same aspect
+    /// ratio, no side longer than [`MAX_SIDE`].
+    pub fn frame_size(viewport: UVec2) -> UVec2 {
+        let viewport = viewport.max(UVec2::ONE);
+        let scale = (MAX_SIDE as f32 / viewport.max_element() as f32).min(1.0);
+        (viewport.as_vec2() * scale)
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE)
+    }
+
+    /// Captures the window's framebuffer of size `viewport` if it's time to,
+    /// `dt` seconds after the previous call. Leaves the window's framebuffer
+    /// bound.
+    pub unsafe fn capture(&mut self, viewport: UVec2, dt: f32) -> Result<(), GlError> {
+        self.time += dt;
+        self.since_capture += dt;
+        if self.since_capture < 1.0 / FPS {
+            return Ok(());
+        }
+        // don't catch up on missed captures after a long frame
+        self.since_capture = (self.since_capture - 1.0 / FPS).min(1.0 / FPS);
+
+        while self.pending.len() >= LATENCY {
+            self.read_oldest();
+        }
+
+        let size = Self::frame_size(viewport);
+        if self.framebuffer.as_ref().map(|fb| fb.size) != Some(size) {
+            if let Some(framebuffer) = self.framebuffer.take() {
+                delete_framebuffer(&framebuffer);
+            }
+            self.framebuffer = Some(create_framebuffer("replay", size)?);
+        }
+        let framebuffer = self.framebuffer.as_ref().unwrap();
+
+        // blits are clipped by the scissor box like any draw
+        let scissor = gl::IsEnabled(gl::SCISSOR_TEST) == gl::TRUE;
+        gl::Disable(gl::SCISSOR_TEST);
+
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, framebuffer.fbo);
+        gl::BlitFramebuffer(
+            0,
+            0,
+            viewport.x as GLint,
+            viewport.y as GLint,
+            0,
+            0,
+            size.x as GLint,
+            size.y as GLint,
+            gl::COLOR_BUFFER_BIT,
+            gl::LINEAR,
+        );
+
+        let pbo = self.free_pbos.pop().unwrap_or_else(|| {
+            let mut pbo = 0;
+            gl::GenBuffers(1, &mut pbo);
+            pbo
+        });
+        let bytes = size.x as usize * size.y as usize * 4;
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer.fbo);
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, pbo);
+        gl::BufferData(
+            gl::PIXEL_PACK_BUFFER,
+            bytes as GLsizeiptr,
+            std::ptr::null(),
+            gl::STREAM_READ,
+        );
+        gpu_memory::record(Object::Buffer(pbo), Category::Buffers, bytes);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            size.x as GLsizei,
+            size.y as GLsizei,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            std::ptr::null_mut(),
+        );
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+        if scissor {
+            gl::Enable(gl::SCISSOR_TEST);
+        }
+
+        self.pending.push_back(Pending {
+            pbo,
+            time: self.time,
+            size,
+        });
+        Ok(())
+    }
+
+    /// Reads back every pending frame, waiting for the GPU if needed, so that
+    /// the last captures are part of what's saved.
+    pub unsafe fn flush(&mut self) {
+        while !self.pending.is_empty() {
+            self.read_oldest();
+        }
+    }
+
+    unsafe fn read_oldest(&mut self) {
+        let Some(Pending { pbo, time, size }) = self.pending.pop_front() else {
+            return;
+        };
+
+        let mut pixels = vec![0u8; size.x as usize * size.y as usize * 4];
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, pbo);
+        gl::GetBufferSubData(
+            gl::PIXEL_PACK_BUFFER,
+            0,
+            pixels.len() as GLsizeiptr,
+            pixels.as_mut_ptr() as *mut _,
+        );
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+        self.free_pbos.push(pbo);
+
+        self.frames.push_back(Frame { time, size, pixels });
+        while (self.frames.front()).is_some_and(|frame| frame.time < time - self.seconds) {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Deletes the GL objects of the buffer, keeping the frames it holds.
+    pub unsafe fn delete(&mut self) {
+        if let Some(framebuffer) = self.framebuffer.take() {
+            delete_framebuffer(&framebuffer);
+        }
+        let pbos = (self.free_pbos.drain(..)).chain(self.pending.drain(..).map(|p| p.pbo));
+        for pbo in pbos.collect::<Vec<_>>() {
+            gl::DeleteBuffers(1, &pbo);
+            gpu_memory::forget(Object::Buffer(pbo));
+        }
+    }
+}
+
+/// Writes `frames` to `dir` as `frame-0000.png`, `frame-0001.png`, etc.,
+/// creating it if needed.
+pub fn save_sequence(dir: &Path, frames: &[Frame]) -> ImageResult<()> {
+    fs::create_dir_all(dir)?;
+
+    for (i, frame) in frames.iter().enumerate() {
+        let row_len = frame.size.x as usize * 4;
+        let flipped = (frame.pixels.chunks_exact(row_len).rev())
+            .flatten()
+            .copied()
+            .collect();
+        let image = RgbaImage::from_raw(frame.size.x, frame.size.y, flipped).unwrap();
+        image.save(dir.join(format!("frame-{i:04}.png")))?;
+    }
+    Ok(())
+}
@@ -1,34 +1,120 @@
 //! A nice scene controller to smoothly move around in the window.
 
+use std::str::FromStr;
 use std::time::Instant;
 
 use crate::camera::Camera;
+use crate::profiling;
+use crate::split_view::{Side, SplitView};
+use crate::viewport::Viewport;
 
 use glam::{vec2, Vec2};
 use winit::event::{ElementState, MouseScrollDelta, WindowEvent};
 
+/// Length of a fixed-timestep update, in seconds.
+pub const FIXED_DT: f32 = 1.0 / 120.0;
+
+/// Fixed steps run at most per frame, so that a long frame doesn't snowball
+/// into even longer ones.
+const MAX_FIXED_STEPS: u32 = 8;
+
+/// How much closer the camera of the right half starts in split view.
+const SPLIT_ZOOM: f32 = 4.0;
+
+/// How far the cameras can zoom out and in, as multiples of the default zoom
+/// of one physical pixel per world unit at a scale factor of 1.
+///
+/// Zooming in without end runs out of float precision, and scenes start to
+/// jitter well before the numbers break down entirely.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoomLimits {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for ZoomLimits {
+    fn default() -> Self {
+        Self {
+            min: 0.01,
+            max: 256.0,
+        }
+    }
+}
+
+impl ZoomLimits {
+    /// Zooms `scale` by `2^octaves`, for a window of scale factor
+    /// `scale_factor`. Steps shrink within an octave of a limit, so that
+    /// zooming eases into it rather than stopping dead.
+    pub fn zoom(&self, scale: f32, octaves: f32, scale_factor: f32) -> f32 {
+        let min = (self.min * scale_factor).log2();
+        let max = (self.max * scale_factor).log2();
+        let current = scale.log2().clamp(min, max);
+
+        let room = match octaves > 0.0 {
+            true => max - current,
+            false => current - min,
+        };
+        let step = octaves * room.min(1.0);
+        (current + step).clamp(min, max).exp2()
+    }
+}
+
+impl FromStr for ZoomLimits {
+    type Err = String;
+
+    /// Parses `MIN,MAX`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let limits = (s.split(','))
+            .map(|zoom| zoom.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        let [min, max] = limits[..] else {
+            return Err("expected MIN,MAX".to_owned());
+        };
+        if !(min > 0.0 && min <= max && max.is_finite()) {
+            return Err("limits must be positive, the smallest first".to_owned());
+        }
+
+        Ok(Self { min, max })
+    }
+}
+
 pub struct SceneController {
     pub camera: Camera,
+    /// Camera of the right half of the window, when it is split in two. The
+    /// left half keeps [`Self::camera`].
+    pub split_camera: Option<Camera>,
+    viewport: Viewport,
 
     // for camera position and mouse interactions
     camera_pos: Vec2,
     mouse_pos: Vec2,
     mouse_pos_held: Vec2,
     mouse_state: ElementState,
+    /// Half of the window the mouse was pressed on.
+    side_held: Side,
 
     // for smooth scrolling
     pub scroll_speed: f32,
+    pub zoom_limits: ZoomLimits,
+    /// Pixels of the scene per pixel of the window, when the scene is drawn
+    /// at another resolution than the window's, so that drags follow the
+    /// mouse.
+    pub pixel_ratio: Vec2,
     hard_scale: Vec2,
+    split_hard_scale: Vec2,
 
     // for FPS-independent interactions
     start: Instant,
     prev_elapsed: f32,
     current_elapsed: f32,
+    /// Time not yet consumed by fixed steps.
+    fixed_accumulator: f32,
 }
 
 impl SceneController {
-    pub fn new(scale_factor: f32, scroll_speed: f32) -> Self {
-        let scale = Vec2::splat(scale_factor);
+    pub fn new(viewport: Viewport, scroll_speed: f32) -> Self {
+        let scale = Vec2::splat(viewport.scale_factor);
 
         let camera = Camera {
             scale,
@@ -37,32 +123,146 @@ impl SceneController {
 
         Self {
             camera,
+            split_camera: None,
+            viewport,
             camera_pos: Vec2::default(),
             mouse_pos: Vec2::default(),
             mouse_pos_held: Vec2::default(),
             mouse_state: ElementState::Released,
+            side_held: Side::Left,
             scroll_speed,
+            zoom_limits: ZoomLimits::default(),
+            pixel_ratio: Vec2::ONE,
             hard_scale: scale,
+            split_hard_scale: scale,
             start: Instant::now(),
             prev_elapsed: 0.0,
             current_elapsed: 0.0,
+            fixed_accumulator: 0.0,
         }
     }
 
+    /// Starts a frame as long as the time since the last one.
     pub fn update(&mut self) {
+        self.update_to(self.start.elapsed().as_secs_f32());
+    }
+
+    /// Starts a frame `dt` seconds long, whatever time it is, as when
+    /// replaying a recorded session.
+    pub fn update_by(&mut self, dt: f32) {
+        self.update_to(self.current_elapsed + dt);
+    }
+
+    fn update_to(&mut self, elapsed: f32) {
+        profiling::cpu_zone!("update");
+
         // Smooth scrolling
         let time_delta = self.current_elapsed - self.prev_elapsed;
-        self.camera.scale += time_delta.powf(0.6) * (self.hard_scale - self.camera.scale);
+        let smoothing = time_delta.powf(0.6);
+        self.camera.scale += smoothing * (self.hard_scale - self.camera.scale);
+        if let Some(split_camera) = &mut self.split_camera {
+            split_camera.scale += smoothing * (self.split_hard_scale - split_camera.scale);
+        }
 
         // Mouse dragging
         if self.mouse_state == ElementState::Pressed {
-            self.camera.position =
-                self.camera_pos + (self.mouse_pos - self.mouse_pos_held) / self.camera.scale;
+            let camera = match self.side_held {
+                Side::Left => Some(&mut self.camera),
+                Side::Right => self.split_camera.as_mut(),
+            };
+            if let Some(camera) = camera {
+                let dragged = (self.mouse_pos - self.mouse_pos_held) * self.pixel_ratio;
+                camera.position = self.camera_pos + dragged / camera.scale;
+            }
         }
 
         // Frame interval
         self.prev_elapsed = self.current_elapsed;
-        self.current_elapsed = self.start.elapsed().as_secs_f32();
+        self.current_elapsed = elapsed;
+        self.fixed_accumulator += self.dt();
+    }
+
+    /// How many fixed steps of [`FIXED_DT`] to run this frame, for animations
+    /// that should play the same whatever the frame rate.
+    pub fn take_fixed_steps(&mut self) -> u32 {
+        let steps = (self.fixed_accumulator / FIXED_DT) as u32;
+        self.fixed_accumulator -= steps as f32 * FIXED_DT;
+
+        if steps > MAX_FIXED_STEPS {
+            self.fixed_accumulator = 0.0;
+            return MAX_FIXED_STEPS;
+        }
+        steps
+    }
+
+    /// Takes over the camera, e.g. when it is animated by the sequencer.
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.hard_scale = camera.scale;
+        self.camera_pos = camera.position;
+        self.camera = camera;
+    }
+
+    /// Splits the window in two, the right half looking closer at the same
+    /// place, or joins it back. Returns whether it is now split.
+    pub fn toggle_split(&mut self) -> bool {
+        if self.split_camera.take().is_some() {
+            self.side_held = Side::Left;
+            return false;
+        }
+
+        self.split_hard_scale = self.hard_scale * SPLIT_ZOOM;
+        self.split_camera = Some(Camera {
+            scale: self.camera.scale * SPLIT_ZOOM,
+            ..self.camera.clone()
+        });
+        true
+    }
+
+    /// Size of the window, to tell which half the mouse is over.
+    ///
+    /// Cameras zoom in physical pixels, so when the scale factor changes they
+    /// zoom along with it to keep showing the same logical area.
+    pub fn resize(&mut self, viewport: Viewport) {
+        let ratio = viewport.scale_factor / self.viewport.scale_factor;
+        if ratio != 1.0 {
+            self.camera.scale *= ratio;
+            self.hard_scale *= ratio;
+            self.split_hard_scale *= ratio;
+            if let Some(split_camera) = &mut self.split_camera {
+                split_camera.scale *= ratio;
+            }
+        }
+        self.viewport = viewport;
+    }
+
+    /// Half of the window `pos` is over, always the left one when it isn't
+    /// split.
+    pub fn side_at(&self, pos: Vec2) -> Side {
+        let size = self.viewport.size();
+        let half = SplitView::half_size(self.viewport.physical()).as_vec2();
+        match self.split_camera {
+            Some(_) if pos.x >= size.x - half.x => Side::Right,
+            _ => Side::Left,
+        }
+    }
+
+    /// The camera of a half of the window, and where that half starts.
+    pub fn view(&self, side: Side) -> (&Camera, Vec2) {
+        match (side, &self.split_camera) {
+            (Side::Right, Some(split_camera)) => {
+                let size = self.viewport.size();
+                let half = SplitView::half_size(self.viewport.physical()).as_vec2();
+                (split_camera, vec2(size.x - half.x, 0.0))
+            }
+            _ => (&self.camera, Vec2::ZERO),
+        }
+    }
+
+    /// The camera looking through the window at `pos`, and where `pos` is in
+    /// the half it draws.
+    pub fn view_at(&self, pos: Vec2) -> (&Camera, Vec2) {
+        let (camera, offset) = self.view(self.side_at(pos));
+        (camera, pos - offset)
     }
 
     pub fn interact(&mut self, event: &WindowEvent) {
@@ -73,8 +273,9 @@ impl SceneController {
             WindowEvent::MouseInput { state, .. } => {
                 self.mouse_state = *state;
                 if self.mouse_state == ElementState::Pressed {
+                    self.side_held = self.side_at(self.mouse_pos);
                     self.mouse_pos_held = self.mouse_pos;
-                    self.camera_pos = self.camera.position;
+                    self.camera_pos = self.view(self.side_held).0.position;
                 }
             }
             WindowEvent::MouseWheel { delta, .. } => {
@@ -84,7 +285,14 @@ impl SceneController {
                     MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 100.,
                 };
 
-                self.hard_scale *= 2_f32.powf(self.scroll_speed * my);
+                let hard_scale = match self.side_at(self.mouse_pos) {
+                    Side::Left => &mut self.hard_scale,
+                    Side::Right => &mut self.split_hard_scale,
+                };
+                let octaves = self.scroll_speed * my;
+                let scale_factor = self.viewport.scale_factor;
+                let zoomed = self.zoom_limits.zoom(hard_scale.x, octaves, scale_factor);
+                *hard_scale *= zoomed / hard_scale.x;
             }
             _ => (),
         }
@@ -1,72 +1,905 @@
+#[cfg(feature = "scene-amplification")]
+pub mod amplification;
+#[cfg(feature = "scene-anisotropy")]
+pub mod anisotropy;
+#[cfg(feature = "scene-antialiasing")]
+pub mod antialiasing;
+#[cfg(feature = "scene-bindless")]
+pub mod bindless;
 pub mod blurring;
+#[cfg(feature = "scene-chroma-key")]
+pub mod chroma_key;
+#[cfg(feature = "scene-cloth")]
+pub mod cloth;
+#[cfg(feature = "scene-clustered-lighting")]
+pub mod clustered_lighting;
+#[cfg(feature = "scene-compression")]
+pub mod compression;
+#[cfg(feature = "scene-culling")]
+pub mod culling;
+#[cfg(feature = "scene-ecs-quads")]
+pub mod ecs_quads;
+pub mod error_screen;
+#[cfg(feature = "scene-gray-scott")]
+pub mod gray_scott;
+#[cfg(feature = "scene-hdr-test")]
+pub mod hdr_test;
 pub mod kawase;
+#[cfg(feature = "scene-kernel")]
+pub mod kernel;
+#[cfg(feature = "scene-marching-squares")]
+pub mod marching_squares;
+#[cfg(feature = "scene-mips")]
+pub mod mips;
+#[cfg(feature = "scene-n-body")]
+pub mod nbody;
+#[cfg(feature = "scene-overlap")]
+pub mod overlap;
+#[cfg(feature = "scene-particles")]
+pub mod particles;
+#[cfg(feature = "scene-physarum")]
+pub mod physarum;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+#[cfg(any(
+    feature = "scene-marching-squares",
+    feature = "scene-splines",
+    feature = "scene-voronoi",
+    feature = "scripting"
+))]
+mod polyline;
+#[cfg(feature = "scene-portal")]
+pub mod portal;
+#[cfg(feature = "scene-raymarching")]
+pub mod raymarching;
 pub mod round_quads;
+#[cfg(feature = "scripting")]
+pub mod scripted;
+#[cfg(feature = "scene-shape-inspector")]
+pub mod shape_inspector;
+#[cfg(feature = "scene-soft-body")]
+pub mod soft_body;
+#[cfg(feature = "scene-spatial")]
+pub mod spatial;
+#[cfg(any(
+    feature = "scene-spatial",
+    feature = "scene-splines",
+    feature = "scene-voronoi"
+))]
+mod spatial_overlay;
+#[cfg(feature = "scene-splines")]
+pub mod splines;
+#[cfg(feature = "scene-sun")]
+pub mod sun;
+mod technique_timings;
+#[cfg(feature = "scene-variable-rate")]
+pub mod variable_rate;
+#[cfg(feature = "scene-virtual-texture")]
+pub mod virtual_texture;
+#[cfg(feature = "scene-voronoi")]
+pub mod voronoi;
 
+#[cfg(feature = "scene-amplification")]
+use amplification::AmplificationScene;
+#[cfg(feature = "scene-anisotropy")]
+use anisotropy::AnisotropyScene;
+#[cfg(feature = "scene-antialiasing")]
+use antialiasing::AntialiasingScene;
+#[cfg(feature = "scene-bindless")]
+use bindless::BindlessScene;
 use blurring::BlurringScene;
+#[cfg(feature = "scene-chroma-key")]
+use chroma_key::ChromaKeyScene;
+#[cfg(feature = "scene-cloth")]
+use cloth::ClothScene;
+#[cfg(feature = "scene-clustered-lighting")]
+use clustered_lighting::ClusteredLightingScene;
+#[cfg(feature = "scene-compression")]
+use compression::CompressionScene;
+#[cfg(feature = "scene-culling")]
+use culling::CullingScene;
+#[cfg(feature = "scene-ecs-quads")]
+use ecs_quads::EcsQuadsScene;
+use error_screen::ErrorScreen;
+#[cfg(feature = "scene-gray-scott")]
+use gray_scott::GrayScottScene;
+#[cfg(feature = "scene-hdr-test")]
+use hdr_test::HdrTestScene;
 use kawase::KawaseScene;
+#[cfg(feature = "scene-kernel")]
+use kernel::KernelScene;
+#[cfg(feature = "scene-marching-squares")]
+use marching_squares::MarchingSquaresScene;
+#[cfg(feature = "scene-mips")]
+use mips::MipsScene;
+#[cfg(feature = "scene-n-body")]
+use nbody::NBodyScene;
+#[cfg(feature = "scene-overlap")]
+use overlap::OverlapScene;
+#[cfg(feature = "scene-particles")]
+use particles::ParticlesScene;
+#[cfg(feature = "scene-physarum")]
+use physarum::PhysarumScene;
+#[cfg(feature = "plugins")]
+use plugin::PluginScene;
+#[cfg(feature = "scene-portal")]
+use portal::PortalScene;
+#[cfg(feature = "scene-raymarching")]
+use raymarching::RaymarchingScene;
 use round_quads::RoundQuadsScene;
+#[cfg(feature = "scene-shape-inspector")]
+use shape_inspector::ShapeInspectorScene;
+#[cfg(feature = "scene-soft-body")]
+use soft_body::SoftBodyScene;
+#[cfg(feature = "scene-spatial")]
+use spatial::SpatialScene;
+#[cfg(feature = "scene-splines")]
+use splines::SplinesScene;
+#[cfg(feature = "scene-sun")]
+use sun::SunScene;
+#[cfg(feature = "scene-variable-rate")]
+use variable_rate::VariableRateScene;
+#[cfg(feature = "scene-virtual-texture")]
+use virtual_texture::VirtualTextureScene;
+#[cfg(feature = "scene-voronoi")]
+use voronoi::VoronoiScene;
 
 use glam::Vec2;
+use image::RgbaImage;
+use winit::dpi::PhysicalSize;
+use winit::event::{ElementState, MouseButton};
 use winit::keyboard::{Key, NamedKey, SmolStr};
-use winit::window::Window;
 
 use crate::camera::Camera;
+use crate::data_grid::DataGrid;
+use crate::error::{catch_panic, GlError, PlaygroundError};
+use crate::input_image::InputImage;
+use crate::locale::tr;
+use crate::params::Params;
+#[cfg(feature = "plugins")]
+use crate::plugin as plugins;
+use crate::profiling;
+use crate::render_state::{self, RenderState};
+use crate::text::SharedText;
 
 // shaders
+#[cfg(feature = "scene-antialiasing")]
+const SRC_VERT_AA_GEOMETRY: &[u8] = include_bytes!("../assets/shaders/aa-geometry.vert");
+#[cfg(feature = "scene-antialiasing")]
+const SRC_FRAG_AA_GEOMETRY: &[u8] = include_bytes!("../assets/shaders/aa-geometry.frag");
+#[cfg(feature = "scene-antialiasing")]
+const SRC_FRAG_AA_FXAA: &[u8] = include_bytes!("../assets/shaders/aa-fxaa.frag");
+#[cfg(feature = "scene-antialiasing")]
+const SRC_FRAG_AA_TAA: &[u8] = include_bytes!("../assets/shaders/aa-taa.frag");
+#[cfg(feature = "scene-bindless")]
+const SRC_FRAG_BINDLESS_TILES: &[u8] = include_bytes!("../assets/shaders/bindless-tiles.frag");
+#[cfg(feature = "scene-culling")]
+const SRC_COMP_CULL: &[u8] = include_bytes!("../assets/shaders/cull.comp");
+#[cfg(any(
+    feature = "scene-culling",
+    feature = "scene-spatial",
+    feature = "scene-splines",
+    feature = "scene-voronoi"
+))]
+const SRC_VERT_CULL: &[u8] = include_bytes!("../assets/shaders/cull.vert");
+#[cfg(feature = "scene-amplification")]
+const SRC_VERT_AMPLIFY_CPU: &[u8] = include_bytes!("../assets/shaders/amplify-cpu.vert");
+#[cfg(feature = "scene-amplification")]
+const SRC_VERT_AMPLIFY_INSTANCED: &[u8] =
+    include_bytes!("../assets/shaders/amplify-instanced.vert");
+#[cfg(feature = "scene-amplification")]
+const SRC_VERT_AMPLIFY_POINTS: &[u8] = include_bytes!("../assets/shaders/amplify-points.vert");
+#[cfg(feature = "scene-amplification")]
+const SRC_GEOM_AMPLIFY: &[u8] = include_bytes!("../assets/shaders/amplify.geom");
+#[cfg(feature = "scene-raymarching")]
+const SRC_FRAG_CHECKERBOARD: &[u8] = include_bytes!("../assets/shaders/checkerboard.frag");
 const SRC_FRAG_BLUR: &[u8] = include_bytes!("../assets/shaders/blur.frag");
+#[cfg(feature = "scene-cloth")]
+const SRC_VERT_CLOTH: &[u8] = include_bytes!("../assets/shaders/cloth.vert");
+#[cfg(feature = "scene-cloth")]
+const SRC_FRAG_CLOTH: &[u8] = include_bytes!("../assets/shaders/cloth.frag");
+#[cfg(feature = "scene-cloth")]
+const SRC_COMP_CLOTH_INTEGRATE: &[u8] = include_bytes!("../assets/shaders/cloth-integrate.comp");
+#[cfg(feature = "scene-cloth")]
+const SRC_VERT_CLOTH_NORMALS: &[u8] = include_bytes!("../assets/shaders/cloth-normals.vert");
+#[cfg(feature = "scene-cloth")]
+const SRC_GEOM_CLOTH_NORMALS: &[u8] = include_bytes!("../assets/shaders/cloth-normals.geom");
+#[cfg(feature = "scene-cloth")]
+const SRC_FRAG_CLOTH_NORMALS: &[u8] = include_bytes!("../assets/shaders/cloth-normals.frag");
+#[cfg(feature = "scene-cloth")]
+const SRC_COMP_CLOTH_RELAX: &[u8] = include_bytes!("../assets/shaders/cloth-relax.comp");
 const SRC_FRAG_DITHER: &[u8] = include_bytes!("../assets/shaders/dither.frag");
-const SRC_FRAG_KAWASE: &[u8] = include_bytes!("../assets/shaders/kawase.frag");
+#[cfg(feature = "scene-gray-scott")]
+const SRC_FRAG_GRAY_SCOTT: &[u8] = include_bytes!("../assets/shaders/gray-scott.frag");
+#[cfg(feature = "scene-gray-scott")]
+const SRC_FRAG_GRAY_SCOTT_VIEW: &[u8] = include_bytes!("../assets/shaders/gray-scott-view.frag");
+#[cfg(feature = "scene-clustered-lighting")]
+const SRC_COMP_LIGHT_BINNING: &[u8] = include_bytes!("../assets/shaders/light-binning.comp");
+#[cfg(feature = "scene-clustered-lighting")]
+const SRC_VERT_LIGHTING: &[u8] = include_bytes!("../assets/shaders/lighting.vert");
+#[cfg(feature = "scene-clustered-lighting")]
+const SRC_FRAG_LIGHTING: &[u8] = include_bytes!("../assets/shaders/lighting.frag");
+const SRC_FRAG_HEATMAP: &[u8] = include_bytes!("../assets/shaders/heatmap.frag");
+#[cfg(feature = "scene-n-body")]
+const SRC_VERT_NBODY: &[u8] = include_bytes!("../assets/shaders/nbody.vert");
+#[cfg(feature = "scene-n-body")]
+const SRC_FRAG_NBODY: &[u8] = include_bytes!("../assets/shaders/nbody.frag");
+#[cfg(feature = "scene-n-body")]
+const SRC_FRAG_NBODY_TONEMAP: &[u8] = include_bytes!("../assets/shaders/nbody-tonemap.frag");
+#[cfg(feature = "scene-spatial")]
+const SRC_FRAG_OUTLINE: &[u8] = include_bytes!("../assets/shaders/outline.frag");
+#[cfg(feature = "scene-particles")]
+const SRC_VERT_PARTICLES: &[u8] = include_bytes!("../assets/shaders/particles.vert");
+#[cfg(feature = "scene-particles")]
+const SRC_FRAG_PARTICLES: &[u8] = include_bytes!("../assets/shaders/particles.frag");
+#[cfg(feature = "scene-particles")]
+const SRC_VERT_PARTICLES_UPDATE: &[u8] = include_bytes!("../assets/shaders/particles-update.vert");
+#[cfg(feature = "scene-physarum")]
+const SRC_COMP_PHYSARUM: &[u8] = include_bytes!("../assets/shaders/physarum.comp");
+#[cfg(feature = "scene-physarum")]
+const SRC_FRAG_PHYSARUM_VIEW: &[u8] = include_bytes!("../assets/shaders/physarum-view.frag");
+#[cfg(feature = "scene-portal")]
+const SRC_VERT_PORTAL: &[u8] = include_bytes!("../assets/shaders/portal.vert");
+#[cfg(feature = "scene-portal")]
+const SRC_FRAG_PORTAL: &[u8] = include_bytes!("../assets/shaders/portal.frag");
+#[cfg(feature = "scene-raymarching")]
+const SRC_FRAG_RAYMARCH: &[u8] = include_bytes!("../assets/shaders/raymarch.frag");
+#[cfg(any(
+    feature = "scene-marching-squares",
+    feature = "scene-splines",
+    feature = "scene-voronoi",
+    feature = "scripting"
+))]
+const SRC_VERT_POLYLINE: &[u8] = include_bytes!("../assets/shaders/polyline.vert");
+#[cfg(feature = "scene-kernel")]
+const SRC_FRAG_KERNEL: &[u8] = include_bytes!("../assets/shaders/kernel.frag");
+#[cfg(feature = "scene-chroma-key")]
+const SRC_FRAG_CHROMA_KEY: &[u8] = include_bytes!("../assets/shaders/chroma-key.frag");
+#[cfg(feature = "scene-mips")]
+const SRC_FRAG_MIPS: &[u8] = include_bytes!("../assets/shaders/mips.frag");
+#[cfg(feature = "scene-anisotropy")]
+const SRC_VERT_ANISOTROPY: &[u8] = include_bytes!("../assets/shaders/anisotropy.vert");
+#[cfg(feature = "scene-anisotropy")]
+const SRC_FRAG_ANISOTROPY: &[u8] = include_bytes!("../assets/shaders/anisotropy.frag");
+#[cfg(feature = "scene-compression")]
+const SRC_FRAG_COMPRESSION: &[u8] = include_bytes!("../assets/shaders/compression.frag");
+#[cfg(feature = "scene-hdr-test")]
+const SRC_FRAG_HDR_TEST: &[u8] = include_bytes!("../assets/shaders/hdr-test.frag");
+#[cfg(feature = "scene-marching-squares")]
+const SRC_FRAG_MARCHING_SQUARES: &[u8] = include_bytes!("../assets/shaders/marching-squares.frag");
+#[cfg(any(
+    feature = "scene-marching-squares",
+    feature = "scene-splines",
+    feature = "scene-voronoi",
+    feature = "scripting"
+))]
+const SRC_FRAG_POLYLINE: &[u8] = include_bytes!("../assets/shaders/polyline.frag");
 const SRC_VERT_QUAD: &[u8] = include_bytes!("../assets/shaders/quad.vert");
+#[cfg(feature = "scene-soft-body")]
+const SRC_VERT_SOFT_BODY: &[u8] = include_bytes!("../assets/shaders/soft-body.vert");
+#[cfg(feature = "scene-soft-body")]
+const SRC_FRAG_SOFT_BODY: &[u8] = include_bytes!("../assets/shaders/soft-body.frag");
+#[cfg(feature = "scene-soft-body")]
+const SRC_FRAG_SOFT_BODY_SURFACE: &[u8] =
+    include_bytes!("../assets/shaders/soft-body-surface.frag");
 const SRC_VERT_ROUND_RECT: &[u8] = include_bytes!("../assets/shaders/round-rect.vert");
 const SRC_FRAG_ROUND_RECT: &[u8] = include_bytes!("../assets/shaders/round-rect.frag");
+const SRC_VERT_ROUND_SHADOW: &[u8] = include_bytes!("../assets/shaders/round-rect-shadow.vert");
+const SRC_FRAG_ROUND_SHADOW: &[u8] = include_bytes!("../assets/shaders/round-rect-shadow.frag");
+#[cfg(any(
+    feature = "scene-amplification",
+    feature = "scene-culling",
+    feature = "scene-spatial",
+    feature = "scene-splines",
+    feature = "scene-voronoi"
+))]
+const SRC_FRAG_SPRITE: &[u8] = include_bytes!("../assets/shaders/sprite.frag");
 const SRC_VERT_SCREEN: &[u8] = include_bytes!("../assets/shaders/screen.vert");
+#[cfg(feature = "scene-sun")]
+const SRC_FRAG_SUN: &[u8] = include_bytes!("../assets/shaders/sun.frag");
+#[cfg(feature = "scene-sun")]
+const SRC_FRAG_SUN_ADAPT: &[u8] = include_bytes!("../assets/shaders/sun-adapt.frag");
+#[cfg(feature = "scene-sun")]
+const SRC_FRAG_SUN_BRIGHT: &[u8] = include_bytes!("../assets/shaders/sun-bright.frag");
+#[cfg(feature = "scene-sun")]
+const SRC_FRAG_SUN_FLARE: &[u8] = include_bytes!("../assets/shaders/sun-flare.frag");
+#[cfg(feature = "scene-sun")]
+const SRC_FRAG_SUN_TONEMAP: &[u8] = include_bytes!("../assets/shaders/sun-tonemap.frag");
+#[cfg(feature = "scene-overlap")]
+const SRC_VERT_OVERLAP_QUAD: &[u8] = include_bytes!("../assets/shaders/overlap-quad.vert");
+#[cfg(feature = "scene-overlap")]
+const SRC_FRAG_OVERLAP_QUAD: &[u8] = include_bytes!("../assets/shaders/overlap-quad.frag");
+#[cfg(feature = "scene-overlap")]
+const SRC_COMP_OVERLAP_STEP: &[u8] = include_bytes!("../assets/shaders/overlap-step.comp");
+#[cfg(feature = "scene-variable-rate")]
+const SRC_FRAG_VRS_SHADE: &[u8] = include_bytes!("../assets/shaders/vrs-shade.frag");
+#[cfg(feature = "scene-variable-rate")]
+const SRC_FRAG_VRS_COMPOSITE: &[u8] = include_bytes!("../assets/shaders/vrs-composite.frag");
+#[cfg(feature = "scene-virtual-texture")]
+const SRC_VERT_VIRTUAL_TEXTURE: &[u8] = include_bytes!("../assets/shaders/virtual-texture.vert");
+#[cfg(feature = "scene-virtual-texture")]
+const SRC_FRAG_VIRTUAL_TEXTURE: &[u8] = include_bytes!("../assets/shaders/virtual-texture.frag");
+#[cfg(feature = "scene-voronoi")]
+const SRC_FRAG_VORONOI: &[u8] = include_bytes!("../assets/shaders/voronoi.frag");
+#[cfg(feature = "scene-bindless")]
+const SRC_VERT_TILES: &[u8] = include_bytes!("../assets/shaders/tiles.vert");
 const SRC_FRAG_TEXTURE: &[u8] = include_bytes!("../assets/shaders/texture.frag");
 
-// images
-const GURA_JPG: &[u8] = include_bytes!("../assets/gura.jpg");
-// const BIG_SQUARES_PNG: &[u8] = include_bytes!("../../assets/big-squares.png");
+/// Offset for the dithering noise, so that different seeds dither differently.
+/// Seed 0 gives the original pattern.
+fn dither_seed(seed: u64) -> f32 {
+    (seed % 10_000) as f32 * 0.01
+}
+
+/// Everything a scene may need when it gets created.
+#[derive(Clone, Copy)]
+pub struct SceneContext<'a> {
+    /// Size of the surface the scene renders to.
+    pub size: PhysicalSize<u32>,
+    pub input: &'a InputImage,
+    /// Table shown by the data mode of the round quads, if one was loaded.
+    pub data: Option<&'a DataGrid>,
+    /// Seed for everything random in the scene, so that runs are reproducible.
+    pub seed: u64,
+    /// For scenes labeling what they draw, which go without their labels if
+    /// it couldn't be created.
+    pub text: Option<&'a SharedText>,
+}
 
-pub enum Scenes {
-    RoundQuads(RoundQuadsScene),
-    Blurring(BlurringScene),
-    Kawase(KawaseScene),
+/// What happens to the scene from the window, besides keys.
+#[derive(Debug, Clone, Copy)]
+pub enum SceneEvent {
+    /// A mouse button, with the pointer at `mouse_pos` in the scene.
+    MouseInput {
+        state: ElementState,
+        button: MouseButton,
+        mouse_pos: Vec2,
+    },
+}
+
+/// What every scene does, called through [`Scenes`]. Only drawing and
+/// resizing are required, the rest does nothing by default. Scenes are
+/// created by the constructor they're listed with in [`REGISTRY`], each
+/// taking what it needs from the [`SceneContext`].
+pub trait Scene {
+    /// GL state the scene draws with, see [`crate::render_state`].
+    fn render_state(&self) -> RenderState;
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError>;
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError>;
+
+    /// Advances animations by a fixed step.
+    fn update(&mut self, _dt: f32) {}
+
+    fn on_key(&mut self, _keycode: Key<SmolStr>) {}
+
+    /// Returns whether the scene handled the event, in which case the camera
+    /// shouldn't react to it.
+    fn on_event(&mut self, _camera: &Camera, _event: &SceneEvent) -> bool {
+        false
+    }
+
+    /// Shifts what the scene draws by `parallax` per unit of height, for one
+    /// eye in stereo. Scenes without height ignore it.
+    fn set_parallax(&mut self, _parallax: Vec2) {}
+
+    /// Shows a new frame of a live input in place, returning whether it
+    /// could, or whether the scene needs recreating for it, as when its size
+    /// changed.
+    fn stream_input(&mut self, _input: &InputImage) -> Result<bool, GlError> {
+        Ok(true)
+    }
+
+    /// Image the scene made to replace the input image, if any.
+    fn take_input(&mut self) -> Option<RgbaImage> {
+        None
+    }
+
+    /// Text to show next to the mouse, about what is under it.
+    fn tooltip(&self) -> Option<String> {
+        None
+    }
+
+    /// Text to show in a corner of the overlay, about what the scene is
+    /// doing, like statistics.
+    fn status(&self) -> Option<String> {
+        None
+    }
+
+    fn params(&self) -> Option<&Params> {
+        None
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        None
+    }
+
+    /// What went wrong, if this stands in for a scene that did.
+    fn error(&self) -> Option<&PlaygroundError> {
+        None
+    }
+}
+
+/// A built-in scene, as listed in [`REGISTRY`].
+pub struct SceneEntry {
+    /// Name used on the command line, in sequences and in the locale files.
+    pub name: &'static str,
+    /// Key switching to the scene, if any.
+    pub key: Option<NamedKey>,
+    /// Whether the scene draws the input image, and gets recreated when it
+    /// changes.
+    pub input: bool,
+    pub new: fn(&SceneContext) -> Result<Box<dyn Scene>, PlaygroundError>,
+}
+
+/// Boxes a scene that was just created.
+fn boxed<S: Scene + 'static>(
+    scene: Result<S, impl Into<PlaygroundError>>,
+) -> Result<Box<dyn Scene>, PlaygroundError> {
+    Ok(Box::new(scene.map_err(Into::into)?))
+}
+
+/// Every built-in scene that was compiled in, in the order Page Up and Page
+/// Down go through them. A new scene only needs an entry here.
+pub const REGISTRY: &[SceneEntry] = &[
+    SceneEntry {
+        name: "round-quads",
+        key: Some(NamedKey::F1),
+        input: true,
+        new: |ctx| {
+            boxed(RoundQuadsScene::new(
+                ctx.size, ctx.input, ctx.data, ctx.seed,
+            ))
+        },
+    },
+    SceneEntry {
+        name: "blurring",
+        key: Some(NamedKey::F2),
+        input: true,
+        new: |ctx| boxed(BlurringScene::new(ctx.size, ctx.input, ctx.seed)),
+    },
+    SceneEntry {
+        name: "kawase",
+        key: Some(NamedKey::F3),
+        input: true,
+        new: |ctx| boxed(KawaseScene::new(ctx.size, ctx.input, ctx.seed)),
+    },
+    #[cfg(feature = "scene-particles")]
+    SceneEntry {
+        name: "particles",
+        key: Some(NamedKey::F4),
+        input: false,
+        new: |ctx| boxed(ParticlesScene::new(ctx.size, ctx.seed)),
+    },
+    #[cfg(feature = "scene-amplification")]
+    SceneEntry {
+        name: "amplification",
+        key: Some(NamedKey::F7),
+        input: false,
+        new: |ctx| boxed(AmplificationScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-culling")]
+    SceneEntry {
+        name: "culling",
+        key: Some(NamedKey::F8),
+        input: false,
+        new: |ctx| boxed(CullingScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-bindless")]
+    SceneEntry {
+        name: "bindless",
+        key: None,
+        input: false,
+        new: |ctx| boxed(BindlessScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-virtual-texture")]
+    SceneEntry {
+        name: "virtual-texture",
+        key: None,
+        input: false,
+        new: |ctx| boxed(VirtualTextureScene::new(ctx.size)),
+    },
+    #[cfg(feature = "scene-clustered-lighting")]
+    SceneEntry {
+        name: "clustered-lighting",
+        key: None,
+        input: false,
+        new: |ctx| boxed(ClusteredLightingScene::new(ctx.size, ctx.seed)),
+    },
+    #[cfg(feature = "scene-ecs-quads")]
+    SceneEntry {
+        name: "ecs-quads",
+        key: None,
+        input: false,
+        new: |ctx| boxed(EcsQuadsScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-spatial")]
+    SceneEntry {
+        name: "spatial",
+        key: None,
+        input: false,
+        new: |ctx| boxed(SpatialScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-splines")]
+    SceneEntry {
+        name: "splines",
+        key: None,
+        input: false,
+        new: |_| boxed(SplinesScene::new()),
+    },
+    #[cfg(feature = "scene-shape-inspector")]
+    SceneEntry {
+        name: "shape-inspector",
+        key: None,
+        input: false,
+        new: |_| boxed(ShapeInspectorScene::new()),
+    },
+    #[cfg(feature = "scene-portal")]
+    SceneEntry {
+        name: "portal",
+        key: None,
+        input: false,
+        new: |ctx| boxed(PortalScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-sun")]
+    SceneEntry {
+        name: "sun",
+        key: None,
+        input: false,
+        new: |ctx| boxed(SunScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-voronoi")]
+    SceneEntry {
+        name: "voronoi",
+        key: None,
+        input: false,
+        new: |ctx| boxed(VoronoiScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-gray-scott")]
+    SceneEntry {
+        name: "gray-scott",
+        key: None,
+        input: false,
+        new: |ctx| boxed(GrayScottScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-physarum")]
+    SceneEntry {
+        name: "physarum",
+        key: None,
+        input: false,
+        new: |ctx| boxed(PhysarumScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-n-body")]
+    SceneEntry {
+        name: "n-body",
+        key: None,
+        input: false,
+        new: |ctx| boxed(NBodyScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-cloth")]
+    SceneEntry {
+        name: "cloth",
+        key: None,
+        input: true,
+        new: |ctx| boxed(ClothScene::new(ctx.input)),
+    },
+    #[cfg(feature = "scene-soft-body")]
+    SceneEntry {
+        name: "soft-body",
+        key: None,
+        input: false,
+        new: |ctx| boxed(SoftBodyScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-marching-squares")]
+    SceneEntry {
+        name: "marching-squares",
+        key: None,
+        input: false,
+        new: |ctx| boxed(MarchingSquaresScene::new(ctx.seed)),
+    },
+    #[cfg(feature = "scene-kernel")]
+    SceneEntry {
+        name: "kernel",
+        key: None,
+        input: true,
+        new: |ctx| boxed(KernelScene::new(ctx.input, ctx.text.cloned())),
+    },
+    #[cfg(feature = "scene-chroma-key")]
+    SceneEntry {
+        name: "chroma-key",
+        key: None,
+        input: true,
+        new: |ctx| boxed(ChromaKeyScene::new(ctx.input, ctx.text.cloned())),
+    },
+    #[cfg(feature = "scene-mips")]
+    SceneEntry {
+        name: "mips",
+        key: None,
+        input: true,
+        new: |ctx| boxed(MipsScene::new(ctx.input, ctx.text.cloned())),
+    },
+    #[cfg(feature = "scene-anisotropy")]
+    SceneEntry {
+        name: "anisotropy",
+        key: None,
+        input: false,
+        new: |ctx| boxed(AnisotropyScene::new(ctx.text.cloned())),
+    },
+    #[cfg(feature = "scene-compression")]
+    SceneEntry {
+        name: "compression",
+        key: None,
+        input: true,
+        new: |ctx| boxed(CompressionScene::new(ctx.input, ctx.text.cloned())),
+    },
+    #[cfg(feature = "scene-hdr-test")]
+    SceneEntry {
+        name: "hdr-test",
+        key: None,
+        input: false,
+        new: |ctx| boxed(HdrTestScene::new(ctx.text.cloned())),
+    },
+    #[cfg(feature = "scene-antialiasing")]
+    SceneEntry {
+        name: "antialiasing",
+        key: None,
+        input: false,
+        new: |ctx| boxed(AntialiasingScene::new(ctx.size, ctx.text.cloned())),
+    },
+    #[cfg(feature = "scene-raymarching")]
+    SceneEntry {
+        name: "raymarching",
+        key: None,
+        input: false,
+        new: |ctx| boxed(RaymarchingScene::new(ctx.size, ctx.text.cloned())),
+    },
+    #[cfg(feature = "scene-variable-rate")]
+    SceneEntry {
+        name: "variable-rate",
+        key: None,
+        input: false,
+        new: |ctx| boxed(VariableRateScene::new(ctx.size, ctx.text.cloned())),
+    },
+    #[cfg(feature = "scene-overlap")]
+    SceneEntry {
+        name: "overlap",
+        key: None,
+        input: false,
+        new: |ctx| boxed(OverlapScene::new(ctx.text.cloned())),
+    },
+];
+
+/// The current scene, which may be a plugin's or the error screen standing
+/// in for one that failed.
+pub struct Scenes {
+    name: &'static str,
+    scene: Box<dyn Scene>,
 }
 
 impl Scenes {
-    pub fn new(window: &Window) -> Self {
-        Self::Kawase(KawaseScene::new(window))
+    pub fn new(ctx: &SceneContext) -> Self {
+        Self::from_name("kawase", ctx).unwrap()
+    }
+
+    /// Switches to the scene of `keycode`, returning whether it's one of the
+    /// keys switching scenes.
+    pub fn switch_scene(&mut self, ctx: &SceneContext, keycode: Key<SmolStr>) -> bool {
+        let name = match keycode {
+            Key::Named(NamedKey::PageDown) => self.cycled(1),
+            Key::Named(NamedKey::PageUp) => self.cycled(-1),
+            Key::Named(key) => match REGISTRY.iter().find(|entry| entry.key == Some(key)) {
+                Some(entry) => entry.name,
+                None => return false,
+            },
+            _ => return false,
+        };
+
+        *self = Self::from_name(name, ctx).unwrap();
+        true
+    }
+
+    /// Names of the built-in scenes, in the order of [`REGISTRY`].
+    pub fn builtin_names() -> Vec<&'static str> {
+        REGISTRY.iter().map(|entry| entry.name).collect()
+    }
+
+    /// Names of the built-in scenes followed by those of the plugins.
+    pub fn names() -> Vec<&'static str> {
+        let names = Self::builtin_names();
+        #[cfg(feature = "plugins")]
+        let names = [names, plugins::names()].concat();
+        names
+    }
+
+    /// Name of the scene `offset` places away from the current one in
+    /// [`Self::names`], wrapping around.
+    fn cycled(&self, offset: isize) -> &'static str {
+        let names = Self::names();
+        let index = (names.iter()).position(|&name| name == self.name());
+        let len = names.len() as isize;
+        names[(index.unwrap() as isize + offset).rem_euclid(len) as usize]
+    }
+
+    /// Creates a scene from its name, as used on the command line and in
+    /// sequences. Returns `None` if there is no such scene, and the error
+    /// screen in its place if it couldn't be created.
+    pub fn from_name(name: &str, ctx: &SceneContext) -> Option<Self> {
+        let name = Self::names().into_iter().find(|&known| known == name)?;
+        let scene = match catch_panic(|| Self::create(name, ctx)).and_then(|created| created) {
+            Ok(scene) => scene,
+            Err(e) => Box::new(ErrorScreen::new(name, e)),
+        };
+        Some(Self { name, scene })
+    }
+
+    fn create(name: &str, ctx: &SceneContext) -> Result<Box<dyn Scene>, PlaygroundError> {
+        if let Some(entry) = REGISTRY.iter().find(|entry| entry.name == name) {
+            return (entry.new)(ctx);
+        }
+        #[cfg(feature = "plugins")]
+        if let Some(plugin) = plugins::find(name) {
+            return boxed(PluginScene::new(plugin, ctx).map_err(PlaygroundError::Plugin));
+        }
+        unreachable!("{name} is a known scene name")
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// GL state the scene draws with, see [`crate::render_state`].
+    pub fn render_state(&self) -> RenderState {
+        self.scene.render_state()
+    }
+
+    /// Name of the scene called `name` in the current locale. Plugins name
+    /// their scenes themselves.
+    pub fn title(name: &str) -> String {
+        #[cfg(feature = "plugins")]
+        if let Some(plugin) = plugins::find(name) {
+            return plugin.name.to_string();
+        }
+        tr(&format!("scene.{name}.name"))
     }
 
-    pub fn switch_scene(&mut self, window: &Window, keycode: Key<SmolStr>) {
-        match keycode {
-            Key::Named(NamedKey::F1) => *self = Self::RoundQuads(RoundQuadsScene::new(window)),
-            Key::Named(NamedKey::F2) => *self = Self::Blurring(BlurringScene::new(window)),
-            Key::Named(NamedKey::F3) => *self = Self::Kawase(KawaseScene::new(window)),
-            _ => (),
+    /// Description of the scene called `name` in the current locale.
+    pub fn description(name: &str) -> String {
+        #[cfg(feature = "plugins")]
+        if let Some(plugin) = plugins::find(name) {
+            return plugin.description.clone();
+        }
+        tr(&format!("scene.{name}.description"))
+    }
+
+    /// Recreates the current scene from scratch, e.g. after reseeding.
+    pub fn recreate(&mut self, ctx: &SceneContext) {
+        *self = Self::from_name(self.name(), ctx).unwrap();
+    }
+
+    /// Recreates the current scene if it depends on the input image, keeping
+    /// its parameters.
+    pub fn reload_input(&mut self, ctx: &SceneContext) {
+        let input = (REGISTRY.iter()).any(|entry| entry.name == self.name && entry.input);
+        if !input && self.error().is_none() {
+            return;
+        }
+
+        let params = self.params().cloned();
+        self.recreate(ctx);
+        if let (Some(current), Some(params)) = (self.params_mut(), params) {
+            *current = params;
         }
     }
 
     pub fn on_key(&mut self, keycode: Key<SmolStr>) {
-        match self {
-            Self::RoundQuads(_) => {}
-            Self::Blurring(scene) => scene.on_key(keycode),
-            Self::Kawase(scene) => scene.on_key(keycode),
+        self.scene.on_key(keycode);
+    }
+
+    /// Lets the current scene handle a mouse button. Returns whether it did,
+    /// in which case the camera shouldn't react to it.
+    pub fn on_mouse_input(
+        &mut self,
+        camera: &Camera,
+        state: ElementState,
+        button: MouseButton,
+        mouse_pos: Vec2,
+    ) -> bool {
+        let event = SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        };
+        self.scene.on_event(camera, &event)
+    }
+
+    /// Shifts what the current scene draws by `parallax` per unit of height,
+    /// for one eye in stereo. Scenes without height ignore it.
+    pub fn set_parallax(&mut self, parallax: Vec2) {
+        self.scene.set_parallax(parallax);
+    }
+
+    /// Shows a new frame of a live input, like a webcam, in the current scene.
+    /// The scenes made for input images upload it in place, or get recreated
+    /// when its size changes, and the others keep the image they were created
+    /// with.
+    pub fn stream_input(&mut self, ctx: &SceneContext) {
+        match self.scene.stream_input(ctx.input) {
+            Ok(true) => {}
+            Ok(false) => self.reload_input(ctx),
+            Err(e) => self.fail(e),
         }
     }
 
+    /// Image the current scene made to replace the input image, if any.
+    pub fn take_input(&mut self) -> Option<RgbaImage> {
+        self.scene.take_input()
+    }
+
+    /// Text to show next to the mouse, about what is under it.
+    pub fn tooltip(&self) -> Option<String> {
+        self.scene.tooltip()
+    }
+
+    /// Text to show in a corner of the overlay, about what the scene is doing.
+    pub fn status(&self) -> Option<String> {
+        self.scene.status()
+    }
+
+    /// Parameters of the current scene, if it has any.
+    pub fn params(&self) -> Option<&Params> {
+        self.scene.params()
+    }
+
+    pub fn params_mut(&mut self) -> Option<&mut Params> {
+        self.scene.params_mut()
+    }
+
+    /// Advances the current scene's animations by a fixed step. A scene that
+    /// panics is replaced by the error screen.
+    pub fn update(&mut self, dt: f32) {
+        // updates render too, and may be the first thing a scene does after
+        // another one was drawn
+        unsafe { self.render_state().apply() };
+        if let Err(e) = catch_panic(|| self.scene.update(dt)) {
+            self.fail(e);
+        }
+        unsafe { render_state::reset_debug() };
+    }
+
+    /// Draws the current scene, which gets replaced by the error screen if it
+    /// fails to or panics.
     pub fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) {
-        match self {
-            Self::RoundQuads(scene) => scene.draw(camera, mouse_pos),
-            Self::Blurring(scene) => scene.draw(camera, mouse_pos),
-            Self::Kawase(scene) => scene.draw(camera, mouse_pos),
+        let state = self.render_state();
+        unsafe { state.apply() };
+        let drawn = catch_panic(|| self.scene.draw(camera, mouse_pos))
+            .and_then(|result| result.map_err(PlaygroundError::from));
+        if let Err(e) = drawn {
+            self.fail(e);
+        } else if render_state::is_validating() {
+            unsafe { render_state::check(self.name(), &state) };
         }
+        unsafe { render_state::reset_debug() };
     }
 
     pub fn resize(&mut self, camera: &Camera, width: i32, height: i32) {
-        match self {
-            Self::RoundQuads(scene) => scene.resize(camera, width, height),
-            Self::Blurring(scene) => scene.resize(camera, width, height),
-            Self::Kawase(scene) => scene.resize(camera, width, height),
+        profiling::cpu_zone!("resize");
+
+        let resized = catch_panic(|| self.scene.resize(camera, width, height))
+            .and_then(|result| result.map_err(PlaygroundError::from));
+        if let Err(e) = resized {
+            self.fail(e);
         }
     }
+
+    /// Error the current scene was replaced with, if it couldn't be created
+    /// or drawn, or panicked.
+    pub fn error(&self) -> Option<&PlaygroundError> {
+        self.scene.error()
+    }
+
+    /// Replaces the current scene with the error screen.
+    fn fail(&mut self, error: impl Into<PlaygroundError>) {
+        self.scene = Box::new(ErrorScreen::new(self.name, error.into()));
+    }
 }
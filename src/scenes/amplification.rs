@@ -0,0 +1,393 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::{f32::consts::TAU, mem, time::Instant};
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{Mat4, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_geometry_shader_program, create_shader_program, delete_buffers,
+};
+use crate::error::GlError;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::technique_timings::TechniqueTimings;
+use super::{
+    Scene, SRC_FRAG_SPRITE, SRC_GEOM_AMPLIFY, SRC_VERT_AMPLIFY_CPU, SRC_VERT_AMPLIFY_INSTANCED,
+    SRC_VERT_AMPLIFY_POINTS,
+};
+
+const N_POINTS: usize = 100_000;
+
+/// Side of the square the points are scattered in.
+const AREA_SIZE: f32 = 4000.0;
+
+const TECHNIQUES: &[&str] = &["cpu", "instanced", "geometry"];
+
+/// The same animated points expanded into quads in three ways:
+///
+/// - `cpu`: the 4 corners of every quad are computed on the CPU and uploaded,
+///   like the round quads scene does.
+/// - `instanced`: one instance per point, the vertex shader places the corners
+///   of a 4-vertex strip.
+/// - `geometry`: points go through a geometry shader emitting the strips.
+///
+/// Each technique is timed on the GPU, and on the CPU for the work it needs to
+/// prepare its buffers. Timings are printed every second.
+pub struct AmplificationScene {
+    matrix: Mat4,
+
+    cpu: Technique,
+    instanced: Technique,
+    geometry: Technique,
+    /// Index buffer of the CPU technique, which never changes.
+    ebo: GLuint,
+
+    points: Vec<Point>,
+    orbits: Vec<Orbit>,
+    corners: Vec<[Corner; 4]>,
+
+    timings: TechniqueTimings,
+
+    params: Params,
+    start: Instant,
+}
+
+impl AmplificationScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let orbits = (0..N_POINTS)
+            .map(|_| Orbit {
+                center: Vec2::new(
+                    rng.gen_range(-0.5..0.5) * AREA_SIZE,
+                    rng.gen_range(-0.5..0.5) * AREA_SIZE,
+                ),
+                radius: rng.gen_range(5.0..40.0),
+                speed: rng.gen_range(-2.0..2.0),
+                phase: rng.gen_range(0.0..TAU),
+            })
+            .collect::<Vec<_>>();
+        let points = (0..N_POINTS)
+            .map(|_| Point {
+                center: Vec2::ZERO,
+                size: rng.gen_range(2.0..8.0),
+                color: [
+                    rng.gen_range(0.3..1.0),
+                    rng.gen_range(0.3..1.0),
+                    rng.gen_range(0.3..1.0),
+                    0.8,
+                ],
+            })
+            .collect::<Vec<_>>();
+        let indices = (0..N_POINTS as u32)
+            .map(|i| [0, 1, 2, 2, 1, 3].map(|j| i * 4 + j))
+            .collect::<Vec<_>>();
+
+        unsafe {
+            let cpu = Technique::new(create_shader_program(
+                SRC_VERT_AMPLIFY_CPU,
+                SRC_FRAG_SPRITE,
+            )?);
+            let instanced = Technique::new(create_shader_program(
+                SRC_VERT_AMPLIFY_INSTANCED,
+                SRC_FRAG_SPRITE,
+            )?);
+            let geometry = Technique::new(create_geometry_shader_program(
+                SRC_VERT_AMPLIFY_POINTS,
+                SRC_GEOM_AMPLIFY,
+                SRC_FRAG_SPRITE,
+            )?);
+
+            let size_f32 = mem::size_of::<f32>() as GLsizei;
+
+            let mut ebo = 0;
+            gl::GenBuffers(1, &mut ebo);
+
+            gl::BindVertexArray(cpu.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, cpu.vbo);
+            buffer_data(
+                gl::ELEMENT_ARRAY_BUFFER,
+                ebo,
+                indices.as_slice(),
+                gl::STATIC_DRAW,
+            );
+
+            let size_corner = mem::size_of::<Corner>() as GLsizei;
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, size_corner, 0 as _);
+            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, size_corner, (2 * size_f32) as _);
+            gl::VertexAttribPointer(2, 4, gl::FLOAT, gl::FALSE, size_corner, (4 * size_f32) as _);
+            gl::EnableVertexAttribArray(0);
+            gl::EnableVertexAttribArray(1);
+            gl::EnableVertexAttribArray(2);
+
+            // instancing and the geometry shader read the exact same points,
+            // only the instanced one advances once per instance
+            let size_point = mem::size_of::<Point>() as GLsizei;
+            for (technique, divisor) in [(&instanced, 1), (&geometry, 0)] {
+                gl::BindVertexArray(technique.vao);
+                gl::BindBuffer(gl::ARRAY_BUFFER, technique.vbo);
+
+                gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, size_point, 0 as _);
+                gl::VertexAttribPointer(
+                    1,
+                    1,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    size_point,
+                    (2 * size_f32) as _,
+                );
+                gl::VertexAttribPointer(
+                    2,
+                    4,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    size_point,
+                    (3 * size_f32) as _,
+                );
+                for location in 0..3 {
+                    gl::EnableVertexAttribArray(location);
+                    gl::VertexAttribDivisor(location, divisor);
+                }
+            }
+
+            gl::BindVertexArray(0);
+
+            Ok(Self {
+                matrix: Mat4::default(),
+
+                cpu,
+                instanced,
+                geometry,
+                ebo,
+
+                points,
+                orbits,
+                corners: Vec::with_capacity(N_POINTS),
+
+                timings: TechniqueTimings::new(TECHNIQUES),
+
+                params: Params::new()
+                    .with_enum("technique", 0, TECHNIQUES)
+                    .with_bool("compare", false),
+                start: Instant::now(),
+            })
+        }
+    }
+
+    unsafe fn draw_cpu(&mut self) {
+        self.corners.clear();
+        self.corners.extend(self.points.iter().map(Point::corners));
+
+        let technique = &self.cpu;
+        technique.bind(&self.matrix);
+        buffer_data(
+            gl::ARRAY_BUFFER,
+            technique.vbo,
+            self.corners.as_slice(),
+            gl::STREAM_DRAW,
+        );
+
+        let count = (self.points.len() * 6) as GLsizei;
+        gl::DrawElements(gl::TRIANGLES, count, gl::UNSIGNED_INT, 0 as _);
+    }
+
+    unsafe fn draw_instanced(&mut self) {
+        let technique = &self.instanced;
+        technique.bind(&self.matrix);
+        buffer_data(
+            gl::ARRAY_BUFFER,
+            technique.vbo,
+            self.points.as_slice(),
+            gl::STREAM_DRAW,
+        );
+
+        let count = self.points.len() as GLsizei;
+        gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, count);
+    }
+
+    unsafe fn draw_geometry(&mut self) {
+        let technique = &self.geometry;
+        technique.bind(&self.matrix);
+        buffer_data(
+            gl::ARRAY_BUFFER,
+            technique.vbo,
+            self.points.as_slice(),
+            gl::STREAM_DRAW,
+        );
+
+        gl::DrawArrays(gl::POINTS, 0, self.points.len() as GLsizei);
+    }
+}
+
+impl Scene for AmplificationScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let (name, steps) = match keycode.as_ref() {
+            Key::Character("t") => ("technique", 1),
+            Key::Character("T") => ("technique", -1),
+            Key::Character("c") => ("compare", 1),
+            _ => return,
+        };
+
+        self.params.step(name, steps).unwrap();
+        println!("amplification config: {}", self.params);
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("amplification draw");
+
+        let time = self.start.elapsed().as_secs_f32();
+        for (point, orbit) in self.points.iter_mut().zip(&self.orbits) {
+            point.center = orbit.position(time);
+        }
+
+        let current = self.params.variant("technique");
+        // the current technique goes last so that it is the one on screen
+        let techniques = if self.params.bool("compare") {
+            [(current + 1) % 3, (current + 2) % 3, current].to_vec()
+        } else {
+            vec![current]
+        };
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            for technique in techniques {
+                theme::clear();
+
+                self.timings.begin(technique);
+                match technique {
+                    0 => self.draw_cpu(),
+                    1 => self.draw_instanced(),
+                    _ => self.draw_geometry(),
+                }
+                self.timings.end();
+            }
+
+            if let Some(summary) = self.timings.end_frame() {
+                println!("amplification: {summary}");
+            }
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.matrix = camera.matrix(Vec2::new(width as f32, height as f32));
+        Ok(())
+    }
+}
+
+impl Drop for AmplificationScene {
+    fn drop(&mut self) {
+        unsafe { delete_buffers(&[self.ebo]) };
+    }
+}
+
+/// A shader with its own vertex array and buffer.
+struct Technique {
+    shader: GLuint,
+    u_mvp: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+}
+
+impl Technique {
+    unsafe fn new(shader: GLuint) -> Self {
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        let mut vbo = 0;
+        gl::GenBuffers(1, &mut vbo);
+
+        Self {
+            shader,
+            u_mvp: gl::GetUniformLocation(shader, c"u_mvp".as_ptr()),
+            vao,
+            vbo,
+        }
+    }
+
+    unsafe fn bind(&self, matrix: &Mat4) {
+        gl::UseProgram(self.shader);
+        gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, matrix.as_ref().as_ptr());
+        gl::BindVertexArray(self.vao);
+    }
+}
+
+impl Drop for Technique {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Point {
+    center: Vec2,
+    size: f32,
+    color: [f32; 4],
+}
+
+impl Point {
+    /// The 4 corners of the quad covering the point, in triangle strip order.
+    fn corners(&self) -> [Corner; 4] {
+        [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].map(|(x, y)| {
+            let uv = Vec2::new(x, y);
+            Corner {
+                position: self.center + uv * self.size,
+                uv,
+                color: self.color,
+            }
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Corner {
+    position: Vec2,
+    uv: Vec2,
+    color: [f32; 4],
+}
+
+/// Circular motion of a point.
+#[derive(Debug, Clone, Copy)]
+struct Orbit {
+    center: Vec2,
+    radius: f32,
+    /// Angular speed in radians per second.
+    speed: f32,
+    phase: f32,
+}
+
+impl Orbit {
+    fn position(&self, time: f32) -> Vec2 {
+        self.center + Vec2::from_angle(self.phase + time * self.speed) * self.radius
+    }
+}
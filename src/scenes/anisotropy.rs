@@ -0,0 +1,328 @@
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{vec2, vec3, Mat4, Vec2};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_shader_program, delete_buffers, delete_textures, max_anisotropy,
+    upload_texture,
+};
+use crate::error::GlError;
+use crate::gl_ext;
+use crate::gpu_timer::GpuTimer;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::{Scene, SRC_FRAG_ANISOTROPY, SRC_VERT_ANISOTROPY};
+
+/// Variants of the `anisotropy` parameter, in order, with the GPU timer zone
+/// of each, as zones are named statically.
+const LEVELS: &[&str] = &["1", "2", "4", "8", "16"];
+const ZONES: &[&str] = &[
+    "anisotropy 1x",
+    "anisotropy 2x",
+    "anisotropy 4x",
+    "anisotropy 8x",
+    "anisotropy 16x",
+];
+
+/// Half the width and the length of the ground, in world units.
+const FAR: f32 = 4000.0;
+/// Side of the checkerboard texture and of its squares, in pixels.
+const CHECKER_SIZE: u32 = 512;
+const SQUARE_SIZE: u32 = 64;
+/// Frames spent on each level when sweeping, enough for the timer queries of
+/// the previous one to resolve.
+const SWEEP_FRAMES: u32 = 30;
+
+const TEXT_SIZE: f32 = 14.0;
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+
+/// A checkerboard ground stretching to the horizon under a perspective
+/// camera, the worst case for isotropic filtering: squares far away cover
+/// many texels in depth but few across, so trilinear filtering picks a level
+/// blurry enough for the former and smears them into gray.
+///
+/// Anisotropic filtering takes more samples along the stretched axis, and
+/// its cost is timed for every level, the ground being drawn several times
+/// over to make it measurable. Panning the camera slides the ground.
+pub struct AnisotropyScene {
+    viewport: Vec2,
+    /// Ground offset from the camera, in texture repetitions.
+    offset: Vec2,
+    /// How far the ground has scrolled on its own.
+    scroll: f32,
+    /// Highest anisotropy supported, 1 if it isn't.
+    max_anisotropy: f32,
+    /// Frames drawn since the last step of the sweep.
+    sweep_frames: u32,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_tile: GLint,
+    u_offset: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    checker: GLuint,
+    gpu_timer: GpuTimer,
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+impl AnisotropyScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    #[allow(clippy::new_without_default)]
+    pub fn new(text: Option<SharedText>) -> Result<Self, GlError> {
+        let vertices = [
+            vec2(-FAR, 0.0),
+            vec2(FAR, 0.0),
+            vec2(FAR, -FAR),
+            vec2(-FAR, 0.0),
+            vec2(FAR, -FAR),
+            vec2(-FAR, -FAR),
+        ];
+
+        let checker = (0..CHECKER_SIZE)
+            .flat_map(|y| {
+                (0..CHECKER_SIZE).flat_map(move |x| {
+                    let light = (x / SQUARE_SIZE + y / SQUARE_SIZE).is_multiple_of(2);
+                    let value = if light { 235 } else { 30 };
+                    [value, value, value, 255]
+                })
+            })
+            .collect::<Vec<u8>>();
+
+        unsafe {
+            let mut texture = 0;
+            gl::GenTextures(1, &mut texture);
+            upload_texture(
+                texture,
+                CHECKER_SIZE,
+                CHECKER_SIZE,
+                checker.as_ptr(),
+                gl::REPEAT,
+            )?;
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as GLint,
+            );
+
+            let shader = create_shader_program(SRC_VERT_ANISOTROPY, SRC_FRAG_ANISOTROPY)?;
+            let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(shader, name.as_ptr());
+            gl::UseProgram(shader);
+            gl::Uniform1i(uniform(c"u_checker"), 0);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            let location = gl::GetAttribLocation(shader, c"position".as_ptr()) as GLuint;
+            gl::VertexAttribPointer(
+                location,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vec2>() as GLsizei,
+                0 as _,
+            );
+            gl::EnableVertexAttribArray(location);
+            gl::BindVertexArray(0);
+
+            let max_anisotropy = max_anisotropy();
+            if max_anisotropy <= 1.0 {
+                println!("anisotropy: anisotropic filtering unsupported, every level is 1x");
+            }
+
+            let params = Params::new()
+                .with_enum("anisotropy", 0, LEVELS)
+                .with_bool("sweep", false)
+                .with_i32("layers", 8, 1, 64)
+                .with_f32("height", 20.0, 2.0, 200.0, 2.0)
+                .with_f32("pitch", 8.0, 0.0, 60.0, 1.0)
+                .with_f32("tile", 40.0, 5.0, 200.0, 5.0)
+                .with_f32("speed", 0.5, 0.0, 4.0, 0.25);
+
+            Ok(Self {
+                viewport: Vec2::ONE,
+                offset: Vec2::ZERO,
+                scroll: 0.0,
+                max_anisotropy,
+                sweep_frames: 0,
+
+                u_mvp: uniform(c"u_mvp"),
+                u_tile: uniform(c"u_tile"),
+                u_offset: uniform(c"u_offset"),
+                shader,
+                vao,
+                vbo,
+                checker: texture,
+                gpu_timer: GpuTimer::new(),
+
+                text,
+
+                params,
+            })
+        }
+    }
+
+    /// The anisotropy of a level, as far as it is supported.
+    fn anisotropy(&self, level: usize) -> f32 {
+        let requested = LEVELS[level].parse::<f32>().unwrap();
+        requested.min(self.max_anisotropy)
+    }
+
+    fn view_projection(&self) -> Mat4 {
+        let aspect = self.viewport.x / self.viewport.y;
+        let projection = Mat4::perspective_rh_gl(60f32.to_radians(), aspect, 0.5, FAR * 2.0);
+        let pitch = self.params.f32("pitch").to_radians();
+        let eye = vec3(0.0, self.params.f32("height"), 0.0);
+        projection * Mat4::from_rotation_x(pitch) * Mat4::from_translation(-eye)
+    }
+}
+
+impl Scene for AnisotropyScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let (name, direction) = match keycode.as_ref() {
+            Key::Character("a") => ("anisotropy", 1),
+            Key::Character("A") => ("anisotropy", -1),
+            Key::Character("l") => ("layers", 1),
+            Key::Character("L") => ("layers", -1),
+            Key::Character("h") => ("height", 1),
+            Key::Character("H") => ("height", -1),
+            Key::Character("p") => ("pitch", 1),
+            Key::Character("P") => ("pitch", -1),
+            Key::Character("t") => ("tile", 1),
+            Key::Character("T") => ("tile", -1),
+            Key::Character("v") => ("speed", 1),
+            Key::Character("V") => ("speed", -1),
+            Key::Character("s" | "S") => {
+                self.params.toggle("sweep").unwrap();
+                println!("anisotropy config: {}", self.params);
+                return;
+            }
+            _ => return,
+        };
+        self.params.step(name, direction).unwrap();
+
+        println!("anisotropy config: {}", self.params);
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.scroll += dt * self.params.f32("speed");
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("anisotropy draw");
+
+        if self.params.bool("sweep") {
+            self.sweep_frames += 1;
+            if self.sweep_frames >= SWEEP_FRAMES {
+                self.sweep_frames = 0;
+                self.params.step("anisotropy", 1).unwrap();
+            }
+        }
+
+        let level = self.params.variant("anisotropy");
+        let mvp = self.view_projection();
+        let offset = self.offset + vec2(0.0, -self.scroll);
+        let theme = theme::current();
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.checker);
+            if self.max_anisotropy > 1.0 {
+                let anisotropy = self.anisotropy(level);
+                gl::TexParameterf(gl::TEXTURE_2D, gl_ext::TEXTURE_MAX_ANISOTROPY, anisotropy);
+            }
+
+            gl::UseProgram(self.shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, mvp.as_ref().as_ptr());
+            gl::Uniform1f(self.u_tile, self.params.f32("tile"));
+            gl::Uniform2f(self.u_offset, offset.x, offset.y);
+            gl::BindVertexArray(self.vao);
+            self.gpu_timer.begin(ZONES[level]);
+            for _ in 0..self.params.i32("layers") {
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            }
+            self.gpu_timer.end();
+            gl::BindVertexArray(0);
+            self.gpu_timer.poll();
+
+            // GPU time of every level measured so far
+            let layers = self.params.i32("layers");
+            let mut lines = vec![format!("{layers} layers, max {}x", self.max_anisotropy)];
+            for (i, zone) in ZONES.iter().enumerate() {
+                let time = match self.gpu_timer.duration(zone) {
+                    Some(ms) => format!("{ms:.3} ms"),
+                    None => "?".to_string(),
+                };
+                let marker = if i == level { ">" } else { " " };
+                let anisotropy = self.anisotropy(i);
+                lines.push(format!("{marker} {:>2}x  {time}", anisotropy));
+            }
+            let table = lines.join("\n");
+
+            let Some(text) = &self.text else {
+                return Ok(());
+            };
+            let mut text = text.borrow_mut();
+            let extent = text.measure(&table, TEXT_SIZE, None);
+            let min = Vec2::splat(MARGIN);
+            let max = min + extent + PADDING * 2.0;
+            text.queue_rect(min, max, theme.hud_background);
+            let position = min + PADDING;
+            text.queue(&table, position, TEXT_SIZE, None, theme.hud_text);
+            text.draw(0, self.viewport);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        // dragging the camera drags the ground under it
+        self.offset = camera.position / self.params.f32("tile");
+        Ok(())
+    }
+}
+
+impl Drop for AnisotropyScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+            delete_textures(&[self.checker]);
+        }
+    }
+}
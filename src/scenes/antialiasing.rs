@@ -0,0 +1,627 @@
+use std::f32::consts::TAU;
+use std::mem;
+
+use gl::types::{GLenum, GLsizei, GLuint};
+use glam::{vec2, Mat4, UVec2, Vec2};
+use winit::dpi::PhysicalSize;
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    bind_framebuffer, bind_texture, create_float_framebuffer, create_framebuffer,
+    delete_framebuffer, set_viewport, Attrib, Buffer, DebugGroup, Framebuffer, Program, Uniform,
+    VertexArray,
+};
+use crate::error::GlError;
+use crate::gpu_memory::{self, Category, Object};
+use crate::params::Params;
+use crate::profiling;
+use crate::render_scale::jitter;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::technique_timings::TechniqueTimings;
+use super::{
+    Scene, SRC_FRAG_AA_FXAA, SRC_FRAG_AA_GEOMETRY, SRC_FRAG_AA_TAA, SRC_VERT_AA_GEOMETRY,
+    SRC_VERT_SCREEN,
+};
+
+/// Variants of the `mode` parameter, in order, which are also the names of
+/// their timings.
+const MODES: &[&str] = &["none", "fxaa", "msaa", "taa"];
+const SAMPLES: &[&str] = &["2", "4", "8"];
+
+/// Spokes of the wheel, and how far they reach in world units.
+const SPOKES: usize = 48;
+const RADIUS: f32 = 300.0;
+/// Turns per second of the wheel at speed 1.
+const SPIN: f32 = 0.05;
+/// Thin bars sliding past the wheel, how long and wide they are, and how
+/// fast they go at speed 1 in world units per second.
+const BARS: usize = 8;
+const BAR_LENGTH: f32 = 240.0;
+const BAR_WIDTH: f32 = 2.5;
+const SLIDE: f32 = 120.0;
+/// Width of the band the bars wrap around in.
+const LANE: f32 = 1600.0;
+
+const VERTICES: usize = SPOKES * 3 + BARS * 6;
+
+const TEXT_SIZE: f32 = 14.0;
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+
+const GEOMETRY_LAYOUT: &[Attrib] = &[
+    Attrib {
+        name: c"position",
+        components: 2,
+        offset: 0,
+    },
+    Attrib {
+        name: c"previous",
+        components: 2,
+        offset: mem::size_of::<Vec2>(),
+    },
+    Attrib {
+        name: c"color",
+        components: 4,
+        offset: mem::size_of::<Vec2>() * 2,
+    },
+];
+
+const SCREEN_LAYOUT: &[Attrib] = &[
+    Attrib {
+        name: c"position",
+        components: 2,
+        offset: 0,
+    },
+    Attrib {
+        name: c"uv",
+        components: 2,
+        offset: mem::size_of::<Vec2>(),
+    },
+];
+
+/// Vertex of the animated shapes, with where it was the frame before.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct Vertex {
+    position: Vec2,
+    previous: Vec2,
+    color: [f32; 4],
+}
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ScreenVertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+impl ScreenVertex {
+    const fn new(position: Vec2, uv: Vec2) -> Self {
+        Self { position, uv }
+    }
+}
+
+#[rustfmt::skip]
+const SCREEN_VERTICES: &[ScreenVertex] = &[
+    ScreenVertex::new(vec2(-1.0,  1.0), vec2(0.0, 1.0)),
+    ScreenVertex::new(vec2(-1.0, -1.0), vec2(0.0, 0.0)),
+    ScreenVertex::new(vec2( 1.0, -1.0), vec2(1.0, 0.0)),
+    ScreenVertex::new(vec2(-1.0,  1.0), vec2(0.0, 1.0)),
+    ScreenVertex::new(vec2( 1.0, -1.0), vec2(1.0, 0.0)),
+    ScreenVertex::new(vec2( 1.0,  1.0), vec2(1.0, 1.0)),
+];
+
+/// A spinning wheel of thin spokes and thin bars sliding past it, edges that
+/// crawl and shimmer when aliased, to compare ways of antialiasing them.
+///
+/// FXAA blurs along the edges it finds in the finished frame, MSAA resolves
+/// several coverage samples per pixel, and TAA jitters the projection by a
+/// different fraction of a pixel every frame and blends each frame into the
+/// history of the previous ones. The history is followed back through a
+/// velocity buffer the shapes write next to their color, from where they and
+/// the camera were the frame before, and clamped to the colors around each
+/// pixel so that what moved doesn't leave trails. Without reprojection, the
+/// moving spokes smear.
+pub struct AntialiasingScene {
+    viewport: Vec2,
+    time: f32,
+    /// Time and view projection of the last frame drawn, which the velocity
+    /// is measured from.
+    last: Option<(f32, Mat4)>,
+    /// Frames drawn with TAA, which pick the jitter.
+    frame: u32,
+    /// Whether the history holds a frame of this size and mode.
+    history_valid: bool,
+    max_samples: i32,
+
+    geometry_shader: Program,
+    u_view_projection: Uniform,
+    u_previous_view_projection: Uniform,
+    u_jitter: Uniform,
+    geometry_vao: VertexArray,
+    geometry_vbo: Buffer<Vertex>,
+
+    fxaa_shader: Program,
+    taa_shader: Program,
+    u_feedback: Uniform,
+    u_reproject: Uniform,
+    u_reset: Uniform,
+    screen_vao: VertexArray,
+    _screen_vbo: Buffer<ScreenVertex>,
+
+    targets: Option<Targets>,
+    timings: TechniqueTimings,
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+impl AntialiasingScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(size: PhysicalSize<u32>, text: Option<SharedText>) -> Result<Self, GlError> {
+        let geometry_shader = Program::new(SRC_VERT_AA_GEOMETRY, SRC_FRAG_AA_GEOMETRY)?;
+        let geometry_vbo = Buffer::new(
+            gl::ARRAY_BUFFER,
+            &[Vertex::default(); VERTICES],
+            gl::DYNAMIC_DRAW,
+        );
+        let mut geometry_vao = VertexArray::new();
+        geometry_vao.set_layout(&geometry_shader, &geometry_vbo, GEOMETRY_LAYOUT);
+
+        let fxaa_shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_AA_FXAA)?;
+        fxaa_shader.set(fxaa_shader.uniform(c"u_image"), 0);
+        let taa_shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_AA_TAA)?;
+        for (name, unit) in [(c"u_current", 0), (c"u_velocity", 1), (c"u_history", 2)] {
+            taa_shader.set(taa_shader.uniform(name), unit);
+        }
+        let screen_vbo = Buffer::new(gl::ARRAY_BUFFER, SCREEN_VERTICES, gl::STATIC_DRAW);
+        let mut screen_vao = VertexArray::new();
+        // both share screen.vert, so their attributes are at the same
+        // locations
+        screen_vao.set_layout(&fxaa_shader, &screen_vbo, SCREEN_LAYOUT);
+
+        let mut max_samples = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_SAMPLES, &mut max_samples) };
+
+        let params = Params::new()
+            .with_enum("mode", 3, MODES)
+            .with_enum("samples", 1, SAMPLES)
+            .with_f32("feedback", 0.9, 0.5, 0.98, 0.02)
+            .with_bool("reproject", true)
+            .with_bool("jitter", true)
+            .with_f32("speed", 1.0, 0.0, 8.0, 0.5);
+
+        let size = UVec2::new(size.width, size.height).max(UVec2::ONE);
+        let mut scene = Self {
+            viewport: size.as_vec2(),
+            time: 0.0,
+            last: None,
+            frame: 0,
+            history_valid: false,
+            max_samples,
+
+            u_view_projection: geometry_shader.uniform(c"u_view_projection"),
+            u_previous_view_projection: geometry_shader.uniform(c"u_previous_view_projection"),
+            u_jitter: geometry_shader.uniform(c"u_jitter"),
+            geometry_shader,
+            geometry_vao,
+            geometry_vbo,
+
+            u_feedback: taa_shader.uniform(c"u_feedback"),
+            u_reproject: taa_shader.uniform(c"u_reproject"),
+            u_reset: taa_shader.uniform(c"u_reset"),
+            fxaa_shader,
+            taa_shader,
+            screen_vao,
+            _screen_vbo: screen_vbo,
+
+            targets: None,
+            timings: TechniqueTimings::create(MODES),
+
+            text,
+
+            params,
+        };
+        scene.resize_targets(size)?;
+        Ok(scene)
+    }
+
+    /// MSAA samples asked for, as far as they're supported.
+    fn samples(&self) -> i32 {
+        let requested = SAMPLES[self.params.variant("samples")]
+            .parse::<i32>()
+            .unwrap();
+        requested.min(self.max_samples).max(1)
+    }
+
+    /// Recreates the render targets if the size or the sample count changed.
+    fn resize_targets(&mut self, size: UVec2) -> Result<(), GlError> {
+        let samples = self.samples();
+        let current = (self.targets.as_ref()).map(|targets| (targets.size(), targets.samples));
+        if current == Some((size, samples)) {
+            return Ok(());
+        }
+
+        self.targets = None;
+        self.targets = Some(unsafe { Targets::new(size, samples) }?);
+        self.history_valid = false;
+        bind_framebuffer(None);
+        Ok(())
+    }
+
+    /// Writes the shapes as they are now and as they were the last frame.
+    fn upload_geometry(&mut self, previous_time: f32) {
+        let current = shapes(self.time);
+        let previous = shapes(previous_time);
+        let vertices = (current.iter().zip(&previous))
+            .map(|(&(position, color), &(previous, _))| Vertex {
+                position,
+                previous,
+                color,
+            })
+            .collect::<Vec<_>>();
+        self.geometry_vbo.update(&vertices);
+    }
+
+    /// Draws the shapes to the bound framebuffer, moved by `jitter` in clip
+    /// space.
+    fn draw_geometry(&self, view_projection: Mat4, previous: Mat4, jitter: Vec2) {
+        let shader = &self.geometry_shader;
+        shader.set(self.u_view_projection, view_projection);
+        shader.set(self.u_previous_view_projection, previous);
+        shader.set(self.u_jitter, jitter);
+        self.geometry_vao.draw_arrays(gl::TRIANGLES);
+    }
+
+    /// Draws the shapes to the window antialiased with `mode`.
+    fn render(&mut self, mode: &str, view_projection: Mat4, previous: Mat4) {
+        let _group = DebugGroup::push(c"Antialiasing");
+        let targets = self.targets.as_ref().unwrap();
+        let clear = theme::current().clear;
+        set_viewport(targets.size());
+
+        unsafe {
+            match mode {
+                "msaa" => {
+                    targets.clear_msaa(clear);
+                    self.draw_geometry(view_projection, previous, Vec2::ZERO);
+                    targets.blit(targets.msaa_fbo, 0);
+                }
+                "fxaa" => {
+                    targets.clear_scene(clear);
+                    self.draw_geometry(view_projection, previous, Vec2::ZERO);
+                    bind_framebuffer(None);
+                    bind_texture(0, targets.scene.texture);
+                    self.fxaa_shader.bind();
+                    self.screen_vao.draw_arrays(gl::TRIANGLES);
+                }
+                "taa" => {
+                    // a fraction of a pixel, y going up in clip space
+                    let offset = match self.params.bool("jitter") {
+                        true => jitter(self.frame) * vec2(1.0, -1.0) * 2.0 / self.viewport,
+                        false => Vec2::ZERO,
+                    };
+
+                    targets.clear_scene(clear);
+                    self.draw_geometry(view_projection, previous, offset);
+
+                    let [last, next] = &targets.history;
+                    bind_framebuffer(Some(next));
+                    bind_texture(2, last.texture);
+                    bind_texture(1, targets.velocity.texture);
+                    bind_texture(0, targets.scene.texture);
+                    let shader = &self.taa_shader;
+                    shader.set(self.u_feedback, self.params.f32("feedback"));
+                    shader.set(self.u_reproject, self.params.bool("reproject"));
+                    shader.set(self.u_reset, !self.history_valid);
+                    self.screen_vao.draw_arrays(gl::TRIANGLES);
+                    targets.blit(next.fbo, 0);
+                }
+                _ => {
+                    targets.clear_scene(clear);
+                    self.draw_geometry(view_projection, previous, Vec2::ZERO);
+                    targets.blit(targets.scene.fbo, 0);
+                }
+            }
+        }
+
+        if mode == "taa" {
+            self.frame = self.frame.wrapping_add(1);
+            self.targets.as_mut().unwrap().history.swap(0, 1);
+            self.history_valid = true;
+        }
+    }
+
+    fn label(&self) -> String {
+        let mode = self.params.variant("mode");
+        match MODES[mode] {
+            "fxaa" => "FXAA".to_string(),
+            "msaa" => format!("MSAA {}x", self.samples()),
+            "taa" => format!(
+                "TAA, feedback {:.2}, {}, {}",
+                self.params.f32("feedback"),
+                match self.params.bool("reproject") {
+                    true => "reprojected",
+                    false => "not reprojected",
+                },
+                match self.params.bool("jitter") {
+                    true => "jittered",
+                    false => "not jittered",
+                },
+            ),
+            _ => "No antialiasing".to_string(),
+        }
+    }
+}
+
+/// Triangles of the wheel and the bars at `time`, in world units, with their
+/// colors. The same triangles come in the same order at any time, so that
+/// the vertices of two times can be paired.
+fn shapes(time: f32) -> Vec<(Vec2, [f32; 4])> {
+    let mut vertices = Vec::with_capacity(VERTICES);
+
+    let spin = time * SPIN * TAU;
+    let half_width = TAU / SPOKES as f32 / 6.0;
+    for i in 0..SPOKES {
+        let angle = i as f32 / SPOKES as f32 * TAU + spin;
+        let color = match i % 2 {
+            0 => [0.95, 0.95, 0.95, 1.0],
+            _ => [0.95, 0.55, 0.2, 1.0],
+        };
+        vertices.push((Vec2::ZERO, color));
+        vertices.push((Vec2::from_angle(angle - half_width) * RADIUS, color));
+        vertices.push((Vec2::from_angle(angle + half_width) * RADIUS, color));
+    }
+
+    for i in 0..BARS {
+        let x = (time * SLIDE + i as f32 * LANE / BARS as f32).rem_euclid(LANE) - LANE / 2.0;
+        let y = RADIUS + 60.0 + (i % 2) as f32 * 40.0;
+        let center = vec2(x, if i % 4 < 2 { y } else { -y });
+        let along = Vec2::from_angle(0.2 + i as f32 * 0.15) * BAR_LENGTH / 2.0;
+        let across = along.perp().normalize() * BAR_WIDTH / 2.0;
+        let color = [0.3, 0.75, 0.95, 1.0];
+        let [a, b, c, d] = [
+            center - along - across,
+            center + along - across,
+            center + along + across,
+            center - along + across,
+        ];
+        vertices.extend([a, b, c, a, c, d].map(|corner| (corner, color)));
+    }
+    vertices
+}
+
+impl Scene for AntialiasingScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("m") => self.params.step("mode", 1).unwrap(),
+            Key::Character("M") => self.params.step("mode", -1).unwrap(),
+            Key::Character("s") => self.params.step("samples", 1).unwrap(),
+            Key::Character("S") => self.params.step("samples", -1).unwrap(),
+            Key::Character("f") => self.params.step("feedback", 1).unwrap(),
+            Key::Character("F") => self.params.step("feedback", -1).unwrap(),
+            Key::Character("v") => self.params.step("speed", 1).unwrap(),
+            Key::Character("V") => self.params.step("speed", -1).unwrap(),
+            Key::Character("r" | "R") => self.params.toggle("reproject").unwrap(),
+            Key::Character("j" | "J") => self.params.toggle("jitter").unwrap(),
+            _ => return,
+        }
+        // a history from another mode is worth nothing
+        self.history_valid = false;
+
+        println!("antialiasing config: {}", self.params);
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.time += dt * self.params.f32("speed");
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("antialiasing draw");
+
+        let size = self.viewport.as_uvec2().max(UVec2::ONE);
+        self.resize_targets(size)?;
+
+        let view_projection = camera.matrix(self.viewport);
+        let (previous_time, previous) = self.last.unwrap_or((self.time, view_projection));
+        self.last = Some((self.time, view_projection));
+        self.upload_geometry(previous_time);
+
+        let mode = self.params.variant("mode");
+        unsafe { self.timings.begin(mode) };
+        self.render(MODES[mode], view_projection, previous);
+        unsafe { self.timings.end() };
+
+        if let Some(summary) = self.timings.summarize() {
+            println!("antialiasing: {summary}");
+        }
+
+        let label = self.label();
+        let theme = theme::current();
+        let Some(text) = &self.text else {
+            return Ok(());
+        };
+        let mut text = text.borrow_mut();
+        let extent = text.measure(&label, TEXT_SIZE, None);
+        let min = Vec2::splat(MARGIN);
+        let max = min + extent + PADDING * 2.0;
+        text.queue_rect(min, max, theme.hud_background);
+        text.queue(&label, min + PADDING, TEXT_SIZE, None, theme.hud_text);
+        unsafe { text.draw(0, self.viewport) };
+        Ok(())
+    }
+
+    fn resize(&mut self, _camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        self.viewport = Vec2::new(width as f32, height as f32);
+        set_viewport(self.viewport.as_uvec2());
+        Ok(())
+    }
+}
+
+/// Everything the scene draws to before the window.
+struct Targets {
+    /// Color of the shapes, with the velocity as a second attachment.
+    scene: Framebuffer,
+    /// How far each pixel moved since the last frame, in texture coordinates.
+    velocity: Framebuffer,
+    /// Multisampled color, resolved by blitting it to the window.
+    msaa_fbo: GLuint,
+    msaa_texture: GLuint,
+    samples: i32,
+    /// Both ends of the ping-pong TAA accumulates in, the last one written
+    /// first.
+    history: [Framebuffer; 2],
+}
+
+impl Targets {
+    unsafe fn new(size: UVec2, samples: i32) -> Result<Self, GlError> {
+        let scene = create_framebuffer("antialiasing scene", size)?;
+        let velocity = create_float_framebuffer("antialiasing velocity", size, gl::RGBA16F)
+            .inspect_err(|_| delete_framebuffer(&scene))?;
+        let history = create_framebuffer("antialiasing history ping", size).and_then(|ping| {
+            let pong = create_framebuffer("antialiasing history pong", size)
+                .inspect_err(|_| delete_framebuffer(&ping))?;
+            Ok([ping, pong])
+        });
+        let history = history.inspect_err(|_| {
+            delete_framebuffer(&scene);
+            delete_framebuffer(&velocity);
+        })?;
+
+        let mut targets = Self {
+            scene,
+            velocity,
+            msaa_fbo: 0,
+            msaa_texture: 0,
+            samples,
+            history,
+        };
+
+        // the velocity goes next to the color of the shapes
+        gl::BindFramebuffer(gl::FRAMEBUFFER, targets.scene.fbo);
+        gl::FramebufferTexture2D(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT1,
+            gl::TEXTURE_2D,
+            targets.velocity.texture,
+            0,
+        );
+        let attachments = [gl::COLOR_ATTACHMENT0, gl::COLOR_ATTACHMENT1];
+        gl::DrawBuffers(2, attachments.as_ptr());
+        check_status("antialiasing scene", size)?;
+
+        gl::GenTextures(1, &mut targets.msaa_texture);
+        gl::BindTexture(gl::TEXTURE_2D_MULTISAMPLE, targets.msaa_texture);
+        gl::TexImage2DMultisample(
+            gl::TEXTURE_2D_MULTISAMPLE,
+            samples,
+            gl::RGBA8,
+            size.x as GLsizei,
+            size.y as GLsizei,
+            gl::TRUE,
+        );
+        gl::BindTexture(gl::TEXTURE_2D_MULTISAMPLE, 0);
+        let bytes = size.x as usize * size.y as usize * 4 * samples as usize;
+        let texture = Object::Texture(targets.msaa_texture);
+        gpu_memory::record(texture, Category::Framebuffers, bytes);
+
+        gl::GenFramebuffers(1, &mut targets.msaa_fbo);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, targets.msaa_fbo);
+        gl::FramebufferTexture2D(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            gl::TEXTURE_2D_MULTISAMPLE,
+            targets.msaa_texture,
+            0,
+        );
+        check_status("antialiasing msaa", size)?;
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+        Ok(targets)
+    }
+
+    fn size(&self) -> UVec2 {
+        self.scene.size
+    }
+
+    /// Binds the scene's framebuffer and clears its color to `color` and its
+    /// velocity to none.
+    unsafe fn clear_scene(&self, color: [f32; 4]) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.scene.fbo);
+        gl::ClearBufferfv(gl::COLOR, 0, color.as_ptr());
+        gl::ClearBufferfv(gl::COLOR, 1, [0.0; 4].as_ptr());
+    }
+
+    unsafe fn clear_msaa(&self, color: [f32; 4]) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.msaa_fbo);
+        gl::ClearBufferfv(gl::COLOR, 0, color.as_ptr());
+    }
+
+    /// Copies the color of `from` to `to`, resolving it if it's multisampled.
+    unsafe fn blit(&self, from: GLuint, to: GLuint) {
+        let size = self.size().as_ivec2();
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, from);
+        gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, to);
+        gl::BlitFramebuffer(
+            0,
+            0,
+            size.x,
+            size.y,
+            0,
+            0,
+            size.x,
+            size.y,
+            gl::COLOR_BUFFER_BIT,
+            gl::NEAREST,
+        );
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    }
+}
+
+/// Fails with the status of the bound framebuffer if it isn't complete.
+unsafe fn check_status(name: &str, size: UVec2) -> Result<(), GlError> {
+    let status: GLenum = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+    if status != gl::FRAMEBUFFER_COMPLETE {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        return Err(GlError::IncompleteFramebuffer {
+            name: name.to_string(),
+            size,
+            status,
+        });
+    }
+    Ok(())
+}
+
+impl Drop for Targets {
+    fn drop(&mut self) {
+        unsafe {
+            delete_framebuffer(&self.scene);
+            delete_framebuffer(&self.velocity);
+            for framebuffer in &self.history {
+                delete_framebuffer(framebuffer);
+            }
+            gl::DeleteFramebuffers(1, &self.msaa_fbo);
+            gpu_memory::forget(Object::Texture(self.msaa_texture));
+            gl::DeleteTextures(1, &self.msaa_texture);
+        }
+    }
+}
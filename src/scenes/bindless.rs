@@ -0,0 +1,328 @@
+#![allow(clippy::missing_safety_doc)]
+
+use gl::types::{GLint, GLsizei, GLuint, GLuint64};
+use glam::{Mat4, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_shader_program, delete_buffers, delete_textures, has_extension,
+    supports_compute, upload_texture,
+};
+use crate::error::GlError;
+use crate::gl_ext;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::technique_timings::TechniqueTimings;
+use super::{Scene, SRC_FRAG_BINDLESS_TILES, SRC_FRAG_TEXTURE, SRC_VERT_TILES};
+
+/// Quads per side of the grid, each with its own texture.
+const COLUMNS: usize = 64;
+const N_QUADS: usize = COLUMNS * COLUMNS;
+
+/// Side of every texture, in texels.
+const TILE_TEXELS: usize = 32;
+
+const SPACING: f32 = 40.0;
+const QUAD_SIZE: f32 = 32.0;
+
+const MODES: &[&str] = &["bind", "atlas", "bindless"];
+
+/// Thousands of quads, each sampling its own texture, drawn in one of three
+/// ways to compare what binding textures costs:
+///
+/// - `bind`: one draw call per quad, binding its texture in between.
+/// - `atlas`: every texture packed in a single atlas, one instanced draw.
+/// - `bindless`: every texture made resident through `ARB_bindless_texture`,
+///   with handles in an SSBO indexed per quad, one instanced draw.
+///
+/// The bindless mode is skipped when the extension (or SSBOs) isn't there.
+pub struct BindlessScene {
+    matrix: Mat4,
+
+    shader: TilesShader,
+    /// `None` without `ARB_bindless_texture`.
+    bindless: Option<Bindless>,
+    vao: GLuint,
+
+    textures: Vec<GLuint>,
+    atlas: GLuint,
+
+    timings: TechniqueTimings,
+
+    params: Params,
+}
+
+struct TilesShader {
+    program: GLuint,
+    u_mvp: GLint,
+    u_base_instance: GLint,
+    u_atlas_columns: GLint,
+}
+
+struct Bindless {
+    shader: TilesShader,
+    handles: Vec<GLuint64>,
+    handles_ssbo: GLuint,
+}
+
+impl TilesShader {
+    unsafe fn new(frag_source: &[u8]) -> Result<Self, GlError> {
+        let program = create_shader_program(SRC_VERT_TILES, frag_source)?;
+
+        let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(program, name.as_ptr());
+        gl::UseProgram(program);
+        gl::Uniform1i(uniform(c"u_columns"), COLUMNS as GLint);
+        gl::Uniform1f(uniform(c"u_spacing"), SPACING);
+        gl::Uniform1f(uniform(c"u_quad_size"), QUAD_SIZE);
+        gl::Uniform1f(uniform(c"u_inset"), 0.5 / TILE_TEXELS as f32);
+        gl::Uniform1i(uniform(c"u_texture"), 0);
+
+        Ok(Self {
+            program,
+            u_mvp: uniform(c"u_mvp"),
+            u_base_instance: uniform(c"u_base_instance"),
+            u_atlas_columns: uniform(c"u_atlas_columns"),
+        })
+    }
+
+    unsafe fn bind(&self, matrix: &Mat4, atlas_columns: usize) {
+        gl::UseProgram(self.program);
+        gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, matrix.as_ref().as_ptr());
+        gl::Uniform1i(self.u_base_instance, 0);
+        gl::Uniform1i(self.u_atlas_columns, atlas_columns as GLint);
+    }
+}
+
+impl Drop for TilesShader {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteProgram(self.program) };
+    }
+}
+
+impl BindlessScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let tiles = (0..N_QUADS)
+            .map(|i| tile_pixels(&mut rng, i))
+            .collect::<Vec<_>>();
+
+        unsafe {
+            let shader = TilesShader::new(SRC_FRAG_TEXTURE)?;
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+
+            let mut textures = vec![0; N_QUADS];
+            gl::GenTextures(N_QUADS as GLsizei, textures.as_mut_ptr());
+            for (&texture, pixels) in textures.iter().zip(&tiles) {
+                let size = TILE_TEXELS as u32;
+                upload_texture(texture, size, size, pixels.as_ptr(), gl::CLAMP_TO_EDGE)?;
+            }
+
+            // row by row, each row of the atlas holding COLUMNS tiles
+            let atlas_size = COLUMNS * TILE_TEXELS;
+            let mut atlas_pixels = vec![0; atlas_size * atlas_size * 4];
+            for (i, pixels) in tiles.iter().enumerate() {
+                let (tx, ty) = (i % COLUMNS, i / COLUMNS);
+                for (y, row) in pixels.chunks_exact(TILE_TEXELS * 4).enumerate() {
+                    let start = ((ty * TILE_TEXELS + y) * atlas_size + tx * TILE_TEXELS) * 4;
+                    atlas_pixels[start..start + row.len()].copy_from_slice(row);
+                }
+            }
+
+            let mut atlas = 0;
+            gl::GenTextures(1, &mut atlas);
+            let size = atlas_size as u32;
+            upload_texture(atlas, size, size, atlas_pixels.as_ptr(), gl::CLAMP_TO_EDGE)?;
+
+            let bindless = if has_extension("GL_ARB_bindless_texture") && supports_compute() {
+                let handles = (textures.iter())
+                    .map(|&texture| {
+                        let handle = gl_ext::get_texture_handle(texture);
+                        gl_ext::make_texture_handle_resident(handle);
+                        handle
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut handles_ssbo = 0;
+                gl::GenBuffers(1, &mut handles_ssbo);
+                buffer_data(
+                    gl::SHADER_STORAGE_BUFFER,
+                    handles_ssbo,
+                    handles.as_slice(),
+                    gl::STATIC_DRAW,
+                );
+
+                Some(Bindless {
+                    shader: TilesShader::new(SRC_FRAG_BINDLESS_TILES)?,
+                    handles,
+                    handles_ssbo,
+                })
+            } else {
+                println!(
+                    "bindless: GL_ARB_bindless_texture unsupported, only bind and atlas modes"
+                );
+                None
+            };
+
+            Ok(Self {
+                matrix: Mat4::default(),
+
+                shader,
+                bindless,
+                vao,
+
+                textures,
+                atlas,
+
+                timings: TechniqueTimings::new(MODES),
+
+                params: Params::new().with_enum("mode", 0, MODES),
+            })
+        }
+    }
+
+    unsafe fn draw_bound(&self) {
+        self.shader.bind(&self.matrix, 0);
+
+        for (i, &texture) in self.textures.iter().enumerate() {
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::Uniform1i(self.shader.u_base_instance, i as GLint);
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+        }
+    }
+
+    unsafe fn draw_atlas(&self) {
+        self.shader.bind(&self.matrix, COLUMNS);
+
+        gl::BindTexture(gl::TEXTURE_2D, self.atlas);
+        gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, N_QUADS as GLsizei);
+    }
+
+    unsafe fn draw_bindless(&self) {
+        let bindless = self.bindless.as_ref().unwrap();
+        bindless.shader.bind(&self.matrix, 0);
+
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, bindless.handles_ssbo);
+        gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, N_QUADS as GLsizei);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, 0);
+    }
+}
+
+impl Scene for BindlessScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let steps = match keycode.as_ref() {
+            Key::Character("m") => 1,
+            Key::Character("M") => -1,
+            _ => return,
+        };
+
+        self.params.step("mode", steps).unwrap();
+        if self.params.variant("mode") == 2 && self.bindless.is_none() {
+            // skip over the unsupported mode
+            self.params.step("mode", steps).unwrap();
+        }
+
+        println!("bindless config: {}", self.params);
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("bindless draw");
+
+        let mut mode = self.params.variant("mode");
+        if mode == 2 && self.bindless.is_none() {
+            mode = 1;
+        }
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            gl::BindVertexArray(self.vao);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            self.timings.begin(mode);
+            match mode {
+                0 => self.draw_bound(),
+                1 => self.draw_atlas(),
+                _ => self.draw_bindless(),
+            }
+            self.timings.end();
+
+            gl::BindVertexArray(0);
+
+            if let Some(summary) = self.timings.end_frame() {
+                println!("bindless: {summary}");
+            }
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.matrix = camera.matrix(Vec2::new(width as f32, height as f32));
+        Ok(())
+    }
+}
+
+impl Drop for BindlessScene {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(bindless) = &self.bindless {
+                // resident textures can't be deleted
+                for &handle in &bindless.handles {
+                    gl_ext::make_texture_handle_non_resident(handle);
+                }
+                delete_buffers(&[bindless.handles_ssbo]);
+            }
+
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_textures(&self.textures);
+            delete_textures(&[self.atlas]);
+        }
+    }
+}
+
+/// RGBA pixels of the texture of quad `i`: one of a few patterns in two
+/// random colors.
+fn tile_pixels(rng: &mut impl Rng, i: usize) -> Vec<u8> {
+    let mut color = || [rng.gen(), rng.gen(), rng.gen(), 255u8];
+    let (a, b) = (color(), color());
+
+    let mut pixels = Vec::with_capacity(TILE_TEXELS * TILE_TEXELS * 4);
+    for y in 0..TILE_TEXELS {
+        for x in 0..TILE_TEXELS {
+            let (fx, fy) = (x as f32 / TILE_TEXELS as f32, y as f32 / TILE_TEXELS as f32);
+            let is_a = match i % 4 {
+                0 => (x / 8 + y / 8) % 2 == 0,
+                1 => (x + y) / 4 % 2 == 0,
+                2 => Vec2::new(fx - 0.5, fy - 0.5).length() < 0.35,
+                _ => fy < fx,
+            };
+            pixels.extend(if is_a { a } else { b });
+        }
+    }
+
+    pixels
+}
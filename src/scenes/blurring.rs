@@ -1,22 +1,50 @@
+// Written with the safe wrappers of `common_gl` only.
+#![forbid(unsafe_code)]
+
 use std::f32::consts::PI;
 use std::{mem, time::Instant};
 
-use gl::types::{GLfloat, GLint, GLsizei, GLsizeiptr, GLuint};
+use gl::types::GLuint;
 use glam::{uvec2, vec2, Mat4, Vec2};
-use image::ImageFormat;
+use winit::dpi::PhysicalSize;
 use winit::keyboard::{Key, NamedKey, SmolStr};
-use winit::{dpi::PhysicalSize, window::Window};
 
+use crate::adjust::{self, Adjuster};
 use crate::camera::Camera;
-use crate::common_gl::{create_framebuffer, create_shader_program, upload_texture, Framebuffer};
-
-use super::{SRC_FRAG_BLUR, SRC_FRAG_DITHER, SRC_FRAG_TEXTURE, SRC_VERT_QUAD, SRC_VERT_SCREEN};
-
-const GURA_JPG: &[u8] = include_bytes!("../../assets/gura.jpg");
-// const BIG_SQUARES_PNG: &[u8] = include_bytes!("../../assets/big-squares.png");
+use crate::common_gl::{
+    bind_framebuffer, bind_texture, clear, set_viewport, Attrib, Buffer, IndexBuffer, Program,
+    RenderTarget, Texture, Uniform, VertexArray,
+};
+use crate::error::GlError;
+use crate::input_image::InputImage;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::{
+    dither_seed, Scene, SRC_FRAG_BLUR, SRC_FRAG_DITHER, SRC_FRAG_TEXTURE, SRC_VERT_QUAD,
+    SRC_VERT_SCREEN,
+};
 
 const RESDIVS: &[u32] = &[2, 4, 8, 16, 32, 64];
 
+// Both `screen.vert` and `quad.vert` have the same vertex attributes, so all
+// shaders use this layout.
+const LAYOUT: &[Attrib] = &[
+    Attrib {
+        name: c"position",
+        components: 2,
+        offset: 0,
+    },
+    Attrib {
+        name: c"uv",
+        components: 2,
+        offset: mem::size_of::<Vec2>(),
+    },
+];
+
+/// Typed view over the scene's parameters.
 struct BlurParams {
     pub kernel: i32,
     pub radius: f32,
@@ -29,477 +57,300 @@ pub struct BlurringScene {
     matrix: Mat4,
     viewport: Vec2,
 
-    quad_shader: GLuint,
-    quad_vao: GLuint,
-    quad_vbo: GLuint,
-    quad_ebo: GLuint,
+    quad_shader: Program,
+    quad_vao: VertexArray,
+    quad_ebo: IndexBuffer,
+    // kept alive for the vertex array
+    _quad_vbo: Buffer<Vertex>,
 
-    composite_fbs: Vec<(Framebuffer, Framebuffer)>,
-    comp_vao: GLuint,
-    comp_vbo: GLuint,
-    comp_shader: GLuint,
-    blur_shader: GLuint,
-    dither_shader: GLuint,
+    composite_fbs: Vec<(RenderTarget, RenderTarget)>,
+    comp_vao: VertexArray,
+    _comp_vbo: Buffer<Vertex>,
+    comp_shader: Program,
+    blur_shader: Program,
+    dither_shader: Program,
 
-    gura_texture: GLuint,
+    gura_texture: Texture,
+    adjuster: Adjuster,
 
-    u_mvp_quad: GLint,
-    u_mvp_dither: GLint,
-    u_direction: GLint,
-    u_kernel_size: GLint,
+    u_mvp_quad: Uniform,
+    u_mvp_dither: Uniform,
+    u_direction: Uniform,
+    u_kernel_size: Uniform,
 
-    blur: BlurParams,
-
-    indices: Vec<[u32; 6]>,
+    params: Params,
 
     last_instant: Instant,
 }
 
 impl BlurringScene {
-    pub fn new(window: &Window) -> Self {
-        let PhysicalSize { width, height } = window.inner_size();
-        let viewport = Vec2::new(width as f32, height as f32);
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
 
-        let (gura, gura_texture) = unsafe {
-            // Gura texture
-            let gura = image::load_from_memory_with_format(GURA_JPG, ImageFormat::Jpeg);
-            // let gura = image::load_from_memory_with_format(BIG_SQUARES_PNG, ImageFormat::Png);
-            let gura = gura.unwrap().into_rgba8();
-
-            let mut gura_texture: GLuint = 0;
-            gl::GenTextures(1, &mut gura_texture);
-            upload_texture(
-                gura_texture,
-                gura.width(),
-                gura.height(),
-                gura.as_ptr(),
-                gl::CLAMP_TO_BORDER,
-            );
-
-            (gura, gura_texture)
-        };
+    pub fn new(size: PhysicalSize<u32>, input: &InputImage, seed: u64) -> Result<Self, GlError> {
+        let PhysicalSize { width, height } = size;
+        let viewport = Vec2::new(width as f32, height as f32);
 
+        let gura = &input.image;
         let gura_size = uvec2(gura.width(), gura.height());
-
-        // They don't need to be vecs, but I'm too lazy to un-vector them now.
-        let mut quads = Vec::with_capacity(1);
-        let mut vertices = Vec::with_capacity(1);
-        let mut indices = Vec::with_capacity(1);
+        let gura_texture = Texture::new(gura_size, gura.as_raw(), gl::CLAMP_TO_BORDER)?;
 
         let quad = Quad {
             position: Vec2::ZERO,
             size: gura_size.as_vec2(),
         };
-        vertices.push(quad.vertices());
-        indices.push(quad.indices(0));
-        quads.push(quad);
-
-        unsafe {
-            // Normal blending
-            gl::Enable(gl::BLEND);
-            gl::BlendEquation(gl::FUNC_ADD);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-
-            // framebuffers
-            let composite_fbs = (RESDIVS.iter().copied())
-                .map(|resdiv| {
-                    (
-                        create_framebuffer("composite", gura_size / resdiv),
-                        create_framebuffer("ping_pong", gura_size / resdiv),
-                    )
-                })
-                .collect::<Vec<_>>();
-
-            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-
-            // quad vertices
-            let mut quad_vao: GLuint = 0;
-            gl::GenVertexArrays(1, &mut quad_vao);
-            gl::BindVertexArray(quad_vao);
-
-            let mut quad_vbo: GLuint = 0;
-            gl::GenBuffers(1, &mut quad_vbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, quad_vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                mem::size_of_val(vertices.as_slice()) as GLsizeiptr,
-                vertices.as_slice().as_ptr() as *const _,
-                gl::DYNAMIC_DRAW,
-            );
-
-            let mut quad_ebo: GLuint = 0;
-            gl::GenBuffers(1, &mut quad_ebo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, quad_ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                mem::size_of_val(indices.as_slice()) as GLsizeiptr,
-                indices.as_slice().as_ptr() as *const _,
-                gl::STATIC_DRAW,
-            );
-
-            // quad shaders
-            let quad_shader = create_shader_program(SRC_VERT_QUAD, SRC_FRAG_TEXTURE);
-            let u_mvp_quad = gl::GetUniformLocation(quad_shader, c"u_mvp".as_ptr());
-            Self::set_pos_uv_vertex_attribs(quad_shader);
-
-            let dither_shader = create_shader_program(SRC_VERT_QUAD, SRC_FRAG_DITHER);
-            let u_mvp_dither = gl::GetUniformLocation(dither_shader, c"u_mvp".as_ptr());
-            Self::set_pos_uv_vertex_attribs(dither_shader);
-
-            // compositing vertices
-            let mut comp_vao: GLuint = 0;
-            gl::GenVertexArrays(1, &mut comp_vao);
-            gl::BindVertexArray(comp_vao);
-
-            let mut comp_vbo: GLuint = 0;
-            gl::GenBuffers(1, &mut comp_vbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, comp_vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                mem::size_of_val(SCREEN_VERTICES) as GLsizeiptr,
-                SCREEN_VERTICES.as_ptr() as *const _,
-                gl::DYNAMIC_DRAW,
-            );
-
-            // compositing shaders
-            let comp_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_TEXTURE);
-            Self::set_pos_uv_vertex_attribs(comp_shader);
-
-            let blur_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_BLUR);
-            let u_direction = gl::GetUniformLocation(blur_shader, c"u_direction".as_ptr());
-            let u_kernel_size = gl::GetUniformLocation(blur_shader, c"u_kernel_size".as_ptr());
-            Self::set_pos_uv_vertex_attribs(blur_shader);
-
-            // default blur parameters
-            let blur = BlurParams {
-                kernel: 5,
-                layers: 4,
-                radius: 2.0,
-                is_diagonal: false,
-                is_dithered: false,
-            };
 
-            Self {
-                matrix: Mat4::default(),
-                viewport,
+        // framebuffers
+        let composite_fbs = (RESDIVS.iter().copied())
+            .map(|resdiv| {
+                Ok((
+                    RenderTarget::new("composite", gura_size / resdiv)?,
+                    RenderTarget::new("ping_pong", gura_size / resdiv)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, GlError>>()?;
+        bind_framebuffer(None);
+
+        // quad vertices
+        let quad_vbo = Buffer::new(gl::ARRAY_BUFFER, &quad.vertices(), gl::DYNAMIC_DRAW);
+        let quad_ebo = IndexBuffer::new(&quad.indices(0), gl::STATIC_DRAW);
+
+        // quad shaders
+        let mut quad_vao = VertexArray::new();
+        let quad_shader = Program::new(SRC_VERT_QUAD, SRC_FRAG_TEXTURE)?;
+        let u_mvp_quad = quad_shader.uniform(c"u_mvp");
+        quad_vao.set_layout(&quad_shader, &quad_vbo, LAYOUT);
+
+        let dither_shader = Program::new(SRC_VERT_QUAD, SRC_FRAG_DITHER)?;
+        let u_mvp_dither = dither_shader.uniform(c"u_mvp");
+        dither_shader.set(dither_shader.uniform(c"u_seed"), dither_seed(seed));
+        quad_vao.set_layout(&dither_shader, &quad_vbo, LAYOUT);
+
+        // compositing vertices and shaders
+        let comp_vbo = Buffer::new(gl::ARRAY_BUFFER, SCREEN_VERTICES, gl::STATIC_DRAW);
+        let mut comp_vao = VertexArray::new();
+        let comp_shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_TEXTURE)?;
+        comp_vao.set_layout(&comp_shader, &comp_vbo, LAYOUT);
+
+        let blur_shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_BLUR)?;
+        let u_direction = blur_shader.uniform(c"u_direction");
+        let u_kernel_size = blur_shader.uniform(c"u_kernel_size");
+        comp_vao.set_layout(&blur_shader, &comp_vbo, LAYOUT);
+
+        // default blur parameters
+        let params = adjust::with_params(
+            Params::new()
+                .with_i32("kernel", 5, 0, 64)
+                .with_f32(
+                    "radius",
+                    2.0,
+                    0.0,
+                    *RESDIVS.last().unwrap() as f32 / 2.0,
+                    0.1,
+                )
+                .with_i32("layers", 4, 0, RESDIVS.len() as i32)
+                .with_enum("mode", 0, &["vert/horz", "diagonal"])
+                .with_bool("dither", false),
+        );
 
-                quad_shader,
-                quad_vao,
-                quad_vbo,
-                quad_ebo,
+        Ok(Self {
+            matrix: Mat4::default(),
+            viewport,
 
-                composite_fbs,
-                comp_vao,
-                comp_vbo,
-                comp_shader,
-                blur_shader,
-                dither_shader,
+            quad_shader,
+            quad_vao,
+            quad_ebo,
+            _quad_vbo: quad_vbo,
 
-                gura_texture,
+            composite_fbs,
+            comp_vao,
+            _comp_vbo: comp_vbo,
+            comp_shader,
+            blur_shader,
+            dither_shader,
 
-                u_mvp_quad,
-                u_mvp_dither,
-                u_direction,
-                u_kernel_size,
+            gura_texture,
+            adjuster: Adjuster::new(gura_size)?,
 
-                blur,
+            u_mvp_quad,
+            u_mvp_dither,
+            u_direction,
+            u_kernel_size,
 
-                indices,
+            params,
 
-                last_instant: Instant::now(),
-            }
-        }
+            last_instant: Instant::now(),
+        })
     }
 
-    unsafe fn set_pos_uv_vertex_attribs(shader: GLuint) {
-        // Both `screen.vert` and `quad.vert` have the same vertex
-        // attributes, so I'm using this function for all shaders.
-
-        const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
-        const SIZE_F32: GLsizei = mem::size_of::<f32>() as GLsizei;
-
-        #[rustfmt::skip]
-        {
-            let a_position = gl::GetAttribLocation(shader, c"position" .as_ptr()) as GLuint;
-            let a_uv       = gl::GetAttribLocation(shader, c"uv"       .as_ptr()) as GLuint;
-
-            gl::VertexAttribPointer(a_position, 2, gl::FLOAT, gl::FALSE, SIZE_VERTEX,  0             as _);
-            gl::VertexAttribPointer(a_uv,       2, gl::FLOAT, gl::FALSE, SIZE_VERTEX, (2 * SIZE_F32) as _);
-
-            gl::EnableVertexAttribArray(a_position as GLuint);
-            gl::EnableVertexAttribArray(a_uv       as GLuint);
-        };
+    fn blur(&self) -> BlurParams {
+        BlurParams {
+            kernel: self.params.i32("kernel"),
+            radius: self.params.f32("radius"),
+            layers: self.params.i32("layers") as usize,
+            is_diagonal: self.params.variant("mode") == 1,
+            is_dithered: self.params.bool("dither"),
+        }
     }
 
-    pub fn on_key(&mut self, keycode: Key<SmolStr>) {
-        match keycode {
-            Key::Named(NamedKey::ArrowUp) => {
-                self.blur.kernel = (self.blur.kernel + 1).min(64);
-            }
-            Key::Named(NamedKey::ArrowDown) => {
-                self.blur.kernel = (self.blur.kernel - 1).max(0);
-            }
-            Key::Named(NamedKey::ArrowRight) => {
-                self.blur.radius =
-                    (self.blur.radius + 0.1).min(*RESDIVS.last().unwrap() as f32 / 2.0);
-            }
-            Key::Named(NamedKey::ArrowLeft) => {
-                self.blur.radius = (self.blur.radius - 0.1).max(0.0);
-            }
-            Key::Character(ch) => match ch.as_str() {
-                "d" | "D" => {
-                    self.blur.is_dithered = !self.blur.is_dithered;
-                }
-                "/" => {
-                    self.blur.is_diagonal = !self.blur.is_diagonal;
-                }
-                "l" => {
-                    self.blur.layers = (self.blur.layers + 1).min(RESDIVS.len());
-                }
-                "L" => {
-                    self.blur.layers = self.blur.layers.saturating_sub(1);
-                }
-                _ => return,
-            },
-            _ => return,
-        };
-
-        let mode = if self.blur.is_diagonal {
-            "diagonal"
+    fn draw_with_clear_color(&self, gura_texture: GLuint, clear_color: [f32; 4]) {
+        let texture = if self.blur().layers == 0 {
+            gura_texture
         } else {
-            "vert/horz"
-        };
+            let mut input_fb = &self.composite_fbs[0].0;
 
-        let dither_mode = if self.blur.is_dithered {
-            " dithering"
-        } else {
-            ""
-        };
+            // draw Gura to framebuffer
+            {
+                input_fb.bind();
+                clear([0.0; 4]);
 
-        println!(
-            "blur config: k={} r={:.2} l={} {}{}",
-            self.blur.kernel, self.blur.radius, self.blur.layers, mode, dither_mode
-        );
-    }
+                self.comp_shader.bind();
+                bind_texture(0, gura_texture);
+                self.comp_vao.draw_arrays(gl::TRIANGLES);
+            }
 
-    pub fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) {
-        self.last_instant = Instant::now();
+            let angles: &[f32] = if self.blur().is_diagonal {
+                &[PI / 4.0]
+            } else {
+                &[0.0]
+            };
 
-        self.draw_with_clear_color(0.0, 0.2, 0.15, 0.5);
-    }
+            // blur at half-resolution, then quarter-res, then eighth-res, ...
+            for fbi in 0..self.blur().layers {
+                // FBI OPEN UP
 
-    fn draw_with_clear_color(&self, r: GLfloat, g: GLfloat, b: GLfloat, a: GLfloat) {
-        unsafe {
-            let texture = if self.blur.layers == 0 {
-                self.gura_texture
-            } else {
-                let mut input_fb = &self.composite_fbs[0].0;
-
-                // draw Gura to framebuffer
-                {
-                    gl::BindFramebuffer(gl::FRAMEBUFFER, input_fb.fbo);
-                    gl::Viewport(0, 0, input_fb.size.x as i32, input_fb.size.y as i32);
-
-                    gl::ClearColor(0.0, 0.0, 0.0, 0.0);
-                    gl::Clear(gl::COLOR_BUFFER_BIT);
-                    gl::UseProgram(self.comp_shader);
-
-                    gl::BindVertexArray(self.comp_vao);
-                    gl::BindBuffer(gl::ARRAY_BUFFER, self.comp_vbo);
-                    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-                    gl::BufferSubData(
-                        gl::ARRAY_BUFFER,
-                        0,
-                        mem::size_of_val(SCREEN_VERTICES) as GLsizeiptr,
-                        SCREEN_VERTICES.as_ptr() as *const _,
+                for angle in angles {
+                    input_fb = self.ping_pong_blur_pass(
+                        *angle,
+                        input_fb,
+                        &self.composite_fbs[fbi].0,
+                        &self.composite_fbs[fbi].1,
                     );
-
-                    gl::BindTexture(gl::TEXTURE_2D, self.gura_texture);
-                    gl::ActiveTexture(gl::TEXTURE0);
-                    gl::DrawArrays(gl::TRIANGLES, 0, 6);
                 }
+            }
 
-                let angles: &[f32] = if self.blur.is_diagonal {
-                    &[PI / 4.0]
-                } else {
-                    &[0.0]
-                };
-
-                // blur at half-resolution, then quarter-res, then eighth-res, ...
-                for fbi in 0..self.blur.layers {
-                    // FBI OPEN UP
-
-                    for angle in angles {
-                        input_fb = self.ping_pong_blur_pass(
-                            *angle,
-                            input_fb,
-                            &self.composite_fbs[fbi].0,
-                            &self.composite_fbs[fbi].1,
-                        );
-                    }
-                }
+            // ..., then eighth-res, then quarter-res, then half-resolution
+            for fbi in (0..(self.blur().layers - 1)).rev() {
+                // FBI OPEN UP
 
-                // ..., then eighth-res, then quarter-res, then half-resolution
-                for fbi in (0..(self.blur.layers - 1)).rev() {
-                    // FBI OPEN UP
-
-                    for angle in angles {
-                        input_fb = self.ping_pong_blur_pass(
-                            *angle,
-                            input_fb,
-                            &self.composite_fbs[fbi].0,
-                            &self.composite_fbs[fbi].1,
-                        );
-                    }
+                for angle in angles {
+                    input_fb = self.ping_pong_blur_pass(
+                        *angle,
+                        input_fb,
+                        &self.composite_fbs[fbi].0,
+                        &self.composite_fbs[fbi].1,
+                    );
                 }
+            }
 
-                input_fb.texture
-            };
+            input_fb.texture
+        };
 
-            // draw framebuffer to screen as quad
-            {
-                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-                gl::Viewport(0, 0, self.viewport.x as i32, self.viewport.y as i32);
-
-                gl::ClearColor(r, g, b, a);
-                gl::Clear(gl::COLOR_BUFFER_BIT);
-                if self.blur.is_dithered {
-                    gl::UseProgram(self.dither_shader);
-                } else {
-                    gl::UseProgram(self.quad_shader);
-                }
+        // draw framebuffer to screen as quad
+        bind_framebuffer(None);
+        set_viewport(self.viewport.as_uvec2());
+        clear(clear_color);
 
-                gl::BindVertexArray(self.quad_vao);
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.quad_vbo);
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.quad_ebo);
-
-                gl::BindTexture(gl::TEXTURE_2D, texture);
-                gl::DrawElements(
-                    gl::TRIANGLES,
-                    mem::size_of_val(self.indices.as_slice()) as GLsizei,
-                    gl::UNSIGNED_INT,
-                    std::ptr::null(),
-                );
-            }
+        if self.blur().is_dithered {
+            self.dither_shader.bind();
+        } else {
+            self.quad_shader.bind();
         }
+        bind_texture(0, texture);
+        self.quad_vao.draw_elements(gl::TRIANGLES, &self.quad_ebo);
     }
 
     fn ping_pong_blur_pass<'a>(
         &self,
         angle: f32,
-        from_fb: &Framebuffer,
-        composite_fb: &'a Framebuffer,
-        ping_pong_fb: &Framebuffer,
-    ) -> &'a Framebuffer {
+        from_fb: &RenderTarget,
+        composite_fb: &'a RenderTarget,
+        ping_pong_fb: &RenderTarget,
+    ) -> &'a RenderTarget {
         // draw framebuffer to ping-pong framebuffer, with X-blurring
-        unsafe {
-            gl::BindFramebuffer(gl::FRAMEBUFFER, ping_pong_fb.fbo);
-            gl::Viewport(0, 0, ping_pong_fb.size.x as i32, ping_pong_fb.size.y as i32);
-
-            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
-            gl::UseProgram(self.blur_shader);
-
-            gl::Uniform1i(self.u_kernel_size, self.blur.kernel);
-            gl::Uniform2f(
-                self.u_direction,
-                angle.cos() * self.blur.radius,
-                angle.sin() * self.blur.radius,
-            );
-
-            gl::BindVertexArray(self.comp_vao);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.comp_vbo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-            gl::BufferSubData(
-                gl::ARRAY_BUFFER,
-                0,
-                mem::size_of_val(SCREEN_VERTICES) as GLsizeiptr,
-                SCREEN_VERTICES.as_ptr() as *const _,
-            );
-
-            gl::BindTexture(gl::TEXTURE_2D, from_fb.texture);
-            gl::DrawArrays(gl::TRIANGLES, 0, 6);
-        }
-
+        self.blur_pass(angle, from_fb, ping_pong_fb);
         // draw ping-pong framebuffer to framebuffer, with Y-blurring
-        let angle = angle + PI / 2.0;
-        unsafe {
-            gl::BindFramebuffer(gl::FRAMEBUFFER, composite_fb.fbo);
-            gl::Viewport(0, 0, composite_fb.size.x as i32, composite_fb.size.y as i32);
-
-            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
-            gl::UseProgram(self.blur_shader);
-
-            gl::Uniform1i(self.u_kernel_size, self.blur.kernel);
-            gl::Uniform2f(
-                self.u_direction,
-                angle.cos() * self.blur.radius,
-                angle.sin() * self.blur.radius,
-            );
-
-            gl::BindVertexArray(self.comp_vao);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.comp_vbo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-            gl::BufferSubData(
-                gl::ARRAY_BUFFER,
-                0,
-                mem::size_of_val(SCREEN_VERTICES) as GLsizeiptr,
-                SCREEN_VERTICES.as_ptr() as *const _,
-            );
-
-            gl::BindTexture(gl::TEXTURE_2D, ping_pong_fb.texture);
-            gl::DrawArrays(gl::TRIANGLES, 0, 6);
-        }
+        self.blur_pass(angle + PI / 2.0, ping_pong_fb, composite_fb);
 
         composite_fb
     }
 
-    pub fn resize(&mut self, camera: &Camera, width: i32, height: i32) {
-        unsafe {
-            gl::Viewport(0, 0, width, height);
+    /// Draws `from` blurred along `angle` to `to`.
+    fn blur_pass(&self, angle: f32, from: &RenderTarget, to: &RenderTarget) {
+        to.bind();
+        clear([0.0; 4]);
 
-            self.viewport = Vec2::new(width as f32, height as f32);
-            self.matrix = camera.matrix(self.viewport);
+        let blur = self.blur();
+        let direction = Vec2::from_angle(angle) * blur.radius;
+        self.blur_shader.set(self.u_kernel_size, blur.kernel);
+        self.blur_shader.set(self.u_direction, direction);
 
-            gl::UseProgram(self.quad_shader);
-            gl::UniformMatrix4fv(self.u_mvp_quad, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+        bind_texture(0, from.texture);
+        self.comp_vao.draw_arrays(gl::TRIANGLES);
+    }
+}
+
+impl Scene for BlurringScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let (name, steps) = match keycode {
+            Key::Named(NamedKey::ArrowUp) => ("kernel", 1),
+            Key::Named(NamedKey::ArrowDown) => ("kernel", -1),
+            Key::Named(NamedKey::ArrowRight) => ("radius", 1),
+            Key::Named(NamedKey::ArrowLeft) => ("radius", -1),
+            Key::Character(ch) => match ch.as_str() {
+                "d" | "D" => ("dither", 1),
+                "/" => ("mode", 1),
+                "l" => ("layers", 1),
+                "L" => ("layers", -1),
+                ch => match adjust::key_step(ch) {
+                    Some(step) => step,
+                    None => return,
+                },
+            },
+            _ => return,
+        };
 
-            gl::UseProgram(self.dither_shader);
-            gl::UniformMatrix4fv(
-                self.u_mvp_dither,
-                1,
-                gl::FALSE,
-                self.matrix.as_ref().as_ptr(),
-            );
+        self.params.step(name, steps).unwrap();
+        println!("blur config: {}", self.params);
+    }
+
+    fn stream_input(&mut self, input: &InputImage) -> Result<bool, GlError> {
+        let streamed = (self.gura_texture).stream(input, gl::CLAMP_TO_BORDER)?;
+        if streamed {
+            self.adjuster.invalidate();
         }
+        Ok(streamed)
     }
-}
 
-impl Drop for BlurringScene {
-    fn drop(&mut self) {
-        unsafe {
-            gl::DeleteProgram(self.quad_shader);
-            gl::DeleteProgram(self.comp_shader);
-            gl::DeleteProgram(self.blur_shader);
-            gl::DeleteProgram(self.dither_shader);
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
 
-            for comp_fb in &self.composite_fbs {
-                let fbs = &[comp_fb.0.fbo, comp_fb.1.fbo];
-                gl::DeleteFramebuffers(fbs.len() as GLsizei, fbs.as_ptr());
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
 
-                let textures = &[comp_fb.0.texture, comp_fb.1.texture];
-                gl::DeleteTextures(textures.len() as GLsizei, textures.as_ptr());
-            }
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("blurring draw");
 
-            let buffers = &[self.quad_vbo, self.quad_ebo, self.comp_vbo];
-            gl::DeleteBuffers(buffers.len() as GLsizei, buffers.as_ptr());
+        self.last_instant = Instant::now();
+
+        let gura_texture = (self.adjuster).apply(self.gura_texture.id(), &self.params);
+        self.draw_with_clear_color(gura_texture, theme::current().backdrop);
+        Ok(())
+    }
 
-            let arrays = &[self.quad_vao, self.comp_vao];
-            gl::DeleteVertexArrays(arrays.len() as GLsizei, arrays.as_ptr());
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        self.viewport = Vec2::new(width as f32, height as f32);
+        set_viewport(self.viewport.as_uvec2());
+        self.matrix = camera.matrix(self.viewport);
 
-            gl::DeleteTextures(1, &self.gura_texture);
-        }
+        self.quad_shader.set(self.u_mvp_quad, self.matrix);
+        self.dither_shader.set(self.u_mvp_dither, self.matrix);
+        Ok(())
     }
 }
 
@@ -523,7 +374,7 @@ impl Quad {
         ];
     }
 
-    fn indices(&self, quad_index: u32) -> [u32; 6] {
+    fn indices(self, quad_index: u32) -> [u32; 6] {
         let i = quad_index * 4;
         [i, 1 + i, 2 + i, i, 2 + i, 3 + i]
     }
@@ -0,0 +1,368 @@
+use std::mem;
+use std::rc::Rc;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, vec3, Mat4, UVec2, Vec2, Vec3};
+use image::RgbaImage;
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_framebuffer, create_shader_program, delete_buffers, delete_framebuffer,
+    read_pixels, Texture,
+};
+use crate::error::GlError;
+use crate::input_image::InputImage;
+use crate::params::{Params, Value};
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::{Scene, SceneEvent, SRC_FRAG_CHROMA_KEY, SRC_VERT_QUAD};
+
+/// Variants of the `view` parameter, in order.
+const VIEWS: &[&str] = &["keyed", "matte", "original"];
+/// Variants of the `background` parameter, in order.
+const BACKGROUNDS: &[&str] = &["checker", "backdrop", "black", "white"];
+/// Parameters of the key color, in RGB order.
+const KEY: [&str; 3] = ["key_r", "key_g", "key_b"];
+
+/// Sizes of the swatch of the key color, in pixels.
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+const SWATCH: f32 = 20.0;
+const TEXT_SIZE: f32 = 14.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// The input image with a color keyed out to transparency, as done with
+/// green screens, to make transparent input for the blur scenes out of any
+/// photo.
+///
+/// The key is picked from the image with the mouse. Pixels whose chroma is
+/// close enough to the key's become transparent, with a soft ramp in between,
+/// and the hue of the key is taken out of what's left, since it spills onto
+/// the edges of the subject. The result can then replace the input image.
+pub struct ChromaKeyScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    /// Kept on the CPU to pick colors from.
+    image: Rc<RgbaImage>,
+    image_size: UVec2,
+    /// Keyed image made with the `I` key, waiting to become the input image.
+    exported: Option<RgbaImage>,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_key: GLint,
+    u_tolerance: GLint,
+    u_softness: GLint,
+    u_spill: GLint,
+    u_view: GLint,
+    u_fill: GLint,
+    u_background: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    texture: Texture,
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+impl ChromaKeyScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(input: &InputImage, text: Option<SharedText>) -> Result<Self, GlError> {
+        let image = input.image.clone();
+        let image_size = uvec2(image.width(), image.height());
+
+        let half = image_size.as_vec2() / 2.0;
+        let vertex = |x: f32, y: f32| Vertex {
+            position: (vec2(x, y) * 2.0 - 1.0) * half,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        let texture = Texture::new(image_size, image.as_raw(), gl::CLAMP_TO_BORDER)?;
+
+        unsafe {
+            let shader = create_shader_program(SRC_VERT_QUAD, SRC_FRAG_CHROMA_KEY)?;
+            let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(shader, name.as_ptr());
+            gl::UseProgram(shader);
+            gl::Uniform1i(uniform(c"u_image"), 0);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            // a green screen until something gets picked
+            let params = Params::new()
+                .with_f32("key_r", 0.0, 0.0, 1.0, 0.05)
+                .with_f32("key_g", 1.0, 0.0, 1.0, 0.05)
+                .with_f32("key_b", 0.0, 0.0, 1.0, 0.05)
+                .with_f32("tolerance", 0.1, 0.0, 1.0, 0.01)
+                .with_f32("softness", 0.1, 0.0, 1.0, 0.01)
+                .with_f32("spill", 0.6, 0.0, 1.0, 0.1)
+                .with_enum("view", 0, VIEWS)
+                .with_enum("background", 0, BACKGROUNDS);
+
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+
+                image,
+                image_size,
+                exported: None,
+
+                u_mvp: uniform(c"u_mvp"),
+                u_key: uniform(c"u_key"),
+                u_tolerance: uniform(c"u_tolerance"),
+                u_softness: uniform(c"u_softness"),
+                u_spill: uniform(c"u_spill"),
+                u_view: uniform(c"u_view"),
+                u_fill: uniform(c"u_fill"),
+                u_background: uniform(c"u_background"),
+                shader,
+                vao,
+                vbo,
+                texture,
+
+                text,
+
+                params,
+            })
+        }
+    }
+
+    fn key(&self) -> Vec3 {
+        Vec3::from_array(KEY.map(|name| self.params.f32(name)))
+    }
+
+    /// Draws the image keyed with `view` over `fill`, see chroma-key.frag.
+    unsafe fn draw_image(&self, matrix: &Mat4, view: usize, fill: GLint) {
+        let key = self.key();
+        let background = match BACKGROUNDS[self.params.variant("background")] {
+            "backdrop" => Vec3::from_slice(&theme::current().backdrop),
+            "white" => Vec3::ONE,
+            _ => Vec3::ZERO,
+        };
+
+        gl::UseProgram(self.shader);
+        gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, matrix.as_ref().as_ptr());
+        gl::Uniform3f(self.u_key, key.x, key.y, key.z);
+        gl::Uniform1f(self.u_tolerance, self.params.f32("tolerance"));
+        gl::Uniform1f(self.u_softness, self.params.f32("softness"));
+        gl::Uniform1f(self.u_spill, self.params.f32("spill"));
+        gl::Uniform1i(self.u_view, view as GLint);
+        gl::Uniform1i(self.u_fill, fill);
+        gl::Uniform3f(self.u_background, background.x, background.y, background.z);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, self.texture.id());
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        gl::BindVertexArray(0);
+    }
+
+    /// Draws the keyed image into an image of its size, with straight alpha.
+    unsafe fn export(&mut self) -> Result<RgbaImage, GlError> {
+        let size = self.image_size;
+        let target = create_framebuffer("chroma key export", size)?;
+        gl::Viewport(0, 0, size.x as GLsizei, size.y as GLsizei);
+        gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        // blending is off in this scene, so the alpha is written as it is
+        // the whole target shows exactly the image, its top row on top
+        let half = size.as_vec2() / 2.0;
+        let matrix = Mat4::from_scale(vec3(1.0 / half.x, -1.0 / half.y, 1.0));
+        self.draw_image(&matrix, 0, 0);
+        let pixels = read_pixels(size.x, size.y);
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        delete_framebuffer(&target);
+        let viewport = self.viewport.as_ivec2();
+        gl::Viewport(0, 0, viewport.x, viewport.y);
+
+        println!(
+            "Exported the {}x{} keyed image as the input image",
+            size.x, size.y
+        );
+        Ok(RgbaImage::from_raw(size.x, size.y, pixels).unwrap())
+    }
+}
+
+impl Scene for ChromaKeyScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let (name, steps) = match keycode.as_ref() {
+            Key::Character("t") => ("tolerance", 1),
+            Key::Character("T") => ("tolerance", -1),
+            Key::Character("f") => ("softness", 1),
+            Key::Character("F") => ("softness", -1),
+            Key::Character("s") => ("spill", 1),
+            Key::Character("S") => ("spill", -1),
+            Key::Character("v") => ("view", 1),
+            Key::Character("V") => ("view", -1),
+            Key::Character("b") => ("background", 1),
+            Key::Character("B") => ("background", -1),
+            Key::Character("i" | "I") => {
+                match unsafe { self.export() } {
+                    Ok(image) => self.exported = Some(image),
+                    Err(e) => eprintln!("Couldn't export the keyed image: {e}"),
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        self.params.step(name, steps).unwrap();
+        println!("chroma key config: {}", self.params);
+    }
+
+    /// Picks the key color from the image under the mouse with the left
+    /// button. Returns whether the click was used, in which case the camera
+    /// shouldn't pan.
+    fn on_event(&mut self, camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if button != MouseButton::Left || state != ElementState::Pressed {
+            return false;
+        }
+
+        let size = self.image_size.as_vec2();
+        let world = camera.pointer_to_pos(mouse_pos, self.viewport);
+        let pixel = (world + size / 2.0).floor();
+        if pixel.cmplt(Vec2::ZERO).any() || pixel.cmpge(size).any() {
+            return false;
+        }
+
+        let [r, g, b, _] = self.image.get_pixel(pixel.x as u32, pixel.y as u32).0;
+        for (name, channel) in KEY.into_iter().zip([r, g, b]) {
+            let value = Value::Number(channel as f64 / 255.0);
+            self.params.set(name, value).unwrap();
+        }
+        println!("chroma key: picked #{r:02x}{g:02x}{b:02x} at {pixel}");
+        true
+    }
+
+    fn stream_input(&mut self, input: &InputImage) -> Result<bool, GlError> {
+        if !self.texture.stream(input, gl::CLAMP_TO_BORDER)? {
+            return Ok(false);
+        }
+        self.image = input.image.clone();
+        Ok(true)
+    }
+
+    /// Keyed image made with the `I` key, to be used as the input image of
+    /// the blur scenes.
+    fn take_input(&mut self) -> Option<RgbaImage> {
+        self.exported.take()
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("chroma key draw");
+
+        let theme = theme::current();
+        let view = self.params.variant("view");
+        let fill = match BACKGROUNDS[self.params.variant("background")] {
+            "checker" => 1,
+            _ => 2,
+        };
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+            self.draw_image(&self.matrix, view, fill);
+
+            // the key color and how it's keyed
+            let key = self.key();
+            let [r, g, b] = (key * 255.0).round().to_array().map(|c| c as u8);
+            let label = format!(
+                "key #{r:02x}{g:02x}{b:02x}   ± {:.2}   spill {:.1}   {}",
+                self.params.f32("tolerance"),
+                self.params.f32("spill"),
+                VIEWS[view],
+            );
+            let Some(text) = &self.text else {
+                return Ok(());
+            };
+            let mut text = text.borrow_mut();
+            let extent = text.measure(&label, TEXT_SIZE, None);
+            let origin = Vec2::splat(MARGIN + PADDING);
+            let height = extent.y.max(SWATCH);
+            let max = origin + vec2(SWATCH + PADDING + extent.x, height) + PADDING;
+            text.queue_rect(Vec2::splat(MARGIN), max, theme.hud_background);
+            let swatch = origin + vec2(0.0, (height - SWATCH) / 2.0);
+            text.queue_rect(swatch, swatch + SWATCH, key.extend(1.0).to_array());
+            let position = origin + vec2(SWATCH + PADDING, (height - extent.y) / 2.0);
+            text.queue(&label, position, TEXT_SIZE, None, theme.hud_text);
+            text.draw(0, self.viewport);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+impl Drop for ChromaKeyScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
@@ -0,0 +1,635 @@
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
+use glam::{vec2, vec3, Mat4, Vec2, Vec3, Vec4};
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::cloth::{Cloth, ConstraintKind};
+use crate::common_gl::{
+    buffer_data, create_compute_program, create_geometry_shader_program, create_shader_program,
+    delete_buffers, delete_textures, gl_version, pop_debug_group, push_debug_group,
+    supports_compute, upload_texture,
+};
+use crate::error::GlError;
+use crate::input_image::InputImage;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::{self, RenderState};
+use crate::theme;
+
+use super::{
+    Scene, SceneEvent, SRC_COMP_CLOTH_INTEGRATE, SRC_COMP_CLOTH_RELAX, SRC_FRAG_CLOTH,
+    SRC_FRAG_CLOTH_NORMALS, SRC_GEOM_CLOTH_NORMALS, SRC_VERT_CLOTH, SRC_VERT_CLOTH_NORMALS,
+};
+
+/// Particles along a row. Rows follow the aspect ratio of the image.
+const COLUMNS: usize = 64;
+/// Width of the cloth in world units.
+const WIDTH: f32 = 640.0;
+/// Pixels per second squared, downward.
+const GRAVITY: Vec3 = vec3(0.0, 980.0, 0.0);
+/// Wind at full strength, mostly blowing into the screen.
+const WIND: Vec3 = vec3(300.0, 0.0, 600.0);
+/// How far from a particle the mouse can grab it, in world units.
+const GRAB_RADIUS: f32 = 40.0;
+/// Shear constraints give more than the others, so that the cloth drapes.
+const SHEAR_STIFFNESS: f32 = 0.8;
+/// Jacobi iterations of the compute solver per Gauss-Seidel one of the CPU
+/// solver, for the cloth to stretch about as much with either.
+const JACOBI_FACTOR: i32 = 4;
+
+/// Length of the normals and tangents drawn for debugging, in springs.
+const NORMAL_LENGTH: f32 = 0.8;
+
+/// Must match `local_size_x` in cloth-integrate.comp and cloth-relax.comp.
+const WORKGROUP_SIZE: usize = 256;
+
+/// Variants of the `solver` parameter, in order.
+const SOLVERS: &[&str] = &["cpu", "compute"];
+const CPU: usize = 0;
+const COMPUTE: usize = 1;
+/// Variants of the `pins` parameter, in order.
+const PINS: &[&str] = &["top", "corners"];
+
+/// The `Particle` struct of the cloth compute shaders.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    /// Inverse mass in w.
+    position: Vec4,
+    previous: Vec4,
+}
+
+/// A hanging cloth of thousands of particles held together by springs,
+/// with the input image printed on it.
+///
+/// The particles move with Verlet integration under gravity and gusts of
+/// wind, and the springs are solved as distance constraints, either one after
+/// the other on the CPU (see [`crate::cloth`]) or all at once in a compute
+/// pass per iteration. Both share the particle buffer the mesh is drawn from,
+/// so switching between them picks up where the other left off. The mouse
+/// can grab the cloth with the right button.
+pub struct ClothScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    cloth: Cloth,
+    spacing: f32,
+    time: f32,
+    /// Where the mouse is in world units.
+    mouse_world: Vec2,
+    /// Particle held by the mouse, and its depth when it was grabbed.
+    grabbed: Option<(usize, f32)>,
+    /// Solver the particles are currently up to date with.
+    solver: usize,
+    /// Pins the cloth was hung with.
+    pins: usize,
+
+    /// `None` when compute shaders aren't supported.
+    compute: Option<ComputeSolver>,
+    /// Particles on the GPU, which the mesh is drawn from. The CPU solver
+    /// uploads to the first one, the compute solver goes back and forth
+    /// between both.
+    particles: [GLuint; 2],
+    current: usize,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_light: GLint,
+    u_columns: GLint,
+    u_rows: GLint,
+    /// Buffer texture over the current particle buffer, through which the
+    /// vertex shader finds the neighbours of a particle for its normal.
+    particles_texture: GLuint,
+    vao: GLuint,
+    uv_vbo: GLuint,
+    ebo: GLuint,
+    n_indices: GLsizei,
+    texture: GLuint,
+    /// Draws a normal and a tangent per particle, see
+    /// [`render_state::show_normals`].
+    normals: NormalsShader,
+
+    params: Params,
+}
+
+struct NormalsShader {
+    program: GLuint,
+    u_mvp: GLint,
+    u_length: GLint,
+    u_columns: GLint,
+    u_rows: GLint,
+}
+
+struct ComputeSolver {
+    integrate: GLuint,
+    u_count: GLint,
+    u_dt: GLint,
+    u_damping: GLint,
+    u_gravity: GLint,
+    u_wind: GLint,
+    u_time: GLint,
+    u_grabbed_integrate: GLint,
+    u_grab_target: GLint,
+
+    relax: GLuint,
+    u_columns: GLint,
+    u_rows: GLint,
+    u_spacing: GLint,
+    u_shear_stiffness: GLint,
+    u_bend_stiffness: GLint,
+    u_grabbed_relax: GLint,
+}
+
+impl ClothScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(input: &InputImage) -> Result<Self, GlError> {
+        let image = &input.image;
+        let aspect = image.height() as f32 / image.width() as f32;
+        let rows = (((COLUMNS - 1) as f32 * aspect).round() as usize + 1).max(2);
+        let spacing = WIDTH / (COLUMNS - 1) as f32;
+
+        let uvs = (0..rows)
+            .flat_map(|y| (0..COLUMNS).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                vec2(
+                    x as f32 / (COLUMNS - 1) as f32,
+                    y as f32 / (rows - 1) as f32,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut indices = Vec::with_capacity((COLUMNS - 1) * (rows - 1) * 6);
+        for y in 0..rows as u32 - 1 {
+            for x in 0..COLUMNS as u32 - 1 {
+                let i = y * COLUMNS as u32 + x;
+                let below = i + COLUMNS as u32;
+                indices.extend([i, below, i + 1, i + 1, below, below + 1]);
+            }
+        }
+
+        unsafe {
+            let compute = if supports_compute() {
+                let integrate = create_compute_program(SRC_COMP_CLOTH_INTEGRATE)?;
+                let relax = create_compute_program(SRC_COMP_CLOTH_RELAX)?;
+                let uniform = |shader: GLuint, name: &std::ffi::CStr| {
+                    gl::GetUniformLocation(shader, name.as_ptr())
+                };
+                Some(ComputeSolver {
+                    u_count: uniform(integrate, c"u_count"),
+                    u_dt: uniform(integrate, c"u_dt"),
+                    u_damping: uniform(integrate, c"u_damping"),
+                    u_gravity: uniform(integrate, c"u_gravity"),
+                    u_wind: uniform(integrate, c"u_wind"),
+                    u_time: uniform(integrate, c"u_time"),
+                    u_grabbed_integrate: uniform(integrate, c"u_grabbed"),
+                    u_grab_target: uniform(integrate, c"u_grab_target"),
+                    integrate,
+                    u_columns: uniform(relax, c"u_columns"),
+                    u_rows: uniform(relax, c"u_rows"),
+                    u_spacing: uniform(relax, c"u_spacing"),
+                    u_shear_stiffness: uniform(relax, c"u_shear_stiffness"),
+                    u_bend_stiffness: uniform(relax, c"u_bend_stiffness"),
+                    u_grabbed_relax: uniform(relax, c"u_grabbed"),
+                    relax,
+                })
+            } else {
+                let (major, minor) = gl_version();
+                println!("cloth: no compute shaders on OpenGL {major}.{minor}, solving on the CPU");
+                None
+            };
+
+            let shader = create_shader_program(SRC_VERT_CLOTH, SRC_FRAG_CLOTH)?;
+            let u_mvp = gl::GetUniformLocation(shader, c"u_mvp".as_ptr());
+            let u_light = gl::GetUniformLocation(shader, c"u_light".as_ptr());
+            let u_columns = gl::GetUniformLocation(shader, c"u_columns".as_ptr());
+            let u_rows = gl::GetUniformLocation(shader, c"u_rows".as_ptr());
+            gl::UseProgram(shader);
+            gl::Uniform1i(gl::GetUniformLocation(shader, c"u_texture".as_ptr()), 0);
+            gl::Uniform1i(gl::GetUniformLocation(shader, c"u_particles".as_ptr()), 1);
+
+            let program = create_geometry_shader_program(
+                SRC_VERT_CLOTH_NORMALS,
+                SRC_GEOM_CLOTH_NORMALS,
+                SRC_FRAG_CLOTH_NORMALS,
+            )?;
+            let normals = NormalsShader {
+                program,
+                u_mvp: gl::GetUniformLocation(program, c"u_mvp".as_ptr()),
+                u_length: gl::GetUniformLocation(program, c"u_length".as_ptr()),
+                u_columns: gl::GetUniformLocation(program, c"u_columns".as_ptr()),
+                u_rows: gl::GetUniformLocation(program, c"u_rows".as_ptr()),
+            };
+            gl::UseProgram(program);
+            gl::Uniform1i(gl::GetUniformLocation(program, c"u_particles".as_ptr()), 1);
+
+            let mut textures = [0; 2];
+            gl::GenTextures(2, textures.as_mut_ptr());
+            let [texture, particles_texture] = textures;
+            upload_texture(
+                texture,
+                image.width(),
+                image.height(),
+                image.as_ptr(),
+                gl::CLAMP_TO_EDGE,
+            )?;
+            // folds squeeze the image together
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as GLint,
+            );
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut buffers = [0; 4];
+            gl::GenBuffers(4, buffers.as_mut_ptr());
+            let [uv_vbo, ebo, particles @ ..] = buffers;
+            buffer_data(gl::ARRAY_BUFFER, uv_vbo, &uvs, gl::STATIC_DRAW);
+            // positions come from the particle buffer through a texture
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 0, 0 as _);
+            gl::EnableVertexAttribArray(0);
+            buffer_data(gl::ELEMENT_ARRAY_BUFFER, ebo, &indices, gl::STATIC_DRAW);
+            gl::BindVertexArray(0);
+
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+
+                cloth: Cloth::grid(COLUMNS, rows, Vec3::ZERO, spacing),
+                spacing,
+                time: 0.0,
+                mouse_world: Vec2::ZERO,
+                grabbed: None,
+                solver: CPU,
+                pins: 0,
+
+                compute,
+                particles,
+                current: 0,
+
+                shader,
+                u_mvp,
+                u_light,
+                u_columns,
+                u_rows,
+                particles_texture,
+                vao,
+                uv_vbo,
+                ebo,
+                n_indices: indices.len() as GLsizei,
+                texture,
+                normals,
+
+                params: Params::new()
+                    .with_enum("solver", CPU, SOLVERS)
+                    .with_i32("iterations", 8, 1, 32)
+                    .with_f32("wind", 0.5, 0.0, 2.0, 0.1)
+                    .with_f32("bend", 0.5, 0.0, 1.0, 0.1)
+                    .with_f32("damping", 0.01, 0.0, 0.1, 0.005)
+                    .with_enum("pins", 0, PINS)
+                    .with_bool("running", true),
+            };
+            scene.reset();
+            Ok(scene)
+        }
+    }
+
+    /// Hangs a flat cloth back up, centered on the origin.
+    unsafe fn reset(&mut self) {
+        self.pins = self.params.variant("pins");
+        self.grabbed = None;
+
+        let size = vec2(WIDTH, (self.cloth.rows - 1) as f32 * self.spacing);
+        let rows = self.cloth.rows;
+        self.cloth = Cloth::grid(COLUMNS, rows, (-size * 0.5).extend(0.0), self.spacing);
+        match PINS[self.pins] {
+            "corners" => {
+                self.cloth.pin(0);
+                self.cloth.pin(COLUMNS - 1);
+            }
+            // every few particles, so that the top edge hangs in scallops
+            _ => {
+                for x in (0..COLUMNS).step_by(7).chain([COLUMNS - 1]) {
+                    self.cloth.pin(x);
+                }
+            }
+        }
+        self.upload();
+    }
+
+    /// The cloth as the compute shaders see it.
+    fn particles(&self) -> Vec<Particle> {
+        let cloth = &self.cloth;
+        (cloth.positions.iter().zip(&cloth.previous))
+            .zip(&cloth.inverse_masses)
+            .map(|((position, previous), &inverse_mass)| Particle {
+                position: position.extend(inverse_mass),
+                previous: previous.extend(0.0),
+            })
+            .collect()
+    }
+
+    /// Puts the CPU's particles in both GPU buffers.
+    unsafe fn upload(&mut self) {
+        let particles = self.particles();
+        for buffer in self.particles {
+            buffer_data(
+                gl::SHADER_STORAGE_BUFFER,
+                buffer,
+                &particles,
+                gl::DYNAMIC_COPY,
+            );
+        }
+        self.current = 0;
+    }
+
+    /// Brings the CPU's particles up to date with the compute solver's.
+    unsafe fn download(&mut self) {
+        if self.solver != COMPUTE {
+            return;
+        }
+
+        let mut particles = vec![Vec4::ZERO; self.cloth.positions.len() * 2];
+        gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, self.particles[self.current]);
+        gl::GetBufferSubData(
+            gl::SHADER_STORAGE_BUFFER,
+            0,
+            mem::size_of_val(particles.as_slice()) as GLsizeiptr,
+            particles.as_mut_ptr() as *mut _,
+        );
+        for (i, particle) in particles.chunks_exact(2).enumerate() {
+            self.cloth.positions[i] = particle[0].truncate();
+            self.cloth.previous[i] = particle[1].truncate();
+        }
+    }
+
+    /// Moves to the solver and pins the parameters ask for.
+    unsafe fn apply_params(&mut self) {
+        if self.params.variant("pins") != self.pins {
+            self.reset();
+        }
+
+        let solver = self.params.variant("solver");
+        if solver == self.solver {
+            return;
+        }
+        if solver == COMPUTE && self.compute.is_none() {
+            println!("cloth: no compute shaders, staying on the CPU");
+            self.params.toggle("solver").unwrap();
+            return;
+        }
+
+        self.download();
+        self.solver = solver;
+        self.upload();
+    }
+
+    fn update_cpu(&mut self, dt: f32) {
+        let wind_strength = WIND * self.params.f32("wind");
+        let time = self.time;
+        let damping = self.params.f32("damping");
+        let bend = self.params.f32("bend");
+
+        // the grabbed particle is pinned under the mouse for the step
+        let grabbed = self.grabbed.map(|(i, z)| {
+            let inverse_mass = mem::take(&mut self.cloth.inverse_masses[i]);
+            let target = self.mouse_world.extend(z);
+            self.cloth.positions[i] = target;
+            self.cloth.previous[i] = target;
+            (i, inverse_mass)
+        });
+
+        self.cloth.integrate(dt, damping, |position| {
+            GRAVITY + wind(wind_strength, position, time)
+        });
+        self.cloth
+            .relax(self.params.i32("iterations") as usize, |kind| match kind {
+                ConstraintKind::Structural => 1.0,
+                ConstraintKind::Shear => SHEAR_STIFFNESS,
+                ConstraintKind::Bend => bend,
+            });
+
+        if let Some((i, inverse_mass)) = grabbed {
+            self.cloth.inverse_masses[i] = inverse_mass;
+        }
+    }
+
+    unsafe fn update_compute(&mut self, dt: f32) {
+        let Some(solver) = &self.compute else {
+            return;
+        };
+
+        let count = self.cloth.positions.len();
+        let groups = count.div_ceil(WORKGROUP_SIZE) as u32;
+        let (grabbed, target) = match self.grabbed {
+            Some((i, z)) => (i as GLint, self.mouse_world.extend(z)),
+            None => (-1, Vec3::ZERO),
+        };
+
+        push_debug_group(c"Cloth integration");
+        let wind = WIND * self.params.f32("wind");
+        gl::UseProgram(solver.integrate);
+        gl::Uniform1ui(solver.u_count, count as u32);
+        gl::Uniform1f(solver.u_dt, dt);
+        gl::Uniform1f(solver.u_damping, self.params.f32("damping"));
+        gl::Uniform3f(solver.u_gravity, GRAVITY.x, GRAVITY.y, GRAVITY.z);
+        gl::Uniform3f(solver.u_wind, wind.x, wind.y, wind.z);
+        gl::Uniform1f(solver.u_time, self.time);
+        gl::Uniform1i(solver.u_grabbed_integrate, grabbed);
+        gl::Uniform3f(solver.u_grab_target, target.x, target.y, target.z);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, self.particles[self.current]);
+        gl::DispatchCompute(groups, 1, 1);
+        gl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+        pop_debug_group();
+
+        push_debug_group(c"Cloth constraints");
+        gl::UseProgram(solver.relax);
+        gl::Uniform1i(solver.u_columns, self.cloth.columns as GLint);
+        gl::Uniform1i(solver.u_rows, self.cloth.rows as GLint);
+        gl::Uniform1f(solver.u_spacing, self.spacing);
+        gl::Uniform1f(solver.u_shear_stiffness, SHEAR_STIFFNESS);
+        gl::Uniform1f(solver.u_bend_stiffness, self.params.f32("bend"));
+        gl::Uniform1i(solver.u_grabbed_relax, grabbed);
+        for _ in 0..self.params.i32("iterations") * JACOBI_FACTOR {
+            let [source, destination] = [self.current, 1 - self.current];
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, self.particles[source]);
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 1, self.particles[destination]);
+            gl::DispatchCompute(groups, 1, 1);
+            gl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+            self.current = destination;
+        }
+        gl::MemoryBarrier(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, 0);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 1, 0);
+        pop_debug_group();
+    }
+}
+
+impl Scene for ClothScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("g" | "G") => self.params.toggle("solver").unwrap(),
+            Key::Character("i") => self.params.step("iterations", 1).unwrap(),
+            Key::Character("I") => self.params.step("iterations", -1).unwrap(),
+            Key::Character("w") => self.params.step("wind", 1).unwrap(),
+            Key::Character("W") => self.params.step("wind", -1).unwrap(),
+            Key::Character("b") => self.params.step("bend", 1).unwrap(),
+            Key::Character("B") => self.params.step("bend", -1).unwrap(),
+            Key::Character("d") => self.params.step("damping", 1).unwrap(),
+            Key::Character("D") => self.params.step("damping", -1).unwrap(),
+            Key::Character("c" | "C") => self.params.toggle("pins").unwrap(),
+            Key::Character("p" | "P") => self.params.toggle("running").unwrap(),
+            Key::Character("r" | "R") => unsafe { self.reset() },
+            _ => return,
+        }
+
+        println!("cloth config: {}", self.params);
+    }
+
+    /// Grabs the particle closest to the mouse while the right button is
+    /// held. Returns whether the scene handled the button.
+    fn on_event(&mut self, camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if button != MouseButton::Right {
+            return false;
+        }
+
+        self.grabbed = None;
+        if state == ElementState::Pressed {
+            unsafe { self.download() };
+            self.mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport);
+            self.grabbed = (self.cloth.nearest(self.mouse_world, GRAB_RADIUS))
+                .map(|i| (i, self.cloth.positions[i].z));
+        }
+        true
+    }
+
+    /// Moves the cloth by one step under gravity and the wind, then solves
+    /// its constraints.
+    fn update(&mut self, dt: f32) {
+        unsafe { self.apply_params() };
+        if !self.params.bool("running") {
+            return;
+        }
+
+        profiling::cpu_zone!("cloth update");
+
+        self.time += dt;
+        match self.solver {
+            COMPUTE => unsafe { self.update_compute(dt) },
+            _ => self.update_cpu(dt),
+        }
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("cloth draw");
+
+        self.mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport);
+        let theme = theme::current();
+        let light = vec3(-0.4, -0.6, -1.0).normalize();
+
+        unsafe {
+            if self.solver == CPU {
+                buffer_data(
+                    gl::ARRAY_BUFFER,
+                    self.particles[0],
+                    &self.particles(),
+                    gl::STREAM_DRAW,
+                );
+                self.current = 0;
+            }
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            let [r, g, b, a] = theme.clear;
+            gl::ClearColor(r, g, b, a);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            gl::UseProgram(self.shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+            gl::Uniform3f(self.u_light, light.x, light.y, light.z);
+            gl::Uniform1i(self.u_columns, self.cloth.columns as GLint);
+            gl::Uniform1i(self.u_rows, self.cloth.rows as GLint);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_BUFFER, self.particles_texture);
+            gl::TexBuffer(
+                gl::TEXTURE_BUFFER,
+                gl::RGBA32F,
+                self.particles[self.current],
+            );
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            gl::BindVertexArray(self.vao);
+            gl::DrawElements(gl::TRIANGLES, self.n_indices, gl::UNSIGNED_INT, 0 as _);
+
+            if render_state::show_normals() {
+                let normals = &self.normals;
+                let length = NORMAL_LENGTH * self.spacing;
+                gl::UseProgram(normals.program);
+                gl::UniformMatrix4fv(normals.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+                gl::Uniform1f(normals.u_length, length);
+                gl::Uniform1i(normals.u_columns, self.cloth.columns as GLint);
+                gl::Uniform1i(normals.u_rows, self.cloth.rows as GLint);
+                let count = self.cloth.columns * self.cloth.rows;
+                gl::DrawArrays(gl::POINTS, 0, count as GLsizei);
+            }
+            gl::BindVertexArray(0);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_BUFFER, 0);
+            gl::ActiveTexture(gl::TEXTURE0);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+/// Wind at `position`, rippling across the cloth and gusting over time. The
+/// same as `wind` in cloth-integrate.comp.
+fn wind(wind: Vec3, position: Vec3, time: f32) -> Vec3 {
+    let ripple = 0.6 + 0.4 * (0.02 * position.x + 0.015 * position.y + 2.0 * time).sin();
+    let gust = 0.7 + 0.3 * (0.7 * time).sin();
+    wind * ripple * gust
+}
+
+impl Drop for ClothScene {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(solver) = &self.compute {
+                gl::DeleteProgram(solver.integrate);
+                gl::DeleteProgram(solver.relax);
+            }
+            gl::DeleteProgram(self.shader);
+            gl::DeleteProgram(self.normals.program);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.uv_vbo, self.ebo, self.particles[0], self.particles[1]]);
+            delete_textures(&[self.texture, self.particles_texture]);
+        }
+    }
+}
@@ -0,0 +1,501 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::{f32::consts::TAU, mem, time::Instant};
+
+use gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
+use glam::{Mat4, UVec2, Vec2, Vec4};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::dpi::PhysicalSize;
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_compute_program, create_shader_program, delete_buffers, delete_textures,
+    gl_version, supports_compute,
+};
+use crate::error::GlError;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::technique_timings::TechniqueTimings;
+use super::{Scene, SRC_COMP_LIGHT_BINNING, SRC_FRAG_LIGHTING, SRC_VERT_LIGHTING};
+
+const MAX_LIGHTS: usize = 8192;
+
+/// Side of a cluster, in pixels. Must match the shaders.
+const CLUSTER_PIXELS: u32 = 32;
+/// Lights beyond this many in a cluster are dropped. Must match the shaders.
+const MAX_LIGHTS_PER_CLUSTER: usize = 128;
+
+/// Quads per side of the lit field.
+const COLUMNS: usize = 256;
+const SPACING: f32 = 24.0;
+const QUAD_SIZE: f32 = 20.0;
+const FIELD_SIZE: f32 = COLUMNS as f32 * SPACING;
+
+const BINNING_MODES: &[&str] = &["gpu", "cpu"];
+const TECHNIQUES: &[&str] = &["gpu binning", "cpu binning", "shading"];
+
+/// A field of quads lit per pixel by thousands of colored point lights
+/// hovering above it.
+///
+/// The screen is split into clusters of [`CLUSTER_PIXELS`]², and every frame
+/// each light is binned into the clusters its radius reaches, so that shading
+/// a pixel only loops over the lights of its cluster. Binning happens in a
+/// compute pass with one workgroup per cluster, or on the CPU without compute
+/// shaders. The fragment shader reads everything through buffer textures so
+/// that both paths share it.
+pub struct ClusteredLightingScene {
+    matrix: Mat4,
+    viewport: Vec2,
+    pixels_per_unit: f32,
+    clusters: UVec2,
+
+    /// `None` when compute shaders aren't supported.
+    binning_shader: Option<BinningShader>,
+    shader: GLuint,
+    u_mvp: GLint,
+    u_clusters_x: GLint,
+    u_heatmap: GLint,
+    vao: GLuint,
+
+    lights_buffer: GLuint,
+    counts_buffer: GLuint,
+    cluster_lights_buffer: GLuint,
+    /// Buffer textures over the three buffers above, in the same order.
+    buffer_textures: [GLuint; 3],
+
+    seeds: Vec<LightSeed>,
+    lights: Vec<Light>,
+    cpu_counts: Vec<u32>,
+    cpu_cluster_lights: Vec<u32>,
+
+    start: Instant,
+    timings: TechniqueTimings,
+
+    params: Params,
+}
+
+struct BinningShader {
+    program: GLuint,
+    u_mvp: GLint,
+    u_viewport: GLint,
+    u_pixels_per_unit: GLint,
+    u_light_count: GLint,
+}
+
+/// Layout of a light in the lights buffer, as two `vec4`s.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct Light {
+    /// xy on the plane, z above it, w being the radius.
+    position_radius: [f32; 4],
+    color: [f32; 4],
+}
+
+/// Where a light wanders around and how.
+struct LightSeed {
+    anchor: Vec2,
+    orbit: f32,
+    speed: f32,
+    phase: f32,
+    height: f32,
+    radius: f32,
+    color: [f32; 4],
+}
+
+impl LightSeed {
+    fn random(rng: &mut impl Rng) -> Self {
+        let hue = rng.gen_range(0.0..TAU);
+        let color =
+            [0.0, TAU / 3.0, 2.0 * TAU / 3.0].map(|offset| 0.6 + 0.4 * (hue + offset).cos());
+
+        Self {
+            anchor: Vec2::new(
+                rng.gen_range(-0.5..0.5) * FIELD_SIZE,
+                rng.gen_range(-0.5..0.5) * FIELD_SIZE,
+            ),
+            orbit: rng.gen_range(20.0..200.0),
+            speed: rng.gen_range(-1.0..1.0),
+            phase: rng.gen_range(0.0..TAU),
+            height: rng.gen_range(10.0..60.0),
+            radius: rng.gen_range(60.0..200.0),
+            color: [color[0], color[1], color[2], 1.0],
+        }
+    }
+
+    fn light(&self, time: f32) -> Light {
+        let angle = self.phase + self.speed * time;
+        let position = self.anchor + Vec2::from_angle(angle) * self.orbit;
+
+        Light {
+            position_radius: [position.x, position.y, self.height, self.radius],
+            color: self.color,
+        }
+    }
+}
+
+impl ClusteredLightingScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(size: PhysicalSize<u32>, seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let seeds = (0..MAX_LIGHTS)
+            .map(|_| LightSeed::random(&mut rng))
+            .collect::<Vec<_>>();
+
+        unsafe {
+            let binning_shader = if supports_compute() {
+                let program = create_compute_program(SRC_COMP_LIGHT_BINNING)?;
+                let uniform =
+                    |name: &std::ffi::CStr| gl::GetUniformLocation(program, name.as_ptr());
+                Some(BinningShader {
+                    program,
+                    u_mvp: uniform(c"u_mvp"),
+                    u_viewport: uniform(c"u_viewport"),
+                    u_pixels_per_unit: uniform(c"u_pixels_per_unit"),
+                    u_light_count: uniform(c"u_light_count"),
+                })
+            } else {
+                let (major, minor) = gl_version();
+                println!(
+                    "clustered-lighting: no compute shaders on OpenGL {major}.{minor}, binning on the CPU"
+                );
+                None
+            };
+
+            let shader = create_shader_program(SRC_VERT_LIGHTING, SRC_FRAG_LIGHTING)?;
+            let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(shader, name.as_ptr());
+            gl::UseProgram(shader);
+            gl::Uniform1i(uniform(c"u_columns"), COLUMNS as GLint);
+            gl::Uniform1f(uniform(c"u_spacing"), SPACING);
+            gl::Uniform1f(uniform(c"u_quad_size"), QUAD_SIZE);
+            gl::Uniform1f(uniform(c"u_ambient"), 0.04);
+            gl::Uniform1i(uniform(c"u_lights"), 0);
+            gl::Uniform1i(uniform(c"u_cluster_counts"), 1);
+            gl::Uniform1i(uniform(c"u_cluster_lights"), 2);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+
+            let mut buffers = [0; 3];
+            gl::GenBuffers(3, buffers.as_mut_ptr());
+            let [lights_buffer, counts_buffer, cluster_lights_buffer] = buffers;
+
+            let lights = vec![Light::default(); MAX_LIGHTS];
+            buffer_data(
+                gl::TEXTURE_BUFFER,
+                lights_buffer,
+                lights.as_slice(),
+                gl::STREAM_DRAW,
+            );
+
+            let mut buffer_textures = [0; 3];
+            gl::GenTextures(3, buffer_textures.as_mut_ptr());
+
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+                pixels_per_unit: 1.0,
+                clusters: UVec2::ZERO,
+
+                binning_shader,
+                u_mvp: uniform(c"u_mvp"),
+                u_clusters_x: uniform(c"u_clusters_x"),
+                u_heatmap: uniform(c"u_heatmap"),
+                shader,
+                vao,
+
+                lights_buffer,
+                counts_buffer,
+                cluster_lights_buffer,
+                buffer_textures,
+
+                seeds,
+                lights,
+                cpu_counts: Vec::new(),
+                cpu_cluster_lights: Vec::new(),
+
+                start: Instant::now(),
+                timings: TechniqueTimings::new(TECHNIQUES),
+
+                params: Params::new()
+                    .with_f32("lights", 2048.0, 256.0, MAX_LIGHTS as f32, 256.0)
+                    .with_enum("binning", 0, BINNING_MODES)
+                    .with_bool("heatmap", false),
+            };
+
+            if scene.binning_shader.is_none() {
+                scene.params.toggle("binning").unwrap();
+            }
+            scene.resize_clusters(size.width, size.height);
+
+            Ok(scene)
+        }
+    }
+
+    unsafe fn bin_on_gpu(&self, light_count: usize) {
+        let shader = self.binning_shader.as_ref().unwrap();
+
+        gl::UseProgram(shader.program);
+        gl::UniformMatrix4fv(shader.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+        gl::Uniform2f(shader.u_viewport, self.viewport.x, self.viewport.y);
+        gl::Uniform1f(shader.u_pixels_per_unit, self.pixels_per_unit);
+        gl::Uniform1ui(shader.u_light_count, light_count as u32);
+
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, self.lights_buffer);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 1, self.counts_buffer);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 2, self.cluster_lights_buffer);
+
+        gl::DispatchCompute(self.clusters.x, self.clusters.y, 1);
+        gl::MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT | gl::BUFFER_UPDATE_BARRIER_BIT);
+
+        for binding in 0..3 {
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, 0);
+        }
+    }
+
+    unsafe fn bin_on_cpu(&mut self, light_count: usize) {
+        self.cpu_counts.fill(0);
+
+        let cluster_size = Vec2::splat(CLUSTER_PIXELS as f32);
+        for (i, light) in self.lights[..light_count].iter().enumerate() {
+            let [x, y, _, radius] = light.position_radius;
+            let clip = self.matrix * Vec4::new(x, y, 0.0, 1.0);
+            let center = (clip.truncate().truncate() * 0.5 + 0.5) * self.viewport;
+            let radius = radius * self.pixels_per_unit;
+
+            // only visit the clusters the light's bounding square overlaps
+            let last = self.clusters.as_vec2() - 1.0;
+            let min = ((center - radius) / cluster_size).floor().max(Vec2::ZERO);
+            let max = ((center + radius) / cluster_size).floor().min(last);
+            if min.cmpgt(max).any() {
+                continue;
+            }
+
+            for cy in min.y as u32..=max.y as u32 {
+                for cx in min.x as u32..=max.x as u32 {
+                    let cluster_min = Vec2::new(cx as f32, cy as f32) * cluster_size;
+                    let closest = center.clamp(cluster_min, cluster_min + cluster_size);
+                    if closest.distance(center) >= radius {
+                        continue;
+                    }
+
+                    let index = (cy * self.clusters.x + cx) as usize;
+                    let slot = self.cpu_counts[index] as usize;
+                    if slot < MAX_LIGHTS_PER_CLUSTER {
+                        self.cpu_cluster_lights[index * MAX_LIGHTS_PER_CLUSTER + slot] = i as u32;
+                        self.cpu_counts[index] += 1;
+                    }
+                }
+            }
+        }
+
+        buffer_data(
+            gl::TEXTURE_BUFFER,
+            self.counts_buffer,
+            self.cpu_counts.as_slice(),
+            gl::STREAM_DRAW,
+        );
+        buffer_data(
+            gl::TEXTURE_BUFFER,
+            self.cluster_lights_buffer,
+            self.cpu_cluster_lights.as_slice(),
+            gl::STREAM_DRAW,
+        );
+        gl::BindBuffer(gl::TEXTURE_BUFFER, 0);
+    }
+
+    /// Average and maximum number of lights per cluster, read back from the
+    /// GPU when it did the binning.
+    unsafe fn occupancy(&mut self, on_gpu: bool) -> (f32, u32) {
+        if on_gpu {
+            // stalls until binning is done, which is fine once a second
+            gl::BindBuffer(gl::TEXTURE_BUFFER, self.counts_buffer);
+            gl::GetBufferSubData(
+                gl::TEXTURE_BUFFER,
+                0,
+                mem::size_of_val(self.cpu_counts.as_slice()) as GLsizeiptr,
+                self.cpu_counts.as_mut_ptr().cast(),
+            );
+            gl::BindBuffer(gl::TEXTURE_BUFFER, 0);
+        }
+
+        let total = self.cpu_counts.iter().sum::<u32>();
+        let max = self.cpu_counts.iter().copied().max().unwrap_or(0);
+        (total as f32 / self.cpu_counts.len().max(1) as f32, max)
+    }
+
+    /// Reallocates the cluster buffers for a viewport of `width`×`height`.
+    unsafe fn resize_clusters(&mut self, width: u32, height: u32) {
+        let clusters = UVec2::new(
+            width.max(1).div_ceil(CLUSTER_PIXELS),
+            height.max(1).div_ceil(CLUSTER_PIXELS),
+        );
+        if clusters == self.clusters {
+            return;
+        }
+        self.clusters = clusters;
+
+        let n_clusters = (clusters.x * clusters.y) as usize;
+        self.cpu_counts = vec![0; n_clusters];
+        self.cpu_cluster_lights = vec![0; n_clusters * MAX_LIGHTS_PER_CLUSTER];
+
+        buffer_data(
+            gl::TEXTURE_BUFFER,
+            self.counts_buffer,
+            self.cpu_counts.as_slice(),
+            gl::DYNAMIC_DRAW,
+        );
+        buffer_data(
+            gl::TEXTURE_BUFFER,
+            self.cluster_lights_buffer,
+            self.cpu_cluster_lights.as_slice(),
+            gl::DYNAMIC_DRAW,
+        );
+
+        let formats = [gl::RGBA32F, gl::R32UI, gl::R32UI];
+        let buffers = [
+            self.lights_buffer,
+            self.counts_buffer,
+            self.cluster_lights_buffer,
+        ];
+        for ((&texture, format), buffer) in self.buffer_textures.iter().zip(formats).zip(buffers) {
+            gl::BindTexture(gl::TEXTURE_BUFFER, texture);
+            gl::TexBuffer(gl::TEXTURE_BUFFER, format, buffer);
+        }
+        gl::BindTexture(gl::TEXTURE_BUFFER, 0);
+        gl::BindBuffer(gl::TEXTURE_BUFFER, 0);
+    }
+}
+
+impl Scene for ClusteredLightingScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("l") => self.params.step("lights", 1).unwrap(),
+            Key::Character("L") => self.params.step("lights", -1).unwrap(),
+            Key::Character("c") => {
+                if self.binning_shader.is_none() {
+                    println!("clustered-lighting: compute shaders unsupported, staying on the CPU");
+                    return;
+                }
+                self.params.toggle("binning").unwrap();
+            }
+            Key::Character("h") => self.params.toggle("heatmap").unwrap(),
+            _ => return,
+        }
+
+        println!("clustered-lighting config: {}", self.params);
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("clustered lighting draw");
+
+        let light_count = self.params.f32("lights") as usize;
+        let on_gpu = self.binning_shader.is_some() && self.params.variant("binning") == 0;
+
+        let time = self.start.elapsed().as_secs_f32();
+        for (light, seed) in self.lights.iter_mut().zip(&self.seeds[..light_count]) {
+            *light = seed.light(time);
+        }
+
+        unsafe {
+            gl::BindBuffer(gl::TEXTURE_BUFFER, self.lights_buffer);
+            gl::BufferSubData(
+                gl::TEXTURE_BUFFER,
+                0,
+                (light_count * mem::size_of::<Light>()) as GLsizeiptr,
+                self.lights.as_ptr().cast(),
+            );
+            gl::BindBuffer(gl::TEXTURE_BUFFER, 0);
+
+            if on_gpu {
+                self.timings.begin(0);
+                self.bin_on_gpu(light_count);
+            } else {
+                self.timings.begin(1);
+                self.bin_on_cpu(light_count);
+            }
+            self.timings.end();
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            self.timings.begin(2);
+            gl::UseProgram(self.shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+            gl::Uniform1i(self.u_clusters_x, self.clusters.x as GLint);
+            gl::Uniform1i(self.u_heatmap, self.params.bool("heatmap") as GLint);
+
+            for (unit, &texture) in self.buffer_textures.iter().enumerate() {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as GLuint);
+                gl::BindTexture(gl::TEXTURE_BUFFER, texture);
+            }
+
+            gl::BindVertexArray(self.vao);
+            let count = (COLUMNS * COLUMNS) as GLsizei;
+            gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, count);
+            gl::BindVertexArray(0);
+
+            for unit in (0..self.buffer_textures.len()).rev() {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as GLuint);
+                gl::BindTexture(gl::TEXTURE_BUFFER, 0);
+            }
+            self.timings.end();
+
+            if let Some(summary) = self.timings.end_frame() {
+                let (average, max) = self.occupancy(on_gpu);
+                println!(
+                    "clustered-lighting: {light_count} lights, {} clusters, {average:.1} avg / {max} max lights per cluster | {summary}",
+                    self.clusters.x * self.clusters.y,
+                );
+            }
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe {
+            gl::Viewport(0, 0, width, height);
+            self.resize_clusters(width as u32, height as u32);
+        }
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        self.pixels_per_unit = camera.scale.x;
+        Ok(())
+    }
+}
+
+impl Drop for ClusteredLightingScene {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(shader) = &self.binning_shader {
+                gl::DeleteProgram(shader.program);
+            }
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_textures(&self.buffer_textures);
+            delete_buffers(&[
+                self.lights_buffer,
+                self.counts_buffer,
+                self.cluster_lights_buffer,
+            ]);
+        }
+    }
+}
@@ -0,0 +1,416 @@
+use std::mem;
+use std::time::{Duration, Instant};
+
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, Mat4, UVec2, Vec2};
+use image::RgbaImage;
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::block_compression::{self, block_aligned};
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_shader_program, delete_buffers, delete_textures, gl_version, has_extension,
+    upload_texture,
+};
+use crate::error::GlError;
+use crate::gl_ext;
+use crate::gpu_memory::{self, Category, Object};
+use crate::input_image::InputImage;
+use crate::params::{Params, Value};
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::{Scene, SceneEvent, SRC_FRAG_COMPRESSION, SRC_VERT_QUAD};
+
+/// Variants of the `format` parameter, in order.
+const FORMATS: &[&str] = &["bc1", "bc7"];
+/// Variants of the `view` parameter, in order.
+const VIEWS: &[&str] = &["split", "heatmap"];
+
+const TEXT_SIZE: f32 = 14.0;
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// The input image compressed to a block format on the CPU and uploaded as
+/// is, for the driver to decode while sampling.
+struct Compressed {
+    /// `None` if the driver doesn't support the format.
+    texture: Option<GLuint>,
+    /// Size of the texture as the driver reports it.
+    bytes: usize,
+    /// Against the original, as decoded on the CPU.
+    psnr: f32,
+    encoding: Duration,
+}
+
+impl Compressed {
+    unsafe fn new(
+        image: &RgbaImage,
+        format: GLenum,
+        supported: bool,
+        encode: fn(&RgbaImage) -> Vec<u8>,
+        decode: fn(&[u8], u32, u32) -> RgbaImage,
+    ) -> Self {
+        let start = Instant::now();
+        let data = encode(image);
+        let encoding = start.elapsed();
+        let psnr = block_compression::psnr(image, &decode(&data, image.width(), image.height()));
+
+        let mut bytes = data.len();
+        let texture = supported.then(|| {
+            let mut texture = 0;
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::CompressedTexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                format,
+                image.width() as GLsizei,
+                image.height() as GLsizei,
+                0,
+                data.len() as GLsizei,
+                data.as_ptr() as *const _,
+            );
+            for (parameter, value) in [
+                (gl::TEXTURE_MIN_FILTER, gl::LINEAR),
+                (gl::TEXTURE_MAG_FILTER, gl::LINEAR),
+                (gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE),
+                (gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE),
+            ] {
+                gl::TexParameteri(gl::TEXTURE_2D, parameter, value as GLint);
+            }
+
+            let mut size = 0;
+            gl::GetTexLevelParameteriv(
+                gl::TEXTURE_2D,
+                0,
+                gl::TEXTURE_COMPRESSED_IMAGE_SIZE,
+                &mut size,
+            );
+            bytes = size as usize;
+            gpu_memory::record(Object::Texture(texture), Category::Textures, bytes);
+            texture
+        });
+
+        Self {
+            texture,
+            bytes,
+            psnr,
+            encoding,
+        }
+    }
+}
+
+/// The input image next to itself compressed to BC1 or BC7, encoded on the
+/// CPU, to see what block compression costs in quality for what it saves in
+/// memory.
+///
+/// The split between the two is dragged with the mouse, and the heatmap view
+/// shows how far apart they are with some gain, as the errors are small. The
+/// size of each texture comes from the driver, next to the PSNR and how long
+/// encoding took.
+pub struct CompressionScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    image_size: UVec2,
+    /// Whether the left button is held, moving the split with the mouse.
+    dragging: bool,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_split: GLint,
+    u_heatmap: GLint,
+    u_gain: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    original: GLuint,
+    /// In the order of [`FORMATS`].
+    compressed: [Compressed; 2],
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+impl CompressionScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(input: &InputImage, text: Option<SharedText>) -> Result<Self, GlError> {
+        profiling::cpu_zone!("compression new");
+
+        let image = block_aligned(&input.image);
+        let image_size = uvec2(image.width(), image.height());
+
+        let half = image_size.as_vec2() / 2.0;
+        let vertex = |x: f32, y: f32| Vertex {
+            position: (vec2(x, y) * 2.0 - 1.0) * half,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        unsafe {
+            let mut original = 0;
+            gl::GenTextures(1, &mut original);
+            upload_texture(
+                original,
+                image.width(),
+                image.height(),
+                image.as_ptr(),
+                gl::CLAMP_TO_EDGE,
+            )?;
+
+            let bc1 = Compressed::new(
+                &image,
+                gl_ext::COMPRESSED_RGB_S3TC_DXT1,
+                has_extension("GL_EXT_texture_compression_s3tc"),
+                block_compression::encode_bc1,
+                block_compression::decode_bc1,
+            );
+            let bc7 = Compressed::new(
+                &image,
+                gl::COMPRESSED_RGBA_BPTC_UNORM,
+                gl_version() >= (4, 2) || has_extension("GL_ARB_texture_compression_bptc"),
+                block_compression::encode_bc7,
+                block_compression::decode_bc7,
+            );
+            for (name, compressed) in FORMATS.iter().zip([&bc1, &bc7]) {
+                if compressed.texture.is_none() {
+                    println!("compression: {name} unsupported by the driver");
+                }
+            }
+
+            let shader = create_shader_program(SRC_VERT_QUAD, SRC_FRAG_COMPRESSION)?;
+            let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(shader, name.as_ptr());
+            gl::UseProgram(shader);
+            gl::Uniform1i(uniform(c"u_original"), 0);
+            gl::Uniform1i(uniform(c"u_compressed"), 1);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let params = Params::new()
+                .with_enum("format", 1, FORMATS)
+                .with_enum("view", 0, VIEWS)
+                .with_f32("split", 0.5, 0.0, 1.0, 0.05)
+                .with_f32("gain", 8.0, 1.0, 64.0, 1.0);
+
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+
+                image_size,
+                dragging: false,
+
+                u_mvp: uniform(c"u_mvp"),
+                u_split: uniform(c"u_split"),
+                u_heatmap: uniform(c"u_heatmap"),
+                u_gain: uniform(c"u_gain"),
+                shader,
+                vao,
+                vbo,
+                original,
+                compressed: [bc1, bc7],
+
+                text,
+
+                params,
+            })
+        }
+    }
+
+    /// Texture coordinates of the image under the mouse.
+    fn uv_at(&self, camera: &Camera, mouse_pos: Vec2) -> Vec2 {
+        let world = camera.pointer_to_pos(mouse_pos, self.viewport);
+        let size = self.image_size.as_vec2();
+        (world + size / 2.0) / size
+    }
+
+    fn drag(&mut self, camera: &Camera, mouse_pos: Vec2) {
+        let split = self.uv_at(camera, mouse_pos).x.clamp(0.0, 1.0);
+        self.params
+            .set("split", Value::Number(split as f64))
+            .unwrap();
+    }
+}
+
+impl Scene for CompressionScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let (name, direction) = match keycode.as_ref() {
+            Key::Character("f") => ("format", 1),
+            Key::Character("F") => ("format", -1),
+            Key::Character("v") => ("view", 1),
+            Key::Character("V") => ("view", -1),
+            Key::Character("g") => ("gain", 1),
+            Key::Character("G") => ("gain", -1),
+            _ => return,
+        };
+        self.params.step(name, direction).unwrap();
+
+        println!("compression config: {}", self.params);
+    }
+
+    /// Starts moving the split with the left button over the image. Returns
+    /// whether the click was used, in which case the camera shouldn't pan.
+    fn on_event(&mut self, camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if button != MouseButton::Left {
+            return false;
+        }
+        if state == ElementState::Released {
+            self.dragging = false;
+            return false;
+        }
+
+        let uv = self.uv_at(camera, mouse_pos);
+        if uv.cmplt(Vec2::ZERO).any() || uv.cmpgt(Vec2::ONE).any() {
+            return false;
+        }
+        self.dragging = true;
+        self.drag(camera, mouse_pos);
+        true
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("compression draw");
+
+        if self.dragging {
+            self.drag(camera, mouse_pos);
+        }
+
+        let format = self.params.variant("format");
+        let compressed = self.compressed[format].texture.unwrap_or(self.original);
+        let heatmap = VIEWS[self.params.variant("view")] == "heatmap";
+        let theme = theme::current();
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            gl::UseProgram(self.shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+            gl::Uniform1f(self.u_split, self.params.f32("split"));
+            gl::Uniform1i(self.u_heatmap, heatmap as GLint);
+            gl::Uniform1f(self.u_gain, self.params.f32("gain"));
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, compressed);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.original);
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::BindVertexArray(0);
+
+            // what each version weighs and how close it is to the original
+            let original_bytes = self.image_size.x as usize * self.image_size.y as usize * 4;
+            let mut lines = vec![format!("  rgba8  {}", mebibytes(original_bytes))];
+            for (i, (name, compressed)) in FORMATS.iter().zip(&self.compressed).enumerate() {
+                let marker = if i == format { ">" } else { " " };
+                let ratio = original_bytes as f32 / compressed.bytes as f32;
+                let mut line = format!(
+                    "{marker} {name}  {}  {ratio:.0}:1  {:.1} dB  encoded in {} ms",
+                    mebibytes(compressed.bytes),
+                    compressed.psnr,
+                    compressed.encoding.as_millis()
+                );
+                if compressed.texture.is_none() {
+                    line += "  (unsupported, showing the original)";
+                }
+                lines.push(line);
+            }
+            let table = lines.join("\n");
+
+            let Some(text) = &self.text else {
+                return Ok(());
+            };
+            let mut text = text.borrow_mut();
+            let extent = text.measure(&table, TEXT_SIZE, None);
+            let min = Vec2::splat(MARGIN);
+            let max = min + extent + PADDING * 2.0;
+            text.queue_rect(min, max, theme.hud_background);
+            let position = min + PADDING;
+            text.queue(&table, position, TEXT_SIZE, None, theme.hud_text);
+            text.draw(0, self.viewport);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+impl Drop for CompressionScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+            delete_textures(&[self.original]);
+            for compressed in &self.compressed {
+                if let Some(texture) = compressed.texture {
+                    delete_textures(&[texture]);
+                }
+            }
+        }
+    }
+}
+
+fn mebibytes(bytes: usize) -> String {
+    format!("{:.2} MiB", bytes as f32 / (1024.0 * 1024.0))
+}
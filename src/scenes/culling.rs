@@ -0,0 +1,450 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
+
+use gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
+use glam::{Mat4, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_compute_program, create_shader_program, delete_buffers, gl_version,
+    supports_compute,
+};
+use crate::error::GlError;
+use crate::gpu_timer::GpuTimer;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::{Scene, SRC_COMP_CULL, SRC_FRAG_SPRITE, SRC_VERT_CULL};
+
+const N_OBJECTS: usize = 50_000;
+
+/// Side of the square the objects are scattered in.
+const AREA_SIZE: f32 = 20_000.0;
+
+/// Must match `local_size_x` in cull.comp.
+const WORKGROUP_SIZE: usize = 256;
+
+const CULLING_MODES: &[&str] = &["gpu", "cpu"];
+
+/// Objects frustum-culled against the view every frame, then drawn as
+/// instances of a single quad.
+///
+/// On the GPU, a compute pass appends the visible objects to a compacted
+/// buffer, counting them with an atomic counter that aliases the instance
+/// count of a `glDrawElementsIndirect` command, so the CPU never learns how
+/// many objects get drawn. On the CPU, the same compaction happens in a loop
+/// before uploading the result. Without compute shaders only the CPU path is
+/// available.
+pub struct CullingScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    /// `None` when compute shaders aren't supported.
+    cull_shader: Option<CullShader>,
+    draw_shader: GLuint,
+    u_mvp: GLint,
+
+    vao: GLuint,
+    quad_vbo: GLuint,
+    ebo: GLuint,
+    objects_ssbo: GLuint,
+    visible_vbo: GLuint,
+    indirect_buffer: GLuint,
+
+    objects: Vec<Object>,
+    visible: Vec<Object>,
+
+    gpu_timer: GpuTimer,
+    cpu_time: Duration,
+    frames: u32,
+    last_report: Instant,
+
+    params: Params,
+}
+
+struct CullShader {
+    program: GLuint,
+    u_view_min: GLint,
+    u_view_max: GLint,
+    u_count: GLint,
+}
+
+/// Layout of the commands read by `glDrawElementsIndirect`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct DrawElementsIndirectCommand {
+    count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    base_instance: u32,
+}
+
+impl CullingScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let objects = (0..N_OBJECTS)
+            .map(|_| Object {
+                center: Vec2::new(
+                    rng.gen_range(-0.5..0.5) * AREA_SIZE,
+                    rng.gen_range(-0.5..0.5) * AREA_SIZE,
+                ),
+                half_size: Vec2::new(rng.gen_range(4.0..40.0), rng.gen_range(4.0..40.0)),
+                color: [
+                    rng.gen_range(0.3..1.0),
+                    rng.gen_range(0.3..1.0),
+                    rng.gen_range(0.3..1.0),
+                    0.9,
+                ],
+            })
+            .collect::<Vec<_>>();
+
+        unsafe {
+            let cull_shader = if supports_compute() {
+                let program = create_compute_program(SRC_COMP_CULL)?;
+                Some(CullShader {
+                    program,
+                    u_view_min: gl::GetUniformLocation(program, c"u_view_min".as_ptr()),
+                    u_view_max: gl::GetUniformLocation(program, c"u_view_max".as_ptr()),
+                    u_count: gl::GetUniformLocation(program, c"u_count".as_ptr()),
+                })
+            } else {
+                let (major, minor) = gl_version();
+                println!(
+                    "culling: no compute shaders on OpenGL {major}.{minor}, culling on the CPU"
+                );
+                None
+            };
+
+            let draw_shader = create_shader_program(SRC_VERT_CULL, SRC_FRAG_SPRITE)?;
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            let mut buffers = [0; 5];
+            gl::GenBuffers(5, buffers.as_mut_ptr());
+            let [quad_vbo, ebo, objects_ssbo, visible_vbo, indirect_buffer] = buffers;
+
+            gl::BindVertexArray(vao);
+
+            let corners: [[f32; 2]; 4] = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]];
+            buffer_data(gl::ARRAY_BUFFER, quad_vbo, &corners, gl::STATIC_DRAW);
+            gl::VertexAttribPointer(3, 2, gl::FLOAT, gl::FALSE, 0, 0 as _);
+            gl::EnableVertexAttribArray(3);
+
+            let indices: [u32; 6] = [0, 1, 2, 2, 1, 3];
+            buffer_data(gl::ELEMENT_ARRAY_BUFFER, ebo, &indices, gl::STATIC_DRAW);
+
+            // big enough for every object to be visible
+            buffer_data(
+                gl::ARRAY_BUFFER,
+                visible_vbo,
+                objects.as_slice(),
+                gl::DYNAMIC_DRAW,
+            );
+
+            let size_f32 = mem::size_of::<f32>() as GLsizei;
+            let size_object = mem::size_of::<Object>() as GLsizei;
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, size_object, 0 as _);
+            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, size_object, (2 * size_f32) as _);
+            gl::VertexAttribPointer(2, 4, gl::FLOAT, gl::FALSE, size_object, (4 * size_f32) as _);
+            for location in 0..3 {
+                gl::EnableVertexAttribArray(location);
+                gl::VertexAttribDivisor(location, 1);
+            }
+
+            gl::BindVertexArray(0);
+
+            if cull_shader.is_some() {
+                buffer_data(
+                    gl::SHADER_STORAGE_BUFFER,
+                    objects_ssbo,
+                    objects.as_slice(),
+                    gl::STATIC_DRAW,
+                );
+
+                let command = DrawElementsIndirectCommand {
+                    count: indices.len() as u32,
+                    instance_count: 0,
+                    first_index: 0,
+                    base_vertex: 0,
+                    base_instance: 0,
+                };
+                buffer_data(
+                    gl::DRAW_INDIRECT_BUFFER,
+                    indirect_buffer,
+                    &[command],
+                    gl::DYNAMIC_DRAW,
+                );
+                gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, 0);
+            }
+
+            let culling = if cull_shader.is_some() { 0 } else { 1 };
+            let params = Params::new()
+                .with_enum("culling", culling, CULLING_MODES)
+                .with_bool("shrink", false);
+
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+
+                cull_shader,
+                u_mvp: gl::GetUniformLocation(draw_shader, c"u_mvp".as_ptr()),
+                draw_shader,
+
+                vao,
+                quad_vbo,
+                ebo,
+                objects_ssbo,
+                visible_vbo,
+                indirect_buffer,
+
+                visible: Vec::with_capacity(N_OBJECTS),
+                objects,
+
+                gpu_timer: GpuTimer::new(),
+                cpu_time: Duration::ZERO,
+                frames: 0,
+                last_report: Instant::now(),
+
+                params,
+            })
+        }
+    }
+
+    /// World-space bounds of what the camera sees, or of the middle of the
+    /// screen when shrinking to make culling visible.
+    fn view_bounds(&self, camera: &Camera) -> (Vec2, Vec2) {
+        let (start, end) = if self.params.bool("shrink") {
+            (self.viewport * 0.25, self.viewport * 0.75)
+        } else {
+            (Vec2::ZERO, self.viewport)
+        };
+
+        // the camera may be rotated, so bound all 4 corners
+        [
+            start,
+            Vec2::new(end.x, start.y),
+            Vec2::new(start.x, end.y),
+            end,
+        ]
+        .map(|corner| camera.pointer_to_pos(corner, self.viewport))
+        .into_iter()
+        .fold((Vec2::MAX, Vec2::MIN), |(min, max), p| {
+            (min.min(p), max.max(p))
+        })
+    }
+
+    unsafe fn cull_on_gpu(&mut self, view_min: Vec2, view_max: Vec2) {
+        let shader = self.cull_shader.as_ref().unwrap();
+
+        // reset the instance count, which the atomic counter increments
+        let zero = 0u32;
+        let offset = mem::offset_of!(DrawElementsIndirectCommand, instance_count);
+        gl::BindBuffer(gl::ATOMIC_COUNTER_BUFFER, self.indirect_buffer);
+        gl::BufferSubData(
+            gl::ATOMIC_COUNTER_BUFFER,
+            offset as _,
+            mem::size_of::<u32>() as GLsizeiptr,
+            (&zero as *const u32).cast(),
+        );
+
+        gl::UseProgram(shader.program);
+        gl::Uniform2f(shader.u_view_min, view_min.x, view_min.y);
+        gl::Uniform2f(shader.u_view_max, view_max.x, view_max.y);
+        gl::Uniform1ui(shader.u_count, self.objects.len() as u32);
+
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, self.objects_ssbo);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 1, self.visible_vbo);
+        gl::BindBufferBase(gl::ATOMIC_COUNTER_BUFFER, 0, self.indirect_buffer);
+
+        let groups = self.objects.len().div_ceil(WORKGROUP_SIZE);
+        gl::DispatchCompute(groups as u32, 1, 1);
+        gl::MemoryBarrier(gl::COMMAND_BARRIER_BIT | gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, 0);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 1, 0);
+        gl::BindBufferBase(gl::ATOMIC_COUNTER_BUFFER, 0, 0);
+    }
+
+    unsafe fn cull_on_cpu(&mut self, view_min: Vec2, view_max: Vec2) {
+        self.visible.clear();
+        self.visible.extend((self.objects.iter()).filter(|object| {
+            let min = object.center - object.half_size;
+            let max = object.center + object.half_size;
+            min.cmple(view_max).all() && max.cmpge(view_min).all()
+        }));
+
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.visible_vbo);
+        gl::BufferSubData(
+            gl::ARRAY_BUFFER,
+            0,
+            mem::size_of_val(self.visible.as_slice()) as GLsizeiptr,
+            self.visible.as_ptr().cast(),
+        );
+    }
+
+    /// Prints how many objects were drawn and how long culling took, once per
+    /// second.
+    fn report(&mut self, on_gpu: bool) {
+        if self.last_report.elapsed() < Duration::from_secs(1) {
+            return;
+        }
+
+        let drawn = if on_gpu {
+            // stalls until the last cull is done, which is fine once a second
+            let mut command = mem::MaybeUninit::<DrawElementsIndirectCommand>::uninit();
+            unsafe {
+                gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, self.indirect_buffer);
+                gl::GetBufferSubData(
+                    gl::DRAW_INDIRECT_BUFFER,
+                    0,
+                    mem::size_of::<DrawElementsIndirectCommand>() as GLsizeiptr,
+                    command.as_mut_ptr().cast(),
+                );
+                gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, 0);
+                command.assume_init().instance_count as usize
+            }
+        } else {
+            self.visible.len()
+        };
+
+        let mode = if on_gpu { "gpu" } else { "cpu" };
+        let cpu_ms = self.cpu_time.as_secs_f32() * 1000.0 / self.frames as f32;
+        let gpu_ms = |zone| match self.gpu_timer.duration(zone) {
+            Some(ms) => format!("{ms:.2}"),
+            None => "?".to_string(),
+        };
+        println!(
+            "culling ({mode}): {drawn} drawn, {} culled | cull {} ms gpu, {cpu_ms:.2} ms cpu | draw {} ms gpu",
+            self.objects.len() - drawn,
+            gpu_ms("cull"),
+            gpu_ms("draw"),
+        );
+
+        self.cpu_time = Duration::ZERO;
+        self.frames = 0;
+        self.last_report = Instant::now();
+    }
+}
+
+impl Scene for CullingScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("c") => {
+                if self.cull_shader.is_none() {
+                    println!("culling: compute shaders unsupported, staying on the CPU");
+                    return;
+                }
+                self.params.toggle("culling").unwrap();
+            }
+            Key::Character("s") => self.params.toggle("shrink").unwrap(),
+            _ => return,
+        }
+
+        println!("culling config: {}", self.params);
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("culling draw");
+
+        let (view_min, view_max) = self.view_bounds(camera);
+        let on_gpu = self.cull_shader.is_some() && self.params.variant("culling") == 0;
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            self.gpu_timer.begin("cull");
+            let cpu_start = Instant::now();
+            if on_gpu {
+                self.cull_on_gpu(view_min, view_max);
+            } else {
+                self.cull_on_cpu(view_min, view_max);
+            }
+            self.cpu_time += cpu_start.elapsed();
+            self.gpu_timer.end();
+
+            gl::UseProgram(self.draw_shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+            gl::BindVertexArray(self.vao);
+
+            self.gpu_timer.begin("draw");
+            if on_gpu {
+                gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, self.indirect_buffer);
+                gl::DrawElementsIndirect(gl::TRIANGLES, gl::UNSIGNED_INT, 0 as _);
+                gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, 0);
+            } else {
+                let count = self.visible.len() as GLsizei;
+                gl::DrawElementsInstanced(gl::TRIANGLES, 6, gl::UNSIGNED_INT, 0 as _, count);
+            }
+            self.gpu_timer.end();
+
+            gl::BindVertexArray(0);
+            self.gpu_timer.poll();
+        }
+
+        self.frames += 1;
+        self.report(on_gpu);
+        Ok(())
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+impl Drop for CullingScene {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(shader) = &self.cull_shader {
+                gl::DeleteProgram(shader.program);
+            }
+            gl::DeleteProgram(self.draw_shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+
+            delete_buffers(&[
+                self.quad_vbo,
+                self.ebo,
+                self.objects_ssbo,
+                self.visible_vbo,
+                self.indirect_buffer,
+            ]);
+        }
+    }
+}
+
+/// Matches the `std430` layout of `Object` in cull.comp.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Object {
+    center: Vec2,
+    half_size: Vec2,
+    color: [f32; 4],
+}
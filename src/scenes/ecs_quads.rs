@@ -0,0 +1,487 @@
+//! The same kind of quads as [`super::round_quads`], but stored as entities of
+//! an ECS and updated by systems, to compare how both approaches feel to write
+//! and how fast they run.
+//!
+//! The exact same work is also implemented over plain vectors, so that the
+//! cost of going through the ECS can be measured on equal terms.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    f32::consts::TAU,
+    mem,
+    time::{Duration, Instant},
+};
+
+use bevy_ecs::prelude::*;
+use gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
+use glam::{vec2, Mat4, Vec2, Vec4};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{buffer_data, create_shader_program, delete_buffers};
+use crate::error::GlError;
+use crate::gpu_timer::GpuTimer;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::round_quads::Vertex;
+use super::{Scene, SRC_FRAG_ROUND_RECT, SRC_VERT_ROUND_RECT};
+
+const N_QUADS: usize = 100_000;
+
+/// Side of the square the quads bounce around in.
+const AREA_SIZE: f32 = 5000.0;
+
+const STORAGES: &[&str] = &["ecs", "vecs"];
+
+#[derive(Component, Debug, Clone, Copy)]
+struct Transform {
+    position: Vec2,
+    rotation: f32,
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+struct QuadStyle {
+    size: Vec2,
+    fill_color: Vec4,
+    stroke_color: Vec4,
+    border_radius: f32,
+    border_width: f32,
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+struct Velocity {
+    linear: Vec2,
+    angular: f32,
+}
+
+/// Whether the quad overlaps the view, as of the last culling pass.
+#[derive(Component, Debug, Clone, Copy, Default)]
+struct Visible(bool);
+
+#[derive(Resource, Default)]
+struct FrameTime {
+    dt: f32,
+}
+
+/// World-space bounds of the view.
+#[derive(Resource, Default)]
+struct View {
+    min: Vec2,
+    max: Vec2,
+}
+
+/// Vertices of every visible quad, ready to be uploaded.
+#[derive(Resource, Default)]
+struct Batch {
+    vertices: Vec<[Vertex; 4]>,
+}
+
+struct Ecs {
+    world: World,
+    schedule: Schedule,
+}
+
+/// The hand-rolled counterpart of the ECS world: one vector per component.
+struct Vecs {
+    transforms: Vec<Transform>,
+    styles: Vec<QuadStyle>,
+    velocities: Vec<Velocity>,
+    visible: Vec<bool>,
+}
+
+pub struct EcsQuadsScene {
+    matrix: Mat4,
+    viewport: Vec2,
+    /// Pixels per world unit.
+    pixels_per_unit: f32,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_pixels_per_unit: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    ebo: GLuint,
+
+    /// Boxed, as a world and its schedule are much bigger than any other
+    /// scene.
+    ecs: Box<Ecs>,
+    vecs: Vecs,
+    /// Batch of the vectors, the ECS one living in the world.
+    vecs_batch: Vec<[Vertex; 4]>,
+
+    gpu_timer: GpuTimer,
+    update_time: Duration,
+    frames: u32,
+    last_report: Instant,
+    last_instant: Instant,
+
+    params: Params,
+}
+
+impl EcsQuadsScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let quads = (0..N_QUADS)
+            .map(|_| random_quad(&mut rng))
+            .collect::<Vec<_>>();
+
+        let mut world = World::new();
+        world.spawn_batch(
+            (quads.iter()).map(|&(transform, style, velocity)| {
+                (transform, style, velocity, Visible::default())
+            }),
+        );
+        world.init_resource::<FrameTime>();
+        world.init_resource::<View>();
+        world.insert_resource(Batch {
+            vertices: Vec::with_capacity(N_QUADS),
+        });
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems((animate, cull, batch).chain());
+
+        let vecs = Vecs {
+            transforms: quads.iter().map(|quad| quad.0).collect(),
+            styles: quads.iter().map(|quad| quad.1).collect(),
+            velocities: quads.iter().map(|quad| quad.2).collect(),
+            visible: vec![false; N_QUADS],
+        };
+
+        unsafe {
+            let shader = create_shader_program(SRC_VERT_ROUND_RECT, SRC_FRAG_ROUND_RECT)?;
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            let mut buffers = [0; 2];
+            gl::GenBuffers(2, buffers.as_mut_ptr());
+            let [vbo, ebo] = buffers;
+
+            gl::BindVertexArray(vao);
+
+            // big enough for every quad to be visible
+            let vertices = vec![[Vertex::default(); 4]; N_QUADS];
+            buffer_data(gl::ARRAY_BUFFER, vbo, vertices.as_slice(), gl::STREAM_DRAW);
+            Vertex::enable_attributes(shader);
+
+            let indices = (0..N_QUADS as u32)
+                .map(|i| {
+                    let i = i * 4;
+                    [i, 1 + i, 2 + i, i, 2 + i, 3 + i]
+                })
+                .collect::<Vec<_>>();
+            buffer_data(
+                gl::ELEMENT_ARRAY_BUFFER,
+                ebo,
+                indices.as_slice(),
+                gl::STATIC_DRAW,
+            );
+
+            gl::BindVertexArray(0);
+
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+                pixels_per_unit: 1.0,
+
+                u_mvp: gl::GetUniformLocation(shader, c"u_mvp".as_ptr()),
+                u_pixels_per_unit: gl::GetUniformLocation(shader, c"u_pixels_per_unit".as_ptr()),
+                shader,
+                vao,
+                vbo,
+                ebo,
+
+                ecs: Box::new(Ecs { world, schedule }),
+                vecs,
+                vecs_batch: Vec::with_capacity(N_QUADS),
+
+                gpu_timer: GpuTimer::new(),
+                update_time: Duration::ZERO,
+                frames: 0,
+                last_report: Instant::now(),
+                last_instant: Instant::now(),
+
+                params: Params::new()
+                    .with_enum("storage", 0, STORAGES)
+                    .with_bool("animate", true),
+            })
+        }
+    }
+
+    /// Prints how many quads were drawn and how long updating them took, once
+    /// per second.
+    fn report(&mut self, drawn: usize) {
+        if self.last_report.elapsed() < Duration::from_secs(1) {
+            return;
+        }
+
+        let storage = STORAGES[self.params.variant("storage")];
+        let update_ms = self.update_time.as_secs_f32() * 1000.0 / self.frames as f32;
+        let draw_ms = match self.gpu_timer.duration("draw") {
+            Some(ms) => format!("{ms:.2}"),
+            None => "?".to_string(),
+        };
+        println!(
+            "ecs-quads ({storage}): {N_QUADS} quads, {drawn} drawn | update {update_ms:.2} ms cpu | draw {draw_ms} ms gpu"
+        );
+
+        self.update_time = Duration::ZERO;
+        self.frames = 0;
+        self.last_report = Instant::now();
+    }
+}
+
+impl Scene for EcsQuadsScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("s") => self.params.toggle("storage").unwrap(),
+            Key::Character("a") => self.params.toggle("animate").unwrap(),
+            _ => return,
+        }
+
+        println!("ecs-quads config: {}", self.params);
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("ecs quads draw");
+
+        let dt = self.last_instant.elapsed().as_secs_f32();
+        self.last_instant = Instant::now();
+        let dt = if self.params.bool("animate") { dt } else { 0.0 };
+
+        let (min, max) = view_bounds(camera, self.viewport);
+        let use_ecs = self.params.variant("storage") == 0;
+
+        let start = Instant::now();
+        let vertices = if use_ecs {
+            let Ecs { world, schedule } = &mut *self.ecs;
+            world.resource_mut::<FrameTime>().dt = dt;
+            *world.resource_mut::<View>() = View { min, max };
+            schedule.run(world);
+            world.resource::<Batch>().vertices.as_slice()
+        } else {
+            self.vecs.animate(dt);
+            self.vecs.cull(min, max);
+            self.vecs.batch(&mut self.vecs_batch);
+            self.vecs_batch.as_slice()
+        };
+        self.update_time += start.elapsed();
+
+        let drawn = vertices.len();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            self.gpu_timer.begin("draw");
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                mem::size_of_val(vertices) as GLsizeiptr,
+                vertices.as_ptr().cast(),
+            );
+
+            gl::UseProgram(self.shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+            gl::Uniform1f(self.u_pixels_per_unit, self.pixels_per_unit);
+            gl::BindVertexArray(self.vao);
+            let count = (drawn * 6) as GLsizei;
+            gl::DrawElements(gl::TRIANGLES, count, gl::UNSIGNED_INT, 0 as _);
+            gl::BindVertexArray(0);
+            self.gpu_timer.end();
+
+            self.gpu_timer.poll();
+        }
+
+        self.frames += 1;
+        self.report(drawn);
+        Ok(())
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        self.pixels_per_unit = camera.scale.x;
+        Ok(())
+    }
+}
+
+impl Drop for EcsQuadsScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo, self.ebo]);
+        }
+    }
+}
+
+// --- systems ---
+
+fn animate(time: Res<FrameTime>, mut quads: Query<(&mut Transform, &mut Velocity)>) {
+    profiling::cpu_zone!("ecs animate");
+
+    for (mut transform, mut velocity) in &mut quads {
+        step(&mut transform, &mut velocity, time.dt);
+    }
+}
+
+fn cull(view: Res<View>, mut quads: Query<(&Transform, &QuadStyle, &mut Visible)>) {
+    profiling::cpu_zone!("ecs cull");
+
+    for (transform, style, mut visible) in &mut quads {
+        visible.0 = overlaps(transform, style, view.min, view.max);
+    }
+}
+
+fn batch(mut batch: ResMut<Batch>, quads: Query<(&Transform, &QuadStyle, &Visible)>) {
+    profiling::cpu_zone!("ecs batch");
+
+    batch.vertices.clear();
+    for (transform, style, visible) in &quads {
+        if visible.0 {
+            batch.vertices.push(vertices(transform, style));
+        }
+    }
+}
+
+// --- the same systems, over vectors ---
+
+impl Vecs {
+    fn animate(&mut self, dt: f32) {
+        profiling::cpu_zone!("vecs animate");
+
+        for (transform, velocity) in self.transforms.iter_mut().zip(&mut self.velocities) {
+            step(transform, velocity, dt);
+        }
+    }
+
+    fn cull(&mut self, min: Vec2, max: Vec2) {
+        profiling::cpu_zone!("vecs cull");
+
+        let quads = self.transforms.iter().zip(&self.styles);
+        for (visible, (transform, style)) in self.visible.iter_mut().zip(quads) {
+            *visible = overlaps(transform, style, min, max);
+        }
+    }
+
+    fn batch(&self, batch: &mut Vec<[Vertex; 4]>) {
+        profiling::cpu_zone!("vecs batch");
+
+        batch.clear();
+        let quads = self.transforms.iter().zip(&self.styles);
+        for (&visible, (transform, style)) in self.visible.iter().zip(quads) {
+            if visible {
+                batch.push(vertices(transform, style));
+            }
+        }
+    }
+}
+
+// --- shared by both ---
+
+fn random_quad(rng: &mut impl Rng) -> (Transform, QuadStyle, Velocity) {
+    let mut color = |range: std::ops::Range<f32>| {
+        Vec4::new(
+            rng.gen_range(range.clone()),
+            rng.gen_range(range.clone()),
+            rng.gen_range(range),
+            rng.gen_range(0.5..1.0),
+        )
+    };
+    let (fill_color, stroke_color) = (color(0.5..1.0), color(0.1..0.5));
+
+    let transform = Transform {
+        position: vec2(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5)) * AREA_SIZE,
+        rotation: rng.gen_range(0.0..TAU),
+    };
+    let style = QuadStyle {
+        size: vec2(rng.gen_range(10.0..=20.0), rng.gen_range(10.0..=20.0)),
+        fill_color,
+        stroke_color,
+        border_radius: rng.gen_range(1.0..=5.0),
+        border_width: rng.gen_range(1.0..=5.0),
+    };
+    let velocity = Velocity {
+        linear: Vec2::from_angle(rng.gen_range(0.0..TAU)) * rng.gen_range(10.0..80.0),
+        angular: rng.gen_range(-2.0..2.0),
+    };
+
+    (transform, style, velocity)
+}
+
+/// Moves a quad, bouncing it off the edges of the area.
+fn step(transform: &mut Transform, velocity: &mut Velocity, dt: f32) {
+    transform.position += velocity.linear * dt;
+    transform.rotation += velocity.angular * dt;
+
+    let half = AREA_SIZE * 0.5;
+    for axis in 0..2 {
+        if transform.position[axis].abs() > half {
+            transform.position[axis] = transform.position[axis].clamp(-half, half);
+            velocity.linear[axis] = -velocity.linear[axis];
+        }
+    }
+}
+
+fn overlaps(transform: &Transform, style: &QuadStyle, min: Vec2, max: Vec2) -> bool {
+    // bounding circle, whatever the rotation
+    let radius = style.size.length() * 0.5;
+    let (quad_min, quad_max) = (transform.position - radius, transform.position + radius);
+    quad_min.cmple(max).all() && quad_max.cmpge(min).all()
+}
+
+fn vertices(transform: &Transform, style: &QuadStyle) -> [Vertex; 4] {
+    let r = Vec2::from_angle(transform.rotation);
+
+    #[rustfmt::skip]
+    let corners = [
+        vec2(-0.5, -0.5),
+        vec2(-0.5,  0.5),
+        vec2( 0.5,  0.5),
+        vec2( 0.5, -0.5),
+    ];
+
+    corners.map(|corner| Vertex {
+        position: (corner * style.size).rotate(r) + transform.position,
+        size: style.size,
+        fill_color: style.fill_color,
+        stroke_color: style.stroke_color,
+        border_radii: [style.border_radius; 4],
+        border_width: style.border_width,
+        intensity: 1.0,
+        ..Default::default()
+    })
+}
+
+/// World-space bounds of what the camera sees.
+fn view_bounds(camera: &Camera, viewport: Vec2) -> (Vec2, Vec2) {
+    // the camera may be rotated, so bound all 4 corners
+    [Vec2::ZERO, viewport * Vec2::X, viewport * Vec2::Y, viewport]
+        .map(|corner| camera.pointer_to_pos(corner, viewport))
+        .into_iter()
+        .fold((Vec2::MAX, Vec2::MIN), |(min, max), p| {
+            (min.min(p), max.max(p))
+        })
+}
@@ -0,0 +1,130 @@
+use glam::{vec2, Vec2};
+
+use crate::camera::Camera;
+use crate::error::{GlError, PlaygroundError};
+use crate::locale::{tr, tr_args};
+use crate::render_state::RenderState;
+use crate::text::TextRenderer;
+use crate::theme;
+
+use super::{Scene, Scenes};
+
+const TITLE_SIZE: f32 = 24.0;
+const TEXT_SIZE: f32 = 14.0;
+const BACKTRACE_SIZE: f32 = 11.0;
+const MAX_WIDTH: f32 = 720.0;
+const PADDING: f32 = 16.0;
+const GAP: f32 = 12.0;
+
+/// Stands in for a scene that couldn't be created or panicked, saying why
+/// instead of drawing whatever its broken objects would. It keeps the scene's
+/// name, so that switching to the next or previous scene works from there.
+pub struct ErrorScreen {
+    name: &'static str,
+    error: PlaygroundError,
+
+    /// None if text can't be drawn either, in which case the error is only
+    /// printed.
+    text: Option<Box<TextRenderer>>,
+}
+
+impl ErrorScreen {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(name: &'static str, error: PlaygroundError) -> Self {
+        // panics were already printed by the hook
+        if !matches!(error, PlaygroundError::Panic { .. }) {
+            eprintln!("Couldn't create {name}: {error}");
+        }
+        let text = match unsafe { TextRenderer::new() } {
+            Ok(text) => Some(Box::new(text)),
+            Err(e) => {
+                eprintln!("Couldn't create the error screen either: {e}");
+                None
+            }
+        };
+
+        Self { name, error, text }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl Scene for ErrorScreen {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+        }
+        let Some(text) = &mut self.text else {
+            return Ok(());
+        };
+
+        // whatever size the scene was given, as it may have failed before
+        // the error screen was resized
+        let mut bounds = [0; 4];
+        unsafe { gl::GetIntegerv(gl::VIEWPORT, bounds.as_mut_ptr()) };
+        let viewport = vec2(bounds[2] as f32, bounds[3] as f32);
+
+        let theme = theme::current();
+        let (title, backtrace) = match &self.error {
+            PlaygroundError::Panic { backtrace, .. } => ("error.crashed", backtrace.trim_end()),
+            _ => ("error.title", ""),
+        };
+        let title = tr_args(title, &[("name", &Scenes::title(self.name))]);
+        let message = self.error.to_string();
+        let hint = tr("error.hint");
+        let width = (viewport.x - PADDING * 4.0).clamp(1.0, MAX_WIDTH);
+
+        let mut extents = vec![
+            text.measure(&title, TITLE_SIZE, Some(width)),
+            text.measure(&message, TEXT_SIZE, Some(width)),
+            text.measure(&hint, TEXT_SIZE, Some(width)),
+        ];
+        if !backtrace.is_empty() {
+            extents.push(text.measure(backtrace, BACKTRACE_SIZE, Some(width)));
+        }
+        let size = vec2(
+            extents.iter().map(|extent| extent.x).fold(0.0, f32::max),
+            extents.iter().map(|extent| extent.y).sum::<f32>() + GAP * (extents.len() - 1) as f32,
+        );
+
+        // centered, growing downwards if it doesn't fit
+        let min = ((viewport - size) / 2.0).max(Vec2::splat(PADDING)) - PADDING;
+        text.queue_rect(min, min + size + PADDING * 2.0, theme.hud_background);
+        let mut pen = min + PADDING;
+        pen.y += text
+            .queue(&title, pen, TITLE_SIZE, Some(width), theme.accent)
+            .y
+            + GAP;
+        pen.y += text
+            .queue(&message, pen, TEXT_SIZE, Some(width), theme.hud_text)
+            .y
+            + GAP;
+        if !backtrace.is_empty() {
+            pen.y += text
+                .queue(backtrace, pen, BACKTRACE_SIZE, Some(width), theme.hud_text)
+                .y
+                + GAP;
+        }
+        text.queue(&hint, pen, TEXT_SIZE, Some(width), theme.hud_text);
+
+        unsafe { text.draw(0, viewport) };
+        Ok(())
+    }
+
+    fn resize(&mut self, _camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+        Ok(())
+    }
+
+    fn error(&self) -> Option<&PlaygroundError> {
+        Some(&self.error)
+    }
+}
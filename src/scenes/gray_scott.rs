@@ -0,0 +1,408 @@
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, Mat4, UVec2, Vec2};
+use image::RgbaImage;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_float_framebuffer, create_framebuffer, create_shader_program,
+    delete_buffers, delete_framebuffer, pop_debug_group, push_debug_group, read_pixels,
+    Framebuffer,
+};
+use crate::error::GlError;
+use crate::params::{Params, Value};
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::{Scene, SceneEvent, SRC_FRAG_GRAY_SCOTT, SRC_FRAG_GRAY_SCOTT_VIEW, SRC_VERT_SCREEN};
+
+/// Cells of the simulation grid.
+const GRID: UVec2 = uvec2(512, 320);
+/// Size of a cell in world units.
+const CELL: f32 = 2.5;
+/// A cell with only u, and one where v was poured in, as in gray-scott.frag.
+const EMPTY: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const SEEDED: [f32; 4] = [0.5, 0.25, 0.0, 1.0];
+/// Square blobs of v the grid starts with.
+const N_BLOBS: usize = 24;
+
+/// Variants of the `colormap` parameter, in order.
+const COLORMAPS: &[&str] = &["ink", "heat", "ocean"];
+/// Feed and kill rates giving each kind of pattern.
+const PRESETS: &[(&str, f32, f32)] = &[
+    ("spots", 0.025, 0.06),
+    ("stripes", 0.029, 0.057),
+    ("worms", 0.054, 0.063),
+];
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// Gray-Scott reaction-diffusion: two chemicals u and v spread over a grid,
+/// u being fed in and v killed off at constant rates while v turns u into
+/// more v. Depending on both rates, v settles into spots, stripes or worms.
+///
+/// The grid lives in two floating point textures that the simulation
+/// ping-pongs between, a few steps per fixed update. v is poured in where the
+/// mouse is held, and the result can be handed to the blur scenes as their
+/// input image.
+pub struct GrayScottScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    /// Read from, then the other one is written to.
+    state: [Framebuffer; 2],
+    /// Index of the framebuffer holding the current state.
+    current: usize,
+    /// Where the mouse is in world units.
+    mouse_world: Vec2,
+    /// Whether v is being poured in under the mouse.
+    painting: bool,
+    rng: StdRng,
+    /// Image made from the grid, waiting to become the input image.
+    exported: Option<RgbaImage>,
+
+    update_shader: GLuint,
+    u_feed: GLint,
+    u_kill: GLint,
+    u_brush: GLint,
+    u_brush_radius: GLint,
+    view_shader: GLuint,
+    u_colormap: GLint,
+    u_inverse: GLint,
+    u_half_size: GLint,
+    u_clear: GLint,
+    u_ink: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+
+    params: Params,
+}
+
+impl GrayScottScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        unsafe {
+            let update_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_GRAY_SCOTT)?;
+            let view_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_GRAY_SCOTT_VIEW)?;
+            let uniform = |shader: GLuint, name: &std::ffi::CStr| {
+                gl::GetUniformLocation(shader, name.as_ptr())
+            };
+            for shader in [update_shader, view_shader] {
+                gl::UseProgram(shader);
+                gl::Uniform1i(uniform(shader, c"u_state"), 0);
+            }
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            // both programs share the screen vertex shader, so the locations match
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(view_shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let state = [
+                create_float_framebuffer("gray-scott ping", GRID, gl::RGBA16F)?,
+                create_float_framebuffer("gray-scott pong", GRID, gl::RGBA16F)?,
+            ];
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            let (feed, kill) = (PRESETS[0].1, PRESETS[0].2);
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+
+                state,
+                current: 0,
+                mouse_world: Vec2::ZERO,
+                painting: false,
+                rng: StdRng::seed_from_u64(seed),
+                exported: None,
+
+                u_feed: uniform(update_shader, c"u_feed"),
+                u_kill: uniform(update_shader, c"u_kill"),
+                u_brush: uniform(update_shader, c"u_brush"),
+                u_brush_radius: uniform(update_shader, c"u_brush_radius"),
+                update_shader,
+                u_colormap: uniform(view_shader, c"u_colormap"),
+                u_inverse: uniform(view_shader, c"u_inverse"),
+                u_half_size: uniform(view_shader, c"u_half_size"),
+                u_clear: uniform(view_shader, c"u_clear"),
+                u_ink: uniform(view_shader, c"u_ink"),
+                view_shader,
+                vao,
+                vbo,
+
+                params: Params::new()
+                    .with_f32("feed", feed, 0.0, 0.1, 0.001)
+                    .with_f32("kill", kill, 0.0, 0.1, 0.001)
+                    .with_i32("steps", 8, 1, 32)
+                    .with_f32("brush", 6.0, 1.0, 32.0, 1.0)
+                    .with_enum("colormap", 0, COLORMAPS)
+                    .with_bool("running", true),
+            };
+            scene.reseed();
+            Ok(scene)
+        }
+    }
+
+    /// Starts over from u everywhere and random blobs of v.
+    unsafe fn reseed(&mut self) {
+        let mut cells = vec![EMPTY; (GRID.x * GRID.y) as usize];
+        for _ in 0..N_BLOBS {
+            let center = uvec2(self.rng.gen_range(0..GRID.x), self.rng.gen_range(0..GRID.y));
+            let half_size = self.rng.gen_range(3..10);
+            for y in center.y.saturating_sub(half_size)..(center.y + half_size).min(GRID.y) {
+                for x in center.x.saturating_sub(half_size)..(center.x + half_size).min(GRID.x) {
+                    cells[(y * GRID.x + x) as usize] = SEEDED;
+                }
+            }
+        }
+        self.upload(&cells);
+    }
+
+    /// Replaces the current state with `cells` in row order.
+    unsafe fn upload(&mut self, cells: &[[f32; 4]]) {
+        gl::BindTexture(gl::TEXTURE_2D, self.state[self.current].texture);
+        gl::TexSubImage2D(
+            gl::TEXTURE_2D,
+            0,
+            0,
+            0,
+            GRID.x as GLsizei,
+            GRID.y as GLsizei,
+            gl::RGBA,
+            gl::FLOAT,
+            cells.as_ptr().cast(),
+        );
+    }
+
+    /// Draws the grid with the current colormap into an image of its size.
+    unsafe fn export(&mut self) -> Result<RgbaImage, GlError> {
+        let target = create_framebuffer("gray-scott export", GRID)?;
+        gl::Viewport(0, 0, GRID.x as GLsizei, GRID.y as GLsizei);
+        // the whole target shows exactly the grid
+        let half_size = GRID.as_vec2() * CELL * 0.5;
+        self.draw_view(&Mat4::from_scale(half_size.extend(1.0)));
+        let pixels = read_pixels(GRID.x, GRID.y);
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        delete_framebuffer(&target);
+        let size = self.viewport.as_ivec2();
+        gl::Viewport(0, 0, size.x, size.y);
+
+        println!("Exported the {}x{} grid as the input image", GRID.x, GRID.y);
+        Ok(RgbaImage::from_raw(GRID.x, GRID.y, pixels).unwrap())
+    }
+
+    /// Draws the grid as seen through `inverse`, from the screen to the world.
+    unsafe fn draw_view(&self, inverse: &Mat4) {
+        let theme = theme::current();
+        let half_size = GRID.as_vec2() * CELL * 0.5;
+
+        gl::UseProgram(self.view_shader);
+        gl::UniformMatrix4fv(self.u_inverse, 1, gl::FALSE, inverse.as_ref().as_ptr());
+        gl::Uniform1i(self.u_colormap, self.params.variant("colormap") as GLint);
+        gl::Uniform2f(self.u_half_size, half_size.x, half_size.y);
+        gl::Uniform4fv(self.u_clear, 1, theme.clear.as_ptr());
+        gl::Uniform4fv(self.u_ink, 1, theme.hud_text.as_ptr());
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, self.state[self.current].texture);
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        gl::BindVertexArray(0);
+    }
+}
+
+impl Scene for GrayScottScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character(digit @ ("1" | "2" | "3")) => {
+                let (name, feed, kill) = PRESETS[digit.parse::<usize>().unwrap() - 1];
+                println!("gray-scott preset: {name}");
+                self.params.set("feed", Value::Number(feed as f64)).unwrap();
+                self.params.set("kill", Value::Number(kill as f64)).unwrap();
+            }
+            Key::Character("f") => self.params.step("feed", 1).unwrap(),
+            Key::Character("F") => self.params.step("feed", -1).unwrap(),
+            Key::Character("k") => self.params.step("kill", 1).unwrap(),
+            Key::Character("K") => self.params.step("kill", -1).unwrap(),
+            Key::Character("s") => self.params.step("steps", 1).unwrap(),
+            Key::Character("S") => self.params.step("steps", -1).unwrap(),
+            Key::Character("b") => self.params.step("brush", 1).unwrap(),
+            Key::Character("B") => self.params.step("brush", -1).unwrap(),
+            Key::Character("c") => self.params.step("colormap", 1).unwrap(),
+            Key::Character("C") => self.params.step("colormap", -1).unwrap(),
+            Key::Character("p" | "P") => self.params.toggle("running").unwrap(),
+            Key::Character("r" | "R") => unsafe { self.reseed() },
+            Key::Character("x" | "X") => unsafe {
+                self.upload(&vec![EMPTY; (GRID.x * GRID.y) as usize])
+            },
+            Key::Character("i" | "I") => {
+                match unsafe { self.export() } {
+                    Ok(image) => self.exported = Some(image),
+                    Err(e) => eprintln!("Couldn't export the grid: {e}"),
+                }
+                return;
+            }
+            _ => return,
+        }
+
+        println!("gray-scott config: {}", self.params);
+    }
+
+    /// Pours v in under the mouse while the right button is held. Returns
+    /// whether the click was used, in which case the camera shouldn't pan.
+    fn on_event(&mut self, _camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos: _mouse_pos,
+        } = *event;
+        if button != MouseButton::Right {
+            return false;
+        }
+
+        self.painting = state == ElementState::Pressed;
+        true
+    }
+
+    /// Runs the simulation for a few steps.
+    fn update(&mut self, _dt: f32) {
+        if !self.params.bool("running") && !self.painting {
+            return;
+        }
+
+        profiling::cpu_zone!("gray-scott update");
+
+        let half_size = GRID.as_vec2() * CELL * 0.5;
+        let brush = (self.mouse_world + half_size) / CELL;
+        let steps = match self.params.bool("running") {
+            true => self.params.i32("steps"),
+            // only pouring in
+            false => 1,
+        };
+
+        unsafe {
+            push_debug_group(c"Gray-Scott steps");
+            gl::Viewport(0, 0, GRID.x as GLsizei, GRID.y as GLsizei);
+            gl::UseProgram(self.update_shader);
+            gl::Uniform1f(self.u_feed, self.params.f32("feed"));
+            gl::Uniform1f(self.u_kill, self.params.f32("kill"));
+            gl::Uniform2f(self.u_brush, brush.x, brush.y);
+            let radius = match self.painting {
+                true => self.params.f32("brush"),
+                false => 0.0,
+            };
+            gl::Uniform1f(self.u_brush_radius, radius);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindVertexArray(self.vao);
+
+            for _ in 0..steps {
+                let next = 1 - self.current;
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.state[next].fbo);
+                gl::BindTexture(gl::TEXTURE_2D, self.state[self.current].texture);
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                self.current = next;
+            }
+
+            gl::BindVertexArray(0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            let size = self.viewport.as_ivec2();
+            gl::Viewport(0, 0, size.x, size.y);
+            pop_debug_group();
+        }
+    }
+
+    /// Image of the grid made with the `I` key, to be used as the input image
+    /// of the blur scenes.
+    fn take_input(&mut self) -> Option<RgbaImage> {
+        self.exported.take()
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("gray-scott draw");
+
+        self.mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport);
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            self.draw_view(&self.matrix.inverse());
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+impl Drop for GrayScottScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.update_shader);
+            gl::DeleteProgram(self.view_shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+            for framebuffer in &self.state {
+                delete_framebuffer(framebuffer);
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::mem;
+
+use glam::{vec2, Vec2};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    bind_framebuffer, set_viewport, Attrib, Buffer, Program, Uniform, VertexArray,
+};
+use crate::error::GlError;
+use crate::output::{self, OutputMode};
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::{Scene, SRC_FRAG_HDR_TEST, SRC_VERT_SCREEN};
+
+/// Seconds a pulse of the pulsing highlight takes.
+const PULSE_PERIOD: f32 = 2.0;
+
+const TEXT_SIZE: f32 = 14.0;
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+
+const LAYOUT: &[Attrib] = &[
+    Attrib {
+        name: c"position",
+        components: 2,
+        offset: 0,
+    },
+    Attrib {
+        name: c"uv",
+        components: 2,
+        offset: mem::size_of::<Vec2>(),
+    },
+];
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+impl Vertex {
+    const fn new(position: Vec2, uv: Vec2) -> Self {
+        Self { position, uv }
+    }
+}
+
+#[rustfmt::skip]
+const SCREEN_VERTICES: &[Vertex] = &[
+    Vertex::new(vec2(-1.0,  1.0), vec2(0.0, 1.0)),
+    Vertex::new(vec2(-1.0, -1.0), vec2(0.0, 0.0)),
+    Vertex::new(vec2( 1.0, -1.0), vec2(1.0, 0.0)),
+    Vertex::new(vec2(-1.0,  1.0), vec2(0.0, 1.0)),
+    Vertex::new(vec2( 1.0, -1.0), vec2(1.0, 0.0)),
+    Vertex::new(vec2( 1.0,  1.0), vec2(1.0, 1.0)),
+];
+
+/// Bands of gradients and highlights to check what the output path does
+/// with values beyond SDR white (see [`crate::output`]).
+///
+/// From the top: a gray ramp from black to SDR white, which looks the same
+/// in both modes, then white and the three primaries from SDR white up to the
+/// peak in steps, and squares at 2x, 4x, 8x and the peak over SDR white, the
+/// last one pulsing. In SDR everything beyond white clips, so the bands below
+/// the first one are flat and the squares disappear.
+pub struct HdrTestScene {
+    viewport: Vec2,
+    time: f32,
+
+    shader: Program,
+    u_hdr_output: Uniform,
+    u_exposure: Uniform,
+    u_pulse: Uniform,
+    vao: VertexArray,
+    _vbo: Buffer<Vertex>,
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+impl HdrTestScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    #[allow(clippy::new_without_default)]
+    pub fn new(text: Option<SharedText>) -> Result<Self, GlError> {
+        let shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_HDR_TEST)?;
+        shader.set(shader.uniform(c"u_peak"), output::PEAK);
+        let vbo = Buffer::new(gl::ARRAY_BUFFER, SCREEN_VERTICES, gl::STATIC_DRAW);
+        let mut vao = VertexArray::new();
+        vao.set_layout(&shader, &vbo, LAYOUT);
+
+        let params = Params::new()
+            .with_f32("exposure", 0.0, -4.0, 4.0, 0.5)
+            .with_bool("pulse", true);
+
+        Ok(Self {
+            viewport: Vec2::ONE,
+            time: 0.0,
+
+            u_hdr_output: shader.uniform(c"u_hdr_output"),
+            u_exposure: shader.uniform(c"u_exposure"),
+            u_pulse: shader.uniform(c"u_pulse"),
+            shader,
+            vao,
+            _vbo: vbo,
+
+            text,
+
+            params,
+        })
+    }
+}
+
+impl Scene for HdrTestScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("e") => self.params.step("exposure", 1).unwrap(),
+            Key::Character("E") => self.params.step("exposure", -1).unwrap(),
+            Key::Character("p" | "P") => self.params.toggle("pulse").unwrap(),
+            _ => return,
+        }
+
+        println!("hdr-test config: {}", self.params);
+    }
+
+    fn update(&mut self, dt: f32) {
+        if self.params.bool("pulse") {
+            self.time += dt;
+        }
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("hdr test draw");
+
+        let pulse = 0.5 - 0.5 * (self.time * std::f32::consts::TAU / PULSE_PERIOD).cos();
+
+        bind_framebuffer(None);
+        self.shader.set(self.u_hdr_output, output::hdr_uniform());
+        self.shader
+            .set(self.u_exposure, self.params.f32("exposure"));
+        self.shader.set(self.u_pulse, pulse);
+        self.vao.draw_arrays(gl::TRIANGLES);
+
+        let label = match output::mode() {
+            OutputMode::Hdr => format!(
+                "HDR: linear up to {}x SDR white, on a float surface",
+                output::PEAK
+            ),
+            OutputMode::Sdr => "SDR: everything above white clips".to_string(),
+        };
+        let theme = theme::current();
+        let Some(text) = &self.text else {
+            return Ok(());
+        };
+        let mut text = text.borrow_mut();
+        let extent = text.measure(&label, TEXT_SIZE, None);
+        // in the corner of the highlights, clear of the ramps
+        let min = vec2(MARGIN, self.viewport.y - MARGIN - extent.y - PADDING * 2.0);
+        let max = min + extent + PADDING * 2.0;
+        text.queue_rect(min, max, theme.hud_background);
+        text.queue(&label, min + PADDING, TEXT_SIZE, None, theme.hud_text);
+        unsafe { text.draw(0, self.viewport) };
+        Ok(())
+    }
+
+    fn resize(&mut self, _camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        self.viewport = Vec2::new(width as f32, height as f32);
+        set_viewport(self.viewport.as_uvec2());
+        Ok(())
+    }
+}
@@ -1,443 +1,354 @@
+// Written with the safe wrappers of `common_gl` only.
+#![forbid(unsafe_code)]
+
 use std::{mem, time::Instant};
 
-use gl::types::{GLfloat, GLint, GLsizei, GLsizeiptr, GLuint};
+use gl::types::GLuint;
 use glam::{uvec2, vec2, Mat4, Vec2};
-use image::ImageFormat;
+use winit::dpi::PhysicalSize;
 use winit::keyboard::{Key, NamedKey, SmolStr};
-use winit::{dpi::PhysicalSize, window::Window};
 
+use crate::adjust::{self, Adjuster};
 use crate::camera::Camera;
-use crate::common_gl::{create_framebuffer, create_shader_program, pop_debug_group, push_debug_group, upload_texture, Framebuffer};
-
+use crate::common_gl::{
+    bind_framebuffer, bind_texture, clear, set_viewport, Attrib, Buffer, DebugGroup, IndexBuffer,
+    Program, Texture, Uniform, VertexArray,
+};
+use crate::downsample::DownsampleChain;
+use crate::error::GlError;
+use crate::input_image::InputImage;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+use crate::tween::{Easing, Tweener};
+
+use super::technique_timings::TechniqueTimings;
 use super::{
-    GURA_JPG, SRC_FRAG_DITHER, SRC_FRAG_KAWASE, SRC_FRAG_TEXTURE, SRC_VERT_QUAD, SRC_VERT_SCREEN,
+    dither_seed, Scene, SRC_FRAG_DITHER, SRC_FRAG_TEXTURE, SRC_VERT_QUAD, SRC_VERT_SCREEN,
 };
 
 const RESDIVS: &[u32] = &[2, 4, 8, 16, 32, 64];
 
+/// A pass per level, or the first levels in a single compute dispatch.
+const DOWNSAMPLING: &[&str] = &["passes", "compute"];
+
+/// How long the radius takes to reach a new value, in seconds.
+const RADIUS_TWEEN_DURATION: f32 = 0.25;
+
+// Both `screen.vert` and `quad.vert` have the same vertex attributes, so all
+// shaders use this layout.
+const LAYOUT: &[Attrib] = &[
+    Attrib {
+        name: c"position",
+        components: 2,
+        offset: 0,
+    },
+    Attrib {
+        name: c"uv",
+        components: 2,
+        offset: mem::size_of::<Vec2>(),
+    },
+];
+
+/// Typed view over the scene's parameters.
 struct BlurParams {
     pub radius: f32,
     pub layers: usize,
     pub is_dithered: bool,
+    pub compute: bool,
 }
 
 pub struct KawaseScene {
     matrix: Mat4,
     viewport: Vec2,
 
-    quad_shader: GLuint,
-    quad_vao: GLuint,
-    quad_vbo: GLuint,
-    quad_ebo: GLuint,
+    quad_shader: Program,
+    quad_vao: VertexArray,
+    quad_ebo: IndexBuffer,
+    // kept alive for the vertex array
+    _quad_vbo: Buffer<Vertex>,
 
-    composite_fbs: Vec<Framebuffer>,
-    comp_vao: GLuint,
-    comp_vbo: GLuint,
-    comp_shader: GLuint,
-    kawase_shader: GLuint,
-    dither_shader: GLuint,
+    chain: DownsampleChain,
+    comp_vao: VertexArray,
+    _comp_vbo: Buffer<Vertex>,
+    comp_shader: Program,
+    dither_shader: Program,
 
-    gura_texture: GLuint,
+    gura_texture: Texture,
+    adjuster: Adjuster,
 
-    u_mvp_quad: GLint,
-    u_mvp_dither: GLint,
-    u_distance: GLint,
-    u_upsample: GLint,
+    u_mvp_quad: Uniform,
+    u_mvp_dither: Uniform,
 
-    blur: BlurParams,
-
-    indices: Vec<[u32; 6]>,
+    params: Params,
+    /// The radius easing towards its parameter.
+    tweens: Tweener<&'static str, f32>,
+    /// Of the downsampling, in both ways.
+    timings: TechniqueTimings,
 
     last_instant: Instant,
 }
 
 impl KawaseScene {
-    pub fn new(window: &Window) -> Self {
-        let PhysicalSize { width, height } = window.inner_size();
-        let viewport = Vec2::new(width as f32, height as f32);
-
-        let (gura, gura_texture) = unsafe {
-            // Gura texture
-            let gura = image::load_from_memory_with_format(GURA_JPG, ImageFormat::Jpeg);
-            // let gura = image::load_from_memory_with_format(BIG_SQUARES_PNG, ImageFormat::Png);
-            let gura = gura.unwrap().into_rgba8();
-
-            let mut gura_texture: GLuint = 0;
-            gl::GenTextures(1, &mut gura_texture);
-            upload_texture(
-                gura_texture,
-                gura.width(),
-                gura.height(),
-                gura.as_ptr(),
-                gl::CLAMP_TO_BORDER,
-            );
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
 
-            (gura, gura_texture)
-        };
+    pub fn new(size: PhysicalSize<u32>, input: &InputImage, seed: u64) -> Result<Self, GlError> {
+        let PhysicalSize { width, height } = size;
+        let viewport = Vec2::new(width as f32, height as f32);
 
+        let gura = &input.image;
         let gura_size = uvec2(gura.width(), gura.height());
-
-        // They don't need to be vecs, but I'm too lazy to un-vector them now.
-        let mut quads = Vec::with_capacity(1);
-        let mut vertices = Vec::with_capacity(1);
-        let mut indices = Vec::with_capacity(1);
+        let gura_texture = Texture::new(gura_size, gura.as_raw(), gl::CLAMP_TO_BORDER)?;
 
         let quad = Quad {
             position: Vec2::ZERO,
             size: gura_size.as_vec2(),
         };
-        vertices.push(quad.vertices());
-        indices.push(quad.indices(0));
-        quads.push(quad);
-
-        unsafe {
-            // Normal blending
-            gl::Enable(gl::BLEND);
-            gl::BlendEquation(gl::FUNC_ADD);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-
-            // framebuffers
-            let composite_fbs = (RESDIVS.iter().copied())
-                .map(|resdiv| create_framebuffer("composite", gura_size / resdiv))
-                .collect::<Vec<_>>();
-
-            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-
-            // quad vertices
-            let mut quad_vao: GLuint = 0;
-            gl::GenVertexArrays(1, &mut quad_vao);
-            gl::BindVertexArray(quad_vao);
-
-            let mut quad_vbo: GLuint = 0;
-            gl::GenBuffers(1, &mut quad_vbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, quad_vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                mem::size_of_val(vertices.as_slice()) as GLsizeiptr,
-                vertices.as_slice().as_ptr() as *const _,
-                gl::DYNAMIC_DRAW,
-            );
-
-            let mut quad_ebo: GLuint = 0;
-            gl::GenBuffers(1, &mut quad_ebo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, quad_ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                mem::size_of_val(indices.as_slice()) as GLsizeiptr,
-                indices.as_slice().as_ptr() as *const _,
-                gl::STATIC_DRAW,
-            );
-
-            // quad shaders
-            let quad_shader = create_shader_program(SRC_VERT_QUAD, SRC_FRAG_TEXTURE);
-            let u_mvp_quad = gl::GetUniformLocation(quad_shader, c"u_mvp".as_ptr());
-            Self::set_pos_uv_vertex_attribs(quad_shader);
-
-            let dither_shader = create_shader_program(SRC_VERT_QUAD, SRC_FRAG_DITHER);
-            let u_mvp_dither = gl::GetUniformLocation(dither_shader, c"u_mvp".as_ptr());
-            Self::set_pos_uv_vertex_attribs(dither_shader);
-
-            // compositing vertices
-            let mut comp_vao: GLuint = 0;
-            gl::GenVertexArrays(1, &mut comp_vao);
-            gl::BindVertexArray(comp_vao);
-
-            let mut comp_vbo: GLuint = 0;
-            gl::GenBuffers(1, &mut comp_vbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, comp_vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                mem::size_of_val(SCREEN_VERTICES) as GLsizeiptr,
-                SCREEN_VERTICES.as_ptr() as *const _,
-                gl::DYNAMIC_DRAW,
-            );
-
-            // compositing shaders
-            let comp_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_TEXTURE);
-            Self::set_pos_uv_vertex_attribs(comp_shader);
 
-            let kawase_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_KAWASE);
-            let u_distance = gl::GetUniformLocation(kawase_shader, c"u_distance".as_ptr());
-            let u_upsample = gl::GetUniformLocation(kawase_shader, c"u_upsample".as_ptr());
-            Self::set_pos_uv_vertex_attribs(kawase_shader);
+        // framebuffers
+        let chain = DownsampleChain::new(
+            "composite",
+            gura_size / RESDIVS[0],
+            RESDIVS.len(),
+            gl::RGBA8,
+        )?;
+        bind_framebuffer(None);
+
+        // quad vertices
+        let quad_vbo = Buffer::new(gl::ARRAY_BUFFER, &quad.vertices(), gl::DYNAMIC_DRAW);
+        let quad_ebo = IndexBuffer::new(&quad.indices(0), gl::STATIC_DRAW);
+
+        // quad shaders
+        let mut quad_vao = VertexArray::new();
+        let quad_shader = Program::new(SRC_VERT_QUAD, SRC_FRAG_TEXTURE)?;
+        let u_mvp_quad = quad_shader.uniform(c"u_mvp");
+        quad_vao.set_layout(&quad_shader, &quad_vbo, LAYOUT);
+
+        let dither_shader = Program::new(SRC_VERT_QUAD, SRC_FRAG_DITHER)?;
+        let u_mvp_dither = dither_shader.uniform(c"u_mvp");
+        dither_shader.set(dither_shader.uniform(c"u_seed"), dither_seed(seed));
+        quad_vao.set_layout(&dither_shader, &quad_vbo, LAYOUT);
+
+        // compositing vertices and shaders
+        let comp_vbo = Buffer::new(gl::ARRAY_BUFFER, SCREEN_VERTICES, gl::STATIC_DRAW);
+        let mut comp_vao = VertexArray::new();
+        let comp_shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_TEXTURE)?;
+        comp_vao.set_layout(&comp_shader, &comp_vbo, LAYOUT);
+
+        // default blur parameters
+        let params = adjust::with_params(
+            Params::new()
+                .with_f32(
+                    "radius",
+                    1.0,
+                    0.2,
+                    *RESDIVS.last().unwrap() as f32 / 2.0,
+                    0.1,
+                )
+                .with_i32("layers", 1, 0, RESDIVS.len() as i32 - 1)
+                .with_bool("dither", false)
+                .with_enum("downsampling", 0, DOWNSAMPLING),
+        );
 
-            // default blur parameters
-            let blur = BlurParams {
-                radius: 1.0,
-                layers: 1,
-                is_dithered: false,
-            };
+        Ok(Self {
+            matrix: Mat4::default(),
+            viewport,
 
-            Self {
-                matrix: Mat4::default(),
-                viewport,
+            quad_shader,
+            quad_vao,
+            quad_ebo,
+            _quad_vbo: quad_vbo,
 
-                quad_shader,
-                quad_vao,
-                quad_vbo,
-                quad_ebo,
+            chain,
+            comp_vao,
+            _comp_vbo: comp_vbo,
+            comp_shader,
+            dither_shader,
 
-                composite_fbs,
-                comp_vao,
-                comp_vbo,
-                comp_shader,
-                kawase_shader,
-                dither_shader,
+            gura_texture,
+            adjuster: Adjuster::new(gura_size)?,
 
-                gura_texture,
+            u_mvp_quad,
+            u_mvp_dither,
 
-                u_mvp_quad,
-                u_mvp_dither,
-                u_distance,
-                u_upsample,
+            params,
+            tweens: Tweener::new(),
+            timings: TechniqueTimings::create(DOWNSAMPLING),
 
-                blur,
+            last_instant: Instant::now(),
+        })
+    }
 
-                indices,
+    fn blur(&self) -> BlurParams {
+        // without updates, e.g. when rendering headlessly, the parameter is used as is
+        let radius = self.params.f32("radius");
+        let radius = match self.tweens.target(&"radius") {
+            Some(target) if target == radius => self.tweens.value(&"radius").unwrap(),
+            _ => radius,
+        };
 
-                last_instant: Instant::now(),
-            }
+        BlurParams {
+            radius,
+            layers: self.params.i32("layers") as usize,
+            is_dithered: self.params.bool("dither"),
+            compute: self.params.variant("downsampling") == 1,
         }
     }
 
-    unsafe fn set_pos_uv_vertex_attribs(shader: GLuint) {
-        // Both `screen.vert` and `quad.vert` have the same vertex
-        // attributes, so I'm using this function for all shaders.
+    fn draw_with_clear_color(
+        &mut self,
+        gura_texture: GLuint,
+        clear_color: [f32; 4],
+        compute: bool,
+    ) {
+        let blur = self.blur();
+        let (_group, texture) = if blur.layers == 0 {
+            (DebugGroup::push(c"Draw normally"), gura_texture)
+        } else {
+            let group = DebugGroup::push(c"Draw with blurring");
 
-        const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
-        const SIZE_F32: GLsizei = mem::size_of::<f32>() as GLsizei;
+            let input_fb = self.chain.level(0);
 
-        #[rustfmt::skip]
-        {
-            let a_position = gl::GetAttribLocation(shader, c"position" .as_ptr()) as GLuint;
-            let a_uv       = gl::GetAttribLocation(shader, c"uv"       .as_ptr()) as GLuint;
+            // draw Gura to framebuffer
+            {
+                let _group = DebugGroup::push(c"Gura to framebuffer");
 
-            gl::VertexAttribPointer(a_position, 2, gl::FLOAT, gl::FALSE, SIZE_VERTEX,  0             as _);
-            gl::VertexAttribPointer(a_uv,       2, gl::FLOAT, gl::FALSE, SIZE_VERTEX, (2 * SIZE_F32) as _);
+                bind_framebuffer(Some(input_fb));
+                set_viewport(input_fb.size);
+                clear([0.0; 4]);
 
-            gl::EnableVertexAttribArray(a_position as GLuint);
-            gl::EnableVertexAttribArray(a_uv       as GLuint);
-        };
-    }
-
-    pub fn on_key(&mut self, keycode: Key<SmolStr>) {
-        match keycode {
-            Key::Named(NamedKey::ArrowRight) => {
-                self.blur.radius =
-                    (self.blur.radius + 0.1).min(*RESDIVS.last().unwrap() as f32 / 2.0);
-            }
-            Key::Named(NamedKey::ArrowLeft) => {
-                self.blur.radius = (self.blur.radius - 0.1).max(0.2);
+                self.comp_shader.bind();
+                bind_texture(0, gura_texture);
+                self.comp_vao.draw_arrays(gl::TRIANGLES);
             }
-            Key::Character(ch) => match ch.as_str() {
-                "d" | "D" => {
-                    self.blur.is_dithered = !self.blur.is_dithered;
-                }
-                "l" => {
-                    self.blur.layers = (self.blur.layers + 1).min(5);
-                }
-                "L" => {
-                    self.blur.layers = self.blur.layers.saturating_sub(1);
-                }
-                _ => return,
-            },
-            _ => return,
-        };
 
-        let dither_mode = if self.blur.is_dithered {
-            " dithering"
-        } else {
-            ""
+            // blur at half-resolution, then quarter-res, then eighth-res, ...
+            let chain = &self.chain;
+            self.timings.time(compute as usize, || match compute {
+                true => chain.downsample_compute(blur.layers, blur.radius),
+                false => chain.downsample(blur.layers, blur.radius),
+            });
+            // ..., then eighth-res, then quarter-res, then half-resolution
+            self.chain.upsample(blur.layers, blur.radius * 0.5);
+
+            (group, input_fb.texture)
         };
 
-        println!(
-            "kawase config: r={:.2} l={} {}",
-            self.blur.radius, self.blur.layers, dither_mode
-        );
-    }
+        // draw framebuffer to screen as quad
+        let _group = DebugGroup::push(c"Final draw to quad");
 
-    pub fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) {
-        self.last_instant = Instant::now();
+        bind_framebuffer(None);
+        set_viewport(self.viewport.as_uvec2());
+        clear(clear_color);
 
-        self.draw_with_clear_color(0.0, 0.2, 0.15, 0.5);
+        if blur.is_dithered {
+            self.dither_shader.bind();
+        } else {
+            self.quad_shader.bind();
+        }
+        bind_texture(0, texture);
+        self.quad_vao.draw_elements(gl::TRIANGLES, &self.quad_ebo);
     }
+}
 
-    fn draw_with_clear_color(&self, r: GLfloat, g: GLfloat, b: GLfloat, a: GLfloat) {
-        unsafe {
-            let texture = if self.blur.layers == 0 {
-                push_debug_group(c"Draw normally");
-
-                self.gura_texture
-            } else {
-                push_debug_group(c"Draw with blurring");
-
-                let mut input_fb = &self.composite_fbs[0];
-
-                // draw Gura to framebuffer
-                push_debug_group(c"Gura to framebuffer");
-                {
-                    gl::BindFramebuffer(gl::FRAMEBUFFER, input_fb.fbo);
-                    gl::Viewport(0, 0, input_fb.size.x as i32, input_fb.size.y as i32);
-
-                    gl::ClearColor(0.0, 0.0, 0.0, 0.0);
-                    gl::Clear(gl::COLOR_BUFFER_BIT);
-                    gl::UseProgram(self.comp_shader);
-
-                    gl::BindVertexArray(self.comp_vao);
-                    gl::BindBuffer(gl::ARRAY_BUFFER, self.comp_vbo);
-                    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-                    gl::BufferSubData(
-                        gl::ARRAY_BUFFER,
-                        0,
-                        mem::size_of_val(SCREEN_VERTICES) as GLsizeiptr,
-                        SCREEN_VERTICES.as_ptr() as *const _,
-                    );
-
-                    gl::BindTexture(gl::TEXTURE_2D, self.gura_texture);
-                    gl::ActiveTexture(gl::TEXTURE0);
-                    gl::DrawArrays(gl::TRIANGLES, 0, 6);
-                }
-                pop_debug_group();
+impl Scene for KawaseScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
 
-                // blur at half-resolution, then quarter-res, then eighth-res, ...
-                push_debug_group(c"Kawase downsampling");
-                #[allow(clippy::needless_range_loop)]
-                for fbi in 1..=self.blur.layers {
-                    // FBI OPEN UP
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let (name, steps) = match keycode {
+            Key::Named(NamedKey::ArrowRight) => ("radius", 1),
+            Key::Named(NamedKey::ArrowLeft) => ("radius", -1),
+            Key::Character(ch) => match ch.as_str() {
+                "d" | "D" => ("dither", 1),
+                "m" => ("downsampling", 1),
+                "M" => ("downsampling", -1),
+                "l" => ("layers", 1),
+                "L" => ("layers", -1),
+                ch => match adjust::key_step(ch) {
+                    Some(step) => step,
+                    None => return,
+                },
+            },
+            _ => return,
+        };
 
-                    let output_fb = &self.composite_fbs[fbi];
-                    let distance = self.blur.radius;
-                    input_fb = self.kawase_pass(distance, false, input_fb, output_fb);
+        self.params.step(name, steps).unwrap();
+        if name == "downsampling" && self.params.variant(name) == 1 {
+            match self.chain.enable_compute() {
+                Ok(true) => {}
+                Ok(false) => {
+                    println!("Compute downsampling needs OpenGL 4.3");
+                    self.params.step(name, steps).unwrap();
                 }
-                pop_debug_group();
-
-                // ..., then eighth-res, then quarter-res, then half-resolution
-                push_debug_group(c"Kawase upsampling");
-                for fbi in (0..self.blur.layers).rev() {
-                    // FBI OPEN UP
-
-                    let output_fb = &self.composite_fbs[fbi];
-                    let distance = self.blur.radius * 0.5;
-                    input_fb = self.kawase_pass(distance, true, input_fb, output_fb);
+                Err(e) => {
+                    eprintln!("Could not set up compute downsampling: {e}");
+                    self.params.step(name, steps).unwrap();
                 }
-                pop_debug_group();
-
-                input_fb.texture
-            };
-
-            // draw framebuffer to screen as quad
-            push_debug_group(c"Final draw to quad");
-            {
-                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-                gl::Viewport(0, 0, self.viewport.x as i32, self.viewport.y as i32);
-
-                gl::ClearColor(r, g, b, a);
-                gl::Clear(gl::COLOR_BUFFER_BIT);
-                if self.blur.is_dithered {
-                    gl::UseProgram(self.dither_shader);
-                } else {
-                    gl::UseProgram(self.quad_shader);
-                }
-
-                gl::BindVertexArray(self.quad_vao);
-                gl::BindBuffer(gl::ARRAY_BUFFER, self.quad_vbo);
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.quad_ebo);
-
-                gl::BindTexture(gl::TEXTURE_2D, texture);
-                gl::DrawElements(
-                    gl::TRIANGLES,
-                    mem::size_of_val(self.indices.as_slice()) as GLsizei,
-                    gl::UNSIGNED_INT,
-                    std::ptr::null(),
-                );
             }
-            pop_debug_group();
-
-            pop_debug_group(); // Draw normally / with blurring
         }
+        println!("kawase config: {}", self.params);
     }
 
-    fn kawase_pass<'a>(
-        &self,
-        distance: f32,
-        upsample: bool,
-        from_fb: &Framebuffer,
-        to_fb: &'a Framebuffer,
-    ) -> &'a Framebuffer {
-        unsafe {
-            push_debug_group(c"Kawase pass");
-
-            gl::BindFramebuffer(gl::FRAMEBUFFER, to_fb.fbo);
-            gl::Viewport(0, 0, to_fb.size.x as i32, to_fb.size.y as i32);
-
-            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
-            gl::UseProgram(self.kawase_shader);
-
-            gl::Uniform1f(self.u_distance, distance);
-            gl::Uniform1i(self.u_upsample, upsample as i32);
-
-            gl::BindVertexArray(self.comp_vao);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.comp_vbo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-            gl::BufferSubData(
-                gl::ARRAY_BUFFER,
-                0,
-                mem::size_of_val(SCREEN_VERTICES) as GLsizeiptr,
-                SCREEN_VERTICES.as_ptr() as *const _,
+    /// Eases the radius towards its parameter, however it was changed.
+    fn update(&mut self, dt: f32) {
+        let radius = self.params.f32("radius");
+        if self.tweens.target(&"radius") != Some(radius) {
+            let current = self.tweens.value(&"radius").unwrap_or(radius);
+            (self.tweens).animate_to(
+                "radius",
+                current,
+                radius,
+                RADIUS_TWEEN_DURATION,
+                Easing::CubicOut,
             );
+        }
 
-            gl::BindTexture(gl::TEXTURE_2D, from_fb.texture);
-            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        self.tweens.update(dt);
+    }
 
-            pop_debug_group();
+    fn stream_input(&mut self, input: &InputImage) -> Result<bool, GlError> {
+        let streamed = (self.gura_texture).stream(input, gl::CLAMP_TO_BORDER)?;
+        if streamed {
+            self.adjuster.invalidate();
         }
+        Ok(streamed)
+    }
 
-        to_fb
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
     }
 
-    pub fn resize(&mut self, camera: &Camera, width: i32, height: i32) {
-        unsafe {
-            gl::Viewport(0, 0, width, height);
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
 
-            self.viewport = Vec2::new(width as f32, height as f32);
-            self.matrix = camera.matrix(self.viewport);
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("kawase draw");
 
-            gl::UseProgram(self.quad_shader);
-            gl::UniformMatrix4fv(self.u_mvp_quad, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+        self.last_instant = Instant::now();
 
-            gl::UseProgram(self.dither_shader);
-            gl::UniformMatrix4fv(
-                self.u_mvp_dither,
-                1,
-                gl::FALSE,
-                self.matrix.as_ref().as_ptr(),
-            );
+        // also when set from the console, drawn with passes without support
+        let compute = self.blur().compute && self.chain.enable_compute()?;
+
+        let gura_texture = (self.adjuster).apply(self.gura_texture.id(), &self.params);
+        self.draw_with_clear_color(gura_texture, theme::current().backdrop, compute);
+        if let Some(summary) = self.timings.summarize() {
+            println!("kawase downsampling: {summary}");
         }
+        Ok(())
     }
-}
 
-impl Drop for KawaseScene {
-    fn drop(&mut self) {
-        unsafe {
-            gl::DeleteProgram(self.quad_shader);
-            gl::DeleteProgram(self.comp_shader);
-            gl::DeleteProgram(self.kawase_shader);
-            gl::DeleteProgram(self.dither_shader);
-
-            for comp_fb in &self.composite_fbs {
-                gl::DeleteFramebuffers(1, &comp_fb.fbo);
-                gl::DeleteTextures(1, &comp_fb.texture);
-            }
-
-            let buffers = &[self.quad_vbo, self.quad_ebo, self.comp_vbo];
-            gl::DeleteBuffers(buffers.len() as GLsizei, buffers.as_ptr());
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        self.viewport = Vec2::new(width as f32, height as f32);
+        set_viewport(self.viewport.as_uvec2());
+        self.matrix = camera.matrix(self.viewport);
 
-            let arrays = &[self.quad_vao, self.comp_vao];
-            gl::DeleteVertexArrays(arrays.len() as GLsizei, arrays.as_ptr());
-
-            gl::DeleteTextures(1, &self.gura_texture);
-        }
+        self.quad_shader.set(self.u_mvp_quad, self.matrix);
+        self.dither_shader.set(self.u_mvp_dither, self.matrix);
+        Ok(())
     }
 }
 
@@ -461,7 +372,7 @@ impl Quad {
         ];
     }
 
-    fn indices(&self, quad_index: u32) -> [u32; 6] {
+    fn indices(self, quad_index: u32) -> [u32; 6] {
         let i = quad_index * 4;
         [i, 1 + i, 2 + i, i, 2 + i, 3 + i]
     }
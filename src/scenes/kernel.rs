@@ -0,0 +1,414 @@
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, Mat4, UVec2, Vec2};
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, NamedKey, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{buffer_data, create_shader_program, delete_buffers, Texture};
+use crate::error::GlError;
+use crate::input_image::InputImage;
+use crate::params::{Params, Value};
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::{Scene, SceneEvent, SRC_FRAG_KERNEL, SRC_VERT_QUAD};
+
+/// Side of the kernel, which kernel.frag goes over in full.
+const SIDE: usize = 5;
+/// Parameters of the cells of the kernel, row by row: `kRC` is at row `R`
+/// and column `C`.
+const CELLS: [&str; SIDE * SIDE] = [
+    "k00", "k01", "k02", "k03", "k04", //
+    "k10", "k11", "k12", "k13", "k14", //
+    "k20", "k21", "k22", "k23", "k24", //
+    "k30", "k31", "k32", "k33", "k34", //
+    "k40", "k41", "k42", "k43", "k44", //
+];
+const MAX_WEIGHT: f32 = 100.0;
+
+/// Variants of the `preset` parameter, in order.
+const PRESETS: &[&str] = &["identity", "sharpen", "emboss", "edge", "gaussian", "box"];
+/// Variants of the `normalize` parameter, in order.
+const NORMALIZATIONS: &[&str] = &["none", "sum", "absolute"];
+
+/// Sizes of the panel showing the kernel, in pixels.
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+const CELL_SIZE: Vec2 = vec2(52.0, 28.0);
+const TEXT_SIZE: f32 = 14.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// The input image convolved with a 5×5 kernel in a single pass, to explore
+/// arbitrary kernels beside the dedicated blur pipelines.
+///
+/// Every weight is a parameter, `k12` being at row 1 and column 2, so cells
+/// can be edited from the console as well as from the panel in the corner.
+/// Presets fill the kernel with the classic ones, and the weighted sum is
+/// optionally divided by the sum of the weights or of their magnitudes, then
+/// offset by a bias, as embossing needs.
+pub struct KernelScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    image_size: UVec2,
+    /// Cell edited with the keyboard, as its column and row.
+    selected: UVec2,
+    /// Preset the kernel was last filled with, only known from the first
+    /// frame on so that parameters carried over from a previous instance
+    /// aren't overwritten.
+    preset: Option<usize>,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_kernel: GLint,
+    u_texel: GLint,
+    u_scale: GLint,
+    u_bias: GLint,
+    u_split: GLint,
+    u_clear: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    texture: Texture,
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+impl KernelScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(input: &InputImage, text: Option<SharedText>) -> Result<Self, GlError> {
+        let image = &input.image;
+        let image_size = uvec2(image.width(), image.height());
+
+        let half = image_size.as_vec2() / 2.0;
+        let vertex = |x: f32, y: f32| Vertex {
+            position: (vec2(x, y) * 2.0 - 1.0) * half,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        let texture = Texture::new(image_size, image.as_raw(), gl::CLAMP_TO_BORDER)?;
+
+        unsafe {
+            let shader = create_shader_program(SRC_VERT_QUAD, SRC_FRAG_KERNEL)?;
+            let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(shader, name.as_ptr());
+            gl::UseProgram(shader);
+            gl::Uniform1i(uniform(c"u_image"), 0);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let params = (CELLS.iter()).fold(
+                Params::new().with_enum("preset", 1, PRESETS),
+                |params, &cell| params.with_f32(cell, 0.0, -MAX_WEIGHT, MAX_WEIGHT, 1.0),
+            );
+            let params = params
+                .with_enum("normalize", 0, NORMALIZATIONS)
+                .with_f32("bias", 0.0, -1.0, 1.0, 0.05)
+                .with_bool("split", false);
+
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+
+                image_size,
+                selected: UVec2::splat(SIDE as u32 / 2),
+                preset: None,
+
+                u_mvp: uniform(c"u_mvp"),
+                u_kernel: uniform(c"u_kernel"),
+                u_texel: uniform(c"u_texel"),
+                u_scale: uniform(c"u_scale"),
+                u_bias: uniform(c"u_bias"),
+                u_split: uniform(c"u_split"),
+                u_clear: uniform(c"u_clear"),
+                shader,
+                vao,
+                vbo,
+                texture,
+
+                text,
+
+                params,
+            };
+            scene.apply_preset();
+            Ok(scene)
+        }
+    }
+
+    fn selected_cell(&self) -> &'static str {
+        CELLS[self.selected.y as usize * SIDE + self.selected.x as usize]
+    }
+
+    /// Fills the kernel with the preset of the `preset` parameter, and picks
+    /// the normalization and bias it goes with.
+    fn apply_preset(&mut self) {
+        let mut weights = [0.0; SIDE * SIDE];
+        // a 3×3 kernel in the middle of the 5×5 one
+        let mut middle = |kernel: [f32; 9]| {
+            for (i, weight) in kernel.into_iter().enumerate() {
+                weights[(i / 3 + 1) * SIDE + i % 3 + 1] = weight;
+            }
+        };
+        let normalize = match PRESETS[self.params.variant("preset")] {
+            "identity" => {
+                middle([0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+                "none"
+            }
+            "sharpen" => {
+                middle([0.0, -1.0, 0.0, -1.0, 5.0, -1.0, 0.0, -1.0, 0.0]);
+                "none"
+            }
+            "emboss" => {
+                middle([-2.0, -1.0, 0.0, -1.0, 1.0, 1.0, 0.0, 1.0, 2.0]);
+                "none"
+            }
+            "edge" => {
+                middle([-1.0, -1.0, -1.0, -1.0, 8.0, -1.0, -1.0, -1.0, -1.0]);
+                "none"
+            }
+            "gaussian" => {
+                // binomial coefficients, the outer product of 1 4 6 4 1
+                const ROW: [f32; SIDE] = [1.0, 4.0, 6.0, 4.0, 1.0];
+                for (i, weight) in weights.iter_mut().enumerate() {
+                    *weight = ROW[i / SIDE] * ROW[i % SIDE];
+                }
+                "sum"
+            }
+            _ => {
+                weights = [1.0; SIDE * SIDE];
+                "sum"
+            }
+        };
+
+        for (cell, weight) in CELLS.into_iter().zip(weights) {
+            self.params.set(cell, Value::Number(weight as f64)).unwrap();
+        }
+        let normalize = Value::Text(normalize.to_string());
+        self.params.set("normalize", normalize).unwrap();
+        self.params.set("bias", Value::Number(0.0)).unwrap();
+    }
+
+    fn weights(&self) -> [f32; SIDE * SIDE] {
+        CELLS.map(|cell| self.params.f32(cell))
+    }
+
+    /// What the weighted sum is divided by, from the `normalize` parameter.
+    /// Kernels adding up to 0 are left as they are.
+    fn divisor(&self) -> f32 {
+        let weights = self.weights();
+        let total = match NORMALIZATIONS[self.params.variant("normalize")] {
+            "sum" => weights.iter().sum::<f32>(),
+            "absolute" => weights.iter().map(|w| w.abs()).sum::<f32>(),
+            _ => 1.0,
+        };
+        match total.abs() > f32::EPSILON {
+            true => total,
+            false => 1.0,
+        }
+    }
+
+    /// Top left corner of the first cell of the panel, in pixels.
+    fn grid_origin() -> Vec2 {
+        Vec2::splat(MARGIN + PADDING)
+    }
+}
+
+impl Scene for KernelScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let selected = self.selected_cell();
+        let side = SIDE as u32;
+        match keycode.as_ref() {
+            Key::Named(NamedKey::ArrowLeft) => {
+                self.selected.x = (self.selected.x + side - 1) % side
+            }
+            Key::Named(NamedKey::ArrowRight) => self.selected.x = (self.selected.x + 1) % side,
+            Key::Named(NamedKey::ArrowUp) => self.selected.y = (self.selected.y + side - 1) % side,
+            Key::Named(NamedKey::ArrowDown) => self.selected.y = (self.selected.y + 1) % side,
+            Key::Character("=" | "+") => self.params.step(selected, 1).unwrap(),
+            Key::Character("-" | "_") => self.params.step(selected, -1).unwrap(),
+            Key::Character("0") => self.params.set(selected, Value::Number(0.0)).unwrap(),
+            Key::Character("k") => self.params.step("preset", 1).unwrap(),
+            Key::Character("K") => self.params.step("preset", -1).unwrap(),
+            Key::Character("n") => self.params.step("normalize", 1).unwrap(),
+            Key::Character("N") => self.params.step("normalize", -1).unwrap(),
+            Key::Character("b") => self.params.step("bias", 1).unwrap(),
+            Key::Character("B") => self.params.step("bias", -1).unwrap(),
+            Key::Character("s" | "S") => self.params.toggle("split").unwrap(),
+            Key::Character("r" | "R") => self.apply_preset(),
+            _ => return,
+        }
+
+        println!("kernel config: {}", self.params);
+    }
+
+    /// Selects the cell of the panel under the mouse with the left button.
+    /// Returns whether the click was used, in which case the camera
+    /// shouldn't pan.
+    fn on_event(&mut self, _camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if button != MouseButton::Left || state != ElementState::Pressed {
+            return false;
+        }
+
+        let cell = ((mouse_pos - Self::grid_origin()) / CELL_SIZE).floor();
+        if cell.cmplt(Vec2::ZERO).any() || cell.cmpge(Vec2::splat(SIDE as f32)).any() {
+            return false;
+        }
+        self.selected = cell.as_uvec2();
+        true
+    }
+
+    fn stream_input(&mut self, input: &InputImage) -> Result<bool, GlError> {
+        if !self.texture.stream(input, gl::CLAMP_TO_BORDER)? {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("kernel draw");
+
+        let preset = self.params.variant("preset");
+        if self.preset.is_some_and(|previous| previous != preset) {
+            self.apply_preset();
+        }
+        self.preset = Some(preset);
+
+        let weights = self.weights();
+        let (divisor, bias) = (self.divisor(), self.params.f32("bias"));
+        let theme = theme::current();
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            gl::UseProgram(self.shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+            gl::Uniform1fv(self.u_kernel, weights.len() as GLsizei, weights.as_ptr());
+            let texel = 1.0 / self.image_size.as_vec2();
+            gl::Uniform2f(self.u_texel, texel.x, texel.y);
+            gl::Uniform1f(self.u_scale, 1.0 / divisor);
+            gl::Uniform1f(self.u_bias, bias);
+            gl::Uniform1i(self.u_split, self.params.bool("split") as GLint);
+            gl::Uniform3fv(self.u_clear, 1, theme.clear.as_ptr());
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture.id());
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::BindVertexArray(0);
+
+            // the kernel, with the selected cell highlighted
+            let origin = Self::grid_origin();
+            let grid = CELL_SIZE * SIDE as f32;
+            let footer = format!("÷ {}   + {:.2}", short(divisor), bias);
+            let Some(text) = &self.text else {
+                return Ok(());
+            };
+            let mut text = text.borrow_mut();
+            let footer_height = text.measure(&footer, TEXT_SIZE, None).y;
+            let max = origin + grid + vec2(PADDING, PADDING * 2.0 + footer_height);
+            text.queue_rect(Vec2::splat(MARGIN), max, theme.hud_background);
+
+            let selected = origin + self.selected.as_vec2() * CELL_SIZE;
+            text.queue_rect(selected, selected + CELL_SIZE, theme.accent(0.4));
+            for (i, weight) in weights.into_iter().enumerate() {
+                let label = short(weight);
+                let cell = origin + vec2((i % SIDE) as f32, (i / SIDE) as f32) * CELL_SIZE;
+                let extent = text.measure(&label, TEXT_SIZE, None);
+                let mut color = theme.hud_text;
+                if weight == 0.0 {
+                    color[3] *= 0.4;
+                }
+                let position = cell + (CELL_SIZE - extent) / 2.0;
+                text.queue(&label, position, TEXT_SIZE, None, color);
+            }
+            let position = origin + vec2(0.0, grid.y + PADDING);
+            text.queue(&footer, position, TEXT_SIZE, None, theme.hud_text);
+            text.draw(0, self.viewport);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+impl Drop for KernelScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
+
+/// `n` with at most 3 decimals and no trailing zeros.
+fn short(n: f32) -> String {
+    let text = format!("{n:.3}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
@@ -0,0 +1,449 @@
+use std::f32::consts::TAU;
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{vec2, Mat4, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_shader_program, delete_buffers, delete_textures, upload_texture,
+};
+use crate::error::GlError;
+use crate::marching_squares::{self, Field};
+use crate::palette;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::polyline::PolylineRenderer;
+use super::{Scene, SceneEvent, SRC_FRAG_MARCHING_SQUARES, SRC_VERT_QUAD};
+
+/// Half the size of the region the field covers, in world units.
+const HALF_EXTENT: Vec2 = vec2(640.0, 360.0);
+/// Octaves of value noise adding up to the `noise` field.
+const OCTAVES: usize = 4;
+/// Points the rings of the `waves` field come from.
+const N_SOURCES: usize = 3;
+/// Texels of the colormap of the bands.
+const COLORMAP_TEXELS: usize = 256;
+
+/// Variants of the `source` parameter, in order.
+const SOURCES: &[&str] = &["noise", "waves"];
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// Iso-contours of an animated scalar field, either fractal value noise or
+/// rings of waves interfering with each other, extracted on the CPU with
+/// marching squares (see [`crate::marching_squares`]) and drawn with the
+/// polyline renderer.
+///
+/// The contours are spaced evenly around the iso-value, which is drawn
+/// thicker and scrubbed along the window with the right button. The bands
+/// between them are filled by a shader sampling the field from a float
+/// texture, whose bilinear filtering crosses the iso-values where marching
+/// squares does along the edges of the cells.
+pub struct MarchingSquaresScene {
+    matrix: Mat4,
+    viewport: Vec2,
+    /// Pixels per world unit.
+    pixels_per_unit: f32,
+
+    seed: u64,
+    /// Where the rings of the `waves` field come from.
+    sources: Vec<Vec2>,
+    /// Seconds the field has been moving for.
+    time: f32,
+    field: Field,
+    /// Whether the right button is held, scrubbing the iso-value.
+    scrubbing: bool,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_iso: GLint,
+    u_spacing: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    field_texture: GLuint,
+    colormap: GLuint,
+
+    polylines: PolylineRenderer,
+
+    params: Params,
+}
+
+impl MarchingSquaresScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let colormap_pixels = (0..COLORMAP_TEXELS)
+            .flat_map(|i| {
+                let t = i as f32 / (COLORMAP_TEXELS - 1) as f32;
+                palette::pack(palette::viridis(t), 255).to_le_bytes()
+            })
+            .collect::<Vec<_>>();
+
+        unsafe {
+            let shader = create_shader_program(SRC_VERT_QUAD, SRC_FRAG_MARCHING_SQUARES)?;
+            let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(shader, name.as_ptr());
+            gl::UseProgram(shader);
+            gl::Uniform1i(uniform(c"u_field"), 0);
+            gl::Uniform1i(uniform(c"u_colormap"), 1);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let mut textures = [0; 2];
+            gl::GenTextures(2, textures.as_mut_ptr());
+            let [field_texture, colormap] = textures;
+            upload_texture(
+                colormap,
+                COLORMAP_TEXELS as u32,
+                1,
+                colormap_pixels.as_ptr(),
+                gl::CLAMP_TO_EDGE,
+            )?;
+            gl::BindTexture(gl::TEXTURE_2D, field_texture);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+                pixels_per_unit: 1.0,
+
+                seed,
+                sources: Vec::new(),
+                time: 0.0,
+                field: Field::new(0, 0, |_, _| 0.0),
+                scrubbing: false,
+
+                u_mvp: uniform(c"u_mvp"),
+                u_iso: uniform(c"u_iso"),
+                u_spacing: uniform(c"u_spacing"),
+                shader,
+                vao,
+                vbo,
+                field_texture,
+                colormap,
+
+                polylines: PolylineRenderer::new()?,
+
+                params: Params::new()
+                    .with_enum("source", 0, SOURCES)
+                    .with_f32("iso", 0.0, -1.0, 1.0, 0.05)
+                    .with_f32("spacing", 0.2, 0.05, 1.0, 0.05)
+                    .with_i32("cell", 8, 4, 32)
+                    .with_f32("scale", 200.0, 50.0, 600.0, 25.0)
+                    .with_f32("speed", 0.2, 0.0, 2.0, 0.1)
+                    .with_bool("bands", true)
+                    .with_bool("running", true),
+            };
+            scene.reseed(seed);
+            Ok(scene)
+        }
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.sources = (0..N_SOURCES)
+            .map(|_| {
+                vec2(
+                    rng.gen_range(-HALF_EXTENT.x..HALF_EXTENT.x),
+                    rng.gen_range(-HALF_EXTENT.y..HALF_EXTENT.y),
+                )
+            })
+            .collect();
+    }
+
+    fn scrub(&mut self, mouse_pos: Vec2) {
+        if self.scrubbing {
+            let t = mouse_pos.x / self.viewport.x;
+            self.params.set_normalized("iso", t).unwrap();
+        }
+    }
+
+    /// Samples the field at every point of the grid, from -1 to 1 or so.
+    fn sample_field(&mut self) {
+        profiling::cpu_zone!("marching squares field");
+
+        let cell = self.params.i32("cell") as f32;
+        let width = (HALF_EXTENT.x * 2.0 / cell).ceil() as usize + 1;
+        let height = (HALF_EXTENT.y * 2.0 / cell).ceil() as usize + 1;
+        let scale = self.params.f32("scale");
+        let (time, seed) = (self.time, self.seed as u32);
+
+        let world = |x: usize, y: usize| -HALF_EXTENT + vec2(x as f32, y as f32) * cell;
+        self.field = match SOURCES[self.params.variant("source")] {
+            "noise" => Field::new(width, height, |x, y| {
+                let p = world(x, y) / scale;
+                let (mut sum, mut amplitude, mut frequency) = (0.0, 1.0, 1.0);
+                for octave in 0..OCTAVES {
+                    // every octave drifts its own way, so the field churns
+                    // rather than slides
+                    let drift = Vec2::from_angle(octave as f32 * 2.4) * time;
+                    let seed = seed.wrapping_add(octave as u32);
+                    sum += amplitude * value_noise(p * frequency + drift, seed);
+                    amplitude *= 0.5;
+                    frequency *= 2.0;
+                }
+                sum
+            }),
+            _ => Field::new(width, height, |x, y| {
+                let p = world(x, y);
+                let rings = (self.sources.iter())
+                    .map(|source| (p.distance(*source) / scale - time) * TAU)
+                    .map(f32::sin)
+                    .sum::<f32>();
+                rings / N_SOURCES as f32 * 1.5
+            }),
+        };
+    }
+}
+
+impl Scene for MarchingSquaresScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("s") => self.params.step("source", 1).unwrap(),
+            Key::Character("S") => self.params.step("source", -1).unwrap(),
+            Key::Character("i") => self.params.step("iso", 1).unwrap(),
+            Key::Character("I") => self.params.step("iso", -1).unwrap(),
+            Key::Character("b") => self.params.step("spacing", 1).unwrap(),
+            Key::Character("B") => self.params.step("spacing", -1).unwrap(),
+            Key::Character("c") => self.params.step("cell", 1).unwrap(),
+            Key::Character("C") => self.params.step("cell", -1).unwrap(),
+            Key::Character("z") => self.params.step("scale", 1).unwrap(),
+            Key::Character("Z") => self.params.step("scale", -1).unwrap(),
+            Key::Character("v") => self.params.step("speed", 1).unwrap(),
+            Key::Character("V") => self.params.step("speed", -1).unwrap(),
+            Key::Character("f" | "F") => self.params.toggle("bands").unwrap(),
+            Key::Character("p" | "P") => self.params.toggle("running").unwrap(),
+            Key::Character("r" | "R") => self.reseed(self.seed.wrapping_add(1)),
+            _ => return,
+        }
+
+        println!("marching squares config: {}", self.params);
+    }
+
+    /// Scrubs the iso-value along the window while the right button is held.
+    /// Returns whether the scene handled the button.
+    fn on_event(&mut self, _camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if button != MouseButton::Right {
+            return false;
+        }
+
+        self.scrubbing = state == ElementState::Pressed;
+        self.scrub(mouse_pos);
+        true
+    }
+
+    fn update(&mut self, dt: f32) {
+        if self.params.bool("running") {
+            self.time += dt * self.params.f32("speed");
+        }
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("marching squares draw");
+
+        self.scrub(mouse_pos);
+        self.sample_field();
+
+        let cell = self.params.i32("cell") as f32;
+        let (iso, spacing) = (self.params.f32("iso"), self.params.f32("spacing"));
+        let (width, height) = (self.field.width, self.field.height);
+        let pixel = 1.0 / self.pixels_per_unit;
+        let theme = theme::current();
+        let bands = self.params.bool("bands");
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            if bands {
+                gl::ActiveTexture(gl::TEXTURE0);
+                gl::BindTexture(gl::TEXTURE_2D, self.field_texture);
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::R32F as GLint,
+                    width as GLsizei,
+                    height as GLsizei,
+                    0,
+                    gl::RED,
+                    gl::FLOAT,
+                    self.field.values.as_ptr().cast(),
+                );
+                gl::ActiveTexture(gl::TEXTURE1);
+                gl::BindTexture(gl::TEXTURE_2D, self.colormap);
+                gl::ActiveTexture(gl::TEXTURE0);
+
+                // from the first grid point to the last, which are the
+                // centers of the texels on the sides
+                let (min, max) = (
+                    -HALF_EXTENT,
+                    -HALF_EXTENT + vec2(width as f32 - 1.0, height as f32 - 1.0) * cell,
+                );
+                let half_texel = 0.5 / vec2(width as f32, height as f32);
+                let vertex = |x: f32, y: f32| Vertex {
+                    position: min + (max - min) * vec2(x, y),
+                    uv: half_texel + (1.0 - 2.0 * half_texel) * vec2(x, y),
+                };
+                let vertices = [
+                    vertex(0.0, 1.0),
+                    vertex(0.0, 0.0),
+                    vertex(1.0, 0.0),
+                    vertex(0.0, 1.0),
+                    vertex(1.0, 0.0),
+                    vertex(1.0, 1.0),
+                ];
+                buffer_data(gl::ARRAY_BUFFER, self.vbo, &vertices, gl::STREAM_DRAW);
+
+                gl::UseProgram(self.shader);
+                gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+                gl::Uniform1f(self.u_iso, iso);
+                gl::Uniform1f(self.u_spacing, spacing);
+                gl::BindVertexArray(self.vao);
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                gl::BindVertexArray(0);
+            }
+
+            // every iso-value the field reaches, a spacing apart from the
+            // scrubbed one
+            let (low, high) = self.field.range();
+            let levels =
+                ((low - iso) / spacing).ceil() as i32..=((high - iso) / spacing).floor() as i32;
+            let minor = match bands {
+                true => [0.0, 0.0, 0.0, 0.4],
+                false => theme.grid,
+            };
+            {
+                profiling::cpu_zone!("marching squares contours");
+                for level in levels {
+                    let (width, color) = match level {
+                        0 => (3.0 * pixel, theme.accent),
+                        _ => (1.0 * pixel, minor),
+                    };
+                    for line in
+                        marching_squares::contours(&self.field, iso + level as f32 * spacing)
+                    {
+                        let points = (line.into_iter())
+                            .map(|p| -HALF_EXTENT + p * cell)
+                            .collect::<Vec<_>>();
+                        if level == 0 {
+                            // outlined, to stand out from bands of any color
+                            self.polylines
+                                .push(&points, width + 2.0 * pixel, [0.0, 0.0, 0.0, 0.6]);
+                        }
+                        self.polylines.push(&points, width, color);
+                    }
+                }
+            }
+            self.polylines.draw(&self.matrix);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        self.pixels_per_unit = camera.scale.x;
+        Ok(())
+    }
+}
+
+impl Drop for MarchingSquaresScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+            delete_textures(&[self.field_texture, self.colormap]);
+        }
+    }
+}
+
+/// Value noise at `p`: random values from -1 to 1 at the points of the
+/// integer grid, blended with a quintic fade.
+fn value_noise(p: Vec2, seed: u32) -> f32 {
+    let floor = p.floor();
+    let f = p - floor;
+    let fade = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+
+    let corner = |dx: i32, dy: i32| hash(floor.x as i32 + dx, floor.y as i32 + dy, seed);
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * fade.x;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * fade.x;
+    top + (bottom - top) * fade.y
+}
+
+/// Random value from -1 to 1 for a point of the integer grid.
+fn hash(x: i32, y: i32, seed: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x27d4_eb2d)
+        ^ (y as u32).wrapping_mul(0x1656_67b1)
+        ^ seed.wrapping_mul(0x9e37_79b9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297a_2d39);
+    h ^= h >> 15;
+    h as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
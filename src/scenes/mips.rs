@@ -0,0 +1,490 @@
+use std::mem;
+use std::rc::Rc;
+
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, vec3, Mat4, UVec2, Vec2};
+use image::imageops::FilterType;
+use image::RgbaImage;
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_shader_program, delete_buffers, delete_textures, upload_texture,
+};
+use crate::error::GlError;
+use crate::gpu_memory::{self, Category, Object};
+use crate::input_image::InputImage;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::{Scene, SRC_FRAG_MIPS, SRC_VERT_QUAD};
+
+/// Variants of the `filter` parameter, in order, with the minification and
+/// magnification filters they stand for.
+const FILTERS: &[(&str, GLenum, GLenum)] = &[
+    ("nearest", gl::NEAREST, gl::NEAREST),
+    ("bilinear", gl::LINEAR, gl::LINEAR),
+    ("nearest-mip", gl::NEAREST_MIPMAP_NEAREST, gl::NEAREST),
+    ("bilinear-mip", gl::LINEAR_MIPMAP_NEAREST, gl::LINEAR),
+    ("trilinear", gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR),
+];
+const FILTER_NAMES: &[&str] = &[
+    "nearest",
+    "bilinear",
+    "nearest-mip",
+    "bilinear-mip",
+    "trilinear",
+];
+/// Variants of the `compare` parameter, in order: off or the filter of the
+/// right half.
+const COMPARISONS: &[&str] = &[
+    "off",
+    "nearest",
+    "bilinear",
+    "nearest-mip",
+    "bilinear-mip",
+    "trilinear",
+];
+/// Variants of the `mode` parameter, in order.
+const MODES: &[&str] = &["auto", "fixed", "bias"];
+/// Variants of the `mips` parameter, in order: the driver's mipmaps or ones
+/// made on the CPU with the filter that downscales input images.
+const GENERATORS: &[&str] = &["driver", "lanczos"];
+
+/// World units between two copies.
+const GAP: f32 = 24.0;
+const TEXT_SIZE: f32 = 14.0;
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// The input image in a row of copies, each smaller than the previous one,
+/// to see which mip level gets sampled at which scale and what it looks like.
+///
+/// The level is either picked from the screen-space derivatives like the
+/// hardware does, forced with `textureLod`, or offset with a bias, and it can
+/// be tinted with a color per level. The right half of every copy can go
+/// through a second filter to compare them side by side, and the mipmaps can
+/// be made by the driver or on the CPU with the same Lanczos filter that
+/// downscales input images.
+pub struct MipsScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    image: Rc<RgbaImage>,
+    image_size: UVec2,
+    /// Number of levels down to 1×1.
+    levels: u32,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_compare: GLint,
+    u_left_mips: GLint,
+    u_right_mips: GLint,
+    u_mode: GLint,
+    u_lod: GLint,
+    u_max_level: GLint,
+    u_size: GLint,
+    u_tint: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    /// Mipmapped by `glGenerateMipmap`.
+    driver_texture: GLuint,
+    /// Mipmapped with Lanczos, only made once it's looked at as it takes a
+    /// while for big images.
+    lanczos_texture: Option<GLuint>,
+    /// For the left and right halves.
+    samplers: [GLuint; 2],
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+impl MipsScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(input: &InputImage, text: Option<SharedText>) -> Result<Self, GlError> {
+        let image = input.image.clone();
+        let image_size = uvec2(image.width(), image.height());
+        let levels = mip_levels(image_size);
+
+        let half = image_size.as_vec2() / 2.0;
+        let vertex = |x: f32, y: f32| Vertex {
+            position: (vec2(x, y) * 2.0 - 1.0) * half,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        unsafe {
+            let mut driver_texture = 0;
+            gl::GenTextures(1, &mut driver_texture);
+            upload_driver_mips(driver_texture, &image)?;
+
+            let mut samplers = [0; 2];
+            gl::GenSamplers(2, samplers.as_mut_ptr());
+            for sampler in samplers {
+                let clamp = gl::CLAMP_TO_EDGE as GLint;
+                gl::SamplerParameteri(sampler, gl::TEXTURE_WRAP_S, clamp);
+                gl::SamplerParameteri(sampler, gl::TEXTURE_WRAP_T, clamp);
+            }
+
+            let shader = create_shader_program(SRC_VERT_QUAD, SRC_FRAG_MIPS)?;
+            let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(shader, name.as_ptr());
+            gl::UseProgram(shader);
+            gl::Uniform1i(uniform(c"u_left"), 0);
+            gl::Uniform1i(uniform(c"u_right"), 1);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let params = Params::new()
+                .with_i32("copies", 8, 1, 12)
+                .with_f32("shrink", 0.6, 0.3, 0.9, 0.05)
+                .with_enum("mode", 0, MODES)
+                .with_f32("lod", 0.0, -4.0, 12.0, 0.25)
+                .with_enum("filter", 4, FILTER_NAMES)
+                .with_enum("compare", 0, COMPARISONS)
+                .with_enum("mips", 0, GENERATORS)
+                .with_f32("tint", 0.0, 0.0, 1.0, 0.25);
+
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+
+                image,
+                image_size,
+                levels,
+
+                u_mvp: uniform(c"u_mvp"),
+                u_compare: uniform(c"u_compare"),
+                u_left_mips: uniform(c"u_left_mips"),
+                u_right_mips: uniform(c"u_right_mips"),
+                u_mode: uniform(c"u_mode"),
+                u_lod: uniform(c"u_lod"),
+                u_max_level: uniform(c"u_max_level"),
+                u_size: uniform(c"u_size"),
+                u_tint: uniform(c"u_tint"),
+                shader,
+                vao,
+                vbo,
+                driver_texture,
+                lanczos_texture: None,
+                samplers,
+
+                text,
+
+                params,
+            })
+        }
+    }
+
+    /// Scales of the copies, from the image at its size down.
+    fn scales(&self) -> impl Iterator<Item = f32> {
+        let shrink = self.params.f32("shrink");
+        (0..self.params.i32("copies")).map(move |i| shrink.powi(i))
+    }
+
+    /// Centers of the copies in world space, side by side with their bottom
+    /// edges aligned and the row centered on the origin.
+    fn centers(&self) -> Vec<Vec2> {
+        let size = self.image_size.as_vec2();
+        let widths = self
+            .scales()
+            .map(|scale| size.x * scale)
+            .collect::<Vec<_>>();
+        let total = widths.iter().sum::<f32>() + GAP * (widths.len() - 1) as f32;
+
+        let mut left = -total / 2.0;
+        (widths.iter().zip(self.scales()))
+            .map(|(&width, scale)| {
+                let center = vec2(left + width / 2.0, (size.y - size.y * scale) / 2.0);
+                left += width + GAP;
+                center
+            })
+            .collect()
+    }
+
+    /// The texture of the `mips` parameter, making the Lanczos one if needed.
+    fn texture(&mut self) -> Result<GLuint, GlError> {
+        if GENERATORS[self.params.variant("mips")] == "driver" {
+            return Ok(self.driver_texture);
+        }
+        if let Some(texture) = self.lanczos_texture {
+            return Ok(texture);
+        }
+        unsafe {
+            profiling::cpu_zone!("lanczos mips");
+            let mut texture = 0;
+            gl::GenTextures(1, &mut texture);
+            upload_lanczos_mips(texture, &self.image)
+                .inspect_err(|_| delete_textures(&[texture]))?;
+            Ok(*self.lanczos_texture.insert(texture))
+        }
+    }
+
+    /// Screen position of a point in world space.
+    fn to_screen(&self, position: Vec2) -> Vec2 {
+        let ndc = self.matrix.project_point3(position.extend(0.0));
+        vec2(ndc.x + 1.0, 1.0 - ndc.y) / 2.0 * self.viewport
+    }
+}
+
+impl Scene for MipsScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let (name, direction) = match keycode.as_ref() {
+            Key::Character("c") => ("copies", 1),
+            Key::Character("C") => ("copies", -1),
+            Key::Character("s") => ("shrink", 1),
+            Key::Character("S") => ("shrink", -1),
+            Key::Character("m") => ("mode", 1),
+            Key::Character("M") => ("mode", -1),
+            Key::Character("l") => ("lod", 1),
+            Key::Character("L") => ("lod", -1),
+            Key::Character("f") => ("filter", 1),
+            Key::Character("F") => ("filter", -1),
+            Key::Character("o") => ("compare", 1),
+            Key::Character("O") => ("compare", -1),
+            Key::Character("g") => ("mips", 1),
+            Key::Character("G") => ("mips", -1),
+            Key::Character("t") => ("tint", 1),
+            Key::Character("T") => ("tint", -1),
+            _ => return,
+        };
+        self.params.step(name, direction).unwrap();
+
+        println!("mips config: {}", self.params);
+    }
+
+    fn stream_input(&mut self, input: &InputImage) -> Result<bool, GlError> {
+        if input.size() != self.image_size {
+            return Ok(false);
+        }
+        self.image = input.image.clone();
+        unsafe {
+            upload_driver_mips(self.driver_texture, &self.image)?;
+            // remade from the new frame when it's looked at
+            if let Some(texture) = self.lanczos_texture.take() {
+                delete_textures(&[texture]);
+            }
+        }
+        Ok(true)
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("mips draw");
+
+        let texture = self.texture()?;
+        let (left_name, left_min, left_mag) = FILTERS[self.params.variant("filter")];
+        let compare = self.params.variant("compare");
+        let (right_name, right_min, right_mag) = FILTERS[compare.saturating_sub(1)];
+        let mode = self.params.variant("mode");
+        let lod = self.params.f32("lod");
+        let is_mipmapped = |filter| filter != gl::NEAREST && filter != gl::LINEAR;
+
+        let size = self.image_size.as_vec2();
+        let centers = self.centers();
+        let theme = theme::current();
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            for (sampler, min, mag) in [
+                (self.samplers[0], left_min, left_mag),
+                (self.samplers[1], right_min, right_mag),
+            ] {
+                gl::SamplerParameteri(sampler, gl::TEXTURE_MIN_FILTER, min as GLint);
+                gl::SamplerParameteri(sampler, gl::TEXTURE_MAG_FILTER, mag as GLint);
+            }
+
+            gl::UseProgram(self.shader);
+            gl::Uniform1i(self.u_compare, (compare > 0) as GLint);
+            gl::Uniform1i(self.u_left_mips, is_mipmapped(left_min) as GLint);
+            gl::Uniform1i(self.u_right_mips, is_mipmapped(right_min) as GLint);
+            gl::Uniform1i(self.u_mode, mode as GLint);
+            gl::Uniform1f(self.u_lod, lod);
+            gl::Uniform1f(self.u_max_level, (self.levels - 1) as f32);
+            gl::Uniform2f(self.u_size, size.x, size.y);
+            gl::Uniform1f(self.u_tint, self.params.f32("tint"));
+            for (unit, sampler) in self.samplers.into_iter().enumerate() {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as GLenum);
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::BindSampler(unit as GLuint, sampler);
+            }
+            gl::BindVertexArray(self.vao);
+            for (&center, scale) in centers.iter().zip(self.scales()) {
+                let model = Mat4::from_translation(center.extend(0.0))
+                    * Mat4::from_scale(vec3(scale, scale, 1.0));
+                let mvp = self.matrix * model;
+                gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, mvp.as_ref().as_ptr());
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            }
+            gl::BindVertexArray(0);
+            // samplers take over the parameters of whatever texture is bound
+            gl::BindSampler(1, 0);
+            gl::BindSampler(0, 0);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            let Some(text) = &self.text else {
+                return Ok(());
+            };
+            let mut text = text.borrow_mut();
+            // the scale and level under each copy, as long as they fit
+            let mut free_from = f32::NEG_INFINITY;
+            for (&center, scale) in centers.iter().zip(self.scales()) {
+                let pixels_per_texel = camera.scale.x * scale;
+                let level = match mode {
+                    1 => lod,
+                    2 => lod - pixels_per_texel.log2(),
+                    _ => -pixels_per_texel.log2(),
+                };
+                let label = format!("×{scale:.2}\nlod {level:.2}");
+                let extent = text.measure(&label, TEXT_SIZE, None);
+                let bottom = center + vec2(0.0, size.y * scale / 2.0);
+                let position = self.to_screen(bottom) + vec2(-extent.x / 2.0, PADDING);
+                if position.x < free_from {
+                    break;
+                }
+                free_from = position.x + extent.x + PADDING;
+                text.queue(&label, position, TEXT_SIZE, None, theme.hud_text);
+            }
+
+            let generator = GENERATORS[self.params.variant("mips")];
+            let filters = match compare {
+                0 => left_name.to_string(),
+                _ => format!("{left_name} | {right_name}"),
+            };
+            let header = format!(
+                "{filters}\n{} mode, {generator} mips, {} levels",
+                MODES[mode], self.levels
+            );
+            let extent = text.measure(&header, TEXT_SIZE, None);
+            let min = Vec2::splat(MARGIN);
+            let max = min + extent + PADDING * 2.0;
+            text.queue_rect(min, max, theme.hud_background);
+            let position = min + PADDING;
+            text.queue(&header, position, TEXT_SIZE, None, theme.hud_text);
+            text.draw(0, self.viewport);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+impl Drop for MipsScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteSamplers(2, self.samplers.as_ptr());
+            delete_buffers(&[self.vbo]);
+            delete_textures(&[self.driver_texture]);
+            if let Some(texture) = self.lanczos_texture {
+                delete_textures(&[texture]);
+            }
+        }
+    }
+}
+
+/// Number of levels of a full mip chain down to 1×1.
+fn mip_levels(size: UVec2) -> u32 {
+    32 - size.max_element().max(1).leading_zeros()
+}
+
+/// Bytes of a full RGBA8 mip chain.
+fn mip_chain_bytes(size: UVec2) -> usize {
+    (0..mip_levels(size))
+        .map(|level| (size >> level).max(UVec2::ONE))
+        .map(|size| size.x as usize * size.y as usize * 4)
+        .sum()
+}
+
+unsafe fn upload_driver_mips(texture: GLuint, image: &RgbaImage) -> Result<(), GlError> {
+    let (width, height) = image.dimensions();
+    upload_texture(texture, width, height, image.as_ptr(), gl::CLAMP_TO_EDGE)?;
+    gl::GenerateMipmap(gl::TEXTURE_2D);
+    let bytes = mip_chain_bytes(uvec2(width, height));
+    gpu_memory::record(Object::Texture(texture), Category::Textures, bytes);
+    Ok(())
+}
+
+/// Uploads every level of the mip chain of `image`, each one resized from the
+/// image itself rather than from the previous level.
+unsafe fn upload_lanczos_mips(texture: GLuint, image: &RgbaImage) -> Result<(), GlError> {
+    let (width, height) = image.dimensions();
+    upload_texture(texture, width, height, image.as_ptr(), gl::CLAMP_TO_EDGE)?;
+    let size = uvec2(width, height);
+    for level in 1..mip_levels(size) {
+        let size = (size >> level).max(UVec2::ONE);
+        let resized = image::imageops::resize(image, size.x, size.y, FilterType::Lanczos3);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            level as GLint,
+            gl::RGBA8 as GLint,
+            size.x as GLsizei,
+            size.y as GLsizei,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            resized.as_ptr() as *const _,
+        );
+    }
+    let bytes = mip_chain_bytes(size);
+    gpu_memory::record(Object::Texture(texture), Category::Textures, bytes);
+    Ok(())
+}
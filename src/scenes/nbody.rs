@@ -0,0 +1,497 @@
+use std::f32::consts::TAU;
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
+use glam::{uvec2, vec2, vec3, Mat4, UVec2, Vec2, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::barnes_hut;
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_float_framebuffer, create_shader_program, delete_buffers,
+    delete_framebuffer, pop_debug_group, push_debug_group, Framebuffer,
+};
+use crate::downsample::DownsampleChain;
+use crate::error::GlError;
+use crate::output;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+
+use super::{Scene, SRC_FRAG_NBODY, SRC_FRAG_NBODY_TONEMAP, SRC_VERT_NBODY, SRC_VERT_SCREEN};
+
+const N_BODIES: usize = 50_000;
+/// Radius of a galaxy, in world units.
+const GALAXY_RADIUS: f32 = 400.0;
+/// Mass of the black hole at the center of a galaxy, and of all its stars.
+const CORE_MASS: f32 = 4e6;
+const DISC_MASS: f32 = 2e6;
+/// Added to every distance, in world units.
+const SOFTENING: f32 = 10.0;
+/// Levels of the bloom chain, the first being half the window.
+const BLOOM_LEVELS: usize = 6;
+
+/// Variants of the `setup` parameter, in order.
+const SETUPS: &[&str] = &["galaxy", "collision"];
+/// Colors of stars from hot to cool, and how common they are.
+const STAR_COLORS: &[(Vec3, f32)] = &[
+    (vec3(0.6, 0.7, 1.0), 0.15),
+    (vec3(1.0, 1.0, 1.0), 0.3),
+    (vec3(1.0, 0.9, 0.7), 0.35),
+    (vec3(1.0, 0.65, 0.4), 0.2),
+];
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// Galaxies of tens of thousands of stars pulling on each other, with
+/// gravity approximated by Barnes-Hut on the CPU and integrated with
+/// leapfrog steps.
+///
+/// Stars are drawn as additive point sprites into an HDR framebuffer, which
+/// blooms through the same downsample chain as the sun scene before being
+/// tonemapped. The view can follow the center of mass, so that the galaxies
+/// stay in sight however they drift.
+pub struct NBodyScene {
+    matrix: Mat4,
+    viewport: UVec2,
+
+    positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    accelerations: Vec<Vec2>,
+    masses: Vec<f32>,
+    seed: u64,
+    /// Setup the bodies were placed for.
+    setup: usize,
+
+    points_shader: GLuint,
+    u_mvp: GLint,
+    u_point_size: GLint,
+    u_brightness: GLint,
+    tonemap_shader: GLuint,
+    u_bloom_intensity: GLint,
+    u_exposure: GLint,
+    u_hdr_output: GLint,
+    points_vao: GLuint,
+    positions_vbo: GLuint,
+    colors_vbo: GLuint,
+    quad_vao: GLuint,
+    quad_vbo: GLuint,
+
+    /// Created at the size of the window.
+    hdr: Option<Framebuffer>,
+    chain: DownsampleChain,
+
+    params: Params,
+}
+
+impl NBodyScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        unsafe {
+            let points_shader = create_shader_program(SRC_VERT_NBODY, SRC_FRAG_NBODY)?;
+            let tonemap_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_NBODY_TONEMAP)?;
+            let uniform = |shader: GLuint, name: &std::ffi::CStr| {
+                gl::GetUniformLocation(shader, name.as_ptr())
+            };
+            gl::UseProgram(tonemap_shader);
+            gl::Uniform1i(uniform(tonemap_shader, c"u_hdr"), 0);
+            gl::Uniform1i(uniform(tonemap_shader, c"u_bloom"), 1);
+            gl::Uniform1f(uniform(tonemap_shader, c"u_peak"), output::PEAK);
+
+            let mut vaos = [0; 2];
+            gl::GenVertexArrays(2, vaos.as_mut_ptr());
+            let [points_vao, quad_vao] = vaos;
+            let mut buffers = [0; 3];
+            gl::GenBuffers(3, buffers.as_mut_ptr());
+            let [positions_vbo, colors_vbo, quad_vbo] = buffers;
+
+            gl::BindVertexArray(quad_vao);
+            buffer_data(gl::ARRAY_BUFFER, quad_vbo, &vertices, gl::STATIC_DRAW);
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(tonemap_shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+
+            // filled in by `reset`
+            gl::BindVertexArray(points_vao);
+            buffer_data::<Vec2>(gl::ARRAY_BUFFER, positions_vbo, &[], gl::STREAM_DRAW);
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 0, 0 as _);
+            gl::EnableVertexAttribArray(0);
+            buffer_data::<Vec3>(gl::ARRAY_BUFFER, colors_vbo, &[], gl::STATIC_DRAW);
+            gl::VertexAttribPointer(1, 3, gl::FLOAT, gl::FALSE, 0, 0 as _);
+            gl::EnableVertexAttribArray(1);
+            gl::BindVertexArray(0);
+
+            let chain =
+                DownsampleChain::new("n-body bloom", UVec2::ZERO, BLOOM_LEVELS, gl::RGBA16F)?;
+
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: UVec2::ZERO,
+
+                positions: Vec::new(),
+                velocities: Vec::new(),
+                accelerations: Vec::new(),
+                masses: Vec::new(),
+                seed,
+                setup: 0,
+
+                u_mvp: uniform(points_shader, c"u_mvp"),
+                u_point_size: uniform(points_shader, c"u_point_size"),
+                u_brightness: uniform(points_shader, c"u_brightness"),
+                points_shader,
+                u_bloom_intensity: uniform(tonemap_shader, c"u_bloom_intensity"),
+                u_exposure: uniform(tonemap_shader, c"u_exposure"),
+                u_hdr_output: uniform(tonemap_shader, c"u_hdr_output"),
+                tonemap_shader,
+                points_vao,
+                positions_vbo,
+                colors_vbo,
+                quad_vao,
+                quad_vbo,
+
+                hdr: None,
+                chain,
+
+                params: Params::new()
+                    .with_enum("setup", 0, SETUPS)
+                    .with_f32("time scale", 1.0, 0.0, 8.0, 0.25)
+                    .with_bool("running", true)
+                    .with_bool("follow", true)
+                    .with_f32("theta", 0.6, 0.2, 0.7, 0.1)
+                    .with_f32("size", 4.0, 1.0, 16.0, 1.0)
+                    .with_f32("brightness", 0.1, 0.01, 1.0, 0.01)
+                    .with_f32("exposure", 0.0, -4.0, 4.0, 0.5)
+                    .with_f32("bloom", 0.6, 0.0, 2.0, 0.1),
+            };
+            scene.reset();
+            Ok(scene)
+        }
+    }
+
+    /// Places the bodies for the current setup, and uploads their colors.
+    unsafe fn reset(&mut self) {
+        self.setup = self.params.variant("setup");
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        self.positions.clear();
+        self.velocities.clear();
+        self.masses.clear();
+        let mut colors = Vec::with_capacity(N_BODIES);
+        // centers, velocities, radii and spins
+        let galaxies: &[(Vec2, Vec2, f32, f32)] = match SETUPS[self.setup] {
+            "collision" => &[
+                (vec2(-320.0, -100.0), vec2(45.0, 20.0), 0.5, 1.0),
+                (vec2(320.0, 100.0), vec2(-45.0, -20.0), 0.5, -1.0),
+            ],
+            _ => &[(Vec2::ZERO, Vec2::ZERO, 1.0, 1.0)],
+        };
+        let n_stars = N_BODIES / galaxies.len() - 1;
+        for &(center, velocity, radius, spin) in galaxies {
+            let start = self.positions.len();
+            let (positions, velocities) = galaxy(&mut rng, n_stars, radius * GALAXY_RADIUS, spin);
+            self.positions.extend(positions.iter().map(|&p| center + p));
+            self.velocities
+                .extend(velocities.iter().map(|&v| velocity + v));
+            self.masses.push(CORE_MASS);
+            self.masses
+                .extend((0..n_stars).map(|_| DISC_MASS / n_stars as f32));
+            // the black hole doesn't shine
+            colors.push(Vec3::ZERO);
+            colors.extend((start + 1..self.positions.len()).map(|_| star_color(&mut rng)));
+        }
+
+        self.accelerations = vec![Vec2::ZERO; self.positions.len()];
+        self.compute_accelerations();
+
+        buffer_data(gl::ARRAY_BUFFER, self.colors_vbo, &colors, gl::STATIC_DRAW);
+        buffer_data(
+            gl::ARRAY_BUFFER,
+            self.positions_vbo,
+            &self.positions,
+            gl::STREAM_DRAW,
+        );
+    }
+
+    fn compute_accelerations(&mut self) {
+        profiling::cpu_zone!("barnes-hut");
+
+        let theta = self.params.f32("theta");
+        barnes_hut::accelerations(
+            &self.positions,
+            &self.masses,
+            theta,
+            SOFTENING,
+            &mut self.accelerations,
+        );
+    }
+
+    /// Where the mass of every body averages out.
+    fn center_of_mass(&self) -> Vec2 {
+        let total = self.masses.iter().sum::<f32>();
+        (self.positions.iter().zip(&self.masses))
+            .map(|(&position, &mass)| position * mass)
+            .sum::<Vec2>()
+            / total
+    }
+}
+
+impl Scene for NBodyScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("n") => self.params.step("setup", 1).unwrap(),
+            Key::Character("N") => self.params.step("setup", -1).unwrap(),
+            Key::Character("t") => self.params.step("time scale", 1).unwrap(),
+            Key::Character("T") => self.params.step("time scale", -1).unwrap(),
+            Key::Character("p" | "P") => self.params.toggle("running").unwrap(),
+            Key::Character("f" | "F") => self.params.toggle("follow").unwrap(),
+            Key::Character("o") => self.params.step("theta", 1).unwrap(),
+            Key::Character("O") => self.params.step("theta", -1).unwrap(),
+            Key::Character("s") => self.params.step("size", 1).unwrap(),
+            Key::Character("S") => self.params.step("size", -1).unwrap(),
+            Key::Character("l") => self.params.step("brightness", 1).unwrap(),
+            Key::Character("L") => self.params.step("brightness", -1).unwrap(),
+            Key::Character("e") => self.params.step("exposure", 1).unwrap(),
+            Key::Character("E") => self.params.step("exposure", -1).unwrap(),
+            Key::Character("b") => self.params.step("bloom", 1).unwrap(),
+            Key::Character("B") => self.params.step("bloom", -1).unwrap(),
+            Key::Character("r" | "R") => unsafe { self.reset() },
+            _ => return,
+        }
+
+        println!("n-body config: {}", self.params);
+    }
+
+    /// Moves the bodies along by one leapfrog step: half a kick, a drift, and
+    /// another half kick with the new accelerations.
+    fn update(&mut self, dt: f32) {
+        let dt = dt * self.params.f32("time scale");
+        if !self.params.bool("running") || dt == 0.0 {
+            return;
+        }
+
+        profiling::cpu_zone!("n-body update");
+
+        for (velocity, acceleration) in self.velocities.iter_mut().zip(&self.accelerations) {
+            *velocity += *acceleration * dt * 0.5;
+        }
+        for (position, velocity) in self.positions.iter_mut().zip(&self.velocities) {
+            *position += *velocity * dt;
+        }
+        self.compute_accelerations();
+        for (velocity, acceleration) in self.velocities.iter_mut().zip(&self.accelerations) {
+            *velocity += *acceleration * dt * 0.5;
+        }
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("n-body draw");
+
+        if self.setup != self.params.variant("setup") {
+            unsafe { self.reset() };
+        }
+        let Some(hdr) = &self.hdr else {
+            return Ok(());
+        };
+        let mvp = match self.params.bool("follow") {
+            true => self.matrix * Mat4::from_translation(-self.center_of_mass().extend(0.0)),
+            false => self.matrix,
+        };
+        let size = self.viewport.as_ivec2();
+
+        unsafe {
+            push_debug_group(c"Stars");
+            gl::BindFramebuffer(gl::FRAMEBUFFER, hdr.fbo);
+            gl::Viewport(0, 0, size.x, size.y);
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.positions_vbo);
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                mem::size_of_val(self.positions.as_slice()) as GLsizeiptr,
+                self.positions.as_ptr().cast(),
+            );
+
+            gl::Enable(gl::BLEND);
+            gl::BlendEquation(gl::FUNC_ADD);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+            gl::Enable(gl::PROGRAM_POINT_SIZE);
+            gl::UseProgram(self.points_shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, mvp.as_ref().as_ptr());
+            gl::Uniform1f(self.u_point_size, self.params.f32("size"));
+            gl::Uniform1f(self.u_brightness, self.params.f32("brightness"));
+            gl::BindVertexArray(self.points_vao);
+            gl::DrawArrays(gl::POINTS, 0, self.positions.len() as GLsizei);
+            gl::Disable(gl::PROGRAM_POINT_SIZE);
+            gl::Disable(gl::BLEND);
+            pop_debug_group();
+
+            push_debug_group(c"Bloom");
+            let bloom = self.chain.level(0);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, hdr.fbo);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, bloom.fbo);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                size.x,
+                size.y,
+                0,
+                0,
+                bloom.size.x as GLint,
+                bloom.size.y as GLint,
+                gl::COLOR_BUFFER_BIT,
+                gl::LINEAR,
+            );
+            self.chain.downsample(BLOOM_LEVELS - 1, 1.0);
+            self.chain.upsample(BLOOM_LEVELS - 1, 0.5);
+            pop_debug_group();
+
+            push_debug_group(c"Tonemapping");
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(0, 0, size.x, size.y);
+            gl::UseProgram(self.tonemap_shader);
+            gl::Uniform1f(self.u_bloom_intensity, self.params.f32("bloom"));
+            gl::Uniform1f(self.u_exposure, self.params.f32("exposure"));
+            gl::Uniform1i(self.u_hdr_output, output::hdr_uniform());
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, self.chain.level(0).texture);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, hdr.texture);
+            gl::BindVertexArray(self.quad_vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::BindVertexArray(0);
+            pop_debug_group();
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.matrix = camera.matrix(vec2(width as f32, height as f32));
+
+        let viewport = uvec2(width as u32, height as u32);
+        if viewport == self.viewport {
+            return Ok(());
+        }
+        self.viewport = viewport;
+
+        unsafe {
+            if let Some(hdr) = self.hdr.take() {
+                delete_framebuffer(&hdr);
+            }
+            self.chain.resize(viewport / 2)?;
+            if viewport.min_element() == 0 || self.chain.is_empty() {
+                return Ok(());
+            }
+
+            self.hdr = Some(create_float_framebuffer(
+                "n-body hdr",
+                viewport,
+                gl::RGBA16F,
+            )?);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            Ok(())
+        }
+    }
+}
+
+impl Drop for NBodyScene {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(hdr) = self.hdr.take() {
+                delete_framebuffer(&hdr);
+            }
+            gl::DeleteProgram(self.points_shader);
+            gl::DeleteProgram(self.tonemap_shader);
+            gl::DeleteVertexArrays(2, [self.points_vao, self.quad_vao].as_ptr());
+            delete_buffers(&[self.positions_vbo, self.colors_vbo, self.quad_vbo]);
+        }
+    }
+}
+
+/// A black hole followed by `n_stars` stars in a disc of about `radius`
+/// around it, in circular orbits turning counterclockwise for a `spin` of 1,
+/// clockwise for -1.
+fn galaxy(rng: &mut StdRng, n_stars: usize, radius: f32, spin: f32) -> (Vec<Vec2>, Vec<Vec2>) {
+    // denser toward the center, roughly exponentially
+    let mut radii = (0..n_stars)
+        .map(|_| {
+            let t = rng.gen_range(0.0..0.95f32);
+            radius * (0.05 - (1.0 - t).ln() / 3.0)
+        })
+        .collect::<Vec<_>>();
+    radii.sort_by(f32::total_cmp);
+
+    let mut positions = vec![Vec2::ZERO];
+    let mut velocities = vec![Vec2::ZERO];
+    let star_mass = DISC_MASS / n_stars as f32;
+    for (i, distance) in radii.into_iter().enumerate() {
+        let direction = Vec2::from_angle(rng.gen_range(0.0..TAU));
+        // fast enough to orbit what's inside, with a little scatter
+        let enclosed = CORE_MASS + star_mass * i as f32;
+        let softened = (distance * distance + SOFTENING * SOFTENING).sqrt();
+        let speed = (enclosed * distance * distance / softened.powi(3)).sqrt();
+        let scatter = vec2(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)) * speed * 0.05;
+
+        positions.push(direction * distance);
+        velocities.push(direction.perp() * speed * spin + scatter);
+    }
+    (positions, velocities)
+}
+
+fn star_color(rng: &mut StdRng) -> Vec3 {
+    let mut t = rng.gen::<f32>();
+    for &(color, share) in STAR_COLORS {
+        if t < share {
+            return color;
+        }
+        t -= share;
+    }
+    STAR_COLORS[0].0
+}
@@ -0,0 +1,427 @@
+use std::{mem, ptr};
+
+use gl::types::GLsync;
+use glam::{vec2, Mat4, Vec2};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    gl_version, pop_debug_group, push_debug_group, supports_compute, Attrib, Buffer, Program,
+    Uniform, VertexArray,
+};
+use crate::error::GlError;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::technique_timings::TechniqueTimings;
+use super::{Scene, SRC_COMP_OVERLAP_STEP, SRC_FRAG_OVERLAP_QUAD, SRC_VERT_OVERLAP_QUAD};
+
+/// Both orders of stepping and drawing, which are also the names of their
+/// timings.
+const TECHNIQUES: &[&str] = &["serialized", "overlapped"];
+
+/// Quads along each side of the field.
+const COLUMNS: usize = 384;
+const COUNT: usize = COLUMNS * COLUMNS;
+/// Distance between the quads at rest, and their width, in world units.
+const SPACING: f32 = 6.0;
+const SIZE: f32 = 4.0;
+/// Longest step, so that a stalled frame doesn't fling the quads away.
+const MAX_DT: f32 = 1.0 / 30.0;
+/// How long the CPU waits on a fence before giving up, in nanoseconds.
+const FENCE_TIMEOUT: u64 = 1_000_000_000;
+
+/// Must match `local_size_x` in overlap-step.comp.
+const WORKGROUP_SIZE: usize = 256;
+
+const TEXT_SIZE: f32 = 14.0;
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+
+const CORNER_LAYOUT: &[Attrib] = &[Attrib {
+    name: c"corner",
+    components: 2,
+    offset: 0,
+}];
+const QUAD_LAYOUT: &[Attrib] = &[
+    Attrib {
+        name: c"position",
+        components: 2,
+        offset: mem::offset_of!(Quad, position),
+    },
+    Attrib {
+        name: c"velocity",
+        components: 2,
+        offset: mem::offset_of!(Quad, velocity),
+    },
+];
+
+/// The `Quad` struct of overlap-step.comp.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Quad {
+    position: Vec2,
+    velocity: Vec2,
+}
+
+/// The `Step` uniform block of overlap-step.comp, laid out as std140.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct Step {
+    mouse: Vec2,
+    time: f32,
+    dt: f32,
+    count: u32,
+    columns: u32,
+    spacing: f32,
+    iterations: i32,
+}
+
+/// A field of quads stepped by a compute shader, for comparing a frame that
+/// steps the quads and then draws them against one that draws the last step
+/// while the next one runs.
+///
+/// Steps go back and forth between two buffers of quads. Serialized, a frame
+/// steps into the other buffer and draws from it, so the draw waits for the
+/// step. Overlapped, a frame draws from the buffer the last frame stepped
+/// into and then steps into the other one, which doesn't touch what's being
+/// drawn, so the GPU is free to run both at once, at the cost of showing the
+/// quads a frame late. The uniforms of each step are written through an
+/// unsynchronized mapping of one of two buffers, each guarded by a fence the
+/// CPU waits on before writing it again, and how often it had to wait is
+/// printed along with the timings.
+pub struct OverlapScene {
+    matrix: Mat4,
+    viewport: Vec2,
+    time: f32,
+    dt: f32,
+    /// Where the mouse is in world units.
+    mouse_world: Vec2,
+
+    /// `None` when compute shaders aren't supported.
+    sim: Option<Simulation>,
+    /// Quads of the last two steps. The current one is the latest, the other
+    /// one is written by the next step.
+    quads: [Buffer<Quad>; 2],
+    current: usize,
+
+    shader: Program,
+    u_mvp: Uniform,
+    u_size: Uniform,
+    /// One per buffer of quads, as the quads are instanced attributes.
+    vaos: [VertexArray; 2],
+    _corners_vbo: Buffer<Vec2>,
+
+    timings: TechniqueTimings,
+    /// Steps that found the CPU waiting on a fence since the last summary.
+    waits: u32,
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+struct Simulation {
+    program: Program,
+    /// Uniforms of every other step, which the CPU writes to while the GPU
+    /// may still be reading the other one.
+    steps: [Buffer<Step>; 2],
+    /// Signaled once the GPU is done with the step buffer of the same index,
+    /// null before its first step.
+    fences: [GLsync; 2],
+    /// Step buffer the next step writes to.
+    slot: usize,
+}
+
+impl Simulation {
+    /// Waits until the GPU is done with the step buffer of `slot`, and
+    /// returns whether it wasn't already.
+    unsafe fn wait(&mut self, slot: usize) -> bool {
+        let fence = mem::replace(&mut self.fences[slot], ptr::null());
+        if fence.is_null() {
+            return false;
+        }
+
+        let stalled = gl::ClientWaitSync(fence, 0, 0) == gl::TIMEOUT_EXPIRED;
+        if stalled {
+            let waited = gl::ClientWaitSync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, FENCE_TIMEOUT);
+            // the buffer is about to be written without synchronization, so
+            // it has to be free even if the fence gave up
+            if !matches!(waited, gl::ALREADY_SIGNALED | gl::CONDITION_SATISFIED) {
+                eprintln!("overlap: gave up on a fence after {FENCE_TIMEOUT} ns, finishing");
+                gl::Finish();
+            }
+        }
+        gl::DeleteSync(fence);
+        stalled
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        for fence in self.fences.into_iter().filter(|fence| !fence.is_null()) {
+            unsafe { gl::DeleteSync(fence) };
+        }
+    }
+}
+
+impl OverlapScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(text: Option<SharedText>) -> Result<Self, GlError> {
+        let corners = [
+            vec2(-0.5, -0.5),
+            vec2(0.5, -0.5),
+            vec2(-0.5, 0.5),
+            vec2(0.5, 0.5),
+        ];
+
+        unsafe {
+            let sim = if supports_compute() {
+                let program = Program::compute(SRC_COMP_OVERLAP_STEP)?;
+                let steps = [(); 2]
+                    .map(|_| Buffer::new(gl::UNIFORM_BUFFER, &[Step::default()], gl::DYNAMIC_DRAW));
+                gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+                Some(Simulation {
+                    program,
+                    steps,
+                    fences: [ptr::null(); 2],
+                    slot: 0,
+                })
+            } else {
+                let (major, minor) = gl_version();
+                println!(
+                    "overlap: no compute shaders on OpenGL {major}.{minor}, the quads stay put"
+                );
+                None
+            };
+
+            let shader = Program::new(SRC_VERT_OVERLAP_QUAD, SRC_FRAG_OVERLAP_QUAD)?;
+            let u_mvp = shader.uniform(c"u_mvp");
+            let u_size = shader.uniform(c"u_size");
+
+            let corners_vbo = Buffer::new(gl::ARRAY_BUFFER, &corners, gl::STATIC_DRAW);
+            let quads =
+                [(); 2].map(|_| Buffer::new(gl::ARRAY_BUFFER, &at_rest(), gl::DYNAMIC_COPY));
+            let vaos = [&quads[0], &quads[1]].map(|buffer| {
+                let mut vao = VertexArray::new();
+                vao.set_layout(&shader, &corners_vbo, CORNER_LAYOUT);
+                vao.set_instance_layout(&shader, buffer, QUAD_LAYOUT);
+                vao
+            });
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+                time: 0.0,
+                dt: 0.0,
+                mouse_world: Vec2::ZERO,
+
+                sim,
+                quads,
+                current: 0,
+
+                shader,
+                u_mvp,
+                u_size,
+                vaos,
+                _corners_vbo: corners_vbo,
+
+                timings: TechniqueTimings::create(TECHNIQUES),
+                waits: 0,
+
+                text,
+
+                params: Params::new()
+                    .with_bool("overlap", true)
+                    .with_i32("iterations", 32, 1, 256)
+                    .with_f32("speed", 1.0, 0.0, 4.0, 0.25),
+            })
+        }
+    }
+
+    /// Puts every quad back at rest in both buffers.
+    fn reset(&mut self) {
+        let quads = at_rest();
+        for buffer in &self.quads {
+            buffer.update(&quads);
+        }
+        unsafe { gl::BindBuffer(gl::ARRAY_BUFFER, 0) };
+    }
+
+    /// Steps the quads from the current buffer into the other one, which
+    /// becomes the current one.
+    unsafe fn step(&mut self) {
+        let Some(sim) = &mut self.sim else {
+            return;
+        };
+
+        let slot = sim.slot;
+        if sim.wait(slot) {
+            self.waits += 1;
+        }
+
+        let step = Step {
+            mouse: self.mouse_world,
+            time: self.time,
+            dt: self.dt,
+            count: COUNT as u32,
+            columns: COLUMNS as u32,
+            spacing: SPACING,
+            iterations: self.params.i32("iterations"),
+        };
+        sim.steps[slot].bind();
+        // the fence says the GPU is done with it, no need for the driver to
+        // make sure of it too
+        let mapped = gl::MapBufferRange(
+            gl::UNIFORM_BUFFER,
+            0,
+            mem::size_of::<Step>() as isize,
+            gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_BUFFER_BIT | gl::MAP_UNSYNCHRONIZED_BIT,
+        );
+        if !mapped.is_null() {
+            (mapped as *mut Step).write(step);
+            gl::UnmapBuffer(gl::UNIFORM_BUFFER);
+        }
+        gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+
+        push_debug_group(c"Overlap step");
+        let [source, destination] = [self.current, 1 - self.current];
+        sim.program.bind();
+        gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, sim.steps[slot].id());
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, self.quads[source].id());
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 1, self.quads[destination].id());
+        gl::DispatchCompute(COUNT.div_ceil(WORKGROUP_SIZE) as u32, 1, 1);
+        sim.fences[slot] = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+        // only the draw that comes after needs the step done, whichever
+        // frame it's in
+        gl::MemoryBarrier(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+        gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, 0);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, 0);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 1, 0);
+        pop_debug_group();
+
+        sim.slot = 1 - slot;
+        self.current = destination;
+    }
+
+    /// Draws the quads of the current buffer.
+    unsafe fn draw_quads(&self) {
+        push_debug_group(c"Overlap quads");
+        self.shader.set(self.u_mvp, self.matrix);
+        self.shader.set(self.u_size, SIZE);
+        self.vaos[self.current].draw_arrays_instanced(gl::TRIANGLE_STRIP, COUNT);
+        pop_debug_group();
+    }
+
+    fn label(&self) -> String {
+        if self.sim.is_none() {
+            return "No compute shaders, the quads stay put".to_string();
+        }
+        if self.params.bool("overlap") {
+            "Overlapped: drawing the last step while the next one runs".to_string()
+        } else {
+            "Serialized: stepping, then drawing that step".to_string()
+        }
+    }
+}
+
+impl Scene for OverlapScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("o" | "O") => self.params.toggle("overlap").unwrap(),
+            Key::Character("i") => self.params.step("iterations", 8).unwrap(),
+            Key::Character("I") => self.params.step("iterations", -8).unwrap(),
+            Key::Character("v") => self.params.step("speed", 1).unwrap(),
+            Key::Character("V") => self.params.step("speed", -1).unwrap(),
+            Key::Character("r" | "R") => self.reset(),
+            _ => return,
+        }
+
+        println!("overlap config: {}", self.params);
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.dt = (dt * self.params.f32("speed")).min(MAX_DT);
+        self.time += self.dt;
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("overlap draw");
+
+        self.mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport);
+        let theme = theme::current();
+        let overlap = self.params.bool("overlap");
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            let [r, g, b, a] = theme.clear;
+            gl::ClearColor(r, g, b, a);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            self.timings.begin(overlap as usize);
+            if overlap {
+                self.draw_quads();
+                self.step();
+            } else {
+                self.step();
+                self.draw_quads();
+            }
+            self.timings.end();
+        }
+
+        if let Some(summary) = self.timings.summarize() {
+            let waits = mem::take(&mut self.waits);
+            println!("overlap: {summary}, waited on {waits} fences");
+        }
+
+        let label = self.label();
+        let Some(text) = &self.text else {
+            return Ok(());
+        };
+        let mut text = text.borrow_mut();
+        let extent = text.measure(&label, TEXT_SIZE, None);
+        let min = Vec2::splat(MARGIN);
+        let max = min + extent + PADDING * 2.0;
+        text.queue_rect(min, max, theme.hud_background);
+        text.queue(&label, min + PADDING, TEXT_SIZE, None, theme.hud_text);
+        unsafe { text.draw(0, self.viewport) };
+        Ok(())
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+/// Every quad at its spot on the grid, centered on the origin, standing
+/// still. The same as `home` in overlap-step.comp.
+fn at_rest() -> Vec<Quad> {
+    let middle = (COLUMNS - 1) as f32 * 0.5;
+    (0..COUNT)
+        .map(|i| Quad {
+            position: (vec2((i % COLUMNS) as f32, (i / COLUMNS) as f32) - middle) * SPACING,
+            velocity: Vec2::ZERO,
+        })
+        .collect()
+}
@@ -0,0 +1,279 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::{f32::consts::TAU, mem, time::Instant};
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{Mat4, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::dpi::PhysicalSize;
+use winit::keyboard::{Key, NamedKey, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_shader_program, create_transform_feedback_program, delete_buffers,
+};
+use crate::error::GlError;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::{Scene, SRC_FRAG_PARTICLES, SRC_VERT_PARTICLES, SRC_VERT_PARTICLES_UPDATE};
+
+const N_PARTICLES: usize = 65_536;
+
+/// Particles attracted by the mouse, simulated entirely on the GPU.
+///
+/// The simulation runs in a vertex shader whose outputs are captured with
+/// transform feedback, ping-ponging between two buffers, so it works on GL 3.x
+/// hardware without compute shaders. Rendering only needs a vertex array with
+/// positions and velocities at locations 0 and 1, so any other simulation
+/// writing the same layout can share [`ParticleRenderer`].
+pub struct ParticlesScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    update_shader: GLuint,
+    u_dt: GLint,
+    u_attractor: GLint,
+    u_strength: GLint,
+
+    renderer: ParticleRenderer,
+
+    /// Ping-pong buffers, `current` being the one holding the latest state.
+    vaos: [GLuint; 2],
+    vbos: [GLuint; 2],
+    current: usize,
+
+    params: Params,
+    last_instant: Instant,
+}
+
+impl ParticlesScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(size: PhysicalSize<u32>, seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let particles = (0..N_PARTICLES)
+            .map(|_| Particle::random(&mut rng))
+            .collect::<Vec<_>>();
+
+        unsafe {
+            let update_shader = create_transform_feedback_program(
+                SRC_VERT_PARTICLES_UPDATE,
+                &[c"v_position", c"v_velocity"],
+            )?;
+
+            let mut vaos = [0; 2];
+            gl::GenVertexArrays(2, vaos.as_mut_ptr());
+            let mut vbos = [0; 2];
+            gl::GenBuffers(2, vbos.as_mut_ptr());
+
+            let size_particle = mem::size_of::<Particle>() as GLsizei;
+            let size_f32 = mem::size_of::<f32>() as GLsizei;
+
+            for (&vao, &vbo) in vaos.iter().zip(&vbos) {
+                gl::BindVertexArray(vao);
+                buffer_data(
+                    gl::ARRAY_BUFFER,
+                    vbo,
+                    particles.as_slice(),
+                    gl::DYNAMIC_COPY,
+                );
+
+                gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, size_particle, 0 as _);
+                gl::VertexAttribPointer(
+                    1,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    size_particle,
+                    (2 * size_f32) as _,
+                );
+                gl::EnableVertexAttribArray(0);
+                gl::EnableVertexAttribArray(1);
+            }
+
+            gl::BindVertexArray(0);
+
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::new(size.width as f32, size.height as f32),
+
+                u_dt: gl::GetUniformLocation(update_shader, c"u_dt".as_ptr()),
+                u_attractor: gl::GetUniformLocation(update_shader, c"u_attractor".as_ptr()),
+                u_strength: gl::GetUniformLocation(update_shader, c"u_strength".as_ptr()),
+                update_shader,
+
+                renderer: ParticleRenderer::new()?,
+
+                vaos,
+                vbos,
+                current: 0,
+
+                params: Params::new()
+                    .with_f32("strength", 3.0, 0.0, 10.0, 0.5)
+                    .with_f32("point size", 2.0, 1.0, 8.0, 0.5),
+                last_instant: Instant::now(),
+            })
+        }
+    }
+
+    unsafe fn simulate(&mut self, dt: f32, attractor: Vec2) {
+        let next = 1 - self.current;
+
+        gl::UseProgram(self.update_shader);
+        gl::Uniform1f(self.u_dt, dt);
+        gl::Uniform2f(self.u_attractor, attractor.x, attractor.y);
+        gl::Uniform1f(self.u_strength, self.params.f32("strength"));
+
+        gl::Enable(gl::RASTERIZER_DISCARD);
+        gl::BindVertexArray(self.vaos[self.current]);
+        gl::BindBufferBase(gl::TRANSFORM_FEEDBACK_BUFFER, 0, self.vbos[next]);
+
+        gl::BeginTransformFeedback(gl::POINTS);
+        gl::DrawArrays(gl::POINTS, 0, N_PARTICLES as GLsizei);
+        gl::EndTransformFeedback();
+
+        gl::BindBufferBase(gl::TRANSFORM_FEEDBACK_BUFFER, 0, 0);
+        gl::Disable(gl::RASTERIZER_DISCARD);
+
+        self.current = next;
+    }
+}
+
+impl Scene for ParticlesScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let (name, steps) = match keycode {
+            Key::Named(NamedKey::ArrowRight) => ("strength", 1),
+            Key::Named(NamedKey::ArrowLeft) => ("strength", -1),
+            Key::Named(NamedKey::ArrowUp) => ("point size", 1),
+            Key::Named(NamedKey::ArrowDown) => ("point size", -1),
+            _ => return,
+        };
+
+        self.params.step(name, steps).unwrap();
+        println!("particles config: {}", self.params);
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("particles draw");
+
+        // big steps make particles slingshot away from the attractor
+        let dt = self.last_instant.elapsed().as_secs_f32().min(1.0 / 30.0);
+        self.last_instant = Instant::now();
+
+        let attractor = camera.pointer_to_pos(mouse_pos, self.viewport);
+
+        unsafe {
+            self.simulate(dt, attractor);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            let point_size = self.params.f32("point size") * camera.scale.x.sqrt();
+            (self.renderer).draw(
+                self.vaos[self.current],
+                N_PARTICLES,
+                &self.matrix,
+                point_size,
+            );
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+impl Drop for ParticlesScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.update_shader);
+            gl::DeleteVertexArrays(2, self.vaos.as_ptr());
+
+            delete_buffers(&self.vbos);
+        }
+    }
+}
+
+/// Draws particles as additive round points.
+pub struct ParticleRenderer {
+    shader: GLuint,
+    u_mvp: GLint,
+    u_point_size: GLint,
+}
+
+impl ParticleRenderer {
+    pub unsafe fn new() -> Result<Self, GlError> {
+        let shader = create_shader_program(SRC_VERT_PARTICLES, SRC_FRAG_PARTICLES)?;
+
+        Ok(Self {
+            shader,
+            u_mvp: gl::GetUniformLocation(shader, c"u_mvp".as_ptr()),
+            u_point_size: gl::GetUniformLocation(shader, c"u_point_size".as_ptr()),
+        })
+    }
+
+    /// Draws `count` particles from a vertex array with positions at location 0
+    /// and velocities at location 1.
+    pub unsafe fn draw(&self, vao: GLuint, count: usize, matrix: &Mat4, point_size: f32) {
+        gl::UseProgram(self.shader);
+        gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, matrix.as_ref().as_ptr());
+        gl::Uniform1f(self.u_point_size, point_size);
+
+        gl::Enable(gl::PROGRAM_POINT_SIZE);
+        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE);
+
+        gl::BindVertexArray(vao);
+        gl::DrawArrays(gl::POINTS, 0, count as GLsizei);
+
+        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        gl::Disable(gl::PROGRAM_POINT_SIZE);
+    }
+}
+
+impl Drop for ParticleRenderer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteProgram(self.shader) };
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+}
+
+impl Particle {
+    /// A particle on a disc, orbiting its center.
+    fn random(rng: &mut impl Rng) -> Self {
+        let angle = rng.gen_range(0.0..TAU);
+        let radius = 600.0 * rng.gen_range(0.0f32..1.0).sqrt();
+        let direction = Vec2::from_angle(angle);
+
+        Self {
+            position: direction * radius,
+            velocity: direction.perp() * rng.gen_range(40.0..120.0),
+        }
+    }
+}
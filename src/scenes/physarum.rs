@@ -0,0 +1,444 @@
+use std::f32::consts::TAU;
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, Mat4, UVec2, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_compute_program, create_float_framebuffer, create_shader_program,
+    delete_buffers, delete_framebuffer, gl_version, pop_debug_group, push_debug_group,
+    supports_compute, Framebuffer,
+};
+use crate::error::GlError;
+use crate::params::{Params, Value};
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::{Scene, SRC_COMP_PHYSARUM, SRC_FRAG_BLUR, SRC_FRAG_PHYSARUM_VIEW, SRC_VERT_SCREEN};
+
+const N_AGENTS: usize = 1 << 20;
+/// Cells of the trail map.
+const TRAIL: UVec2 = uvec2(1024, 640);
+/// Size of a cell in world units.
+const CELL: f32 = 1.25;
+
+/// Must match `local_size_x` in physarum.comp.
+const WORKGROUP_SIZE: usize = 256;
+
+/// Sensor angle, sensor distance, turn angle and speed giving each kind of
+/// network, in degrees and cells.
+const PRESETS: &[(&str, f32, f32, f32, f32)] = &[
+    ("network", 22.5, 9.0, 45.0, 1.0),
+    ("veins", 45.0, 20.0, 15.0, 1.5),
+    ("cells", 60.0, 4.0, 60.0, 1.0),
+    ("whorls", 10.0, 30.0, 80.0, 2.0),
+];
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// The `Agent` struct of physarum.comp.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Agent {
+    position: Vec2,
+    angle: f32,
+    _padding: f32,
+}
+
+/// Slime mold growing networks between the places it forages, after Jeff
+/// Jones' model of Physarum polycephalum.
+///
+/// A million agents each sense the trail map ahead and to both sides of
+/// them, turn toward the strongest trail, step forward and deposit on it, in
+/// a compute pass writing straight into the trail image. The trail then
+/// diffuses with the separable Gaussian blur of the blurring scene, and decays
+/// through blending with a constant factor. Without compute shaders there is
+/// nothing to simulate.
+pub struct PhysarumScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    /// `None` when compute shaders aren't supported.
+    agent_shader: Option<AgentShader>,
+    agents_ssbo: GLuint,
+    /// Written to by the agents, then blurred through the other one and back.
+    trail: [Framebuffer; 2],
+    frame: u32,
+    rng: StdRng,
+
+    blur_shader: GLuint,
+    u_direction: GLint,
+    u_kernel_size: GLint,
+    view_shader: GLuint,
+    u_inverse: GLint,
+    u_half_size: GLint,
+    u_exposure: GLint,
+    u_clear: GLint,
+    u_color: GLint,
+    u_highlight: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+
+    params: Params,
+}
+
+struct AgentShader {
+    program: GLuint,
+    u_count: GLint,
+    u_frame: GLint,
+    u_sensor_angle: GLint,
+    u_sensor_distance: GLint,
+    u_turn_angle: GLint,
+    u_speed: GLint,
+    u_deposit: GLint,
+}
+
+impl PhysarumScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        unsafe {
+            let agent_shader = if supports_compute() {
+                let program = create_compute_program(SRC_COMP_PHYSARUM)?;
+                let uniform =
+                    |name: &std::ffi::CStr| gl::GetUniformLocation(program, name.as_ptr());
+                Some(AgentShader {
+                    program,
+                    u_count: uniform(c"u_count"),
+                    u_frame: uniform(c"u_frame"),
+                    u_sensor_angle: uniform(c"u_sensor_angle"),
+                    u_sensor_distance: uniform(c"u_sensor_distance"),
+                    u_turn_angle: uniform(c"u_turn_angle"),
+                    u_speed: uniform(c"u_speed"),
+                    u_deposit: uniform(c"u_deposit"),
+                })
+            } else {
+                let (major, minor) = gl_version();
+                println!(
+                    "physarum: no compute shaders on OpenGL {major}.{minor}, nothing to simulate"
+                );
+                None
+            };
+
+            let blur_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_BLUR)?;
+            let view_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_PHYSARUM_VIEW)?;
+            let uniform = |shader: GLuint, name: &std::ffi::CStr| {
+                gl::GetUniformLocation(shader, name.as_ptr())
+            };
+            gl::UseProgram(blur_shader);
+            gl::Uniform1i(uniform(blur_shader, c"u_tex"), 0);
+            gl::UseProgram(view_shader);
+            gl::Uniform1i(uniform(view_shader, c"u_trail"), 0);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut buffers = [0; 2];
+            gl::GenBuffers(2, buffers.as_mut_ptr());
+            let [vbo, agents_ssbo] = buffers;
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            // both programs share the screen vertex shader, so the locations match
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(view_shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let trail = [
+                create_float_framebuffer("physarum trail", TRAIL, gl::RGBA16F)?,
+                create_float_framebuffer("physarum diffusion", TRAIL, gl::RGBA16F)?,
+            ];
+            for framebuffer in &trail {
+                // the agents wrap around, so the trail blurs across the edges too
+                gl::BindTexture(gl::TEXTURE_2D, framebuffer.texture);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            let (_, sensor_angle, sensor_distance, turn_angle, speed) = PRESETS[0];
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+
+                agent_shader,
+                agents_ssbo,
+                trail,
+                frame: 0,
+                rng: StdRng::seed_from_u64(seed),
+
+                u_direction: uniform(blur_shader, c"u_direction"),
+                u_kernel_size: uniform(blur_shader, c"u_kernel_size"),
+                blur_shader,
+                u_inverse: uniform(view_shader, c"u_inverse"),
+                u_half_size: uniform(view_shader, c"u_half_size"),
+                u_exposure: uniform(view_shader, c"u_exposure"),
+                u_clear: uniform(view_shader, c"u_clear"),
+                u_color: uniform(view_shader, c"u_color"),
+                u_highlight: uniform(view_shader, c"u_highlight"),
+                view_shader,
+                vao,
+                vbo,
+
+                params: Params::new()
+                    .with_f32("sensor angle", sensor_angle, 0.0, 90.0, 2.5)
+                    .with_f32("sensor distance", sensor_distance, 1.0, 40.0, 1.0)
+                    .with_f32("turn angle", turn_angle, 0.0, 90.0, 2.5)
+                    .with_f32("speed", speed, 0.25, 4.0, 0.25)
+                    .with_f32("deposit", 0.1, 0.01, 1.0, 0.01)
+                    .with_f32("decay", 0.9, 0.5, 0.99, 0.01)
+                    .with_i32("diffusion", 3, 0, 9)
+                    .with_f32("exposure", 0.25, 0.05, 4.0, 0.05)
+                    .with_bool("running", true),
+            };
+            scene.reseed();
+            Ok(scene)
+        }
+    }
+
+    /// Starts over from agents in a disc facing its center, on an empty
+    /// trail.
+    unsafe fn reseed(&mut self) {
+        if self.agent_shader.is_none() {
+            return;
+        }
+
+        let center = TRAIL.as_vec2() * 0.5;
+        let radius = TRAIL.min_element() as f32 * 0.4;
+        let agents = (0..N_AGENTS)
+            .map(|_| {
+                let angle = self.rng.gen_range(0.0..TAU);
+                let distance = radius * self.rng.gen::<f32>().sqrt();
+                Agent {
+                    position: center + distance * Vec2::from_angle(angle),
+                    angle: angle + TAU / 2.0,
+                    _padding: 0.0,
+                }
+            })
+            .collect::<Vec<_>>();
+        buffer_data(
+            gl::SHADER_STORAGE_BUFFER,
+            self.agents_ssbo,
+            &agents,
+            gl::DYNAMIC_COPY,
+        );
+
+        for framebuffer in &self.trail {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.fbo);
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    }
+}
+
+impl Scene for PhysarumScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character(digit @ ("1" | "2" | "3" | "4")) => {
+                let preset = PRESETS[digit.parse::<usize>().unwrap() - 1];
+                let (name, sensor_angle, sensor_distance, turn_angle, speed) = preset;
+                println!("physarum preset: {name}");
+                for (param, value) in [
+                    ("sensor angle", sensor_angle),
+                    ("sensor distance", sensor_distance),
+                    ("turn angle", turn_angle),
+                    ("speed", speed),
+                ] {
+                    self.params.set(param, Value::Number(value as f64)).unwrap();
+                }
+                unsafe { self.reseed() };
+            }
+            Key::Character("a") => self.params.step("sensor angle", 1).unwrap(),
+            Key::Character("A") => self.params.step("sensor angle", -1).unwrap(),
+            Key::Character("d") => self.params.step("sensor distance", 1).unwrap(),
+            Key::Character("D") => self.params.step("sensor distance", -1).unwrap(),
+            Key::Character("t") => self.params.step("turn angle", 1).unwrap(),
+            Key::Character("T") => self.params.step("turn angle", -1).unwrap(),
+            Key::Character("s") => self.params.step("speed", 1).unwrap(),
+            Key::Character("S") => self.params.step("speed", -1).unwrap(),
+            Key::Character("k") => self.params.step("decay", 1).unwrap(),
+            Key::Character("K") => self.params.step("decay", -1).unwrap(),
+            Key::Character("b") => self.params.step("diffusion", 1).unwrap(),
+            Key::Character("B") => self.params.step("diffusion", -1).unwrap(),
+            Key::Character("e") => self.params.step("exposure", 1).unwrap(),
+            Key::Character("E") => self.params.step("exposure", -1).unwrap(),
+            Key::Character("p" | "P") => self.params.toggle("running").unwrap(),
+            Key::Character("r" | "R") => unsafe { self.reseed() },
+            _ => return,
+        }
+
+        println!("physarum config: {}", self.params);
+    }
+
+    /// Moves every agent one step, then diffuses and decays the trail.
+    fn update(&mut self, _dt: f32) {
+        let Some(shader) = &self.agent_shader else {
+            return;
+        };
+        if !self.params.bool("running") {
+            return;
+        }
+
+        profiling::cpu_zone!("physarum update");
+
+        unsafe {
+            push_debug_group(c"Physarum agents");
+            gl::UseProgram(shader.program);
+            gl::Uniform1ui(shader.u_count, N_AGENTS as u32);
+            gl::Uniform1ui(shader.u_frame, self.frame);
+            gl::Uniform1f(
+                shader.u_sensor_angle,
+                self.params.f32("sensor angle").to_radians(),
+            );
+            gl::Uniform1f(shader.u_sensor_distance, self.params.f32("sensor distance"));
+            gl::Uniform1f(
+                shader.u_turn_angle,
+                self.params.f32("turn angle").to_radians(),
+            );
+            gl::Uniform1f(shader.u_speed, self.params.f32("speed"));
+            gl::Uniform1f(shader.u_deposit, self.params.f32("deposit"));
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, self.agents_ssbo);
+            gl::BindImageTexture(
+                0,
+                self.trail[0].texture,
+                0,
+                gl::FALSE,
+                0,
+                gl::READ_WRITE,
+                gl::RGBA16F,
+            );
+            gl::DispatchCompute(N_AGENTS.div_ceil(WORKGROUP_SIZE) as u32, 1, 1);
+            gl::MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT | gl::FRAMEBUFFER_BARRIER_BIT);
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, 0);
+            pop_debug_group();
+
+            push_debug_group(c"Physarum diffusion");
+            gl::Viewport(0, 0, TRAIL.x as GLsizei, TRAIL.y as GLsizei);
+            gl::UseProgram(self.blur_shader);
+            gl::Uniform1i(self.u_kernel_size, self.params.i32("diffusion"));
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindVertexArray(self.vao);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.trail[1].fbo);
+            gl::BindTexture(gl::TEXTURE_2D, self.trail[0].texture);
+            gl::Uniform2f(self.u_direction, 1.0, 0.0);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+            // decays on the way back, keeping the alpha the blur relies on
+            gl::Enable(gl::BLEND);
+            gl::BlendColor(0.0, 0.0, 0.0, self.params.f32("decay"));
+            gl::BlendFuncSeparate(gl::CONSTANT_ALPHA, gl::ZERO, gl::ONE, gl::ZERO);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.trail[0].fbo);
+            gl::BindTexture(gl::TEXTURE_2D, self.trail[1].texture);
+            gl::Uniform2f(self.u_direction, 0.0, 1.0);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::Disable(gl::BLEND);
+
+            gl::BindVertexArray(0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            let size = self.viewport.as_ivec2();
+            gl::Viewport(0, 0, size.x, size.y);
+            pop_debug_group();
+        }
+
+        self.frame = self.frame.wrapping_add(1);
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("physarum draw");
+
+        let theme = theme::current();
+        let half_size = TRAIL.as_vec2() * CELL * 0.5;
+        let inverse = self.matrix.inverse();
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            gl::UseProgram(self.view_shader);
+            gl::UniformMatrix4fv(self.u_inverse, 1, gl::FALSE, inverse.as_ref().as_ptr());
+            gl::Uniform2f(self.u_half_size, half_size.x, half_size.y);
+            gl::Uniform1f(self.u_exposure, self.params.f32("exposure"));
+            gl::Uniform4fv(self.u_clear, 1, theme.clear.as_ptr());
+            gl::Uniform4fv(self.u_color, 1, theme.accent.as_ptr());
+            gl::Uniform4fv(self.u_highlight, 1, theme.hud_text.as_ptr());
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.trail[0].texture);
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::BindVertexArray(0);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
+
+impl Drop for PhysarumScene {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(shader) = &self.agent_shader {
+                gl::DeleteProgram(shader.program);
+            }
+            gl::DeleteProgram(self.blur_shader);
+            gl::DeleteProgram(self.view_shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo, self.agents_ssbo]);
+            for framebuffer in &self.trail {
+                delete_framebuffer(framebuffer);
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::ffi::{c_void, CString};
+use std::rc::Rc;
+
+use glam::Vec2;
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::error::GlError;
+use crate::plugin::{self, CreateInfo, Plugin};
+use crate::profiling;
+use crate::render_state::RenderState;
+
+use super::{Scene, SceneContext};
+
+/// A scene living in a plugin, driven through its vtable.
+pub struct PluginScene {
+    plugin: Rc<Plugin>,
+    scene: *mut c_void,
+    viewport: Vec2,
+}
+
+impl PluginScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(plugin: Rc<Plugin>, ctx: &SceneContext) -> Result<Self, String> {
+        let info = CreateInfo {
+            width: ctx.size.width,
+            height: ctx.size.height,
+            seed: ctx.seed,
+        };
+        let host = plugin::host();
+
+        let scene = unsafe { (plugin.vtable.create)(&host, &info) };
+        if scene.is_null() {
+            return Err(format!("the plugin could not create '{}'", plugin.name));
+        }
+
+        Ok(Self {
+            plugin,
+            scene,
+            viewport: Vec2::new(ctx.size.width as f32, ctx.size.height as f32),
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.plugin.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.plugin.description
+    }
+}
+
+impl Scene for PluginScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let key = match keycode {
+            Key::Character(c) => c.to_string(),
+            Key::Named(named) => format!("{named:?}"),
+            _ => return,
+        };
+        let Ok(key) = CString::new(key) else {
+            return;
+        };
+
+        unsafe { (self.plugin.vtable.on_key)(self.scene, key.as_ptr()) };
+    }
+
+    fn update(&mut self, dt: f32) {
+        unsafe { (self.plugin.vtable.update)(self.scene, dt) };
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("plugin draw");
+
+        let matrix = camera.matrix(self.viewport).to_cols_array();
+        let mouse = camera.pointer_to_pos(mouse_pos, self.viewport);
+        unsafe { (self.plugin.vtable.draw)(self.scene, matrix.as_ptr(), mouse.x, mouse.y) };
+        Ok(())
+    }
+
+    fn resize(&mut self, _camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        self.viewport = Vec2::new(width as f32, height as f32);
+        unsafe { (self.plugin.vtable.resize)(self.scene, width, height) };
+        Ok(())
+    }
+}
+
+impl Drop for PluginScene {
+    fn drop(&mut self) {
+        unsafe { (self.plugin.vtable.destroy)(self.scene) };
+    }
+}
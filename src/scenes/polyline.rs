@@ -0,0 +1,142 @@
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{Mat4, Vec2};
+
+use crate::common_gl::{buffer_data, create_shader_program, delete_buffers};
+use crate::error::GlError;
+
+use super::{SRC_FRAG_POLYLINE, SRC_VERT_POLYLINE};
+
+/// Miters longer than this many half widths are cut short, so that sharp
+/// turns don't spike out.
+const MITER_LIMIT: f32 = 4.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct PolylineVertex {
+    position: Vec2,
+    color: [f32; 4],
+    edge: f32,
+}
+
+/// Batches thick antialiased polylines, expanded into triangles on the CPU
+/// with mitered joins, and draws them in one call.
+pub struct PolylineRenderer {
+    shader: GLuint,
+    u_mvp: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    vertices: Vec<PolylineVertex>,
+}
+
+impl PolylineRenderer {
+    pub unsafe fn new() -> Result<Self, GlError> {
+        let shader = create_shader_program(SRC_VERT_POLYLINE, SRC_FRAG_POLYLINE)?;
+
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        let mut vbo = 0;
+        gl::GenBuffers(1, &mut vbo);
+
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+        let size_f32 = mem::size_of::<f32>() as GLsizei;
+        let size_vertex = mem::size_of::<PolylineVertex>() as GLsizei;
+        gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, size_vertex, 0 as _);
+        gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, size_vertex, (2 * size_f32) as _);
+        gl::VertexAttribPointer(2, 1, gl::FLOAT, gl::FALSE, size_vertex, (6 * size_f32) as _);
+        for location in 0..3 {
+            gl::EnableVertexAttribArray(location);
+        }
+
+        gl::BindVertexArray(0);
+
+        Ok(Self {
+            u_mvp: gl::GetUniformLocation(shader, c"u_mvp".as_ptr()),
+            shader,
+            vao,
+            vbo,
+            vertices: Vec::new(),
+        })
+    }
+
+    /// Adds a polyline through `points` to the batch, `width` being in world
+    /// units.
+    pub fn push(&mut self, points: &[Vec2], width: f32, color: [f32; 4]) {
+        if points.len() < 2 {
+            return;
+        }
+
+        let half = width * 0.5;
+        let direction = |a: Vec2, b: Vec2| (b - a).try_normalize().unwrap_or(Vec2::X);
+
+        // offset from each point to the left side of the line
+        let offsets = (0..points.len())
+            .map(|i| {
+                let before = direction(points[i.saturating_sub(1)], points[i]);
+                let after = direction(points[i], points[(i + 1).min(points.len() - 1)]);
+                let (before, after) = match i {
+                    0 => (after, after),
+                    _ if i == points.len() - 1 => (before, before),
+                    _ => (before, after),
+                };
+
+                let normal = before.perp();
+                let miter = (before + after).try_normalize().unwrap_or(before).perp();
+                let scale = 1.0 / miter.dot(normal).max(1.0 / MITER_LIMIT);
+                miter * half * scale
+            })
+            .collect::<Vec<_>>();
+
+        for i in 0..points.len() - 1 {
+            let vertex = |j: usize, edge: f32| PolylineVertex {
+                position: points[j] + offsets[j] * edge,
+                color,
+                edge,
+            };
+
+            self.vertices.extend([
+                vertex(i, 1.0),
+                vertex(i, -1.0),
+                vertex(i + 1, 1.0),
+                vertex(i + 1, 1.0),
+                vertex(i, -1.0),
+                vertex(i + 1, -1.0),
+            ]);
+        }
+    }
+
+    /// Draws and empties the batch.
+    pub unsafe fn draw(&mut self, matrix: &Mat4) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        gl::UseProgram(self.shader);
+        gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, matrix.as_ref().as_ptr());
+
+        buffer_data(
+            gl::ARRAY_BUFFER,
+            self.vbo,
+            self.vertices.as_slice(),
+            gl::STREAM_DRAW,
+        );
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, self.vertices.len() as GLsizei);
+        gl::BindVertexArray(0);
+
+        self.vertices.clear();
+    }
+}
+
+impl Drop for PolylineRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
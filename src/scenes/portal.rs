@@ -0,0 +1,373 @@
+use std::ffi::{c_void, CStr};
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{vec2, Vec2, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::{Camera, Projection};
+use crate::common_gl::{buffer_data, create_shader_program, delete_buffers};
+use crate::error::GlError;
+use crate::palette::Palette;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::{Scene, SRC_FRAG_PORTAL, SRC_VERT_PORTAL};
+
+/// Variants of the `head` parameter, in order.
+const HEADS: &[&str] = &["mouse", "orbit", "still"];
+
+const N_CARDS: usize = 80;
+/// Half the size of the region cards are scattered over, in world units.
+const SPREAD: Vec2 = vec2(900.0, 520.0);
+/// How far in front of the screen the closest cards float, as a fraction of
+/// the depth of the room.
+const POP_OUT: f32 = 0.12;
+/// How quickly the head catches up with where it is going, per second.
+const HEAD_SPEED: f32 = 8.0;
+/// Radius of the circle the head goes around in orbit mode, in pixels.
+const ORBIT_RADIUS: f32 = 320.0;
+const ORBIT_SPEED: f32 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Head {
+    /// Where the mouse is over the window.
+    Mouse,
+    /// Going around the center of the window on its own.
+    Orbit,
+    /// Right in front of the center of the window.
+    Still,
+}
+
+impl Head {
+    fn from_variant(variant: usize) -> Self {
+        match variant {
+            1 => Self::Orbit,
+            2 => Self::Still,
+            _ => Self::Mouse,
+        }
+    }
+}
+
+/// Card floating in the room, facing the screen.
+#[derive(Debug, Clone, Copy)]
+struct Card {
+    center: Vec2,
+    /// Depth as a fraction of the depth of the room, negative in front of
+    /// the screen.
+    depth: f32,
+    size: Vec2,
+    color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct Vertex {
+    position: Vec3,
+    local: Vec2,
+    size: Vec2,
+    // not a `Vec4`, whose alignment would pad the vertex
+    color: [f32; 4],
+    kind: f32,
+}
+
+impl Vertex {
+    /// Name, number of floats and offset in floats of every attribute.
+    #[rustfmt::skip]
+    const ATTRIBUTES: [(&'static CStr, GLint, GLsizei); 5] = [
+        (c"position", 3,  0),
+        (c"local",    2,  3),
+        (c"size",     2,  5),
+        (c"color",    4,  7),
+        (c"kind",     1, 11),
+    ];
+}
+
+/// The window as a portal into a room behind the screen: an off-axis
+/// perspective follows an assumed head position, so that the room and the
+/// cards floating in it shift like they would behind real glass.
+///
+/// The room is as wide and tall as the window at the screen, wherever the
+/// camera is, while the cards stay put in the world.
+pub struct PortalScene {
+    viewport: Vec2,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_depth: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    ebo: GLuint,
+
+    /// Sorted from the back of the room to the front.
+    cards: Vec<Card>,
+    vertices: Vec<[Vertex; 4]>,
+
+    /// Where the head is relative to the center of the window, in pixels.
+    head: Vec2,
+    /// Where the head is going.
+    head_target: Vec2,
+    /// Clock of the orbit mode.
+    time: f32,
+
+    params: Params,
+}
+
+impl PortalScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut cards = (0..N_CARDS)
+            .map(|_| {
+                let swatch = Palette::Pastel.swatch(&mut rng);
+                let [r, g, b, _] = swatch.fill.to_le_bytes().map(|c| c as f32 / 255.0);
+                Card {
+                    center: vec2(
+                        rng.gen_range(-SPREAD.x..=SPREAD.x),
+                        rng.gen_range(-SPREAD.y..=SPREAD.y),
+                    ),
+                    depth: rng.gen_range(-POP_OUT..=0.95),
+                    size: vec2(rng.gen_range(60.0..=180.0), rng.gen_range(60.0..=180.0)),
+                    color: [r, g, b, 0.92],
+                }
+            })
+            .collect::<Vec<_>>();
+        cards.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+
+        // the 5 walls, then the cards
+        let n_quads = 5 + N_CARDS;
+        let indices = (0..n_quads as u32)
+            .map(|i| [0, 1, 2, 0, 2, 3].map(|corner| i * 4 + corner))
+            .collect::<Vec<_>>();
+
+        unsafe {
+            let shader = create_shader_program(SRC_VERT_PORTAL, SRC_FRAG_PORTAL)?;
+            let u_mvp = gl::GetUniformLocation(shader, c"u_mvp".as_ptr());
+            let u_depth = gl::GetUniformLocation(shader, c"u_depth".as_ptr());
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+
+            let mut buffers = [0; 2];
+            gl::GenBuffers(2, buffers.as_mut_ptr());
+            let [vbo, ebo] = buffers;
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            let size_vertex = mem::size_of::<Vertex>() as GLsizei;
+            let size_f32 = mem::size_of::<f32>() as GLsizei;
+            for (name, size, offset) in Vertex::ATTRIBUTES {
+                let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+                let offset = (offset * size_f32) as *const c_void;
+                gl::VertexAttribPointer(location, size, gl::FLOAT, gl::FALSE, size_vertex, offset);
+                gl::EnableVertexAttribArray(location);
+            }
+            buffer_data(
+                gl::ELEMENT_ARRAY_BUFFER,
+                ebo,
+                indices.as_slice(),
+                gl::STATIC_DRAW,
+            );
+            gl::BindVertexArray(0);
+
+            Ok(Self {
+                viewport: Vec2::ONE,
+
+                shader,
+                u_mvp,
+                u_depth,
+                vao,
+                vbo,
+                ebo,
+
+                cards,
+                vertices: Vec::with_capacity(n_quads),
+
+                head: Vec2::ZERO,
+                head_target: Vec2::ZERO,
+                time: 0.0,
+
+                params: Params::new()
+                    .with_enum("head", 0, HEADS)
+                    .with_f32("distance", 1600.0, 400.0, 4000.0, 200.0)
+                    .with_f32("depth", 1200.0, 200.0, 4000.0, 200.0),
+            })
+        }
+    }
+
+    /// Walls of the room, from the edges of the window at the screen to the
+    /// back wall `depth` behind it.
+    fn push_walls(&mut self, camera: &Camera, depth: f32) {
+        // corners of the window in the world, clockwise from the top-left
+        let corners = [
+            Vec2::ZERO,
+            vec2(self.viewport.x, 0.0),
+            self.viewport,
+            vec2(0.0, self.viewport.y),
+        ]
+        .map(|pixel| camera.pointer_to_pos(pixel, self.viewport));
+        let [r, g, b, _] = theme::current().grid;
+        let color = [r, g, b, 1.0];
+
+        for i in 0..4 {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            let length = a.distance(b);
+            self.vertices.push(quad(
+                |corner| a.lerp(b, corner.x + 0.5).extend((corner.y + 0.5) * depth),
+                |corner| vec2((corner.x + 0.5) * length, (corner.y + 0.5) * depth),
+                Vec2::ZERO,
+                color,
+                0.0,
+            ));
+        }
+
+        let [top_left, top_right, _, bottom_left] = corners;
+        let (right, down) = (top_right - top_left, bottom_left - top_left);
+        self.vertices.push(quad(
+            |corner| (top_left + right * (corner.x + 0.5) + down * (corner.y + 0.5)).extend(depth),
+            |corner| (corner + 0.5) * vec2(right.length(), down.length()),
+            Vec2::ZERO,
+            color,
+            0.0,
+        ));
+    }
+}
+
+impl Scene for PortalScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("h") => self.params.step("head", 1).unwrap(),
+            Key::Character("H") => self.params.step("head", -1).unwrap(),
+            Key::Character("d") => self.params.step("distance", 1).unwrap(),
+            Key::Character("D") => self.params.step("distance", -1).unwrap(),
+            Key::Character("z") => self.params.step("depth", 1).unwrap(),
+            Key::Character("Z") => self.params.step("depth", -1).unwrap(),
+            _ => return,
+        }
+
+        println!("portal config: {}", self.params);
+    }
+
+    /// Moves the head toward where it is going.
+    fn update(&mut self, dt: f32) {
+        self.time += dt;
+        if Head::from_variant(self.params.variant("head")) == Head::Orbit {
+            let angle = self.time * ORBIT_SPEED;
+            self.head_target = Vec2::from_angle(angle) * vec2(ORBIT_RADIUS, ORBIT_RADIUS * 0.5);
+        }
+
+        let t = 1.0 - (-HEAD_SPEED * dt).exp();
+        self.head += (self.head_target - self.head) * t;
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("portal draw");
+
+        match Head::from_variant(self.params.variant("head")) {
+            Head::Mouse => self.head_target = mouse_pos - self.viewport * 0.5,
+            Head::Orbit => {}
+            Head::Still => self.head_target = Vec2::ZERO,
+        }
+
+        let depth = self.params.f32("depth");
+        let eye = self.head.extend(self.params.f32("distance"));
+        let projection = Projection::OffAxis { eye, far: depth };
+        let matrix = camera.projection_matrix(self.viewport, projection);
+
+        self.vertices.clear();
+        self.push_walls(camera, depth);
+        for card in &self.cards {
+            let center = card.center.extend(card.depth * depth);
+            self.vertices.push(quad(
+                |corner| center + (corner * card.size).extend(0.0),
+                |corner| corner * card.size,
+                card.size,
+                card.color,
+                1.0,
+            ));
+        }
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            gl::UseProgram(self.shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, matrix.as_ref().as_ptr());
+            gl::Uniform1f(self.u_depth, depth);
+
+            gl::BindVertexArray(self.vao);
+            buffer_data(
+                gl::ARRAY_BUFFER,
+                self.vbo,
+                self.vertices.as_slice(),
+                gl::STREAM_DRAW,
+            );
+            gl::DrawElements(
+                gl::TRIANGLES,
+                (self.vertices.len() * 6) as GLsizei,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            );
+            gl::BindVertexArray(0);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, _camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        Ok(())
+    }
+}
+
+impl Drop for PortalScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo, self.ebo]);
+        }
+    }
+}
+
+/// The 4 vertices of a quad, with `position` and `local` giving the position
+/// and local coordinates of each corner, from -0.5 to 0.5.
+fn quad(
+    position: impl Fn(Vec2) -> Vec3,
+    local: impl Fn(Vec2) -> Vec2,
+    size: Vec2,
+    color: [f32; 4],
+    kind: f32,
+) -> [Vertex; 4] {
+    [
+        vec2(-0.5, -0.5),
+        vec2(0.5, -0.5),
+        vec2(0.5, 0.5),
+        vec2(-0.5, 0.5),
+    ]
+    .map(|corner| Vertex {
+        position: position(corner),
+        local: local(corner),
+        size,
+        color,
+        kind,
+    })
+}
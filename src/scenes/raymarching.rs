@@ -0,0 +1,397 @@
+use std::mem;
+
+use glam::{uvec2, vec2, vec3, UVec2, Vec2};
+use winit::dpi::PhysicalSize;
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    bind_framebuffer, bind_texture, create_framebuffer, delete_framebuffer, set_viewport, Attrib,
+    Buffer, DebugGroup, Framebuffer, Program, Uniform, VertexArray,
+};
+use crate::error::GlError;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::technique_timings::TechniqueTimings;
+use super::{Scene, SRC_FRAG_CHECKERBOARD, SRC_FRAG_RAYMARCH, SRC_VERT_SCREEN};
+
+/// Both ways of drawing the scene, which are also the names of their timings.
+const TECHNIQUES: &[&str] = &["full", "checkerboard"];
+/// Variants of the `fill` parameter: where the pixels that weren't drawn
+/// come from.
+const FILLS: &[&str] = &["history", "neighbors"];
+
+/// Radius and height of the orbit of the eye, and how fast it goes around at
+/// speed 1, in radians per second.
+const ORBIT_RADIUS: f32 = 11.0;
+const ORBIT_HEIGHT: f32 = 3.5;
+const ORBIT_SPEED: f32 = 0.15;
+
+const TEXT_SIZE: f32 = 14.0;
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+
+const LAYOUT: &[Attrib] = &[
+    Attrib {
+        name: c"position",
+        components: 2,
+        offset: 0,
+    },
+    Attrib {
+        name: c"uv",
+        components: 2,
+        offset: mem::size_of::<Vec2>(),
+    },
+];
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+impl Vertex {
+    const fn new(position: Vec2, uv: Vec2) -> Self {
+        Self { position, uv }
+    }
+}
+
+#[rustfmt::skip]
+const SCREEN_VERTICES: &[Vertex] = &[
+    Vertex::new(vec2(-1.0,  1.0), vec2(0.0, 1.0)),
+    Vertex::new(vec2(-1.0, -1.0), vec2(0.0, 0.0)),
+    Vertex::new(vec2( 1.0, -1.0), vec2(1.0, 0.0)),
+    Vertex::new(vec2(-1.0,  1.0), vec2(0.0, 1.0)),
+    Vertex::new(vec2( 1.0, -1.0), vec2(1.0, 0.0)),
+    Vertex::new(vec2( 1.0,  1.0), vec2(1.0, 1.0)),
+];
+
+/// Blobs and a ring of boxes over a checkered floor, sphere traced in a
+/// single fragment shader with soft shadows and ambient occlusion, under an
+/// eye circling them.
+///
+/// The scene costs nothing but fragments, which checkerboard rendering
+/// halves: every frame draws one of the two colors of a checkerboard of
+/// pixels, into a target half as wide, and the picture is rebuilt from it and
+/// the last picture, clamped to the pixels drawn around each missing one
+/// since nothing tells where things were the frame before. That breaks down
+/// on thin lines and fast motion, which the artifacts view shows.
+pub struct RaymarchingScene {
+    viewport: Vec2,
+    time: f32,
+    orbit: f32,
+    /// Frames drawn in checkerboard, which pick the half drawn.
+    frame: u32,
+    /// Whether the history holds a picture of this size.
+    history_valid: bool,
+
+    raymarch_shader: Program,
+    u_resolution: Uniform,
+    u_checkerboard: Uniform,
+    u_parity: Uniform,
+    u_time: Uniform,
+    u_eye: Uniform,
+    u_steps: Uniform,
+    u_shadow_steps: Uniform,
+
+    reconstruct_shader: Program,
+    u_reconstruct_parity: Uniform,
+    u_use_history: Uniform,
+    u_reset: Uniform,
+    u_artifacts: Uniform,
+
+    vao: VertexArray,
+    _vbo: Buffer<Vertex>,
+
+    targets: Option<Targets>,
+    timings: TechniqueTimings,
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+impl RaymarchingScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(size: PhysicalSize<u32>, text: Option<SharedText>) -> Result<Self, GlError> {
+        let raymarch_shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_RAYMARCH)?;
+        raymarch_shader.set(raymarch_shader.uniform(c"u_target"), vec3(0.0, 0.8, 0.0));
+        let reconstruct_shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_CHECKERBOARD)?;
+        for (name, unit) in [(c"u_half", 0), (c"u_history", 1)] {
+            reconstruct_shader.set(reconstruct_shader.uniform(name), unit);
+        }
+
+        let vbo = Buffer::new(gl::ARRAY_BUFFER, SCREEN_VERTICES, gl::STATIC_DRAW);
+        let mut vao = VertexArray::new();
+        // both share screen.vert, so their attributes are at the same
+        // locations
+        vao.set_layout(&raymarch_shader, &vbo, LAYOUT);
+
+        let params = Params::new()
+            .with_bool("checkerboard", true)
+            .with_enum("fill", 0, FILLS)
+            .with_bool("artifacts", false)
+            .with_i32("steps", 128, 16, 512)
+            .with_i32("shadow steps", 48, 0, 256)
+            .with_f32("speed", 1.0, 0.0, 4.0, 0.25);
+
+        let size = uvec2(size.width, size.height).max(UVec2::ONE);
+        let mut scene = Self {
+            viewport: size.as_vec2(),
+            time: 0.0,
+            orbit: 0.0,
+            frame: 0,
+            history_valid: false,
+
+            u_resolution: raymarch_shader.uniform(c"u_resolution"),
+            u_checkerboard: raymarch_shader.uniform(c"u_checkerboard"),
+            u_parity: raymarch_shader.uniform(c"u_parity"),
+            u_time: raymarch_shader.uniform(c"u_time"),
+            u_eye: raymarch_shader.uniform(c"u_eye"),
+            u_steps: raymarch_shader.uniform(c"u_steps"),
+            u_shadow_steps: raymarch_shader.uniform(c"u_shadow_steps"),
+            raymarch_shader,
+
+            u_reconstruct_parity: reconstruct_shader.uniform(c"u_parity"),
+            u_use_history: reconstruct_shader.uniform(c"u_use_history"),
+            u_reset: reconstruct_shader.uniform(c"u_reset"),
+            u_artifacts: reconstruct_shader.uniform(c"u_artifacts"),
+            reconstruct_shader,
+
+            vao,
+            _vbo: vbo,
+
+            targets: None,
+            timings: TechniqueTimings::create(TECHNIQUES),
+
+            text,
+
+            params,
+        };
+        scene.resize_targets(size)?;
+        Ok(scene)
+    }
+
+    /// Recreates the half target and the history if the size changed.
+    fn resize_targets(&mut self, size: UVec2) -> Result<(), GlError> {
+        if (self.targets.as_ref()).is_some_and(|targets| targets.size == size) {
+            return Ok(());
+        }
+
+        self.targets = None;
+        self.targets = Some(unsafe { Targets::new(size) }?);
+        self.history_valid = false;
+        bind_framebuffer(None);
+        Ok(())
+    }
+
+    /// Sphere traces the scene over the bound framebuffer, every pixel of it
+    /// or this frame's half of the checkerboard.
+    fn raymarch(&self, checkerboard: bool, parity: i32) {
+        let eye = vec3(
+            self.orbit.cos() * ORBIT_RADIUS,
+            ORBIT_HEIGHT,
+            self.orbit.sin() * ORBIT_RADIUS,
+        );
+        let shader = &self.raymarch_shader;
+        shader.set(self.u_resolution, self.viewport);
+        shader.set(self.u_checkerboard, checkerboard);
+        shader.set(self.u_parity, parity);
+        shader.set(self.u_time, self.time);
+        shader.set(self.u_eye, eye);
+        shader.set(self.u_steps, self.params.i32("steps"));
+        shader.set(self.u_shadow_steps, self.params.i32("shadow steps"));
+        self.vao.draw_arrays(gl::TRIANGLES);
+    }
+
+    /// Draws this frame's half of the checkerboard and rebuilds the picture
+    /// from it into the history, which gets shown.
+    fn draw_checkerboard(&mut self) {
+        let parity = (self.frame % 2) as i32;
+        let targets = self.targets.as_ref().unwrap();
+
+        bind_framebuffer(Some(&targets.half));
+        set_viewport(targets.half.size);
+        self.raymarch(true, parity);
+
+        let [last, next] = &targets.history;
+        bind_framebuffer(Some(next));
+        set_viewport(targets.size);
+        bind_texture(1, last.texture);
+        bind_texture(0, targets.half.texture);
+        let use_history = FILLS[self.params.variant("fill")] == "history";
+        let shader = &self.reconstruct_shader;
+        shader.set(self.u_reconstruct_parity, parity);
+        shader.set(self.u_use_history, use_history);
+        shader.set(self.u_reset, !self.history_valid);
+        shader.set(self.u_artifacts, self.params.bool("artifacts"));
+        self.vao.draw_arrays(gl::TRIANGLES);
+
+        let size = targets.size.as_ivec2();
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, next.fbo);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                size.x,
+                size.y,
+                0,
+                0,
+                size.x,
+                size.y,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+        }
+        bind_framebuffer(None);
+
+        self.targets.as_mut().unwrap().history.swap(0, 1);
+        self.history_valid = true;
+        self.frame = self.frame.wrapping_add(1);
+    }
+
+    fn label(&self) -> String {
+        if !self.params.bool("checkerboard") {
+            return "Full resolution".to_string();
+        }
+        let fill = match FILLS[self.params.variant("fill")] {
+            "history" => "the last frame, clamped",
+            _ => "the neighbors",
+        };
+        format!("Checkerboard, half the pixels filled from {fill}")
+    }
+}
+
+impl Scene for RaymarchingScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("c" | "C") => {
+                self.params.toggle("checkerboard").unwrap();
+                // the history is as old as the last checkerboard frame
+                self.history_valid = false;
+            }
+            Key::Character("f") => self.params.step("fill", 1).unwrap(),
+            Key::Character("F") => self.params.step("fill", -1).unwrap(),
+            Key::Character("a" | "A") => self.params.toggle("artifacts").unwrap(),
+            Key::Character("s") => self.params.step("steps", 16).unwrap(),
+            Key::Character("S") => self.params.step("steps", -16).unwrap(),
+            Key::Character("h") => self.params.step("shadow steps", 16).unwrap(),
+            Key::Character("H") => self.params.step("shadow steps", -16).unwrap(),
+            Key::Character("v") => self.params.step("speed", 1).unwrap(),
+            Key::Character("V") => self.params.step("speed", -1).unwrap(),
+            _ => return,
+        }
+
+        println!("raymarching config: {}", self.params);
+    }
+
+    fn update(&mut self, dt: f32) {
+        let dt = dt * self.params.f32("speed");
+        self.time += dt;
+        self.orbit += dt * ORBIT_SPEED;
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("raymarching draw");
+
+        self.resize_targets(self.viewport.as_uvec2().max(UVec2::ONE))?;
+
+        let checkerboard = self.params.bool("checkerboard");
+        {
+            let _group = DebugGroup::push(c"Raymarching");
+            unsafe { self.timings.begin(checkerboard as usize) };
+            if checkerboard {
+                self.draw_checkerboard();
+            } else {
+                bind_framebuffer(None);
+                set_viewport(self.viewport.as_uvec2());
+                self.raymarch(false, 0);
+            }
+            unsafe { self.timings.end() };
+        }
+
+        if let Some(summary) = self.timings.summarize() {
+            println!("raymarching: {summary}");
+        }
+
+        let label = self.label();
+        let theme = theme::current();
+        let Some(text) = &self.text else {
+            return Ok(());
+        };
+        let mut text = text.borrow_mut();
+        let extent = text.measure(&label, TEXT_SIZE, None);
+        let min = Vec2::splat(MARGIN);
+        let max = min + extent + PADDING * 2.0;
+        text.queue_rect(min, max, theme.hud_background);
+        text.queue(&label, min + PADDING, TEXT_SIZE, None, theme.hud_text);
+        unsafe { text.draw(0, self.viewport) };
+        Ok(())
+    }
+
+    fn resize(&mut self, _camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        self.viewport = Vec2::new(width as f32, height as f32);
+        set_viewport(self.viewport.as_uvec2());
+        Ok(())
+    }
+}
+
+/// What checkerboard rendering draws to before the window.
+struct Targets {
+    /// Size of the whole picture.
+    size: UVec2,
+    /// This frame's half of the checkerboard, half as wide as the picture.
+    half: Framebuffer,
+    /// Both ends of the ping-pong the picture is rebuilt in, the last one
+    /// written first.
+    history: [Framebuffer; 2],
+}
+
+impl Targets {
+    unsafe fn new(size: UVec2) -> Result<Self, GlError> {
+        let half_size = uvec2(size.x.div_ceil(2), size.y);
+        let half = create_framebuffer("checkerboard half", half_size)?;
+        let ping = create_framebuffer("checkerboard history ping", size)
+            .inspect_err(|_| delete_framebuffer(&half))?;
+        let pong = create_framebuffer("checkerboard history pong", size).inspect_err(|_| {
+            delete_framebuffer(&half);
+            delete_framebuffer(&ping);
+        })?;
+        Ok(Self {
+            size,
+            half,
+            history: [ping, pong],
+        })
+    }
+}
+
+impl Drop for Targets {
+    fn drop(&mut self) {
+        unsafe {
+            delete_framebuffer(&self.half);
+            for framebuffer in &self.history {
+                delete_framebuffer(framebuffer);
+            }
+        }
+    }
+}
@@ -1,257 +1,1177 @@
 use std::{
     f32::consts::{PI, TAU},
+    ffi::CStr,
     mem,
-    time::Instant,
+    ops::RangeInclusive,
+    rc::Rc,
+    time::{Duration, Instant},
 };
 
-use gl::types::{GLfloat, GLint, GLsizei, GLsizeiptr, GLuint};
-use glam::{vec2, Mat4, Vec2, Vec4};
-use rand::Rng;
-use winit::window::Window;
+use glam::{uvec2, vec2, Mat4, UVec2, Vec2, Vec3, Vec4};
+use image::RgbaImage;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::dpi::PhysicalSize;
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    bind_framebuffer, bind_texture, clear, generate_mipmaps, set_blend_func,
+    set_blend_func_separate, set_viewport, set_viewport_rect, Attrib, Buffer, IndexBuffer, Program,
+    RenderTarget, Texture, Uniform, VertexArray,
+};
+use crate::data_grid::DataGrid;
+use crate::error::GlError;
+use crate::gpu_memory::format_bytes;
+use crate::input_image::InputImage;
+use crate::palette::{self, Palette, Swatch, PALETTES};
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+use crate::tween::{Easing, Tween, Tweener};
+
+use super::{
+    Scene, SceneEvent, SRC_FRAG_HEATMAP, SRC_FRAG_ROUND_RECT, SRC_FRAG_ROUND_SHADOW,
+    SRC_FRAG_TEXTURE, SRC_VERT_QUAD, SRC_VERT_ROUND_RECT, SRC_VERT_ROUND_SHADOW,
+};
 
-use crate::{camera::Camera, common_gl::create_shader_program};
+const N_QUADS: usize = 100_000;
 
-use super::{SRC_FRAG_ROUND_RECT, SRC_VERT_ROUND_RECT};
+/// Distance between the centers of two neighboring quads.
+const CELL_SIZE: f32 = 16.0;
 
-const N_QUADS: usize = 100_000;
+/// Side of a square chunk of quads, baked as a single impostor.
+const CHUNK_QUADS: u32 = 32;
+/// Side of a chunk in the impostor atlas.
+const CHUNK_TEXELS: u32 = 128;
+
+/// Below this camera scale, chunks are drawn as impostors. At exactly this
+/// scale an atlas texel covers a pixel, so switching is seamless.
+const LOD_SCALE: f32 = CHUNK_TEXELS as f32 / (CHUNK_QUADS as f32 * CELL_SIZE);
+
+/// How much the input image replaces the fill colors in mosaic mode.
+const MOSAIC_MIX: f32 = 0.85;
+
+/// Radius around the mouse in which quads spin, in world units.
+const SPIN_RADIUS: f32 = 320.0;
+
+/// Scale of the quad under the mouse.
+const HOVER_SCALE: f32 = 1.4;
+/// Durations of the hover animations, in seconds.
+const HOVER_IN: f32 = 0.25;
+const HOVER_OUT: f32 = 0.4;
+
+/// How far ripples go before they die out, in world units.
+const RIPPLE_RADIUS: f32 = 640.0;
+/// Width of the ring of a ripple, in world units.
+const RIPPLE_WIDTH: f32 = 24.0;
+/// How much the crest of a ripple scales quads up.
+const RIPPLE_SCALE: f32 = 0.6;
+/// In seconds.
+const RIPPLE_TIME: f32 = 1.6;
+/// How far the mouse may move between press and release for a click, in pixels.
+const CLICK_PIXELS: f32 = 4.0;
+
+/// Variants of the "fill" parameter, in the order of [`Fill`].
+const FILLS: &[&str] = &["flat", "linear", "radial"];
+
+/// Variants of the "ambient" parameter, numbered like `u_ambient` in the
+/// round-rect vertex shaders.
+const AMBIENTS: &[&str] = &["none", "waves", "ripples", "flow"];
+
+/// Variants of the "data" parameter, in the order of [`DataMode`].
+const DATA_MODES: &[&str] = &["off", "image", "csv"];
 
 pub struct RoundQuadsScene {
     matrix: Mat4,
     viewport: Vec2,
 
-    round_rect_shader: GLuint,
-    vao: GLuint,
-    vbo: GLuint,
-    ebo: GLuint,
-
-    u_mvp_quad: GLint,
+    round_rect_shader: Program,
+    vao: VertexArray,
+    vbo: Buffer<Vertex>,
+    ebo: IndexBuffer,
+
+    u_mvp_quad: Uniform,
+    u_parallax: Uniform,
+    u_pixels_per_unit: Uniform,
+    /// Pixels per world unit.
+    pixels_per_unit: f32,
+    /// World offset of the quads per unit of elevation, for the eye being
+    /// drawn in stereo.
+    parallax: Vec2,
+
+    /// Draws the shadows of the quads from the same vertices, in a pass
+    /// before them so that every shadow falls under every quad.
+    shadow_shader: Program,
+    shadow_vao: VertexArray,
+    u_mvp_shadow: Uniform,
+    /// Whether the impostors were baked with shadows.
+    shadows: bool,
+
+    ambient_quad: AmbientUniforms,
+    ambient_shadow: AmbientUniforms,
+    /// Clock of the ambient animations, advanced by the fixed-step update.
+    time: f32,
+
+    /// Input image, cut into one tile per quad in mosaic mode.
+    input_texture: Texture,
+    /// Whether the vertices currently sample the input image.
+    mosaic: bool,
+    /// How the vertices currently fill their quad.
+    fill: Fill,
+    /// Where the colors of the quads currently come from.
+    palette: Palette,
+    /// What the quads currently show instead of random shapes.
+    data: DataMode,
+    seed: u64,
+    /// Part of the input image the grid covers in mosaic mode.
+    crop: Vec2,
+
+    /// Pixels of the input image, for the image data mode.
+    image: Rc<RgbaImage>,
+    /// Table of the CSV data mode.
+    csv: Option<DataGrid>,
 
     quads: Vec<Quad>,
     vertices: Vec<[Vertex; 4]>,
-    indices: Vec<[u32; 6]>,
 
     area_width: u32,
 
+    impostors: Impostors,
+    heatmap: UploadHeatmap,
+    params: Params,
+
+    /// Quad under the mouse.
+    hovered: Option<u32>,
+    /// Scales of quads being hovered or left, by index.
+    hover_scales: Tweener<u32, f32>,
+    /// Radii of the ripples, by index of the quad they started from.
+    ripples: Tweener<u32, f32>,
+    /// Where the left button was pressed, to tell clicks from drags.
+    pressed_at: Option<Vec2>,
+
     last_instant: Instant,
 }
 
 impl RoundQuadsScene {
-    pub fn new(window: &Window) -> Self {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(
+        size: PhysicalSize<u32>,
+        input: &InputImage,
+        data: Option<&DataGrid>,
+        seed: u64,
+    ) -> Result<Self, GlError> {
         let area_width = (N_QUADS as f32).sqrt() as u32;
 
+        // the grid is about square, so crop the image to a centered square
+        let image = &input.image;
+        let image_size = uvec2(image.width(), image.height()).as_vec2();
+        let crop = image_size.min_element() / image_size;
+
         let mut quads = Vec::with_capacity(N_QUADS);
         let mut vertices = Vec::with_capacity(N_QUADS);
         let mut indices = Vec::with_capacity(N_QUADS);
 
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut color_rng = Self::color_rng(seed);
         for i in 0..(N_QUADS as u32) {
-            let quad = Quad::random(&mut rng, i, area_width);
-            vertices.push(quad.vertices(0.5));
+            let swatch = Palette::Random.swatch(&mut color_rng);
+            let quad = Quad::random(&mut rng, swatch, i, area_width, crop);
+            vertices.push(quad.vertices(0.5, 1.0));
             indices.push(quad.indices(i));
             quads.push(quad);
         }
 
-        unsafe {
-            // Normal blending
-            gl::Enable(gl::BLEND);
-            gl::BlendEquation(gl::FUNC_ADD);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-
-            let round_rect_shader = create_shader_program(SRC_VERT_ROUND_RECT, SRC_FRAG_ROUND_RECT);
-
-            let u_mvp_quad = gl::GetUniformLocation(round_rect_shader, c"u_mvp".as_ptr());
-
-            let mut vao: u32 = 0;
-            gl::GenVertexArrays(1, &mut vao);
-            gl::BindVertexArray(vao);
-
-            let mut ssbo: u32 = 0;
-            gl::GenBuffers(1, &mut ssbo);
-            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, ssbo);
-
-            let mut vbo: u32 = 0;
-            gl::GenBuffers(1, &mut vbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                mem::size_of_val(vertices.as_slice()) as GLsizeiptr,
-                vertices.as_slice().as_ptr() as *const _,
-                gl::DYNAMIC_DRAW,
-            );
-
-            let mut ebo: u32 = 0;
-            gl::GenBuffers(1, &mut ebo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                mem::size_of_val(indices.as_slice()) as GLsizeiptr,
-                indices.as_slice().as_ptr() as *const _,
-                gl::STATIC_DRAW,
-            );
-
-            let size_vertex = mem::size_of::<Vertex>() as GLsizei;
-            let size_f32 = mem::size_of::<f32>() as GLsizei;
-
-            #[rustfmt::skip]
-            {
-                let a_position      = gl::GetAttribLocation(round_rect_shader, c"position"      .as_ptr()) as GLuint;
-                let a_size          = gl::GetAttribLocation(round_rect_shader, c"size"          .as_ptr()) as GLuint;
-                let a_fill_color    = gl::GetAttribLocation(round_rect_shader, c"fill_color"    .as_ptr()) as GLuint;
-                let a_stroke_color  = gl::GetAttribLocation(round_rect_shader, c"stroke_color"  .as_ptr()) as GLuint;
-                let a_border_radius = gl::GetAttribLocation(round_rect_shader, c"border_radius" .as_ptr()) as GLuint;
-                let a_border_width  = gl::GetAttribLocation(round_rect_shader, c"border_width"  .as_ptr()) as GLuint;
-                let a_intensity     = gl::GetAttribLocation(round_rect_shader, c"intensity"     .as_ptr()) as GLuint;
-
-                gl::VertexAttribPointer(a_position,      2, gl::FLOAT, gl::FALSE, size_vertex,   0             as _);
-                gl::VertexAttribPointer(a_size,          2, gl::FLOAT, gl::FALSE, size_vertex, ( 2 * size_f32) as _);
-                gl::VertexAttribPointer(a_fill_color,    4, gl::FLOAT, gl::FALSE, size_vertex, ( 4 * size_f32) as _);
-                gl::VertexAttribPointer(a_stroke_color,  4, gl::FLOAT, gl::FALSE, size_vertex, ( 8 * size_f32) as _);
-                gl::VertexAttribPointer(a_border_radius, 1, gl::FLOAT, gl::FALSE, size_vertex, (12 * size_f32) as _);
-                gl::VertexAttribPointer(a_border_width,  1, gl::FLOAT, gl::FALSE, size_vertex, (13 * size_f32) as _);
-                gl::VertexAttribPointer(a_intensity,     1, gl::FLOAT, gl::FALSE, size_vertex, (14 * size_f32) as _);
-
-                gl::EnableVertexAttribArray(a_position      as GLuint);
-                gl::EnableVertexAttribArray(a_size          as GLuint);
-                gl::EnableVertexAttribArray(a_fill_color    as GLuint);
-                gl::EnableVertexAttribArray(a_stroke_color  as GLuint);
-                gl::EnableVertexAttribArray(a_border_radius as GLuint);
-                gl::EnableVertexAttribArray(a_border_width  as GLuint);
-                gl::EnableVertexAttribArray(a_intensity     as GLuint);
-            };
+        let round_rect_shader = Program::new(SRC_VERT_ROUND_RECT, SRC_FRAG_ROUND_RECT)?;
+        let u_mvp_quad = round_rect_shader.uniform(c"u_mvp");
+        let u_parallax = round_rect_shader.uniform(c"u_parallax");
+        let u_pixels_per_unit = round_rect_shader.uniform(c"u_pixels_per_unit");
+
+        let shadow_shader = Program::new(SRC_VERT_ROUND_SHADOW, SRC_FRAG_ROUND_SHADOW)?;
+        let u_mvp_shadow = shadow_shader.uniform(c"u_mvp");
+
+        let field = Vec2::splat(area_width as f32 * CELL_SIZE * 0.5);
+        let ambient_quad = AmbientUniforms::new(&round_rect_shader, field);
+        let ambient_shadow = AmbientUniforms::new(&shadow_shader, field);
+
+        let image_size = uvec2(image.width(), image.height());
+        let input_texture = Texture::new(image_size, image.as_raw(), gl::CLAMP_TO_EDGE)?;
+        // a tile covers a handful of pixels of the image at most
+        generate_mipmaps(input_texture.id());
+
+        let vbo = Buffer::new(gl::ARRAY_BUFFER, vertices.as_flattened(), gl::DYNAMIC_DRAW);
+        let ebo = IndexBuffer::new(indices.as_flattened(), gl::STATIC_DRAW);
+
+        // each shader skips the attributes it doesn't use, so they need
+        // their own vertex array
+        let mut vao = VertexArray::new();
+        vao.set_layout(&round_rect_shader, &vbo, &Vertex::LAYOUT);
+        let mut shadow_vao = VertexArray::new();
+        shadow_vao.set_layout(&shadow_shader, &vbo, &Vertex::LAYOUT);
+
+        let viewport = Vec2::new(size.width as f32, size.height as f32);
+
+        let impostors = Impostors::new(area_width)?;
+        let heatmap = UploadHeatmap::new(impostors.chunks)?;
+
+        let scene = Self {
+            matrix: Mat4::default(),
+            viewport,
+
+            round_rect_shader,
+            vao,
+            vbo,
+            ebo,
+
+            u_mvp_quad,
+            u_parallax,
+            u_pixels_per_unit,
+            pixels_per_unit: 1.0,
+            parallax: Vec2::ZERO,
+
+            shadow_shader,
+            shadow_vao,
+            u_mvp_shadow,
+            shadows: false,
+
+            ambient_quad,
+            ambient_shadow,
+            time: 0.0,
+
+            input_texture,
+            mosaic: false,
+            fill: Fill::Flat,
+            palette: Palette::Random,
+            data: DataMode::Off,
+            seed,
+            crop,
+
+            image: input.image.clone(),
+            csv: data.cloned(),
+
+            quads,
+            vertices,
+
+            area_width,
+
+            impostors,
+            heatmap,
+            params: Params::new()
+                .with_bool("impostors", true)
+                .with_bool("heatmap", false)
+                .with_bool("mosaic", false)
+                .with_enum("fill", 0, FILLS)
+                .with_enum("palette", 0, PALETTES)
+                .with_bool("shadows", false)
+                .with_enum("ambient", 0, AMBIENTS)
+                .with_enum("data", 0, DATA_MODES),
+
+            hovered: None,
+            hover_scales: Tweener::new(),
+            ripples: Tweener::new(),
+            pressed_at: None,
+
+            last_instant: Instant::now(),
+        };
+
+        scene.bake_impostors();
+        Ok(scene)
+    }
+
+    /// Colors have their own stream of random numbers, so that they can be
+    /// generated again without moving or resizing the quads.
+    fn color_rng(seed: u64) -> StdRng {
+        StdRng::seed_from_u64(seed.wrapping_add(1))
+    }
+
+    /// Paints every quad from `palette`, updating their vertices in place,
+    /// then bakes the impostors again.
+    fn recolor(&mut self, palette: Palette) {
+        self.palette = palette;
+        if self.data != DataMode::Off {
+            // data has colors of its own
+            return;
+        }
+
+        let mut rng = Self::color_rng(self.seed);
+        for (quad, vertices) in self.quads.iter_mut().zip(&mut self.vertices) {
+            let swatch = palette.swatch(&mut rng);
+            quad.fill_color = swatch.fill;
+            quad.stroke_color = swatch.stroke;
+            quad.gradient_color = swatch.gradient;
+            *vertices = quad.vertices(0.5, 1.0);
+        }
+
+        self.vbo.update(self.vertices.as_flattened());
+        self.bake_impostors();
+    }
+
+    /// Generates the quads again, showing `data` unless it is off, then
+    /// bakes the impostors again.
+    fn show_data(&mut self, data: DataMode) {
+        self.data = data;
+        if data == DataMode::Csv && self.csv.is_none() {
+            println!("No CSV data, pass one with --csv or drop one onto the window");
+        }
+
+        let texture_mix = if self.mosaic { MOSAIC_MIX } else { 0.0 };
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut color_rng = Self::color_rng(self.seed);
+        for i in 0..self.quads.len() {
+            let swatch = self.palette.swatch(&mut color_rng);
+            let mut quad = Quad::random(&mut rng, swatch, i as u32, self.area_width, self.crop);
+            quad.texture_mix = texture_mix;
+            quad.fill = self.fill;
+
+            if data != DataMode::Off {
+                match self.datum(i as u32) {
+                    Some(datum) => quad.show(datum),
+                    None => quad.hide(),
+                }
+            }
+
+            self.quads[i] = quad;
+            self.vertices[i] = quad.vertices(self.rest_intensity(), 1.0);
+        }
+
+        self.vbo.update(self.vertices.as_flattened());
+        self.bake_impostors();
+    }
+
+    /// How the data maps onto the grid, unless there is none to show.
+    fn blocks(&self) -> Option<Blocks> {
+        let size = match self.data {
+            DataMode::Off => return None,
+            DataMode::Image => uvec2(self.image.width(), self.image.height()),
+            DataMode::Csv => {
+                let csv = self.csv.as_ref()?;
+                uvec2(csv.width, csv.height)
+            }
+        };
+        Some(Blocks::fit(size, self.area_width))
+    }
+
+    /// Color and magnitude from 0 to 1 of the data under quad `i`.
+    fn datum(&self, i: u32) -> Option<(u32, f32)> {
+        let cell = uvec2(i % self.area_width, i / self.area_width);
+        let (min, max) = self.blocks()?.region(cell)?;
+
+        match self.data {
+            DataMode::Off => None,
+            DataMode::Image => {
+                let mut sum = Vec4::ZERO;
+                for y in min.y..max.y {
+                    for x in min.x..max.x {
+                        sum += Vec4::from_array(self.image.get_pixel(x, y).0.map(|n| n as f32));
+                    }
+                }
+                let color = sum / (max - min).element_product() as f32 / 255.0;
+                let luma = color.truncate().dot(Vec3::new(0.2126, 0.7152, 0.0722));
+                Some((palette::pack(color.truncate(), 255), luma))
+            }
+            DataMode::Csv => {
+                let value = block_mean(self.csv.as_ref()?, min, max)?;
+                let t = self.csv.as_ref()?.normalize(value);
+                Some((palette::pack(palette::viridis(t), 255), t))
+            }
+        }
+    }
+
+    /// Makes every quad sample its tile of the input image or not, and fill
+    /// itself with `fill`, then bakes the impostors again.
+    fn restyle(&mut self, mosaic: bool, fill: Fill) {
+        self.mosaic = mosaic;
+        self.fill = fill;
+
+        let texture_mix = if mosaic { MOSAIC_MIX } else { 0.0 };
+        let intensity = self.rest_intensity();
+        for (quad, vertices) in self.quads.iter_mut().zip(&mut self.vertices) {
+            quad.texture_mix = texture_mix;
+            quad.fill = fill;
+            *vertices = quad.vertices(intensity, 1.0);
+        }
+
+        self.vbo.update(self.vertices.as_flattened());
+        self.bake_impostors();
+    }
+
+    /// Renders every chunk of quads into the impostor atlas.
+    fn bake_impostors(&self) {
+        // also baked on creation, before the state is applied for a draw
+        unsafe { Self::RENDER_STATE.apply() };
+
+        // the impostors are only drawn while the field is still, and seen
+        // from the middle
+        self.set_ambient(0);
+        (self.round_rect_shader).set(self.u_parallax, Vec2::ZERO);
+
+        let atlas = &self.impostors.atlas;
+        let chunks = self.impostors.chunks;
+
+        bind_framebuffer(Some(atlas));
+        clear([0.0; 4]);
+
+        // keep the atlas premultiplied so that it composites like the quads would
+        set_blend_func_separate(
+            gl::SRC_ALPHA,
+            gl::ONE_MINUS_SRC_ALPHA,
+            gl::ONE,
+            gl::ONE_MINUS_SRC_ALPHA,
+        );
+        for cy in 0..chunks.y {
+            for cx in 0..chunks.x {
+                // chunk rows go down in the world but up in the texture
+                let texel = uvec2(cx, chunks.y - 1 - cy) * CHUNK_TEXELS;
+                set_viewport_rect(texel, UVec2::splat(CHUNK_TEXELS));
+
+                let (min, max) = Impostors::chunk_bounds(uvec2(cx, cy), self.area_width);
+                let matrix = Mat4::orthographic_lh(min.x, max.x, max.y, min.y, -1.0, 1.0);
+                // chunks are baked at the scale impostors start being drawn at
+                self.set_matrix(&matrix, LOD_SCALE);
+
+                // quads and their shadows overlap their cell, so include the
+                // neighbors of the chunk
+                let cells = |c: u32| (c * CHUNK_QUADS).saturating_sub(1)..=(c + 1) * CHUNK_QUADS;
+                if self.shadows {
+                    self.shadow_shader.bind();
+                    self.draw_quads(&self.shadow_vao, cells(cx), cells(cy));
+                }
+                self.round_rect_shader.bind();
+                self.draw_quads(&self.vao, cells(cx), cells(cy));
+            }
+        }
+
+        set_blend_func(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        bind_framebuffer(None);
+        set_viewport(self.viewport.as_uvec2());
+        self.set_matrix(&self.matrix, self.pixels_per_unit);
+
+        generate_mipmaps(atlas.texture);
+    }
+
+    /// Sets the matrix of both the quads and their shadows, and how many
+    /// pixels a world unit covers with it.
+    fn set_matrix(&self, matrix: &Mat4, pixels_per_unit: f32) {
+        self.shadow_shader.set(self.u_mvp_shadow, *matrix);
+        self.round_rect_shader.set(self.u_mvp_quad, *matrix);
+        (self.round_rect_shader).set(self.u_pixels_per_unit, pixels_per_unit);
+    }
+
+    /// Sets the ambient animation of both the quads and their shadows, as
+    /// the index of its variant in [`AMBIENTS`].
+    fn set_ambient(&self, ambient: usize) {
+        (self.ambient_shadow).set(&self.shadow_shader, ambient, self.time);
+        (self.ambient_quad).set(&self.round_rect_shader, ambient, self.time);
+    }
+
+    /// Draws the quads of a rectangle of the grid row by row, through `vao`
+    /// with the current program.
+    fn draw_quads(&self, vao: &VertexArray, xs: RangeInclusive<u32>, ys: RangeInclusive<u32>) {
+        let aw = self.area_width;
+        let last = N_QUADS as u32 - 1;
+
+        // 6 indices per quad
+        let mut rows = Vec::new();
+        for y in ys {
+            let i_beg = y * aw + (*xs.start()).min(aw - 1);
+            let i_end = (y * aw + (*xs.end()).min(aw - 1)).min(last);
+            if i_beg > i_end {
+                continue;
+            }
+
+            rows.push(i_beg as usize * 6..(i_end as usize + 1) * 6);
+        }
+
+        bind_texture(0, self.input_texture.id());
+        vao.multi_draw_elements(gl::TRIANGLES, &self.ebo, &rows);
+    }
+
+    /// Index of the topmost quad under `pos`, in world space.
+    fn pick(&self, pos: Vec2) -> Option<u32> {
+        // quads only ever overlap the cells next to theirs
+        let (cx, cy) = Quad::closest_grid_idx_from_pos(pos, self.area_width);
+        let cells = |c: u32| c.saturating_sub(1)..=(c + 1).min(self.area_width - 1);
 
-            let win_size = window.inner_size();
-            let viewport = Vec2::new(win_size.width as f32, win_size.height as f32);
+        (cells(cy).flat_map(|y| cells(cx).map(move |x| y * self.area_width + x)))
+            .filter(|&i| (self.quads.get(i as usize)).is_some_and(|quad| quad.contains(pos)))
+            // later quads are drawn on top
+            .max()
+    }
 
-            Self {
-                matrix: Mat4::default(),
-                viewport,
+    /// Scales the quad under the mouse up, and the one that was back down.
+    fn hover(&mut self, hovered: Option<u32>) {
+        if hovered == self.hovered {
+            return;
+        }
 
-                round_rect_shader,
-                vao,
-                vbo,
-                ebo,
+        if let Some(i) = self.hovered {
+            let current = HOVER_SCALE;
+            (self.hover_scales).animate_to(i, current, 1.0, HOVER_OUT, Easing::QuadOut);
+        }
+        if let Some(i) = hovered {
+            (self.hover_scales).animate_to(i, 1.0, HOVER_SCALE, HOVER_IN, Easing::BackOut);
+        }
+        self.hovered = hovered;
+    }
 
-                u_mvp_quad,
+    /// Intensity of quads far from the mouse. Spinning quads light up, so
+    /// still ones are dimmed, except data which shows its true colors.
+    fn rest_intensity(&self) -> f32 {
+        match self.data {
+            DataMode::Off => 0.5,
+            _ => 1.0,
+        }
+    }
 
-                quads,
-                vertices,
-                indices,
+    /// Intensity and scale of quad `i` at `position` this frame.
+    fn animation(&self, i: u32, position: Vec2, mouse_pos: Vec2) -> (f32, f32) {
+        let spin = match self.data {
+            DataMode::Off => spin(position.distance(mouse_pos)),
+            _ => 0.0,
+        };
+        let mut intensity = 2.0 * spin + self.rest_intensity();
+        let mut scale = match self.hover_scales.value(&i) {
+            Some(scale) => scale,
+            None if self.hovered == Some(i) => HOVER_SCALE,
+            None => 1.0,
+        };
+
+        for (&center, radius) in self.ripples.iter() {
+            let distance = self.quads[center as usize].position.distance(position);
+            // the ring fades out as it slows down
+            let strength = 1.0 - radius / RIPPLE_RADIUS;
+            let wave = ((distance - radius) / RIPPLE_WIDTH).powi(2);
+            let bump = strength * (-wave).exp();
+
+            intensity += bump;
+            scale += bump * RIPPLE_SCALE;
+        }
 
-                area_width,
+        (intensity, scale)
+    }
 
-                last_instant: Instant::now(),
+    fn update_vertices(&mut self, x_beg: u32, x_end: u32, y_beg: u32, y_end: u32) {
+        for y in y_beg..=y_end {
+            let i_beg = (y * self.area_width + x_beg) as usize;
+            let i_end = (y * self.area_width + x_end) as usize;
+
+            self.heatmap.record_row(y, x_beg..=x_end);
+            (self.vbo).update_at(i_beg * 4, self.vertices[i_beg..=i_end].as_flattened());
+        }
+    }
+
+    /// Draws every quad, or with `live_chunks`, impostors for every chunk
+    /// except those which are drawn as quads.
+    fn draw_with_clear_color(
+        &self,
+        clear_color: [f32; 4],
+        live_chunks: Option<(RangeInclusive<u32>, RangeInclusive<u32>)>,
+    ) {
+        bind_framebuffer(None);
+        clear(clear_color);
+
+        let Some((cxs, cys)) = live_chunks else {
+            bind_texture(0, self.input_texture.id());
+            if self.shadows {
+                self.shadow_shader.bind();
+                self.shadow_vao.draw_elements(gl::TRIANGLES, &self.ebo);
             }
+            self.round_rect_shader.bind();
+            self.vao.draw_elements(gl::TRIANGLES, &self.ebo);
+            return;
+        };
+
+        self.impostors.draw(&self.matrix, (&cxs, &cys));
+
+        let cells =
+            |cs: &RangeInclusive<u32>| cs.start() * CHUNK_QUADS..=(cs.end() + 1) * CHUNK_QUADS - 1;
+        if self.shadows {
+            self.shadow_shader.bind();
+            self.draw_quads(&self.shadow_vao, cells(&cxs), cells(&cys));
         }
+        self.round_rect_shader.bind();
+        self.draw_quads(&self.vao, cells(&cxs), cells(&cys));
+    }
+}
+
+impl Scene for RoundQuadsScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
     }
 
-    pub fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) {
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        let (name, steps) = match keycode {
+            Key::Character(ch) => match ch.as_str() {
+                "i" | "I" => ("impostors", 1),
+                "h" | "H" => ("heatmap", 1),
+                "m" | "M" => ("mosaic", 1),
+                "s" | "S" => ("shadows", 1),
+                "g" => ("fill", 1),
+                "G" => ("fill", -1),
+                "p" => ("palette", 1),
+                "P" => ("palette", -1),
+                "a" => ("ambient", 1),
+                "A" => ("ambient", -1),
+                "d" => ("data", 1),
+                "D" => ("data", -1),
+                _ => return,
+            },
+            _ => return,
+        };
+
+        self.params.step(name, steps).unwrap();
+        println!("round quads config: {}", self.params);
+    }
+
+    /// Sends a ripple through the field from the quad under the mouse, if
+    /// the mouse didn't move since the button was pressed. The camera pans
+    /// with every button, so drags are left to it.
+    fn on_event(&mut self, camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if button != MouseButton::Left {
+            return false;
+        }
+
+        match state {
+            ElementState::Pressed => self.pressed_at = Some(mouse_pos),
+            ElementState::Released => {
+                let pressed_at = self.pressed_at.take();
+                let clicked = pressed_at.is_some_and(|at| at.distance(mouse_pos) < CLICK_PIXELS);
+                let picked = self.pick(camera.pointer_to_pos(mouse_pos, self.viewport));
+                if let (true, Some(i)) = (clicked, picked) {
+                    (self.ripples).start(
+                        i,
+                        Tween::new(0.0, RIPPLE_RADIUS, RIPPLE_TIME, Easing::QuadOut),
+                    );
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Shifts the quads by `parallax` per unit of elevation from the next
+    /// draw on, for one eye in stereo. Their shadows lie on the field and
+    /// stay where they are.
+    fn set_parallax(&mut self, parallax: Vec2) {
+        self.parallax = parallax;
+    }
+
+    /// Advances the hover and ripple animations by a fixed step.
+    fn update(&mut self, dt: f32) {
+        self.time += dt;
+        self.hover_scales.update(dt);
+        self.hover_scales.remove_finished();
+        self.ripples.update(dt);
+        self.ripples.remove_finished();
+    }
+
+    /// What the data under the quad under the mouse is, in data mode.
+    fn tooltip(&self) -> Option<String> {
+        let i = self.hovered?;
+        let cell = uvec2(i % self.area_width, i / self.area_width);
+        let (min, max) = self.blocks()?.region(cell)?;
+
+        match self.data {
+            DataMode::Off => None,
+            DataMode::Image => {
+                let [r, g, b, _] = self.quads[i as usize].fill_color.to_le_bytes();
+                Some(format!(
+                    "x {}–{}, y {}–{}: #{r:02x}{g:02x}{b:02x}",
+                    min.x,
+                    max.x - 1,
+                    min.y,
+                    max.y - 1
+                ))
+            }
+            DataMode::Csv => {
+                let value = block_mean(self.csv.as_ref()?, min, max);
+                let value = value.map_or("no value".to_string(), |value| value.to_string());
+                // 1-based, like spreadsheets
+                Some(if max - min == UVec2::ONE {
+                    format!("row {}, column {}: {value}", min.y + 1, min.x + 1)
+                } else {
+                    format!(
+                        "rows {}–{}, columns {}–{}: mean {value}",
+                        min.y + 1,
+                        max.y,
+                        min.x + 1,
+                        max.x
+                    )
+                })
+            }
+        }
+    }
+
+    /// How much gets uploaded, while the heatmap shows where.
+    fn status(&self) -> Option<String> {
+        if !self.params.bool("heatmap") {
+            return None;
+        }
+        Some(match self.heatmap.rates {
+            Some((per_second, per_frame)) => format!(
+                "uploads: {}/s, {}/frame",
+                format_bytes(per_second),
+                format_bytes(per_frame)
+            ),
+            None => "uploads: measuring…".to_string(),
+        })
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("round quads draw");
+
         let dt = self.last_instant.elapsed().as_secs_f32();
         self.last_instant = Instant::now();
 
-        // rotate surroundings of mouse
+        let mosaic = self.params.bool("mosaic");
+        let fill = Fill::from_variant(self.params.variant("fill"));
+        if (mosaic, fill) != (self.mosaic, self.fill) {
+            self.restyle(mosaic, fill);
+        }
+
+        let palette = Palette::from_variant(self.params.variant("palette"));
+        if palette != self.palette {
+            self.recolor(palette);
+        }
+
+        let data = DataMode::from_variant(self.params.variant("data"));
+        if data != self.data {
+            self.show_data(data);
+        }
+
+        let shadows = self.params.bool("shadows");
+        if shadows != self.shadows {
+            self.shadows = shadows;
+            self.bake_impostors();
+        }
+
         let mouse_pos = camera.pointer_to_pos(mouse_pos, self.viewport);
-        let surround_radius = 320.0;
-        let surround_area = Vec2::splat(surround_radius);
+        self.hover(self.pick(mouse_pos));
 
-        let aw = self.area_width;
-        let (x_beg, y_beg) = Quad::closest_grid_idx_from_pos(mouse_pos - surround_area, aw);
-        let (x_end, y_end) = Quad::closest_grid_idx_from_pos(mouse_pos + surround_area, aw);
+        // rotate surroundings of mouse, unless they show data to be read
+        let spun = Quad::grid_rect(mouse_pos, SPIN_RADIUS, self.area_width);
+        let (x_beg, x_end, y_beg, y_end) = spun;
+        if self.data == DataMode::Off {
+            for y in y_beg..=y_end {
+                for x in x_beg..=x_end {
+                    let i = (y * self.area_width + x) as usize;
 
-        for y in y_beg..=y_end {
-            for x in x_beg..=x_end {
-                let i = (y * self.area_width + x) as usize;
+                    if let Some(quad) = self.quads.get_mut(i) {
+                        let spin = spin(quad.position.distance(mouse_pos));
+                        quad.rotation += (dt * PI) * 2.0 * spin;
+                    }
+                }
+            }
+        }
 
-                if let Some(quad) = self.quads.get_mut(i) {
-                    let distance = Vec2::distance(quad.position, mouse_pos);
-                    let intensity = (surround_radius - distance).max(0.0) / surround_radius;
+        // everything animated this frame: the quads around the mouse, the
+        // rings of the ripples, and the quads scaling up or back down
+        let mut animated = vec![spun];
+        for (&i, radius) in self.ripples.iter() {
+            let center = self.quads[i as usize].position;
+            let reach = radius + RIPPLE_WIDTH * 2.0;
+            animated.push(Quad::grid_rect(center, reach, self.area_width));
+        }
+        for i in self.hovered.iter().chain(self.hover_scales.keys()) {
+            let (x, y) = (i % self.area_width, i / self.area_width);
+            animated.push((x, x, y, y));
+        }
 
-                    quad.rotation += (dt * PI) * 2.0 * intensity;
-                    self.vertices[i] = quad.vertices(2.0 * intensity + 0.5);
+        for &(x_beg, x_end, y_beg, y_end) in &animated {
+            for y in y_beg..=y_end {
+                for x in x_beg..=x_end {
+                    let i = y * self.area_width + x;
+                    if let Some(quad) = self.quads.get(i as usize) {
+                        let (intensity, scale) = self.animation(i, quad.position, mouse_pos);
+                        self.vertices[i as usize] = quad.vertices(intensity, scale);
+                    }
                 }
             }
+            self.update_vertices(x_beg, x_end, y_beg, y_end);
         }
 
-        self.update_vertices(x_beg, x_end, y_beg, y_end);
+        // the ambient animations move the whole field, so the baked
+        // impostors would show it frozen
+        let ambient = self.params.variant("ambient");
+        self.set_ambient(ambient);
+        self.round_rect_shader.set(self.u_parallax, self.parallax);
+
+        // chunks of animated quads stay real quads so that they can move
+        let use_impostors =
+            self.params.bool("impostors") && camera.scale.x < LOD_SCALE && ambient == 0;
+        let live_chunks = use_impostors.then(|| {
+            let (x_beg, x_end, y_beg, y_end) = (animated.iter()).fold(animated[0], |a, b| {
+                (a.0.min(b.0), a.1.max(b.1), a.2.min(b.2), a.3.max(b.3))
+            });
+            (
+                x_beg / CHUNK_QUADS..=x_end / CHUNK_QUADS,
+                y_beg / CHUNK_QUADS..=y_end / CHUNK_QUADS,
+            )
+        });
+
+        self.draw_with_clear_color(theme::current().clear, live_chunks);
+
+        if self.params.bool("heatmap") {
+            self.heatmap.draw(&self.impostors, &self.matrix);
+        }
+        self.heatmap.end_frame();
 
-        self.draw_with_clear_color(0.0, 0.0, 0.0, 0.5);
+        // reset intensity and scale
+        let intensity = self.rest_intensity();
+        for (x_beg, x_end, y_beg, y_end) in animated {
+            for y in y_beg..=y_end {
+                for x in x_beg..=x_end {
+                    let i = (y * self.area_width + x) as usize;
 
-        // reset intensity
-        for y in y_beg..=y_end {
-            for x in x_beg..=x_end {
-                let i = (y * self.area_width + x) as usize;
+                    if let Some(quad) = self.quads.get(i) {
+                        self.vertices[i] = quad.vertices(intensity, 1.0);
+                    }
+                }
+            }
+
+            // reset vertices (otherwise artifacts appear if the mouse moves too quickly)
+            self.update_vertices(x_beg, x_end, y_beg, y_end);
+        }
+        Ok(())
+    }
 
-                if let Some(quad) = self.quads.get_mut(i) {
-                    self.vertices[i] = quad.vertices(0.5);
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        self.viewport = Vec2::new(width as f32, height as f32);
+        set_viewport(self.viewport.as_uvec2());
+        self.matrix = camera.matrix(self.viewport);
+        self.pixels_per_unit = camera.scale.x;
+        self.set_matrix(&self.matrix, self.pixels_per_unit);
+        Ok(())
+    }
+}
+
+/// Uniforms of the ambient animations in one of the round-rect vertex shaders.
+#[derive(Debug, Clone, Copy)]
+struct AmbientUniforms {
+    u_time: Uniform,
+    u_ambient: Uniform,
+}
+
+impl AmbientUniforms {
+    /// Locates the uniforms of `shader` and sets `u_field`, half the size of
+    /// the field.
+    fn new(shader: &Program, field: Vec2) -> Self {
+        shader.set(shader.uniform(c"u_field"), field);
+
+        Self {
+            u_time: shader.uniform(c"u_time"),
+            u_ambient: shader.uniform(c"u_ambient"),
+        }
+    }
+
+    /// Sets the uniforms of `shader`, which they were located in.
+    fn set(&self, shader: &Program, ambient: usize, time: f32) {
+        shader.set(self.u_ambient, ambient as i32);
+        shader.set(self.u_time, time);
+    }
+}
+
+/// How fast a quad `distance` away from the mouse spins, from 0 to 1.
+fn spin(distance: f32) -> f32 {
+    (SPIN_RADIUS - distance).max(0.0) / SPIN_RADIUS
+}
+
+/// Chunks of quads pre-rendered into an atlas, drawn instead of the quads
+/// themselves when these get too small on screen to be worth their cost.
+///
+/// The atlas is laid out like the grid, so its mipmaps filter across chunks
+/// correctly.
+struct Impostors {
+    shader: Program,
+    u_mvp: Uniform,
+    vao: VertexArray,
+    // kept alive for the vertex array
+    _vbo: Buffer<ImpostorVertex>,
+    atlas: RenderTarget,
+    /// Number of chunks on each axis.
+    chunks: UVec2,
+}
+
+impl Impostors {
+    fn new(area_width: u32) -> Result<Self, GlError> {
+        let rows = (N_QUADS as u32).div_ceil(area_width);
+        let chunks = uvec2(area_width, rows).map(|n| n.div_ceil(CHUNK_QUADS));
+        let atlas = RenderTarget::new("impostor atlas", chunks * CHUNK_TEXELS)?;
+
+        let mut vertices = Vec::with_capacity((chunks.x * chunks.y) as usize);
+        for cy in 0..chunks.y {
+            for cx in 0..chunks.x {
+                let (min, max) = Self::chunk_bounds(uvec2(cx, cy), area_width);
+                let uv_min = uvec2(cx, cy).as_vec2() / chunks.as_vec2();
+                let uv_max = uvec2(cx + 1, cy + 1).as_vec2() / chunks.as_vec2();
+
+                // v is flipped, see bake_impostors
+                #[rustfmt::skip]
+                let corners = [
+                    ImpostorVertex { position: vec2(min.x, min.y), uv: vec2(uv_min.x, 1.0 - uv_min.y) },
+                    ImpostorVertex { position: vec2(max.x, min.y), uv: vec2(uv_max.x, 1.0 - uv_min.y) },
+                    ImpostorVertex { position: vec2(max.x, max.y), uv: vec2(uv_max.x, 1.0 - uv_max.y) },
+                    ImpostorVertex { position: vec2(min.x, max.y), uv: vec2(uv_min.x, 1.0 - uv_max.y) },
+                ];
+                vertices.push([0, 1, 2, 0, 2, 3].map(|i| corners[i]));
+            }
+        }
+
+        let shader = Program::new(SRC_VERT_QUAD, SRC_FRAG_TEXTURE)?;
+        let u_mvp = shader.uniform(c"u_mvp");
+
+        let vbo = Buffer::new(gl::ARRAY_BUFFER, vertices.as_flattened(), gl::STATIC_DRAW);
+        let mut vao = VertexArray::new();
+        vao.set_layout(&shader, &vbo, ImpostorVertex::LAYOUT);
+
+        Ok(Self {
+            shader,
+            u_mvp,
+            vao,
+            _vbo: vbo,
+            atlas,
+            chunks,
+        })
+    }
+
+    /// World-space rectangle covered by a chunk.
+    fn chunk_bounds(chunk: UVec2, area_width: u32) -> (Vec2, Vec2) {
+        let origin = Quad::pos_from_grid_idx((0, 0), area_width) - CELL_SIZE * 0.5;
+        let min = origin + chunk.as_vec2() * CHUNK_QUADS as f32 * CELL_SIZE;
+        (min, min + CHUNK_QUADS as f32 * CELL_SIZE)
+    }
+
+    /// Draws every chunk but the `skipped` ones.
+    fn draw(&self, matrix: &Mat4, skipped: (&RangeInclusive<u32>, &RangeInclusive<u32>)) {
+        // every chunk has an impostor of 6 vertices
+        let mut chunks = Vec::new();
+        for cy in 0..self.chunks.y {
+            for cx in 0..self.chunks.x {
+                if !(skipped.0.contains(&cx) && skipped.1.contains(&cy)) {
+                    let first = (cy * self.chunks.x + cx) as usize * 6;
+                    chunks.push(first..first + 6);
                 }
             }
         }
 
-        // reset vertices (otherwise artifacts appear if the mouse moves too quickly)
-        self.update_vertices(x_beg, x_end, y_beg, y_end);
+        self.shader.set(self.u_mvp, *matrix);
+        bind_texture(0, self.atlas.texture);
+
+        // the atlas is premultiplied
+        set_blend_func(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+        self.vao.multi_draw_arrays(gl::TRIANGLES, &chunks);
+        set_blend_func(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+    }
+}
+
+/// Debug view of the vertex data re-uploaded every frame, tinting each chunk
+/// by how many of its bytes were sent to the GPU.
+struct UploadHeatmap {
+    shader: Program,
+    u_mvp: Uniform,
+    u_color: Uniform,
+    chunks: UVec2,
+    /// Bytes uploaded per chunk since the last frame.
+    bytes: Vec<usize>,
+
+    window_start: Instant,
+    window_bytes: usize,
+    window_frames: u32,
+    /// Bytes uploaded per second and per frame over the last full second.
+    rates: Option<(usize, usize)>,
+}
+
+impl UploadHeatmap {
+    /// Bytes of a chunk whose every quad got uploaded once.
+    const FULL_CHUNK: usize = (CHUNK_QUADS * CHUNK_QUADS) as usize * mem::size_of::<[Vertex; 4]>();
+
+    fn new(chunks: UVec2) -> Result<Self, GlError> {
+        let shader = Program::new(SRC_VERT_QUAD, SRC_FRAG_HEATMAP)?;
+
+        Ok(Self {
+            u_mvp: shader.uniform(c"u_mvp"),
+            u_color: shader.uniform(c"u_color"),
+            shader,
+            chunks,
+            bytes: vec![0; (chunks.x * chunks.y) as usize],
+
+            window_start: Instant::now(),
+            window_bytes: 0,
+            window_frames: 0,
+            rates: None,
+        })
     }
 
-    fn update_vertices(&mut self, x_beg: u32, x_end: u32, y_beg: u32, y_end: u32) {
-        unsafe {
-            gl::BindVertexArray(self.vao);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+    /// Records the upload of the quads `xs` of grid row `y`.
+    fn record_row(&mut self, y: u32, xs: RangeInclusive<u32>) {
+        let (x_beg, x_end) = xs.into_inner();
+        let row = (y / CHUNK_QUADS * self.chunks.x) as usize;
 
-            for y in y_beg..=y_end {
-                let i_beg = (y * self.area_width + x_beg) as usize;
-                let i_end = (y * self.area_width + x_end) as usize;
-
-                gl::BufferSubData(
-                    gl::ARRAY_BUFFER,
-                    mem::size_of_val(&self.vertices[..i_beg]) as GLsizeiptr,
-                    mem::size_of_val(&self.vertices[i_beg..=i_end]) as GLsizeiptr,
-                    self.vertices[i_beg..=i_end].as_ptr() as *const _,
-                );
+        for cx in x_beg / CHUNK_QUADS..=x_end / CHUNK_QUADS {
+            let quads = x_end.min((cx + 1) * CHUNK_QUADS - 1) - x_beg.max(cx * CHUNK_QUADS) + 1;
+            let bytes = quads as usize * mem::size_of::<[Vertex; 4]>();
+
+            if let Some(chunk) = self.bytes.get_mut(row + cx as usize) {
+                *chunk += bytes;
             }
+            self.window_bytes += bytes;
+        }
+    }
+
+    /// Tints the chunks the impostors cover. Uses the current blending.
+    fn draw(&self, impostors: &Impostors, matrix: &Mat4) {
+        self.shader.set(self.u_mvp, *matrix);
+
+        for (i, &bytes) in self.bytes.iter().enumerate().filter(|(_, &b)| b > 0) {
+            // blue for a few quads, red for the whole chunk
+            let heat = (bytes as f32 / Self::FULL_CHUNK as f32).min(1.0).sqrt();
+            self.shader.set(self.u_color, [heat, 0.2, 1.0 - heat, 0.45]);
+            // every chunk has an impostor of 6 vertices
+            (impostors.vao).draw_arrays_range(gl::TRIANGLES, i * 6..i * 6 + 6);
         }
     }
 
-    fn draw_with_clear_color(&self, r: GLfloat, g: GLfloat, b: GLfloat, a: GLfloat) {
-        unsafe {
-            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    /// Clears the per-chunk counts, updating the rolling total every second.
+    fn end_frame(&mut self) {
+        self.bytes.fill(0);
+        self.window_frames += 1;
+
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.rates = Some((
+                (self.window_bytes as f32 / elapsed.as_secs_f32()) as usize,
+                self.window_bytes / self.window_frames as usize,
+            ));
+
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
+            self.window_frames = 0;
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ImpostorVertex {
+    position: Vec2,
+    uv: Vec2,
+}
 
-            gl::BindVertexArray(self.vao);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+impl ImpostorVertex {
+    const LAYOUT: &[Attrib] = &[
+        Attrib {
+            name: c"position",
+            components: 2,
+            offset: 0,
+        },
+        Attrib {
+            name: c"uv",
+            components: 2,
+            offset: mem::size_of::<Vec2>(),
+        },
+    ];
+}
 
-            gl::ClearColor(r, g, b, a);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+/// What the quads show instead of random shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataMode {
+    Off,
+    /// The average color of a block of pixels of the input image per quad,
+    /// brighter ones bigger.
+    Image,
+    /// A block of values of the CSV table per quad, as a heatmap, larger
+    /// values bigger.
+    Csv,
+}
 
-            gl::UseProgram(self.round_rect_shader);
-            gl::DrawElements(
-                gl::TRIANGLES,
-                mem::size_of_val(self.indices.as_slice()) as GLsizei,
-                gl::UNSIGNED_INT,
-                std::ptr::null(),
-            );
+impl DataMode {
+    fn from_variant(variant: usize) -> Self {
+        match variant {
+            1 => Self::Image,
+            2 => Self::Csv,
+            _ => Self::Off,
         }
     }
+}
 
-    pub fn resize(&mut self, camera: &Camera, width: i32, height: i32) {
-        unsafe {
-            gl::Viewport(0, 0, width, height);
+/// How a source of pixels or values maps onto the grid: one quad per square
+/// block of them, the quads centered in the grid.
+#[derive(Debug, Clone, Copy)]
+struct Blocks {
+    /// Side of a block.
+    block: u32,
+    /// Size of the source.
+    size: UVec2,
+    /// Cell of the grid showing the first block.
+    offset: UVec2,
+}
 
-            self.viewport = Vec2::new(width as f32, height as f32);
-            self.matrix = camera.matrix(self.viewport);
+impl Blocks {
+    fn fit(size: UVec2, area_width: u32) -> Self {
+        let block = size.max_element().div_ceil(area_width).max(1);
+        let cells = (size + block - 1) / block;
+        Self {
+            block,
+            size,
+            offset: (UVec2::splat(area_width) - cells) / 2,
+        }
+    }
+
+    /// Region of the source under cell `cell` of the grid, as `(min, max)`
+    /// with `max` excluded.
+    fn region(&self, cell: UVec2) -> Option<(UVec2, UVec2)> {
+        if cell.cmplt(self.offset).any() {
+            return None;
+        }
 
-            gl::UseProgram(self.round_rect_shader);
-            gl::UniformMatrix4fv(self.u_mvp_quad, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+        let min = (cell - self.offset) * self.block;
+        if min.cmpge(self.size).any() {
+            return None;
         }
+        Some((min, (min + self.block).min(self.size)))
     }
 }
 
-impl Drop for RoundQuadsScene {
-    fn drop(&mut self) {
-        unsafe {
-            gl::DeleteProgram(self.round_rect_shader);
-            gl::DeleteVertexArrays(1, &self.vao);
+/// Mean of the values of `csv` in a region, ignoring missing ones.
+fn block_mean(csv: &DataGrid, min: UVec2, max: UVec2) -> Option<f32> {
+    let values = (min.y..max.y)
+        .flat_map(|y| (min.x..max.x).filter_map(move |x| csv.get(x, y)))
+        .collect::<Vec<_>>();
+    (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+}
+
+/// How a quad blends its fill color into its gradient color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fill {
+    Flat,
+    /// Along the gradient direction, across the whole quad.
+    Linear,
+    /// Away from the gradient center, up to the farthest corner.
+    Radial,
+}
 
-            let buffers = &[self.vbo, self.ebo];
-            gl::DeleteBuffers(1, buffers.as_ptr());
+impl Fill {
+    fn from_variant(variant: usize) -> Self {
+        match variant {
+            1 => Self::Linear,
+            2 => Self::Radial,
+            _ => Self::Flat,
         }
     }
 }
@@ -266,6 +1186,18 @@ struct Quad {
     pub border_width: f32,
     pub fill_color: u32,
     pub stroke_color: u32,
+    /// Color the fill blends into, unless the fill is flat.
+    pub gradient_color: u32,
+    /// Direction of linear gradients, in the space of the quad.
+    pub gradient_direction: Vec2,
+    /// Center of radial gradients, from -0.5 to 0.5 across the quad.
+    pub gradient_center: Vec2,
+    pub fill: Fill,
+    /// Height above the field, for the shadow.
+    pub elevation: f32,
+    /// Region of the input image the quad shows in mosaic mode.
+    pub atlas_uv: Vec4,
+    pub texture_mix: f32,
 }
 
 impl Quad {
@@ -274,43 +1206,91 @@ impl Quad {
     }
 
     fn pos_from_grid_idx((x, y): (u32, u32), area_width: u32) -> Vec2 {
-        (vec2(x as f32, y as f32) - area_width as f32 * 0.5) * 16.0
+        (vec2(x as f32, y as f32) - area_width as f32 * 0.5) * CELL_SIZE
     }
 
     fn closest_grid_idx_from_pos(pos: Vec2, area_width: u32) -> (u32, u32) {
         let width = area_width as f32;
         let upper_limit = width - 1.0;
 
-        let pos = pos / 16.0 + width * 0.5;
+        let pos = pos / CELL_SIZE + width * 0.5;
         (
             pos.x.round().clamp(0.0, upper_limit) as u32,
             pos.y.round().clamp(0.0, upper_limit) as u32,
         )
     }
 
-    fn random(rng: &mut impl Rng, i: u32, area_width: u32) -> Self {
+    /// Region of the image under a quad of `size` in cell `i`, the grid
+    /// covering the centered part of the image of relative size `crop`.
+    fn tile_uv(i: u32, area_width: u32, size: Vec2, crop: Vec2) -> Vec4 {
+        let rows = (N_QUADS as u32).div_ceil(area_width);
+        let cells = uvec2(area_width, rows).as_vec2();
+        let cell = uvec2(i % area_width, i / area_width).as_vec2();
+
+        let center = (cell + 0.5) / cells;
+        let half = size / CELL_SIZE / cells * 0.5;
+        let to_image = |uv: Vec2| (uv - 0.5) * crop + 0.5;
+        Vec4::from((to_image(center - half), to_image(center + half)))
+    }
+
+    fn random(rng: &mut impl Rng, swatch: Swatch, i: u32, area_width: u32, crop: Vec2) -> Self {
+        let size = vec2(rng.gen_range(10.0..=20.0), rng.gen_range(10.0..=20.0));
+
         Self {
             position: Self::pos_from_idx(i, area_width),
-            size: vec2(rng.gen_range(10.0..=20.0), rng.gen_range(10.0..=20.0)),
+            size,
             rotation: rng.gen_range(0.0..TAU),
             border_radius: rng.gen_range(1.0..=5.0),
             border_width: rng.gen_range(1.0..=5.0),
-            fill_color: u32::from_le_bytes([
-                rng.gen_range(128..=255),
-                rng.gen_range(128..=255),
-                rng.gen_range(128..=255),
-                rng.gen_range(128..=255),
-            ]),
-            stroke_color: u32::from_le_bytes([
-                rng.gen_range(24..=128),
-                rng.gen_range(24..=128),
-                rng.gen_range(24..=128),
-                rng.gen_range(128..=255),
-            ]),
-        }
-    }
-
-    fn vertices(self, intensity: f32) -> [Vertex; 4] {
+            fill_color: swatch.fill,
+            stroke_color: swatch.stroke,
+            gradient_color: swatch.gradient,
+            gradient_direction: Vec2::from_angle(rng.gen_range(0.0..TAU)),
+            gradient_center: vec2(rng.gen_range(-0.3..=0.3), rng.gen_range(-0.3..=0.3)),
+            fill: Fill::Flat,
+            elevation: rng.gen_range(1.0..=6.0),
+            atlas_uv: Self::tile_uv(i, area_width, size, crop),
+            texture_mix: 0.0,
+        }
+    }
+
+    /// Turns the quad into a still square showing a datum of `color`, as
+    /// big as its `magnitude` from 0 to 1.
+    fn show(&mut self, (color, magnitude): (u32, f32)) {
+        self.size = Vec2::splat(CELL_SIZE * (0.35 + 0.6 * magnitude));
+        self.rotation = 0.0;
+        self.border_radius = 3.0;
+        self.border_width = 1.5;
+        self.fill_color = color;
+        self.stroke_color = palette::shade(color, 0.0, 0.5);
+        self.gradient_color = palette::shade(color, 0.0, 0.7);
+    }
+
+    /// Makes the quad disappear, where there is no data.
+    fn hide(&mut self) {
+        self.size = Vec2::ZERO;
+        self.fill_color = 0;
+        self.stroke_color = 0;
+        self.gradient_color = 0;
+    }
+
+    /// Region of the grid around `pos` in world space, up to `radius` away,
+    /// as `(x_beg, x_end, y_beg, y_end)`.
+    fn grid_rect(pos: Vec2, radius: f32, area_width: u32) -> (u32, u32, u32, u32) {
+        let (x_beg, y_beg) = Self::closest_grid_idx_from_pos(pos - radius, area_width);
+        let (x_end, y_end) = Self::closest_grid_idx_from_pos(pos + radius, area_width);
+        (x_beg, x_end, y_beg, y_end)
+    }
+
+    /// Whether `pos` in world space is inside the quad, border included.
+    fn contains(&self, pos: Vec2) -> bool {
+        let local = (pos - self.position).rotate(Vec2::from_angle(-self.rotation));
+        let radius = self.border_radius.min(self.size.min_element() * 0.5);
+        let q = local.abs() - self.size * 0.5 + radius;
+        q.max_element().min(0.0) + q.max(Vec2::ZERO).length() - radius <= 0.0
+    }
+
+    fn vertices(self, intensity: f32, scale: f32) -> [Vertex; 4] {
         let Self {
             position,
             size,
@@ -319,9 +1299,24 @@ impl Quad {
             border_width,
             fill_color,
             stroke_color,
+            gradient_color,
+            gradient_direction,
+            gradient_center,
+            fill,
+            elevation,
+            atlas_uv,
+            texture_mix,
         } = self;
 
+        let (gradient_kind, gradient_vector) = match fill {
+            Fill::Flat => (0.0, Vec2::ZERO),
+            Fill::Linear => (1.0, gradient_direction),
+            Fill::Radial => (2.0, gradient_center),
+        };
+        let color = |color: u32| Vec4::from_array(color.to_le_bytes().map(|n| n as f32)) / 255.0;
+
         let r = vec2(rotation.cos(), rotation.sin());
+        let size = size * scale;
 
         #[rustfmt::skip]
         let pos_dims = [
@@ -334,11 +1329,19 @@ impl Quad {
         pos_dims.map(|position| Vertex {
             position,
             size,
-            fill_color: Vec4::from_array(fill_color.to_le_bytes().map(|n| n as f32)) / 255.0,
-            stroke_color: Vec4::from_array(stroke_color.to_le_bytes().map(|n| n as f32)) / 255.0,
-            border_radius,
-            border_width,
+            fill_color: color(fill_color),
+            stroke_color: color(stroke_color),
+            border_radii: [border_radius * scale; 4],
+            border_width: border_width * scale,
             intensity,
+            atlas_uv: atlas_uv.to_array(),
+            texture_mix,
+            gradient_color: color(gradient_color).to_array(),
+            gradient_vector: gradient_vector.to_array(),
+            gradient_kind,
+            corner_exponent: 2.0,
+            rotation,
+            elevation,
         })
     }
 
@@ -348,14 +1351,111 @@ impl Quad {
     }
 }
 
+/// Vertex of round-rect.vert and round-rect-shadow.vert, each quad having 4
+/// of them.
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
-struct Vertex {
-    position: Vec2,
-    size: Vec2,
-    fill_color: Vec4,
-    stroke_color: Vec4,
-    border_radius: f32,
-    border_width: f32,
-    intensity: f32,
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Vertex {
+    pub position: Vec2,
+    pub size: Vec2,
+    pub fill_color: Vec4,
+    pub stroke_color: Vec4,
+    /// Radii of the top-left, top-right, bottom-right and bottom-left corners.
+    pub border_radii: [f32; 4],
+    pub border_width: f32,
+    pub intensity: f32,
+    /// Region of the bound texture covering the quad, as `(min, max)`. Not
+    /// a `Vec4`, whose alignment would pad it away from its offset.
+    pub atlas_uv: [f32; 4],
+    /// How much the texture replaces the fill color, 0 to ignore it.
+    pub texture_mix: f32,
+    /// Color the fill color blends into.
+    pub gradient_color: [f32; 4],
+    /// Direction of a linear gradient, or center of a radial one.
+    pub gradient_vector: [f32; 2],
+    /// 0 for a flat fill, 1 for a linear gradient, 2 for a radial one.
+    pub gradient_kind: f32,
+    /// Exponent of the superellipses the corners are, 2 for circular corners
+    /// and 4 or so for squircles.
+    pub corner_exponent: f32,
+    /// Rotation of the quad, which the shadow needs to find its center.
+    pub rotation: f32,
+    /// Height of the quad above the field, setting how far its shadow
+    /// spreads and drops.
+    pub elevation: f32,
+}
+
+impl Default for Vertex {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            size: Vec2::ZERO,
+            fill_color: Vec4::ZERO,
+            stroke_color: Vec4::ZERO,
+            border_radii: [0.0; 4],
+            border_width: 0.0,
+            intensity: 0.0,
+            atlas_uv: [0.0; 4],
+            texture_mix: 0.0,
+            gradient_color: [0.0; 4],
+            gradient_vector: [0.0; 2],
+            gradient_kind: 0.0,
+            corner_exponent: 2.0,
+            rotation: 0.0,
+            elevation: 0.0,
+        }
+    }
+}
+
+impl Vertex {
+    /// Every attribute, with its number of floats and offset in floats.
+    #[rustfmt::skip]
+    pub(super) const LAYOUT: [Attrib; 15] = [
+        attrib(c"position",        2,  0),
+        attrib(c"size",            2,  2),
+        attrib(c"fill_color",      4,  4),
+        attrib(c"stroke_color",    4,  8),
+        attrib(c"border_radii",    4, 12),
+        attrib(c"border_width",    1, 16),
+        attrib(c"intensity",       1, 17),
+        attrib(c"atlas_uv",        4, 18),
+        attrib(c"texture_mix",     1, 22),
+        attrib(c"gradient_color",  4, 23),
+        attrib(c"gradient_vector", 2, 27),
+        attrib(c"gradient_kind",   1, 29),
+        attrib(c"corner_exponent", 1, 30),
+        attrib(c"rotation",        1, 31),
+        attrib(c"elevation",       1, 32),
+    ];
+
+    /// Points the attributes of `shader` at the bound array buffer, which
+    /// must hold tightly packed vertices. Attributes the shader doesn't use
+    /// are skipped, so each shader needs its own vertex array.
+    #[cfg(any(feature = "scene-ecs-quads", feature = "scene-shape-inspector"))]
+    pub(super) unsafe fn enable_attributes(shader: gl::types::GLuint) {
+        use gl::types::{GLint, GLsizei};
+
+        let size_vertex = mem::size_of::<Vertex>() as GLsizei;
+
+        for attrib in Self::LAYOUT {
+            let location = gl::GetAttribLocation(shader, attrib.name.as_ptr());
+            let Ok(location) = gl::types::GLuint::try_from(location) else {
+                continue;
+            };
+
+            let size = attrib.components as GLint;
+            let offset = attrib.offset as *const std::ffi::c_void;
+            gl::VertexAttribPointer(location, size, gl::FLOAT, gl::FALSE, size_vertex, offset);
+            gl::EnableVertexAttribArray(location);
+        }
+    }
+}
+
+/// Attribute of `components` floats, `floats` into a [`Vertex`].
+const fn attrib(name: &'static CStr, components: usize, floats: usize) -> Attrib {
+    Attrib {
+        name,
+        components,
+        offset: floats * mem::size_of::<f32>(),
+    }
 }
@@ -0,0 +1,136 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::path::{Path, PathBuf};
+
+use glam::Vec2;
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::SavedState;
+use crate::script::{Frame, Script};
+
+use super::polyline::PolylineRenderer;
+use super::Scenes;
+
+/// Width of the lines drawn by scripts, in pixels.
+const LINE_PIXELS: f32 = 2.0;
+
+/// Runs a script on top of whichever scene is current: its `frame` before the
+/// scene is drawn, its `on_key` before the scene gets the key, and its debug
+/// lines over the scene.
+///
+/// A script stops at its first error, until it is loaded again.
+pub struct ScriptedScene {
+    script: Script,
+    path: PathBuf,
+    /// Seconds since the script was loaded.
+    time: f32,
+    stopped: bool,
+    polylines: PolylineRenderer,
+}
+
+impl ScriptedScene {
+    pub unsafe fn load(path: &Path) -> Result<Self, String> {
+        Ok(Self {
+            script: Script::load(path)?,
+            path: path.to_path_buf(),
+            time: 0.0,
+            stopped: false,
+            polylines: PolylineRenderer::new().map_err(|e| e.to_string())?,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Runs the script's `frame`. Returns the camera if the script moved it.
+    pub fn update(
+        &mut self,
+        dt: f32,
+        scenes: &mut Scenes,
+        camera: &Camera,
+        mouse: Vec2,
+    ) -> Result<Option<Camera>, String> {
+        if self.stopped {
+            return Ok(None);
+        }
+
+        self.time += dt;
+        let frame = Frame {
+            time: self.time,
+            dt,
+            mouse,
+            camera,
+            params: scenes.params_mut(),
+        };
+
+        let result = self.script.frame(frame);
+        self.stop_on_error(result)?;
+        Ok(self.script.take_camera())
+    }
+
+    /// Runs the script's `on_key`. Returns whether the script used the key,
+    /// and the camera if the script moved it.
+    pub fn on_key(
+        &mut self,
+        key: &Key<SmolStr>,
+        scenes: &mut Scenes,
+        camera: &Camera,
+        mouse: Vec2,
+    ) -> Result<(bool, Option<Camera>), String> {
+        if self.stopped {
+            return Ok((false, None));
+        }
+
+        let key = match key {
+            Key::Character(c) => c.to_string(),
+            Key::Named(named) => format!("{named:?}"),
+            _ => return Ok((false, None)),
+        };
+        let frame = Frame {
+            time: self.time,
+            dt: 0.0,
+            mouse,
+            camera,
+            params: scenes.params_mut(),
+        };
+
+        let result = self.script.on_key(&key, frame);
+        let used = self.stop_on_error(result)?;
+        Ok((used, self.script.take_camera()))
+    }
+
+    fn stop_on_error<T>(&mut self, result: Result<T, String>) -> Result<T, String> {
+        result.map_err(|e| {
+            self.stopped = true;
+            format!("{} stopped: {e}", self.path.display())
+        })
+    }
+
+    /// Draws the lines of the last frame over the default framebuffer.
+    pub unsafe fn draw(&mut self, camera: &Camera, viewport: Vec2) {
+        let lines = self.script.take_lines();
+        if lines.is_empty() {
+            return;
+        }
+
+        let width = LINE_PIXELS / camera.scale.x;
+        for line in &lines {
+            self.polylines.push(&line.points, width, line.color);
+        }
+
+        let saved = SavedState::save();
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, viewport.x as i32, viewport.y as i32);
+
+        // Normal blending
+        gl::Enable(gl::BLEND);
+        gl::BlendEquation(gl::FUNC_ADD);
+        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+        self.polylines.draw(&camera.matrix(viewport));
+        saved.restore();
+    }
+}
@@ -0,0 +1,362 @@
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{vec2, Mat4, Vec2, Vec4};
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{buffer_data, create_shader_program, delete_buffers};
+use crate::error::GlError;
+use crate::params::{Params, Value};
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::round_quads::Vertex;
+use super::{Scene, SceneEvent, SRC_FRAG_ROUND_RECT, SRC_VERT_ROUND_RECT};
+
+/// Size of the inspected shape, in world units.
+const SHAPE_SIZE: Vec2 = vec2(480.0, 320.0);
+const SHAPE_FILL: [f32; 4] = [0.55, 0.45, 0.85, 1.0];
+const SHAPE_STROKE: [f32; 4] = [0.25, 0.2, 0.45, 1.0];
+
+/// The sliders from top to bottom, each with the parameter it controls and
+/// its color. Corner sliders share their color with a dot on their corner.
+const SLIDERS: &[(&str, [f32; 4])] = &[
+    ("top-left", [0.95, 0.45, 0.4, 1.0]),
+    ("top-right", [0.45, 0.8, 0.45, 1.0]),
+    ("bottom-right", [0.4, 0.6, 0.95, 1.0]),
+    ("bottom-left", [0.9, 0.75, 0.3, 1.0]),
+    ("exponent", [0.85, 0.85, 0.85, 1.0]),
+    ("border", [0.6, 0.6, 0.6, 1.0]),
+];
+
+/// Distance from the top-right corner of the window to the first slider, in
+/// pixels. The help overlay takes the top-left corner.
+const SLIDER_MARGIN: f32 = 24.0;
+/// Size of the track of a slider, in pixels.
+const SLIDER_SIZE: Vec2 = vec2(240.0, 8.0);
+/// Vertical distance between two sliders, in pixels.
+const SLIDER_SPACING: f32 = 28.0;
+const KNOB_SIZE: f32 = 18.0;
+
+/// Quads drawn at most in one batch: the shape and its corners, or the sliders.
+const MAX_QUADS: usize = 3 * 6;
+
+/// A single large rounded rectangle, with every parameter of its SDF on a
+/// slider: the radius of each corner, the exponent of the superellipses the
+/// corners are, and the border width.
+///
+/// The shape and the sliders are both drawn with round-rect.frag.
+pub struct ShapeInspectorScene {
+    matrix: Mat4,
+    viewport: Vec2,
+    /// Pixels per world unit.
+    pixels_per_unit: f32,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_pixels_per_unit: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    ebo: GLuint,
+
+    /// Index in [`SLIDERS`] of the slider being dragged.
+    dragging: Option<usize>,
+    quads: Vec<[Vertex; 4]>,
+
+    params: Params,
+}
+
+impl ShapeInspectorScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Result<Self, GlError> {
+        let indices = (0..MAX_QUADS as u32)
+            .map(|i| [0, 1, 2, 0, 2, 3].map(|corner| i * 4 + corner))
+            .collect::<Vec<_>>();
+
+        unsafe {
+            let shader = create_shader_program(SRC_VERT_ROUND_RECT, SRC_FRAG_ROUND_RECT)?;
+            let u_mvp = gl::GetUniformLocation(shader, c"u_mvp".as_ptr());
+            let u_pixels_per_unit = gl::GetUniformLocation(shader, c"u_pixels_per_unit".as_ptr());
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+
+            let mut buffers = [0; 2];
+            gl::GenBuffers(2, buffers.as_mut_ptr());
+            let [vbo, ebo] = buffers;
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            Vertex::enable_attributes(shader);
+            buffer_data(
+                gl::ELEMENT_ARRAY_BUFFER,
+                ebo,
+                indices.as_slice(),
+                gl::STATIC_DRAW,
+            );
+            gl::BindVertexArray(0);
+
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+                pixels_per_unit: 1.0,
+
+                shader,
+                u_mvp,
+                u_pixels_per_unit,
+                vao,
+                vbo,
+                ebo,
+
+                dragging: None,
+                quads: Vec::with_capacity(MAX_QUADS),
+
+                params: Self::default_params(),
+            })
+        }
+    }
+
+    fn default_params() -> Params {
+        let max_radius = SHAPE_SIZE.min_element() * 0.5;
+        Params::new()
+            .with_f32("top-left", 48.0, 0.0, max_radius, 8.0)
+            .with_f32("top-right", 96.0, 0.0, max_radius, 8.0)
+            .with_f32("bottom-right", 48.0, 0.0, max_radius, 8.0)
+            .with_f32("bottom-left", 0.0, 0.0, max_radius, 8.0)
+            .with_f32("exponent", 2.0, 0.5, 8.0, 0.25)
+            .with_f32("border", 12.0, 0.0, 48.0, 2.0)
+    }
+
+    /// Rounds every corner as much as it goes, with the exponent of Apple's
+    /// icons, give or take.
+    fn squircle(&mut self) {
+        for &(name, _) in &SLIDERS[..4] {
+            self.params.set_normalized(name, 1.0).unwrap();
+        }
+        self.params.set("exponent", Value::Number(5.0)).unwrap();
+    }
+
+    /// Screen-space rectangle of the track of a slider.
+    fn slider_bounds(&self, i: usize) -> (Vec2, Vec2) {
+        let x = self.viewport.x - SLIDER_MARGIN - SLIDER_SIZE.x;
+        let min = vec2(x, SLIDER_MARGIN + i as f32 * SLIDER_SPACING);
+        (min, min + SLIDER_SIZE)
+    }
+
+    /// Moves the knob of the dragged slider under the mouse.
+    fn drag(&mut self, mouse_pos: Vec2) {
+        let Some(i) = self.dragging else {
+            return;
+        };
+
+        let (min, max) = self.slider_bounds(i);
+        let t = (mouse_pos.x - min.x) / (max.x - min.x);
+        self.params.set_normalized(SLIDERS[i].0, t).unwrap();
+    }
+
+    unsafe fn draw_quads(&self, matrix: &Mat4, pixels_per_unit: f32) {
+        gl::UseProgram(self.shader);
+        gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, matrix.as_ref().as_ptr());
+        gl::Uniform1f(self.u_pixels_per_unit, pixels_per_unit);
+
+        gl::BindVertexArray(self.vao);
+        buffer_data(
+            gl::ARRAY_BUFFER,
+            self.vbo,
+            self.quads.as_slice(),
+            gl::STREAM_DRAW,
+        );
+        gl::DrawElements(
+            gl::TRIANGLES,
+            (self.quads.len() * 6) as GLsizei,
+            gl::UNSIGNED_INT,
+            std::ptr::null(),
+        );
+        gl::BindVertexArray(0);
+    }
+}
+
+impl Scene for ShapeInspectorScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("e") => self.params.step("exponent", 1).unwrap(),
+            Key::Character("E") => self.params.step("exponent", -1).unwrap(),
+            Key::Character("b") => self.params.step("border", 1).unwrap(),
+            Key::Character("B") => self.params.step("border", -1).unwrap(),
+            Key::Character("s" | "S") => self.squircle(),
+            Key::Character("r" | "R") => self.params = Self::default_params(),
+            _ => return,
+        }
+
+        println!("shape inspector config: {}", self.params);
+    }
+
+    /// Grabs the slider under the mouse. Returns whether the click was used,
+    /// in which case the camera shouldn't pan.
+    fn on_event(&mut self, _camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if button != MouseButton::Left {
+            return false;
+        }
+
+        if state == ElementState::Released {
+            if self.dragging.take().is_none() {
+                return false;
+            }
+            println!("shape inspector config: {}", self.params);
+            return true;
+        }
+
+        self.dragging = (0..SLIDERS.len()).find(|&i| {
+            let (min, max) = self.slider_bounds(i);
+            let grab = Vec2::new(0.0, (KNOB_SIZE - SLIDER_SIZE.y) * 0.5);
+            mouse_pos.cmpge(min - grab).all() && mouse_pos.cmple(max + grab).all()
+        });
+        self.drag(mouse_pos);
+        self.dragging.is_some()
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("shape inspector draw");
+
+        self.drag(mouse_pos);
+
+        let radii = std::array::from_fn(|i| self.params.f32(SLIDERS[i].0));
+        let exponent = self.params.f32("exponent");
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            // the shape, with a dot on each corner in the color of its slider
+            self.quads.clear();
+            self.quads.push(rect(
+                Vec2::ZERO,
+                SHAPE_SIZE,
+                radii,
+                exponent,
+                self.params.f32("border"),
+                SHAPE_FILL,
+                SHAPE_STROKE,
+            ));
+            let corners = [
+                vec2(-0.5, -0.5),
+                vec2(0.5, -0.5),
+                vec2(0.5, 0.5),
+                vec2(-0.5, 0.5),
+            ];
+            for (corner, (_, color)) in corners.iter().zip(SLIDERS) {
+                self.quads.push(dot(*corner * SHAPE_SIZE, 12.0, *color));
+            }
+            self.draw_quads(&self.matrix, self.pixels_per_unit);
+
+            // the sliders, in pixels from the top-left corner of the window
+            self.quads.clear();
+            for (i, &(name, color)) in SLIDERS.iter().enumerate() {
+                let (min, max) = self.slider_bounds(i);
+                let t = self
+                    .params
+                    .iter()
+                    .find(|p| p.name == name)
+                    .unwrap()
+                    .normalized();
+                let knob = vec2(min.x + t * (max.x - min.x), (min.y + max.y) * 0.5);
+
+                let track = theme::current().grid;
+                self.quads.push(bar(min, max, track));
+                self.quads.push(bar(min, vec2(knob.x, max.y), color));
+                self.quads.push(dot(knob, KNOB_SIZE, color));
+            }
+            let screen =
+                Mat4::orthographic_lh(0.0, self.viewport.x, self.viewport.y, 0.0, -1.0, 1.0);
+            self.draw_quads(&screen, 1.0);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        self.pixels_per_unit = camera.scale.x;
+        Ok(())
+    }
+}
+
+impl Drop for ShapeInspectorScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo, self.ebo]);
+        }
+    }
+}
+
+/// The 4 vertices of an axis-aligned rounded rectangle.
+fn rect(
+    center: Vec2,
+    size: Vec2,
+    border_radii: [f32; 4],
+    corner_exponent: f32,
+    border_width: f32,
+    fill_color: [f32; 4],
+    stroke_color: [f32; 4],
+) -> [Vertex; 4] {
+    // same corner order as round-rect.vert
+    [
+        vec2(-0.5, -0.5),
+        vec2(-0.5, 0.5),
+        vec2(0.5, 0.5),
+        vec2(0.5, -0.5),
+    ]
+    .map(|corner| Vertex {
+        position: center + corner * size,
+        size,
+        fill_color: Vec4::from(fill_color),
+        stroke_color: Vec4::from(stroke_color),
+        border_radii,
+        border_width,
+        intensity: 1.0,
+        corner_exponent,
+        ..Default::default()
+    })
+}
+
+fn dot(center: Vec2, diameter: f32, color: [f32; 4]) -> [Vertex; 4] {
+    let size = Vec2::splat(diameter);
+    rect(center, size, [diameter * 0.5; 4], 2.0, 0.0, color, color)
+}
+
+fn bar(min: Vec2, max: Vec2, color: [f32; 4]) -> [Vertex; 4] {
+    let size = max - min;
+    rect(
+        (min + max) * 0.5,
+        size,
+        [size.y * 0.5; 4],
+        2.0,
+        0.0,
+        color,
+        color,
+    )
+}
@@ -0,0 +1,472 @@
+use std::f32::consts::TAU;
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, Mat4, UVec2, Vec2, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_float_framebuffer, create_shader_program, delete_buffers,
+    delete_framebuffer, pop_debug_group, push_debug_group, Framebuffer,
+};
+use crate::error::GlError;
+use crate::palette;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::soft_body::{Settings, SoftBodies};
+use crate::theme;
+
+use super::{
+    Scene, SceneEvent, SRC_FRAG_BLUR, SRC_FRAG_SOFT_BODY, SRC_FRAG_SOFT_BODY_SURFACE,
+    SRC_VERT_SCREEN, SRC_VERT_SOFT_BODY,
+};
+
+const N_BLOBS: usize = 14;
+const MAX_BLOBS: usize = 64;
+/// Radii of new blobs, in world units.
+const RADII: (f32, f32) = (25.0, 55.0);
+/// Length of the springs of a ring at rest, in world units.
+const SPRING_LENGTH: f32 = 8.0;
+/// Half the size of the box the blobs are in.
+const HALF_BOX: Vec2 = vec2(440.0, 300.0);
+/// Radius of the finger poking the blobs, in world units.
+const FINGER: f32 = 40.0;
+/// Steps of the simulation per update, which its springs need to stay
+/// stable.
+const SUBSTEPS: usize = 10;
+
+/// Variants of the `gravity` parameter, in order.
+const GRAVITIES: &[&str] = &["down", "off", "up"];
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// Vertex of the triangles filling the blobs.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct FillVertex {
+    position: Vec2,
+    color: Vec3,
+}
+
+/// Squishy blobs in a box, each a ring of points held together by springs
+/// and the pressure of the gas inside (see [`crate::soft_body`]).
+///
+/// Rather than as polygons, the blobs are drawn as metaballs: their polygons
+/// fill a half resolution density texture, which gets blurred and then
+/// thresholded by a full-screen pass. That pass estimates the distance to the
+/// surface from the slope of the density for antialiasing and an outline,
+/// and lights the density as a height field, so blobs running into each other
+/// melt together. The mouse pokes them with the right button.
+pub struct SoftBodyScene {
+    matrix: Mat4,
+    viewport: UVec2,
+
+    bodies: SoftBodies,
+    colors: Vec<Vec3>,
+    rng: StdRng,
+    seed: u64,
+    /// Where the mouse is in world units.
+    mouse_world: Vec2,
+    /// Whether the right button is held, poking the blobs under the mouse.
+    poking: bool,
+
+    fill_shader: GLuint,
+    u_mvp: GLint,
+    blur_shader: GLuint,
+    u_direction: GLint,
+    u_kernel_size: GLint,
+    surface_shader: GLuint,
+    u_threshold: GLint,
+    u_pixels_per_texel: GLint,
+    u_inverse: GLint,
+    u_bounds_min: GLint,
+    u_bounds_max: GLint,
+    u_finger: GLint,
+    u_clear: GLint,
+    u_wall: GLint,
+    fill_vao: GLuint,
+    fill_vbo: GLuint,
+    quad_vao: GLuint,
+    quad_vbo: GLuint,
+
+    /// Created at half the size of the window. The blobs fill the first one,
+    /// which is blurred through the second one and back.
+    density: Option<[Framebuffer; 2]>,
+
+    params: Params,
+}
+
+impl SoftBodyScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        unsafe {
+            let fill_shader = create_shader_program(SRC_VERT_SOFT_BODY, SRC_FRAG_SOFT_BODY)?;
+            let blur_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_BLUR)?;
+            let surface_shader =
+                create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_SOFT_BODY_SURFACE)?;
+            let uniform = |shader: GLuint, name: &std::ffi::CStr| {
+                gl::GetUniformLocation(shader, name.as_ptr())
+            };
+            gl::UseProgram(blur_shader);
+            gl::Uniform1i(uniform(blur_shader, c"u_tex"), 0);
+            gl::UseProgram(surface_shader);
+            gl::Uniform1i(uniform(surface_shader, c"u_density"), 0);
+
+            let mut vaos = [0; 2];
+            gl::GenVertexArrays(2, vaos.as_mut_ptr());
+            let [fill_vao, quad_vao] = vaos;
+            let mut buffers = [0; 2];
+            gl::GenBuffers(2, buffers.as_mut_ptr());
+            let [fill_vbo, quad_vbo] = buffers;
+
+            gl::BindVertexArray(fill_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, fill_vbo);
+            const SIZE_FILL_VERTEX: GLsizei = mem::size_of::<FillVertex>() as GLsizei;
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, SIZE_FILL_VERTEX, 0 as _);
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                1,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                SIZE_FILL_VERTEX,
+                mem::size_of::<Vec2>() as _,
+            );
+            gl::EnableVertexAttribArray(1);
+
+            gl::BindVertexArray(quad_vao);
+            buffer_data(gl::ARRAY_BUFFER, quad_vbo, &vertices, gl::STATIC_DRAW);
+            // both programs share the screen vertex shader, so the locations match
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(surface_shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: UVec2::ZERO,
+
+                bodies: SoftBodies::new(),
+                colors: Vec::new(),
+                rng: StdRng::seed_from_u64(seed),
+                seed,
+                mouse_world: Vec2::ZERO,
+                poking: false,
+
+                u_mvp: uniform(fill_shader, c"u_mvp"),
+                fill_shader,
+                u_direction: uniform(blur_shader, c"u_direction"),
+                u_kernel_size: uniform(blur_shader, c"u_kernel_size"),
+                blur_shader,
+                u_threshold: uniform(surface_shader, c"u_threshold"),
+                u_pixels_per_texel: uniform(surface_shader, c"u_pixels_per_texel"),
+                u_inverse: uniform(surface_shader, c"u_inverse"),
+                u_bounds_min: uniform(surface_shader, c"u_bounds_min"),
+                u_bounds_max: uniform(surface_shader, c"u_bounds_max"),
+                u_finger: uniform(surface_shader, c"u_finger"),
+                u_clear: uniform(surface_shader, c"u_clear"),
+                u_wall: uniform(surface_shader, c"u_wall"),
+                surface_shader,
+                fill_vao,
+                fill_vbo,
+                quad_vao,
+                quad_vbo,
+
+                density: None,
+
+                params: Params::new()
+                    .with_enum("gravity", 0, GRAVITIES)
+                    .with_f32("pressure", 3000.0, 500.0, 10000.0, 500.0)
+                    .with_i32("smoothness", 12, 3, 32)
+                    .with_f32("threshold", 0.5, 0.1, 0.9, 0.05)
+                    .with_bool("running", true),
+            };
+            scene.reset();
+            Ok(scene)
+        }
+    }
+
+    /// Starts over with blobs scattered over the top of the box.
+    fn reset(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.bodies = SoftBodies::new();
+        self.colors.clear();
+
+        let mut attempts = 0;
+        while self.bodies.blobs.len() < N_BLOBS && attempts < 1000 {
+            attempts += 1;
+            let radius = self.rng.gen_range(RADII.0..RADII.1);
+            let center = vec2(
+                self.rng
+                    .gen_range(-HALF_BOX.x + radius..HALF_BOX.x - radius),
+                self.rng.gen_range(-HALF_BOX.y + radius..0.0),
+            );
+            let overlaps = (self.bodies.blobs.iter()).any(|blob| {
+                let other_radius = (blob.rest_area / (TAU / 2.0)).sqrt();
+                self.bodies.centroid(blob).distance(center) < radius + other_radius + 4.0
+            });
+            if !overlaps {
+                self.add_blob_with_radius(center, radius);
+            }
+        }
+    }
+
+    /// Drops a blob of a random size at `center`, unless the box is full.
+    fn add_blob(&mut self, center: Vec2) {
+        if self.bodies.blobs.len() >= MAX_BLOBS {
+            println!("soft body: already {MAX_BLOBS} blobs");
+            return;
+        }
+
+        let radius = self.rng.gen_range(RADII.0..RADII.1);
+        let center = center.clamp(-HALF_BOX + radius, HALF_BOX - radius);
+        self.add_blob_with_radius(center, radius);
+    }
+
+    fn add_blob_with_radius(&mut self, center: Vec2, radius: f32) {
+        let n_points = ((TAU * radius / SPRING_LENGTH) as usize).max(12);
+        self.bodies.add_blob(center, radius, n_points);
+        // candy colors, every hue washed with a little white
+        let hue = palette::hue(self.rng.gen());
+        self.colors.push(hue.lerp(Vec3::ONE, 0.3));
+    }
+
+    fn settings(&self) -> Settings {
+        let gravity = match GRAVITIES[self.params.variant("gravity")] {
+            "up" => vec2(0.0, -980.0),
+            "off" => Vec2::ZERO,
+            _ => vec2(0.0, 980.0),
+        };
+        Settings {
+            gravity,
+            stiffness: 20000.0,
+            damping: 50.0,
+            pressure: self.params.f32("pressure"),
+            bounds: (-HALF_BOX, HALF_BOX),
+            friction: 0.02,
+        }
+    }
+
+    /// Triangles fanning out of the middle of every blob.
+    fn fill_vertices(&self) -> Vec<FillVertex> {
+        let mut vertices = Vec::with_capacity(self.bodies.positions.len() * 3);
+        for (blob, &color) in self.bodies.blobs.iter().zip(&self.colors) {
+            let center = self.bodies.centroid(blob);
+            let points = self.bodies.points(blob);
+            for i in 0..points.len() {
+                vertices.extend(
+                    [center, points[i], points[(i + 1) % points.len()]]
+                        .map(|position| FillVertex { position, color }),
+                );
+            }
+        }
+        vertices
+    }
+}
+
+impl Scene for SoftBodyScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("g") => self.params.step("gravity", 1).unwrap(),
+            Key::Character("G") => self.params.step("gravity", -1).unwrap(),
+            Key::Character("f") => self.params.step("pressure", 1).unwrap(),
+            Key::Character("F") => self.params.step("pressure", -1).unwrap(),
+            Key::Character("m") => self.params.step("smoothness", 1).unwrap(),
+            Key::Character("M") => self.params.step("smoothness", -1).unwrap(),
+            Key::Character("t") => self.params.step("threshold", 1).unwrap(),
+            Key::Character("T") => self.params.step("threshold", -1).unwrap(),
+            Key::Character("a" | "A") => self.add_blob(self.mouse_world),
+            Key::Character("p" | "P") => self.params.toggle("running").unwrap(),
+            Key::Character("r" | "R") => self.reset(),
+            _ => return,
+        }
+
+        println!("soft body config: {}", self.params);
+    }
+
+    /// Pokes the blobs under the mouse while the right button is held.
+    /// Returns whether the scene handled the button.
+    fn on_event(&mut self, camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if button != MouseButton::Right {
+            return false;
+        }
+
+        self.mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport.as_vec2());
+        self.poking = state == ElementState::Pressed;
+        true
+    }
+
+    fn update(&mut self, dt: f32) {
+        if !self.params.bool("running") {
+            return;
+        }
+
+        profiling::cpu_zone!("soft body update");
+
+        let settings = self.settings();
+        let dt = dt / SUBSTEPS as f32;
+        for _ in 0..SUBSTEPS {
+            self.bodies.step(dt, &settings);
+            if self.poking {
+                self.bodies.push_out(self.mouse_world, FINGER);
+            }
+        }
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("soft body draw");
+
+        self.mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport.as_vec2());
+        let Some(density) = &self.density else {
+            return Ok(());
+        };
+        let theme = theme::current();
+        let vertices = self.fill_vertices();
+        let half = (self.viewport / 2).max(UVec2::ONE).as_ivec2();
+        let size = self.viewport.as_ivec2();
+        let inverse = self.matrix.inverse();
+        let finger = match self.poking {
+            true => self.mouse_world.extend(FINGER),
+            false => Vec3::ZERO,
+        };
+
+        unsafe {
+            push_debug_group(c"Soft body density");
+            gl::Viewport(0, 0, half.x, half.y);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, density[0].fbo);
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            gl::UseProgram(self.fill_shader);
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+            buffer_data(gl::ARRAY_BUFFER, self.fill_vbo, &vertices, gl::STREAM_DRAW);
+            gl::BindVertexArray(self.fill_vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, vertices.len() as GLsizei);
+
+            gl::UseProgram(self.blur_shader);
+            gl::Uniform1i(self.u_kernel_size, self.params.i32("smoothness"));
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindVertexArray(self.quad_vao);
+            for (from, to, direction) in [(0, 1, vec2(1.0, 0.0)), (1, 0, vec2(0.0, 1.0))] {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, density[to].fbo);
+                gl::BindTexture(gl::TEXTURE_2D, density[from].texture);
+                gl::Uniform2f(self.u_direction, direction.x, direction.y);
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            }
+            pop_debug_group();
+
+            push_debug_group(c"Soft body surface");
+            gl::Viewport(0, 0, size.x, size.y);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::UseProgram(self.surface_shader);
+            gl::Uniform1f(self.u_threshold, self.params.f32("threshold"));
+            gl::Uniform1f(self.u_pixels_per_texel, size.x as f32 / half.x as f32);
+            gl::UniformMatrix4fv(self.u_inverse, 1, gl::FALSE, inverse.as_ref().as_ptr());
+            gl::Uniform2f(self.u_bounds_min, -HALF_BOX.x, -HALF_BOX.y);
+            gl::Uniform2f(self.u_bounds_max, HALF_BOX.x, HALF_BOX.y);
+            gl::Uniform3f(self.u_finger, finger.x, finger.y, finger.z);
+            gl::Uniform4fv(self.u_clear, 1, theme.clear.as_ptr());
+            gl::Uniform4fv(self.u_wall, 1, theme.grid.as_ptr());
+            gl::BindTexture(gl::TEXTURE_2D, density[0].texture);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::BindVertexArray(0);
+            pop_debug_group();
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.matrix = camera.matrix(vec2(width as f32, height as f32));
+
+        let viewport = uvec2(width as u32, height as u32);
+        if viewport == self.viewport {
+            return Ok(());
+        }
+        self.viewport = viewport;
+
+        unsafe {
+            for framebuffer in self.density.take().iter().flatten() {
+                delete_framebuffer(framebuffer);
+            }
+            if (viewport / 2).min_element() == 0 {
+                return Ok(());
+            }
+
+            let density = create_float_framebuffer("soft body density", viewport / 2, gl::RGBA16F)?;
+            let blur = create_float_framebuffer("soft body blur", viewport / 2, gl::RGBA16F)
+                .inspect_err(|_| delete_framebuffer(&density))?;
+            self.density = Some([density, blur]);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            Ok(())
+        }
+    }
+}
+
+impl Drop for SoftBodyScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.fill_shader);
+            gl::DeleteProgram(self.blur_shader);
+            gl::DeleteProgram(self.surface_shader);
+            gl::DeleteVertexArrays(1, &self.fill_vao);
+            gl::DeleteVertexArrays(1, &self.quad_vao);
+            delete_buffers(&[self.fill_vbo, self.quad_vbo]);
+            for framebuffer in self.density.iter().flatten() {
+                delete_framebuffer(framebuffer);
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    f32::consts::TAU,
+    time::{Duration, Instant},
+};
+
+use glam::{Mat4, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::error::GlError;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::spatial::{Quadtree, SpatialIndex, UniformGrid};
+use crate::theme;
+
+use super::spatial_overlay::{Shape, ShapeRenderer, SpatialOverlay};
+use super::{Scene, SRC_FRAG_SPRITE};
+
+const N_POINTS: usize = 20_000;
+
+/// Side of the square the points wander in.
+const AREA_SIZE: f32 = 4000.0;
+
+const CELL_SIZE: f32 = 64.0;
+const QUADTREE_CAPACITY: usize = 16;
+const QUADTREE_MAX_DEPTH: u32 = 10;
+
+const INDICES: &[&str] = &["grid", "quadtree"];
+
+/// Points wandering around, indexed every frame by a [`UniformGrid`] or a
+/// [`Quadtree`] to find those around the mouse.
+///
+/// Half of the points gather in a few clusters, where a uniform grid gets
+/// crowded cells while a quadtree subdivides.
+pub struct SpatialScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    points: Vec<Point>,
+    grid: UniformGrid<u32>,
+    quadtree: Quadtree<u32>,
+    found: Vec<u32>,
+
+    renderer: ShapeRenderer,
+    overlay: SpatialOverlay,
+    shapes: Vec<Shape>,
+
+    build_time: Duration,
+    query_time: Duration,
+    frames: u32,
+    last_report: Instant,
+    last_instant: Instant,
+
+    params: Params,
+}
+
+struct Point {
+    position: Vec2,
+    velocity: Vec2,
+}
+
+impl SpatialScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let clusters = (0..6)
+            .map(|_| Vec2::new(rng.gen_range(-0.4..0.4), rng.gen_range(-0.4..0.4)) * AREA_SIZE)
+            .collect::<Vec<_>>();
+
+        let points = (0..N_POINTS)
+            .map(|i| {
+                let position = if i % 2 == 0 {
+                    let offset = Vec2::from_angle(rng.gen_range(0.0..TAU));
+                    clusters[i / 2 % clusters.len()]
+                        + offset * rng.gen_range(0.0f32..1.0).powi(2) * 300.0
+                } else {
+                    Vec2::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5)) * AREA_SIZE
+                };
+
+                Point {
+                    position,
+                    velocity: Vec2::from_angle(rng.gen_range(0.0..TAU)) * rng.gen_range(5.0..30.0),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let half = Vec2::splat(AREA_SIZE * 0.5);
+
+        unsafe {
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+
+                points,
+                grid: UniformGrid::new(CELL_SIZE),
+                quadtree: Quadtree::new(-half, half, QUADTREE_CAPACITY, QUADTREE_MAX_DEPTH),
+                found: Vec::new(),
+
+                renderer: ShapeRenderer::new(SRC_FRAG_SPRITE)?,
+                overlay: SpatialOverlay::new()?,
+                shapes: Vec::with_capacity(N_POINTS),
+
+                build_time: Duration::ZERO,
+                query_time: Duration::ZERO,
+                frames: 0,
+                last_report: Instant::now(),
+                last_instant: Instant::now(),
+
+                params: Params::new()
+                    .with_enum("index", 0, INDICES)
+                    .with_f32("radius", 150.0, 10.0, 1000.0, 10.0)
+                    .with_bool("overlay", true),
+            })
+        }
+    }
+
+    /// Prints how long building and querying the index took, once per second.
+    fn report(&mut self, regions: Option<usize>) {
+        if self.last_report.elapsed() < Duration::from_secs(1) {
+            return;
+        }
+
+        let index = INDICES[self.params.variant("index")];
+        let ms = |time: Duration| time.as_secs_f32() * 1000.0 / self.frames as f32;
+        let regions = match regions {
+            Some(regions) => format!(", {regions} regions"),
+            None => String::new(),
+        };
+        println!(
+            "spatial ({index}): {N_POINTS} points, {} found{regions} | build {:.2} ms, query {:.3} ms",
+            self.found.len(),
+            ms(self.build_time),
+            ms(self.query_time),
+        );
+
+        self.build_time = Duration::ZERO;
+        self.query_time = Duration::ZERO;
+        self.frames = 0;
+        self.last_report = Instant::now();
+    }
+}
+
+impl Scene for SpatialScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("i") => self.params.toggle("index").unwrap(),
+            Key::Character("r") => self.params.step("radius", 1).unwrap(),
+            Key::Character("R") => self.params.step("radius", -1).unwrap(),
+            Key::Character("o") => self.params.toggle("overlay").unwrap(),
+            _ => return,
+        }
+
+        println!("spatial config: {}", self.params);
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("spatial draw");
+
+        let dt = self.last_instant.elapsed().as_secs_f32();
+        self.last_instant = Instant::now();
+
+        let half = AREA_SIZE * 0.5;
+        for point in &mut self.points {
+            point.position += point.velocity * dt;
+            for axis in 0..2 {
+                if point.position[axis].abs() > half {
+                    point.position[axis] = point.position[axis].clamp(-half, half);
+                    point.velocity[axis] = -point.velocity[axis];
+                }
+            }
+        }
+
+        let index: &mut dyn SpatialIndex<u32> = match self.params.variant("index") {
+            0 => &mut self.grid,
+            _ => &mut self.quadtree,
+        };
+
+        let start = Instant::now();
+        index.clear();
+        for (i, point) in self.points.iter().enumerate() {
+            index.insert(point.position, i as u32);
+        }
+        self.build_time += start.elapsed();
+
+        let center = camera.pointer_to_pos(mouse_pos, self.viewport);
+        let radius = self.params.f32("radius");
+
+        let start = Instant::now();
+        self.found.clear();
+        index.query_circle(center, radius, &mut self.found);
+        self.query_time += start.elapsed();
+
+        self.shapes.clear();
+        self.shapes.extend(self.points.iter().map(|point| Shape {
+            center: point.position,
+            half_size: Vec2::splat(4.0),
+            color: [0.5, 0.6, 0.8, 0.8],
+        }));
+        for &i in &self.found {
+            let shape = &mut self.shapes[i as usize];
+            shape.half_size = Vec2::splat(6.0);
+            shape.color = [1.0, 0.8, 0.2, 1.0];
+        }
+
+        // how many items make a region of the overlay opaque
+        let capacity = match self.params.variant("index") {
+            0 => (N_POINTS as f32 * (CELL_SIZE / AREA_SIZE).powi(2)) as usize * 4,
+            _ => QUADTREE_CAPACITY,
+        };
+        let regions = self.params.bool("overlay").then(|| index.regions());
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            if let Some(regions) = &regions {
+                let query = Some((center, radius));
+                self.overlay.draw(&self.matrix, regions, capacity, query);
+            }
+            self.renderer.draw(&self.matrix, &self.shapes);
+        }
+
+        self.frames += 1;
+        self.report(regions.map(|regions| regions.len()));
+        Ok(())
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{Mat4, Vec2};
+
+use crate::common_gl::{buffer_data, create_shader_program, delete_buffers};
+use crate::error::GlError;
+#[cfg(feature = "scene-spatial")]
+use crate::spatial::Region;
+#[cfg(feature = "scene-spatial")]
+use crate::theme;
+
+#[cfg(feature = "scene-spatial")]
+use super::SRC_FRAG_OUTLINE;
+use super::SRC_VERT_CULL;
+
+/// An instance of cull.vert: a quad around `center`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Shape {
+    pub center: Vec2,
+    pub half_size: Vec2,
+    pub color: [f32; 4],
+}
+
+/// Draws batches of [`Shape`]s as instances of a single quad, shaded by the
+/// fragment shader it's created with.
+pub struct ShapeRenderer {
+    shader: GLuint,
+    u_mvp: GLint,
+    vao: GLuint,
+    quad_vbo: GLuint,
+    instance_vbo: GLuint,
+}
+
+impl ShapeRenderer {
+    pub unsafe fn new(frag_source: &[u8]) -> Result<Self, GlError> {
+        let shader = create_shader_program(SRC_VERT_CULL, frag_source)?;
+
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        let mut buffers = [0; 2];
+        gl::GenBuffers(2, buffers.as_mut_ptr());
+        let [quad_vbo, instance_vbo] = buffers;
+
+        gl::BindVertexArray(vao);
+
+        let corners: [[f32; 2]; 4] = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]];
+        buffer_data(gl::ARRAY_BUFFER, quad_vbo, &corners, gl::STATIC_DRAW);
+        gl::VertexAttribPointer(3, 2, gl::FLOAT, gl::FALSE, 0, 0 as _);
+        gl::EnableVertexAttribArray(3);
+
+        gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+        let size_f32 = mem::size_of::<f32>() as GLsizei;
+        let size_shape = mem::size_of::<Shape>() as GLsizei;
+        gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, size_shape, 0 as _);
+        gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, size_shape, (2 * size_f32) as _);
+        gl::VertexAttribPointer(2, 4, gl::FLOAT, gl::FALSE, size_shape, (4 * size_f32) as _);
+        for location in 0..3 {
+            gl::EnableVertexAttribArray(location);
+            gl::VertexAttribDivisor(location, 1);
+        }
+
+        gl::BindVertexArray(0);
+
+        Ok(Self {
+            u_mvp: gl::GetUniformLocation(shader, c"u_mvp".as_ptr()),
+            shader,
+            vao,
+            quad_vbo,
+            instance_vbo,
+        })
+    }
+
+    #[cfg(feature = "scene-spatial")]
+    pub fn shader(&self) -> GLuint {
+        self.shader
+    }
+
+    pub unsafe fn draw(&self, matrix: &Mat4, shapes: &[Shape]) {
+        if shapes.is_empty() {
+            return;
+        }
+
+        gl::UseProgram(self.shader);
+        gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, matrix.as_ref().as_ptr());
+
+        buffer_data(gl::ARRAY_BUFFER, self.instance_vbo, shapes, gl::STREAM_DRAW);
+        gl::BindVertexArray(self.vao);
+        gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, shapes.len() as GLsizei);
+        gl::BindVertexArray(0);
+    }
+}
+
+impl Drop for ShapeRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.quad_vbo, self.instance_vbo]);
+        }
+    }
+}
+
+/// Debug view of a [`SpatialIndex`](crate::spatial::SpatialIndex): the
+/// outline of every occupied region, filled more opaquely the more items it
+/// holds, and the region of a query.
+#[cfg(feature = "scene-spatial")]
+pub struct SpatialOverlay {
+    renderer: ShapeRenderer,
+    u_circle: GLint,
+    shapes: Vec<Shape>,
+}
+
+#[cfg(feature = "scene-spatial")]
+impl SpatialOverlay {
+    pub unsafe fn new() -> Result<Self, GlError> {
+        let renderer = ShapeRenderer::new(SRC_FRAG_OUTLINE)?;
+        let u_circle = gl::GetUniformLocation(renderer.shader(), c"u_circle".as_ptr());
+
+        Ok(Self {
+            renderer,
+            u_circle,
+            shapes: Vec::new(),
+        })
+    }
+
+    /// Draws `regions`, and the circle of a query around `query.0` of radius
+    /// `query.1`. `capacity` is how many items make a region fully opaque.
+    pub unsafe fn draw(
+        &mut self,
+        matrix: &Mat4,
+        regions: &[Region],
+        capacity: usize,
+        query: Option<(Vec2, f32)>,
+    ) {
+        self.shapes.clear();
+        self.shapes.extend(regions.iter().map(|region| {
+            // hue by depth, so that quadtree levels stand apart
+            let hue = region.depth as f32 * 0.9;
+            let [r, g, b] = [0.0, 2.1, 4.2].map(|offset: f32| 0.6 + 0.4 * (hue + offset).cos());
+            let fill = (region.items as f32 / capacity as f32).min(1.0) * 0.35;
+
+            Shape {
+                center: (region.min + region.max) * 0.5,
+                half_size: (region.max - region.min) * 0.5,
+                color: [r, g, b, fill],
+            }
+        }));
+
+        gl::UseProgram(self.renderer.shader());
+        gl::Uniform1i(self.u_circle, 0);
+        self.renderer.draw(matrix, &self.shapes);
+
+        if let Some((center, radius)) = query {
+            let circle = Shape {
+                center,
+                half_size: Vec2::splat(radius),
+                color: theme::current().accent(0.15),
+            };
+            gl::UseProgram(self.renderer.shader());
+            gl::Uniform1i(self.u_circle, 1);
+            self.renderer.draw(matrix, &[circle]);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use glam::{Mat4, Vec2};
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::error::GlError;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::spline::{ArcLength, Curve, CurveKind, CurveSet};
+use crate::theme;
+
+use super::polyline::PolylineRenderer;
+use super::spatial_overlay::{Shape, ShapeRenderer};
+use super::{Scene, SceneEvent, SRC_FRAG_SPRITE};
+
+/// Where the curves are saved and loaded from, in the working directory.
+const CURVES_PATH: &str = "splines.toml";
+
+/// How close to a control point a click has to be to grab it, in pixels.
+const GRAB_PIXELS: f32 = 10.0;
+
+const BEZIER_COLOR: [f32; 4] = [1.0, 0.6, 0.2, 1.0];
+const CATMULL_ROM_COLOR: [f32; 4] = [0.3, 0.8, 1.0, 1.0];
+
+/// An editor for cubic bezier and Catmull-Rom curves, with a quad running
+/// along each of them at constant speed.
+///
+/// Curves are tessellated adaptively every frame, so that they stay within a
+/// given number of pixels of the real curve whatever the zoom, and drawn with
+/// the polyline renderer. Walking the resulting polylines by distance rather
+/// than by curve parameter keeps the quads from speeding up where control
+/// points are far apart.
+pub struct SplinesScene {
+    matrix: Mat4,
+    viewport: Vec2,
+    /// Pixels per world unit.
+    pixels_per_unit: f32,
+    mouse_world: Vec2,
+
+    curves: CurveSet,
+    /// Curve and point index.
+    selected: Option<(usize, usize)>,
+    dragging: bool,
+
+    polylines: PolylineRenderer,
+    discs: ShapeRenderer,
+    shapes: Vec<Shape>,
+
+    start: Instant,
+    vertices: usize,
+    last_report: Instant,
+
+    params: Params,
+}
+
+impl SplinesScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Result<Self, GlError> {
+        let curves = CurveSet {
+            curves: vec![
+                Curve {
+                    kind: CurveKind::Bezier,
+                    points: [
+                        (-400.0, 0.0),
+                        (-300.0, -250.0),
+                        (-100.0, -250.0),
+                        (0.0, 0.0),
+                        (100.0, 250.0),
+                        (300.0, 250.0),
+                        (400.0, 0.0),
+                    ]
+                    .map(Vec2::from)
+                    .to_vec(),
+                },
+                Curve {
+                    kind: CurveKind::CatmullRom,
+                    points: [
+                        (-350.0, 300.0),
+                        (-150.0, 200.0),
+                        (0.0, 350.0),
+                        (150.0, 200.0),
+                        (350.0, 300.0),
+                    ]
+                    .map(Vec2::from)
+                    .to_vec(),
+                },
+            ],
+        };
+
+        unsafe {
+            Ok(Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+                pixels_per_unit: 1.0,
+                mouse_world: Vec2::ZERO,
+
+                curves,
+                selected: None,
+                dragging: false,
+
+                polylines: PolylineRenderer::new()?,
+                discs: ShapeRenderer::new(SRC_FRAG_SPRITE)?,
+                shapes: Vec::new(),
+
+                start: Instant::now(),
+                vertices: 0,
+                last_report: Instant::now(),
+
+                params: Params::new()
+                    .with_f32("tolerance", 0.5, 0.05, 20.0, 0.25)
+                    .with_f32("speed", 200.0, 0.0, 2000.0, 50.0)
+                    .with_bool("vertices", false),
+            })
+        }
+    }
+
+    fn add_curve(&mut self, kind: CurveKind) {
+        let step = Vec2::new(80.0, 0.0) / self.pixels_per_unit.max(0.01);
+        let points = match kind {
+            CurveKind::Bezier => (0..4).map(|i| self.mouse_world + step * i as f32).collect(),
+            CurveKind::CatmullRom => (0..2).map(|i| self.mouse_world + step * i as f32).collect(),
+        };
+
+        self.curves.curves.push(Curve { kind, points });
+        let curve = self.curves.curves.len() - 1;
+        self.selected = Some((curve, self.curves.curves[curve].points.len() - 1));
+    }
+
+    /// Extends the selected curve up to the mouse.
+    fn append_point(&mut self) {
+        let Some((c, _)) = self.selected else {
+            println!("splines: select a curve to append to");
+            return;
+        };
+
+        let curve = &mut self.curves.curves[c];
+        let last = *curve.points.last().unwrap();
+        match curve.kind {
+            CurveKind::Bezier => {
+                // handles a third of the way, mirroring the last handle
+                let handle = curve.points[curve.points.len() - 2];
+                let to = self.mouse_world;
+                curve
+                    .points
+                    .extend([last * 2.0 - handle, last.lerp(to, 2.0 / 3.0), to]);
+            }
+            CurveKind::CatmullRom => curve.points.push(self.mouse_world),
+        }
+
+        self.selected = Some((c, curve.points.len() - 1));
+    }
+
+    fn delete_selected(&mut self) {
+        let Some((c, p)) = self.selected.take() else {
+            return;
+        };
+
+        let curve = &mut self.curves.curves[c];
+        match curve.kind {
+            CurveKind::Bezier => {
+                // an anchor goes with its handles
+                let anchor = (p + 1) / 3 * 3;
+                let range = match anchor {
+                    0 => 0..3,
+                    _ if anchor >= curve.points.len() - 1 => anchor - 2..anchor + 1,
+                    _ => anchor - 1..anchor + 2,
+                };
+                curve.points.drain(range);
+            }
+            CurveKind::CatmullRom => {
+                curve.points.remove(p);
+            }
+        }
+
+        let min_points = match curve.kind {
+            CurveKind::Bezier => 4,
+            CurveKind::CatmullRom => 2,
+        };
+        if curve.points.len() < min_points {
+            self.curves.curves.remove(c);
+        }
+    }
+
+    /// Turns the selected curve into the other kind, keeping its shape when
+    /// going to bezier.
+    fn convert_selected(&mut self) {
+        let Some((c, _)) = self.selected else {
+            return;
+        };
+
+        let curve = &mut self.curves.curves[c];
+        *curve = match curve.kind {
+            CurveKind::Bezier => Curve {
+                kind: CurveKind::CatmullRom,
+                points: curve.points.iter().step_by(3).copied().collect(),
+            },
+            CurveKind::CatmullRom => {
+                let segments = curve.segments();
+                let mut points = vec![curve.points[0]];
+                points.extend(segments.iter().flat_map(|s| [s[1], s[2], s[3]]));
+                Curve {
+                    kind: CurveKind::Bezier,
+                    points,
+                }
+            }
+        };
+        self.selected = Some((c, 0));
+    }
+
+    fn save(&self) {
+        match self.curves.save(Path::new(CURVES_PATH)) {
+            Ok(()) => println!("Saved {} curves to {CURVES_PATH}", self.curves.curves.len()),
+            Err(e) => eprintln!("Could not save curves to {CURVES_PATH}: {e}"),
+        }
+    }
+
+    fn load(&mut self) {
+        match CurveSet::load(Path::new(CURVES_PATH)) {
+            Ok(curves) => {
+                println!("Loaded {} curves from {CURVES_PATH}", curves.curves.len());
+                self.curves = curves;
+                self.selected = None;
+                self.dragging = false;
+            }
+            Err(e) => eprintln!("Could not load curves from {CURVES_PATH}: {e}"),
+        }
+    }
+
+    /// Moves the dragged point to the mouse, along with the handles of a
+    /// bezier anchor.
+    fn drag(&mut self) {
+        let Some((c, p)) = self.selected.filter(|_| self.dragging) else {
+            return;
+        };
+
+        let curve = &mut self.curves.curves[c];
+        let delta = self.mouse_world - curve.points[p];
+        curve.points[p] = self.mouse_world;
+
+        if curve.kind == CurveKind::Bezier && p % 3 == 0 {
+            for handle in [p.wrapping_sub(1), p + 1] {
+                if let Some(handle) = curve.points.get_mut(handle) {
+                    *handle += delta;
+                }
+            }
+        }
+    }
+
+    /// Prints how many vertices the curves were tessellated into, once per
+    /// second.
+    fn report(&mut self) {
+        if self.last_report.elapsed() < Duration::from_secs(1) {
+            return;
+        }
+
+        println!(
+            "splines: {} curves tessellated into {} vertices within {} px",
+            self.curves.curves.len(),
+            self.vertices,
+            self.params.f32("tolerance"),
+        );
+        self.last_report = Instant::now();
+    }
+}
+
+impl Scene for SplinesScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("n") => self.add_curve(CurveKind::Bezier),
+            Key::Character("N") => self.add_curve(CurveKind::CatmullRom),
+            Key::Character("a") => self.append_point(),
+            Key::Character("x") => self.delete_selected(),
+            Key::Character("k") => self.convert_selected(),
+            Key::Character("s") => self.save(),
+            Key::Character("l") => self.load(),
+            Key::Character("t") => self.params.step("tolerance", 1).unwrap(),
+            Key::Character("T") => self.params.step("tolerance", -1).unwrap(),
+            Key::Character("v") => self.params.toggle("vertices").unwrap(),
+            _ => return,
+        }
+
+        println!("splines config: {}", self.params);
+    }
+
+    /// Grabs the control point under the mouse. Returns whether the click was
+    /// used, in which case the camera shouldn't pan.
+    fn on_event(&mut self, camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if button != MouseButton::Left {
+            return false;
+        }
+
+        if state == ElementState::Released {
+            return std::mem::take(&mut self.dragging);
+        }
+
+        let mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport);
+        let grab_distance = GRAB_PIXELS / self.pixels_per_unit;
+
+        let closest = (self.curves.curves.iter().enumerate())
+            .flat_map(|(c, curve)| {
+                (curve.points.iter().enumerate()).map(move |(p, point)| (c, p, *point))
+            })
+            .map(|(c, p, point)| (c, p, point.distance(mouse_world)))
+            .filter(|&(_, _, distance)| distance <= grab_distance)
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+
+        self.selected = closest.map(|(c, p, _)| (c, p));
+        self.dragging = self.selected.is_some();
+        self.dragging
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("splines draw");
+
+        self.mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport);
+        self.drag();
+
+        let pixel = 1.0 / self.pixels_per_unit;
+        let tolerance = self.params.f32("tolerance") * pixel;
+        let distance = self.start.elapsed().as_secs_f32() * self.params.f32("speed");
+        let show_vertices = self.params.bool("vertices");
+
+        self.shapes.clear();
+        self.vertices = 0;
+
+        for (c, curve) in self.curves.curves.iter().enumerate() {
+            let selected = self.selected.is_some_and(|(sc, _)| sc == c);
+            let color = match curve.kind {
+                CurveKind::Bezier => BEZIER_COLOR,
+                CurveKind::CatmullRom => CATMULL_ROM_COLOR,
+            };
+
+            let mut points = Vec::new();
+            curve.tessellate(tolerance, &mut points);
+            self.vertices += points.len();
+
+            let width = if selected { 4.0 } else { 2.5 } * pixel;
+            self.polylines.push(&points, width, color);
+
+            if show_vertices {
+                self.shapes.extend(points.iter().map(|&center| Shape {
+                    center,
+                    half_size: Vec2::splat(2.0 * pixel),
+                    color: theme::current().hud_text,
+                }));
+            }
+
+            // the quad running along the curve, back and forth
+            let path = ArcLength::new(points);
+            let length = path.length();
+            if length > 0.0 {
+                let along = length - (distance % (2.0 * length) - length).abs();
+                if let Some((position, direction)) = path.sample(along) {
+                    let half = direction * 8.0 * pixel;
+                    let quad = [position - half, position + half];
+                    self.polylines
+                        .push(&quad, 10.0 * pixel, theme::current().hud_text);
+                }
+            }
+
+            if curve.kind == CurveKind::Bezier {
+                for segment in curve.points.windows(4).step_by(3) {
+                    let handle_color = theme::current().grid;
+                    self.polylines.push(&segment[..2], pixel, handle_color);
+                    self.polylines.push(&segment[2..], pixel, handle_color);
+                }
+            }
+
+            for (p, &center) in curve.points.iter().enumerate() {
+                let is_anchor = curve.kind == CurveKind::CatmullRom || p % 3 == 0;
+                let radius = if is_anchor { 6.0 } else { 4.0 };
+                let color = if self.selected == Some((c, p)) {
+                    theme::current().accent
+                } else {
+                    color
+                };
+
+                self.shapes.push(Shape {
+                    center,
+                    half_size: Vec2::splat(radius * pixel),
+                    color,
+                });
+            }
+        }
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            self.polylines.draw(&self.matrix);
+            self.discs.draw(&self.matrix, &self.shapes);
+        }
+
+        self.report();
+        Ok(())
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        self.pixels_per_unit = camera.scale.x;
+        Ok(())
+    }
+}
@@ -0,0 +1,696 @@
+use std::f32::consts::TAU;
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, vec3, UVec2, Vec2, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_float_framebuffer, create_shader_program, delete_buffers,
+    delete_framebuffer, delete_textures, pop_debug_group, push_debug_group, upload_texture,
+    Framebuffer,
+};
+use crate::downsample::DownsampleChain;
+use crate::error::GlError;
+use crate::gpu_memory::{self, Category, Object};
+use crate::output;
+use crate::params::{Params, Value};
+use crate::profiling;
+use crate::render_state::RenderState;
+
+use super::{
+    Scene, SRC_FRAG_SUN, SRC_FRAG_SUN_ADAPT, SRC_FRAG_SUN_BRIGHT, SRC_FRAG_SUN_FLARE,
+    SRC_FRAG_SUN_TONEMAP, SRC_VERT_SCREEN,
+};
+
+/// Texels per side of the ground texture.
+const GROUND_TEXELS: usize = 1024;
+/// Half the side of the ground, in world units.
+const FIELD: f32 = 2048.0;
+const TEXELS_PER_UNIT: f32 = GROUND_TEXELS as f32 / (2.0 * FIELD);
+
+/// Side of a city block, street included, in world units.
+const BLOCK: f32 = 256.0;
+const STREET: f32 = 40.0;
+const SIDEWALK: f32 = 8.0;
+/// Height of the tallest building downtown, plus the shortest ones. Must stay
+/// under `MAX_HEIGHT` in the shader.
+const DOWNTOWN_HEIGHT: f32 = 220.0;
+const MIN_HEIGHT: f32 = 20.0;
+
+const ROAD: [f32; 3] = [0.05, 0.05, 0.055];
+const PAVEMENT: [f32; 3] = [0.25, 0.24, 0.22];
+const GRASS: [f32; 3] = [0.08, 0.18, 0.05];
+const TREE: [f32; 3] = [0.04, 0.12, 0.03];
+
+/// Latitude of the city and declination of the sun, in degrees: a spring day
+/// somewhere in Europe.
+const LATITUDE: f32 = 45.0;
+const DECLINATION: f32 = 10.0;
+
+/// Height of the sky panorama, as a fraction of the height of the window.
+const SKY_FRACTION: f32 = 0.22;
+/// How quickly the eye adapts to a change in brightness, per second.
+const ADAPTATION_SPEED: f32 = 1.5;
+
+/// Levels of the downsample chain bloom goes through, from half resolution.
+const BLOOM_LEVELS: usize = 6;
+/// Level of the chain the lens flare reflects, blurred enough for ghosts.
+const FLARE_LEVEL: usize = 2;
+/// Texels per side of the lens dirt texture.
+const DIRT_TEXELS: usize = 512;
+
+/// Direction toward the sun at `hours` of the day, with x east, y south and z
+/// up, like the world with y going down the screen.
+pub fn sun_direction(hours: f32) -> Vec3 {
+    let latitude = LATITUDE.to_radians();
+    let declination = DECLINATION.to_radians();
+    let hour_angle = (hours - 12.0) / 24.0 * TAU;
+
+    let east = -declination.cos() * hour_angle.sin();
+    let north =
+        declination.sin() * latitude.cos() - declination.cos() * hour_angle.cos() * latitude.sin();
+    let up =
+        declination.sin() * latitude.sin() + declination.cos() * hour_angle.cos() * latitude.cos();
+    vec3(east, -north, up)
+}
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// A city seen from above through a day: the sun goes around, shadows are
+/// traced toward it over the height field of the buildings, and the sky is
+/// approximated by single scattering, at the top of the window as a
+/// panorama and on the ground as ambient light.
+///
+/// Everything is lit in HDR, then the exposure adapts to the average log
+/// luminance over a couple of seconds like an eye would, before the ACES
+/// curve tonemaps it to the window.
+///
+/// What is much brighter than the eye is adapted to blooms through a
+/// [`DownsampleChain`], and makes a lens flare: ghosts and a halo reflected
+/// through the center from a blurred level of the chain, composited after
+/// bloom with a starburst and the dirt on the lens.
+pub struct SunScene {
+    viewport: UVec2,
+
+    sun_shader: GLuint,
+    u_inverse: GLint,
+    u_sun: GLint,
+    u_sky_height: GLint,
+    u_viewport: GLint,
+
+    adapt_shader: GLuint,
+    u_lod: GLint,
+
+    bright_shader: GLuint,
+    u_adapt_bright: GLint,
+
+    flare_shader: GLuint,
+    u_ghosts: GLint,
+    u_halo: GLint,
+    u_aspect_flare: GLint,
+
+    tonemap_shader: GLuint,
+    u_adapt: GLint,
+    u_exposure: GLint,
+    u_hdr_output: GLint,
+    u_bloom_intensity: GLint,
+    u_starburst: GLint,
+    u_dirt_intensity: GLint,
+    u_rotation: GLint,
+    u_aspect: GLint,
+
+    vao: GLuint,
+    vbo: GLuint,
+    /// Albedo and height of the city.
+    ground: GLuint,
+    /// Dust and smudges on the lens, lit up by bloom and flares.
+    dirt: GLuint,
+
+    /// The lit scene, with log luminance in alpha for its mipmaps to average.
+    hdr: Option<Framebuffer>,
+    /// Average log luminance the eye is adapted to.
+    adapted: Framebuffer,
+    /// Whether `adapted` holds something yet.
+    has_adapted: bool,
+    /// Bright spots at half resolution, then bloom once blurred back up.
+    chain: DownsampleChain,
+    /// Ghosts and halo, at the size of the second level of the chain.
+    flare: Option<Framebuffer>,
+    dt: f32,
+
+    params: Params,
+}
+
+impl SunScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let ground = city(&mut rng);
+        let dirt = lens_dirt(&mut rng);
+
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        unsafe {
+            let sun_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_SUN)?;
+            let adapt_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_SUN_ADAPT)?;
+            let bright_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_SUN_BRIGHT)?;
+            let flare_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_SUN_FLARE)?;
+            let tonemap_shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_SUN_TONEMAP)?;
+            let uniform = |shader: GLuint, name: &std::ffi::CStr| {
+                gl::GetUniformLocation(shader, name.as_ptr())
+            };
+
+            gl::UseProgram(sun_shader);
+            gl::Uniform1i(uniform(sun_shader, c"u_ground"), 0);
+            gl::Uniform1f(uniform(sun_shader, c"u_field"), FIELD);
+            gl::UseProgram(adapt_shader);
+            gl::Uniform1i(uniform(adapt_shader, c"u_hdr"), 0);
+            gl::UseProgram(bright_shader);
+            gl::Uniform1i(uniform(bright_shader, c"u_hdr"), 0);
+            gl::Uniform1i(uniform(bright_shader, c"u_adapted"), 1);
+            gl::UseProgram(flare_shader);
+            gl::Uniform1i(uniform(flare_shader, c"u_bright"), 0);
+            gl::UseProgram(tonemap_shader);
+            let units = [c"u_hdr", c"u_adapted", c"u_bloom", c"u_flare", c"u_dirt"];
+            for (unit, name) in units.into_iter().enumerate() {
+                gl::Uniform1i(uniform(tonemap_shader, name), unit as GLint);
+            }
+            gl::Uniform1f(uniform(tonemap_shader, c"u_peak"), output::PEAK);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            // every shader shares screen.vert, so the same locations
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(sun_shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            let mut ground_texture = 0;
+            gl::GenTextures(1, &mut ground_texture);
+            gl::BindTexture(gl::TEXTURE_2D, ground_texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA16F as GLint,
+                GROUND_TEXELS as GLsizei,
+                GROUND_TEXELS as GLsizei,
+                0,
+                gl::RGBA,
+                gl::FLOAT,
+                ground.as_ptr().cast(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gpu_memory::record(
+                Object::Texture(ground_texture),
+                Category::Textures,
+                GROUND_TEXELS * GROUND_TEXELS * 8,
+            );
+
+            let mut dirt_texture = 0;
+            gl::GenTextures(1, &mut dirt_texture);
+            upload_texture(
+                dirt_texture,
+                DIRT_TEXELS as u32,
+                DIRT_TEXELS as u32,
+                dirt.as_ptr().cast(),
+                gl::CLAMP_TO_EDGE,
+            )?;
+
+            let adapted = create_float_framebuffer("sun adaptation", UVec2::ONE, gl::R32F)?;
+            let chain = DownsampleChain::new("sun bloom", UVec2::ZERO, BLOOM_LEVELS, gl::RGBA16F)?;
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            Ok(Self {
+                viewport: UVec2::ZERO,
+
+                u_inverse: uniform(sun_shader, c"u_inverse"),
+                u_sun: uniform(sun_shader, c"u_sun"),
+                u_sky_height: uniform(sun_shader, c"u_sky_height"),
+                u_viewport: uniform(sun_shader, c"u_viewport"),
+                sun_shader,
+
+                u_lod: uniform(adapt_shader, c"u_lod"),
+                adapt_shader,
+
+                u_adapt_bright: uniform(bright_shader, c"u_adapt"),
+                bright_shader,
+
+                u_ghosts: uniform(flare_shader, c"u_ghosts"),
+                u_halo: uniform(flare_shader, c"u_halo"),
+                u_aspect_flare: uniform(flare_shader, c"u_aspect"),
+                flare_shader,
+
+                u_adapt: uniform(tonemap_shader, c"u_adapt"),
+                u_exposure: uniform(tonemap_shader, c"u_exposure"),
+                u_hdr_output: uniform(tonemap_shader, c"u_hdr_output"),
+                u_bloom_intensity: uniform(tonemap_shader, c"u_bloom_intensity"),
+                u_starburst: uniform(tonemap_shader, c"u_starburst"),
+                u_dirt_intensity: uniform(tonemap_shader, c"u_dirt_intensity"),
+                u_rotation: uniform(tonemap_shader, c"u_rotation"),
+                u_aspect: uniform(tonemap_shader, c"u_aspect"),
+                tonemap_shader,
+
+                vao,
+                vbo,
+                ground: ground_texture,
+                dirt: dirt_texture,
+
+                hdr: None,
+                adapted,
+                has_adapted: false,
+                chain,
+                flare: None,
+                dt: 0.0,
+
+                params: Params::new()
+                    .with_f32("time", 9.0, 0.0, 24.0, 0.5)
+                    .with_bool("running", true)
+                    .with_f32("day length", 60.0, 10.0, 600.0, 10.0)
+                    .with_bool("adaptation", true)
+                    .with_f32("exposure", 0.0, -4.0, 4.0, 0.5)
+                    .with_bool("sky", true)
+                    .with_f32("bloom", 0.6, 0.0, 2.0, 0.1)
+                    .with_f32("ghosts", 1.0, 0.0, 2.0, 0.1)
+                    .with_f32("halo", 1.0, 0.0, 2.0, 0.1)
+                    .with_f32("starburst", 1.0, 0.0, 2.0, 0.1)
+                    .with_f32("dirt", 1.0, 0.0, 2.0, 0.1),
+            })
+        }
+    }
+}
+
+impl Scene for SunScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("t") => self.params.step("time", 1).unwrap(),
+            Key::Character("T") => self.params.step("time", -1).unwrap(),
+            Key::Character("p" | "P") => self.params.toggle("running").unwrap(),
+            Key::Character("l") => self.params.step("day length", 1).unwrap(),
+            Key::Character("L") => self.params.step("day length", -1).unwrap(),
+            Key::Character("a" | "A") => self.params.toggle("adaptation").unwrap(),
+            Key::Character("e") => self.params.step("exposure", 1).unwrap(),
+            Key::Character("E") => self.params.step("exposure", -1).unwrap(),
+            Key::Character("k" | "K") => self.params.toggle("sky").unwrap(),
+            Key::Character("b") => self.params.step("bloom", 1).unwrap(),
+            Key::Character("B") => self.params.step("bloom", -1).unwrap(),
+            Key::Character("g") => self.params.step("ghosts", 1).unwrap(),
+            Key::Character("G") => self.params.step("ghosts", -1).unwrap(),
+            Key::Character("h") => self.params.step("halo", 1).unwrap(),
+            Key::Character("H") => self.params.step("halo", -1).unwrap(),
+            Key::Character("s") => self.params.step("starburst", 1).unwrap(),
+            Key::Character("S") => self.params.step("starburst", -1).unwrap(),
+            Key::Character("d") => self.params.step("dirt", 1).unwrap(),
+            Key::Character("D") => self.params.step("dirt", -1).unwrap(),
+            _ => return,
+        }
+
+        println!("sun config: {}", self.params);
+    }
+
+    /// Moves the time of day along, wrapping around at midnight.
+    fn update(&mut self, dt: f32) {
+        self.dt += dt;
+        if !self.params.bool("running") {
+            return;
+        }
+
+        let hours = self.params.f32("time") + dt / self.params.f32("day length") * 24.0;
+        let hours = hours.rem_euclid(24.0);
+        self.params
+            .set("time", Value::Number(hours as f64))
+            .unwrap();
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("sun draw");
+
+        let (Some(hdr), Some(flare)) = (&self.hdr, &self.flare) else {
+            return Ok(());
+        };
+        let size = self.viewport.as_ivec2();
+        let sun = sun_direction(self.params.f32("time"));
+        let inverse = camera.matrix(self.viewport.as_vec2()).inverse();
+        let sky_height = if self.params.bool("sky") {
+            (self.viewport.y as f32 * SKY_FRACTION).round()
+        } else {
+            0.0
+        };
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
+
+            push_debug_group(c"Sun lighting");
+            gl::BindFramebuffer(gl::FRAMEBUFFER, hdr.fbo);
+            gl::Viewport(0, 0, size.x, size.y);
+            gl::UseProgram(self.sun_shader);
+            gl::UniformMatrix4fv(self.u_inverse, 1, gl::FALSE, inverse.as_ref().as_ptr());
+            gl::Uniform3f(self.u_sun, sun.x, sun.y, sun.z);
+            gl::Uniform1f(self.u_sky_height, sky_height);
+            gl::Uniform2f(self.u_viewport, size.x as f32, size.y as f32);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.ground);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            pop_debug_group();
+
+            push_debug_group(c"Exposure adaptation");
+            gl::BindTexture(gl::TEXTURE_2D, hdr.texture);
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+
+            // eases the adapted luminance toward this frame's average by
+            // blending with a constant alpha
+            let t = if self.has_adapted {
+                1.0 - (-ADAPTATION_SPEED * self.dt).exp()
+            } else {
+                1.0
+            };
+            self.has_adapted = true;
+            self.dt = 0.0;
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.adapted.fbo);
+            gl::Viewport(0, 0, 1, 1);
+            gl::Enable(gl::BLEND);
+            gl::BlendEquation(gl::FUNC_ADD);
+            gl::BlendFunc(gl::CONSTANT_ALPHA, gl::ONE_MINUS_CONSTANT_ALPHA);
+            gl::BlendColor(0.0, 0.0, 0.0, t);
+            gl::UseProgram(self.adapt_shader);
+            let lod = size.max_element().max(1).ilog2();
+            gl::Uniform1f(self.u_lod, lod as f32);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::Disable(gl::BLEND);
+            pop_debug_group();
+
+            let adapt = self.params.bool("adaptation") as GLint;
+            let aspect = size.x as f32 / size.y as f32;
+
+            push_debug_group(c"Bright pass");
+            let bright = self.chain.level(0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, bright.fbo);
+            gl::Viewport(0, 0, bright.size.x as GLint, bright.size.y as GLint);
+            gl::UseProgram(self.bright_shader);
+            gl::Uniform1i(self.u_adapt_bright, adapt);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, self.adapted.texture);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, hdr.texture);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            pop_debug_group();
+
+            self.chain.downsample(BLOOM_LEVELS - 1, 1.0);
+
+            // before upsampling overwrites the level
+            push_debug_group(c"Lens flare");
+            gl::BindFramebuffer(gl::FRAMEBUFFER, flare.fbo);
+            gl::Viewport(0, 0, flare.size.x as GLint, flare.size.y as GLint);
+            gl::UseProgram(self.flare_shader);
+            gl::Uniform1f(self.u_ghosts, self.params.f32("ghosts"));
+            gl::Uniform1f(self.u_halo, self.params.f32("halo"));
+            gl::Uniform1f(self.u_aspect_flare, aspect);
+            gl::BindVertexArray(self.vao);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.chain.level(FLARE_LEVEL).texture);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            pop_debug_group();
+
+            self.chain.upsample(BLOOM_LEVELS - 1, 0.5);
+
+            push_debug_group(c"Tonemapping");
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(0, 0, size.x, size.y);
+            gl::UseProgram(self.tonemap_shader);
+            gl::Uniform1i(self.u_adapt, adapt);
+            gl::Uniform1f(self.u_exposure, self.params.f32("exposure"));
+            gl::Uniform1i(self.u_hdr_output, output::hdr_uniform());
+            gl::Uniform1f(self.u_bloom_intensity, self.params.f32("bloom"));
+            gl::Uniform1f(self.u_starburst, self.params.f32("starburst"));
+            gl::Uniform1f(self.u_dirt_intensity, self.params.f32("dirt"));
+            // the starburst turns with the camera and as the sun goes around
+            let rotation = camera.rotation + sun.y.atan2(sun.x);
+            gl::Uniform1f(self.u_rotation, rotation);
+            gl::Uniform1f(self.u_aspect, aspect);
+
+            let textures = [
+                hdr.texture,
+                self.adapted.texture,
+                self.chain.level(0).texture,
+                flare.texture,
+                self.dirt,
+            ];
+            for (unit, texture) in textures.into_iter().enumerate().rev() {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as GLuint);
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+            }
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            for unit in (0..textures.len()).rev() {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as GLuint);
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+            }
+            pop_debug_group();
+
+            gl::BindVertexArray(0);
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, _camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        let viewport = uvec2(width as u32, height as u32);
+        if viewport == self.viewport {
+            return Ok(());
+        }
+        self.viewport = viewport;
+
+        unsafe {
+            for framebuffer in self.hdr.take().into_iter().chain(self.flare.take()) {
+                delete_framebuffer(&framebuffer);
+            }
+            self.chain.resize(viewport / 2)?;
+            if viewport.min_element() == 0 || self.chain.len() <= FLARE_LEVEL {
+                return Ok(());
+            }
+
+            let hdr = create_float_framebuffer("sun hdr", viewport, gl::RGBA16F)?;
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as GLint,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            self.hdr = Some(hdr);
+            let flare_size = self.chain.level(1).size;
+            self.flare = Some(create_float_framebuffer(
+                "sun flare",
+                flare_size,
+                gl::RGBA16F,
+            )?);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            Ok(())
+        }
+    }
+}
+
+impl Drop for SunScene {
+    fn drop(&mut self) {
+        unsafe {
+            for framebuffer in self.hdr.iter().chain(&self.flare) {
+                delete_framebuffer(framebuffer);
+            }
+            delete_framebuffer(&self.adapted);
+            gl::DeleteProgram(self.sun_shader);
+            gl::DeleteProgram(self.adapt_shader);
+            gl::DeleteProgram(self.bright_shader);
+            gl::DeleteProgram(self.flare_shader);
+            gl::DeleteProgram(self.tonemap_shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+            delete_textures(&[self.ground, self.dirt]);
+        }
+    }
+}
+
+/// Albedo and height of every texel of a city of blocks of buildings and
+/// parks, taller downtown, on a grid of streets.
+fn city(rng: &mut impl Rng) -> Vec<[f32; 4]> {
+    let mut texels = vec![[ROAD[0], ROAD[1], ROAD[2], 0.0]; GROUND_TEXELS * GROUND_TEXELS];
+    let mut fill = |min: Vec2, max: Vec2, albedo: [f32; 3], height: f32| {
+        let to_texel = |world: Vec2| ((world + FIELD) * TEXELS_PER_UNIT).as_uvec2();
+        let (min, max) = (to_texel(min), to_texel(max));
+        for y in min.y..max.y.min(GROUND_TEXELS as u32) {
+            for x in min.x..max.x.min(GROUND_TEXELS as u32) {
+                let [r, g, b] = albedo;
+                texels[y as usize * GROUND_TEXELS + x as usize] = [r, g, b, height];
+            }
+        }
+    };
+
+    // grass all around, so that the edges of the texture stretch out as such
+    fill(Vec2::splat(-FIELD), Vec2::splat(FIELD), GRASS, 0.0);
+    let border = BLOCK * 0.5;
+    fill(
+        Vec2::splat(-FIELD + border),
+        Vec2::splat(FIELD - border),
+        ROAD,
+        0.0,
+    );
+
+    let blocks = ((FIELD - border) * 2.0 / BLOCK) as i32;
+    for by in 0..blocks {
+        for bx in 0..blocks {
+            let corner = Vec2::splat(-FIELD + border) + vec2(bx as f32, by as f32) * BLOCK;
+            let lot_min = corner + STREET * 0.5;
+            let lot_max = corner + BLOCK - STREET * 0.5;
+            fill(lot_min, lot_max, PAVEMENT, 0.0);
+            let (lot_min, lot_max) = (lot_min + SIDEWALK, lot_max - SIDEWALK);
+
+            if rng.gen_bool(0.12) {
+                fill(lot_min, lot_max, GRASS, 0.0);
+                for _ in 0..rng.gen_range(6..14) {
+                    let radius = rng.gen_range(8.0..14.0);
+                    let center = vec2(
+                        rng.gen_range(lot_min.x + radius..lot_max.x - radius),
+                        rng.gen_range(lot_min.y + radius..lot_max.y - radius),
+                    );
+                    let height = rng.gen_range(25.0..40.0);
+                    fill(center - radius, center + radius, TREE, height);
+                }
+                continue;
+            }
+
+            // taller buildings toward the center of the city
+            let center = (lot_min + lot_max) * 0.5;
+            let downtown = (-(center.length() / 1000.0).powi(2)).exp();
+            let cells = uvec2(rng.gen_range(1..=3), rng.gen_range(1..=3));
+            let cell_size = (lot_max - lot_min) / cells.as_vec2();
+            for cy in 0..cells.y {
+                for cx in 0..cells.x {
+                    let cell_min = lot_min + vec2(cx as f32, cy as f32) * cell_size;
+                    let inset = vec2(rng.gen_range(2.0..10.0), rng.gen_range(2.0..10.0));
+                    let height = MIN_HEIGHT
+                        + rng.gen_range(0.0..60.0)
+                        + DOWNTOWN_HEIGHT * downtown * rng.gen_range(0.3..1.0);
+                    let gray = rng.gen_range(0.18..0.45);
+                    let albedo = [0; 3].map(|_| gray + rng.gen_range(-0.04..0.04));
+                    fill(
+                        cell_min + inset,
+                        cell_min + cell_size - inset,
+                        albedo,
+                        height,
+                    );
+                }
+            }
+        }
+    }
+
+    texels
+}
+
+/// Gray levels of dust and smudges on a lens: soft specks, dried droplets
+/// brighter at their rim, and a few wide smears.
+fn lens_dirt(rng: &mut impl Rng) -> Vec<[u8; 4]> {
+    let mut dirt = vec![0.0f32; DIRT_TEXELS * DIRT_TEXELS];
+    let size = DIRT_TEXELS as f32;
+    let mut stamp = |center: Vec2, radii: Vec2, angle: f32, shape: &dyn Fn(f32) -> f32| {
+        let reach = radii.max_element();
+        let min = (center - reach).max(Vec2::ZERO).as_uvec2();
+        let max = (center + reach).min(Vec2::splat(size - 1.0)).as_uvec2();
+        let rotation = Vec2::from_angle(-angle);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let local = rotation.rotate(vec2(x as f32, y as f32) - center) / radii;
+                dirt[y as usize * DIRT_TEXELS + x as usize] += shape(local.length());
+            }
+        }
+    };
+
+    for _ in 0..400 {
+        let center = vec2(rng.gen_range(0.0..size), rng.gen_range(0.0..size));
+        let radius = rng.gen_range(1.5..10.0);
+        let strength = rng.gen_range(0.05..0.4);
+        stamp(center, Vec2::splat(radius), 0.0, &|d| {
+            strength * (1.0 - d).clamp(0.0, 1.0).powf(0.5)
+        });
+    }
+    for _ in 0..40 {
+        let center = vec2(rng.gen_range(0.0..size), rng.gen_range(0.0..size));
+        let radius = rng.gen_range(8.0..24.0);
+        let strength = rng.gen_range(0.1..0.35);
+        stamp(center, Vec2::splat(radius), 0.0, &|d| {
+            let rim = (-((d - 0.9) * 8.0).powi(2)).exp();
+            strength * (0.3 * (d < 1.0) as u8 as f32 + rim)
+        });
+    }
+    for _ in 0..6 {
+        let center = vec2(rng.gen_range(0.0..size), rng.gen_range(0.0..size));
+        let radii = vec2(rng.gen_range(60.0..140.0), rng.gen_range(10.0..30.0));
+        let angle = rng.gen_range(0.0..TAU);
+        let strength = rng.gen_range(0.05..0.12);
+        stamp(center, radii, angle, &|d| strength * (1.0 - d * d).max(0.0));
+    }
+
+    dirt.into_iter()
+        .map(|gray| [(gray.min(1.0) * 255.0) as u8; 3])
+        .map(|[r, g, b]| [r, g, b, 255])
+        .collect()
+}
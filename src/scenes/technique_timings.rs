@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+use crate::gpu_timer::GpuTimer;
+use crate::pipeline_stats::PipelineStats;
+
+/// GPU and CPU times of the techniques a scene compares, summarized once per
+/// second, along with the work they give the GPU where pipeline statistics
+/// are available.
+pub struct TechniqueTimings {
+    names: &'static [&'static str],
+    gpu_timer: GpuTimer,
+    /// `None` without pipeline statistics queries.
+    stats: Option<PipelineStats>,
+    /// CPU time spent in each technique since the last summary.
+    cpu_times: Vec<Duration>,
+    current: Option<(usize, Instant)>,
+    frames: u32,
+    last_summary: Instant,
+}
+
+impl TechniqueTimings {
+    pub unsafe fn new(names: &'static [&'static str]) -> Self {
+        Self {
+            names,
+            gpu_timer: GpuTimer::new(),
+            stats: PipelineStats::new(),
+            cpu_times: vec![Duration::ZERO; names.len()],
+            current: None,
+            frames: 0,
+            last_summary: Instant::now(),
+        }
+    }
+
+    /// Starts timing technique `index` on the CPU and GPU.
+    pub unsafe fn begin(&mut self, index: usize) {
+        self.gpu_timer.begin(self.names[index]);
+        if let Some(stats) = &mut self.stats {
+            stats.begin(self.names[index]);
+        }
+        self.current = Some((index, Instant::now()));
+    }
+
+    pub unsafe fn end(&mut self) {
+        let (index, start) = self.current.take().expect("end called without begin");
+        self.cpu_times[index] += start.elapsed();
+        if let Some(stats) = &mut self.stats {
+            stats.end();
+        }
+        self.gpu_timer.end();
+    }
+
+    /// Call once per frame. Returns a summary of the techniques used during
+    /// the last second, once per second.
+    pub unsafe fn end_frame(&mut self) -> Option<String> {
+        self.gpu_timer.poll();
+        if let Some(stats) = &mut self.stats {
+            stats.poll();
+        }
+        self.frames += 1;
+
+        if self.last_summary.elapsed() < Duration::from_secs(1) {
+            return None;
+        }
+
+        let mut stats = (self.stats.as_mut())
+            .map(PipelineStats::take_averages)
+            .unwrap_or_default();
+        let timings = (self.names.iter().zip(&self.cpu_times))
+            .filter(|(_, cpu_time)| !cpu_time.is_zero())
+            .map(|(name, cpu_time)| {
+                let cpu_ms = cpu_time.as_secs_f32() * 1000.0 / self.frames as f32;
+                let mut timing = match self.gpu_timer.duration(name) {
+                    Some(gpu_ms) => format!("{name} {gpu_ms:.2} ms gpu, {cpu_ms:.2} ms cpu"),
+                    None => format!("{name} ? ms gpu, {cpu_ms:.2} ms cpu"),
+                };
+                if let Some(stats) = stats.remove(name) {
+                    timing += &format!(", {stats}");
+                }
+                timing
+            })
+            .collect::<Vec<_>>();
+
+        self.cpu_times.fill(Duration::ZERO);
+        self.frames = 0;
+        self.last_summary = Instant::now();
+
+        Some(timings.join(" | "))
+    }
+}
+
+/// Safe versions of the above for scenes written with the safe wrappers of
+/// `common_gl` only, which take a current context for granted the same way.
+impl TechniqueTimings {
+    pub fn create(names: &'static [&'static str]) -> Self {
+        unsafe { Self::new(names) }
+    }
+
+    /// Times what `f` does as technique `index`.
+    pub fn time<R>(&mut self, index: usize, f: impl FnOnce() -> R) -> R {
+        unsafe { self.begin(index) };
+        let result = f();
+        unsafe { self.end() };
+        result
+    }
+
+    /// [`Self::end_frame`].
+    pub fn summarize(&mut self) -> Option<String> {
+        unsafe { self.end_frame() }
+    }
+}
@@ -0,0 +1,372 @@
+use std::mem;
+
+use glam::{uvec2, vec2, UVec2, Vec2};
+use winit::dpi::PhysicalSize;
+use winit::keyboard::{Key, NamedKey, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    bind_framebuffer, bind_texture, set_viewport, Attrib, Buffer, DebugGroup, Program,
+    RenderTarget, Texture, Uniform, VertexArray,
+};
+use crate::error::GlError;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::text::SharedText;
+use crate::theme;
+
+use super::technique_timings::TechniqueTimings;
+use super::{Scene, SRC_FRAG_VRS_COMPOSITE, SRC_FRAG_VRS_SHADE, SRC_VERT_SCREEN};
+
+/// Both ways of drawing the scene, which are also the names of their timings.
+const TECHNIQUES: &[&str] = &["full rate", "variable rate"];
+
+/// Shading rates, as the side of the block of pixels a fragment covers, each
+/// with a target of its own.
+const RATES: [u32; 3] = [1, 2, 4];
+/// Variants of the `coarsest` parameter, the coarsest rate tiles may get.
+const COARSEST: &[&str] = &["2x2", "4x4"];
+
+/// Side of a tile of the rate map, in pixels. A multiple of twice the
+/// coarsest rate, see `vrs-shade.frag`.
+const TILE: u32 = 16;
+
+const TEXT_SIZE: f32 = 14.0;
+const MARGIN: f32 = 16.0;
+const PADDING: f32 = 8.0;
+
+const LAYOUT: &[Attrib] = &[
+    Attrib {
+        name: c"position",
+        components: 2,
+        offset: 0,
+    },
+    Attrib {
+        name: c"uv",
+        components: 2,
+        offset: mem::size_of::<Vec2>(),
+    },
+];
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+impl Vertex {
+    const fn new(position: Vec2, uv: Vec2) -> Self {
+        Self { position, uv }
+    }
+}
+
+#[rustfmt::skip]
+const SCREEN_VERTICES: &[Vertex] = &[
+    Vertex::new(vec2(-1.0,  1.0), vec2(0.0, 1.0)),
+    Vertex::new(vec2(-1.0, -1.0), vec2(0.0, 0.0)),
+    Vertex::new(vec2( 1.0, -1.0), vec2(1.0, 0.0)),
+    Vertex::new(vec2(-1.0,  1.0), vec2(0.0, 1.0)),
+    Vertex::new(vec2( 1.0, -1.0), vec2(1.0, 0.0)),
+    Vertex::new(vec2( 1.0,  1.0), vec2(1.0, 1.0)),
+];
+
+/// Variable rate shading without the hardware for it: a fragment shader
+/// heavy enough to be worth shading less of, with the screen cut into tiles
+/// that each get a rate from how far they are from the mouse, standing in
+/// for where the eye looks.
+///
+/// Every rate is shaded into a target that much smaller on each side, over
+/// the tiles with that rate only, and the picture is put together from the
+/// three with each pixel taking the fragment of its block. The fragments of
+/// the other tiles are still started to be discarded, which hardware would
+/// skip, so the savings are a little below the share of pixels not shaded.
+pub struct VariableRateScene {
+    viewport: Vec2,
+    time: f32,
+    /// Middle of the finest tiles, in pixels from the bottom left corner.
+    fovea: Vec2,
+
+    shade_shader: Program,
+    u_variable: Uniform,
+    u_rate: Uniform,
+    u_time: Uniform,
+    u_octaves: Uniform,
+
+    composite_shader: Program,
+    u_overlay: Uniform,
+
+    vao: VertexArray,
+    _vbo: Buffer<Vertex>,
+
+    /// The rate of every tile, in the red of an RGBA8 texel.
+    rate_map: Vec<u8>,
+    rates: Texture,
+    /// Share of the pixels of the picture shaded with the current map.
+    shaded: f32,
+
+    targets: Option<Targets>,
+    timings: TechniqueTimings,
+
+    text: Option<SharedText>,
+
+    params: Params,
+}
+
+impl VariableRateScene {
+    pub const RENDER_STATE: RenderState = RenderState::OPAQUE;
+
+    pub fn new(size: PhysicalSize<u32>, text: Option<SharedText>) -> Result<Self, GlError> {
+        let shade_shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_VRS_SHADE)?;
+        let composite_shader = Program::new(SRC_VERT_SCREEN, SRC_FRAG_VRS_COMPOSITE)?;
+        for shader in [&shade_shader, &composite_shader] {
+            shader.set(shader.uniform(c"u_tile"), TILE as i32);
+            shader.set(shader.uniform(c"u_rates"), 3);
+        }
+        for (name, unit) in [(c"u_full", 0), (c"u_half", 1), (c"u_quarter", 2)] {
+            composite_shader.set(composite_shader.uniform(name), unit);
+        }
+
+        let vbo = Buffer::new(gl::ARRAY_BUFFER, SCREEN_VERTICES, gl::STATIC_DRAW);
+        let mut vao = VertexArray::new();
+        // both share screen.vert, so their attributes are at the same
+        // locations
+        vao.set_layout(&shade_shader, &vbo, LAYOUT);
+
+        let params = Params::new()
+            .with_bool("variable rate", true)
+            .with_bool("overlay", false)
+            .with_f32("fovea", 160.0, 40.0, 1000.0, 20.0)
+            .with_enum("coarsest", 1, COARSEST)
+            .with_i32("octaves", 8, 2, 16)
+            .with_f32("speed", 1.0, 0.0, 4.0, 0.25);
+
+        let size = uvec2(size.width, size.height).max(UVec2::ONE);
+        let mut scene = Self {
+            viewport: size.as_vec2(),
+            time: 0.0,
+            fovea: size.as_vec2() * 0.5,
+
+            u_variable: shade_shader.uniform(c"u_variable"),
+            u_rate: shade_shader.uniform(c"u_rate"),
+            u_time: shade_shader.uniform(c"u_time"),
+            u_octaves: shade_shader.uniform(c"u_octaves"),
+            shade_shader,
+
+            u_overlay: composite_shader.uniform(c"u_overlay"),
+            composite_shader,
+
+            vao,
+            _vbo: vbo,
+
+            rate_map: Vec::new(),
+            rates: Texture::new(UVec2::ONE, &[1, 0, 0, 0], gl::CLAMP_TO_EDGE)?,
+            shaded: 1.0,
+
+            targets: None,
+            timings: TechniqueTimings::create(TECHNIQUES),
+
+            text,
+
+            params,
+        };
+        scene.resize_targets(size)?;
+        Ok(scene)
+    }
+
+    /// Recreates the target of every rate if the size changed.
+    fn resize_targets(&mut self, size: UVec2) -> Result<(), GlError> {
+        if (self.targets.as_ref()).is_some_and(|targets| targets.size == size) {
+            return Ok(());
+        }
+
+        self.targets = None;
+        self.targets = Some(Targets::new(size)?);
+        bind_framebuffer(None);
+        Ok(())
+    }
+
+    /// Gives every tile its rate from its distance to the fovea, finest
+    /// within the fovea's radius, then a rate coarser every radius further,
+    /// and uploads the map if it changed.
+    fn update_rate_map(&mut self) -> Result<(), GlError> {
+        let size = self.viewport.as_uvec2().max(UVec2::ONE);
+        let tiles = size.map(|n| n.div_ceil(TILE));
+        let radius = self.params.f32("fovea");
+        let coarsest = match COARSEST[self.params.variant("coarsest")] {
+            "2x2" => 2,
+            _ => 4,
+        };
+
+        let mut rate_map = Vec::with_capacity((tiles.x * tiles.y * 4) as usize);
+        let mut shaded = 0.0;
+        for y in 0..tiles.y {
+            for x in 0..tiles.x {
+                let min = uvec2(x, y) * TILE;
+                let max = (min + TILE).min(size);
+                let middle = (min + max).as_vec2() * 0.5;
+
+                let rings = (middle.distance(self.fovea) / radius) as usize;
+                let rate = RATES[rings.min(RATES.len() - 1)].min(coarsest);
+                rate_map.extend_from_slice(&[rate as u8, 0, 0, 255]);
+
+                let pixels = (max - min).element_product() as f32;
+                shaded += pixels / (rate * rate) as f32;
+            }
+        }
+        self.shaded = shaded / size.element_product() as f32;
+
+        if rate_map != self.rate_map {
+            (self.rates).upload(tiles, &rate_map, gl::CLAMP_TO_EDGE)?;
+            self.rate_map = rate_map;
+        }
+        Ok(())
+    }
+
+    /// Shades the pattern over the bound framebuffer, at `rate`, over its
+    /// tiles only with `variable`.
+    fn shade(&self, variable: bool, rate: u32) {
+        let shader = &self.shade_shader;
+        shader.set(self.u_variable, variable);
+        shader.set(self.u_rate, rate as i32);
+        shader.set(self.u_time, self.time);
+        shader.set(self.u_octaves, self.params.i32("octaves"));
+        self.vao.draw_arrays(gl::TRIANGLES);
+    }
+
+    /// Shades every rate into its target, then puts them together over the
+    /// window.
+    fn draw_variable_rate(&self) {
+        let targets = self.targets.as_ref().unwrap();
+        bind_texture(3, self.rates.id());
+
+        let coarsest = self.rate_map.chunks(4).map(|texel| texel[0]).max();
+        for (rate, target) in RATES.into_iter().zip(&targets.levels) {
+            if coarsest.is_some_and(|coarsest| rate > coarsest as u32) {
+                continue;
+            }
+            target.bind();
+            self.shade(true, rate);
+        }
+
+        bind_framebuffer(None);
+        set_viewport(targets.size);
+        for (unit, target) in targets.levels.iter().enumerate() {
+            bind_texture(unit as u32, target.texture);
+        }
+        let overlay = self.params.bool("overlay");
+        self.composite_shader.set(self.u_overlay, overlay);
+        self.vao.draw_arrays(gl::TRIANGLES);
+    }
+
+    fn label(&self) -> String {
+        if !self.params.bool("variable rate") {
+            return "Full rate".to_string();
+        }
+        let percent = self.shaded * 100.0;
+        format!("Variable rate, {percent:.0}% of the pixels shaded")
+    }
+}
+
+impl Scene for VariableRateScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("r" | "R") => self.params.toggle("variable rate").unwrap(),
+            Key::Character("o" | "O") => self.params.toggle("overlay").unwrap(),
+            Key::Named(NamedKey::ArrowUp) => self.params.step("fovea", 1).unwrap(),
+            Key::Named(NamedKey::ArrowDown) => self.params.step("fovea", -1).unwrap(),
+            Key::Character("c" | "C") => self.params.step("coarsest", 1).unwrap(),
+            Key::Character("d") => self.params.step("octaves", 1).unwrap(),
+            Key::Character("D") => self.params.step("octaves", -1).unwrap(),
+            Key::Character("v") => self.params.step("speed", 1).unwrap(),
+            Key::Character("V") => self.params.step("speed", -1).unwrap(),
+            _ => return,
+        }
+
+        println!("variable rate config: {}", self.params);
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.time += dt * self.params.f32("speed");
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, _camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("variable rate draw");
+
+        self.resize_targets(self.viewport.as_uvec2().max(UVec2::ONE))?;
+
+        // the mouse is from the top left corner, the tiles from the bottom left
+        self.fovea = vec2(mouse_pos.x, self.viewport.y - mouse_pos.y);
+        self.update_rate_map()?;
+
+        let variable = self.params.bool("variable rate");
+        {
+            let _group = DebugGroup::push(c"Variable rate");
+            unsafe { self.timings.begin(variable as usize) };
+            if variable {
+                self.draw_variable_rate();
+            } else {
+                bind_framebuffer(None);
+                set_viewport(self.viewport.as_uvec2());
+                self.shade(false, 1);
+            }
+            unsafe { self.timings.end() };
+        }
+
+        if let Some(summary) = self.timings.summarize() {
+            println!("variable rate: {summary}");
+        }
+
+        let label = self.label();
+        let theme = theme::current();
+        let Some(text) = &self.text else {
+            return Ok(());
+        };
+        let mut text = text.borrow_mut();
+        let extent = text.measure(&label, TEXT_SIZE, None);
+        let min = Vec2::splat(MARGIN);
+        let max = min + extent + PADDING * 2.0;
+        text.queue_rect(min, max, theme.hud_background);
+        text.queue(&label, min + PADDING, TEXT_SIZE, None, theme.hud_text);
+        unsafe { text.draw(0, self.viewport) };
+        Ok(())
+    }
+
+    fn resize(&mut self, _camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        self.viewport = Vec2::new(width as f32, height as f32);
+        set_viewport(self.viewport.as_uvec2());
+        Ok(())
+    }
+}
+
+/// What each rate is shaded into before the window.
+struct Targets {
+    /// Size of the whole picture.
+    size: UVec2,
+    /// One per rate of [`RATES`], that much smaller on each side.
+    levels: [RenderTarget; 3],
+}
+
+impl Targets {
+    fn new(size: UVec2) -> Result<Self, GlError> {
+        let level = |rate: u32| RenderTarget::new("variable rate", size.map(|n| n.div_ceil(rate)));
+        Ok(Self {
+            size,
+            levels: [level(RATES[0])?, level(RATES[1])?, level(RATES[2])?],
+        })
+    }
+}
@@ -0,0 +1,679 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{Mat4, UVec2, Vec2, Vec4};
+use winit::dpi::PhysicalSize;
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_shader_program, delete_buffers, delete_textures, upload_texture,
+};
+use crate::error::GlError;
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::{Scene, SRC_FRAG_VIRTUAL_TEXTURE, SRC_VERT_VIRTUAL_TEXTURE};
+
+/// Side of a tile, in texels.
+const TILE_TEXELS: u32 = 128;
+/// Side of the virtual texture at its finest level: a terapixel.
+const VIRTUAL_TEXELS: u32 = 1 << 20;
+/// Level at which the whole texture fits in a single tile.
+const MAX_LEVEL: u32 = (VIRTUAL_TEXELS / TILE_TEXELS).ilog2();
+
+/// Side of the square the texture covers in the world.
+const WORLD_SIZE: f32 = 4096.0;
+
+/// Tiles per side of the physical cache texture.
+const CACHE_SLOTS: u32 = 24;
+/// Tiles uploaded per frame at most, so that streaming never stalls a frame.
+const MAX_UPLOADS_PER_FRAME: usize = 8;
+
+/// A tile of the virtual texture. Level 0 is the finest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct TileId {
+    level: u32,
+    x: u32,
+    y: u32,
+}
+
+impl TileId {
+    const ROOT: Self = Self {
+        level: MAX_LEVEL,
+        x: 0,
+        y: 0,
+    };
+
+    /// Tiles per side at a level.
+    fn tiles_per_side(level: u32) -> u32 {
+        1 << (MAX_LEVEL - level)
+    }
+
+    fn parent(self) -> Option<Self> {
+        (self.level < MAX_LEVEL).then(|| Self {
+            level: self.level + 1,
+            x: self.x / 2,
+            y: self.y / 2,
+        })
+    }
+
+    /// Rectangle of the tile in `[0, 1]²` texture space, as `(x, y, w, h)`.
+    fn rect(self) -> Vec4 {
+        let size = 1.0 / Self::tiles_per_side(self.level) as f32;
+        Vec4::new(self.x as f32 * size, self.y as f32 * size, size, size)
+    }
+}
+
+/// A huge virtual texture, a Mandelbrot set rendered on demand, streamed in
+/// tiles depending on what the camera sees.
+///
+/// Tiles are generated on worker threads and uploaded into a fixed-size cache
+/// texture, evicting the least recently used ones. A visible tile that isn't
+/// resident yet is drawn from the closest resident ancestor, so the view
+/// sharpens as tiles stream in. The root tile is generated upfront and never
+/// evicted, so there is always something to draw.
+///
+/// This is the software fallback of sparse textures: no `ARB_sparse_texture`,
+/// the page table lives on the CPU and every tile is its own instance.
+pub struct VirtualTextureScene {
+    matrix: Mat4,
+    viewport: Vec2,
+
+    shader: GLuint,
+    u_mvp: GLint,
+    u_debug: GLint,
+    vao: GLuint,
+    instance_vbo: GLuint,
+    cache_texture: GLuint,
+
+    cache: TileCache,
+    workers: Workers,
+    instances: Vec<TileInstance>,
+
+    frame: u64,
+    uploads: usize,
+    last_report: Instant,
+
+    params: Params,
+}
+
+impl VirtualTextureScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(size: PhysicalSize<u32>) -> Result<Self, GlError> {
+        unsafe {
+            let shader = create_shader_program(SRC_VERT_VIRTUAL_TEXTURE, SRC_FRAG_VIRTUAL_TEXTURE)?;
+
+            let mut cache_texture = 0;
+            gl::GenTextures(1, &mut cache_texture);
+            let cache_size = CACHE_SLOTS * TILE_TEXELS;
+            upload_texture(
+                cache_texture,
+                cache_size,
+                cache_size,
+                std::ptr::null(),
+                gl::CLAMP_TO_EDGE,
+            )?;
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            let mut instance_vbo = 0;
+            gl::GenBuffers(1, &mut instance_vbo);
+
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+
+            let size_f32 = mem::size_of::<f32>() as GLsizei;
+            let size_instance = mem::size_of::<TileInstance>() as GLsizei;
+            gl::VertexAttribPointer(0, 4, gl::FLOAT, gl::FALSE, size_instance, 0 as _);
+            gl::VertexAttribPointer(
+                1,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                size_instance,
+                (4 * size_f32) as _,
+            );
+            gl::VertexAttribPointer(
+                2,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                size_instance,
+                (8 * size_f32) as _,
+            );
+            for location in 0..3 {
+                gl::EnableVertexAttribArray(location);
+                gl::VertexAttribDivisor(location, 1);
+            }
+
+            gl::BindVertexArray(0);
+
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::new(size.width as f32, size.height as f32),
+
+                u_mvp: gl::GetUniformLocation(shader, c"u_mvp".as_ptr()),
+                u_debug: gl::GetUniformLocation(shader, c"u_debug".as_ptr()),
+                shader,
+                vao,
+                instance_vbo,
+                cache_texture,
+
+                cache: TileCache::new(),
+                workers: Workers::spawn(),
+                instances: Vec::new(),
+
+                frame: 0,
+                uploads: 0,
+                last_report: Instant::now(),
+
+                params: Params::new().with_bool("debug", false),
+            };
+
+            let root = generate_tile(TileId::ROOT);
+            scene.upload(TileId::ROOT, &root);
+
+            Ok(scene)
+        }
+    }
+
+    unsafe fn draw_instances(&self) {
+        buffer_data(
+            gl::ARRAY_BUFFER,
+            self.instance_vbo,
+            self.instances.as_slice(),
+            gl::STREAM_DRAW,
+        );
+
+        let count = self.instances.len() as GLsizei;
+        gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, count);
+    }
+
+    /// Shows the physical cache texture in the bottom left corner.
+    unsafe fn draw_cache_overlay(&mut self) {
+        let size = self.viewport.y.min(self.viewport.x) * 0.4;
+        let screen = Mat4::orthographic_lh(0.0, self.viewport.x, self.viewport.y, 0.0, -1.0, 1.0);
+
+        self.instances.clear();
+        self.instances.push(TileInstance {
+            rect: Vec4::new(0.0, self.viewport.y - size, size, size),
+            uv_rect: Vec4::new(0.0, 0.0, 1.0, 1.0),
+            level: MAX_LEVEL as f32,
+        });
+
+        gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, screen.as_ref().as_ptr());
+        gl::Uniform1i(self.u_debug, 0);
+        self.draw_instances();
+    }
+
+    /// The level whose texels are at least as big as a pixel.
+    fn wanted_level(&self, camera: &Camera) -> u32 {
+        let texels_per_pixel = VIRTUAL_TEXELS as f32 / WORLD_SIZE / camera.scale.x;
+        (texels_per_pixel.log2().ceil().max(0.0) as u32).min(MAX_LEVEL)
+    }
+
+    /// World position to texture space, the texture covering
+    /// `[-WORLD_SIZE / 2, WORLD_SIZE / 2]²`.
+    fn world_to_texture(&self, pos: Vec2) -> Vec2 {
+        pos / WORLD_SIZE + 0.5
+    }
+
+    /// Tiles of `level` overlapping the view.
+    fn visible_tiles(&self, camera: &Camera, level: u32) -> Vec<TileId> {
+        // the camera may be rotated, so bound all 4 corners
+        let (min, max) = [
+            Vec2::ZERO,
+            self.viewport * Vec2::X,
+            self.viewport * Vec2::Y,
+            self.viewport,
+        ]
+        .map(|corner| self.world_to_texture(camera.pointer_to_pos(corner, self.viewport)))
+        .into_iter()
+        .fold((Vec2::MAX, Vec2::MIN), |(min, max), p| {
+            (min.min(p), max.max(p))
+        });
+
+        let n = TileId::tiles_per_side(level);
+        let to_tile = |t: f32| ((t * n as f32).floor().max(0.0) as u32).min(n - 1);
+        if max.cmplt(Vec2::ZERO).any() || min.cmpgt(Vec2::ONE).any() {
+            return Vec::new();
+        }
+
+        let mut tiles = Vec::new();
+        for y in to_tile(min.y)..=to_tile(max.y) {
+            for x in to_tile(min.x)..=to_tile(max.x) {
+                tiles.push(TileId { level, x, y });
+            }
+        }
+        tiles
+    }
+
+    /// Draws `tile` with the part of `resident` (itself or an ancestor) it
+    /// covers, which lives in cache slot `slot`.
+    fn instance(&self, tile: TileId, resident: TileId, slot: UVec2) -> TileInstance {
+        let rect = tile.rect();
+        let resident_rect = resident.rect();
+
+        // where the tile sits within the resident tile, in [0, 1]²
+        let sub = Vec4::new(
+            (rect.x - resident_rect.x) / resident_rect.z,
+            (rect.y - resident_rect.y) / resident_rect.w,
+            rect.z / resident_rect.z,
+            rect.w / resident_rect.w,
+        );
+
+        // inset by half a texel so that neighboring slots don't bleed in
+        let inset = 0.5 / TILE_TEXELS as f32;
+        let slot_size = 1.0 / CACHE_SLOTS as f32;
+        let uv_origin = slot.as_vec2() * slot_size + inset * slot_size;
+        let uv_size = slot_size * (1.0 - 2.0 * inset);
+
+        TileInstance {
+            rect: (rect - Vec4::new(0.5, 0.5, 0.0, 0.0)) * WORLD_SIZE,
+            uv_rect: Vec4::new(
+                uv_origin.x + sub.x * uv_size,
+                uv_origin.y + sub.y * uv_size,
+                sub.z * uv_size,
+                sub.w * uv_size,
+            ),
+            level: resident.level as f32,
+        }
+    }
+
+    unsafe fn upload_finished_tiles(&mut self) {
+        for (tile, pixels) in self.workers.finished(MAX_UPLOADS_PER_FRAME) {
+            self.upload(tile, &pixels);
+        }
+    }
+
+    unsafe fn upload(&mut self, tile: TileId, pixels: &[u8]) {
+        let Some(slot) = self.cache.insert(tile, self.frame) else {
+            return;
+        };
+
+        gl::BindTexture(gl::TEXTURE_2D, self.cache_texture);
+        gl::TexSubImage2D(
+            gl::TEXTURE_2D,
+            0,
+            (slot.x * TILE_TEXELS) as GLint,
+            (slot.y * TILE_TEXELS) as GLint,
+            TILE_TEXELS as GLsizei,
+            TILE_TEXELS as GLsizei,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_ptr().cast(),
+        );
+
+        self.uploads += 1;
+    }
+
+    /// Prints the state of the cache once per second.
+    fn report(&mut self, level: u32, drawn: usize) {
+        if self.last_report.elapsed() < Duration::from_secs(1) {
+            return;
+        }
+
+        println!(
+            "virtual-texture: level {level}, {drawn} tiles drawn, {}/{} resident, {} pending, {} uploads",
+            self.cache.len(),
+            CACHE_SLOTS * CACHE_SLOTS,
+            self.workers.pending(),
+            self.uploads,
+        );
+
+        self.uploads = 0;
+        self.last_report = Instant::now();
+    }
+}
+
+impl Scene for VirtualTextureScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("d") => self.params.toggle("debug").unwrap(),
+            _ => return,
+        }
+
+        println!("virtual-texture config: {}", self.params);
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, _mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("virtual texture draw");
+
+        self.frame += 1;
+
+        unsafe {
+            self.upload_finished_tiles();
+        }
+
+        let level = self.wanted_level(camera);
+        let visible = self.visible_tiles(camera, level);
+
+        // request what is missing, closest to the center of the view first
+        let mut missing = (visible.iter().copied())
+            .filter(|tile| !self.cache.contains(*tile))
+            .collect::<Vec<_>>();
+        let center = self
+            .world_to_texture(camera.pointer_to_pos(self.viewport / 2.0, self.viewport))
+            * TileId::tiles_per_side(level) as f32;
+        missing.sort_by_key(|tile| {
+            let d = Vec2::new(tile.x as f32 + 0.5, tile.y as f32 + 0.5) - center;
+            (d.length_squared() * 16.0) as u32
+        });
+        self.workers.request(missing);
+
+        // tiles of the finest levels are a fraction of a world unit, drawn
+        // around the camera so that they don't jitter when zoomed in on
+        let origin = camera.center();
+        self.instances.clear();
+        for tile in visible {
+            let (resident, slot) = self.cache.closest_resident(tile, self.frame);
+            let mut instance = self.instance(tile, resident, slot);
+            instance.rect -= origin.extend(0.0).extend(0.0);
+            self.instances.push(instance);
+        }
+
+        let drawn = unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            gl::UseProgram(self.shader);
+            gl::Uniform1i(self.u_debug, self.params.bool("debug") as GLint);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.cache_texture);
+            gl::BindVertexArray(self.vao);
+
+            gl::UniformMatrix4fv(self.u_mvp, 1, gl::FALSE, self.matrix.as_ref().as_ptr());
+            self.draw_instances();
+            let drawn = self.instances.len();
+
+            if self.params.bool("debug") {
+                self.draw_cache_overlay();
+            }
+
+            gl::BindVertexArray(0);
+            drawn
+        };
+
+        self.report(level, drawn);
+        Ok(())
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.centered().matrix(self.viewport);
+        Ok(())
+    }
+}
+
+impl Drop for VirtualTextureScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+
+            delete_buffers(&[self.instance_vbo]);
+            delete_textures(&[self.cache_texture]);
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct TileInstance {
+    /// World rectangle, as `(x, y, w, h)`.
+    rect: Vec4,
+    /// Rectangle in the cache texture.
+    uv_rect: Vec4,
+    /// Level of the tile the texels come from.
+    level: f32,
+}
+
+// --- cache ---
+
+/// Which tile lives in which slot of the cache texture: the page table.
+struct TileCache {
+    slots: HashMap<TileId, UVec2>,
+    last_used: HashMap<TileId, u64>,
+    free: Vec<UVec2>,
+}
+
+impl TileCache {
+    fn new() -> Self {
+        let free = (0..CACHE_SLOTS)
+            .flat_map(|y| (0..CACHE_SLOTS).map(move |x| UVec2::new(x, y)))
+            .rev()
+            .collect();
+
+        Self {
+            slots: HashMap::new(),
+            last_used: HashMap::new(),
+            free,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn contains(&self, tile: TileId) -> bool {
+        self.slots.contains_key(&tile)
+    }
+
+    /// Finds a slot for a tile, evicting the least recently used one if the
+    /// cache is full. Returns `None` if every tile was drawn last frame.
+    fn insert(&mut self, tile: TileId, frame: u64) -> Option<UVec2> {
+        if let Some(&slot) = self.slots.get(&tile) {
+            return Some(slot);
+        }
+
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                let (&lru, _) = (self.last_used.iter())
+                    .filter(|&(&tile, &used)| tile != TileId::ROOT && used + 1 < frame)
+                    .min_by_key(|&(_, &used)| used)?;
+                self.last_used.remove(&lru);
+                self.slots.remove(&lru).unwrap()
+            }
+        };
+
+        self.slots.insert(tile, slot);
+        self.last_used.insert(tile, frame);
+        Some(slot)
+    }
+
+    /// The tile itself if resident, otherwise its closest resident ancestor,
+    /// along with its slot. Marks it as used.
+    fn closest_resident(&mut self, tile: TileId, frame: u64) -> (TileId, UVec2) {
+        let mut current = tile;
+        loop {
+            if let Some(&slot) = self.slots.get(&current) {
+                self.last_used.insert(current, frame);
+                return (current, slot);
+            }
+            // the root is always resident
+            current = current.parent().unwrap();
+        }
+    }
+}
+
+// --- tile generation ---
+
+struct Queue {
+    /// Latest first, replaced every frame so that tiles that scrolled out of
+    /// view don't get generated.
+    requests: Mutex<VecDeque<TileId>>,
+    condvar: Condvar,
+    quit: AtomicBool,
+}
+
+struct Workers {
+    queue: Arc<Queue>,
+    /// Requested tiles, queued or being generated.
+    pending: HashSet<TileId>,
+    finished: mpsc::Receiver<(TileId, Vec<u8>)>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Workers {
+    fn spawn() -> Self {
+        let queue = Arc::new(Queue {
+            requests: Mutex::new(VecDeque::new()),
+            condvar: Condvar::new(),
+            quit: AtomicBool::new(false),
+        });
+        let (sender, finished) = mpsc::channel();
+
+        let n_threads = thread::available_parallelism().map_or(2, |n| n.get().clamp(1, 4));
+        let threads = (0..n_threads)
+            .map(|_| {
+                let queue = queue.clone();
+                let sender = sender.clone();
+                thread::spawn(move || worker(&queue, &sender))
+            })
+            .collect();
+
+        Self {
+            queue,
+            pending: HashSet::new(),
+            finished,
+            threads,
+        }
+    }
+
+    /// Replaces the queued requests, keeping their order.
+    fn request(&mut self, tiles: Vec<TileId>) {
+        let mut requests = self.queue.requests.lock().unwrap();
+
+        // dropped requests must be requestable again
+        for tile in requests.drain(..) {
+            self.pending.remove(&tile);
+        }
+        for tile in tiles {
+            if self.pending.insert(tile) {
+                requests.push_back(tile);
+            }
+        }
+
+        self.queue.condvar.notify_all();
+    }
+
+    fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn finished(&mut self, max: usize) -> Vec<(TileId, Vec<u8>)> {
+        let finished = self.finished.try_iter().take(max).collect::<Vec<_>>();
+        for (tile, _) in &finished {
+            self.pending.remove(tile);
+        }
+        finished
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.queue.quit.store(true, Ordering::Relaxed);
+        self.queue.condvar.notify_all();
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn worker(queue: &Queue, sender: &mpsc::Sender<(TileId, Vec<u8>)>) {
+    loop {
+        let tile = {
+            let mut requests = queue.requests.lock().unwrap();
+            loop {
+                if queue.quit.load(Ordering::Relaxed) {
+                    return;
+                }
+                if let Some(tile) = requests.pop_front() {
+                    break tile;
+                }
+                requests = queue.condvar.wait(requests).unwrap();
+            }
+        };
+
+        if sender.send((tile, generate_tile(tile))).is_err() {
+            return;
+        }
+    }
+}
+
+/// RGBA pixels of a tile of the Mandelbrot set.
+fn generate_tile(tile: TileId) -> Vec<u8> {
+    // the whole texture spans [-2.25, 0.75] × [-1.5, 1.5]
+    const ORIGIN: (f64, f64) = (-2.25, -1.5);
+    const SPAN: f64 = 3.0;
+
+    let n = TileId::tiles_per_side(tile.level) as f64;
+    let texel = SPAN / n / TILE_TEXELS as f64;
+    let x0 = ORIGIN.0 + tile.x as f64 / n * SPAN;
+    let y0 = ORIGIN.1 + tile.y as f64 / n * SPAN;
+
+    // finer levels need more iterations to resolve the boundary
+    let max_iterations = 64 + 48 * (MAX_LEVEL - tile.level);
+
+    let mut pixels = Vec::with_capacity((TILE_TEXELS * TILE_TEXELS * 4) as usize);
+    for py in 0..TILE_TEXELS {
+        for px in 0..TILE_TEXELS {
+            let cx = x0 + (px as f64 + 0.5) * texel;
+            let cy = y0 + (py as f64 + 0.5) * texel;
+
+            let (mut zx, mut zy) = (0.0f64, 0.0f64);
+            let mut i = 0;
+            while i < max_iterations && zx * zx + zy * zy < 256.0 {
+                (zx, zy) = (zx * zx - zy * zy + cx, 2.0 * zx * zy + cy);
+                i += 1;
+            }
+
+            let color = if i == max_iterations {
+                [0, 0, 0]
+            } else {
+                // smooth iteration count through a cosine palette
+                let smooth = i as f64 + 1.0 - (zx * zx + zy * zy).ln().ln() / 2f64.ln();
+                let t = smooth * 0.05;
+                [0.0, 0.15, 0.25].map(|phase| {
+                    let c = 0.5 + 0.5 * (std::f64::consts::TAU * (t + phase)).cos();
+                    (c * 255.0) as u8
+                })
+            };
+            pixels.extend(color);
+            pixels.push(255);
+        }
+    }
+
+    pixels
+}
@@ -0,0 +1,388 @@
+use std::mem;
+
+use gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
+use glam::{vec2, Mat4, Vec2, Vec4};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, SmolStr};
+
+use crate::camera::Camera;
+use crate::common_gl::{buffer_data, create_shader_program, delete_buffers};
+use crate::delaunay;
+use crate::error::GlError;
+use crate::palette::{Palette, PALETTES};
+use crate::params::Params;
+use crate::profiling;
+use crate::render_state::RenderState;
+use crate::theme;
+
+use super::polyline::PolylineRenderer;
+use super::spatial_overlay::{Shape, ShapeRenderer};
+use super::{Scene, SceneEvent, SRC_FRAG_SPRITE, SRC_FRAG_VORONOI, SRC_VERT_SCREEN};
+
+/// Length of the arrays of the `Seeds` block in voronoi.frag.
+const MAX_SEEDS: usize = 256;
+const N_SEEDS: usize = 48;
+/// Half the size of the region seeds are scattered over, in world units.
+const SPREAD: Vec2 = vec2(700.0, 420.0);
+/// How close to a seed a click has to be to grab it, in pixels.
+const GRAB_PIXELS: f32 = 10.0;
+
+/// Variants of the `metric` parameter, in order.
+const METRICS: &[&str] = &["euclidean", "manhattan", "chebyshev"];
+/// Variants of the `coloring` parameter, in order.
+const COLORINGS: &[&str] = &["cells", "distance", "worley", "borders"];
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+/// The `Seeds` uniform block of voronoi.frag, laid out as std140.
+#[repr(C)]
+struct SeedsBlock {
+    positions: [Vec4; MAX_SEEDS],
+    colors: [Vec4; MAX_SEEDS],
+}
+
+/// A Voronoi diagram computed per pixel: every fragment goes through the
+/// seeds in a uniform buffer to find the closest two, under the chosen
+/// distance metric, which colors cells, distance fields, Worley noise or
+/// just the borders between cells.
+///
+/// Seeds are dragged around, and added or removed with a right click. The
+/// Delaunay triangulation of the seeds, the dual of the diagram, is computed
+/// on the CPU and drawn over it with the polyline renderer.
+pub struct VoronoiScene {
+    matrix: Mat4,
+    viewport: Vec2,
+    /// Pixels per world unit.
+    pixels_per_unit: f32,
+
+    seeds: Vec<Vec2>,
+    colors: Vec<[f32; 4]>,
+    /// Index of the seed being dragged.
+    dragging: Option<usize>,
+    /// Where the colors of new seeds come from.
+    rng: StdRng,
+    seed: u64,
+    /// Palette the seeds were colored with.
+    palette: usize,
+
+    shader: GLuint,
+    u_count: GLint,
+    u_metric: GLint,
+    u_coloring: GLint,
+    u_inverse: GLint,
+    u_pixel: GLint,
+    u_scale: GLint,
+    u_clear: GLint,
+    u_border: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    ubo: GLuint,
+
+    polylines: PolylineRenderer,
+    discs: ShapeRenderer,
+
+    params: Params,
+}
+
+impl VoronoiScene {
+    pub const RENDER_STATE: RenderState = RenderState::ALPHA_BLENDED;
+
+    pub fn new(seed: u64) -> Result<Self, GlError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let seeds = (0..N_SEEDS)
+            .map(|_| {
+                vec2(
+                    rng.gen_range(-SPREAD.x..SPREAD.x),
+                    rng.gen_range(-SPREAD.y..SPREAD.y),
+                )
+            })
+            .collect();
+
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        unsafe {
+            let shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_VORONOI)?;
+            let uniform = |name: &std::ffi::CStr| gl::GetUniformLocation(shader, name.as_ptr());
+            let block = gl::GetUniformBlockIndex(shader, c"Seeds".as_ptr());
+            gl::UniformBlockBinding(shader, block, 0);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut buffers = [0; 2];
+            gl::GenBuffers(2, buffers.as_mut_ptr());
+            let [vbo, ubo] = buffers;
+            buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+            const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+            for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+                let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+                gl::VertexAttribPointer(
+                    location,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    SIZE_VERTEX,
+                    offset as _,
+                );
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindVertexArray(0);
+
+            // allocated once, then only updated
+            let block = SeedsBlock {
+                positions: [Vec4::ZERO; MAX_SEEDS],
+                colors: [Vec4::ZERO; MAX_SEEDS],
+            };
+            buffer_data(
+                gl::UNIFORM_BUFFER,
+                ubo,
+                std::slice::from_ref(&block),
+                gl::DYNAMIC_DRAW,
+            );
+
+            let mut scene = Self {
+                matrix: Mat4::default(),
+                viewport: Vec2::ONE,
+                pixels_per_unit: 1.0,
+
+                seeds,
+                colors: Vec::new(),
+                dragging: None,
+                rng,
+                seed,
+                palette: 2,
+
+                u_count: uniform(c"u_count"),
+                u_metric: uniform(c"u_metric"),
+                u_coloring: uniform(c"u_coloring"),
+                u_inverse: uniform(c"u_inverse"),
+                u_pixel: uniform(c"u_pixel"),
+                u_scale: uniform(c"u_scale"),
+                u_clear: uniform(c"u_clear"),
+                u_border: uniform(c"u_border"),
+                shader,
+                vao,
+                vbo,
+                ubo,
+
+                polylines: PolylineRenderer::new()?,
+                discs: ShapeRenderer::new(SRC_FRAG_SPRITE)?,
+
+                params: Params::new()
+                    .with_enum("metric", 0, METRICS)
+                    .with_enum("coloring", 0, COLORINGS)
+                    .with_enum("palette", 2, PALETTES)
+                    .with_f32("scale", 120.0, 10.0, 1000.0, 10.0)
+                    .with_bool("delaunay", true)
+                    .with_bool("seeds", true),
+            };
+            scene.recolor();
+            Ok(scene)
+        }
+    }
+
+    /// Gives every seed a new color from the palette.
+    fn recolor(&mut self) {
+        self.palette = self.params.variant("palette");
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.colors = (0..self.seeds.len()).map(|_| self.color()).collect();
+    }
+
+    /// Opaque fill of the next swatch of the palette.
+    fn color(&mut self) -> [f32; 4] {
+        let palette = Palette::from_variant(self.palette);
+        let [r, g, b, _] = palette.swatch(&mut self.rng).fill.to_le_bytes();
+        [r, g, b, 255].map(|c| c as f32 / 255.0)
+    }
+}
+
+impl Scene for VoronoiScene {
+    fn render_state(&self) -> RenderState {
+        Self::RENDER_STATE
+    }
+
+    fn on_key(&mut self, keycode: Key<SmolStr>) {
+        match keycode.as_ref() {
+            Key::Character("m") => self.params.step("metric", 1).unwrap(),
+            Key::Character("M") => self.params.step("metric", -1).unwrap(),
+            Key::Character("c") => self.params.step("coloring", 1).unwrap(),
+            Key::Character("C") => self.params.step("coloring", -1).unwrap(),
+            Key::Character("p") => self.params.step("palette", 1).unwrap(),
+            Key::Character("P") => self.params.step("palette", -1).unwrap(),
+            Key::Character("s") => self.params.step("scale", 1).unwrap(),
+            Key::Character("S") => self.params.step("scale", -1).unwrap(),
+            Key::Character("d" | "D") => self.params.toggle("delaunay").unwrap(),
+            Key::Character("v" | "V") => self.params.toggle("seeds").unwrap(),
+            Key::Character("x" | "X") => {
+                self.seeds.clear();
+                self.colors.clear();
+                self.dragging = None;
+            }
+            _ => return,
+        }
+
+        println!("voronoi config: {}", self.params);
+    }
+
+    /// Grabs the seed under the mouse with the left button, and removes it
+    /// or adds one there with the right one. Returns whether the click was
+    /// used, in which case the camera shouldn't pan.
+    fn on_event(&mut self, camera: &Camera, event: &SceneEvent) -> bool {
+        let SceneEvent::MouseInput {
+            state,
+            button,
+            mouse_pos,
+        } = *event;
+        if state == ElementState::Released {
+            return button == MouseButton::Left && self.dragging.take().is_some();
+        }
+
+        let mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport);
+        let grab_distance = GRAB_PIXELS / self.pixels_per_unit;
+        let under_mouse = (self.seeds.iter().enumerate())
+            .map(|(i, seed)| (i, seed.distance(mouse_world)))
+            .filter(|&(_, distance)| distance <= grab_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i);
+
+        match (button, under_mouse) {
+            (MouseButton::Left, Some(i)) => self.dragging = Some(i),
+            (MouseButton::Right, Some(i)) => {
+                self.seeds.remove(i);
+                self.colors.remove(i);
+                self.dragging = None;
+            }
+            (MouseButton::Right, None) if self.seeds.len() < MAX_SEEDS => {
+                self.seeds.push(mouse_world);
+                let color = self.color();
+                self.colors.push(color);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn params(&self) -> Option<&Params> {
+        Some(&self.params)
+    }
+
+    fn params_mut(&mut self) -> Option<&mut Params> {
+        Some(&mut self.params)
+    }
+
+    fn draw(&mut self, camera: &Camera, mouse_pos: Vec2) -> Result<(), GlError> {
+        profiling::cpu_zone!("voronoi draw");
+
+        let mouse_world = camera.pointer_to_pos(mouse_pos, self.viewport);
+        if let Some(i) = self.dragging {
+            self.seeds[i] = mouse_world;
+        }
+        if self.palette != self.params.variant("palette") {
+            self.recolor();
+        }
+
+        let mut block = SeedsBlock {
+            positions: [Vec4::ZERO; MAX_SEEDS],
+            colors: [Vec4::ZERO; MAX_SEEDS],
+        };
+        for (i, (seed, color)) in self.seeds.iter().zip(&self.colors).enumerate() {
+            block.positions[i] = seed.extend(0.0).extend(0.0);
+            block.colors[i] = Vec4::from_array(*color);
+        }
+
+        let pixel = 1.0 / self.pixels_per_unit;
+        let theme = theme::current();
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear();
+
+            gl::BindBuffer(gl::UNIFORM_BUFFER, self.ubo);
+            gl::BufferSubData(
+                gl::UNIFORM_BUFFER,
+                0,
+                mem::size_of::<SeedsBlock>() as GLsizeiptr,
+                (&block as *const SeedsBlock).cast(),
+            );
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, self.ubo);
+
+            gl::UseProgram(self.shader);
+            let inverse = self.matrix.inverse();
+            gl::UniformMatrix4fv(self.u_inverse, 1, gl::FALSE, inverse.as_ref().as_ptr());
+            gl::Uniform1i(self.u_count, self.seeds.len() as GLint);
+            gl::Uniform1i(self.u_metric, self.params.variant("metric") as GLint);
+            gl::Uniform1i(self.u_coloring, self.params.variant("coloring") as GLint);
+            gl::Uniform1f(self.u_pixel, pixel);
+            gl::Uniform1f(self.u_scale, self.params.f32("scale"));
+            gl::Uniform4fv(self.u_clear, 1, theme.clear.as_ptr());
+            gl::Uniform4fv(self.u_border, 1, theme.grid.as_ptr());
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::BindVertexArray(0);
+
+            if self.params.bool("delaunay") {
+                let triangles = delaunay::triangulate(&self.seeds);
+                for (a, b) in delaunay::edges(&triangles) {
+                    let edge = [self.seeds[a], self.seeds[b]];
+                    self.polylines.push(&edge, 1.5 * pixel, theme.hud_text);
+                }
+                self.polylines.draw(&self.matrix);
+            }
+
+            if self.params.bool("seeds") {
+                let shapes = (self.seeds.iter().enumerate())
+                    .map(|(i, &center)| Shape {
+                        center,
+                        half_size: Vec2::splat(5.0 * pixel),
+                        color: match self.dragging == Some(i) {
+                            true => theme.accent,
+                            false => theme.hud_text,
+                        },
+                    })
+                    .collect::<Vec<_>>();
+                self.discs.draw(&self.matrix, &shapes);
+            }
+            Ok(())
+        }
+    }
+
+    fn resize(&mut self, camera: &Camera, width: i32, height: i32) -> Result<(), GlError> {
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.matrix = camera.matrix(self.viewport);
+        self.pixels_per_unit = camera.scale.x;
+        Ok(())
+    }
+}
+
+impl Drop for VoronoiScene {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo, self.ubo]);
+        }
+    }
+}
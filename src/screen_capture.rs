@@ -0,0 +1,193 @@
+//! Screen capture on X11: a thread grabs a monitor, a window or a region of
+//! the screen with `GetImage` and hands it to the main thread, which streams
+//! it into the scenes reading the input image, for blurs and frosted glass
+//! over the live desktop.
+//!
+//! Under Wayland, XWayland only sees the windows of other X11 clients.
+
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+
+use glam::{ivec2, uvec2, IVec2, UVec2};
+use image::RgbaImage;
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xproto::{ConnectionExt as _, ImageFormat, ImageOrder, Window};
+use x11rb::rust_connection::RustConnection;
+
+/// What gets captured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    /// A monitor, by its index in RandR's list.
+    Monitor(usize),
+    /// A window, by its id as shown by `xwininfo`.
+    Window(u32),
+    /// A region of the whole screen.
+    Region { position: IVec2, size: UVec2 },
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    /// `0` for a monitor, `0x3a00007` for a window, or `1280x720+0+0` for a
+    /// region, as X11 geometries are written.
+    fn from_str(s: &str) -> Result<Self, String> {
+        if let Some(hex) = s.strip_prefix("0x") {
+            return (u32::from_str_radix(hex, 16).map(Self::Window))
+                .map_err(|_| format!("invalid window id '{s}'"));
+        }
+        if let Ok(index) = s.parse() {
+            return Ok(Self::Monitor(index));
+        }
+        parse_geometry(s).ok_or_else(|| {
+            format!(
+                "expected a monitor index, a window id or a region like 1280x720+0+0, got '{s}'"
+            )
+        })
+    }
+}
+
+fn parse_geometry(s: &str) -> Option<Source> {
+    let (size, offset) = s.split_once('+')?;
+    let (width, height) = size.split_once('x')?;
+    let (x, y) = offset.split_once('+')?;
+    Some(Source::Region {
+        position: ivec2(x.parse().ok()?, y.parse().ok()?),
+        size: uvec2(width.parse().ok()?, height.parse().ok()?),
+    })
+}
+
+/// A connection to the X server grabbing one source.
+struct Grabber {
+    connection: RustConnection,
+    root: Window,
+    /// Monitors are resolved to their region when opening.
+    source: Source,
+}
+
+impl Grabber {
+    /// The drawable to grab and the rectangle of it, queried for every grab
+    /// since windows move and resize.
+    fn area(&self) -> Result<(Window, IVec2, UVec2), Box<dyn Error>> {
+        match self.source {
+            Source::Window(window) => {
+                let geometry = self.connection.get_geometry(window)?.reply()?;
+                let size = uvec2(geometry.width as u32, geometry.height as u32);
+                Ok((window, IVec2::ZERO, size))
+            }
+            Source::Region { position, size } => Ok((self.root, position, size)),
+            Source::Monitor(_) => unreachable!("monitors are resolved when opening"),
+        }
+    }
+
+    fn grab(&self) -> Result<RgbaImage, Box<dyn Error>> {
+        let (drawable, position, size) = self.area()?;
+        let reply = (self.connection)
+            .get_image(
+                ImageFormat::Z_PIXMAP,
+                drawable,
+                position.x as i16,
+                position.y as i16,
+                size.x as u16,
+                size.y as u16,
+                !0,
+            )?
+            .reply()?;
+
+        // BGRX for 24-bit visuals, BGRA for 32-bit ones
+        let opaque = match reply.depth {
+            24 => true,
+            32 => false,
+            depth => return Err(format!("unsupported depth {depth}").into()),
+        };
+        let pixels = (reply.data.chunks_exact(4))
+            .flat_map(|bgra| {
+                [
+                    bgra[2],
+                    bgra[1],
+                    bgra[0],
+                    if opaque { 255 } else { bgra[3] },
+                ]
+            })
+            .collect();
+        RgbaImage::from_raw(size.x, size.y, pixels).ok_or_else(|| "incomplete image".into())
+    }
+
+    /// Sends grabs until the capture is dropped or the server fails.
+    fn run(self, frames: SyncSender<RgbaImage>) {
+        loop {
+            match self.grab() {
+                // waits for the main thread to take the previous one, so that
+                // the screen is grabbed once per frame at most
+                Ok(frame) => {
+                    if frames.send(frame).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Screen capture: failed: {e}");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+pub struct ScreenCapture {
+    pub source: Source,
+    frames: Receiver<RgbaImage>,
+}
+
+impl ScreenCapture {
+    /// Starts capturing `source` from the X server of `DISPLAY`.
+    pub fn open(source: Source) -> Result<Self, Box<dyn Error>> {
+        let (connection, screen) = x11rb::connect(None)?;
+        let setup = connection.setup();
+        let root = setup.roots[screen].root;
+
+        // pixels are read as 4 bytes in little endian order
+        let format = (setup.pixmap_formats.iter()).find(|format| format.depth == 24);
+        if setup.image_byte_order != ImageOrder::LSB_FIRST
+            || format.is_some_and(|format| format.bits_per_pixel != 32)
+        {
+            return Err("unsupported pixel layout, only 32 bits in LSB order are".into());
+        }
+
+        let source = match source {
+            Source::Monitor(index) => {
+                let monitors = connection.randr_get_monitors(root, true)?.reply()?.monitors;
+                let monitor = monitors
+                    .get(index)
+                    .ok_or_else(|| format!("no monitor {index}, there are {}", monitors.len()))?;
+                Source::Region {
+                    position: ivec2(monitor.x as i32, monitor.y as i32),
+                    size: uvec2(monitor.width as u32, monitor.height as u32),
+                }
+            }
+            source => source,
+        };
+
+        let grabber = Grabber {
+            connection,
+            root,
+            source,
+        };
+        // fails early for windows that don't exist
+        let (_, _, size) = grabber.area()?;
+
+        let (sender, frames) = mpsc::sync_channel(1);
+        thread::Builder::new()
+            .name("screen capture".to_string())
+            .spawn(move || grabber.run(sender))?;
+
+        println!("Screen capture: {source:?} at {}x{}", size.x, size.y);
+        Ok(Self { source, frames })
+    }
+
+    /// The last grab since the previous call, if any.
+    pub fn latest(&self) -> Option<RgbaImage> {
+        self.frames.try_iter().last()
+    }
+}
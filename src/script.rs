@@ -0,0 +1,336 @@
+//! [Rhai](https://rhai.rs) scripts driving a scene: animating its parameters,
+//! moving the camera, reacting to keys and drawing debug shapes, without
+//! recompiling.
+//!
+//! A script runs once when loaded, then the app calls those of these functions
+//! it defines:
+//!
+//! ```text
+//! fn init() { ... }       // before the first frame
+//! fn frame() { ... }      // every frame
+//! fn on_key(key) { ... }  // on key presses, returning true if it used the key
+//! ```
+//!
+//! Functions don't see the variables at the top of the script, so anything
+//! kept between calls goes in the object map `this`. See `assets/scripts` for
+//! the whole API in use.
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use glam::{vec2, Vec2};
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope, AST};
+
+use crate::camera::Camera;
+use crate::params::{Params, Value};
+use crate::theme;
+
+/// Segments of the polylines approximating circles.
+const CIRCLE_SEGMENTS: usize = 48;
+
+/// What a script can see of the app while it runs.
+pub struct Frame<'a> {
+    /// Seconds since the script was loaded.
+    pub time: f32,
+    /// Seconds since the last frame.
+    pub dt: f32,
+    /// Mouse position in world coordinates.
+    pub mouse: Vec2,
+    pub camera: &'a Camera,
+    /// Parameters of the current scene, if it has any.
+    pub params: Option<&'a mut Params>,
+}
+
+/// A polyline drawn by a script, in world coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugLine {
+    pub points: Vec<Vec2>,
+    pub color: [f32; 4],
+}
+
+/// Shared between the script's API functions and the app.
+#[derive(Debug, Default)]
+struct State {
+    time: f32,
+    dt: f32,
+    mouse: Vec2,
+    camera: Camera,
+    camera_changed: bool,
+    params: Option<Params>,
+    color: Option<[f32; 4]>,
+    lines: Vec<DebugLine>,
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    /// `this` of every call.
+    this: Dynamic,
+    initialized: bool,
+    state: Rc<RefCell<State>>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::new(&source).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    pub fn new(source: &str) -> Result<Self, String> {
+        let state = Rc::new(RefCell::new(State::default()));
+
+        let mut engine = Engine::new();
+        engine.on_print(|text| println!("[script] {text}"));
+        register_api(&mut engine, &state);
+
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        let mut scope = Scope::new();
+        (engine.run_ast_with_scope(&mut scope, &ast)).map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            engine,
+            ast,
+            scope,
+            this: Dynamic::from_map(Map::new()),
+            initialized: false,
+            state,
+        })
+    }
+
+    /// Calls `init` the first time, then `frame`.
+    pub fn frame(&mut self, mut frame: Frame) -> Result<(), String> {
+        if !self.initialized {
+            self.initialized = true;
+            self.call("init", (), &mut frame)?;
+        }
+
+        self.call("frame", (), &mut frame)?;
+        Ok(())
+    }
+
+    /// Calls `on_key` with the name of the key, as the sequencer spells it.
+    /// Returns whether the script used the key.
+    pub fn on_key(&mut self, key: &str, mut frame: Frame) -> Result<bool, String> {
+        let used = self.call("on_key", (key.to_string(),), &mut frame)?;
+        Ok(used.and_then(|used| used.as_bool().ok()).unwrap_or(false))
+    }
+
+    /// The camera, if the script moved it since the last call.
+    pub fn take_camera(&mut self) -> Option<Camera> {
+        let mut state = self.state.borrow_mut();
+        std::mem::take(&mut state.camera_changed).then(|| state.camera.clone())
+    }
+
+    /// Lines drawn by the script since the last call.
+    pub fn take_lines(&mut self) -> Vec<DebugLine> {
+        std::mem::take(&mut self.state.borrow_mut().lines)
+    }
+
+    /// Calls `name` if the script defines it with as many parameters as
+    /// `args`, with the parameters of the scene copied in and out around it.
+    fn call(
+        &mut self,
+        name: &str,
+        args: impl FuncArgs,
+        frame: &mut Frame,
+    ) -> Result<Option<Dynamic>, String> {
+        let mut arg_values = Vec::new();
+        args.parse(&mut arg_values);
+
+        let defined = (self.ast.iter_functions())
+            .any(|f| f.name == name && f.params.len() == arg_values.len());
+        if !defined {
+            return Ok(None);
+        }
+
+        {
+            let mut state = self.state.borrow_mut();
+            state.time = frame.time;
+            state.dt = frame.dt;
+            state.mouse = frame.mouse;
+            if !state.camera_changed {
+                state.camera = frame.camera.clone();
+            }
+            state.params = frame.params.as_deref().cloned();
+        }
+
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.this);
+        let result = (self.engine).call_fn_with_options::<Dynamic>(
+            options,
+            &mut self.scope,
+            &self.ast,
+            name,
+            arg_values,
+        );
+
+        // the changes made before an error stay
+        let changed = self.state.borrow_mut().params.take();
+        if let (Some(params), Some(changed)) = (frame.params.as_deref_mut(), changed) {
+            *params = changed;
+        }
+
+        result.map(Some).map_err(|e| format!("{name}: {e}"))
+    }
+}
+
+fn register_api(engine: &mut Engine, state: &Rc<RefCell<State>>) {
+    let s = state.clone();
+    engine.register_fn("time", move || s.borrow().time as f64);
+    let s = state.clone();
+    engine.register_fn("dt", move || s.borrow().dt as f64);
+    let s = state.clone();
+    engine.register_fn("mouse", move || point_map(s.borrow().mouse));
+
+    // camera
+
+    let s = state.clone();
+    engine.register_fn("camera", move || {
+        let camera = &s.borrow().camera;
+        let mut map = point_map(-camera.position);
+        map.insert("zoom".into(), (camera.scale.x as f64).into());
+        map.insert("rotation".into(), (camera.rotation as f64).into());
+        map
+    });
+    let s = state.clone();
+    engine.register_fn("set_camera", move |fields: Map| -> ScriptResult<()> {
+        let mut state = s.borrow_mut();
+        for (name, value) in fields {
+            let value = number(value)?;
+            let camera = &mut state.camera;
+            match name.as_str() {
+                // the camera is positioned by its offset to the world
+                "x" => camera.position.x = -value,
+                "y" => camera.position.y = -value,
+                "zoom" => camera.scale = Vec2::splat(value.max(f32::EPSILON)),
+                "rotation" => camera.rotation = value,
+                _ => return Err(format!("the camera has no '{name}'").into()),
+            }
+        }
+        state.camera_changed = true;
+        Ok(())
+    });
+
+    // parameters
+
+    let s = state.clone();
+    engine.register_fn("param", move |name: &str| -> ScriptResult<Dynamic> {
+        let state = s.borrow();
+        let value = (state.params.as_ref())
+            .ok_or("this scene has no parameters")?
+            .get(name)
+            .ok_or_else(|| format!("unknown parameter '{name}'"))?;
+
+        Ok(match value {
+            Value::Bool(b) => b.into(),
+            Value::Number(n) => n.into(),
+            Value::Text(t) => t.into(),
+        })
+    });
+    let s = state.clone();
+    engine.register_fn(
+        "set_param",
+        move |name: &str, value: Dynamic| -> ScriptResult<()> {
+            let value = match value.type_name() {
+                "bool" => Value::Bool(value.as_bool()?),
+                "string" => Value::Text(value.into_string()?),
+                _ => Value::Number(number(value)? as f64),
+            };
+
+            let mut state = s.borrow_mut();
+            let params = (state.params.as_mut()).ok_or("this scene has no parameters")?;
+            params.set(name, value).map_err(|e| e.to_string().into())
+        },
+    );
+    let s = state.clone();
+    engine.register_fn(
+        "step_param",
+        move |name: &str, steps: i64| -> ScriptResult<()> {
+            let mut state = s.borrow_mut();
+            let params = (state.params.as_mut()).ok_or("this scene has no parameters")?;
+            params
+                .step(name, steps as i32)
+                .map_err(|e| e.to_string().into())
+        },
+    );
+
+    // debug drawing
+
+    let s = state.clone();
+    engine.register_fn(
+        "color",
+        move |r: Dynamic, g: Dynamic, b: Dynamic, a: Dynamic| -> ScriptResult<()> {
+            s.borrow_mut().color = Some([number(r)?, number(g)?, number(b)?, number(a)?]);
+            Ok(())
+        },
+    );
+    let s = state.clone();
+    engine.register_fn(
+        "line",
+        move |x1: Dynamic, y1: Dynamic, x2: Dynamic, y2: Dynamic| -> ScriptResult<()> {
+            let points = vec![
+                vec2(number(x1)?, number(y1)?),
+                vec2(number(x2)?, number(y2)?),
+            ];
+            push_line(&s, points);
+            Ok(())
+        },
+    );
+    let s = state.clone();
+    engine.register_fn(
+        "rect",
+        move |x1: Dynamic, y1: Dynamic, x2: Dynamic, y2: Dynamic| -> ScriptResult<()> {
+            let (min, max) = (
+                vec2(number(x1)?, number(y1)?),
+                vec2(number(x2)?, number(y2)?),
+            );
+            let points = vec![min, vec2(max.x, min.y), max, vec2(min.x, max.y), min];
+            push_line(&s, points);
+            Ok(())
+        },
+    );
+    let s = state.clone();
+    engine.register_fn(
+        "circle",
+        move |x: Dynamic, y: Dynamic, radius: Dynamic| -> ScriptResult<()> {
+            let (center, radius) = (vec2(number(x)?, number(y)?), number(radius)?);
+            let points = (0..=CIRCLE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    center + radius * Vec2::from_angle(angle)
+                })
+                .collect();
+            push_line(&s, points);
+            Ok(())
+        },
+    );
+}
+
+/// Integers and floats alike, so that scripts can write `1` for `1.0`.
+fn number(value: Dynamic) -> ScriptResult<f32> {
+    match value.as_float() {
+        Ok(float) => Ok(float as f32),
+        Err(_) => (value.as_int())
+            .map(|int| int as f32)
+            .map_err(|type_name| format!("expected a number, got {type_name}").into()),
+    }
+}
+
+fn point_map(point: Vec2) -> Map {
+    let mut map = Map::new();
+    map.insert("x".into(), (point.x as f64).into());
+    map.insert("y".into(), (point.y as f64).into());
+    map
+}
+
+fn push_line(state: &RefCell<State>, points: Vec<Vec2>) {
+    let mut state = state.borrow_mut();
+    let color = (state.color).unwrap_or_else(|| theme::current().accent);
+    state.lines.push(DebugLine { points, color });
+}
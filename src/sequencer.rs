@@ -0,0 +1,222 @@
+//! Plays a scripted timeline loaded from TOML, so the playground can run as
+//! an unattended demo loop.
+//!
+//! ```toml
+//! loop = true
+//! duration = 30.0
+//!
+//! [[event]]
+//! at = 0.0
+//! scene = "kawase"
+//!
+//! [[event]]
+//! at = 2.5
+//! keys = ["l", "l", "ArrowRight", "d"]
+//!
+//! [[camera]]
+//! at = 0.0
+//! position = [0.0, 0.0]
+//! zoom = 0.5
+//!
+//! [[camera]]
+//! at = 10.0
+//! position = [200.0, -100.0]
+//! zoom = 2.0
+//! rotation = 15.0
+//! ```
+
+use std::error::Error;
+use std::path::Path;
+use std::time::Instant;
+
+use glam::Vec2;
+use serde::Deserialize;
+use winit::keyboard::{Key, NamedKey, SmolStr};
+
+use crate::camera::Camera;
+use crate::scenes::Scenes;
+use crate::tween::{Easing, Lerp};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Timeline {
+    /// Restart from the beginning once the timeline is over.
+    #[serde(default, rename = "loop")]
+    pub looping: bool,
+    /// Length of the timeline in seconds. Defaults to the time of the last
+    /// event or camera keyframe.
+    pub duration: Option<f32>,
+    #[serde(default, rename = "event")]
+    pub events: Vec<Event>,
+    #[serde(default, rename = "camera")]
+    pub camera: Vec<CameraKeyframe>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Event {
+    /// Time in seconds since the start of the timeline.
+    pub at: f32,
+    /// Scene to switch to, by name.
+    pub scene: Option<String>,
+    /// Keys to press in the current scene, in order.
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraKeyframe {
+    pub at: f32,
+    #[serde(default)]
+    pub position: [f32; 2],
+    /// Zoom relative to the window's scale factor.
+    #[serde(default = "one")]
+    pub zoom: f32,
+    /// Rotation in degrees.
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+/// Something the sequencer wants the app to do.
+#[derive(Debug, Clone)]
+pub enum Cue {
+    SwitchScene(String),
+    PressKey(Key<SmolStr>),
+}
+
+pub struct Sequencer {
+    timeline: Timeline,
+    duration: f32,
+    start: Instant,
+    next_event: usize,
+}
+
+impl Sequencer {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let source = std::fs::read_to_string(path)?;
+        let mut timeline: Timeline = toml::from_str(&source)?;
+
+        for event in &timeline.events {
+            if let Some(scene) = &event.scene {
+                if !Scenes::names().contains(&scene.as_str()) {
+                    return Err(format!("unknown scene '{scene}'").into());
+                }
+            }
+
+            if let Some(key) = event.keys.iter().find(|k| parse_key(k).is_none()) {
+                return Err(format!("unknown key '{key}'").into());
+            }
+        }
+
+        timeline.events.sort_by(|a, b| a.at.total_cmp(&b.at));
+        timeline.camera.sort_by(|a, b| a.at.total_cmp(&b.at));
+
+        let last_event = timeline.events.last().map_or(0.0, |e| e.at);
+        let last_keyframe = timeline.camera.last().map_or(0.0, |k| k.at);
+        let duration = (timeline.duration).unwrap_or(last_event.max(last_keyframe));
+
+        Ok(Self {
+            timeline,
+            duration,
+            start: Instant::now(),
+            next_event: 0,
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.timeline.looping
+            && self.next_event >= self.timeline.events.len()
+            && self.start.elapsed().as_secs_f32() >= self.duration
+    }
+
+    /// Advances the timeline, returning every cue that became due since the
+    /// last call.
+    pub fn update(&mut self) -> Vec<Cue> {
+        let mut cues = Vec::new();
+
+        let mut elapsed = self.start.elapsed().as_secs_f32();
+        if self.timeline.looping && elapsed > self.duration && self.duration > 0.0 {
+            // flush the remaining events before wrapping around
+            self.collect_cues(f32::INFINITY, &mut cues);
+
+            self.start = Instant::now();
+            self.next_event = 0;
+            elapsed = 0.0;
+        }
+
+        self.collect_cues(elapsed, &mut cues);
+        cues
+    }
+
+    fn collect_cues(&mut self, until: f32, cues: &mut Vec<Cue>) {
+        while let Some(event) = self.timeline.events.get(self.next_event) {
+            if event.at > until {
+                break;
+            }
+
+            if let Some(scene) = &event.scene {
+                cues.push(Cue::SwitchScene(scene.clone()));
+            }
+            cues.extend(
+                event
+                    .keys
+                    .iter()
+                    .filter_map(|k| parse_key(k))
+                    .map(Cue::PressKey),
+            );
+
+            self.next_event += 1;
+        }
+    }
+
+    /// Camera at the current time, or `None` if the timeline doesn't animate it.
+    pub fn camera(&self, scale_factor: f32) -> Option<Camera> {
+        let keyframes = &self.timeline.camera;
+        let elapsed = self.start.elapsed().as_secs_f32();
+
+        let next = keyframes.iter().position(|k| k.at > elapsed);
+        let (from, to, t) = match next {
+            None => {
+                let last = keyframes.last()?;
+                (last, last, 0.0)
+            }
+            Some(0) => (&keyframes[0], &keyframes[0], 0.0),
+            Some(i) => {
+                let (from, to) = (&keyframes[i - 1], &keyframes[i]);
+                let t = (elapsed - from.at) / (to.at - from.at);
+                (from, to, Easing::Smoothstep.ease(t))
+            }
+        };
+
+        let camera = |keyframe: &CameraKeyframe| Camera {
+            position: Vec2::from(keyframe.position),
+            rotation: keyframe.rotation.to_radians(),
+            scale: Vec2::splat(keyframe.zoom * scale_factor),
+        };
+
+        Some(camera(from).lerp(&camera(to), t))
+    }
+}
+
+/// Parses a key as written in a timeline: either a single character or the
+/// name of a special key.
+pub fn parse_key(key: &str) -> Option<Key<SmolStr>> {
+    let named = match key {
+        "ArrowUp" => NamedKey::ArrowUp,
+        "ArrowDown" => NamedKey::ArrowDown,
+        "ArrowLeft" => NamedKey::ArrowLeft,
+        "ArrowRight" => NamedKey::ArrowRight,
+        "Space" => NamedKey::Space,
+        "Enter" => NamedKey::Enter,
+        "Tab" => NamedKey::Tab,
+        _ if key.chars().count() == 1 => return Some(Key::Character(SmolStr::new(key))),
+        _ => return None,
+    };
+
+    Some(Key::Named(named))
+}
@@ -0,0 +1,434 @@
+//! Recording of input sessions and their deterministic replay.
+//!
+//! `--record-input <file>` writes what the window was told frame by frame:
+//! the length of each frame and the input events that came before it. The
+//! file starts with a [`Snapshot`] of the view the session started from, so
+//! `--replay-input <file>` restores it, feeds the events back at the same
+//! frames and runs the clock with the recorded frame lengths instead of the
+//! wall clock. Scenes and their animations then see the same interaction
+//! whatever the frame rate of the replay, and how long frames actually take
+//! can be compared across code changes. Live input is ignored until the
+//! replay is over.
+//!
+//! The file is JSON, one line for the header and one per frame, written as
+//! the session goes so that a session ending in a crash is still complete up
+//! to it.
+//!
+//! Only keys chords can use are recorded by name (see [`crate::bindings`]);
+//! other named keys, like modifiers on their own, are left out.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{DeviceId, ElementState, Ime, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{Key, ModifiersState, NativeKeyCode, PhysicalKey, SmolStr};
+
+use crate::bindings;
+use crate::input::KeyInput;
+use crate::snapshot::Snapshot;
+
+const VERSION: u32 = 1;
+
+/// First line of a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Header {
+    pub version: u32,
+    /// Physical size of the window.
+    pub size: [u32; 2],
+    pub snapshot: Snapshot,
+}
+
+/// An event the window got, in a form that can be saved and made up again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key {
+        /// What the key types, or the name of a named key.
+        key: RecordedKey,
+        /// Name of the place of the key, for chords bound to places.
+        code: Option<String>,
+        pressed: bool,
+        repeat: bool,
+        text: Option<String>,
+    },
+    Modifiers(u32),
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    MouseInput {
+        button: RecordedButton,
+        pressed: bool,
+    },
+    MouseWheel {
+        x: f32,
+        y: f32,
+        /// In pixels rather than lines.
+        pixels: bool,
+    },
+    ImeCommit(String),
+    DroppedFile(PathBuf),
+    Unfocused,
+    Resized {
+        width: u32,
+        height: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedKey {
+    Character(String),
+    Named(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    Other(u16),
+}
+
+impl InputEvent {
+    /// The recorded form of `event`, if it's one a session keeps.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        Some(match event {
+            WindowEvent::KeyboardInput { event, .. } => Self::from_key(&KeyInput::from(event))?,
+            WindowEvent::ModifiersChanged(modifiers) => Self::Modifiers(modifiers.state().bits()),
+            WindowEvent::CursorMoved { position, .. } => Self::CursorMoved {
+                x: position.x,
+                y: position.y,
+            },
+            WindowEvent::MouseInput { state, button, .. } => Self::MouseInput {
+                button: match button {
+                    MouseButton::Left => RecordedButton::Left,
+                    MouseButton::Right => RecordedButton::Right,
+                    MouseButton::Middle => RecordedButton::Middle,
+                    MouseButton::Back => RecordedButton::Back,
+                    MouseButton::Forward => RecordedButton::Forward,
+                    MouseButton::Other(button) => RecordedButton::Other(*button),
+                },
+                pressed: *state == ElementState::Pressed,
+            },
+            WindowEvent::MouseWheel { delta, .. } => match *delta {
+                MouseScrollDelta::LineDelta(x, y) => Self::MouseWheel {
+                    x,
+                    y,
+                    pixels: false,
+                },
+                MouseScrollDelta::PixelDelta(pos) => Self::MouseWheel {
+                    x: pos.x as f32,
+                    y: pos.y as f32,
+                    pixels: true,
+                },
+            },
+            WindowEvent::Ime(Ime::Commit(text)) => Self::ImeCommit(text.clone()),
+            WindowEvent::DroppedFile(path) => Self::DroppedFile(path.clone()),
+            WindowEvent::Focused(false) => Self::Unfocused,
+            WindowEvent::Resized(size) => Self::Resized {
+                width: size.width,
+                height: size.height,
+            },
+            _ => return None,
+        })
+    }
+
+    pub fn from_key(input: &KeyInput) -> Option<Self> {
+        let key = match &input.logical_key {
+            Key::Character(ch) => RecordedKey::Character(ch.to_string()),
+            Key::Named(named) => RecordedKey::Named(bindings::named_key_name(*named)?.into()),
+            _ => return None,
+        };
+        let code = match input.physical_key {
+            PhysicalKey::Code(code) => bindings::key_code_name(code).map(str::to_string),
+            PhysicalKey::Unidentified(_) => None,
+        };
+
+        Some(Self::Key {
+            key,
+            code,
+            pressed: input.state == ElementState::Pressed,
+            repeat: input.repeat,
+            text: input.text.as_ref().map(SmolStr::to_string),
+        })
+    }
+
+    /// The key press or release of a key event.
+    pub fn to_key(&self) -> Option<KeyInput> {
+        let Self::Key {
+            key,
+            code,
+            pressed,
+            repeat,
+            text,
+        } = self
+        else {
+            return None;
+        };
+
+        let logical_key = match key {
+            RecordedKey::Character(ch) => Key::Character(ch.into()),
+            RecordedKey::Named(name) => Key::Named(bindings::named_key(name)?),
+        };
+        let physical_key = match code.as_deref().and_then(bindings::key_code) {
+            Some(code) => PhysicalKey::Code(code),
+            None => PhysicalKey::Unidentified(NativeKeyCode::Unidentified),
+        };
+
+        Some(KeyInput {
+            logical_key,
+            physical_key,
+            state: match pressed {
+                true => ElementState::Pressed,
+                false => ElementState::Released,
+            },
+            repeat: *repeat,
+            text: text.as_deref().map(SmolStr::from),
+        })
+    }
+
+    /// The window event to handle again, for all but keys, which winit
+    /// doesn't let be made up, and resizes, which the window has to be asked
+    /// for.
+    pub fn to_window_event(&self) -> Option<WindowEvent> {
+        let device_id = DeviceId::dummy();
+        let state = |pressed: bool| match pressed {
+            true => ElementState::Pressed,
+            false => ElementState::Released,
+        };
+
+        Some(match self {
+            Self::Key { .. } | Self::Resized { .. } => return None,
+            Self::Modifiers(bits) => {
+                WindowEvent::ModifiersChanged(ModifiersState::from_bits_truncate(*bits).into())
+            }
+            Self::CursorMoved { x, y } => WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(*x, *y),
+            },
+            Self::MouseInput { button, pressed } => WindowEvent::MouseInput {
+                device_id,
+                state: state(*pressed),
+                button: match *button {
+                    RecordedButton::Left => MouseButton::Left,
+                    RecordedButton::Right => MouseButton::Right,
+                    RecordedButton::Middle => MouseButton::Middle,
+                    RecordedButton::Back => MouseButton::Back,
+                    RecordedButton::Forward => MouseButton::Forward,
+                    RecordedButton::Other(button) => MouseButton::Other(button),
+                },
+            },
+            Self::MouseWheel { x, y, pixels } => WindowEvent::MouseWheel {
+                device_id,
+                delta: match pixels {
+                    true => {
+                        MouseScrollDelta::PixelDelta(PhysicalPosition::new(*x as f64, *y as f64))
+                    }
+                    false => MouseScrollDelta::LineDelta(*x, *y),
+                },
+                phase: winit::event::TouchPhase::Moved,
+            },
+            Self::ImeCommit(text) => WindowEvent::Ime(Ime::Commit(text.clone())),
+            Self::DroppedFile(path) => WindowEvent::DroppedFile(path.clone()),
+            Self::Unfocused => WindowEvent::Focused(false),
+        })
+    }
+
+    /// Size the window was resized to, which a replay asks the window for.
+    pub fn resized(&self) -> Option<PhysicalSize<u32>> {
+        match *self {
+            Self::Resized { width, height } => Some(PhysicalSize::new(width, height)),
+            _ => None,
+        }
+    }
+}
+
+/// A frame of a session: how long it was, and what happened before it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub dt: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<InputEvent>,
+}
+
+/// Writes a session as it happens.
+pub struct Recorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    started: bool,
+    /// Events since the last frame.
+    events: Vec<InputEvent>,
+    frames: u64,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(File::create(path)?),
+            started: false,
+            events: Vec::new(),
+            frames: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Whether the header was written yet, after which frames can be.
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Writes the header, once the view the session starts from exists.
+    pub fn start(&mut self, size: PhysicalSize<u32>, snapshot: Snapshot) -> std::io::Result<()> {
+        let header = Header {
+            version: VERSION,
+            size: [size.width, size.height],
+            snapshot,
+        };
+        serde_json::to_writer(&mut self.writer, &header)?;
+        writeln!(self.writer)?;
+        self.started = true;
+        Ok(())
+    }
+
+    pub fn push(&mut self, event: InputEvent) {
+        self.events.push(event);
+    }
+
+    /// Writes a frame `dt` seconds long, with the events since the last one.
+    pub fn end_frame(&mut self, dt: f32) -> std::io::Result<()> {
+        let frame = Frame {
+            dt,
+            events: std::mem::take(&mut self.events),
+        };
+        serde_json::to_writer(&mut self.writer, &frame)?;
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// Plays a recorded session back, frame by frame.
+pub struct Player {
+    pub header: Header,
+    frames: Vec<Frame>,
+    next: usize,
+    /// When each frame was played, to report how long they took.
+    played_at: Vec<Instant>,
+}
+
+impl Player {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+
+        let header: Header = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => return Err("empty session".into()),
+        };
+        if header.version != VERSION {
+            return Err(format!("session version {} instead of {VERSION}", header.version).into());
+        }
+
+        let mut frames = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // the last line of a session cut short may be incomplete
+            match serde_json::from_str(&line) {
+                Ok(frame) => frames.push(frame),
+                Err(e) => {
+                    eprintln!("Session {} ends at frame {i}: {e}", path.display());
+                    break;
+                }
+            }
+        }
+
+        Ok(Self {
+            header,
+            frames,
+            next: 0,
+            played_at: Vec::new(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.frames.len()
+    }
+
+    /// The next frame to play, if any is left.
+    pub fn next_frame(&mut self) -> Option<&Frame> {
+        let frame = self.frames.get(self.next)?;
+        self.next += 1;
+        self.played_at.push(Instant::now());
+        Some(frame)
+    }
+
+    /// How long the frames took to play, as measured on this machine.
+    pub fn report(&self) -> Report {
+        let mut intervals: Vec<Duration> =
+            (self.played_at.windows(2)).map(|w| w[1] - w[0]).collect();
+        intervals.sort();
+
+        let total = intervals.iter().sum::<Duration>();
+        let percentile = |p: f32| {
+            let i = ((intervals.len() as f32 - 1.0) * p).round() as usize;
+            intervals.get(i).copied().unwrap_or_default()
+        };
+        Report {
+            frames: self.played_at.len(),
+            total,
+            mean: total / (intervals.len().max(1) as u32),
+            p95: percentile(0.95),
+            worst: intervals.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Frame times of a replay.
+#[derive(Debug, Clone, Copy)]
+pub struct Report {
+    pub frames: usize,
+    pub total: Duration,
+    pub mean: Duration,
+    pub p95: Duration,
+    pub worst: Duration,
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "{} frames in {:.2} s, {:.2} ms mean, {:.2} ms p95, {:.2} ms worst",
+            self.frames,
+            self.total.as_secs_f64(),
+            ms(self.mean),
+            ms(self.p95),
+            ms(self.worst),
+        )
+    }
+}
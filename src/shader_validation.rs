@@ -0,0 +1,295 @@
+//! Compiles shaders ahead of time to catch GLSL mistakes before they turn
+//! into black screens at runtime.
+//!
+//! Every combination of the symbols a shader tests with `#ifdef`, `#ifndef`
+//! or `defined(...)` gets compiled, and errors point back to the line of the
+//! original file.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use gl::types::GLenum;
+
+use crate::common_gl::{has_extension, shader_info_log, supports_compute};
+
+/// Permutations compiled at most per shader, so that a shader testing many
+/// symbols doesn't take forever.
+const MAX_PERMUTATIONS: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct ShaderError {
+    pub path: PathBuf,
+    /// Symbols defined in the permutation that failed.
+    pub defines: Vec<String>,
+    /// Line in the original file, if the driver reported one.
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": {}", self.message)?;
+        if !self.defines.is_empty() {
+            write!(f, " (with {})", self.defines.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Validation {
+    /// Number of shader permutations compiled.
+    pub compiled: usize,
+    /// Number of shaders the context can't compile at all, like compute
+    /// shaders before 4.3 or shaders requiring a missing extension.
+    pub skipped: usize,
+    pub errors: Vec<ShaderError>,
+}
+
+impl Validation {
+    /// Validates every shader in a directory. A GL context must be current.
+    pub unsafe fn dir(dir: &Path) -> io::Result<Self> {
+        let mut paths = (std::fs::read_dir(dir)?)
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        paths.sort();
+
+        let mut validation = Self::default();
+        for path in paths {
+            if shader_stage(&path).is_some() {
+                let source = std::fs::read_to_string(&path)?;
+                validation.check(&path, &source);
+            }
+        }
+
+        Ok(validation)
+    }
+
+    /// Compiles every permutation of a shader, its stage being deduced from
+    /// the extension of `path`. A GL context must be current.
+    pub unsafe fn check(&mut self, path: &Path, source: &str) {
+        let Some(stage) = shader_stage(path) else {
+            self.errors.push(ShaderError {
+                path: path.to_path_buf(),
+                defines: Vec::new(),
+                line: None,
+                message: "unknown shader stage".to_string(),
+            });
+            return;
+        };
+
+        // not an error in the shader, scenes fall back to something else
+        if !is_supported(stage, source) {
+            self.skipped += 1;
+            return;
+        }
+
+        let symbols = preprocessor_symbols(source);
+        let permutations =
+            (1usize << symbols.len().min(usize::BITS as usize - 1)).min(MAX_PERMUTATIONS);
+
+        for permutation in 0..permutations {
+            let defines = (symbols.iter().enumerate())
+                .filter(|(i, _)| permutation & (1 << i) != 0)
+                .map(|(_, symbol)| symbol.clone())
+                .collect::<Vec<_>>();
+
+            let (source, version_line) = with_defines(source, &defines);
+            let inserted = defines.len() as u32;
+
+            self.compiled += 1;
+            if let Err(log) = compile(stage, &source) {
+                for entry in log.lines().filter(|l| !l.trim().is_empty()) {
+                    let (line, message) = match parse_log_line(entry) {
+                        // lines after the inserted defines moved down
+                        Some((line, message)) if line > version_line => {
+                            (Some(line - inserted), message)
+                        }
+                        Some((line, message)) => (Some(line), message),
+                        None => (None, entry.trim().to_string()),
+                    };
+
+                    self.errors.push(ShaderError {
+                        path: path.to_path_buf(),
+                        defines: defines.clone(),
+                        line,
+                        message,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+fn shader_stage(path: &Path) -> Option<GLenum> {
+    match path.extension()?.to_str()? {
+        "vert" => Some(gl::VERTEX_SHADER),
+        "frag" => Some(gl::FRAGMENT_SHADER),
+        "geom" => Some(gl::GEOMETRY_SHADER),
+        "comp" => Some(gl::COMPUTE_SHADER),
+        _ => None,
+    }
+}
+
+unsafe fn is_supported(stage: GLenum, source: &str) -> bool {
+    if stage == gl::COMPUTE_SHADER && !supports_compute() {
+        return false;
+    }
+
+    required_extensions(source).all(|extension| has_extension(extension))
+}
+
+/// Extensions listed with `#extension <name> : require`.
+fn required_extensions(source: &str) -> impl Iterator<Item = &str> {
+    source.lines().filter_map(|line| {
+        let directive = line.trim().strip_prefix('#')?.trim_start();
+        let (name, behavior) = directive.strip_prefix("extension")?.split_once(':')?;
+        (behavior.trim() == "require").then_some(name.trim())
+    })
+}
+
+unsafe fn compile(stage: GLenum, source: &str) -> Result<(), String> {
+    let shader = gl::CreateShader(stage);
+
+    let length = source.len() as i32;
+    let ptr = source.as_ptr() as *const i8;
+    gl::ShaderSource(shader, 1, &ptr, &length);
+    gl::CompileShader(shader);
+
+    let mut status = 0;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+    let log = shader_info_log(shader);
+    gl::DeleteShader(shader);
+
+    if status == 1 {
+        Ok(())
+    } else if log.trim().is_empty() {
+        Err("compilation failed without a log".to_string())
+    } else {
+        Err(log)
+    }
+}
+
+/// Symbols the shader tests for but doesn't define itself.
+fn preprocessor_symbols(source: &str) -> Vec<String> {
+    let mut tested = Vec::new();
+    let mut defined = Vec::new();
+
+    for line in source.lines() {
+        let Some(directive) = line.trim().strip_prefix('#') else {
+            continue;
+        };
+        let directive = directive.trim_start();
+
+        if let Some(rest) =
+            (directive.strip_prefix("ifdef")).or_else(|| directive.strip_prefix("ifndef"))
+        {
+            tested.extend(rest.split_whitespace().next().map(str::to_string));
+        } else if directive.starts_with("if") || directive.starts_with("elif") {
+            let mut rest = directive;
+            while let Some(i) = rest.find("defined") {
+                rest = &rest[i + "defined".len()..];
+                let symbol = (rest.trim_start().trim_start_matches('('))
+                    .trim_start()
+                    .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .next()
+                    .unwrap_or_default();
+                if !symbol.is_empty() {
+                    tested.push(symbol.to_string());
+                }
+            }
+        } else if let Some(rest) = directive.strip_prefix("define") {
+            defined.extend(rest.split_whitespace().next().map(str::to_string));
+        }
+    }
+
+    // built-in macros are set by the driver
+    tested.retain(|s| !s.starts_with("GL_") && !s.starts_with("__") && !defined.contains(s));
+    tested.sort();
+    tested.dedup();
+    tested
+}
+
+/// Inserts `#define`s right after the `#version` line, returning the new
+/// source and the line number of the `#version` directive (1-based, 0 if
+/// there is none).
+fn with_defines(source: &str, defines: &[String]) -> (String, u32) {
+    if defines.is_empty() {
+        return (source.to_string(), 0);
+    }
+
+    let version_line = (source.lines())
+        .position(|l| l.trim_start().starts_with("#version"))
+        .map_or(0, |i| i + 1);
+
+    let mut out = String::with_capacity(source.len() + defines.len() * 32);
+    for (i, line) in source.lines().enumerate() {
+        if i == version_line {
+            for define in defines {
+                out.push_str(&format!("#define {define}\n"));
+            }
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    (out, version_line as u32)
+}
+
+/// Extracts the line number from an info log entry, in any of the formats
+/// drivers commonly use:
+///
+/// - Mesa: `0:12(5): error: ...`
+/// - NVIDIA: `0(12) : error C0000: ...`
+/// - AMD: `ERROR: 0:12: ...`
+fn parse_log_line(entry: &str) -> Option<(u32, String)> {
+    let (severity, rest) = match entry.split_once(": ") {
+        Some((severity @ ("ERROR" | "WARNING"), rest)) => (Some(severity), rest),
+        _ => (None, entry),
+    };
+
+    let digits = |s: &str| s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    // source string number
+    let n = digits(rest);
+    if n == 0 {
+        return None;
+    }
+    let rest = &rest[n..];
+
+    let (line, rest) = if let Some(rest) = rest.strip_prefix(':') {
+        let n = digits(rest);
+        let line = rest[..n].parse().ok()?;
+        let rest = &rest[n..];
+        // skip the column
+        let rest = match rest.strip_prefix('(') {
+            Some(rest) => rest.split_once(')')?.1,
+            None => rest,
+        };
+        (line, rest)
+    } else if let Some(rest) = rest.strip_prefix('(') {
+        let (line, rest) = rest.split_once(')')?;
+        (line.parse().ok()?, rest)
+    } else {
+        return None;
+    };
+
+    let message = rest.trim_start().trim_start_matches(':').trim();
+    let message = match severity {
+        Some(severity) => format!("{}: {message}", severity.to_lowercase()),
+        None => message.to_string(),
+    };
+
+    Some((line, message))
+}
@@ -0,0 +1,68 @@
+//! Everything needed to reproduce the current view, saved as a small TOML
+//! file that can be attached to bug reports.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::camera::Camera;
+use crate::params::{Params, Value};
+use crate::scenes::Scenes;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub scene: String,
+    pub seed: u64,
+    /// Input image of the blur scenes, `None` for the embedded one.
+    pub input: Option<PathBuf>,
+    pub camera: Camera,
+    #[serde(default)]
+    pub params: BTreeMap<String, Value>,
+}
+
+impl Snapshot {
+    pub fn capture(scenes: &Scenes, camera: &Camera, seed: u64, input: Option<&Path>) -> Self {
+        let params = (scenes.params().into_iter())
+            .flat_map(Params::iter)
+            .map(|param| (param.name.to_string(), param.value()))
+            .collect();
+
+        Self {
+            scene: scenes.name().to_string(),
+            seed,
+            input: input.map(Path::to_path_buf),
+            camera: camera.clone(),
+            params,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let snapshot: Self = toml::from_str(&std::fs::read_to_string(path)?)?;
+
+        if !Scenes::names().contains(&snapshot.scene.as_str()) {
+            return Err(format!("unknown scene '{}'", snapshot.scene).into());
+        }
+
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Restores the parameters of a scene created from this snapshot.
+    pub fn restore_params(&self, scenes: &mut Scenes) {
+        let Some(params) = scenes.params_mut() else {
+            return;
+        };
+
+        for (name, value) in &self.params {
+            if let Err(e) = params.set(name, value.clone()) {
+                eprintln!("Snapshot: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Soft bodies with the pressure model of Matyka and Ollila: every blob is a
+//! ring of points joined by springs, filled with a gas pushing on the ring
+//! harder the more the blob is squeezed below its area at rest.
+//!
+//! Blobs collide by pushing the points of one that end up inside another back
+//! out through its closest edge, and stay within rectangular bounds.
+
+use std::f32::consts::TAU;
+
+use glam::Vec2;
+
+/// One blob, as a range of the points of [`SoftBodies`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blob {
+    pub start: usize,
+    pub len: usize,
+    /// Area the gas pushes the blob back to.
+    pub rest_area: f32,
+    /// Length of every spring of the ring at rest.
+    pub rest_length: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub gravity: Vec2,
+    /// Springs of the ring, per unit of stretch.
+    pub stiffness: f32,
+    /// Springs of the ring, per unit of stretching speed.
+    pub damping: f32,
+    /// How hard the gas pushes back, per unit of length of the ring, when the
+    /// blob is squeezed to half its area.
+    pub pressure: f32,
+    /// Rectangle the points stay within, from its minimum to its maximum.
+    pub bounds: (Vec2, Vec2),
+    /// Share of the speed along a wall lost when hitting it.
+    pub friction: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SoftBodies {
+    pub positions: Vec<Vec2>,
+    pub velocities: Vec<Vec2>,
+    pub blobs: Vec<Blob>,
+    forces: Vec<Vec2>,
+}
+
+impl SoftBodies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a round blob of `n_points` at rest, returning its index.
+    pub fn add_blob(&mut self, center: Vec2, radius: f32, n_points: usize) -> usize {
+        let start = self.positions.len();
+        self.positions.extend(
+            (0..n_points)
+                .map(|i| center + radius * Vec2::from_angle(i as f32 / n_points as f32 * TAU)),
+        );
+        self.velocities.resize(self.positions.len(), Vec2::ZERO);
+
+        let mut blob = Blob {
+            start,
+            len: n_points,
+            rest_area: 0.0,
+            rest_length: self.positions[start].distance(self.positions[start + 1]),
+        };
+        blob.rest_area = self.area(&blob);
+        self.blobs.push(blob);
+        self.blobs.len() - 1
+    }
+
+    pub fn points(&self, blob: &Blob) -> &[Vec2] {
+        &self.positions[blob.start..blob.start + blob.len]
+    }
+
+    /// Area enclosed by the ring, positive for the winding blobs are made
+    /// with.
+    pub fn area(&self, blob: &Blob) -> f32 {
+        let points = self.points(blob);
+        let shoelace = (0..points.len())
+            .map(|i| points[i].perp_dot(points[(i + 1) % points.len()]))
+            .sum::<f32>();
+        shoelace * 0.5
+    }
+
+    pub fn centroid(&self, blob: &Blob) -> Vec2 {
+        self.points(blob).iter().sum::<Vec2>() / blob.len as f32
+    }
+
+    /// Whether `point` is inside the ring of `blob`.
+    pub fn contains(&self, blob: &Blob, point: Vec2) -> bool {
+        let points = self.points(blob);
+        let mut inside = false;
+        for i in 0..points.len() {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            if (a.y > point.y) != (b.y > point.y)
+                && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    /// Moves every point by one step of `dt`, with semi-implicit Euler.
+    pub fn step(&mut self, dt: f32, settings: &Settings) {
+        self.forces.clear();
+        self.forces.resize(self.positions.len(), settings.gravity);
+
+        for blob in &self.blobs {
+            let area = self.area(blob).max(blob.rest_area * 0.05);
+            let pressure = settings.pressure * (blob.rest_area / area - 1.0);
+            for i in 0..blob.len {
+                let a = blob.start + i;
+                let b = blob.start + (i + 1) % blob.len;
+                let delta = self.positions[b] - self.positions[a];
+                let length = delta.length();
+                if length < f32::EPSILON {
+                    continue;
+                }
+                let direction = delta / length;
+
+                let stretching = (self.velocities[b] - self.velocities[a]).dot(direction);
+                let spring = direction
+                    * (settings.stiffness * (length - blob.rest_length)
+                        + settings.damping * stretching);
+                // outward for the winding of the ring, along the whole edge
+                let gas = Vec2::new(delta.y, -delta.x) * (pressure * 0.5);
+                self.forces[a] += spring + gas;
+                self.forces[b] += gas - spring;
+            }
+        }
+
+        for ((position, velocity), force) in (self.positions.iter_mut())
+            .zip(&mut self.velocities)
+            .zip(&self.forces)
+        {
+            *velocity += *force * dt;
+            *position += *velocity * dt;
+        }
+
+        self.collide();
+        self.keep_within(settings);
+    }
+
+    /// Pushes every point out of a disc, like a finger poking the blobs.
+    pub fn push_out(&mut self, center: Vec2, radius: f32) {
+        for (position, velocity) in self.positions.iter_mut().zip(&mut self.velocities) {
+            let offset = *position - center;
+            let distance = offset.length();
+            if distance >= radius || distance < f32::EPSILON {
+                continue;
+            }
+            let normal = offset / distance;
+            *position = center + normal * radius;
+            *velocity -= normal * velocity.dot(normal).min(0.0);
+        }
+    }
+
+    /// Pushes the points inside another blob back out through its closest
+    /// edge, moving that edge by as much the other way.
+    fn collide(&mut self) {
+        let boxes = (self.blobs.iter())
+            .map(|blob| {
+                let points = self.points(blob);
+                let min = points.iter().fold(Vec2::MAX, |min, &p| min.min(p));
+                let max = points.iter().fold(Vec2::MIN, |max, &p| max.max(p));
+                (min, max)
+            })
+            .collect::<Vec<_>>();
+
+        for i in 0..self.blobs.len() {
+            for j in 0..self.blobs.len() {
+                let (blob, other) = (self.blobs[i], self.blobs[j]);
+                let ((min, max), (other_min, other_max)) = (boxes[i], boxes[j]);
+                if i == j || max.cmplt(other_min).any() || min.cmpgt(other_max).any() {
+                    continue;
+                }
+
+                for p in blob.start..blob.start + blob.len {
+                    let point = self.positions[p];
+                    if point.cmplt(other_min).any()
+                        || point.cmpgt(other_max).any()
+                        || !self.contains(&other, point)
+                    {
+                        continue;
+                    }
+                    self.resolve(p, &other, point);
+                }
+            }
+        }
+    }
+
+    /// Moves the point `p` at `point` and the closest edge of `other` halfway
+    /// toward each other.
+    fn resolve(&mut self, p: usize, other: &Blob, point: Vec2) {
+        let (mut closest, mut closest_distance) = ((0, 0, 0.0, point), f32::MAX);
+        for k in 0..other.len {
+            let a = other.start + k;
+            let b = other.start + (k + 1) % other.len;
+            let (pa, pb) = (self.positions[a], self.positions[b]);
+            let edge = pb - pa;
+            let t =
+                ((point - pa).dot(edge) / edge.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+            let on_edge = pa + edge * t;
+            let distance = on_edge.distance_squared(point);
+            if distance < closest_distance {
+                (closest, closest_distance) = ((a, b, t, on_edge), distance);
+            }
+        }
+
+        let (a, b, t, on_edge) = closest;
+        let correction = on_edge - point;
+        self.positions[p] += correction * 0.5;
+        self.positions[a] -= correction * 0.5 * (1.0 - t);
+        self.positions[b] -= correction * 0.5 * t;
+
+        // both sides meet at the average of their speeds along the push
+        let Some(normal) = correction.try_normalize() else {
+            return;
+        };
+        let edge_velocity = self.velocities[a].lerp(self.velocities[b], t);
+        let closing = (self.velocities[p] - edge_velocity).dot(normal);
+        if closing < 0.0 {
+            self.velocities[p] -= normal * closing * 0.5;
+            self.velocities[a] += normal * closing * 0.5 * (1.0 - t);
+            self.velocities[b] += normal * closing * 0.5 * t;
+        }
+    }
+
+    fn keep_within(&mut self, settings: &Settings) {
+        let (min, max) = settings.bounds;
+        for (position, velocity) in self.positions.iter_mut().zip(&mut self.velocities) {
+            for axis in 0..2 {
+                let (other, low, high) = (1 - axis, min[axis], max[axis]);
+                let hit = match position[axis] {
+                    x if x < low => (low, velocity[axis] < 0.0),
+                    x if x > high => (high, velocity[axis] > 0.0),
+                    _ => continue,
+                };
+                position[axis] = hit.0;
+                if hit.1 {
+                    velocity[axis] = 0.0;
+                }
+                velocity[other] *= 1.0 - settings.friction;
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Spatial indices over items with a 2D position, to find what is near a point
+//! without looking at everything.
+//!
+//! [`UniformGrid`] buckets items into square cells and suits items spread
+//! evenly, [`Quadtree`] subdivides where items cluster. Both are rebuilt from
+//! scratch whenever items move, which is cheap enough for tens of thousands of
+//! items per frame.
+
+use std::collections::HashMap;
+
+use glam::{IVec2, Vec2};
+
+/// A region of an index, as reported by [`SpatialIndex::regions`] for debug
+/// views.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    pub min: Vec2,
+    pub max: Vec2,
+    /// Number of items stored directly in the region.
+    pub items: usize,
+    /// Depth in a quadtree, 0 for grid cells.
+    pub depth: u32,
+}
+
+pub trait SpatialIndex<T: Copy> {
+    fn clear(&mut self);
+
+    fn insert(&mut self, position: Vec2, item: T);
+
+    /// Pushes the items within `radius` of `center` onto `out`.
+    fn query_circle(&self, center: Vec2, radius: f32, out: &mut Vec<T>);
+
+    /// Every non-empty region of the index.
+    fn regions(&self) -> Vec<Region>;
+}
+
+/// Items bucketed into square cells of `cell_size`, only occupied cells
+/// taking up memory.
+#[derive(Debug, Clone)]
+pub struct UniformGrid<T> {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Vec2, T)>>,
+}
+
+impl<T> UniformGrid<T> {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "cell size must be positive");
+
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+}
+
+impl<T: Copy> SpatialIndex<T> for UniformGrid<T> {
+    fn clear(&mut self) {
+        // keep the allocations of the cells around for the next frame
+        self.cells.values_mut().for_each(Vec::clear);
+    }
+
+    fn insert(&mut self, position: Vec2, item: T) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push((position, item));
+    }
+
+    fn query_circle(&self, center: Vec2, radius: f32, out: &mut Vec<T>) {
+        let min = self.cell(center - radius);
+        let max = self.cell(center + radius);
+        let radius_squared = radius * radius;
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let Some(items) = self.cells.get(&IVec2::new(x, y)) else {
+                    continue;
+                };
+
+                out.extend(
+                    (items.iter())
+                        .filter(|(position, _)| position.distance_squared(center) <= radius_squared)
+                        .map(|&(_, item)| item),
+                );
+            }
+        }
+    }
+
+    fn regions(&self) -> Vec<Region> {
+        (self.cells.iter())
+            .filter(|(_, items)| !items.is_empty())
+            .map(|(cell, items)| {
+                let min = cell.as_vec2() * self.cell_size;
+                Region {
+                    min,
+                    max: min + self.cell_size,
+                    items: items.len(),
+                    depth: 0,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Items in a square that is split in four whenever one of its quadrants holds
+/// more than `capacity` items, up to `max_depth` times.
+///
+/// Items outside of the bounds are kept in the root.
+#[derive(Debug, Clone)]
+pub struct Quadtree<T> {
+    capacity: usize,
+    max_depth: u32,
+    /// The root is node 0, and the children of a node are 4 consecutive nodes.
+    nodes: Vec<Node<T>>,
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    min: Vec2,
+    max: Vec2,
+    depth: u32,
+    /// Index of the first of the 4 children, if split.
+    children: Option<usize>,
+    items: Vec<(Vec2, T)>,
+}
+
+impl<T> Node<T> {
+    fn new(min: Vec2, max: Vec2, depth: u32) -> Self {
+        Self {
+            min,
+            max,
+            depth,
+            children: None,
+            items: Vec::new(),
+        }
+    }
+
+    fn contains(&self, position: Vec2) -> bool {
+        position.cmpge(self.min).all() && position.cmplt(self.max).all()
+    }
+
+    /// Which of the 4 children `position` goes into.
+    fn quadrant(&self, position: Vec2) -> usize {
+        let center = (self.min + self.max) * 0.5;
+        (position.x >= center.x) as usize + 2 * (position.y >= center.y) as usize
+    }
+}
+
+impl<T: Copy> Quadtree<T> {
+    pub fn new(min: Vec2, max: Vec2, capacity: usize, max_depth: u32) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            max_depth,
+            nodes: vec![Node::new(min, max, 0)],
+        }
+    }
+
+    /// Splits node `index` in four, moving its items into the children.
+    fn split(&mut self, index: usize) {
+        let Node {
+            min, max, depth, ..
+        } = self.nodes[index];
+        let center = (min + max) * 0.5;
+
+        let first = self.nodes.len();
+        for quadrant in 0..4 {
+            let (x, y) = (quadrant % 2 == 1, quadrant / 2 == 1);
+            let child_min = Vec2::new(
+                if x { center.x } else { min.x },
+                if y { center.y } else { min.y },
+            );
+            let child_max = Vec2::new(
+                if x { max.x } else { center.x },
+                if y { max.y } else { center.y },
+            );
+            self.nodes.push(Node::new(child_min, child_max, depth + 1));
+        }
+
+        let node = &mut self.nodes[index];
+        node.children = Some(first);
+        let items = std::mem::take(&mut node.items);
+        for (position, item) in items {
+            let quadrant = self.nodes[index].quadrant(position);
+            self.nodes[first + quadrant].items.push((position, item));
+        }
+    }
+}
+
+impl<T: Copy> SpatialIndex<T> for Quadtree<T> {
+    fn clear(&mut self) {
+        let root = &self.nodes[0];
+        self.nodes = vec![Node::new(root.min, root.max, 0)];
+    }
+
+    fn insert(&mut self, position: Vec2, item: T) {
+        let mut index = 0;
+        if !self.nodes[0].contains(position) {
+            self.nodes[0].items.push((position, item));
+            return;
+        }
+
+        loop {
+            let node = &self.nodes[index];
+            if let Some(first) = node.children {
+                index = first + node.quadrant(position);
+                continue;
+            }
+
+            if node.items.len() < self.capacity || node.depth >= self.max_depth {
+                self.nodes[index].items.push((position, item));
+                return;
+            }
+
+            self.split(index);
+        }
+    }
+
+    fn query_circle(&self, center: Vec2, radius: f32, out: &mut Vec<T>) {
+        let radius_squared = radius * radius;
+
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+
+            out.extend(
+                (node.items.iter())
+                    .filter(|(position, _)| position.distance_squared(center) <= radius_squared)
+                    .map(|&(_, item)| item),
+            );
+
+            if let Some(first) = node.children {
+                stack.extend((first..first + 4).filter(|&child| {
+                    let child = &self.nodes[child];
+                    let closest = center.clamp(child.min, child.max);
+                    closest.distance_squared(center) <= radius_squared
+                }));
+            }
+        }
+    }
+
+    fn regions(&self) -> Vec<Region> {
+        (self.nodes.iter())
+            .filter(|node| node.children.is_some() || !node.items.is_empty())
+            .map(|node| Region {
+                min: node.min,
+                max: node.max,
+                items: node.items.len(),
+                depth: node.depth,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,163 @@
+//! Cubic bezier and Catmull-Rom curves, tessellated adaptively into polylines
+//! that can be walked at constant speed.
+
+use std::error::Error;
+use std::path::Path;
+
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Subdivisions stop at this depth even if the curve isn't flat enough, which
+/// only happens with degenerate control points.
+const MAX_DEPTH: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CurveKind {
+    /// Anchors and handles: `anchor, handle, handle, anchor, handle, ...`.
+    Bezier,
+    /// Points the curve passes through.
+    CatmullRom,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Curve {
+    pub kind: CurveKind,
+    pub points: Vec<Vec2>,
+}
+
+impl Curve {
+    /// The curve as cubic bezier segments.
+    pub fn segments(&self) -> Vec<[Vec2; 4]> {
+        let points = &self.points;
+
+        match self.kind {
+            CurveKind::Bezier => (points.windows(4).step_by(3))
+                .map(|w| [w[0], w[1], w[2], w[3]])
+                .collect(),
+            CurveKind::CatmullRom => (0..points.len().saturating_sub(1))
+                .map(|i| {
+                    // the ends are extended by repeating the first and last points
+                    let p0 = points[i.saturating_sub(1)];
+                    let (p1, p2) = (points[i], points[i + 1]);
+                    let p3 = points[(i + 2).min(points.len() - 1)];
+                    [p1, p1 + (p2 - p0) / 6.0, p2 - (p3 - p1) / 6.0, p2]
+                })
+                .collect(),
+        }
+    }
+
+    /// Appends the curve as a polyline onto `out`, straying at most
+    /// `tolerance` away from it.
+    pub fn tessellate(&self, tolerance: f32, out: &mut Vec<Vec2>) {
+        let segments = self.segments();
+        if let Some(first) = segments.first() {
+            out.push(first[0]);
+        }
+
+        for segment in segments {
+            tessellate_cubic(segment, tolerance, 0, out);
+        }
+    }
+}
+
+/// Appends a cubic bezier segment onto `out`, without its first point,
+/// splitting it in halves until each is flat enough.
+pub fn tessellate_cubic(segment: [Vec2; 4], tolerance: f32, depth: u32, out: &mut Vec<Vec2>) {
+    let [p0, p1, p2, p3] = segment;
+
+    if depth >= MAX_DEPTH || flatness(segment) <= tolerance {
+        out.push(p3);
+        return;
+    }
+
+    // de Casteljau at t = 0.5
+    let (p01, p12, p23) = ((p0 + p1) * 0.5, (p1 + p2) * 0.5, (p2 + p3) * 0.5);
+    let (p012, p123) = ((p01 + p12) * 0.5, (p12 + p23) * 0.5);
+    let middle = (p012 + p123) * 0.5;
+
+    tessellate_cubic([p0, p01, p012, middle], tolerance, depth + 1, out);
+    tessellate_cubic([middle, p123, p23, p3], tolerance, depth + 1, out);
+}
+
+/// How far the handles of a segment are from the line between its anchors,
+/// which bounds how far the curve is from it.
+fn flatness([p0, p1, p2, p3]: [Vec2; 4]) -> f32 {
+    let chord = p3 - p0;
+    let length = chord.length();
+    if length < f32::EPSILON {
+        return p1.distance(p0).max(p2.distance(p0));
+    }
+
+    let normal = chord.perp() / length;
+    (p1 - p0).dot(normal).abs().max((p2 - p0).dot(normal).abs())
+}
+
+/// A polyline parameterized by the distance along it.
+#[derive(Debug, Clone, Default)]
+pub struct ArcLength {
+    points: Vec<Vec2>,
+    /// Distance from the start to each point.
+    distances: Vec<f32>,
+}
+
+impl ArcLength {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        let mut total = 0.0;
+        let distances = (points.iter().enumerate())
+            .map(|(i, point)| {
+                if i > 0 {
+                    total += point.distance(points[i - 1]);
+                }
+                total
+            })
+            .collect();
+
+        Self { points, distances }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    /// Position and direction at `distance` along the polyline, clamped to
+    /// its ends.
+    pub fn sample(&self, distance: f32) -> Option<(Vec2, Vec2)> {
+        match self.points.len() {
+            0 => return None,
+            1 => return Some((self.points[0], Vec2::X)),
+            _ => {}
+        }
+
+        let distance = distance.clamp(0.0, self.length());
+        let i = self.distances.partition_point(|&d| d < distance);
+        let i = i.clamp(1, self.points.len() - 1);
+
+        let (a, b) = (self.points[i - 1], self.points[i]);
+        let span = self.distances[i] - self.distances[i - 1];
+        let t = if span > 0.0 {
+            (distance - self.distances[i - 1]) / span
+        } else {
+            0.0
+        };
+
+        Some((a.lerp(b, t), (b - a).try_normalize().unwrap_or(Vec2::X)))
+    }
+}
+
+/// Curves saved by the spline editor.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CurveSet {
+    pub curves: Vec<Curve>,
+}
+
+impl CurveSet {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
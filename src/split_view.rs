@@ -0,0 +1,160 @@
+//! Split view: the scene drawn twice side by side, each half through its own
+//! camera.
+//!
+//! Scenes draw over the whole default framebuffer from its origin and set up
+//! their own viewports and offscreen passes, so they can't be told to stay in
+//! one half. Instead, each half is drawn on its own at the size of a half,
+//! copied aside, and both copies are blitted to their side once done.
+
+#![allow(clippy::missing_safety_doc)]
+
+use gl::types::GLint;
+use glam::{uvec2, UVec2};
+
+use crate::common_gl::{
+    create_framebuffer, delete_framebuffer, pop_debug_group, push_debug_group, Framebuffer,
+};
+use crate::error::GlError;
+use crate::theme;
+
+/// Width of the line between both halves, in pixels.
+const DIVIDER_WIDTH: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn other(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SplitView {
+    /// Framebuffers sized for the halves of this viewport.
+    viewport: UVec2,
+    /// Copies of the left and right halves.
+    halves: Option<[Framebuffer; 2]>,
+}
+
+impl SplitView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size of each half of `viewport`. The right half starts where the
+    /// window ends minus this width, leaving the odd column in the middle.
+    pub fn half_size(viewport: UVec2) -> UVec2 {
+        uvec2(viewport.x / 2, viewport.y)
+    }
+
+    /// Draws both halves of `viewport` with `draw`, which renders the given
+    /// side to the default framebuffer at the size of a half. `last` is drawn
+    /// last, so that whatever a scene keeps from its last draw, like what is
+    /// hovered, comes from the half under the mouse.
+    pub unsafe fn draw(
+        &mut self,
+        viewport: UVec2,
+        last: Side,
+        mut draw: impl FnMut(Side),
+    ) -> Result<(), GlError> {
+        self.resize(viewport)?;
+        let Some(halves) = &self.halves else {
+            return Ok(());
+        };
+
+        push_debug_group(c"Split view");
+        let half = Self::half_size(viewport);
+        for side in [last.other(), last] {
+            draw(side);
+
+            let copy = &halves[side as usize];
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, copy.fbo);
+            blit(half, UVec2::ZERO);
+        }
+
+        push_debug_group(c"Halves to screen");
+        gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+        for (copy, x) in halves.iter().zip([0, viewport.x - half.x]) {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, copy.fbo);
+            blit(half, uvec2(x, 0));
+        }
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+        let [r, g, b, a] = theme::current().grid;
+        let divider_x = (viewport.x - DIVIDER_WIDTH) / 2;
+        gl::Enable(gl::SCISSOR_TEST);
+        gl::Scissor(
+            divider_x as GLint,
+            0,
+            DIVIDER_WIDTH as GLint,
+            viewport.y as GLint,
+        );
+        gl::ClearColor(r, g, b, a);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        gl::Disable(gl::SCISSOR_TEST);
+        pop_debug_group();
+
+        // scenes left the viewport at the size of a half
+        gl::Viewport(0, 0, viewport.x as GLint, viewport.y as GLint);
+        pop_debug_group();
+        Ok(())
+    }
+
+    /// Recreates the framebuffers if the viewport changed size.
+    unsafe fn resize(&mut self, viewport: UVec2) -> Result<(), GlError> {
+        if viewport == self.viewport {
+            return Ok(());
+        }
+
+        self.delete_framebuffers();
+        let half = Self::half_size(viewport);
+        if half.min_element() > 0 {
+            let left = create_framebuffer("split view left", half)?;
+            let right = create_framebuffer("split view right", half)
+                .inspect_err(|_| delete_framebuffer(&left))?;
+            self.halves = Some([left, right]);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        // only once they exist, so that failing tries again next time
+        self.viewport = viewport;
+        Ok(())
+    }
+
+    unsafe fn delete_framebuffers(&mut self) {
+        for framebuffer in self.halves.take().iter().flatten() {
+            delete_framebuffer(framebuffer);
+        }
+    }
+}
+
+impl Drop for SplitView {
+    fn drop(&mut self) {
+        unsafe { self.delete_framebuffers() };
+    }
+}
+
+/// Copies the rectangle of `size` at the origin of the read framebuffer to
+/// `to` in the draw framebuffer.
+unsafe fn blit(size: UVec2, to: UVec2) {
+    let (size, to) = (size.as_ivec2(), to.as_ivec2());
+    gl::BlitFramebuffer(
+        0,
+        0,
+        size.x,
+        size.y,
+        to.x,
+        to.y,
+        to.x + size.x,
+        to.y + size.y,
+        gl::COLOR_BUFFER_BIT,
+        gl::NEAREST,
+    );
+}
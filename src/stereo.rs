@@ -0,0 +1,284 @@
+//! Stereo rendering: the scene drawn once per eye, from cameras a few pixels
+//! apart, then composited as a red/cyan anaglyph or side by side.
+//!
+//! Scenes are flat, so on its own this only pushes the whole field behind the
+//! screen. Scenes with a notion of height, like the elevation of the round
+//! quads, shift each eye's image by [`Eye::parallax`] to bring what is higher
+//! toward the viewer.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::mem;
+
+use gl::types::{GLsizei, GLuint};
+use glam::{vec2, UVec2, Vec2};
+
+use crate::camera::Camera;
+use crate::common_gl::{
+    buffer_data, create_framebuffer, create_shader_program, delete_buffers, delete_framebuffer,
+    pop_debug_group, push_debug_group, Framebuffer, SavedState,
+};
+use crate::error::GlError;
+use crate::split_view::{Side, SplitView};
+
+const SRC_VERT_SCREEN: &[u8] = include_bytes!("../assets/shaders/screen.vert");
+const SRC_FRAG_ANAGLYPH: &[u8] = include_bytes!("../assets/shaders/anaglyph.frag");
+
+/// Distance between the images of both eyes for things lying on the field,
+/// in pixels. The field appears a little behind the screen.
+const EYE_SEPARATION: f32 = 8.0;
+/// How far each eye's image of something moves toward the other eye per unit
+/// of height, in pixels.
+const PARALLAX: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StereoMode {
+    #[default]
+    Off,
+    /// Both eyes over each other, the left one in red and the right one in
+    /// cyan, for red/cyan glasses.
+    Anaglyph,
+    /// Each eye in its half of the window, for parallel viewing.
+    SideBySide,
+}
+
+impl StereoMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Anaglyph => "anaglyph",
+            Self::SideBySide => "side-by-side",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Anaglyph,
+            Self::Anaglyph => Self::SideBySide,
+            Self::SideBySide => Self::Off,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+impl Eye {
+    fn sign(self) -> f32 {
+        match self {
+            Self::Left => -1.0,
+            Self::Right => 1.0,
+        }
+    }
+
+    /// `camera` as seen from this eye.
+    pub fn camera(self, camera: &Camera) -> Camera {
+        // each eye sees the field moved away from the other eye, so that it
+        // sits behind the screen
+        let shift = vec2(self.sign() * EYE_SEPARATION * 0.5, 0.0) / camera.scale;
+        Camera {
+            position: camera.position + Vec2::from_angle(-camera.rotation).rotate(shift),
+            ..camera.clone()
+        }
+    }
+
+    /// World offset per unit of height of what this eye sees, toward the
+    /// other eye so that higher things come out of the screen.
+    pub fn parallax(self, camera: &Camera) -> Vec2 {
+        let shift = vec2(-self.sign() * PARALLAX, 0.0) / camera.scale;
+        Vec2::from_angle(-camera.rotation).rotate(shift)
+    }
+}
+
+/// Vertex of the quad covering the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vec2,
+    uv: Vec2,
+}
+
+pub struct Stereo {
+    mode: StereoMode,
+    split_view: SplitView,
+
+    /// Framebuffers sized for this viewport.
+    viewport: UVec2,
+    /// What the left and right eyes see, for the anaglyph.
+    eyes: Option<[Framebuffer; 2]>,
+
+    shader: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+}
+
+impl Stereo {
+    pub unsafe fn new() -> Result<Self, GlError> {
+        let shader = create_shader_program(SRC_VERT_SCREEN, SRC_FRAG_ANAGLYPH)?;
+        for (name, unit) in [(c"u_left", 0), (c"u_right", 1)] {
+            gl::Uniform1i(gl::GetUniformLocation(shader, name.as_ptr()), unit);
+        }
+
+        let vertex = |x: f32, y: f32| Vertex {
+            position: vec2(x, y) * 2.0 - 1.0,
+            uv: vec2(x, y),
+        };
+        let vertices = [
+            vertex(0.0, 1.0),
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+        ];
+
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        gl::BindVertexArray(vao);
+        let mut vbo = 0;
+        gl::GenBuffers(1, &mut vbo);
+        buffer_data(gl::ARRAY_BUFFER, vbo, &vertices, gl::STATIC_DRAW);
+
+        const SIZE_VERTEX: GLsizei = mem::size_of::<Vertex>() as GLsizei;
+        for (name, offset) in [(c"position", 0), (c"uv", mem::size_of::<Vec2>())] {
+            let location = gl::GetAttribLocation(shader, name.as_ptr()) as GLuint;
+            gl::VertexAttribPointer(location, 2, gl::FLOAT, gl::FALSE, SIZE_VERTEX, offset as _);
+            gl::EnableVertexAttribArray(location);
+        }
+        gl::BindVertexArray(0);
+
+        Ok(Self {
+            mode: StereoMode::Off,
+            split_view: SplitView::new(),
+            viewport: UVec2::ZERO,
+            eyes: None,
+            shader,
+            vao,
+            vbo,
+        })
+    }
+
+    pub fn mode(&self) -> StereoMode {
+        self.mode
+    }
+
+    /// Goes from off to anaglyph, side by side, and back. Returns the new mode.
+    pub fn cycle(&mut self) -> StereoMode {
+        self.mode = self.mode.next();
+        self.mode
+    }
+
+    pub fn turn_off(&mut self) {
+        self.mode = StereoMode::Off;
+    }
+
+    /// Draws the scene once per eye with `draw`, which renders what the given
+    /// eye sees to the default framebuffer at the given size, then composites
+    /// both eyes there. Does nothing while stereo is off.
+    pub unsafe fn draw(
+        &mut self,
+        viewport: UVec2,
+        mut draw: impl FnMut(Eye, UVec2),
+    ) -> Result<(), GlError> {
+        match self.mode {
+            StereoMode::Off => Ok(()),
+            StereoMode::SideBySide => {
+                let half = SplitView::half_size(viewport);
+                self.split_view
+                    .draw(viewport, Side::Right, |side| match side {
+                        Side::Left => draw(Eye::Left, half),
+                        Side::Right => draw(Eye::Right, half),
+                    })
+            }
+            StereoMode::Anaglyph => self.draw_anaglyph(viewport, draw),
+        }
+    }
+
+    unsafe fn draw_anaglyph(
+        &mut self,
+        viewport: UVec2,
+        mut draw: impl FnMut(Eye, UVec2),
+    ) -> Result<(), GlError> {
+        self.resize(viewport)?;
+        let Some(eyes) = &self.eyes else {
+            return Ok(());
+        };
+
+        push_debug_group(c"Anaglyph");
+        let size = viewport.as_ivec2();
+        for (eye, copy) in [Eye::Left, Eye::Right].into_iter().zip(eyes) {
+            draw(eye, viewport);
+
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, copy.fbo);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                size.x,
+                size.y,
+                0,
+                0,
+                size.x,
+                size.y,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+        }
+
+        let saved = SavedState::save();
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, size.x, size.y);
+        gl::Disable(gl::BLEND);
+
+        gl::UseProgram(self.shader);
+        gl::BindVertexArray(self.vao);
+        gl::ActiveTexture(gl::TEXTURE1);
+        gl::BindTexture(gl::TEXTURE_2D, eyes[1].texture);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, eyes[0].texture);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+        saved.restore();
+        pop_debug_group();
+        Ok(())
+    }
+
+    /// Recreates the framebuffers if the viewport changed size.
+    unsafe fn resize(&mut self, viewport: UVec2) -> Result<(), GlError> {
+        if viewport == self.viewport {
+            return Ok(());
+        }
+
+        self.delete_framebuffers();
+        if viewport.min_element() > 0 {
+            let left = create_framebuffer("stereo left eye", viewport)?;
+            let right = create_framebuffer("stereo right eye", viewport)
+                .inspect_err(|_| delete_framebuffer(&left))?;
+            self.eyes = Some([left, right]);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        // only once they exist, so that failing tries again next time
+        self.viewport = viewport;
+        Ok(())
+    }
+
+    unsafe fn delete_framebuffers(&mut self) {
+        for framebuffer in self.eyes.take().iter().flatten() {
+            delete_framebuffer(framebuffer);
+        }
+    }
+}
+
+impl Drop for Stereo {
+    fn drop(&mut self) {
+        unsafe {
+            self.delete_framebuffers();
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+        }
+    }
+}
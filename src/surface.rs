@@ -0,0 +1,83 @@
+//! Whether the window is opaque or shows what's behind it.
+//!
+//! Scenes clear with a translucent color and blend over it, so the alpha of
+//! the window ends up anywhere between 0 and 1. Compositors honoring surface
+//! alpha then show the desktop through the whole window. Unless transparency
+//! was asked for, the alpha of the window is set back to 1 right before it's
+//! presented, whatever was drawn and whichever config was picked.
+//!
+//! Where a surface can be transparent at all is decided when it's created:
+//! X11 needs a config with an ARGB visual, which the config picker only
+//! prefers for transparent windows. Switching at runtime works where winit
+//! supports [`Window::set_transparent`](winit::window::Window::set_transparent)
+//! and the config has alpha.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compositing {
+    /// The window covers what's behind it.
+    #[default]
+    Opaque,
+    /// The alpha of each pixel lets what's behind the window through.
+    Transparent,
+}
+
+impl Compositing {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Opaque => "opaque",
+            Self::Transparent => "transparent",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Opaque => Self::Transparent,
+            Self::Transparent => Self::Opaque,
+        }
+    }
+}
+
+impl FromStr for Compositing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "opaque" => Ok(Self::Opaque),
+            "transparent" => Ok(Self::Transparent),
+            _ => Err(format!("'{s}' is neither opaque nor transparent")),
+        }
+    }
+}
+
+static TRANSPARENT: AtomicBool = AtomicBool::new(false);
+
+pub fn compositing() -> Compositing {
+    match TRANSPARENT.load(Ordering::Relaxed) {
+        true => Compositing::Transparent,
+        false => Compositing::Opaque,
+    }
+}
+
+pub fn set_compositing(compositing: Compositing) {
+    TRANSPARENT.store(compositing == Compositing::Transparent, Ordering::Relaxed);
+}
+
+/// Ends the frame of the window's framebuffer, making it opaque unless it
+/// should be transparent.
+pub unsafe fn finish_frame() {
+    if compositing() == Compositing::Transparent {
+        return;
+    }
+
+    gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    gl::Disable(gl::SCISSOR_TEST);
+    gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::TRUE);
+    gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+    gl::Clear(gl::COLOR_BUFFER_BIT);
+    gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+}
@@ -0,0 +1,505 @@
+//! On-screen text, shaped with cosmic-text and drawn from a glyph atlas.
+//!
+//! Shaping handles any UTF-8 text: glyphs missing from the default sans-serif
+//! font are taken from whichever system font covers their script, which is how
+//! CJK text gets drawn. Glyphs are rasterized once into RGBA atlas pages:
+//! outlines as white coverage tinted by the text color, and color glyphs (COLR
+//! or bitmap emoji) as they are, only faded by the text's alpha.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+
+use cosmic_text::{
+    Attrs, Buffer, CacheKey, Family, FontSystem, Metrics, Shaping, SwashCache, SwashContent,
+};
+use gl::types::{GLint, GLsizei, GLuint};
+use glam::{uvec2, vec2, UVec2, Vec2};
+
+use crate::common_gl::{
+    buffer_data, create_shader_program, delete_buffers, delete_textures, SavedState,
+};
+use crate::error::GlError;
+use crate::gpu_memory::{self, Category, Object};
+
+const SRC_VERT_TEXT: &[u8] = include_bytes!("../assets/shaders/text.vert");
+const SRC_FRAG_TEXT: &[u8] = include_bytes!("../assets/shaders/text.frag");
+
+const ATLAS_SIZE: u32 = 1024;
+
+/// Pages added as they fill up, before starting over from an empty atlas.
+const MAX_PAGES: usize = 4;
+
+/// Empty texels around each glyph, so that filtering doesn't bleed neighbors in.
+const PADDING: u32 = 1;
+
+/// Lines are this many times as tall as the font size.
+const LINE_HEIGHT: f32 = 1.3;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct TextVertex {
+    position: Vec2,
+    uv: Vec2,
+    color: [f32; 4],
+    /// 1 for glyphs drawn with their own colors, 0 for the rest.
+    is_color: f32,
+}
+
+/// Where a rasterized glyph is in the atlas, and how to place it relative to
+/// its pen position.
+#[derive(Debug, Clone, Copy)]
+struct AtlasGlyph {
+    page: usize,
+    is_color: bool,
+    min: UVec2,
+    size: UVec2,
+    left: i32,
+    top: i32,
+}
+
+/// Packs rectangles into rows, each as tall as the tallest rectangle it had
+/// room for when it was opened.
+#[derive(Debug)]
+struct ShelfPacker {
+    size: u32,
+    /// `(y, height, x)` of each row.
+    shelves: Vec<(u32, u32, u32)>,
+    next_y: u32,
+}
+
+impl ShelfPacker {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            shelves: Vec::new(),
+            next_y: 0,
+        }
+    }
+
+    fn allocate(&mut self, size: UVec2) -> Option<UVec2> {
+        let padded = size + PADDING * 2;
+
+        let shelf = (self.shelves.iter_mut())
+            .find(|(_, height, x)| padded.y <= *height && x + padded.x <= self.size);
+        if let Some((y, _, x)) = shelf {
+            let min = uvec2(*x, *y) + PADDING;
+            *x += padded.x;
+            return Some(min);
+        }
+
+        if self.next_y + padded.y > self.size || padded.x > self.size {
+            return None;
+        }
+
+        let y = self.next_y;
+        self.shelves.push((y, padded.y, padded.x));
+        self.next_y += padded.y;
+        Some(uvec2(0, y) + PADDING)
+    }
+}
+
+/// One texture of the atlas, with the glyphs queued from it this frame.
+#[derive(Debug)]
+struct Page {
+    texture: GLuint,
+    packer: ShelfPacker,
+    vertices: Vec<TextVertex>,
+}
+
+impl Page {
+    unsafe fn new() -> Self {
+        let mut texture = 0;
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA8 as GLint,
+            ATLAS_SIZE as GLsizei,
+            ATLAS_SIZE as GLsizei,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            std::ptr::null(),
+        );
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_S,
+            gl::CLAMP_TO_EDGE as GLint,
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_T,
+            gl::CLAMP_TO_EDGE as GLint,
+        );
+        gpu_memory::record(
+            Object::Texture(texture),
+            Category::Textures,
+            (ATLAS_SIZE * ATLAS_SIZE * 4) as usize,
+        );
+
+        Self {
+            texture,
+            packer: ShelfPacker::new(ATLAS_SIZE),
+            vertices: Vec::new(),
+        }
+    }
+
+    /// Writes RGBA `pixels` of `size` at `min`.
+    unsafe fn upload(&self, min: UVec2, size: UVec2, pixels: &[u8]) {
+        gl::BindTexture(gl::TEXTURE_2D, self.texture);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        gl::TexSubImage2D(
+            gl::TEXTURE_2D,
+            0,
+            min.x as GLint,
+            min.y as GLint,
+            size.x as GLsizei,
+            size.y as GLsizei,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_ptr() as *const _,
+        );
+    }
+}
+
+/// The text renderer of the window, shared by the overlay and the scenes that
+/// label what they draw, as creating one scans the system's fonts.
+pub type SharedText = Rc<RefCell<TextRenderer>>;
+
+/// Batches text and rectangles in window pixels, drawn in one call on top of
+/// whatever is already there.
+pub struct TextRenderer {
+    font_system: FontSystem,
+    swash_cache: SwashCache,
+    /// Reused to shape every piece of text.
+    buffer: Buffer,
+
+    /// `None` for glyphs without pixels, like spaces.
+    glyphs: HashMap<CacheKey, Option<AtlasGlyph>>,
+    pages: Vec<Page>,
+    /// A block of opaque white texels in the first page that rectangles are
+    /// drawn with.
+    solid: Vec2,
+
+    shader: GLuint,
+    u_viewport: GLint,
+    u_atlas_size: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+
+    /// Drawn before the glyphs, so that panels can be queued after their text
+    /// has been measured.
+    rects: Vec<TextVertex>,
+}
+
+impl TextRenderer {
+    pub unsafe fn new() -> Result<Self, GlError> {
+        let mut font_system = FontSystem::new();
+        let buffer = Buffer::new(&mut font_system, Metrics::new(16.0, 16.0 * LINE_HEIGHT));
+
+        let shader = create_shader_program(SRC_VERT_TEXT, SRC_FRAG_TEXT)?;
+        let u_viewport = gl::GetUniformLocation(shader, c"u_viewport".as_ptr());
+        let u_atlas_size = gl::GetUniformLocation(shader, c"u_atlas_size".as_ptr());
+
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        let mut vbo = 0;
+        gl::GenBuffers(1, &mut vbo);
+
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+        let size_f32 = mem::size_of::<f32>() as GLsizei;
+        let size_vertex = mem::size_of::<TextVertex>() as GLsizei;
+        gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, size_vertex, 0 as _);
+        gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, size_vertex, (2 * size_f32) as _);
+        gl::VertexAttribPointer(2, 4, gl::FLOAT, gl::FALSE, size_vertex, (4 * size_f32) as _);
+        gl::VertexAttribPointer(3, 1, gl::FLOAT, gl::FALSE, size_vertex, (8 * size_f32) as _);
+        for location in 0..4 {
+            gl::EnableVertexAttribArray(location);
+        }
+
+        gl::BindVertexArray(0);
+
+        let mut renderer = Self {
+            font_system,
+            swash_cache: SwashCache::new(),
+            buffer,
+
+            glyphs: HashMap::new(),
+            pages: vec![Page::new()],
+            solid: Vec2::ZERO,
+
+            shader,
+            u_viewport,
+            u_atlas_size,
+            vao,
+            vbo,
+
+            rects: Vec::new(),
+        };
+        renderer.clear_atlas();
+        Ok(renderer)
+    }
+
+    pub unsafe fn shared() -> Result<SharedText, GlError> {
+        Ok(Rc::new(RefCell::new(Self::new()?)))
+    }
+
+    /// Forgets every glyph, keeping the first page with only the solid block.
+    unsafe fn clear_atlas(&mut self) {
+        self.glyphs.clear();
+        for page in self.pages.drain(1..) {
+            delete_textures(&[page.texture]);
+        }
+
+        let page = &mut self.pages[0];
+        page.packer = ShelfPacker::new(ATLAS_SIZE);
+        page.vertices.clear();
+
+        let min = page.packer.allocate(UVec2::splat(2)).unwrap();
+        page.upload(min, UVec2::splat(2), &[255; 16]);
+        self.solid = min.as_vec2() + 1.0;
+    }
+
+    /// Shapes `text` into the scratch buffer, wrapping lines longer than
+    /// `max_width`. Returns the size it takes up.
+    fn shape(&mut self, text: &str, size: f32, max_width: Option<f32>) -> Vec2 {
+        let metrics = Metrics::new(size, size * LINE_HEIGHT);
+        let font_system = &mut self.font_system;
+
+        (self.buffer).set_metrics_and_size(font_system, metrics, max_width, None);
+        (self.buffer).set_text(
+            font_system,
+            text,
+            Attrs::new().family(Family::SansSerif),
+            Shaping::Advanced,
+        );
+
+        (self.buffer.layout_runs()).fold(Vec2::ZERO, |extent, run| {
+            extent.max(vec2(run.line_w, run.line_top + run.line_height))
+        })
+    }
+
+    /// Size `text` would take up, in pixels.
+    pub fn measure(&mut self, text: &str, size: f32, max_width: Option<f32>) -> Vec2 {
+        self.shape(text, size, max_width)
+    }
+
+    /// Queues `text` with its top left corner at `position`. Returns the size it
+    /// takes up.
+    pub fn queue(
+        &mut self,
+        text: &str,
+        position: Vec2,
+        size: f32,
+        max_width: Option<f32>,
+        color: [f32; 4],
+    ) -> Vec2 {
+        let extent = self.shape(text, size, max_width);
+
+        let mut glyphs = Vec::new();
+        for run in self.buffer.layout_runs() {
+            for glyph in run.glyphs {
+                let physical = glyph.physical((position.x, position.y + run.line_y), 1.0);
+                glyphs.push((physical.cache_key, physical.x, physical.y));
+            }
+        }
+
+        for (cache_key, x, y) in glyphs {
+            let Some(glyph) = (unsafe { self.glyph(cache_key) }) else {
+                continue;
+            };
+
+            let min = vec2((x + glyph.left) as f32, (y - glyph.top) as f32);
+            let uv = glyph.min.as_vec2();
+            let size = glyph.size.as_vec2();
+            let vertices = &mut self.pages[glyph.page].vertices;
+            let is_color = glyph.is_color;
+            push_quad(vertices, min, min + size, uv, uv + size, color, is_color);
+        }
+
+        extent
+    }
+
+    /// Queues a filled rectangle, drawn below every piece of text.
+    pub fn queue_rect(&mut self, min: Vec2, max: Vec2, color: [f32; 4]) {
+        push_quad(
+            &mut self.rects,
+            min,
+            max,
+            self.solid,
+            self.solid,
+            color,
+            false,
+        );
+    }
+
+    /// The glyph from the atlas, rasterizing it on first use.
+    unsafe fn glyph(&mut self, cache_key: CacheKey) -> Option<AtlasGlyph> {
+        if let Some(&glyph) = self.glyphs.get(&cache_key) {
+            return glyph;
+        }
+
+        let image = (self.swash_cache).get_image_uncached(&mut self.font_system, cache_key);
+        let glyph = image.and_then(|image| {
+            let size = uvec2(image.placement.width, image.placement.height);
+            if size.cmpeq(UVec2::ZERO).any() {
+                return None;
+            }
+
+            let (pixels, is_color) = match image.content {
+                SwashContent::Mask => {
+                    let pixels = (image.data.iter())
+                        .flat_map(|&coverage| [255, 255, 255, coverage])
+                        .collect();
+                    (pixels, false)
+                }
+                SwashContent::Color => (image.data, true),
+                // only asked for by subpixel rendering, which is off
+                SwashContent::SubpixelMask => return None,
+            };
+
+            let (page, min) = self.allocate(size)?;
+            self.pages[page].upload(min, size, &pixels);
+
+            Some(AtlasGlyph {
+                page,
+                is_color,
+                min,
+                size,
+                left: image.placement.left,
+                top: image.placement.top,
+            })
+        });
+
+        self.glyphs.insert(cache_key, glyph);
+        glyph
+    }
+
+    /// Room for a glyph of `size`, in a new page if the others are full.
+    unsafe fn allocate(&mut self, size: UVec2) -> Option<(usize, UVec2)> {
+        let free = (self.pages.iter_mut().enumerate())
+            .find_map(|(index, page)| Some((index, page.packer.allocate(size)?)));
+        if free.is_some() {
+            return free;
+        }
+
+        if self.pages.len() == MAX_PAGES {
+            // start over, the glyphs in use get rasterized again
+            self.clear_atlas();
+        } else {
+            self.pages.push(Page::new());
+        }
+
+        let index = self.pages.len() - 1;
+        Some((index, self.pages[index].packer.allocate(size)?))
+    }
+
+    /// Whether nothing was queued since the last draw.
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty() && self.pages.iter().all(|page| page.vertices.is_empty())
+    }
+
+    /// Draws and empties the batch over `framebuffer`, 0 being the default one.
+    pub unsafe fn draw(&mut self, framebuffer: GLuint, viewport: Vec2) {
+        if self.is_empty() {
+            return;
+        }
+
+        let saved = SavedState::save();
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+        gl::Viewport(0, 0, viewport.x as GLsizei, viewport.y as GLsizei);
+
+        // Normal blending, with alpha accumulated so that a transparent
+        // framebuffer ends up holding premultiplied colors
+        gl::Enable(gl::BLEND);
+        gl::BlendEquation(gl::FUNC_ADD);
+        gl::BlendFuncSeparate(
+            gl::SRC_ALPHA,
+            gl::ONE_MINUS_SRC_ALPHA,
+            gl::ONE,
+            gl::ONE_MINUS_SRC_ALPHA,
+        );
+
+        gl::UseProgram(self.shader);
+        gl::Uniform2f(self.u_viewport, viewport.x, viewport.y);
+        gl::Uniform2f(self.u_atlas_size, ATLAS_SIZE as f32, ATLAS_SIZE as f32);
+
+        // rectangles come from the first page, so they share its draw call
+        let mut vertices = mem::take(&mut self.rects);
+        let mut draws = Vec::with_capacity(self.pages.len());
+        for page in &mut self.pages {
+            let first = vertices.len();
+            vertices.append(&mut page.vertices);
+            draws.push((page.texture, first, vertices.len()));
+        }
+        draws[0].1 = 0;
+
+        buffer_data(gl::ARRAY_BUFFER, self.vbo, &vertices, gl::STREAM_DRAW);
+        gl::BindVertexArray(self.vao);
+        for (texture, first, end) in draws {
+            if first < end {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::DrawArrays(gl::TRIANGLES, first as GLint, (end - first) as GLsizei);
+            }
+        }
+
+        vertices.clear();
+        self.rects = vertices;
+        saved.restore();
+    }
+}
+
+fn push_quad(
+    out: &mut Vec<TextVertex>,
+    min: Vec2,
+    max: Vec2,
+    uv_min: Vec2,
+    uv_max: Vec2,
+    color: [f32; 4],
+    is_color: bool,
+) {
+    let vertex = |x: bool, y: bool| TextVertex {
+        position: vec2(if x { max.x } else { min.x }, if y { max.y } else { min.y }),
+        uv: vec2(
+            if x { uv_max.x } else { uv_min.x },
+            if y { uv_max.y } else { uv_min.y },
+        ),
+        color,
+        is_color: is_color as u8 as f32,
+    };
+
+    out.extend([
+        vertex(false, false),
+        vertex(true, false),
+        vertex(false, true),
+        vertex(false, true),
+        vertex(true, false),
+        vertex(true, true),
+    ]);
+}
+
+impl Drop for TextRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader);
+            gl::DeleteVertexArrays(1, &self.vao);
+            delete_buffers(&[self.vbo]);
+            let textures = self
+                .pages
+                .iter()
+                .map(|page| page.texture)
+                .collect::<Vec<_>>();
+            delete_textures(&textures);
+        }
+    }
+}
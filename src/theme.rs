@@ -0,0 +1,138 @@
+//! Day and night colors for what scenes draw around their content: clear
+//! colors, overlays and helper lines.
+//!
+//! The theme follows the OS unless it was picked by hand. It is global, like
+//! the GL context, so that scenes can read it while drawing without it being
+//! threaded through every call.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    /// Behind everything.
+    pub clear: [f32; 4],
+    /// Behind the image of the blur scenes.
+    pub backdrop: [f32; 4],
+    /// Text and lines of overlays drawn on top of a scene.
+    pub hud_text: [f32; 4],
+    /// Panels of overlays drawn on top of a scene.
+    pub hud_background: [f32; 4],
+    /// Panels of overlays with the scene blurred behind them, thinner so that
+    /// the blur shows.
+    pub hud_frosted: [f32; 4],
+    /// Guides, handles and grid lines.
+    pub grid: [f32; 4],
+    /// Highlights, like a selection or a query.
+    pub accent: [f32; 4],
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        name: "dark",
+        clear: [0.0, 0.0, 0.0, 0.5],
+        backdrop: [0.0, 0.2, 0.15, 0.5],
+        hud_text: [0.95, 0.95, 0.95, 1.0],
+        hud_background: [0.0, 0.0, 0.0, 0.6],
+        hud_frosted: [0.0, 0.0, 0.0, 0.25],
+        grid: [0.6, 0.6, 0.6, 0.8],
+        accent: [1.0, 1.0, 0.3, 1.0],
+    };
+
+    pub const LIGHT: Theme = Theme {
+        name: "light",
+        clear: [0.92, 0.92, 0.9, 0.5],
+        backdrop: [0.75, 0.88, 0.84, 0.5],
+        hud_text: [0.08, 0.08, 0.1, 1.0],
+        hud_background: [1.0, 1.0, 1.0, 0.7],
+        hud_frosted: [1.0, 1.0, 1.0, 0.35],
+        grid: [0.35, 0.35, 0.4, 0.8],
+        accent: [0.85, 0.35, 0.0, 1.0],
+    };
+
+    /// The accent color with another alpha.
+    pub fn accent(&self, alpha: f32) -> [f32; 4] {
+        let [r, g, b, _] = self.accent;
+        [r, g, b, alpha]
+    }
+}
+
+/// Where the theme comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    System,
+    Dark,
+    Light,
+}
+
+impl Mode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::Dark => "dark",
+            Self::Light => "light",
+        }
+    }
+
+    fn from_u8(mode: u8) -> Self {
+        match mode {
+            1 => Self::Dark,
+            2 => Self::Light,
+            _ => Self::System,
+        }
+    }
+}
+
+static MODE: AtomicU8 = AtomicU8::new(Mode::System as u8);
+static SYSTEM_IS_DARK: AtomicBool = AtomicBool::new(true);
+
+pub fn mode() -> Mode {
+    Mode::from_u8(MODE.load(Ordering::Relaxed))
+}
+
+pub fn set_mode(mode: Mode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// Goes from following the OS to dark, light, and back. Returns the new mode.
+pub fn cycle_mode() -> Mode {
+    let next = match mode() {
+        Mode::System => Mode::Dark,
+        Mode::Dark => Mode::Light,
+        Mode::Light => Mode::System,
+    };
+    set_mode(next);
+    next
+}
+
+/// Records the theme of the OS, used while following it.
+pub fn set_system_dark(is_dark: bool) {
+    SYSTEM_IS_DARK.store(is_dark, Ordering::Relaxed);
+}
+
+pub fn current() -> &'static Theme {
+    let is_dark = match mode() {
+        Mode::System => SYSTEM_IS_DARK.load(Ordering::Relaxed),
+        Mode::Dark => true,
+        Mode::Light => false,
+    };
+
+    match is_dark {
+        true => &Theme::DARK,
+        false => &Theme::LIGHT,
+    }
+}
+
+/// Clears the bound framebuffer with `color`.
+pub unsafe fn clear_with(color: [f32; 4]) {
+    let [r, g, b, a] = color;
+    gl::ClearColor(r, g, b, a);
+    gl::Clear(gl::COLOR_BUFFER_BIT);
+}
+
+/// Clears the bound framebuffer with the current theme's clear color.
+pub unsafe fn clear() {
+    clear_with(current().clear);
+}
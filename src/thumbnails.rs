@@ -0,0 +1,83 @@
+//! Labeled pictures of every scene, rendered offscreen with `--thumbnails`
+//! to catalog what each experiment looks like.
+//!
+//! Every scene starts from the same seed and the default camera, runs a
+//! second of fixed steps so that simulations have something to show, and is
+//! drawn once with its title over a strip along the bottom. Files are named
+//! after the scene, `<scene>.png`, so that they can be looked up by name.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::path::{Path, PathBuf};
+
+use glam::{vec2, UVec2, Vec2};
+use image::RgbaImage;
+
+use crate::camera::Camera;
+use crate::common_gl::read_pixels;
+use crate::profiling;
+use crate::scene_controller::FIXED_DT;
+use crate::scenes::Scenes;
+use crate::text::SharedText;
+use crate::theme;
+
+/// Size of a thumbnail, in pixels.
+pub const SIZE: UVec2 = UVec2::new(480, 270);
+/// Seed every scene is created with, so that thumbnails don't change between
+/// runs.
+pub const SEED: u64 = 0;
+/// Time simulated before the picture is taken, in seconds.
+pub const WARMUP: f32 = 1.0;
+
+/// Sizes in pixels.
+const LABEL_SIZE: f32 = 18.0;
+const PADDING: f32 = 6.0;
+
+/// Where the thumbnail of scene `name` goes in `dir`.
+pub fn path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.png"))
+}
+
+/// Runs `scenes` for [`WARMUP`] seconds and draws it to the bound framebuffer
+/// of size `size`, labeled with `title`. Returns the picture, opaque.
+pub unsafe fn render(
+    scenes: &mut Scenes,
+    title: &str,
+    text: &SharedText,
+    size: UVec2,
+) -> RgbaImage {
+    profiling::cpu_zone!("thumbnail");
+
+    let camera = Camera::default();
+    // keep the pointer out of the picture, as in tiled exports
+    let pointer = Vec2::splat(-1e9);
+
+    scenes.resize(&camera, size.x as i32, size.y as i32);
+    for _ in 0..(WARMUP / FIXED_DT) as u32 {
+        scenes.update(FIXED_DT);
+    }
+    scenes.draw(&camera, pointer);
+
+    let mut text = text.borrow_mut();
+    let theme = theme::current();
+    let size_f = size.as_vec2();
+    let label_height = text.measure(title, LABEL_SIZE, None).y + 2.0 * PADDING;
+    let strip_min = vec2(0.0, size_f.y - label_height);
+    text.queue_rect(strip_min, size_f, theme.hud_background);
+    text.queue(
+        title,
+        strip_min + PADDING,
+        LABEL_SIZE,
+        Some(size_f.x - 2.0 * PADDING),
+        theme.hud_text,
+    );
+    text.draw(0, size_f);
+    gl::Finish();
+
+    let mut pixels = read_pixels(size.x, size.y);
+    // the window lets the desktop through half transparent clear colors
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+    RgbaImage::from_raw(size.x, size.y, pixels).unwrap()
+}
@@ -0,0 +1,319 @@
+//! Easing curves and value animations.
+//!
+//! A [`Tweener`] holds animations by key, advanced by the app's fixed-timestep
+//! update so that they play at the same speed whatever the frame rate. Values
+//! are read back whenever they're needed, and finished animations keep their
+//! final value until they're removed.
+
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+use std::hash::Hash;
+
+use glam::{Vec2, Vec3, Vec4};
+
+use crate::camera::Camera;
+
+/// Maps linear progress in `[0, 1]` to eased progress, which starts at 0 and
+/// ends at 1 but may overshoot in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    Smoothstep,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    /// Pulls back a little before going.
+    BackIn,
+    /// Overshoots a little before settling.
+    BackOut,
+    BackInOut,
+    ElasticOut,
+    BounceOut,
+}
+
+impl Easing {
+    pub const ALL: &'static [Easing] = &[
+        Self::Linear,
+        Self::Smoothstep,
+        Self::QuadIn,
+        Self::QuadOut,
+        Self::QuadInOut,
+        Self::CubicIn,
+        Self::CubicOut,
+        Self::CubicInOut,
+        Self::SineIn,
+        Self::SineOut,
+        Self::SineInOut,
+        Self::ExpoIn,
+        Self::ExpoOut,
+        Self::ExpoInOut,
+        Self::BackIn,
+        Self::BackOut,
+        Self::BackInOut,
+        Self::ElasticOut,
+        Self::BounceOut,
+    ];
+
+    pub fn ease(self, t: f32) -> f32 {
+        // how far back the back easings pull
+        const BACK: f32 = 1.70158;
+        const BACK_IN_OUT: f32 = BACK * 1.525;
+
+        let t = t.clamp(0.0, 1.0);
+        // mirrors an ease-in into an ease-in-out
+        let in_out = |ease_in: fn(f32) -> f32| {
+            if t < 0.5 {
+                ease_in(t * 2.0) / 2.0
+            } else {
+                1.0 - ease_in((1.0 - t) * 2.0) / 2.0
+            }
+        };
+
+        match self {
+            Self::Linear => t,
+            Self::Smoothstep => t * t * (3.0 - 2.0 * t),
+            Self::QuadIn => t * t,
+            Self::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Self::QuadInOut => in_out(|t| t * t),
+            Self::CubicIn => t.powi(3),
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::CubicInOut => in_out(|t| t.powi(3)),
+            Self::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Self::SineOut => (t * PI / 2.0).sin(),
+            Self::SineInOut => (1.0 - (t * PI).cos()) / 2.0,
+            Self::ExpoIn => expo_in(t),
+            Self::ExpoOut => 1.0 - expo_in(1.0 - t),
+            Self::ExpoInOut => in_out(expo_in),
+            Self::BackIn => t * t * ((BACK + 1.0) * t - BACK),
+            Self::BackOut => {
+                let t = 1.0 - t;
+                1.0 - t * t * ((BACK + 1.0) * t - BACK)
+            }
+            Self::BackInOut => in_out(|t| t * t * ((BACK_IN_OUT + 1.0) * t - BACK_IN_OUT)),
+            Self::ElasticOut => match t {
+                0.0 | 1.0 => t,
+                _ => 2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * TAU / 3.0).sin() + 1.0,
+            },
+            Self::BounceOut => bounce_out(t),
+        }
+    }
+}
+
+fn expo_in(t: f32) -> f32 {
+    match t {
+        0.0 => 0.0,
+        _ => 2f32.powf(10.0 * t - 10.0),
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Values that can be interpolated, `t` being eased progress that may go
+/// slightly outside of `[0, 1]`.
+pub trait Lerp: Clone {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Vec2::lerp(*self, *to, t)
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Vec3::lerp(*self, *to, t)
+    }
+}
+
+impl Lerp for Vec4 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Vec4::lerp(*self, *to, t)
+    }
+}
+
+impl Lerp for Camera {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        // scale is interpolated geometrically so that zooming feels linear
+        let ln = |scale: Vec2| Vec2::new(scale.x.ln(), scale.y.ln());
+        let scale = Lerp::lerp(&ln(self.scale), &ln(to.scale), t);
+        let scale = Vec2::new(scale.x.exp(), scale.y.exp());
+
+        Camera {
+            position: Lerp::lerp(&self.position, &to.position, t),
+            rotation: Lerp::lerp(&self.rotation, &to.rotation, t),
+            scale,
+        }
+    }
+}
+
+/// An animation from one value to another.
+#[derive(Debug, Clone)]
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    pub easing: Easing,
+    /// In seconds.
+    pub duration: f32,
+    elapsed: f32,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            easing,
+            duration,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    /// Linear progress in `[0, 1]`.
+    pub fn progress(&self) -> f32 {
+        match self.duration {
+            d if d <= 0.0 => 1.0,
+            d => self.elapsed / d,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn value(&self) -> T {
+        match self.is_finished() {
+            true => self.to.clone(),
+            false => self.from.lerp(&self.to, self.easing.ease(self.progress())),
+        }
+    }
+}
+
+/// Animations by key, e.g. a name or the index of whatever they animate.
+#[derive(Debug, Clone)]
+pub struct Tweener<K, T> {
+    tweens: HashMap<K, Tween<T>>,
+}
+
+impl<K, T> Default for Tweener<K, T> {
+    fn default() -> Self {
+        Self {
+            tweens: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash, T: Lerp> Tweener<K, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts an animation, replacing the one `key` had.
+    pub fn start(&mut self, key: K, tween: Tween<T>) {
+        self.tweens.insert(key, tween);
+    }
+
+    /// Animates `key` to `to`, starting from where its current animation is,
+    /// or from `current` if it has none, so that animations can be
+    /// interrupted without jumping.
+    pub fn animate_to(&mut self, key: K, current: T, to: T, duration: f32, easing: Easing) {
+        let from = self.value(&key).unwrap_or(current);
+        self.start(key, Tween::new(from, to, duration, easing));
+    }
+
+    pub fn value(&self, key: &K) -> Option<T> {
+        self.tweens.get(key).map(Tween::value)
+    }
+
+    /// Where the animation of `key` ends.
+    pub fn target(&self, key: &K) -> Option<T> {
+        self.tweens.get(key).map(|tween| tween.to.clone())
+    }
+
+    pub fn is_animating(&self, key: &K) -> bool {
+        self.tweens
+            .get(key)
+            .is_some_and(|tween| !tween.is_finished())
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<Tween<T>> {
+        self.tweens.remove(key)
+    }
+
+    /// Removes the finished animations, returning their final values.
+    pub fn remove_finished(&mut self) -> Vec<(K, T)> {
+        let finished = (self.tweens.iter())
+            .filter(|(_, tween)| tween.is_finished())
+            .count();
+        if finished == 0 {
+            return Vec::new();
+        }
+
+        let (finished, running) = (self.tweens.drain()).partition(|(_, tween)| tween.is_finished());
+        self.tweens = running;
+        (finished.into_iter())
+            .map(|(key, tween): (K, Tween<T>)| (key, tween.to))
+            .collect()
+    }
+
+    /// Advances every animation by a fixed step.
+    pub fn update(&mut self, dt: f32) {
+        for tween in self.tweens.values_mut() {
+            tween.advance(dt);
+        }
+    }
+
+    /// Every animated key, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.tweens.keys()
+    }
+
+    /// Every animated key with its current value, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, T)> + '_ {
+        (self.tweens.iter()).map(|(key, tween)| (key, tween.value()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.tweens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tweens.is_empty()
+    }
+}
@@ -0,0 +1,67 @@
+//! Size of the window, kept in logical pixels with the scale factor.
+//!
+//! Under fractional scaling, as 1.25x on Wayland, physical sizes don't
+//! divide evenly by the scale factor, and converting back and forth along
+//! different paths used to round differently. Everything derives its sizes
+//! from a single [`Viewport`] instead, rounding to physical pixels once.
+
+use glam::{UVec2, Vec2};
+use winit::dpi::PhysicalSize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Size in logical pixels.
+    pub logical: Vec2,
+    /// Physical pixels per logical pixel.
+    pub scale_factor: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            logical: Vec2::ONE,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    /// Viewport of a surface `size` physical pixels big.
+    pub fn from_physical(size: PhysicalSize<u32>, scale_factor: f64) -> Self {
+        let scale_factor = scale_factor as f32;
+        Self {
+            logical: Vec2::new(size.width as f32, size.height as f32) / scale_factor,
+            scale_factor,
+        }
+    }
+
+    /// The same logical size with another scale factor, as when the window
+    /// moves to another monitor.
+    pub fn with_scale_factor(self, scale_factor: f64) -> Self {
+        Self {
+            scale_factor: scale_factor as f32,
+            ..self
+        }
+    }
+
+    /// Size in physical pixels, which is what framebuffers are made of.
+    pub fn physical(&self) -> UVec2 {
+        (self.logical * self.scale_factor).round().as_uvec2()
+    }
+
+    /// [`Self::physical`] as floats, for cameras and text.
+    pub fn size(&self) -> Vec2 {
+        self.physical().as_vec2()
+    }
+
+    pub fn physical_size(&self) -> PhysicalSize<u32> {
+        let size = self.physical();
+        PhysicalSize::new(size.x, size.y)
+    }
+
+    /// Length of `logical` logical pixels in physical pixels, rounded so that
+    /// what is laid out with it stays on the pixel grid.
+    pub fn px(&self, logical: f32) -> f32 {
+        (logical * self.scale_factor).round()
+    }
+}
@@ -0,0 +1,253 @@
+//! Watchdog for frames that take too long, like a kawase chain hundreds of
+//! passes deep or a kernel scene blowing up on a huge image.
+//!
+//! The frame loop tells it when frames begin and end, and debug groups (see
+//! [`push_debug_group`](crate::common_gl::push_debug_group)) tell it which
+//! pass is running. A thread watches the frame from the side, so that a frame
+//! stuck in the driver still gets reported while it's stuck.
+//!
+//! GL commands only run when the driver gets to them, so a slow pass usually
+//! shows up as a slow `swap`. In diagnostic mode every pass is bracketed by
+//! `glFinish`, which makes each of them wait for the GPU: frames get slower,
+//! but the report of a long frame then lists the passes that took the time.
+
+use std::ffi::CStr;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::params::Params;
+
+pub const DEFAULT_THRESHOLD_MS: u64 = 500;
+
+/// Passes listed in the report of a long frame, the slowest first.
+const SLOWEST_PASSES: usize = 5;
+
+// Set while a watchdog is running, so that debug groups don't lock anything
+// otherwise.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static DIAGNOSE: AtomicBool = AtomicBool::new(false);
+static FRAME: Mutex<Frame> = Mutex::new(Frame::new());
+
+/// The frame being watched.
+struct Frame {
+    number: u64,
+    /// None between frames.
+    started: Option<Instant>,
+    scene: &'static str,
+    params: String,
+    /// Nested passes running right now, with when they began.
+    passes: Vec<(&'static CStr, Instant)>,
+    /// How long each pass took, in diagnostic mode.
+    timings: Vec<(String, Duration)>,
+    /// Whether the watchdog thread already reported this frame as stuck.
+    reported: bool,
+}
+
+impl Frame {
+    const fn new() -> Self {
+        Self {
+            number: 0,
+            started: None,
+            scene: "",
+            params: String::new(),
+            passes: Vec::new(),
+            timings: Vec::new(),
+            reported: false,
+        }
+    }
+
+    fn pass_path(&self) -> String {
+        let names = self.passes.iter().map(|(name, _)| name.to_string_lossy());
+        names.collect::<Vec<_>>().join(" / ")
+    }
+}
+
+/// A frame that took longer than the threshold.
+#[derive(Debug, Clone)]
+pub struct LongFrame {
+    pub number: u64,
+    pub duration: Duration,
+    pub scene: &'static str,
+    pub params: String,
+    /// The slowest passes of the frame with their duration, only measured in
+    /// diagnostic mode.
+    pub slowest: Vec<(String, Duration)>,
+}
+
+impl fmt::Display for LongFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "frame {} took {:.0} ms in {}",
+            self.number,
+            self.duration.as_secs_f32() * 1000.0,
+            self.scene
+        )?;
+        if !self.params.is_empty() {
+            write!(f, " ({})", self.params)?;
+        }
+        for (pass, duration) in &self.slowest {
+            write!(f, "\n  {:8.1} ms  {pass}", duration.as_secs_f32() * 1000.0)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Watchdog {
+    threshold: Duration,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Starts watching frames, flagging those longer than `threshold`. With
+    /// `diagnose`, every pass waits for the GPU so that it can be timed.
+    pub fn start(threshold: Duration, diagnose: bool) -> Self {
+        *lock() = Frame::new();
+        DIAGNOSE.store(diagnose, Ordering::Relaxed);
+        ACTIVE.store(true, Ordering::Relaxed);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::Builder::new()
+            .name("watchdog".to_owned())
+            .spawn({
+                let stop = stop.clone();
+                move || watch(threshold, &stop)
+            })
+            .inspect_err(|e| eprintln!("Could not start the watchdog thread: {e}"))
+            .ok();
+
+        Self {
+            threshold,
+            stop,
+            thread,
+        }
+    }
+
+    /// Starts timing a frame of `scene`, whose parameters are logged if it
+    /// takes too long.
+    pub fn begin_frame(&self, scene: &'static str, params: Option<&Params>) {
+        let mut frame = lock();
+        frame.number += 1;
+        frame.started = Some(Instant::now());
+        frame.scene = scene;
+        frame.params.clear();
+        if let Some(params) = params {
+            use fmt::Write;
+            let _ = write!(frame.params, "{params}");
+        }
+        frame.passes.clear();
+        frame.timings.clear();
+        frame.reported = false;
+    }
+
+    /// Stops timing the frame, returning it if it took too long.
+    pub fn end_frame(&self) -> Option<LongFrame> {
+        let mut frame = lock();
+        let duration = frame.started.take()?.elapsed();
+        if duration < self.threshold {
+            return None;
+        }
+
+        let mut slowest = std::mem::take(&mut frame.timings);
+        slowest.sort_by_key(|&(_, duration)| std::cmp::Reverse(duration));
+        slowest.truncate(SLOWEST_PASSES);
+        Some(LongFrame {
+            number: frame.number,
+            duration,
+            scene: frame.scene,
+            params: frame.params.clone(),
+            slowest,
+        })
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        ACTIVE.store(false, Ordering::Relaxed);
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Marks the start of a pass. Called by debug groups, so it needs a GL
+/// context in diagnostic mode.
+pub fn enter_pass(name: &'static CStr) {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return;
+    }
+    finish_if_diagnosing();
+
+    let mut frame = lock();
+    if frame.started.is_some() {
+        frame.passes.push((name, Instant::now()));
+    }
+}
+
+/// Marks the end of the innermost pass.
+pub fn leave_pass() {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return;
+    }
+    finish_if_diagnosing();
+
+    let mut frame = lock();
+    let path = frame.pass_path();
+    if let Some((_, started)) = frame.passes.pop() {
+        if DIAGNOSE.load(Ordering::Relaxed) {
+            frame.timings.push((path, started.elapsed()));
+        }
+    }
+}
+
+fn finish_if_diagnosing() {
+    if DIAGNOSE.load(Ordering::Relaxed) {
+        unsafe { gl::Finish() };
+    }
+}
+
+/// Checks on the frame a few times per threshold, reporting it once if it's
+/// stuck for longer.
+fn watch(threshold: Duration, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        thread::park_timeout(threshold / 4);
+
+        let mut frame = lock();
+        let Some(started) = frame.started else {
+            continue;
+        };
+        let elapsed = started.elapsed();
+        if elapsed < threshold || frame.reported {
+            continue;
+        }
+        frame.reported = true;
+
+        let pass = if frame.passes.is_empty() {
+            "no pass".to_owned()
+        } else {
+            frame.pass_path()
+        };
+        let mut scene = frame.scene.to_owned();
+        if !frame.params.is_empty() {
+            scene = format!("{scene} ({})", frame.params);
+        }
+        eprintln!(
+            "Watchdog: frame {} stuck for {:.0} ms in {scene}, in {pass}",
+            frame.number,
+            elapsed.as_secs_f32() * 1000.0,
+        );
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Frame> {
+    // a panic while holding it can't leave a frame worse than stale
+    FRAME
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
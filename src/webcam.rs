@@ -0,0 +1,418 @@
+//! Webcam capture through Video4Linux2: a thread dequeues the frames of the
+//! device, converts them to RGBA and hands the latest one to the main thread,
+//! which streams it into the scenes reading the input image.
+//!
+//! Devices are asked for YUYV, which every UVC webcam supports, and MJPEG is
+//! decoded too for drivers that insist on it.
+
+use std::error::Error;
+use std::ffi::{c_void, CStr};
+use std::fs::{self, File, OpenOptions};
+use std::os::fd::{AsRawFd, RawFd};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::{io, mem, ptr, slice, thread};
+
+use glam::{uvec2, UVec2};
+use image::{ImageFormat, RgbaImage};
+
+/// Size asked of devices, which pick the closest one they support.
+const REQUESTED_SIZE: UVec2 = UVec2::new(640, 480);
+/// Buffers the driver fills in turn.
+const N_BUFFERS: u32 = 4;
+
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+const YUYV: u32 = fourcc(b"YUYV");
+const MJPG: u32 = fourcc(b"MJPG");
+
+const CAP_VIDEO_CAPTURE: u32 = 0x0000_0001;
+const CAP_STREAMING: u32 = 0x0400_0000;
+const CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+const BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+const FIELD_NONE: u32 = 1;
+const MEMORY_MMAP: u32 = 1;
+
+// --- the parts of videodev2.h used here ---
+
+#[repr(C)]
+struct Capability {
+    driver: [u8; 16],
+    card: [u8; 32],
+    bus_info: [u8; 32],
+    version: u32,
+    capabilities: u32,
+    device_caps: u32,
+    reserved: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PixFormat {
+    width: u32,
+    height: u32,
+    pixelformat: u32,
+    field: u32,
+    bytesperline: u32,
+    sizeimage: u32,
+    colorspace: u32,
+    private: u32,
+    flags: u32,
+    ycbcr_enc: u32,
+    quantization: u32,
+    xfer_func: u32,
+}
+
+#[repr(C)]
+union FormatUnion {
+    pix: PixFormat,
+    raw_data: [u8; 200],
+    // some of the other formats hold pointers
+    _align: [usize; 0],
+}
+
+#[repr(C)]
+struct Format {
+    kind: u32,
+    fmt: FormatUnion,
+}
+
+#[repr(C)]
+struct RequestBuffers {
+    count: u32,
+    kind: u32,
+    memory: u32,
+    capabilities: u32,
+    flags: u8,
+    reserved: [u8; 3],
+}
+
+#[repr(C)]
+struct Timecode {
+    kind: u32,
+    flags: u32,
+    frames: u8,
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    userbits: [u8; 4],
+}
+
+#[repr(C)]
+union BufferMemory {
+    offset: u32,
+    userptr: libc::c_ulong,
+    planes: *mut c_void,
+    fd: i32,
+}
+
+#[repr(C)]
+struct Buffer {
+    index: u32,
+    kind: u32,
+    bytesused: u32,
+    flags: u32,
+    field: u32,
+    timestamp: libc::timeval,
+    timecode: Timecode,
+    sequence: u32,
+    memory: u32,
+    m: BufferMemory,
+    length: u32,
+    reserved2: u32,
+    request_fd: i32,
+}
+
+impl Buffer {
+    fn new(index: u32) -> Self {
+        // SAFETY: plain integers, a union of them and a pointer
+        let mut buffer: Self = unsafe { mem::zeroed() };
+        buffer.index = index;
+        buffer.kind = BUF_TYPE_VIDEO_CAPTURE;
+        buffer.memory = MEMORY_MMAP;
+        buffer
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+const _: () = {
+    assert!(mem::size_of::<Capability>() == 104);
+    assert!(mem::size_of::<Format>() == 208);
+    assert!(mem::size_of::<RequestBuffers>() == 20);
+    assert!(mem::size_of::<Buffer>() == 88);
+};
+
+const fn ioc<T>(dir: u64, nr: u64) -> u64 {
+    dir << 30 | (mem::size_of::<T>() as u64) << 16 | (b'V' as u64) << 8 | nr
+}
+
+const WRITE: u64 = 1;
+const READ: u64 = 2;
+const VIDIOC_QUERYCAP: u64 = ioc::<Capability>(READ, 0);
+const VIDIOC_S_FMT: u64 = ioc::<Format>(READ | WRITE, 5);
+const VIDIOC_REQBUFS: u64 = ioc::<RequestBuffers>(READ | WRITE, 8);
+const VIDIOC_QUERYBUF: u64 = ioc::<Buffer>(READ | WRITE, 9);
+const VIDIOC_QBUF: u64 = ioc::<Buffer>(READ | WRITE, 15);
+const VIDIOC_DQBUF: u64 = ioc::<Buffer>(READ | WRITE, 17);
+const VIDIOC_STREAMON: u64 = ioc::<i32>(WRITE, 18);
+const VIDIOC_STREAMOFF: u64 = ioc::<i32>(WRITE, 19);
+
+/// `ioctl`, retried when interrupted by a signal.
+unsafe fn ioctl<T>(fd: RawFd, request: u64, arg: &mut T) -> io::Result<()> {
+    loop {
+        if libc::ioctl(fd, request as _, arg as *mut T) != -1 {
+            return Ok(());
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+/// `/dev/video<n>` for an index, or the path as it is.
+fn device_path(device: &str) -> PathBuf {
+    match device.parse::<u32>() {
+        Ok(index) => PathBuf::from(format!("/dev/video{index}")),
+        Err(_) => PathBuf::from(device),
+    }
+}
+
+/// Name of the device open as `fd`, if it can capture video by streaming.
+fn capture_name(fd: RawFd) -> io::Result<Option<String>> {
+    // SAFETY: plain integers and bytes
+    let mut capability: Capability = unsafe { mem::zeroed() };
+    unsafe { ioctl(fd, VIDIOC_QUERYCAP, &mut capability)? };
+
+    let caps = match capability.capabilities & CAP_DEVICE_CAPS {
+        0 => capability.capabilities,
+        _ => capability.device_caps,
+    };
+    if caps & CAP_VIDEO_CAPTURE == 0 || caps & CAP_STREAMING == 0 {
+        return Ok(None);
+    }
+    let name = CStr::from_bytes_until_nul(&capability.card)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(Some(name))
+}
+
+/// Devices that can capture video, with their names.
+pub fn devices() -> Vec<(PathBuf, String)> {
+    let Ok(entries) = fs::read_dir("/dev") else {
+        return Vec::new();
+    };
+    let mut devices = (entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            (path.file_name()).is_some_and(|name| name.to_string_lossy().starts_with("video"))
+        })
+        .filter_map(|path| {
+            let file = OpenOptions::new().read(true).write(true).open(&path).ok()?;
+            let name = capture_name(file.as_raw_fd()).ok()??;
+            Some((path, name))
+        })
+        .collect::<Vec<_>>();
+    devices.sort();
+    devices
+}
+
+/// A buffer of the driver mapped into memory.
+struct Mapping {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+// SAFETY: only the capture thread reads it once the stream has started
+unsafe impl Send for Mapping {}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr, self.len) };
+    }
+}
+
+/// An open device streaming into its buffers, read by the capture thread.
+struct Stream {
+    file: File,
+    buffers: Vec<Mapping>,
+    size: UVec2,
+    bytes_per_line: usize,
+    pixel_format: u32,
+}
+
+impl Stream {
+    fn open(file: File) -> Result<Self, Box<dyn Error>> {
+        let fd = file.as_raw_fd();
+        unsafe {
+            // SAFETY: plain integers and bytes
+            let mut format: Format = mem::zeroed();
+            format.kind = BUF_TYPE_VIDEO_CAPTURE;
+            format.fmt.pix.width = REQUESTED_SIZE.x;
+            format.fmt.pix.height = REQUESTED_SIZE.y;
+            format.fmt.pix.pixelformat = YUYV;
+            format.fmt.pix.field = FIELD_NONE;
+            ioctl(fd, VIDIOC_S_FMT, &mut format)?;
+            let pix = format.fmt.pix;
+            if pix.pixelformat != YUYV && pix.pixelformat != MJPG {
+                let code = String::from_utf8_lossy(&pix.pixelformat.to_le_bytes()).into_owned();
+                return Err(format!("unsupported pixel format {code}").into());
+            }
+
+            let mut request = RequestBuffers {
+                count: N_BUFFERS,
+                kind: BUF_TYPE_VIDEO_CAPTURE,
+                memory: MEMORY_MMAP,
+                capabilities: 0,
+                flags: 0,
+                reserved: [0; 3],
+            };
+            ioctl(fd, VIDIOC_REQBUFS, &mut request)?;
+
+            let mut buffers = Vec::with_capacity(request.count as usize);
+            for index in 0..request.count {
+                let mut buffer = Buffer::new(index);
+                ioctl(fd, VIDIOC_QUERYBUF, &mut buffer)?;
+                let len = buffer.length as usize;
+                let ptr = libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    fd,
+                    buffer.m.offset as libc::off_t,
+                );
+                if ptr == libc::MAP_FAILED {
+                    return Err(io::Error::last_os_error().into());
+                }
+                buffers.push(Mapping { ptr, len });
+                ioctl(fd, VIDIOC_QBUF, &mut buffer)?;
+            }
+
+            let mut kind = BUF_TYPE_VIDEO_CAPTURE as i32;
+            ioctl(fd, VIDIOC_STREAMON, &mut kind)?;
+
+            Ok(Self {
+                file,
+                buffers,
+                size: uvec2(pix.width, pix.height),
+                bytes_per_line: pix.bytesperline as usize,
+                pixel_format: pix.pixelformat,
+            })
+        }
+    }
+
+    /// Sends frames until the webcam is dropped or the device fails.
+    fn run(self, frames: SyncSender<RgbaImage>) {
+        let fd = self.file.as_raw_fd();
+        loop {
+            let mut buffer = Buffer::new(0);
+            if let Err(e) = unsafe { ioctl(fd, VIDIOC_DQBUF, &mut buffer) } {
+                eprintln!("Webcam: capture failed: {e}");
+                return;
+            }
+
+            let mapping = &self.buffers[buffer.index as usize];
+            let used = (buffer.bytesused as usize).min(mapping.len);
+            // SAFETY: the driver is done with the buffer until it's queued again
+            let data = unsafe { slice::from_raw_parts(mapping.ptr as *const u8, used) };
+            let frame = self.decode(data);
+
+            if let Err(e) = unsafe { ioctl(fd, VIDIOC_QBUF, &mut buffer) } {
+                eprintln!("Webcam: capture failed: {e}");
+                return;
+            }
+
+            match frame {
+                // the main thread is behind, so this frame is skipped
+                Ok(frame) => match frames.try_send(frame) {
+                    Err(TrySendError::Disconnected(_)) => return,
+                    Ok(()) | Err(TrySendError::Full(_)) => {}
+                },
+                Err(e) => eprintln!("Webcam: dropped a frame: {e}"),
+            }
+        }
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<RgbaImage, Box<dyn Error>> {
+        if self.pixel_format == MJPG {
+            let image = image::load_from_memory_with_format(data, ImageFormat::Jpeg)?;
+            return Ok(image.into_rgba8());
+        }
+
+        let UVec2 {
+            x: width,
+            y: height,
+        } = self.size;
+        if data.len() < self.bytes_per_line * height as usize {
+            return Err(format!("frame of {} bytes is incomplete", data.len()).into());
+        }
+        let mut image = RgbaImage::new(width, height);
+        for (y, row) in image.rows_mut().enumerate() {
+            let line = &data[y * self.bytes_per_line..];
+            // Y0 U Y1 V for every two pixels
+            for (x, pixel) in row.enumerate() {
+                let pair = &line[x / 2 * 4..x / 2 * 4 + 4];
+                let luma = pair[if x % 2 == 0 { 0 } else { 2 }];
+                pixel.0 = yuv_to_rgba(luma, pair[1], pair[3]);
+            }
+        }
+        Ok(image)
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        let mut kind = BUF_TYPE_VIDEO_CAPTURE as i32;
+        let _ = unsafe { ioctl(self.file.as_raw_fd(), VIDIOC_STREAMOFF, &mut kind) };
+    }
+}
+
+/// BT.601 with studio swing, which webcams use.
+fn yuv_to_rgba(y: u8, u: u8, v: u8) -> [u8; 4] {
+    let y = 1.164 * (y as f32 - 16.0);
+    let (u, v) = (u as f32 - 128.0, v as f32 - 128.0);
+    let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+    [
+        channel(y + 1.596 * v),
+        channel(y - 0.392 * u - 0.813 * v),
+        channel(y + 2.017 * u),
+        255,
+    ]
+}
+
+pub struct Webcam {
+    pub name: String,
+    pub size: UVec2,
+    frames: Receiver<RgbaImage>,
+}
+
+impl Webcam {
+    /// Starts capturing from `device`, either an index like `0` or a path
+    /// like `/dev/video2`.
+    pub fn open(device: &str) -> Result<Self, Box<dyn Error>> {
+        let path = device_path(device);
+        let file = (OpenOptions::new().read(true).write(true).open(&path))
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        let name = (capture_name(file.as_raw_fd()))
+            .map_err(|e| format!("{}: {e}", path.display()))?
+            .ok_or_else(|| format!("{} can't capture video", path.display()))?;
+
+        let stream = Stream::open(file)?;
+        let size = stream.size;
+        let (sender, frames) = mpsc::sync_channel(1);
+        thread::Builder::new()
+            .name("webcam".to_string())
+            .spawn(move || stream.run(sender))?;
+
+        println!("Webcam: {name} at {}x{}", size.x, size.y);
+        Ok(Self { name, size, frames })
+    }
+
+    /// The last frame captured since the previous call, if any.
+    pub fn latest(&self) -> Option<RgbaImage> {
+        self.frames.try_iter().last()
+    }
+}
@@ -0,0 +1,110 @@
+use opengl_playground::ab::{
+    paired_t_test, student_t_critical, student_t_p, AbHarness, Comparison, Mode, Variant,
+    WARMUP_FRAMES,
+};
+use opengl_playground::params::Value;
+
+#[test]
+fn comparisons_are_registered_or_spelled_out() {
+    let cloth: Comparison = "cloth".parse().unwrap();
+    assert_eq!(cloth.param, "solver");
+    assert_eq!(cloth.a, Value::Text("cpu".to_string()));
+    assert_eq!(cloth.b, Value::Text("compute".to_string()));
+
+    let custom: Comparison = "kawase.layers=1,5".parse().unwrap();
+    assert_eq!(custom.scene, "kawase");
+    assert_eq!(custom.param, "layers");
+    assert_eq!(custom.a, Value::Number(1.0));
+    assert_eq!(custom.b, Value::Number(5.0));
+    assert_eq!(custom.to_string(), "kawase.layers: 1 vs 5");
+
+    let kawase: Comparison = "kawase".parse().unwrap();
+    assert_eq!(kawase.param, "downsampling");
+    assert!("blurring".parse::<Comparison>().is_err());
+    assert!("kawase.layers=1".parse::<Comparison>().is_err());
+    assert!("layers=1,5".parse::<Comparison>().is_err());
+}
+
+#[test]
+fn student_t_matches_the_tables() {
+    // two-sided 5% critical values
+    for (df, t) in [(1.0, 12.706), (5.0, 2.571), (10.0, 2.228), (30.0, 2.042)] {
+        assert!((student_t_p(t, df) - 0.05).abs() < 1e-3, "df {df}");
+        assert!((student_t_critical(0.05, df) - t).abs() < 1e-2, "df {df}");
+    }
+    assert!((student_t_critical(0.05, 10000.0) - 1.960).abs() < 1e-2);
+    assert!((student_t_p(0.0, 10.0) - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn paired_differences_are_told_from_noise() {
+    let a = [10.0, 12.0, 11.0, 13.0, 10.5, 12.5];
+    let faster: Vec<f64> = a.iter().map(|a| a - 1.0).collect();
+    let test = paired_t_test(&a, &faster).unwrap();
+    assert_eq!(test.difference, -1.0);
+    assert_eq!(test.margin, 0.0);
+    assert!(test.is_significant());
+
+    let noisy = [10.2, 11.7, 11.1, 13.2, 10.3, 12.4];
+    let test = paired_t_test(&a, &noisy).unwrap();
+    assert!(!test.is_significant());
+    assert!(test.margin > test.difference.abs());
+
+    assert_eq!(paired_t_test(&a[..1], &noisy[..1]), None);
+}
+
+#[test]
+fn alternating_blocks_pair_up() {
+    let frames = 10;
+    let mut harness = AbHarness::new("cloth".parse().unwrap(), Mode::Alternate { frames });
+
+    let mut order = Vec::new();
+    for block in 0..40 {
+        let variant = harness.variant();
+        order.push(variant);
+        for frame in 0..frames {
+            // the first frames after switching are slow, and left out
+            let noise = ((block * frames + frame) % 7) as f64 * 0.01;
+            let ms = match (variant, frame < WARMUP_FRAMES) {
+                (_, true) => 100.0,
+                (Variant::A, false) => 2.0 + noise,
+                (Variant::B, false) => 1.0 + noise,
+            };
+            harness.record_cpu(variant, ms);
+            harness.record_gpu(&[("draw", 50.0), (variant.zone(), ms as f32 / 2.0)]);
+            harness.end_frame();
+        }
+    }
+
+    use Variant::{A, B};
+    assert_eq!(order[..8], [A, B, B, A, A, B, B, A]);
+
+    let report = harness.take_report().unwrap();
+    let cpu = report.cpu.unwrap();
+    assert_eq!(cpu.pairs, 20);
+    assert!((cpu.mean_a - 2.03).abs() < 0.05);
+    assert!((cpu.difference + 1.0).abs() < 0.05);
+    assert!(cpu.is_significant());
+    let gpu = report.gpu.unwrap();
+    assert!((gpu.difference + 0.5).abs() < 0.05);
+
+    // no new report until enough new pairs came in
+    assert!(harness.take_report().is_none());
+    assert!(harness.last_report().is_some());
+}
+
+#[test]
+fn split_frames_swap_which_half_comes_first() {
+    let mut harness = AbHarness::new("bindless".parse().unwrap(), Mode::Split);
+    let first = harness.draw_order();
+    let second = harness.draw_order();
+    assert_eq!(first, [second[1], second[0]]);
+
+    for _ in 0..3 {
+        harness.record_cpu(Variant::A, 1.0);
+        harness.record_cpu(Variant::B, 3.0);
+    }
+    let cpu = harness.report().cpu.unwrap();
+    assert_eq!(cpu.pairs, 3);
+    assert_eq!(cpu.difference, 2.0);
+}
@@ -0,0 +1,66 @@
+use gl::types::GLuint;
+use glam::{uvec2, UVec2};
+use opengl_playground::adjust::{self, Adjuster};
+use opengl_playground::common_gl::{supports_compute, upload_texture};
+use opengl_playground::params::{Params, Value};
+
+mod common;
+
+const SIZE: UVec2 = UVec2::new(64, 64);
+
+/// A gradient from black to dark gray, like an underexposed photo.
+unsafe fn dark_image() -> GLuint {
+    let pixels = (0..SIZE.y)
+        .flat_map(|_| (0..SIZE.x).flat_map(|x| [(x / 2) as u8; 3].into_iter().chain([255])))
+        .collect::<Vec<_>>();
+    let mut texture = 0;
+    gl::GenTextures(1, &mut texture);
+    upload_texture(texture, SIZE.x, SIZE.y, pixels.as_ptr(), gl::CLAMP_TO_EDGE).unwrap();
+    texture
+}
+
+unsafe fn mean_luma(texture: GLuint) -> f32 {
+    let mut pixels = vec![0u8; (SIZE.x * SIZE.y * 4) as usize];
+    gl::BindTexture(gl::TEXTURE_2D, texture);
+    gl::GetTexImage(
+        gl::TEXTURE_2D,
+        0,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixels.as_mut_ptr() as *mut _,
+    );
+    let sum = pixels.chunks_exact(4).map(|p| p[1] as f32).sum::<f32>();
+    sum / (SIZE.x * SIZE.y) as f32 / 255.0
+}
+
+#[test]
+fn adjustments_brighten_a_dark_image() {
+    let Some(_headless) = common::headless(uvec2(1, 1)) else {
+        return;
+    };
+
+    unsafe {
+        let source = dark_image();
+        let mut adjuster = Adjuster::new(SIZE).unwrap();
+        let mut params = adjust::with_params(Params::new());
+
+        // nothing to adjust by default
+        assert_eq!(adjuster.apply(source, &params), source);
+        let dark = mean_luma(source);
+        assert!(dark < 0.15, "{dark}");
+
+        params.set("gamma", Value::Number(2.0)).unwrap();
+        let brighter = mean_luma(adjuster.apply(source, &params));
+        assert!(brighter > dark + 0.1, "{brighter} after gamma, from {dark}");
+
+        if supports_compute() {
+            params.set("gamma", Value::Number(1.0)).unwrap();
+            params
+                .set("equalize", Value::Text("global".into()))
+                .unwrap();
+            // equalizing a uniform spread of levels stretches it over all of them
+            let equalized = mean_luma(adjuster.apply(source, &params));
+            assert!((equalized - 0.5).abs() < 0.05, "{equalized} once equalized");
+        }
+    }
+}
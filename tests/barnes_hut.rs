@@ -0,0 +1,66 @@
+use glam::Vec2;
+use opengl_playground::barnes_hut;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const SOFTENING: f32 = 1.0;
+
+fn bodies(n: usize) -> (Vec<Vec2>, Vec<f32>) {
+    let mut rng = StdRng::seed_from_u64(7);
+    let positions = (0..n)
+        .map(|_| Vec2::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)))
+        .collect();
+    let masses = (0..n).map(|_| rng.gen_range(1.0..10.0)).collect();
+    (positions, masses)
+}
+
+fn brute_force(positions: &[Vec2], masses: &[f32]) -> Vec<Vec2> {
+    (positions.iter().enumerate())
+        .map(|(i, &p)| {
+            (positions.iter().zip(masses).enumerate())
+                .filter(|&(j, _)| j != i)
+                .map(|(_, (&q, &mass))| {
+                    let d = q - p;
+                    let distance_squared = d.length_squared() + SOFTENING * SOFTENING;
+                    d * (mass / (distance_squared * distance_squared.sqrt()))
+                })
+                .sum()
+        })
+        .collect()
+}
+
+#[test]
+fn theta_zero_is_exact() {
+    let (positions, masses) = bodies(300);
+    let mut accelerations = vec![Vec2::ZERO; positions.len()];
+    barnes_hut::accelerations(&positions, &masses, 0.0, SOFTENING, &mut accelerations);
+
+    for (a, b) in accelerations.iter().zip(brute_force(&positions, &masses)) {
+        assert!(a.distance(b) <= b.length() * 1e-4, "{a} != {b}");
+    }
+}
+
+#[test]
+fn approximation_stays_close() {
+    let (positions, masses) = bodies(2000);
+    let mut accelerations = vec![Vec2::ZERO; positions.len()];
+    barnes_hut::accelerations(&positions, &masses, 0.5, SOFTENING, &mut accelerations);
+
+    let exact = brute_force(&positions, &masses);
+    let error = (accelerations.iter().zip(&exact))
+        .map(|(a, b)| a.distance(*b) / b.length())
+        .sum::<f32>()
+        / exact.len() as f32;
+    assert!(error < 0.03, "average relative error {error}");
+}
+
+#[test]
+fn bodies_on_top_of_each_other() {
+    // more than a leaf holds, so splitting has to give up at some depth
+    let positions = vec![Vec2::splat(3.0); 10];
+    let masses = vec![1.0; 10];
+    let mut accelerations = vec![Vec2::ONE; 10];
+    barnes_hut::accelerations(&positions, &masses, 0.5, SOFTENING, &mut accelerations);
+
+    assert!(accelerations.iter().all(|&a| a == Vec2::ZERO));
+}
@@ -0,0 +1,94 @@
+//! The polygon modes of OpenGL ES, drawn by the shaders themselves. Desktop
+//! contexts run the same shaders, so they are checked there, on a binary of
+//! their own since whether the context is GLES is global.
+
+use glam::uvec2;
+use opengl_playground::common_gl::create_shader_program;
+use opengl_playground::render_state::{self, barycentric_source, PolygonMode, RenderState};
+
+mod common;
+
+const VERT: &[u8] = b"#version 330 core
+const vec2 CORNERS[3] = vec2[](vec2(-0.75, -0.75), vec2(0.75, -0.75), vec2(-0.75, 0.75));
+void main () {
+    gl_Position = vec4(CORNERS[gl_VertexID], 0.0, 1.0);
+}
+";
+
+const FRAG: &[u8] = b"#version 330 core
+out vec4 frag_color;
+void main() {
+    frag_color = vec4(1.0);
+}
+";
+
+#[test]
+fn only_main_is_wrapped() {
+    let source = b"void domain() {}\nvoid main () {}\n";
+    let wrapped = barycentric_source(source, gl::FRAGMENT_SHADER).unwrap();
+    let wrapped = String::from_utf8(wrapped).unwrap();
+
+    assert!(wrapped.starts_with("void domain() {}\nvoid debug_scene_main () {}\n"));
+    assert!(wrapped.contains("debug_scene_main();"));
+    assert_eq!(
+        barycentric_source(b"void domain() {}", gl::VERTEX_SHADER),
+        None
+    );
+    assert_eq!(barycentric_source(source, gl::COMPUTE_SHADER), None);
+}
+
+#[test]
+fn triangles_are_drawn_as_edges_and_corners() {
+    let Some(headless) = common::headless(uvec2(64, 64)) else {
+        return;
+    };
+    let size = headless.size();
+
+    render_state::set_gles(true);
+    let program = unsafe { create_shader_program(VERT, FRAG) }.unwrap();
+    let mut vao = 0;
+    unsafe { gl::GenVertexArrays(1, &mut vao) };
+
+    // corners land on pixel 8 from the left and the bottom, and 56
+    let drawn = |mode| unsafe {
+        render_state::set_polygon_mode(mode);
+        gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        RenderState::OPAQUE.apply();
+        gl::UseProgram(program);
+        gl::BindVertexArray(vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        render_state::reset_debug();
+
+        let mut pixels = vec![0u8; (size.x * size.y * 4) as usize];
+        gl::ReadPixels(
+            0,
+            0,
+            size.x as i32,
+            size.y as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut _,
+        );
+        assert_eq!(gl::GetError(), gl::NO_ERROR, "{mode:?}");
+        move |x: u32, y: u32| pixels[((y * size.x + x) * 4) as usize] > 0
+    };
+
+    let fill = drawn(PolygonMode::Fill);
+    assert!(fill(20, 20) && fill(32, 8));
+
+    let line = drawn(PolygonMode::Line);
+    assert!(!line(20, 20), "inside a wireframe");
+    assert!(line(32, 8) && line(8, 32), "on the edges of a wireframe");
+
+    let point = drawn(PolygonMode::Point);
+    assert!(point(8, 8), "on a corner");
+    assert!(!point(32, 8) && !point(20, 20), "away from the corners");
+
+    render_state::set_polygon_mode(PolygonMode::Fill);
+    render_state::set_gles(false);
+    unsafe {
+        gl::DeleteVertexArrays(1, &vao);
+        gl::DeleteProgram(program);
+    }
+}
@@ -0,0 +1,114 @@
+use opengl_playground::bindings::{parse_binding, Action, Bindings, KeyRepeat, RepeatConfig};
+use winit::keyboard::{Key, KeyCode, ModifiersState, NamedKey, PhysicalKey};
+
+fn character(ch: &str) -> Key {
+    Key::Character(ch.into())
+}
+
+#[test]
+fn chords_need_their_modifiers_and_no_others() {
+    let bindings = Bindings::default();
+    let s = PhysicalKey::Code(KeyCode::KeyS);
+
+    assert_eq!(
+        bindings.action(&character("s"), s, ModifiersState::CONTROL),
+        Some(Action::Snapshot)
+    );
+    // plain S goes to the scene
+    assert_eq!(
+        bindings.action(&character("s"), s, ModifiersState::empty()),
+        None
+    );
+    assert_eq!(
+        bindings.action(&character("s"), s, ModifiersState::ALT),
+        None
+    );
+
+    let f5 = Key::Named(NamedKey::F5);
+    let code = PhysicalKey::Code(KeyCode::F5);
+    assert_eq!(
+        bindings.action(&f5, code, ModifiersState::empty()),
+        Some(Action::Reseed)
+    );
+    // Shift is only needed where the chord says so
+    assert_eq!(
+        bindings.action(&f5, code, ModifiersState::SHIFT),
+        Some(Action::Reseed)
+    );
+    assert_eq!(bindings.action(&f5, code, ModifiersState::CONTROL), None);
+
+    let f12 = Key::Named(NamedKey::F12);
+    let code = PhysicalKey::Code(KeyCode::F12);
+    assert_eq!(
+        bindings.action(&f12, code, ModifiersState::empty()),
+        Some(Action::Screenshot)
+    );
+
+    // punctuation is found wherever the layout puts it
+    let backquote = PhysicalKey::Code(KeyCode::Backquote);
+    assert_eq!(
+        bindings.action(&character("²"), backquote, ModifiersState::empty()),
+        Some(Action::Console)
+    );
+}
+
+#[test]
+fn bindings_parse_and_override_the_defaults() {
+    let (chord, action) = parse_binding("Ctrl+Shift+S=export").unwrap();
+    assert_eq!(chord.to_string(), "Ctrl+Shift+S");
+    assert_eq!(action, Action::Export);
+
+    let mut bindings = Bindings::default();
+    bindings.bind(chord, action);
+    let s = PhysicalKey::Code(KeyCode::KeyS);
+    let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+    // the chord with the most modifiers wins
+    assert_eq!(
+        bindings.action(&character("S"), s, ctrl_shift),
+        Some(Action::Export)
+    );
+    assert_eq!(
+        bindings.action(&character("s"), s, ModifiersState::CONTROL),
+        Some(Action::Snapshot)
+    );
+
+    let (chord, _) = parse_binding("Alt++=help").unwrap();
+    assert_eq!(chord.to_string(), "Alt++");
+    assert!(parse_binding("Hyper+S=help").is_err());
+    assert!(parse_binding("Ctrl+S=fly").is_err());
+    assert!(parse_binding("Ctrl+S").is_err());
+}
+
+#[test]
+fn held_keys_repeat_faster_and_faster() {
+    let config: RepeatConfig = "300,100,20".parse().unwrap();
+    assert_eq!(config.fastest, 0.02);
+    let mut key_repeat = KeyRepeat::new(config);
+
+    let up = Key::Named(NamedKey::ArrowUp);
+    key_repeat.press(up.clone());
+    assert!(key_repeat.update(0.25).is_empty());
+    assert_eq!(key_repeat.update(0.06), vec![up.clone()]);
+
+    // each interval is shorter than the one before, down to the fastest
+    let mut count = 0;
+    for _ in 0..100 {
+        count += key_repeat.update(0.01).len();
+    }
+    assert!(count > 10, "{count}");
+    // 20 ms apart by now, but a stall doesn't make up for the missed ones
+    assert_eq!(key_repeat.update(0.2), vec![up.clone()]);
+    assert!(key_repeat.update(0.01).is_empty());
+    assert_eq!(key_repeat.update(0.01), vec![up.clone()]);
+
+    key_repeat.release(&up);
+    assert!(key_repeat.update(1.0).is_empty());
+
+    // released without Shift
+    key_repeat.press(character("L"));
+    key_repeat.release(&character("l"));
+    assert!(key_repeat.update(1.0).is_empty());
+
+    assert!("300,0,20".parse::<RepeatConfig>().is_err());
+    assert!("300,100".parse::<RepeatConfig>().is_err());
+}
@@ -0,0 +1,58 @@
+//! Checks the BC1 and BC7 encoders against their decoders.
+
+use image::{Rgba, RgbaImage};
+use opengl_playground::block_compression::{
+    block_aligned, decode_bc1, decode_bc7, encode_bc1, encode_bc7, psnr, BC1_BLOCK_BYTES,
+    BC7_BLOCK_BYTES,
+};
+
+/// Smooth gradients with a few hard edges, like a photo would have.
+fn test_image() -> RgbaImage {
+    RgbaImage::from_fn(64, 48, |x, y| {
+        let edge = if (x / 16 + y / 16) % 2 == 0 { 0 } else { 60 };
+        Rgba([
+            (x * 3) as u8 + edge,
+            (y * 4) as u8,
+            200 - (x + y) as u8,
+            255,
+        ])
+    })
+}
+
+#[test]
+fn compressed_sizes_follow_the_block_sizes() {
+    let image = test_image();
+    let blocks = (64 / 4) * (48 / 4);
+    assert_eq!(encode_bc1(&image).len(), blocks * BC1_BLOCK_BYTES);
+    assert_eq!(encode_bc7(&image).len(), blocks * BC7_BLOCK_BYTES);
+}
+
+#[test]
+fn bc7_is_closer_to_the_original_than_bc1() {
+    let image = test_image();
+    let bc1 = decode_bc1(&encode_bc1(&image), 64, 48);
+    let bc7 = decode_bc7(&encode_bc7(&image), 64, 48);
+
+    let (bc1_psnr, bc7_psnr) = (psnr(&image, &bc1), psnr(&image, &bc7));
+    assert!(bc1_psnr > 30.0, "BC1 at {bc1_psnr} dB");
+    assert!(bc7_psnr > 40.0, "BC7 at {bc7_psnr} dB");
+    assert!(bc7_psnr > bc1_psnr);
+}
+
+#[test]
+fn flat_blocks_survive_bc7() {
+    let image = RgbaImage::from_pixel(8, 8, Rgba([12, 200, 99, 128]));
+    let decoded = decode_bc7(&encode_bc7(&image), 8, 8);
+    for (a, b) in image.pixels().zip(decoded.pixels()) {
+        for c in 0..4 {
+            assert!(a[c].abs_diff(b[c]) <= 1, "{a:?} became {b:?}");
+        }
+    }
+}
+
+#[test]
+fn images_are_cropped_to_whole_blocks() {
+    let image = RgbaImage::new(30, 17);
+    let aligned = block_aligned(&image);
+    assert_eq!(aligned.dimensions(), (28, 16));
+}
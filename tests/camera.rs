@@ -0,0 +1,176 @@
+use glam::{vec2, vec3, Vec2, Vec4Swizzles};
+use opengl_playground::camera::{Camera, Projection};
+use opengl_playground::scene_controller::ZoomLimits;
+
+#[test]
+fn off_axis_lines_up_with_orthographic_on_the_screen_plane() {
+    let camera = Camera {
+        position: vec2(30.0, -50.0),
+        rotation: 0.4,
+        scale: Vec2::splat(1.5),
+    };
+    let viewport = vec2(1280.0, 720.0);
+    let orthographic = camera.matrix(viewport);
+    let projection = Projection::OffAxis {
+        eye: vec3(200.0, -100.0, 1500.0),
+        far: 1000.0,
+    };
+    let off_axis = camera.projection_matrix(viewport, projection);
+
+    for point in [vec2(0.0, 0.0), vec2(-300.0, 120.0), vec2(410.0, 290.0)] {
+        let expected = orthographic * point.extend(0.0).extend(1.0);
+        let projected = off_axis * point.extend(0.0).extend(1.0);
+        let ndc = projected.xy() / projected.w;
+        assert!((ndc - expected.xy()).length() < 1e-4, "{ndc} != {expected}");
+    }
+}
+
+#[test]
+fn off_axis_shifts_deeper_points_away_from_the_eye() {
+    let camera = Camera::default();
+    let viewport = vec2(800.0, 600.0);
+    let projection = Projection::OffAxis {
+        eye: vec3(300.0, 0.0, 1000.0),
+        far: 1000.0,
+    };
+    let matrix = camera.projection_matrix(viewport, projection);
+
+    let ndc = |z: f32| {
+        let clip = matrix * vec3(0.0, 0.0, z).extend(1.0);
+        clip.xyz() / clip.w
+    };
+
+    // seen from the right, things behind the screen shift right and things
+    // in front of it shift left
+    assert!(ndc(500.0).x > ndc(0.0).x);
+    assert!(ndc(-200.0).x < ndc(0.0).x);
+    assert!((-1.0..1.0).contains(&ndc(500.0).z));
+    assert!(ndc(500.0).z > ndc(0.0).z);
+}
+
+fn cameras() -> Vec<Camera> {
+    let mut cameras = Vec::new();
+    for scale in [
+        Vec2::ONE,
+        Vec2::splat(0.25),
+        Vec2::splat(7.5),
+        vec2(2.0, 0.5),
+    ] {
+        for rotation in [0.0, 0.4, -1.3, std::f32::consts::PI] {
+            cameras.push(Camera {
+                position: vec2(30.0, -50.0),
+                rotation,
+                scale,
+            });
+        }
+    }
+    cameras
+}
+
+const VIEWPORTS: [Vec2; 3] = [vec2(1280.0, 720.0), vec2(800.0, 600.0), vec2(301.0, 977.0)];
+
+#[test]
+fn pointers_and_positions_round_trip() {
+    for camera in cameras() {
+        for viewport in VIEWPORTS {
+            for pointer in [vec2(0.0, 0.0), vec2(640.0, 360.0), vec2(17.5, 590.25)] {
+                let pos = camera.pointer_to_pos(pointer, viewport);
+                let back = camera.pos_to_pointer(pos, viewport);
+                assert!(
+                    (back - pointer).length() < 1e-2,
+                    "{camera:?} at {viewport}: {pointer} -> {pos} -> {back}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn pointers_land_where_the_matrix_draws() {
+    for camera in cameras() {
+        for viewport in VIEWPORTS {
+            let matrix = camera.matrix(viewport);
+            for pos in [vec2(0.0, 0.0), vec2(-300.0, 120.0), vec2(410.0, 290.0)] {
+                let ndc = (matrix * pos.extend(0.0).extend(1.0)).xy();
+                // NDC y goes up, window pixels go down
+                let pixel = vec2(ndc.x + 1.0, 1.0 - ndc.y) / 2.0 * viewport;
+
+                let picked = camera.pointer_to_pos(pixel, viewport);
+                assert!(
+                    (picked - pos).length() < 1e-2,
+                    "{camera:?} at {viewport}: {pos} drawn at {pixel}, picked {picked}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn the_camera_position_offsets_the_center_of_the_window() {
+    for camera in cameras() {
+        for viewport in VIEWPORTS {
+            let center = camera.pointer_to_pos(viewport / 2.0, viewport);
+            assert!((center + camera.position).length() < 1e-3);
+        }
+    }
+
+    // a quarter turn brings what was right of the center below it
+    let camera = Camera {
+        rotation: std::f32::consts::FRAC_PI_2,
+        ..Camera::default()
+    };
+    let viewport = vec2(800.0, 600.0);
+    let pointer = camera.pos_to_pointer(vec2(100.0, 0.0), viewport);
+    assert!((pointer - vec2(400.0, 400.0)).length() < 1e-3, "{pointer}");
+}
+
+#[test]
+fn centered_cameras_draw_relative_positions_in_the_same_place() {
+    let viewport = vec2(1280.0, 720.0);
+    for camera in cameras() {
+        let centered = camera.centered().matrix(viewport);
+        for pos in [vec2(0.0, 0.0), vec2(-300.0, 120.0), vec2(410.0, 290.0)] {
+            let expected = camera.matrix(viewport) * pos.extend(0.0).extend(1.0);
+            let relative = (pos - camera.center()).extend(0.0).extend(1.0);
+            assert!(((centered * relative) - expected).length() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn zoom_limits_parse() {
+    let limits: ZoomLimits = "0.5, 8".parse().unwrap();
+    assert_eq!((limits.min, limits.max), (0.5, 8.0));
+
+    for invalid in ["8,0.5", "0,4", "1", "1,2,3", "a,b"] {
+        assert!(invalid.parse::<ZoomLimits>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn zooming_eases_into_the_limits() {
+    let limits = ZoomLimits {
+        min: 0.25,
+        max: 16.0,
+    };
+
+    // away from the limits, a step is as long as asked
+    assert_eq!(limits.zoom(1.0, 1.0, 1.0), 2.0);
+    assert_eq!(limits.zoom(1.0, -1.0, 1.0), 0.5);
+
+    // closer to the limit, the same step goes a shorter way, never past it
+    let mut scale = 1.0;
+    let mut last_step = f32::INFINITY;
+    for _ in 0..20 {
+        let zoomed = limits.zoom(scale, 0.5, 1.0);
+        let step = (zoomed / scale).log2();
+        assert!(step <= last_step + 1e-6);
+        assert!(zoomed <= 16.0);
+        (scale, last_step) = (zoomed, step);
+    }
+    assert!(scale > 15.0);
+
+    // the limits are in logical pixels
+    assert_eq!(limits.zoom(0.5, -10.0, 2.0), 0.5);
+    assert!(limits.zoom(16.0, 1.0, 2.0) > 16.0);
+}
@@ -0,0 +1,81 @@
+//! Checks the constraints of a cloth grid and that solving them holds it
+//! together.
+
+use glam::{Vec2, Vec3};
+use opengl_playground::cloth::{Cloth, ConstraintKind};
+
+const GRAVITY: Vec3 = Vec3::new(0.0, 980.0, 0.0);
+
+fn count(cloth: &Cloth, kind: ConstraintKind) -> usize {
+    (cloth.constraints.iter())
+        .filter(|constraint| constraint.kind == kind)
+        .count()
+}
+
+#[test]
+fn grid_links_every_neighbour_once() {
+    let (columns, rows) = (5, 4);
+    let cloth = Cloth::grid(columns, rows, Vec3::ZERO, 2.0);
+
+    assert_eq!(
+        count(&cloth, ConstraintKind::Structural),
+        (columns - 1) * rows + columns * (rows - 1)
+    );
+    assert_eq!(
+        count(&cloth, ConstraintKind::Shear),
+        2 * (columns - 1) * (rows - 1)
+    );
+    assert_eq!(
+        count(&cloth, ConstraintKind::Bend),
+        (columns - 2) * rows + columns * (rows - 2)
+    );
+
+    // a cloth at rest is already solved
+    for constraint in &cloth.constraints {
+        let (a, b) = (constraint.a as usize, constraint.b as usize);
+        let length = cloth.positions[a].distance(cloth.positions[b]);
+        assert!((length - constraint.rest).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn pinned_particles_stay_put() {
+    let mut cloth = Cloth::grid(8, 8, Vec3::ZERO, 10.0);
+    cloth.pin(0);
+    cloth.pin(7);
+
+    for _ in 0..120 {
+        cloth.integrate(1.0 / 60.0, 0.01, |_| GRAVITY);
+        cloth.relax(8, |_| 1.0);
+    }
+
+    assert_eq!(cloth.positions[0], Vec3::ZERO);
+    assert_eq!(cloth.positions[7], Vec3::new(70.0, 0.0, 0.0));
+    // and the rest of it fell
+    assert!(cloth.positions[cloth.index(4, 7)].y > 70.0);
+}
+
+#[test]
+fn hanging_cloth_barely_stretches() {
+    let mut cloth = Cloth::grid(16, 16, Vec3::ZERO, 10.0);
+    for x in 0..16 {
+        cloth.pin(x);
+    }
+
+    for _ in 0..300 {
+        cloth.integrate(1.0 / 60.0, 0.01, |_| GRAVITY);
+        cloth.relax(16, |_| 1.0);
+    }
+
+    let bottom = cloth.positions[cloth.index(8, 15)];
+    assert!(bottom.y > 140.0 && bottom.y < 150.0 * 1.05, "{bottom}");
+}
+
+#[test]
+fn nearest_looks_on_the_plane() {
+    let mut cloth = Cloth::grid(3, 3, Vec3::ZERO, 10.0);
+    cloth.positions[4].z = 1000.0;
+
+    assert_eq!(cloth.nearest(Vec2::new(11.0, 9.0), 5.0), Some(4));
+    assert_eq!(cloth.nearest(Vec2::new(-50.0, 0.0), 5.0), None);
+}
@@ -0,0 +1,22 @@
+//! Shared by the integration tests that need a GL context.
+
+use glam::UVec2;
+use opengl_playground::headless::HeadlessContext;
+
+/// A headless context of `size`, or `None` after saying the test is skipped
+/// if there is none. With `REQUIRE_GL=1`, as on machines that are known to
+/// have one, the test fails instead, so that it can't pass without running.
+pub fn headless(size: UVec2) -> Option<HeadlessContext> {
+    let e = match HeadlessContext::new(size) {
+        Ok(headless) => return Some(headless),
+        Err(e) => e,
+    };
+
+    let test = std::thread::current();
+    let test = test.name().unwrap_or("test");
+    if std::env::var("REQUIRE_GL").is_ok_and(|value| value == "1") {
+        panic!("{test}: no headless GL context with REQUIRE_GL=1: {e}");
+    }
+    eprintln!("Skipping {test}, no headless GL context: {e}");
+    None
+}
@@ -0,0 +1,94 @@
+//! Checks the console's command parsing and line editing.
+
+use opengl_playground::console::{self, Action, Console};
+use opengl_playground::params::Value;
+use opengl_playground::remote::Command;
+use serde_json::json;
+
+#[test]
+fn lines_parse_into_remote_commands() {
+    let parsed = |line| match console::parse(line) {
+        Ok(Action::Run(command)) => command,
+        other => panic!("{line}: {other:?}"),
+    };
+
+    assert!(matches!(
+        parsed("scene kawase"),
+        Command::SwitchScene { scene } if scene == "kawase"
+    ));
+    assert!(matches!(
+        parsed("set kawase.radius 3"),
+        Command::SetParam { name, value: Value::Number(n) } if name == "kawase.radius" && n == 3.0
+    ));
+    assert!(matches!(
+        parsed("set  dither   on"),
+        Command::SetParam {
+            value: Value::Bool(true),
+            ..
+        }
+    ));
+    assert!(matches!(
+        parsed("set mode gaussian"),
+        Command::SetParam { value: Value::Text(t), .. } if t == "gaussian"
+    ));
+    assert!(matches!(
+        parsed("screenshot"),
+        Command::Screenshot { path: None }
+    ));
+    assert!(matches!(parsed("fps"), Command::Fps));
+
+    assert!(matches!(console::parse("help"), Ok(Action::Help)));
+    assert!(console::parse("scene").is_err());
+    assert!(console::parse("teleport home").is_err());
+}
+
+#[test]
+fn editing_moves_over_whole_characters() {
+    let mut console = Console::new();
+    console.insert("set 半径");
+    console.move_left();
+    console.backspace();
+    assert_eq!(console.input(), "set 径");
+
+    console.move_right();
+    console.insert(" 3");
+    console.move_left();
+    console.move_left();
+    console.delete();
+    assert_eq!(console.input(), "set 径3");
+}
+
+#[test]
+fn history_is_browsed_with_the_arrows() {
+    let mut console = Console::new();
+    for line in ["scene kawase", "get", "get"] {
+        console.insert(line);
+        console.submit();
+    }
+    assert_eq!(console.input(), "");
+
+    console.history_previous();
+    assert_eq!(console.input(), "get");
+    console.history_previous();
+    assert_eq!(console.input(), "scene kawase");
+    console.history_previous();
+    assert_eq!(console.input(), "scene kawase");
+
+    console.history_next();
+    assert_eq!(console.input(), "get");
+    console.history_next();
+    assert_eq!(console.input(), "");
+
+    assert_eq!(console.take_pending().len(), 3);
+}
+
+#[test]
+fn replies_are_described_on_one_line() {
+    assert_eq!(console::describe(&json!({})), "ok");
+    assert_eq!(
+        console::describe(
+            &json!({ "scene": "kawase", "params": { "radius": 3.5, "dither": false } })
+        ),
+        "params: dither=false radius=3.5, scene: kawase",
+    );
+}
@@ -0,0 +1,31 @@
+//! Checks how CSV tables are parsed for the data mode of the round quads.
+
+use opengl_playground::data_grid::DataGrid;
+
+#[test]
+fn header_is_skipped_and_rows_are_padded() {
+    let grid = DataGrid::parse_csv("a,b,c\n1,2,3\n4,5\n\n-1,x,10\n").unwrap();
+
+    assert_eq!((grid.width, grid.height), (3, 3));
+    assert_eq!((grid.min, grid.max), (-1.0, 10.0));
+    assert_eq!(grid.get(1, 0), Some(2.0));
+    // short rows and cells that aren't numbers are missing values
+    assert_eq!(grid.get(2, 1), None);
+    assert_eq!(grid.get(1, 2), None);
+    assert_eq!(grid.normalize(4.5), 0.5);
+}
+
+#[test]
+fn separator_is_guessed() {
+    let semicolons = DataGrid::parse_csv("1;2\n3;4").unwrap();
+    let tabs = DataGrid::parse_csv("1\t2\n3\t4").unwrap();
+    assert_eq!(semicolons.values, [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(tabs.values, semicolons.values);
+}
+
+#[test]
+fn tables_without_numbers_are_rejected() {
+    assert!(DataGrid::parse_csv("").is_err());
+    assert!(DataGrid::parse_csv("name,value\n").is_err());
+    assert!(DataGrid::parse_csv("a,b\nc,d\n").is_err());
+}
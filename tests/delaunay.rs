@@ -0,0 +1,51 @@
+//! Checks the triangulation against the empty circumcircle property.
+
+use glam::{vec2, Vec2};
+use opengl_playground::delaunay::{edges, triangulate};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[test]
+fn square_makes_two_triangles() {
+    let points = [
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(1.0, 1.1),
+        vec2(0.0, 1.0),
+    ];
+    let triangles = triangulate(&points);
+    assert_eq!(triangles.len(), 2);
+    // four sides and a diagonal
+    assert_eq!(edges(&triangles).len(), 5);
+    assert!(triangulate(&points[..2]).is_empty());
+}
+
+#[test]
+fn no_point_is_inside_a_circumcircle() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let points = (0..200)
+        .map(|_| vec2(rng.gen_range(-500.0..500.0), rng.gen_range(-300.0..300.0)))
+        .collect::<Vec<Vec2>>();
+
+    let triangles = triangulate(&points);
+    // a triangulation of n points with h of them on the hull has 2n - 2 - h
+    // triangles
+    assert!(triangles.len() > points.len() && triangles.len() <= 2 * points.len());
+
+    for &[a, b, c] in &triangles {
+        let [a, b, c] = [a, b, c].map(|i| points[i].as_dvec2());
+        assert!((b - a).perp_dot(c - a) > 0.0, "counterclockwise");
+
+        let (b, c) = (b - a, c - a);
+        let d = 2.0 * b.perp_dot(c);
+        let center = a + glam::DVec2::new(
+            c.y * b.length_squared() - b.y * c.length_squared(),
+            b.x * c.length_squared() - c.x * b.length_squared(),
+        ) / d;
+        let radius = (a - center).length();
+
+        for point in &points {
+            assert!(point.as_dvec2().distance(center) > radius - 1e-6);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use gl::types::GLuint;
+use glam::{uvec2, UVec2};
+use opengl_playground::common_gl::supports_compute;
+use opengl_playground::downsample::{DownsampleChain, COMPUTE_LEVELS};
+
+mod common;
+
+const SIZE: UVec2 = UVec2::new(256, 128);
+
+/// Diagonal stripes, with enough detail for filters to differ, over
+/// gradients.
+unsafe fn fill_stripes(texture: GLuint) {
+    let pixels = (0..SIZE.y)
+        .flat_map(|y| {
+            (0..SIZE.x).flat_map(move |x| {
+                let v = if (x + y) / 3 % 2 == 0 { 230 } else { 20 };
+                [v, (x * 255 / SIZE.x) as u8, (y * 255 / SIZE.y) as u8, 255]
+            })
+        })
+        .collect::<Vec<_>>();
+    gl::BindTexture(gl::TEXTURE_2D, texture);
+    gl::TexSubImage2D(
+        gl::TEXTURE_2D,
+        0,
+        0,
+        0,
+        SIZE.x as i32,
+        SIZE.y as i32,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixels.as_ptr() as *const _,
+    );
+}
+
+unsafe fn pixels(texture: GLuint, size: UVec2) -> Vec<u8> {
+    let mut pixels = vec![0u8; (size.x * size.y * 4) as usize];
+    gl::BindTexture(gl::TEXTURE_2D, texture);
+    gl::GetTexImage(
+        gl::TEXTURE_2D,
+        0,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixels.as_mut_ptr() as *mut _,
+    );
+    pixels
+}
+
+fn mean(pixels: &[u8]) -> f32 {
+    pixels.iter().map(|&p| p as f32).sum::<f32>() / pixels.len() as f32
+}
+
+#[test]
+fn one_dispatch_matches_the_passes() {
+    let Some(_headless) = common::headless(uvec2(1, 1)) else {
+        return;
+    };
+
+    let mut chain = DownsampleChain::new("test", SIZE, COMPUTE_LEVELS + 2, gl::RGBA8).unwrap();
+    if !chain.enable_compute().unwrap() || !unsafe { supports_compute() } {
+        eprintln!("Skipping downsample test, no compute shaders");
+        return;
+    }
+
+    let layers = chain.len() - 1;
+    let levels = |chain: &DownsampleChain| unsafe {
+        (1..chain.len())
+            .map(|i| pixels(chain.level(i).texture, chain.level(i).size))
+            .collect::<Vec<_>>()
+    };
+    unsafe { fill_stripes(chain.level(0).texture) };
+    chain.downsample(layers, 1.0);
+    let passes = levels(&chain);
+    chain.downsample_compute(layers, 1.0);
+    let compute = levels(&chain);
+
+    // level 1 is filtered the same way
+    let diff = (passes[0].iter().zip(&compute[0]))
+        .map(|(a, b)| a.abs_diff(*b))
+        .max()
+        .unwrap();
+    assert!(diff <= 1, "level 1 differs by up to {diff}");
+
+    // the deeper ones are averaged rather than filtered, but as bright with
+    // even sizes, down to those past the dispatch
+    for (i, (passes, compute)) in passes.iter().zip(&compute).enumerate() {
+        let (a, b) = (mean(passes), mean(compute));
+        assert!((a - b).abs() < 1.0, "level {}: {a} vs {b}", i + 1);
+    }
+}
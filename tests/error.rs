@@ -0,0 +1,102 @@
+//! Checks that resources that can't be created come back as errors, and that
+//! scenes failing to be created show the error screen, as well as panics.
+
+use glam::{uvec2, UVec2, Vec2};
+use image::RgbaImage;
+use opengl_playground::camera::Camera;
+use opengl_playground::common_gl::{
+    create_framebuffer, create_shader_program, max_texture_size, upload_texture,
+};
+use opengl_playground::error::{catch_panic, GlError, PlaygroundError};
+use opengl_playground::input_image::InputImage;
+use opengl_playground::scenes::{SceneContext, Scenes};
+
+mod common;
+
+const VERT: &[u8] = b"#version 330 core
+void main() {
+    gl_Position = vec4(0.0, 0.0, 0.0, 1.0);
+}
+";
+
+const BROKEN_FRAG: &[u8] = b"#version 330 core
+out vec4 color;
+void main() {
+    color = vec4(oops);
+}
+";
+
+#[test]
+fn broken_shaders_and_textures_are_errors() {
+    let Some(_headless) = common::headless(UVec2::ONE) else {
+        return;
+    };
+
+    match unsafe { create_shader_program(VERT, BROKEN_FRAG) } {
+        Err(GlError::Compile { stage, log }) => {
+            assert_eq!(stage, "frag");
+            assert!(!log.is_empty());
+        }
+        other => panic!("expected a compile error, got {other:?}"),
+    }
+
+    let max = unsafe { max_texture_size() };
+    let mut texture = 0;
+    unsafe { gl::GenTextures(1, &mut texture) };
+    for size in [uvec2(0, 4), uvec2(max + 1, 1)] {
+        let result = unsafe { upload_texture(texture, size.x, size.y, std::ptr::null(), 0) };
+        assert_eq!(result, Err(GlError::TextureSize { size, max }));
+    }
+    unsafe { gl::DeleteTextures(1, &texture) };
+
+    let framebuffer = unsafe { create_framebuffer("too wide", uvec2(max + 1, 1)) };
+    assert!(matches!(framebuffer, Err(GlError::TextureSize { .. })));
+    assert_eq!(unsafe { gl::GetError() }, gl::NO_ERROR);
+}
+
+#[test]
+fn scenes_that_fail_show_the_error_screen() {
+    let size = uvec2(64, 48);
+    let Some(headless) = common::headless(size) else {
+        return;
+    };
+
+    // wider than any texture can be
+    let width = unsafe { max_texture_size() } + 1;
+    let input = InputImage::from_pixels(RgbaImage::new(width, 1), u32::MAX);
+    let ctx = SceneContext {
+        size: headless.physical_size(),
+        input: &input,
+        data: None,
+        seed: 0,
+        text: None,
+    };
+
+    assert!(Scenes::from_name("no-such-scene", &ctx).is_none());
+
+    let mut scenes = Scenes::from_name("blurring", &ctx).unwrap();
+    assert_eq!(scenes.name(), "blurring");
+    assert!(matches!(
+        scenes.error(),
+        Some(PlaygroundError::Gl(GlError::TextureSize { .. }))
+    ));
+
+    let camera = Camera::default();
+    scenes.resize(&camera, size.x as i32, size.y as i32);
+    scenes.draw(&camera, Vec2::ZERO);
+    unsafe { gl::Finish() };
+    assert_eq!(unsafe { gl::GetError() }, gl::NO_ERROR);
+}
+
+#[test]
+fn panics_are_caught_with_where_they_happened() {
+    assert_eq!(catch_panic(|| 4), Ok(4));
+
+    match catch_panic(|| -> u32 { panic!("boom {}", 42) }) {
+        Err(PlaygroundError::Panic { message, .. }) => {
+            assert!(message.starts_with("tests/error.rs:"), "{message}");
+            assert!(message.ends_with("boom 42"), "{message}");
+        }
+        other => panic!("expected a panic, got {other:?}"),
+    }
+}
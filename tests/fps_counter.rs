@@ -0,0 +1,25 @@
+use opengl_playground::fps_counter::{glyph_index, FrameTimes};
+
+#[test]
+fn frames_older_than_a_second_are_dropped() {
+    let mut times = FrameTimes::new();
+    for _ in 0..30 {
+        times.push(0.05);
+    }
+    times.push(0.02);
+
+    assert_eq!(times.max(), 0.05);
+    assert_eq!(times.min(), 0.02);
+    // the 0.02 s frame and the twenty 0.05 s frames before it
+    assert!((times.fps() - 21.0 / 1.02).abs() < 0.01, "{}", times.fps());
+}
+
+#[test]
+fn the_font_covers_the_counter() {
+    let mut times = FrameTimes::new();
+    times.push(1.0 / 144.0);
+
+    let label = times.to_string();
+    assert_eq!(label, "144 FPS  6.94 ms  min 6.94  max 6.94");
+    assert!(label.chars().all(|c| glyph_index(c).is_some()));
+}
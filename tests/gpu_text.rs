@@ -0,0 +1,62 @@
+use opengl_playground::fps_counter::{glyph_index, FpsMode, FrameTimes};
+use opengl_playground::gpu_text::{decode_label, encode_label, sdf_atlas};
+
+#[test]
+fn the_label_is_formatted_like_the_glyph_quads() {
+    let mut times = FrameTimes::new();
+    times.push(1.0 / 144.0);
+    let values = [
+        times.fps(),
+        times.average() * 1000.0,
+        times.min() * 1000.0,
+        times.max() * 1000.0,
+    ];
+
+    let label = decode_label(&encode_label(), values);
+    // numbers are right aligned, so the label doesn't change width
+    assert_eq!(label, " 144 FPS    6.94 ms  min   6.94  max   6.94");
+    assert_eq!(label.split_whitespace().collect::<Vec<_>>(), {
+        let quads = times.to_string();
+        quads
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+    });
+}
+
+#[test]
+fn digits_index_the_font() {
+    for digit in 0..10 {
+        let c = char::from_digit(digit, 10).unwrap();
+        assert_eq!(glyph_index(c), Some(digit as usize));
+    }
+    assert_eq!(glyph_index(' '), Some(11));
+    assert!(encode_label().len() <= 48);
+}
+
+#[test]
+fn distances_are_half_on_the_edges() {
+    let (size, texels) = sdf_atlas();
+    let red = |x: u32, y: u32| texels[((y * size.x + x) * 4) as usize];
+
+    // '1' is the second glyph, 42 texels wide with 6 of padding, its stroke
+    // a column in the middle half a texel of the font from either edge
+    let middle = 42 + 6 + 2 * 6 + 3;
+    let row = 6 + 3 * 6 + 3;
+    assert!(red(middle - 1, row) > 180 && red(middle, row) > 180);
+    assert!(red(middle + 2, row) > 128 && red(middle + 3, row) < 128);
+    assert_eq!(red(42 + 1, row), 0);
+    // the space has nothing inside
+    assert!((0..size.y).all(|y| red(11 * 42 + 21, y) < 128));
+}
+
+#[test]
+fn modes_cycle_back_to_hidden() {
+    let mut mode = FpsMode::Hidden;
+    for _ in FpsMode::ALL {
+        mode = mode.next();
+    }
+    assert_eq!(mode, FpsMode::Hidden);
+    assert_eq!(FpsMode::Hidden.next(), FpsMode::Quads);
+    assert_eq!(FpsMode::Quads.next(), FpsMode::Shader);
+}
@@ -0,0 +1,118 @@
+//! Relinking programs when their files change, with the hot-reload feature.
+#![cfg(feature = "hot-reload")]
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use glam::UVec2;
+use opengl_playground::common_gl::{
+    create_shader_program, read_pixels, reload_shaders, watch_shaders, Reload,
+};
+
+mod common;
+
+const VERT: &str = "#version 330 core
+in vec2 position;
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0);
+}
+";
+
+/// A fragment shader writing `u_color` into `channel`.
+fn frag(channel: &str) -> String {
+    format!(
+        "#version 330 core
+uniform float u_color;
+out vec4 FragColor;
+void main() {{
+    FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+    FragColor.{channel} = u_color;
+}}
+"
+    )
+}
+
+/// Reloads until the watcher reports something, or a few seconds passed.
+fn wait_for_reload() -> Vec<Reload> {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        let reloads = unsafe { reload_shaders() };
+        if !reloads.is_empty() {
+            return reloads;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    Vec::new()
+}
+
+/// Draws a triangle covering the viewport with `program` and reads the
+/// pixel in the middle.
+unsafe fn draw(program: u32) -> [u8; 4] {
+    let vertices: [f32; 6] = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+    let (mut vao, mut vbo) = (0, 0);
+    gl::GenVertexArrays(1, &mut vao);
+    gl::BindVertexArray(vao);
+    gl::GenBuffers(1, &mut vbo);
+    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        std::mem::size_of_val(&vertices) as isize,
+        vertices.as_ptr().cast(),
+        gl::STATIC_DRAW,
+    );
+    gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 0, std::ptr::null());
+    gl::EnableVertexAttribArray(0);
+    gl::UseProgram(program);
+    gl::DrawArrays(gl::TRIANGLES, 0, 3);
+    gl::DeleteBuffers(1, &vbo);
+    gl::DeleteVertexArrays(1, &vao);
+
+    let pixels = read_pixels(4, 4);
+    let center = (2 * 4 + 2) * 4;
+    pixels[center..center + 4].try_into().unwrap()
+}
+
+#[test]
+fn changed_files_relink_programs_keeping_their_uniforms() {
+    let Some(_headless) = common::headless(UVec2::splat(4)) else {
+        return;
+    };
+
+    let dir = std::env::temp_dir().join(format!("hot-reload-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let frag_path = dir.join("color.frag");
+    fs::write(dir.join("color.vert"), VERT).unwrap();
+    fs::write(&frag_path, frag("r")).unwrap();
+    watch_shaders(&dir).unwrap();
+
+    let program = unsafe { create_shader_program(VERT.as_bytes(), frag("r").as_bytes()) }.unwrap();
+    unsafe {
+        gl::BindAttribLocation(program, 0, c"position".as_ptr());
+        gl::LinkProgram(program);
+        gl::UseProgram(program);
+        let location = gl::GetUniformLocation(program, c"u_color".as_ptr());
+        gl::Uniform1f(location, 1.0);
+        assert_eq!(draw(program), [255, 0, 0, 255]);
+    }
+
+    // a broken shader is reported, and the old one keeps drawing
+    fs::write(&frag_path, "#version 330 core\nvoid main() { nope }\n").unwrap();
+    let reloads = wait_for_reload();
+    assert!(
+        matches!(reloads[..], [Reload { result: Err(_), .. }]),
+        "{reloads:?}"
+    );
+    assert_eq!(unsafe { draw(program) }, [255, 0, 0, 255]);
+
+    fs::write(&frag_path, frag("g")).unwrap();
+    let reloads = wait_for_reload();
+    assert!(
+        matches!(reloads[..], [Reload { result: Ok(1), .. }]),
+        "{reloads:?}"
+    );
+    // the same program, with its uniform carried over
+    assert_eq!(unsafe { draw(program) }, [0, 255, 0, 255]);
+
+    unsafe { gl::DeleteProgram(program) };
+    fs::remove_dir_all(&dir).unwrap();
+}
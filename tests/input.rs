@@ -0,0 +1,40 @@
+use glam::DVec2;
+use opengl_playground::input::Pointer;
+use winit::dpi::PhysicalPosition;
+
+#[test]
+fn positions_keep_their_fractions_and_every_sample_of_the_frame() {
+    let mut pointer = Pointer::new(0.0);
+    pointer.moved(PhysicalPosition::new(10.25, 20.125));
+    pointer.moved(PhysicalPosition::new(10.5, 20.375));
+    pointer.moved(PhysicalPosition::new(4096.0625, 2048.03125));
+    assert_eq!(pointer.raw(), DVec2::new(4096.0625, 2048.03125));
+
+    pointer.update(1.0 / 60.0);
+    assert_eq!(pointer.samples().len(), 3);
+    assert_eq!(pointer.samples()[0], DVec2::new(10.25, 20.125));
+    // without smoothing, both positions are the same
+    assert_eq!(pointer.smoothed(), pointer.raw());
+
+    pointer.update(1.0 / 60.0);
+    assert!(pointer.samples().is_empty());
+}
+
+#[test]
+fn smoothed_position_catches_up_over_time() {
+    let mut pointer = Pointer::new(50.0);
+    pointer.moved(PhysicalPosition::new(0.0, 0.0));
+    pointer.update(0.01);
+    assert_eq!(pointer.smoothed(), DVec2::ZERO);
+
+    pointer.moved(PhysicalPosition::new(100.0, 0.0));
+    pointer.update(0.05);
+    // one time constant in, about 63% of the way
+    let x = pointer.smoothed().x;
+    assert!((x - 63.2).abs() < 0.1, "{x}");
+
+    for _ in 0..100 {
+        pointer.update(0.01);
+    }
+    assert!(pointer.smoothed().abs_diff_eq(pointer.raw(), 1e-6));
+}
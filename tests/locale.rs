@@ -0,0 +1,55 @@
+//! Checks that every locale has the same strings as English.
+
+use std::collections::BTreeSet;
+
+use opengl_playground::locale::{self, table, tr, tr_args, Locale};
+
+/// Names of the `{name}` placeholders in `string`.
+fn placeholders(string: &str) -> BTreeSet<&str> {
+    (string.split('{').skip(1))
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+#[test]
+fn locales_have_the_same_keys_and_placeholders() {
+    let english = table(Locale::English);
+    assert!(!english.is_empty());
+
+    for &locale in Locale::ALL {
+        let strings = table(locale);
+
+        let keys = strings.keys().collect::<BTreeSet<_>>();
+        assert_eq!(keys, english.keys().collect(), "keys of {locale:?}");
+
+        for (key, string) in strings {
+            assert_eq!(
+                placeholders(string),
+                placeholders(&english[key]),
+                "placeholders of {key} in {locale:?}",
+            );
+        }
+    }
+}
+
+#[test]
+fn locale_codes_round_trip() {
+    for &locale in Locale::ALL {
+        assert_eq!(locale.code().parse::<Locale>(), Ok(locale));
+    }
+    assert_eq!("JA".parse::<Locale>(), Ok(Locale::Japanese));
+    assert!("xx".parse::<Locale>().is_err());
+}
+
+#[test]
+fn lookups_fall_back_and_fill_placeholders() {
+    locale::set_current(Locale::Japanese);
+    assert_eq!(tr("no.such.key"), "no.such.key");
+    assert_eq!(
+        tr_args("toast.locale", &[("locale", "English")]),
+        "🌐 言語：English",
+    );
+
+    locale::set_current(Locale::English);
+    assert_eq!(tr_args("toast.scene", &[("name", "Splines")]), "Splines");
+}
@@ -0,0 +1,83 @@
+use glam::{vec2, Vec2};
+use opengl_playground::marching_squares::{contours, Field};
+
+/// A cone peaking at 1 in the middle of a `size`×`size` grid, going down by 1
+/// every `radius`.
+fn cone(size: usize, radius: f32) -> Field {
+    let center = Vec2::splat((size - 1) as f32 / 2.0);
+    Field::new(size, size, |x, y| {
+        1.0 - vec2(x as f32, y as f32).distance(center) / radius
+    })
+}
+
+#[test]
+fn bump_gives_one_closed_loop() {
+    let field = cone(32, 10.0);
+    let lines = contours(&field, 0.5);
+
+    assert_eq!(lines.len(), 1);
+    let line = &lines[0];
+    assert_eq!(line.first(), line.last(), "the loop isn't closed");
+    for &point in line {
+        let distance = point.distance(Vec2::splat(15.5));
+        assert!((distance - 5.0).abs() < 0.1, "{point} is {distance} away");
+    }
+}
+
+#[test]
+fn values_above_stay_on_the_right() {
+    let field = cone(32, 10.0);
+    for line in contours(&field, 0.5) {
+        for segment in line.windows(2) {
+            let middle = (segment[0] + segment[1]) / 2.0;
+            let right = -(segment[1] - segment[0]).normalize().perp();
+            assert!(field.sample(middle + right * 0.2) > 0.5);
+            assert!(field.sample(middle - right * 0.2) < 0.5);
+        }
+    }
+}
+
+#[test]
+fn ramp_gives_one_open_line_across() {
+    let field = Field::new(10, 8, |x, _| x as f32);
+    let lines = contours(&field, 4.5);
+
+    assert_eq!(lines.len(), 1);
+    let line = &lines[0];
+    assert_eq!(line.len(), 8);
+    assert!(line.iter().all(|point| point.x == 4.5));
+    // opposite `perp` of a line going toward +y is +x, where the values are
+    // above
+    assert_eq!((line[0].y, line[7].y), (0.0, 7.0));
+}
+
+#[test]
+fn saddles_follow_their_middle() {
+    let field = Field::new(2, 2, |x, y| if x == y { 1.0 } else { 0.0 });
+
+    // the middle is at 0.5, so the corners above stay apart
+    let apart = contours(&field, 0.5);
+    assert_eq!(apart.len(), 2);
+    let mut sums = apart
+        .iter()
+        .map(|line| (line[0].x + line[0].y, line[1].x + line[1].y))
+        .collect::<Vec<_>>();
+    sums.sort_by(|a, b| a.0.total_cmp(&b.0));
+    assert_eq!(sums, [(0.5, 0.5), (1.5, 1.5)]);
+
+    // and here it's above, so they join and the corners below are cut off
+    let joined = contours(&field, 0.4);
+    assert_eq!(joined.len(), 2);
+    for line in &joined {
+        let differences = line.iter().map(|p| p.x - p.y).collect::<Vec<_>>();
+        assert!((differences[0].abs() - 0.6).abs() < 1e-6);
+        assert!((differences[1] - differences[0]).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn flat_field_has_no_contours() {
+    let field = Field::new(8, 8, |_, _| 1.0);
+    assert!(contours(&field, 1.0).is_empty());
+    assert!(contours(&field, 0.0).is_empty());
+}
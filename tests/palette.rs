@@ -0,0 +1,40 @@
+//! Checks the color helpers and palettes used to generate quads.
+
+use glam::Vec3;
+use opengl_playground::palette::{hue, viridis, Palette, PALETTES};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn close(a: Vec3, b: Vec3) -> bool {
+    (a - b).abs().max_element() < 0.02
+}
+
+#[test]
+fn hues_go_around_the_wheel() {
+    assert!(close(hue(0.0), Vec3::new(1.0, 0.0, 0.0)));
+    assert!(close(hue(1.0 / 3.0), Vec3::new(0.0, 1.0, 0.0)));
+    assert!(close(hue(2.0 / 3.0), Vec3::new(0.0, 0.0, 1.0)));
+    assert!(close(hue(1.0 / 6.0), Vec3::new(1.0, 1.0, 0.0)));
+    assert!(close(hue(1.25), hue(0.25)));
+}
+
+#[test]
+fn viridis_goes_from_purple_to_yellow() {
+    assert!(close(viridis(0.0), Vec3::new(0.267, 0.005, 0.329)));
+    assert!(close(viridis(1.0), Vec3::new(0.993, 0.906, 0.144)));
+}
+
+#[test]
+fn swatches_only_depend_on_the_seed() {
+    for (variant, name) in PALETTES.iter().enumerate() {
+        let palette = Palette::from_variant(variant);
+        let swatches = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..16)
+                .map(|_| palette.swatch(&mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(swatches(7), swatches(7), "{name}");
+        assert_ne!(swatches(7), swatches(8), "{name}");
+    }
+}
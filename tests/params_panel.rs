@@ -0,0 +1,15 @@
+use opengl_playground::params::Params;
+use opengl_playground::params_panel::format_value;
+
+#[test]
+fn values_show_as_many_decimals_as_their_step() {
+    let params = Params::new()
+        .with_f32("radius", 1.5, 0.0, 10.0, 0.25)
+        .with_f32("speed", 3.0, 0.0, 10.0, 1.0)
+        .with_i32("layers", 4, 1, 8)
+        .with_bool("dither", true)
+        .with_enum("mode", 1, &["box", "gaussian"]);
+
+    let shown = params.iter().map(format_value).collect::<Vec<_>>();
+    assert_eq!(shown, ["1.50", "3", "4", "true", "gaussian"]);
+}
@@ -0,0 +1,69 @@
+use glam::uvec2;
+use opengl_playground::common_gl::{Program, VertexArray};
+use opengl_playground::pipeline_stats::{PipelineStats, Stats};
+
+mod common;
+
+const VERT: &[u8] = b"#version 330 core
+void main() {
+    // two triangles over the whole target
+    vec2 corners[6] = vec2[](vec2(-1, -1), vec2(1, -1), vec2(1, 1), vec2(-1, -1), vec2(1, 1), vec2(-1, 1));
+    gl_Position = vec4(corners[gl_VertexID], 0.0, 1.0);
+}
+";
+
+const FRAG: &[u8] = b"#version 330 core
+out vec4 FragColor;
+void main() {
+    FragColor = vec4(1.0);
+}
+";
+
+#[test]
+fn stats_print_with_metric_suffixes() {
+    let stats = Stats {
+        vertices: 6,
+        primitives: 2,
+        fragments: 2_070_000,
+        samples: 12_345,
+    };
+    assert_eq!(
+        stats.to_string(),
+        "6 vertices, 2 primitives, 2.07M fragments, 12.3k samples"
+    );
+}
+
+#[test]
+fn passes_count_the_work_they_give_the_gpu() {
+    let Some(_headless) = common::headless(uvec2(64, 32)) else {
+        return;
+    };
+    let Some(mut stats) = (unsafe { PipelineStats::new() }) else {
+        eprintln!("Skipping pipeline stats test, no pipeline statistics queries");
+        return;
+    };
+
+    let program = Program::new(VERT, FRAG).unwrap();
+    let vao = VertexArray::new();
+    program.bind();
+    vao.bind();
+    for _ in 0..3 {
+        unsafe {
+            stats.begin("quad");
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            stats.end();
+        }
+    }
+    unsafe {
+        gl::Finish();
+        stats.poll();
+    }
+
+    let averages = stats.take_averages();
+    let quad = averages["quad"];
+    assert_eq!((quad.vertices, quad.primitives), (6, 2));
+    assert_eq!(quad.samples, 64 * 32);
+    // the quads of fragments along the diagonal may shade some twice
+    assert!(quad.fragments >= 64 * 32);
+    assert!(stats.take_averages().is_empty());
+}
@@ -0,0 +1,61 @@
+use glam::{ivec2, uvec2};
+use opengl_playground::placement::{self, Monitor};
+
+const LEFT: Monitor = Monitor {
+    position: ivec2(0, 0),
+    size: uvec2(1920, 1080),
+};
+const RIGHT: Monitor = Monitor {
+    position: ivec2(1920, -200),
+    size: uvec2(3840, 2160),
+};
+
+#[test]
+fn positions_parse() {
+    assert_eq!(placement::parse_position("100,-20"), Ok(ivec2(100, -20)));
+    assert_eq!(placement::parse_position(" 5 , 7"), Ok(ivec2(5, 7)));
+    for invalid in ["100", "1.5,2", "a,b", ""] {
+        assert!(placement::parse_position(invalid).is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn sizes_parse() {
+    assert_eq!(placement::parse_size("1280x720"), Ok(uvec2(1280, 720)));
+    for invalid in ["1280", "0x720", "-1x2", "axb", ""] {
+        assert!(placement::parse_size(invalid).is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn windows_are_placed_on_their_monitor() {
+    assert_eq!(RIGHT.place(uvec2(800, 600), None), ivec2(3440, 580));
+    assert_eq!(
+        RIGHT.place(uvec2(800, 600), Some(ivec2(10, 20))),
+        ivec2(1930, -180)
+    );
+    // too large to center, the corner stays on the monitor
+    assert_eq!(LEFT.place(uvec2(2560, 1440), None), ivec2(0, 0));
+}
+
+#[test]
+fn windows_keep_their_place_across_monitors() {
+    let window = uvec2(800, 600);
+    assert_eq!(
+        RIGHT.carry_over(&LEFT, ivec2(960, 540), window),
+        ivec2(1920 + 1920, -200 + 1080)
+    );
+    // near the far corner of a large monitor, it's kept fully on a small one
+    assert_eq!(
+        LEFT.carry_over(&RIGHT, ivec2(1920 + 3700, 2000), window),
+        ivec2(1120, 480)
+    );
+}
+
+#[test]
+fn the_next_monitor_wraps_around() {
+    assert_eq!(placement::next(Some(0), 2), 1);
+    assert_eq!(placement::next(Some(1), 2), 0);
+    assert_eq!(placement::next(None, 3), 0);
+    assert_eq!(placement::next(Some(0), 1), 0);
+}
@@ -0,0 +1,129 @@
+//! Drives a plugin scene linked into the test through the same vtable a
+//! shared library would export, with the plugins feature.
+#![cfg(feature = "plugins")]
+
+use std::ffi::{c_char, c_void};
+use std::path::Path;
+use std::ptr;
+
+use glam::{Mat4, UVec2, Vec2};
+use opengl_playground::camera::Camera;
+use opengl_playground::common_gl::read_pixels;
+use opengl_playground::input_image::{InputImage, DEFAULT_MAX_DIMENSION};
+use opengl_playground::plugin::{self, CreateInfo, Host, Plugin, Scene, SceneVTable};
+use opengl_playground::scenes::{SceneContext, Scenes};
+
+mod common;
+
+/// Clears to green, or red after the space key.
+struct Clear {
+    red: bool,
+}
+
+impl Scene for Clear {
+    fn new(_info: &CreateInfo) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self { red: false })
+    }
+
+    fn draw(&mut self, _matrix: &Mat4, _mouse: Vec2) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            match self.red {
+                true => gl::ClearColor(1.0, 0.0, 0.0, 1.0),
+                false => gl::ClearColor(0.0, 1.0, 0.0, 1.0),
+            }
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+
+    fn on_key(&mut self, key: &str) {
+        if key == " " {
+            self.red = !self.red;
+        }
+    }
+}
+
+opengl_playground::export_scene!(Clear, c"clear", c"Clears the screen.");
+
+#[test]
+fn plugin_scenes_join_the_cycle_and_render() {
+    let size = UVec2::new(64, 48);
+    let Some(headless) = common::headless(size) else {
+        return;
+    };
+
+    let plugin = unsafe { Plugin::from_entry(playground_plugin_v1) }.unwrap();
+    assert_eq!(plugin.name, "clear");
+    assert_eq!(plugin.description, "Clears the screen.");
+    plugin::register(plugin, &Scenes::builtin_names()).unwrap();
+    assert_eq!(Scenes::names().last(), Some(&"clear"));
+    assert_eq!(Scenes::title("clear"), "clear");
+
+    let input = InputImage::gura(DEFAULT_MAX_DIMENSION);
+    let ctx = SceneContext {
+        size: headless.physical_size(),
+        input: &input,
+        data: None,
+        seed: 0,
+        text: None,
+    };
+    let mut scenes = Scenes::from_name("clear", &ctx).unwrap();
+    assert_eq!(scenes.name(), "clear");
+
+    let camera = Camera::default();
+    let center = |scenes: &mut Scenes| {
+        scenes.resize(&camera, size.x as i32, size.y as i32);
+        scenes.draw(&camera, size.as_vec2() / 2.0);
+        let pixels = unsafe { read_pixels(size.x, size.y) };
+        let i = ((size.y / 2 * size.x + size.x / 2) * 4) as usize;
+        [pixels[i], pixels[i + 1], pixels[i + 2]]
+    };
+
+    assert_eq!(center(&mut scenes), [0, 255, 0]);
+    scenes.on_key(winit::keyboard::Key::Character(" ".into()));
+    assert_eq!(center(&mut scenes), [255, 0, 0]);
+    assert_eq!(unsafe { gl::GetError() }, gl::NO_ERROR);
+}
+
+#[test]
+fn scene_names_are_unique() {
+    let plugin = || unsafe { Plugin::from_entry(playground_plugin_v1) }.unwrap();
+    plugin::register(plugin(), &Scenes::builtin_names()).unwrap();
+    assert!(plugin::register(plugin(), &Scenes::builtin_names()).is_err());
+    assert!(plugin::register(plugin(), &["clear"]).is_err());
+}
+
+unsafe extern "C" fn create(_host: *const Host, _info: *const CreateInfo) -> *mut c_void {
+    ptr::null_mut()
+}
+unsafe extern "C" fn destroy(_scene: *mut c_void) {}
+unsafe extern "C" fn resize(_scene: *mut c_void, _width: i32, _height: i32) {}
+unsafe extern "C" fn update(_scene: *mut c_void, _dt: f32) {}
+unsafe extern "C" fn draw(_scene: *mut c_void, _matrix: *const f32, _x: f32, _y: f32) {}
+unsafe extern "C" fn on_key(_scene: *mut c_void, _key: *const c_char) {}
+
+unsafe extern "C" fn future_entry() -> *const SceneVTable {
+    static VTABLE: SceneVTable = SceneVTable {
+        abi_version: plugin::ABI_VERSION + 1,
+        name: c"future".as_ptr(),
+        description: c"".as_ptr(),
+        create,
+        destroy,
+        resize,
+        update,
+        draw,
+        on_key,
+    };
+    &VTABLE
+}
+
+#[test]
+fn other_abi_versions_and_missing_libraries_are_rejected() {
+    let error = unsafe { Plugin::from_entry(future_entry) }.err().unwrap();
+    assert!(error.contains("ABI version"), "{error}");
+
+    let error = unsafe { Plugin::load(Path::new("no-such-plugin.so")) }
+        .err()
+        .unwrap();
+    assert!(error.contains("no-such-plugin.so"), "{error}");
+}
@@ -0,0 +1,105 @@
+use opengl_playground::{
+    blue_noise,
+    params::Value,
+    post::{PassKind, PostStack},
+};
+
+fn order(post: &PostStack) -> Vec<PassKind> {
+    post.passes().iter().map(|pass| pass.kind).collect()
+}
+
+#[test]
+fn passes_move_within_the_stack() {
+    let mut post = PostStack::new();
+    assert!(!post.is_active());
+    assert_eq!(post.selected(), PassKind::Vignette);
+
+    post.move_selected(1);
+    assert_eq!(
+        order(&post),
+        [
+            PassKind::Grain,
+            PassKind::Vignette,
+            PassKind::Sharpen,
+            PassKind::Edges,
+            PassKind::Kuwahara
+        ]
+    );
+    // the selection follows the pass
+    assert_eq!(post.selected(), PassKind::Vignette);
+
+    post.move_selected(5);
+    assert_eq!(
+        order(&post),
+        [
+            PassKind::Grain,
+            PassKind::Sharpen,
+            PassKind::Edges,
+            PassKind::Kuwahara,
+            PassKind::Vignette
+        ]
+    );
+
+    assert_eq!(post.select_next(), PassKind::Grain);
+    assert!(post.toggle_selected());
+    assert!(post.is_active());
+    post.move_selected(-1);
+    assert_eq!(post.passes()[0].kind, PassKind::Grain);
+    assert!(post.passes()[0].enabled);
+}
+
+#[test]
+fn pass_params_are_set_by_kind() {
+    let mut post = PostStack::new();
+    post.move_selected(2);
+    let params = post.params_mut(PassKind::Vignette);
+    params.set("strength", Value::Number(2.0)).unwrap();
+    assert_eq!(post.params(PassKind::Vignette).f32("strength"), 1.0);
+    assert_eq!(PassKind::from_name("sharpen"), Some(PassKind::Sharpen));
+
+    let edges = post.params_mut(PassKind::Edges);
+    edges.set("operator", Value::Text("scharr".into())).unwrap();
+    assert_eq!(edges.variant("operator"), 1);
+}
+
+#[test]
+fn blue_noise_uses_every_value_equally() {
+    let noise = blue_noise::generate(32, 1);
+    let mut counts = [0; 256];
+    for value in noise {
+        counts[value as usize] += 1;
+    }
+    assert!(counts.iter().all(|&count| count == 4));
+}
+
+#[test]
+fn blue_noise_has_less_low_frequencies_than_white_noise() {
+    const SIZE: usize = 32;
+
+    // energy left after a 3x3 box blur, which only lets low frequencies through
+    let low_frequencies = |noise: &[u8]| {
+        let at = |x: usize, y: usize| noise[(y % SIZE) * SIZE + x % SIZE] as f32 - 127.5;
+        let mut energy = 0.0;
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let mut sum = 0.0;
+                for (dx, dy) in (0..3).flat_map(|dx| (0..3).map(move |dy| (dx, dy))) {
+                    sum += at(x + SIZE + dx - 1, y + SIZE + dy - 1);
+                }
+                energy += (sum / 9.0) * (sum / 9.0);
+            }
+        }
+        energy
+    };
+
+    // the same values shuffled by a simple LCG
+    let blue = blue_noise::generate(SIZE, 1);
+    let mut white = blue.clone();
+    let mut state = 12345u32;
+    for i in (1..white.len()).rev() {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        white.swap(i, (state >> 8) as usize % (i + 1));
+    }
+
+    assert!(low_frequencies(&blue) < low_frequencies(&white) * 0.5);
+}
@@ -0,0 +1,81 @@
+use glam::{uvec2, vec2};
+use opengl_playground::present::PresentMode;
+
+#[test]
+fn modes_parse() {
+    assert_eq!("free".parse(), Ok(PresentMode::Free));
+    assert_eq!(
+        "fixed:320x180".parse(),
+        Ok(PresentMode::Fixed(uvec2(320, 180)))
+    );
+    assert_eq!(
+        "integer:256x224".parse(),
+        Ok(PresentMode::Integer(uvec2(256, 224)))
+    );
+
+    for mode in [
+        "fixed",
+        "fixed:320",
+        "integer:0x180",
+        "stretch:320x180",
+        "fixed:ax2",
+    ] {
+        assert!(mode.parse::<PresentMode>().is_err(), "{mode}");
+    }
+    for mode in ["free", "fixed:320x180", "integer:256x224"] {
+        assert_eq!(mode.parse::<PresentMode>().unwrap().to_string(), mode);
+    }
+}
+
+#[test]
+fn free_covers_the_window() {
+    let layout = PresentMode::Free.layout(uvec2(1280, 720));
+    assert!(layout.is_full());
+    assert_eq!(layout.to_scene(vec2(17.0, 42.0)), vec2(17.0, 42.0));
+}
+
+#[test]
+fn fixed_scales_to_fit_with_bars() {
+    // wider than 16:9, bars on the sides
+    let layout = PresentMode::Fixed(uvec2(320, 180)).layout(uvec2(1920, 900));
+    assert_eq!(layout.size, uvec2(320, 180));
+    assert_eq!(layout.shown, uvec2(1600, 900));
+    assert_eq!(layout.min, uvec2(160, 0));
+
+    // the corners of the window inside the bars are the corners of the scene
+    assert_eq!(layout.to_scene(vec2(160.0, 0.0)), vec2(0.0, 0.0));
+    assert_eq!(layout.to_scene(vec2(1760.0, 900.0)), vec2(320.0, 180.0));
+    assert_eq!(layout.ratio(), vec2(0.2, 0.2));
+}
+
+#[test]
+fn integer_scales_by_whole_pixels() {
+    let mode = PresentMode::Integer(uvec2(320, 180));
+
+    // 4.5 times as wide and 4 times as high, so 4 times in both
+    let layout = mode.layout(uvec2(1440, 720));
+    assert_eq!(layout.shown, uvec2(1280, 720));
+    assert_eq!(layout.min, uvec2(80, 0));
+    assert_eq!(layout.to_scene(vec2(84.0, 8.0)), vec2(1.0, 2.0));
+
+    // just short of twice as large is shown once, in the middle
+    let layout = mode.layout(uvec2(639, 359));
+    assert_eq!(layout.shown, uvec2(320, 180));
+    assert_eq!(layout.min, uvec2(159, 89));
+
+    // exactly the resolution is the same as drawing over the window
+    assert!(mode.layout(uvec2(320, 180)).is_full());
+}
+
+#[test]
+fn resolutions_larger_than_the_window_draw_at_what_fits() {
+    let layout = PresentMode::Fixed(uvec2(3840, 2160)).layout(uvec2(1280, 1024));
+    assert_eq!(layout.shown, uvec2(1280, 720));
+    assert_eq!(layout.size, layout.shown);
+    assert_eq!(layout.min, uvec2(0, 152));
+
+    let layout = PresentMode::Integer(uvec2(640, 480)).layout(uvec2(320, 600));
+    assert_eq!(layout.shown, uvec2(320, 240));
+    assert_eq!(layout.size, layout.shown);
+    assert_eq!(layout.min, uvec2(0, 180));
+}
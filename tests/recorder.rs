@@ -0,0 +1,49 @@
+use glam::uvec2;
+use opengl_playground::recorder::{Format, Recording};
+use opengl_playground::theme;
+
+mod common;
+
+#[test]
+fn formats_parse_and_print() {
+    assert_eq!("png".parse(), Ok(Format::Png));
+    assert_eq!("ffmpeg".parse(), Ok(Format::Ffmpeg));
+    assert!("gif".parse::<Format>().is_err());
+    assert_eq!(Format::Ffmpeg.to_string(), "ffmpeg");
+    assert_eq!(Format::Ffmpeg.extension(), "mp4");
+}
+
+#[test]
+fn every_frame_is_recorded_top_row_first() {
+    let Some(headless) = common::headless(uvec2(48, 32)) else {
+        return;
+    };
+    let size = headless.size();
+
+    let dir = std::env::temp_dir().join(format!("recorder-test-{}", std::process::id()));
+    let mut recording = Recording::start(Format::Png, &dir, size).unwrap();
+    for frame in 0..5 {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear_with([frame as f32 / 4.0, 0.0, 0.0, 1.0]);
+            // the top row, which GL reads last
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(0, size.y as i32 - 1, size.x as i32, 1);
+            theme::clear_with([0.0, 0.0, 1.0, 1.0]);
+            gl::Disable(gl::SCISSOR_TEST);
+            assert!(recording.capture());
+        }
+    }
+    let frames = unsafe { recording.finish() }.unwrap();
+
+    let saved = std::fs::read_dir(&dir).unwrap().count();
+    let first = image::open(dir.join("frame-00000.png")).unwrap().to_rgba8();
+    let last = image::open(dir.join("frame-00004.png")).unwrap().to_rgba8();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!((frames, saved), (5, 5));
+    assert_eq!(first.dimensions(), (48, 32));
+    assert_eq!(first.get_pixel(0, 0).0, [0, 0, 255, 255]);
+    assert_eq!(first.get_pixel(0, 1).0, [0, 0, 0, 255]);
+    assert_eq!(last.get_pixel(0, 31).0, [255, 0, 0, 255]);
+}
@@ -0,0 +1,78 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+
+use opengl_playground::remote::{
+    is_within, screenshot_path, scripts_dir, RemoteServer, SCREENSHOT_DIR,
+};
+
+#[test]
+fn only_this_machine_is_listened_to_by_default() {
+    let server = RemoteServer::start(0, false).unwrap();
+    assert!(server.addr().ip().is_loopback());
+}
+
+#[test]
+fn screenshots_stay_in_their_directory() {
+    let server = RemoteServer::start(0, false).unwrap();
+    let stream = TcpStream::connect(server.addr()).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+    let mut writer = stream;
+    for path in [
+        "../escaped.png",
+        "/tmp/escaped.png",
+        "src/escaped.png",
+        "..",
+    ] {
+        let command = format!("{{\"cmd\": \"screenshot\", \"path\": \"{path}\"}}\n");
+        writer.write_all(command.as_bytes()).unwrap();
+        let mut response = String::new();
+        reader.read_line(&mut response).unwrap();
+        assert!(response.contains("\"ok\":false"), "{path}: {response}");
+    }
+}
+
+#[test]
+fn screenshots_are_pngs() {
+    let dir = std::env::current_dir().unwrap().join(SCREENSHOT_DIR);
+    assert_eq!(screenshot_path(Some("shot")), Ok(dir.join("shot.png")));
+    assert_eq!(screenshot_path(Some("shot.rs")), Ok(dir.join("shot.png")));
+    let generated = screenshot_path(None).unwrap();
+    assert!(generated.starts_with(&dir));
+    assert_eq!(generated.extension(), Some("png".as_ref()));
+}
+
+#[test]
+fn scripts_only_run_from_the_scripts_directory() {
+    let script = |lan, path: &str| {
+        let server = RemoteServer::start(0, lan).unwrap();
+        let stream = TcpStream::connect(server.addr()).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        let command = format!("{{\"cmd\": \"run_script\", \"path\": \"{path}\"}}\n");
+        writer.write_all(command.as_bytes()).unwrap();
+
+        // accepted commands wait for the main thread, which isn't there,
+        // until the server is gone
+        drop(server);
+        let mut response = String::new();
+        reader.read_line(&mut response).unwrap();
+        response
+    };
+
+    let inside = scripts_dir().join("pulse.rhai");
+    let inside = inside.to_str().unwrap();
+    assert!(script(false, "Cargo.toml").contains("scripts must be in"));
+    assert!(script(false, inside).is_empty());
+    assert!(script(true, inside).contains("--remote-lan"));
+}
+
+#[test]
+fn paths_are_resolved_before_being_checked() {
+    let dir = std::env::current_dir().unwrap();
+    assert!(is_within("shot.png".as_ref(), &dir));
+    assert!(is_within("src/../shot.png".as_ref(), &dir));
+    assert!(!is_within("src/../../shot.png".as_ref(), &dir));
+    assert!(!is_within("missing/shot.png".as_ref(), &dir));
+    assert!(!is_within("..".as_ref(), &dir));
+}
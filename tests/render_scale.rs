@@ -0,0 +1,162 @@
+use glam::{uvec2, vec2, UVec2, Vec2};
+use opengl_playground::camera::Camera;
+use opengl_playground::common_gl::read_pixels;
+use opengl_playground::render_scale::{jitter, parse_scale, RenderScale, Upscale};
+
+mod common;
+
+#[test]
+fn scales_parse() {
+    assert_eq!(parse_scale("50"), Ok(0.5));
+    assert_eq!(parse_scale("150%"), Ok(1.5));
+    for scale in ["49", "201", "half", "-50", ""] {
+        assert!(parse_scale(scale).is_err(), "{scale}");
+    }
+
+    assert_eq!("temporal".parse(), Ok(Upscale::Temporal));
+    assert!("nearest".parse::<Upscale>().is_err());
+}
+
+#[test]
+fn jitter_stays_in_the_texel() {
+    let jitters = (0..8).map(jitter).collect::<Vec<_>>();
+    for j in &jitters {
+        assert!(j.abs().max_element() < 0.5, "{j}");
+    }
+    // every frame of the cycle lands somewhere else, then it repeats
+    for (i, a) in jitters.iter().enumerate() {
+        assert!(jitters[i + 1..].iter().all(|b| a != b));
+    }
+    assert_eq!(jitter(8), jitter(0));
+}
+
+#[test]
+fn a_region_sees_what_the_full_view_does() {
+    let camera = Camera {
+        position: vec2(30.0, -12.0),
+        rotation: 0.4,
+        scale: Vec2::splat(1.5),
+    };
+    let size = uvec2(640, 360);
+    let render_scale = RenderScale::new(0.5, Upscale::Bilinear);
+    let regions = render_scale.regions(&camera, size, size, Vec2::ZERO);
+    assert_eq!(regions.len(), 1);
+    let region = &regions[0];
+    assert_eq!(region.size, uvec2(320, 180));
+
+    for pos in [vec2(0.0, 0.0), vec2(100.0, 300.0), vec2(639.0, 17.0)] {
+        let seen = region
+            .camera
+            .pointer_to_pos(region.pointer(pos), region.size.as_vec2());
+        let full = camera.pointer_to_pos(pos, size.as_vec2());
+        assert!(seen.distance(full) < 1e-3, "{seen} != {full}");
+    }
+}
+
+#[test]
+fn supersampling_past_the_framebuffer_draws_tiles() {
+    let camera = Camera {
+        rotation: -1.1,
+        ..Camera::default()
+    };
+    let size = uvec2(300, 200);
+    let render_scale = RenderScale::new(2.0, Upscale::Bilinear);
+    let regions = render_scale.regions(&camera, size, size, Vec2::ZERO);
+    assert_eq!(regions.len(), 4);
+    assert!(regions.iter().all(|region| region.size == size));
+
+    // wherever the pointer is, the tile under it sees the same thing
+    for pos in [vec2(10.0, 10.0), vec2(290.0, 20.0), vec2(160.0, 190.0)] {
+        let scaled = pos * 2.0;
+        let region = (regions.iter())
+            .find(|r| {
+                let min = r.min.as_vec2();
+                scaled.cmpge(min).all() && scaled.cmplt(min + r.size.as_vec2()).all()
+            })
+            .unwrap();
+        let seen = region
+            .camera
+            .pointer_to_pos(region.pointer(pos), region.size.as_vec2());
+        let full = camera.pointer_to_pos(pos, size.as_vec2());
+        assert!(seen.distance(full) < 1e-3, "{seen} != {full}");
+    }
+}
+
+#[test]
+fn tiles_land_where_they_belong() {
+    const SIZE: UVec2 = UVec2::new(64, 64);
+    let Some(_headless) = common::headless(SIZE) else {
+        return;
+    };
+
+    // a color per quadrant of the supersampled image
+    let color = |min: UVec2| match (min.x > 0, min.y > 0) {
+        (false, false) => [255, 0, 0],
+        (true, false) => [0, 255, 0],
+        (false, true) => [0, 0, 255],
+        (true, true) => [255, 255, 0],
+    };
+    let mut render_scale = RenderScale::new(2.0, Upscale::Bilinear);
+    let mut drawn = 0;
+    let result = unsafe {
+        render_scale.draw(SIZE, SIZE, &Camera::default(), |region| {
+            let [r, g, b] = color(region.min).map(|c| c as f32 / 255.0);
+            gl::Viewport(0, 0, region.size.x as i32, region.size.y as i32);
+            gl::ClearColor(r, g, b, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            drawn += 1;
+        })
+    };
+    result.unwrap();
+    assert_eq!(drawn, 4);
+
+    let pixels = unsafe {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        read_pixels(SIZE.x, SIZE.y)
+    };
+    for (at, min) in [
+        (uvec2(10, 10), uvec2(0, 0)),
+        (uvec2(50, 10), uvec2(64, 0)),
+        (uvec2(10, 50), uvec2(0, 64)),
+        (uvec2(50, 50), uvec2(64, 64)),
+    ] {
+        let i = ((at.y * SIZE.x + at.x) * 4) as usize;
+        assert_eq!(pixels[i..i + 3], color(min), "at {at}");
+    }
+}
+
+#[test]
+fn upscaling_keeps_flat_colors() {
+    const SIZE: UVec2 = UVec2::new(64, 48);
+    let Some(_headless) = common::headless(SIZE) else {
+        return;
+    };
+
+    for upscale in Upscale::ALL {
+        let mut render_scale = RenderScale::new(0.5, upscale);
+        // a few frames, for the history to be used
+        for _ in 0..3 {
+            let result = unsafe {
+                render_scale.draw(SIZE, SIZE, &Camera::default(), |region| {
+                    assert_eq!(region.size, SIZE / 2);
+                    gl::Viewport(0, 0, region.size.x as i32, region.size.y as i32);
+                    gl::ClearColor(0.2, 0.6, 0.4, 1.0);
+                    gl::Clear(gl::COLOR_BUFFER_BIT);
+                })
+            };
+            result.unwrap();
+        }
+
+        let pixels = unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            read_pixels(SIZE.x, SIZE.y)
+        };
+        for pixel in pixels.chunks_exact(4) {
+            let diff = (pixel.iter().zip([51u8, 153, 102, 255]))
+                .map(|(&a, b)| a.abs_diff(b))
+                .max()
+                .unwrap();
+            assert!(diff <= 1, "{upscale}: {pixel:?}");
+        }
+    }
+}
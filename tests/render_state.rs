@@ -0,0 +1,67 @@
+//! Debug polygon modes and normals, on a binary of their own since they are
+//! global and would leak into the scene smoke tests.
+
+use gl::types::{GLenum, GLint};
+use glam::UVec2;
+use opengl_playground::camera::Camera;
+use opengl_playground::input_image::{InputImage, DEFAULT_MAX_DIMENSION};
+use opengl_playground::render_state::{self, PolygonMode};
+use opengl_playground::scenes::{SceneContext, Scenes};
+
+mod common;
+
+#[test]
+fn polygon_modes_cycle_back_to_fill() {
+    let mut mode = PolygonMode::default();
+    let mut names = Vec::new();
+    for _ in 0..4 {
+        names.push(mode.name());
+        mode = mode.next();
+    }
+    assert_eq!(names, ["fill", "line", "point", "fill"]);
+}
+
+#[test]
+fn every_scene_draws_in_every_polygon_mode() {
+    let size = UVec2::new(320, 240);
+    let Some(headless) = common::headless(size) else {
+        return;
+    };
+
+    let input = InputImage::gura(DEFAULT_MAX_DIMENSION);
+    let ctx = SceneContext {
+        size: headless.physical_size(),
+        input: &input,
+        data: None,
+        seed: 0,
+        text: None,
+    };
+    let camera = Camera::default();
+    render_state::set_normals(true);
+
+    for name in Scenes::builtin_names() {
+        let mut scenes = Scenes::from_name(name, &ctx).unwrap();
+        scenes.resize(&camera, size.x as i32, size.y as i32);
+
+        for mode in [PolygonMode::Line, PolygonMode::Point] {
+            render_state::set_polygon_mode(mode);
+            scenes.update(1.0 / 60.0);
+            scenes.draw(&camera, size.as_vec2() / 2.0);
+
+            let mut modes: [GLint; 2] = [0; 2];
+            let mut point_size = 0.0;
+            let error = unsafe {
+                gl::GetIntegerv(gl::POLYGON_MODE, modes.as_mut_ptr());
+                gl::GetFloatv(gl::POINT_SIZE, &mut point_size);
+                gl::GetError()
+            };
+            assert_eq!(scenes.error(), None, "{name} in {mode:?}");
+            assert_eq!(error, gl::NO_ERROR, "{name} in {mode:?}");
+            assert_eq!(modes[0] as GLenum, gl::FILL, "{name} left {mode:?} on");
+            assert_eq!(point_size, 1.0, "{name} left the point size of {mode:?}");
+        }
+    }
+
+    render_state::set_polygon_mode(PolygonMode::Fill);
+    render_state::set_normals(false);
+}
@@ -0,0 +1,58 @@
+use glam::uvec2;
+use opengl_playground::replay::{self, ReplayBuffer, FPS, MAX_SIDE};
+use opengl_playground::theme;
+
+mod common;
+
+#[test]
+fn frames_keep_the_aspect_ratio_within_the_largest_side() {
+    assert_eq!(ReplayBuffer::frame_size(uvec2(1920, 1080)), uvec2(360, 203));
+    assert_eq!(ReplayBuffer::frame_size(uvec2(600, 1200)), uvec2(180, 360));
+    // small windows aren't upscaled
+    assert_eq!(ReplayBuffer::frame_size(uvec2(64, 32)), uvec2(64, 32));
+    assert_eq!(ReplayBuffer::frame_size(uvec2(0, 0)), uvec2(1, 1));
+    assert!(ReplayBuffer::frame_size(uvec2(10000, 3)).max_element() <= MAX_SIDE);
+}
+
+#[test]
+fn only_the_last_seconds_are_kept_and_saved() {
+    let Some(headless) = common::headless(uvec2(64, 32)) else {
+        return;
+    };
+    let size = headless.size();
+
+    let mut replay = ReplayBuffer::new(1.0);
+    let dt = 1.0 / 60.0;
+    for frame in 0..180 {
+        let shade = frame as f32 / 180.0;
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            theme::clear_with([shade, 0.5, 1.0 - shade, 1.0]);
+            replay.capture(size, dt).unwrap();
+        }
+    }
+    unsafe { replay.flush() };
+
+    // 3 seconds went by, a bit more than one second of captures is left
+    let frames: Vec<_> = replay.frames().iter().cloned().collect();
+    assert!((FPS as usize..=FPS as usize + 2).contains(&frames.len()));
+    assert!(frames.windows(2).all(|w| w[0].time < w[1].time));
+    assert!(frames[0].time >= 2.0 - dt);
+    assert!(frames.iter().all(|f| f.size == uvec2(64, 32)));
+    // the red channel follows the clear color, the last capture the reddest
+    let red = |frame: &replay::Frame| frame.pixels[0];
+    assert!(red(&frames[0]) < red(frames.last().unwrap()));
+    assert!(red(frames.last().unwrap()) > 240);
+
+    let dir = std::env::temp_dir().join(format!("replay-test-{}", std::process::id()));
+    replay::save_sequence(&dir, &frames).unwrap();
+    let saved = std::fs::read_dir(&dir).unwrap().count();
+    let first = image::open(dir.join("frame-0000.png")).unwrap().to_rgba8();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(saved, frames.len());
+    assert_eq!(first.dimensions(), (64, 32));
+    assert_eq!(first.get_pixel(0, 0).0[0], red(&frames[0]));
+
+    unsafe { replay.delete() };
+}
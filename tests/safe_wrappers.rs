@@ -0,0 +1,77 @@
+use glam::uvec2;
+use opengl_playground::common_gl::{Buffer, IndexBuffer, Program, RenderTarget, VertexArray};
+use opengl_playground::gpu_memory::{self, Category};
+
+mod common;
+
+#[test]
+fn dropped_render_targets_give_their_memory_back() {
+    let Some(_headless) = common::headless(uvec2(1, 1)) else {
+        return;
+    };
+
+    let before = gpu_memory::allocated(Category::Framebuffers);
+    let target = RenderTarget::new("test", uvec2(64, 32)).unwrap();
+    let float = RenderTarget::new_float("test float", uvec2(16, 16), gl::RGBA16F).unwrap();
+    assert_eq!(
+        gpu_memory::allocated(Category::Framebuffers) - before,
+        64 * 32 * 4 + 16 * 16 * 8
+    );
+
+    drop((target, float));
+    assert_eq!(gpu_memory::allocated(Category::Framebuffers), before);
+}
+
+#[test]
+fn buffers_are_updated_from_any_item() {
+    let Some(_headless) = common::headless(uvec2(1, 1)) else {
+        return;
+    };
+
+    let buffer = Buffer::new(gl::ARRAY_BUFFER, &[0u32; 6], gl::DYNAMIC_DRAW);
+    buffer.update_at(2, &[7, 8, 9]);
+    buffer.update(&[1]);
+
+    let mut items = [0u32; 6];
+    buffer.bind();
+    unsafe {
+        gl::GetBufferSubData(
+            gl::ARRAY_BUFFER,
+            0,
+            std::mem::size_of_val(&items) as isize,
+            items.as_mut_ptr() as *mut _,
+        );
+    }
+    assert_eq!(items, [1, 0, 7, 8, 9, 0]);
+}
+
+#[test]
+#[should_panic(expected = "don't fit")]
+fn updates_past_the_end_panic() {
+    let Some(_headless) = common::headless(uvec2(1, 1)) else {
+        panic!("don't fit");
+    };
+
+    let buffer = Buffer::new(gl::ARRAY_BUFFER, &[0u32; 4], gl::DYNAMIC_DRAW);
+    buffer.update_at(3, &[1, 2]);
+}
+
+#[test]
+#[should_panic(expected = "past the 3 vertices")]
+fn indices_past_the_vertices_panic() {
+    let Some(_headless) = common::headless(uvec2(1, 1)) else {
+        panic!("past the 3 vertices");
+    };
+
+    let program = Program::new(
+        b"#version 330 core\nvoid main() { gl_Position = vec4(0.0); }\n",
+        b"#version 330 core\nout vec4 color;\nvoid main() { color = vec4(1.0); }\n",
+    )
+    .unwrap();
+    let vertices = Buffer::new(gl::ARRAY_BUFFER, &[0f32; 3], gl::STATIC_DRAW);
+    let mut vao = VertexArray::new();
+    vao.set_layout(&program, &vertices, &[]);
+
+    let indices = IndexBuffer::new(&[0, 1, 3], gl::STATIC_DRAW);
+    vao.draw_elements(gl::TRIANGLES, &indices);
+}
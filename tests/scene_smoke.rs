@@ -0,0 +1,128 @@
+//! Creates every scene on a headless context and renders a few frames at a
+//! couple of sizes, so that changes to `common_gl` can't silently break one.
+
+use gl::types::GLenum;
+use glam::{UVec2, Vec2};
+use opengl_playground::camera::Camera;
+use opengl_playground::input_image::{InputImage, DEFAULT_MAX_DIMENSION};
+use opengl_playground::render_state::{Blend, RenderState};
+use opengl_playground::scenes::{SceneContext, Scenes};
+use opengl_playground::text::TextRenderer;
+
+mod common;
+
+const SIZES: [UVec2; 2] = [UVec2::new(320, 240), UVec2::new(1280, 720)];
+const FRAMES: usize = 3;
+
+fn gl_errors() -> Vec<GLenum> {
+    let mut errors = Vec::new();
+    loop {
+        match unsafe { gl::GetError() } {
+            gl::NO_ERROR => return errors,
+            error => errors.push(error),
+        }
+    }
+}
+
+#[test]
+fn every_scene_renders_without_gl_errors() {
+    let input = InputImage::gura(DEFAULT_MAX_DIMENSION);
+
+    for size in SIZES {
+        let Some(headless) = common::headless(size) else {
+            return;
+        };
+
+        // for the scenes that label what they draw
+        let text = unsafe { TextRenderer::shared() }.unwrap();
+        let ctx = SceneContext {
+            size: headless.physical_size(),
+            input: &input,
+            data: None,
+            seed: 0,
+            text: Some(&text),
+        };
+        let camera = Camera::default();
+        let center = size.as_vec2() / 2.0;
+
+        for name in Scenes::builtin_names() {
+            let mut scenes = Scenes::from_name(name, &ctx).unwrap();
+            assert_eq!(scenes.error(), None, "{name} at {size}: failed to create");
+            assert_eq!(
+                gl_errors(),
+                [0; 0],
+                "{name} at {size}: GL errors on creation"
+            );
+
+            for frame in 0..FRAMES {
+                scenes.resize(&camera, size.x as i32, size.y as i32);
+                scenes.draw(&camera, center + Vec2::splat(frame as f32 * 10.0));
+                unsafe { gl::Finish() };
+
+                assert_eq!(
+                    scenes.error(),
+                    None,
+                    "{name} at {size}: failed in frame {frame}"
+                );
+                assert_eq!(
+                    gl_errors(),
+                    [0; 0],
+                    "{name} at {size}: GL errors in frame {frame}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn every_scene_leaves_the_state_it_declares() {
+    let size = SIZES[0];
+    let Some(headless) = common::headless(size) else {
+        return;
+    };
+
+    let input = InputImage::gura(DEFAULT_MAX_DIMENSION);
+    let ctx = SceneContext {
+        size: headless.physical_size(),
+        input: &input,
+        data: None,
+        seed: 0,
+        text: None,
+    };
+    let camera = Camera::default();
+
+    // what a previous scene could have left behind
+    let leftover = RenderState {
+        blend: Blend::Additive,
+        depth_test: true,
+        cull_face: true,
+        srgb: true,
+    };
+
+    unsafe { leftover.apply() };
+    assert_eq!(unsafe { leftover.differences() }, [""; 0]);
+    assert_eq!(
+        unsafe { RenderState::ALPHA_BLENDED.differences() },
+        [
+            "blend factors [302, 1, 302, 1]",
+            "depth test on",
+            "face culling on",
+            "sRGB encoding on"
+        ]
+    );
+
+    for name in Scenes::builtin_names() {
+        unsafe { leftover.apply() };
+        let mut scenes = Scenes::from_name(name, &ctx).unwrap();
+        let state = scenes.render_state();
+
+        for _ in 0..FRAMES {
+            scenes.update(1.0 / 60.0);
+            assert_eq!(unsafe { state.differences() }, [""; 0], "{name} update");
+            scenes.resize(&camera, size.x as i32, size.y as i32);
+            scenes.draw(&camera, size.as_vec2() / 2.0);
+            assert_eq!(unsafe { state.differences() }, [""; 0], "{name} draw");
+        }
+        assert_eq!(scenes.error(), None, "{name}");
+    }
+}
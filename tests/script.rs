@@ -0,0 +1,143 @@
+//! Checks the scripting API against scripts run without a scene, with the
+//! scripting feature.
+#![cfg(feature = "scripting")]
+
+use std::path::Path;
+
+use glam::Vec2;
+use opengl_playground::camera::Camera;
+use opengl_playground::params::{Params, Value};
+use opengl_playground::script::{Frame, Script};
+
+fn frame<'a>(time: f32, camera: &'a Camera, params: &'a mut Params) -> Frame<'a> {
+    Frame {
+        time,
+        dt: 0.5,
+        mouse: Vec2::new(3.0, 4.0),
+        camera,
+        params: Some(params),
+    }
+}
+
+fn params() -> Params {
+    Params::new()
+        .with_f32("radius", 1.0, 0.0, 10.0, 0.5)
+        .with_bool("dither", false)
+}
+
+#[test]
+fn scripts_keep_state_in_this_and_animate_params() {
+    let mut script = Script::new(
+        r#"
+        fn init() { this.frames = 0; }
+        fn frame() {
+            this.frames += 1;
+            set_param("radius", time() * this.frames);
+            set_param("dither", !param("dither"));
+        }
+        "#,
+    )
+    .unwrap();
+
+    let camera = Camera::default();
+    let mut params = params();
+    script.frame(frame(1.0, &camera, &mut params)).unwrap();
+    script.frame(frame(2.0, &camera, &mut params)).unwrap();
+
+    assert_eq!(params.get("radius"), Some(Value::Number(4.0)));
+    assert_eq!(params.get("dither"), Some(Value::Bool(false)));
+}
+
+#[test]
+fn keys_are_used_only_when_the_script_says_so() {
+    let mut script = Script::new(
+        r#"
+        fn on_key(key) {
+            if key == "ArrowUp" { step_param("radius", 2); return true; }
+            false
+        }
+        "#,
+    )
+    .unwrap();
+
+    let camera = Camera::default();
+    let mut params = params();
+    assert!(script
+        .on_key("ArrowUp", frame(0.0, &camera, &mut params))
+        .unwrap());
+    assert!(!script
+        .on_key("x", frame(0.0, &camera, &mut params))
+        .unwrap());
+    assert_eq!(params.get("radius"), Some(Value::Number(2.0)));
+
+    // a script without on_key never uses keys
+    let mut script = Script::new("fn frame() {}").unwrap();
+    assert!(!script
+        .on_key("x", frame(0.0, &camera, &mut params))
+        .unwrap());
+}
+
+#[test]
+fn camera_and_debug_lines_come_out_of_the_script() {
+    let mut script = Script::new(
+        r#"
+        fn frame() {
+            let m = mouse();
+            set_camera(#{ x: m.x, zoom: 2 });
+            color(1, 0, 0, 1);
+            line(0, 0, camera().x, 0);
+        }
+        "#,
+    )
+    .unwrap();
+
+    let camera = Camera::default();
+    let mut params = params();
+    script.frame(frame(0.0, &camera, &mut params)).unwrap();
+
+    // the camera is positioned by its offset, opposite to what it looks at
+    let moved = script.take_camera().unwrap();
+    assert_eq!(moved.position, Vec2::new(-3.0, 0.0));
+    assert_eq!(moved.scale, Vec2::splat(2.0));
+    assert!(script.take_camera().is_none());
+
+    let lines = script.take_lines();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].points, [Vec2::ZERO, Vec2::new(3.0, 0.0)]);
+    assert_eq!(lines[0].color, [1.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn errors_name_the_function_that_failed() {
+    assert!(Script::new("fn frame( {").is_err());
+
+    let mut script = Script::new(r#"fn frame() { set_param("nope", 1); }"#).unwrap();
+    let camera = Camera::default();
+    let mut params = params();
+    let error = script.frame(frame(0.0, &camera, &mut params)).unwrap_err();
+    assert!(error.starts_with("frame:"), "{error}");
+    assert!(error.contains("nope"), "{error}");
+}
+
+#[test]
+fn example_scripts_run() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/scripts");
+    for entry in dir.read_dir().unwrap() {
+        let path = entry.unwrap().path();
+        let mut script = Script::load(&path).unwrap();
+
+        let camera = Camera::default();
+        let mut params = params();
+        for time in [0.0, 0.5] {
+            script.frame(frame(time, &camera, &mut params)).unwrap();
+        }
+        script
+            .on_key("ArrowUp", frame(1.0, &camera, &mut params))
+            .unwrap();
+        assert!(
+            !script.take_lines().is_empty(),
+            "{} draws nothing",
+            path.display()
+        );
+    }
+}
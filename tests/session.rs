@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use opengl_playground::camera::Camera;
+use opengl_playground::input::KeyInput;
+use opengl_playground::scene_controller::SceneController;
+use opengl_playground::session::{InputEvent, Player, Recorder};
+use opengl_playground::snapshot::Snapshot;
+use opengl_playground::viewport::Viewport;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{DeviceId, ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{Key, KeyCode, NamedKey, NativeKeyCode, PhysicalKey};
+
+fn key(logical_key: Key, physical_key: PhysicalKey, text: Option<&str>) -> KeyInput {
+    KeyInput {
+        logical_key,
+        physical_key,
+        state: ElementState::Pressed,
+        repeat: false,
+        text: text.map(Into::into),
+    }
+}
+
+#[test]
+fn keys_come_back_as_they_were_pressed() {
+    let unidentified = PhysicalKey::Unidentified(NativeKeyCode::Unidentified);
+    let keys = [
+        // case matters to scenes
+        key(Key::Character("E".into()), unidentified, Some("E")),
+        key(Key::Named(NamedKey::ArrowRight), unidentified, None),
+        // chords bound to places need the place
+        key(
+            Key::Character("^".into()),
+            PhysicalKey::Code(KeyCode::Backquote),
+            Some("^"),
+        ),
+    ];
+
+    for key in keys {
+        let event = InputEvent::from_key(&key).unwrap();
+        assert_eq!(event.to_key(), Some(key));
+    }
+
+    // modifiers on their own have no name chords know
+    let shift = key(Key::Named(NamedKey::Shift), unidentified, None);
+    assert_eq!(InputEvent::from_key(&shift), None);
+}
+
+#[test]
+fn mouse_events_are_made_up_again() {
+    let device_id = DeviceId::dummy();
+    let events = [
+        WindowEvent::CursorMoved {
+            device_id,
+            position: PhysicalPosition::new(12.25, 40.5),
+        },
+        WindowEvent::MouseInput {
+            device_id,
+            state: ElementState::Pressed,
+            button: MouseButton::Left,
+        },
+        WindowEvent::MouseWheel {
+            device_id,
+            delta: MouseScrollDelta::LineDelta(0.0, -2.0),
+            phase: winit::event::TouchPhase::Moved,
+        },
+    ];
+
+    for event in events {
+        let input = InputEvent::from_window_event(&event).unwrap();
+        let replayed = input.to_window_event().unwrap();
+        assert_eq!(InputEvent::from_window_event(&replayed), Some(input));
+    }
+
+    // resizes are asked for instead
+    let resized = InputEvent::from_window_event(&WindowEvent::Resized(PhysicalSize::new(640, 480)));
+    assert_eq!(resized.as_ref().and_then(InputEvent::to_window_event), None);
+    assert_eq!(
+        resized.and_then(|r| r.resized()),
+        Some(PhysicalSize::new(640, 480))
+    );
+}
+
+#[test]
+fn recorded_sessions_play_back_frame_by_frame() {
+    let path = std::env::temp_dir().join(format!("session-{}.jsonl", std::process::id()));
+    let snapshot = Snapshot {
+        scene: "kawase".to_string(),
+        seed: 42,
+        input: None,
+        camera: Camera::default(),
+        params: BTreeMap::new(),
+    };
+
+    let mut recorder = Recorder::create(&path).unwrap();
+    assert!(!recorder.is_started());
+    recorder
+        .start(PhysicalSize::new(800, 600), snapshot)
+        .unwrap();
+    recorder.push(InputEvent::CursorMoved { x: 1.0, y: 2.0 });
+    recorder.end_frame(0.016).unwrap();
+    recorder.end_frame(0.020).unwrap();
+    assert_eq!(recorder.frames(), 2);
+    drop(recorder);
+
+    // a session cut short by a crash ends with half a line
+    let mut contents = std::fs::read_to_string(&path).unwrap();
+    contents.push_str("{\"dt\":0.0");
+    std::fs::write(&path, contents).unwrap();
+
+    let mut player = Player::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(player.header.size, [800, 600]);
+    assert_eq!(player.header.snapshot.seed, 42);
+    assert_eq!(player.len(), 2);
+
+    let first = player.next_frame().unwrap().clone();
+    assert_eq!(first.dt, 0.016);
+    assert_eq!(first.events, [InputEvent::CursorMoved { x: 1.0, y: 2.0 }]);
+    let second = player.next_frame().unwrap().clone();
+    assert_eq!(second.dt, 0.020);
+    assert!(second.events.is_empty());
+    assert!(player.next_frame().is_none());
+    assert!(player.is_finished());
+    assert_eq!(player.report().frames, 2);
+}
+
+#[test]
+fn replayed_frames_have_the_recorded_length() {
+    let viewport = Viewport::from_physical(PhysicalSize::new(800, 600), 1.0);
+    let mut scene_ctrl = SceneController::new(viewport, 0.1);
+
+    scene_ctrl.update_by(0.25);
+    assert_eq!(scene_ctrl.dt(), 0.25);
+    scene_ctrl.update_by(0.5);
+    assert_eq!(scene_ctrl.dt(), 0.5);
+    assert_eq!(scene_ctrl.current_elapsed(), 0.75);
+    // fixed steps follow the recorded time, not the wall clock
+    assert_eq!(scene_ctrl.take_fixed_steps(), 8);
+}
@@ -0,0 +1,55 @@
+//! Compiles the shaders of the playground on a headless context.
+
+use std::path::Path;
+
+use glam::UVec2;
+use opengl_playground::shader_validation::Validation;
+
+mod common;
+
+#[test]
+fn every_shader_compiles() {
+    let Some(_headless) = common::headless(UVec2::ONE) else {
+        return;
+    };
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/shaders");
+    let validation = unsafe { Validation::dir(&dir) }.unwrap();
+
+    let errors = validation.errors.iter().map(ToString::to_string);
+    assert!(
+        validation.is_ok(),
+        "{}",
+        errors.collect::<Vec<_>>().join("\n")
+    );
+    assert!(validation.compiled > 0);
+}
+
+#[test]
+fn errors_point_to_the_original_line() {
+    let Some(_headless) = common::headless(UVec2::ONE) else {
+        return;
+    };
+
+    let source = "\
+#version 330 core
+out vec4 color;
+void main() {
+#ifdef BROKEN
+    color = vec4(oops);
+#else
+    color = vec4(1.0);
+#endif
+}
+";
+
+    let mut validation = Validation::default();
+    unsafe { validation.check(Path::new("broken.frag"), source) };
+
+    assert_eq!(validation.compiled, 2);
+    assert!(!validation.errors.is_empty());
+    for error in &validation.errors {
+        assert_eq!(error.defines, ["BROKEN"]);
+        assert_eq!(error.line, Some(5), "{error}");
+    }
+}
@@ -0,0 +1,99 @@
+//! Drops pressure blobs into a box and checks that they hold their shape and
+//! keep out of each other.
+
+use std::f32::consts::PI;
+
+use glam::Vec2;
+use opengl_playground::soft_body::{Settings, SoftBodies};
+
+const SUBSTEPS: usize = 10;
+
+fn settings() -> Settings {
+    Settings {
+        gravity: Vec2::new(0.0, 980.0),
+        stiffness: 20000.0,
+        damping: 50.0,
+        pressure: 3000.0,
+        bounds: (Vec2::new(-300.0, -300.0), Vec2::new(300.0, 200.0)),
+        friction: 0.02,
+    }
+}
+
+fn simulate(bodies: &mut SoftBodies, frames: usize) {
+    let settings = settings();
+    for _ in 0..frames * SUBSTEPS {
+        bodies.step(1.0 / 60.0 / SUBSTEPS as f32, &settings);
+    }
+}
+
+#[test]
+fn new_blob_is_round() {
+    let mut bodies = SoftBodies::new();
+    let center = Vec2::new(10.0, -20.0);
+    let i = bodies.add_blob(center, 50.0, 40);
+    let blob = bodies.blobs[i];
+
+    let circle = PI * 50.0 * 50.0;
+    assert!((bodies.area(&blob) - circle).abs() < circle * 0.01);
+    assert!(bodies.centroid(&blob).distance(center) < 1e-3);
+    assert!(bodies.contains(&blob, center));
+    assert!(!bodies.contains(&blob, center + Vec2::new(51.0, 0.0)));
+}
+
+#[test]
+fn blobs_settle_without_collapsing() {
+    let mut bodies = SoftBodies::new();
+    for i in 0..6 {
+        let center = Vec2::new(-200.0 + 80.0 * i as f32, -150.0 + 30.0 * (i % 2) as f32);
+        bodies.add_blob(center, 30.0 + 10.0 * (i % 3) as f32, 32);
+    }
+    simulate(&mut bodies, 300);
+
+    let (min, max) = settings().bounds;
+    for &position in &bodies.positions {
+        assert!(position.cmpge(min).all() && position.cmple(max).all());
+    }
+    for blob in &bodies.blobs {
+        let ratio = bodies.area(blob) / blob.rest_area;
+        assert!(ratio > 0.8 && ratio < 1.1, "{ratio}");
+        // resting on the floor, not falling through it
+        assert!(bodies.centroid(blob).y > 100.0);
+    }
+}
+
+#[test]
+fn stacked_blobs_stay_apart() {
+    // in a tube barely wider than them, so that one has to rest on the other
+    let settings = Settings {
+        bounds: (Vec2::new(-55.0, -300.0), Vec2::new(55.0, 200.0)),
+        ..settings()
+    };
+    let mut bodies = SoftBodies::new();
+    let bottom = bodies.add_blob(Vec2::new(0.0, 150.0), 50.0, 40);
+    let top = bodies.add_blob(Vec2::new(0.0, 30.0), 50.0, 40);
+    for _ in 0..180 * SUBSTEPS {
+        bodies.step(1.0 / 60.0 / SUBSTEPS as f32, &settings);
+    }
+
+    let (bottom, top) = (bodies.blobs[bottom], bodies.blobs[top]);
+    assert!(bodies.centroid(&top).y < bodies.centroid(&bottom).y - 50.0);
+    let inside = (bodies.points(&top).iter())
+        .filter(|&&point| bodies.contains(&bottom, point))
+        .count();
+    assert!(
+        inside <= 2,
+        "{inside} points of the top blob inside the bottom one"
+    );
+}
+
+#[test]
+fn poking_pushes_points_out() {
+    let mut bodies = SoftBodies::new();
+    bodies.add_blob(Vec2::ZERO, 50.0, 40);
+    let finger = Vec2::new(45.0, 0.0);
+    bodies.push_out(finger, 20.0);
+
+    for &position in &bodies.positions {
+        assert!(position.distance(finger) >= 20.0 - 1e-3);
+    }
+}
@@ -0,0 +1,84 @@
+//! Checks the spatial indices against a brute force search.
+
+use glam::Vec2;
+use opengl_playground::spatial::{Quadtree, SpatialIndex, UniformGrid};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn points(rng: &mut impl Rng) -> Vec<Vec2> {
+    (0..2000)
+        .map(|i| {
+            // some clustered, some spread out, a few outside of the quadtree
+            let spread = [50.0, 500.0, 700.0][i % 3];
+            Vec2::new(
+                rng.gen_range(-spread..spread),
+                rng.gen_range(-spread..spread),
+            )
+        })
+        .collect()
+}
+
+fn check(index: &mut dyn SpatialIndex<u32>, name: &str) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let points = points(&mut rng);
+
+    index.clear();
+    for (i, &point) in points.iter().enumerate() {
+        index.insert(point, i as u32);
+    }
+
+    let items = index
+        .regions()
+        .iter()
+        .map(|region| region.items)
+        .sum::<usize>();
+    assert_eq!(items, points.len(), "{name}: regions must hold every item");
+
+    for _ in 0..200 {
+        let center = Vec2::new(rng.gen_range(-600.0..600.0), rng.gen_range(-600.0..600.0));
+        let radius = rng.gen_range(0.0..300.0);
+
+        let mut found = Vec::new();
+        index.query_circle(center, radius, &mut found);
+        found.sort_unstable();
+
+        let expected = (0..points.len() as u32)
+            .filter(|&i| points[i as usize].distance_squared(center) <= radius * radius)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found, expected,
+            "{name}: query around {center} of radius {radius}"
+        );
+    }
+}
+
+#[test]
+fn uniform_grid_finds_what_brute_force_finds() {
+    check(&mut UniformGrid::new(37.0), "grid");
+}
+
+#[test]
+fn quadtree_finds_what_brute_force_finds() {
+    let half = Vec2::splat(512.0);
+    check(&mut Quadtree::new(-half, half, 8, 8), "quadtree");
+}
+
+#[test]
+fn clearing_empties_the_index() {
+    let mut grid = UniformGrid::new(10.0);
+    let half = Vec2::splat(100.0);
+    let mut quadtree = Quadtree::new(-half, half, 2, 4);
+
+    let indices: [&mut dyn SpatialIndex<u32>; 2] = [&mut grid, &mut quadtree];
+    for index in indices {
+        for i in 0..50 {
+            index.insert(Vec2::splat(i as f32), i);
+        }
+        index.clear();
+
+        let mut found = Vec::new();
+        index.query_circle(Vec2::ZERO, 1000.0, &mut found);
+        assert!(found.is_empty());
+        assert!(index.regions().is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+//! Checks curve tessellation and arc-length sampling.
+
+use glam::Vec2;
+use opengl_playground::spline::{ArcLength, Curve, CurveKind, CurveSet};
+
+fn bezier_point([p0, p1, p2, p3]: [Vec2; 4], t: f32) -> Vec2 {
+    let u = 1.0 - t;
+    p0 * u * u * u + p1 * 3.0 * u * u * t + p2 * 3.0 * u * t * t + p3 * t * t * t
+}
+
+fn distance_to_polyline(point: Vec2, polyline: &[Vec2]) -> f32 {
+    (polyline.windows(2))
+        .map(|w| {
+            let (a, b) = (w[0], w[1]);
+            let t = ((point - a).dot(b - a) / (b - a).length_squared().max(f32::EPSILON))
+                .clamp(0.0, 1.0);
+            point.distance(a.lerp(b, t))
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[test]
+fn tessellation_stays_within_tolerance() {
+    let curves = [
+        Curve {
+            kind: CurveKind::Bezier,
+            points: [(0.0, 0.0), (100.0, -300.0), (300.0, 300.0), (400.0, 0.0)]
+                .map(Vec2::from)
+                .to_vec(),
+        },
+        Curve {
+            kind: CurveKind::CatmullRom,
+            points: [(0.0, 0.0), (100.0, 200.0), (200.0, -100.0), (300.0, 50.0)]
+                .map(Vec2::from)
+                .to_vec(),
+        },
+    ];
+
+    for curve in &curves {
+        for tolerance in [0.1, 1.0, 10.0] {
+            let mut polyline = Vec::new();
+            curve.tessellate(tolerance, &mut polyline);
+
+            assert_eq!(polyline.first(), curve.points.first());
+            assert_eq!(polyline.last(), curve.points.last());
+
+            for segment in curve.segments() {
+                for i in 0..=100 {
+                    let point = bezier_point(segment, i as f32 / 100.0);
+                    let distance = distance_to_polyline(point, &polyline);
+                    assert!(
+                        distance <= tolerance + 1e-3,
+                        "{:?} strays {distance} away at tolerance {tolerance}",
+                        curve.kind,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn catmull_rom_passes_through_its_points() {
+    let curve = Curve {
+        kind: CurveKind::CatmullRom,
+        points: [(0.0, 0.0), (50.0, 80.0), (120.0, -20.0), (200.0, 10.0)]
+            .map(Vec2::from)
+            .to_vec(),
+    };
+
+    let segments = curve.segments();
+    assert_eq!(segments.len(), curve.points.len() - 1);
+    for (segment, points) in segments.iter().zip(curve.points.windows(2)) {
+        assert_eq!(segment[0], points[0]);
+        assert_eq!(segment[3], points[1]);
+    }
+}
+
+#[test]
+fn arc_length_samples_by_distance() {
+    let path = ArcLength::new(vec![
+        Vec2::new(0.0, 0.0),
+        Vec2::new(10.0, 0.0),
+        Vec2::new(10.0, 30.0),
+    ]);
+    assert_eq!(path.length(), 40.0);
+
+    let (position, direction) = path.sample(5.0).unwrap();
+    assert_eq!(position, Vec2::new(5.0, 0.0));
+    assert_eq!(direction, Vec2::X);
+
+    let (position, direction) = path.sample(25.0).unwrap();
+    assert_eq!(position, Vec2::new(10.0, 15.0));
+    assert_eq!(direction, Vec2::Y);
+
+    assert_eq!(path.sample(-1.0).unwrap().0, Vec2::ZERO);
+    assert_eq!(path.sample(100.0).unwrap().0, Vec2::new(10.0, 30.0));
+    assert!(ArcLength::new(Vec::new()).sample(0.0).is_none());
+}
+
+#[test]
+fn curve_set_roundtrips() {
+    let set = CurveSet {
+        curves: vec![
+            Curve {
+                kind: CurveKind::Bezier,
+                points: [(0.0, 0.0), (1.0, 2.0), (3.0, 4.0), (5.5, -6.25)]
+                    .map(Vec2::from)
+                    .to_vec(),
+            },
+            Curve {
+                kind: CurveKind::CatmullRom,
+                points: vec![Vec2::ZERO, Vec2::ONE],
+            },
+        ],
+    };
+
+    let path = std::env::temp_dir().join(format!("splines-{}.toml", std::process::id()));
+    set.save(&path).unwrap();
+    let loaded = CurveSet::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap(), set);
+}
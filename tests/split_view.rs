@@ -0,0 +1,51 @@
+use glam::{vec2, Vec2};
+use opengl_playground::scene_controller::SceneController;
+use opengl_playground::split_view::{Side, SplitView};
+use opengl_playground::viewport::Viewport;
+use winit::dpi::PhysicalSize;
+
+fn viewport(width: u32, height: u32) -> Viewport {
+    Viewport::from_physical(PhysicalSize::new(width, height), 1.0)
+}
+
+#[test]
+fn unsplit_window_is_all_left() {
+    let mut scene_ctrl = SceneController::new(Viewport::default(), 0.5);
+    scene_ctrl.resize(viewport(800, 600));
+
+    assert_eq!(scene_ctrl.side_at(vec2(700.0, 300.0)), Side::Left);
+    let (_, pos) = scene_ctrl.view_at(vec2(700.0, 300.0));
+    assert_eq!(pos, vec2(700.0, 300.0));
+}
+
+#[test]
+fn right_half_is_a_close_up_of_its_own() {
+    let mut scene_ctrl = SceneController::new(Viewport::default(), 0.5);
+    scene_ctrl.resize(viewport(801, 600));
+    scene_ctrl.camera.position = vec2(12.0, -34.0);
+
+    assert!(scene_ctrl.toggle_split());
+    let split_camera = scene_ctrl.split_camera.clone().unwrap();
+    assert_eq!(split_camera.position, scene_ctrl.camera.position);
+    assert!(split_camera.scale.cmpgt(scene_ctrl.camera.scale).all());
+
+    // the odd column in the middle belongs to the left half
+    assert_eq!(
+        SplitView::half_size(glam::uvec2(801, 600)),
+        glam::uvec2(400, 600)
+    );
+    assert_eq!(scene_ctrl.side_at(vec2(400.0, 0.0)), Side::Left);
+    assert_eq!(scene_ctrl.side_at(vec2(401.0, 0.0)), Side::Right);
+
+    let (camera, pos) = scene_ctrl.view_at(vec2(501.0, 300.0));
+    assert_eq!(camera.scale, split_camera.scale);
+    assert_eq!(pos, vec2(100.0, 300.0));
+
+    let (camera, pos) = scene_ctrl.view_at(vec2(100.0, 300.0));
+    assert_eq!(camera.scale, scene_ctrl.camera.scale);
+    assert_eq!(pos, vec2(100.0, 300.0));
+
+    assert!(!scene_ctrl.toggle_split());
+    assert!(scene_ctrl.split_camera.is_none());
+    assert_eq!(scene_ctrl.view(Side::Right).1, Vec2::ZERO);
+}
@@ -0,0 +1,22 @@
+use glam::{vec2, Vec2};
+use opengl_playground::camera::Camera;
+use opengl_playground::stereo::Eye;
+
+#[test]
+fn eyes_look_from_either_side_of_the_camera() {
+    let camera = Camera {
+        position: vec2(10.0, 20.0),
+        rotation: 0.3,
+        scale: Vec2::splat(2.0),
+    };
+
+    let [left, right] = [Eye::Left, Eye::Right].map(|eye| eye.camera(&camera));
+    assert!((left.position + right.position - 2.0 * camera.position).length() < 1e-4);
+    assert_ne!(left.position, right.position);
+    assert_eq!((left.rotation, left.scale), (camera.rotation, camera.scale));
+
+    // higher things move toward the middle in both eyes
+    let parallax = [Eye::Left, Eye::Right].map(|eye| eye.parallax(&camera));
+    assert!((parallax[0] + parallax[1]).length() < 1e-4);
+    assert!(parallax[0].dot(right.position - left.position) > 0.0);
+}
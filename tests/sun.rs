@@ -0,0 +1,24 @@
+#![cfg(feature = "scene-sun")]
+
+use opengl_playground::scenes::sun::sun_direction;
+
+#[test]
+fn sun_is_highest_at_noon_in_the_south() {
+    let noon = sun_direction(12.0);
+    assert!(noon.x.abs() < 1e-5);
+    // south is down the screen
+    assert!(noon.y > 0.0);
+    for hours in [6.0, 9.0, 11.0, 13.0, 15.0, 18.0] {
+        assert!(sun_direction(hours).z < noon.z);
+    }
+    assert!((noon.length() - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn sun_rises_in_the_east_and_sets_below_the_horizon() {
+    let (morning, evening) = (sun_direction(8.0), sun_direction(16.0));
+    assert!(morning.x > 0.0 && evening.x < 0.0);
+    assert!((morning.z - evening.z).abs() < 1e-5);
+    assert!(sun_direction(0.0).z < 0.0);
+    assert!(sun_direction(24.0).abs_diff_eq(sun_direction(0.0), 1e-5));
+}
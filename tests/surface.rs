@@ -0,0 +1,43 @@
+use glam::uvec2;
+use opengl_playground::common_gl::read_pixels;
+use opengl_playground::surface::{self, Compositing};
+use opengl_playground::theme;
+
+mod common;
+
+#[test]
+fn compositing_parses_its_names() {
+    for compositing in [Compositing::Opaque, Compositing::Transparent] {
+        assert_eq!(compositing.name().parse(), Ok(compositing));
+        assert_eq!(compositing.toggled().toggled(), compositing);
+    }
+    assert!("translucent".parse::<Compositing>().is_err());
+    assert_eq!(Compositing::default(), Compositing::Opaque);
+}
+
+#[test]
+fn opaque_surfaces_end_frames_with_full_alpha() {
+    let Some(headless) = common::headless(uvec2(4, 4)) else {
+        return;
+    };
+    let size = headless.size();
+
+    let frame = |compositing| unsafe {
+        surface::set_compositing(compositing);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        theme::clear_with([0.2, 0.4, 0.6, 0.5]);
+        surface::finish_frame();
+        read_pixels(size.x, size.y)
+    };
+
+    let transparent = frame(Compositing::Transparent);
+    let opaque = frame(Compositing::Opaque);
+    surface::set_compositing(Compositing::Opaque);
+
+    for (transparent, opaque) in transparent.chunks(4).zip(opaque.chunks(4)) {
+        assert_eq!(transparent[3], 128);
+        assert_eq!(opaque[3], 255);
+        // the colors themselves are left alone
+        assert_eq!(transparent[..3], opaque[..3]);
+    }
+}
@@ -0,0 +1,24 @@
+//! Checks how the theme follows the OS and manual picks. The theme is global,
+//! so everything is checked in one test.
+
+use opengl_playground::theme::{self, Mode, Theme};
+
+#[test]
+fn theme_follows_the_os_unless_picked() {
+    theme::set_mode(Mode::System);
+    theme::set_system_dark(false);
+    assert_eq!(*theme::current(), Theme::LIGHT);
+    theme::set_system_dark(true);
+    assert_eq!(*theme::current(), Theme::DARK);
+
+    assert_eq!(theme::cycle_mode(), Mode::Dark);
+    theme::set_system_dark(false);
+    assert_eq!(*theme::current(), Theme::DARK);
+
+    assert_eq!(theme::cycle_mode(), Mode::Light);
+    theme::set_system_dark(true);
+    assert_eq!(*theme::current(), Theme::LIGHT);
+
+    assert_eq!(theme::cycle_mode(), Mode::System);
+    assert_eq!(*theme::current(), Theme::DARK);
+}
@@ -0,0 +1,46 @@
+use opengl_playground::input_image::{InputImage, DEFAULT_MAX_DIMENSION};
+use opengl_playground::scenes::{SceneContext, Scenes};
+use opengl_playground::text::TextRenderer;
+use opengl_playground::thumbnails::{self, SIZE};
+
+mod common;
+
+#[test]
+fn thumbnails_are_opaque_and_labeled() {
+    let Some(headless) = common::headless(SIZE) else {
+        return;
+    };
+
+    let input = InputImage::gura(DEFAULT_MAX_DIMENSION);
+    let text = unsafe { TextRenderer::shared() }.unwrap();
+    let ctx = SceneContext {
+        size: headless.physical_size(),
+        input: &input,
+        data: None,
+        seed: thumbnails::SEED,
+        text: Some(&text),
+    };
+
+    let render = |title: &str| {
+        let mut scenes = Scenes::from_name("kawase", &ctx).unwrap();
+        let image = unsafe { thumbnails::render(&mut scenes, title, &text, SIZE) };
+        assert_eq!(scenes.error(), None);
+        image
+    };
+    let labeled = render("Kawase Blur");
+    let unlabeled = render("");
+
+    assert_eq!(labeled.dimensions(), (SIZE.x, SIZE.y));
+    assert!(labeled.pixels().all(|pixel| pixel[3] == 255));
+
+    // the same scene with the same seed, apart from the label at the bottom
+    let differs = |y: u32| (0..SIZE.x).any(|x| labeled[(x, y)] != unlabeled[(x, y)]);
+    assert!(!(0..SIZE.y / 2).any(differs));
+    assert!((SIZE.y * 3 / 4..SIZE.y).any(differs));
+}
+
+#[test]
+fn thumbnails_are_named_after_scenes() {
+    let path = thumbnails::path("thumbs".as_ref(), "gray-scott");
+    assert_eq!(path, std::path::Path::new("thumbs/gray-scott.png"));
+}
@@ -0,0 +1,109 @@
+//! Checks the easing curves and tweens.
+
+use glam::Vec2;
+use opengl_playground::camera::Camera;
+use opengl_playground::tween::{Easing, Lerp, Tween, Tweener};
+
+#[test]
+fn easings_start_at_0_and_end_at_1() {
+    for &easing in Easing::ALL {
+        assert!(easing.ease(0.0).abs() < 1e-5, "{easing:?} at 0");
+        assert!((easing.ease(1.0) - 1.0).abs() < 1e-5, "{easing:?} at 1");
+
+        // out of range progress is clamped
+        assert_eq!(easing.ease(-1.0), easing.ease(0.0), "{easing:?} before 0");
+        assert_eq!(easing.ease(2.0), easing.ease(1.0), "{easing:?} after 1");
+    }
+}
+
+#[test]
+fn in_out_easings_are_symmetric() {
+    for easing in [
+        Easing::Smoothstep,
+        Easing::QuadInOut,
+        Easing::CubicInOut,
+        Easing::SineInOut,
+        Easing::ExpoInOut,
+        Easing::BackInOut,
+    ] {
+        for i in 0..=10 {
+            let t = i as f32 / 10.0;
+            let mirrored = 1.0 - easing.ease(1.0 - t);
+            assert!(
+                (easing.ease(t) - mirrored).abs() < 1e-5,
+                "{easing:?} isn't symmetric at {t}",
+            );
+        }
+    }
+}
+
+#[test]
+fn tween_reaches_its_target_after_its_duration() {
+    let mut tween = Tween::new(10.0, 20.0, 1.0, Easing::Linear);
+    assert_eq!(tween.value(), 10.0);
+
+    tween.advance(0.25);
+    assert_eq!(tween.value(), 12.5);
+    assert!(!tween.is_finished());
+
+    tween.advance(10.0);
+    assert_eq!(tween.value(), 20.0);
+    assert_eq!(tween.progress(), 1.0);
+    assert!(tween.is_finished());
+
+    let instant = Tween::new(Vec2::ZERO, Vec2::ONE, 0.0, Easing::CubicOut);
+    assert_eq!(instant.value(), Vec2::ONE);
+}
+
+#[test]
+fn interrupted_animations_continue_from_where_they_were() {
+    let mut tweener = Tweener::new();
+    tweener.animate_to("x", 0.0, 100.0, 1.0, Easing::Linear);
+    tweener.update(0.5);
+    assert_eq!(tweener.value(&"x"), Some(50.0));
+
+    // the current value passed in is only used when there is no animation
+    tweener.animate_to("x", 0.0, 0.0, 1.0, Easing::Linear);
+    assert_eq!(tweener.value(&"x"), Some(50.0));
+    assert_eq!(tweener.target(&"x"), Some(0.0));
+    assert!(tweener.is_animating(&"x"));
+
+    tweener.update(1.0);
+    assert!(!tweener.is_animating(&"x"));
+    assert_eq!(tweener.remove_finished(), [("x", 0.0)]);
+    assert!(tweener.is_empty());
+}
+
+#[test]
+fn tweener_iterates_current_values() {
+    let mut tweener = Tweener::new();
+    tweener.start(1, Tween::new(0.0, 10.0, 1.0, Easing::Linear));
+    tweener.start(2, Tween::new(10.0, 0.0, 2.0, Easing::Linear));
+    tweener.update(0.5);
+
+    let mut values = tweener.iter().map(|(&k, v)| (k, v)).collect::<Vec<_>>();
+    values.sort_by_key(|&(k, _)| k);
+    assert_eq!(values, [(1, 5.0), (2, 7.5)]);
+
+    let mut keys = tweener.keys().copied().collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, [1, 2]);
+}
+
+#[test]
+fn camera_zooms_geometrically() {
+    let from = Camera {
+        scale: Vec2::splat(1.0),
+        ..Default::default()
+    };
+    let to = Camera {
+        position: Vec2::new(100.0, -50.0),
+        rotation: 1.0,
+        scale: Vec2::splat(4.0),
+    };
+
+    let halfway = from.lerp(&to, 0.5);
+    assert_eq!(halfway.position, Vec2::new(50.0, -25.0));
+    assert_eq!(halfway.rotation, 0.5);
+    assert!((halfway.scale - Vec2::splat(2.0)).length() < 1e-5);
+}
@@ -0,0 +1,42 @@
+use glam::{uvec2, vec2, Vec2};
+use opengl_playground::scene_controller::SceneController;
+use opengl_playground::viewport::Viewport;
+use winit::dpi::PhysicalSize;
+
+#[test]
+fn fractional_scales_round_trip_to_the_same_physical_size() {
+    for scale_factor in [1.0, 1.25, 1.5, 1.75, 2.0, 2.25] {
+        for (width, height) in [(1, 1), (799, 601), (1366, 767), (3841, 2161)] {
+            let viewport = Viewport::from_physical(PhysicalSize::new(width, height), scale_factor);
+            assert_eq!(viewport.physical(), uvec2(width, height), "{scale_factor}x");
+        }
+    }
+
+    let viewport = Viewport::from_physical(PhysicalSize::new(1001, 750), 1.25);
+    assert_eq!(viewport.logical, vec2(800.8, 600.0));
+    // lengths land on whole physical pixels
+    assert_eq!(viewport.px(15.0), 19.0);
+    assert_eq!(viewport.px(12.0), 15.0);
+}
+
+#[test]
+fn cameras_keep_showing_the_same_area_when_the_scale_factor_changes() {
+    let viewport = Viewport::from_physical(PhysicalSize::new(1000, 800), 1.0);
+    let mut scene_ctrl = SceneController::new(viewport, 0.5);
+    scene_ctrl.resize(viewport);
+    scene_ctrl.camera.scale = Vec2::splat(2.0);
+    scene_ctrl.camera.position = vec2(30.0, -20.0);
+
+    let corner = |scene_ctrl: &SceneController, viewport: &Viewport| {
+        let camera = &scene_ctrl.camera;
+        camera.pointer_to_pos(viewport.size(), viewport.size())
+    };
+    let before = corner(&scene_ctrl, &viewport);
+
+    // moved to a 1.25x monitor, where the window keeps its logical size
+    let viewport = viewport.with_scale_factor(1.25);
+    assert_eq!(viewport.physical(), uvec2(1250, 1000));
+    scene_ctrl.resize(viewport);
+    assert_eq!(scene_ctrl.camera.scale, Vec2::splat(2.5));
+    assert!(corner(&scene_ctrl, &viewport).abs_diff_eq(before, 1e-3));
+}
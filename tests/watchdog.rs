@@ -0,0 +1,49 @@
+//! The watchdog shares its state with debug groups, so everything runs in one
+//! test.
+
+use std::thread;
+use std::time::Duration;
+
+use glam::UVec2;
+use opengl_playground::common_gl::{pop_debug_group, push_debug_group};
+use opengl_playground::watchdog::Watchdog;
+
+mod common;
+
+const THRESHOLD: Duration = Duration::from_millis(40);
+
+#[test]
+fn long_frames_are_reported_with_their_slowest_passes() {
+    let watchdog = Watchdog::start(THRESHOLD, false);
+    watchdog.begin_frame("kawase", None);
+    assert!(watchdog.end_frame().is_none());
+
+    watchdog.begin_frame("kernel", None);
+    thread::sleep(THRESHOLD * 2);
+    let long_frame = watchdog.end_frame().unwrap();
+    assert_eq!(long_frame.scene, "kernel");
+    assert!(long_frame.duration >= THRESHOLD * 2);
+    assert!(long_frame.slowest.is_empty());
+    drop(watchdog);
+
+    let Some(_headless) = common::headless(UVec2::ONE) else {
+        return;
+    };
+
+    let watchdog = Watchdog::start(THRESHOLD, true);
+    watchdog.begin_frame("kernel", None);
+    unsafe {
+        push_debug_group(c"Outer");
+        push_debug_group(c"Fast");
+        pop_debug_group();
+        push_debug_group(c"Slow");
+        thread::sleep(THRESHOLD * 2);
+        pop_debug_group();
+        pop_debug_group();
+    }
+    let long_frame = watchdog.end_frame().unwrap();
+    let passes = (long_frame.slowest.iter())
+        .map(|(pass, _)| pass.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(passes, ["Outer", "Outer / Slow", "Outer / Fast"]);
+}